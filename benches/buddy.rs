@@ -0,0 +1,202 @@
+#![feature(new_uninit)]
+
+// The tree is private to the allocator, so borrow it directly.
+// All bookkeeping happens on the CPU, which keeps the GPU out of the numbers
+#[allow(dead_code)]
+#[path = "../src/buddy/tree.rs"]
+mod tree;
+
+use std::{
+    hint,
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use tree::{Layout, Tree};
+
+// Same shape as the quad buffer in `axial.rs`
+const MAX_ORDER: u8 = 25;
+const MIN_ORDER: u8 = 8;
+
+const LAYOUTS: [Layout; 2] = [Layout::Orders, Layout::Bitmap];
+
+fn full_tree(layout: Layout) -> (Tree, Vec<usize>) {
+    let mut tree = Tree::new(layout, MAX_ORDER, MIN_ORDER);
+    let mut blocks = Vec::with_capacity(1 << (MAX_ORDER - MIN_ORDER));
+
+    while let Some(block) = tree.alloc(MIN_ORDER) {
+        blocks.push(block);
+    }
+
+    (tree, blocks)
+}
+
+fn uniform(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uniform");
+    group.sample_size(10);
+
+    for layout in LAYOUTS {
+        let id = BenchmarkId::new("alloc", format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                || Tree::new(layout, MAX_ORDER, MIN_ORDER),
+                |mut tree| while tree.alloc(MIN_ORDER).is_some() {},
+                BatchSize::LargeInput,
+            )
+        });
+
+        let id = BenchmarkId::new("free", format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                || full_tree(layout),
+                |(mut tree, blocks)| blocks.into_iter().for_each(|block| tree.free(block)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mixed");
+    group.sample_size(10);
+
+    for layout in LAYOUTS {
+        let id = BenchmarkId::from_parameter(format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                || {
+                    let tree = Tree::new(layout, MAX_ORDER, MIN_ORDER);
+                    (tree, StdRng::seed_from_u64(0), Vec::new())
+                },
+                |(mut tree, mut rng, mut live)| {
+                    // Interleave allocations of up to 16 minimum blocks
+                    // with frees of random live blocks
+                    for _ in 0..100_000 {
+                        if rng.gen_ratio(1, 3) && !live.is_empty() {
+                            let index = rng.gen_range(0..live.len());
+                            tree.free(live.swap_remove(index));
+                        } else if let Some(block) =
+                            tree.alloc(rng.gen_range(MIN_ORDER..=MIN_ORDER + 4))
+                        {
+                            live.push(block);
+                        }
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn fragmentation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fragmentation");
+    group.sample_size(10);
+
+    // Every other minimum block is free, so no bigger block ever fits
+    let fragmented = |layout| {
+        let (mut tree, blocks) = full_tree(layout);
+        blocks
+            .into_iter()
+            .step_by(2)
+            .for_each(|block| tree.free(block));
+        tree
+    };
+
+    for layout in LAYOUTS {
+        let id = BenchmarkId::new("alloc_fail", format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_batched_ref(
+                || fragmented(layout),
+                |tree| tree.alloc(MIN_ORDER + 1),
+                BatchSize::LargeInput,
+            )
+        });
+
+        let id = BenchmarkId::new("alloc_holes", format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                || fragmented(layout),
+                |mut tree| while tree.alloc(MIN_ORDER).is_some() {},
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+fn free_order(c: &mut Criterion) {
+    let mut group = c.benchmark_group("free_order");
+    group.sample_size(10);
+
+    let orders: [(&str, fn(&mut Vec<usize>)); 4] = [
+        ("forward", |_| {}),
+        ("reverse", |blocks| blocks.reverse()),
+        ("shuffled", |blocks| {
+            blocks.shuffle(&mut StdRng::seed_from_u64(0))
+        }),
+        ("bulk", |_| {}),
+    ];
+
+    for layout in LAYOUTS {
+        for (name, reorder) in orders {
+            let id = BenchmarkId::new(name, format!("{layout:?}"));
+            group.bench_function(id, |b| {
+                b.iter_batched(
+                    || {
+                        let (tree, mut blocks) = full_tree(layout);
+                        reorder(&mut blocks);
+                        (tree, blocks)
+                    },
+                    |(mut tree, blocks)| {
+                        if name == "bulk" {
+                            tree.free_many(blocks);
+                        } else {
+                            blocks.into_iter().for_each(|block| tree.free(block));
+                        }
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+}
+
+fn descent(c: &mut Criterion) {
+    let mut group = c.benchmark_group("descent");
+    group.sample_size(10);
+
+    // Wide enough that a tree walk misses the cache at every level
+    let junk = vec![0u8; 64 << 20];
+
+    for layout in LAYOUTS {
+        let (mut tree, mut blocks) = full_tree(layout);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let id = BenchmarkId::from_parameter(format!("{layout:?}"));
+        group.bench_function(id, |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+
+                for _ in 0..iters {
+                    // Punch a random hole and evict the tree from the cache,
+                    // so only the walk down to the hole is measured
+                    let index = rng.gen_range(0..blocks.len());
+                    tree.free(blocks[index]);
+                    junk.iter()
+                        .step_by(64)
+                        .for_each(|byte| _ = hint::black_box(byte));
+
+                    let then = Instant::now();
+                    blocks[index] = hint::black_box(tree.alloc(MIN_ORDER)).unwrap();
+                    total += then.elapsed();
+                }
+
+                total
+            })
+        });
+    }
+}
+
+criterion_group!(benches, uniform, mixed, fragmentation, free_order, descent);
+criterion_main!(benches);
@@ -0,0 +1,167 @@
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use rust_playground::mesh::{
+    self, cull, greedy, palette::Palette, stats::MeshStats, upload::Packed, Chunk, Mesh,
+    QuadLayout, AIR, MESHERS,
+};
+
+const STONE: u16 = 1;
+const GRASS: u16 = 2;
+const DIRT: u16 = 3;
+const ORE: u16 = 4;
+
+// Rolling hills of stone under a few layers of dirt and grass,
+// with ore sprinkled in and caves carved out
+fn terrain(seed: u64) -> Box<Chunk> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+    let phase = rng.gen::<f32>() * 100.0;
+
+    for z in 0..32 {
+        for x in 0..32 {
+            let (fx, fz) = (x as f32, z as f32);
+            let hills = (fx * 0.21 + phase).sin() + (fz * 0.17 + phase).cos();
+            let bumps = ((fx + fz) * 0.4).sin();
+            let height = (14.0 + 6.0 * hills + 2.0 * bumps) as usize;
+
+            for y in 0..height.min(32) {
+                chunk[z][y][x] = match height - y {
+                    1 => GRASS,
+                    2..=3 => DIRT,
+                    _ if rng.gen_ratio(1, 40) => ORE,
+                    _ => STONE,
+                };
+            }
+        }
+    }
+
+    for _ in 0..4 {
+        let center = [0; 3].map(|_| rng.gen_range(4..28) as i32);
+        let radius = rng.gen_range(2..5);
+
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let location = [x, y, z].map(|c| c as i32);
+                    let distance2: i32 = (0..3).map(|i| (location[i] - center[i]).pow(2)).sum();
+                    if distance2 < radius * radius {
+                        chunk[z][y][x] = AIR;
+                    }
+                }
+            }
+        }
+    }
+
+    chunk
+}
+
+// Visible faces across chunks, so that throughput counts quads before merging
+fn faces(chunks: &[Box<Chunk>]) -> Mesh {
+    let culled: Vec<_> = chunks.iter().map(|chunk| cull(chunk)).collect();
+    merged(&culled)
+}
+
+fn throughput(faces: &Mesh) -> Throughput {
+    Throughput::Elements(faces.iter().map(Vec::len).sum::<usize>() as _)
+}
+
+fn meshers(c: &mut Criterion) {
+    let chunks: Vec<_> = (0..8).map(terrain).collect();
+    let faces = faces(&chunks);
+
+    let mut group = c.benchmark_group("terrain");
+    group.throughput(throughput(&faces));
+
+    for name in MESHERS {
+        let mesher = mesh::mesher(name).unwrap();
+
+        // Merging quality does not change between runs, report it once
+        let meshes: Vec<_> = chunks.iter().map(|chunk| mesher.mesh(chunk)).collect();
+        let stats = MeshStats::new(&faces, &merged(&meshes));
+        let (input, output, reduction) = (stats.input, stats.output, stats.reduction() * 100.0);
+        println!("terrain/{name}: {input} -> {output} quads ({reduction:.1}% fewer)");
+
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| {
+                for chunk in &chunks {
+                    black_box(mesher.mesh(chunk));
+                }
+            })
+        });
+    }
+}
+
+// The slices greedy2d sees while meshing terrain, already merged along x
+fn slices() -> Vec<Vec<u64>> {
+    let layout = QuadLayout::DEFAULT;
+    let mut slices = Vec::new();
+
+    for seed in 0..8 {
+        for mut quads in cull(&terrain(seed)) {
+            greedy::greedy1d(&mut quads);
+
+            for slice in quads.chunk_by(|&a, &b| layout.location(a).2 == layout.location(b).2) {
+                slices.push(slice.to_vec());
+            }
+        }
+    }
+
+    slices
+}
+
+fn greedy2d(c: &mut Criterion) {
+    let slices = slices();
+
+    c.benchmark_group("greedy2d")
+        .bench_function(BenchmarkId::from_parameter("terrain"), |b| {
+            b.iter_batched(
+                || slices.clone(),
+                |mut slices| {
+                    for slice in &mut slices {
+                        greedy::greedy2d(slice);
+                    }
+
+                    slices
+                },
+                BatchSize::SmallInput,
+            )
+        });
+}
+
+// Every mesh of a list as one, to gather statistics across chunks
+fn merged(meshes: &[Mesh]) -> Mesh {
+    let mut merged = Mesh::default();
+
+    for mesh in meshes {
+        for (merged, quads) in merged.iter_mut().zip(mesh) {
+            merged.extend_from_slice(quads);
+        }
+    }
+
+    merged
+}
+
+// Everything between chunk data and the buffer contents of its mesh and palette
+fn upload(c: &mut Criterion) {
+    let chunks: Vec<_> = (0..8).map(terrain).collect();
+
+    let mut group = c.benchmark_group("upload");
+    group.throughput(throughput(&faces(&chunks)));
+
+    group.bench_function(BenchmarkId::from_parameter("greedy"), |b| {
+        b.iter(|| {
+            for chunk in &chunks {
+                let mut mesh = greedy::mesh_chunk(chunk);
+                let palette = Palette::index(chunk, &mut mesh);
+                black_box(palette.entries(|block| block as u32));
+                black_box(Packed::new(&mesh));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, meshers, greedy2d, upload);
+criterion_main!(benches);
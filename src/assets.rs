@@ -0,0 +1,270 @@
+// Files the engine loads at runtime, read and cached by their path under a root
+// directory, along with whatever gets built out of them: block textures out of
+// their images, shaders out of their source, and so on. Anything built remembers
+// what it was built out of, so polling for changed files once drops every asset
+// made stale by them, however indirectly, for it to be loaded again
+
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use wgpu::{ShaderModule, ShaderModuleDescriptor, ShaderSource};
+
+use crate::gfx::Gfx;
+
+#[derive(Debug)]
+pub struct Assets {
+    root: PathBuf,
+
+    // Every file and directory read, with when it was modified as of reading it
+    modified: HashMap<String, Option<SystemTime>>,
+    files: HashMap<String, Arc<[u8]>>,
+
+    // Whatever got built, as `load` was told to build it
+    built: HashMap<String, Arc<dyn Any + Send + Sync>>,
+
+    // Assets built out of every path, and those being built, innermost last
+    dependents: HashMap<String, HashSet<String>>,
+    building: Vec<String>,
+}
+
+impl Assets {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            modified: HashMap::new(),
+            files: HashMap::new(),
+            built: HashMap::new(),
+            dependents: HashMap::new(),
+            building: Vec::new(),
+        }
+    }
+
+    // Contents of a file, read once until it changes
+    pub fn bytes(&mut self, path: &str) -> Result<Arc<[u8]>, AssetError> {
+        self.depend(path);
+        if let Some(bytes) = self.files.get(path) {
+            return Ok(bytes.clone());
+        }
+
+        // Taken before reading, so anything written meanwhile shows up as changed
+        let full = self.root.join(path);
+        self.modified.insert(path.to_owned(), modified(&full));
+
+        let bytes: Arc<[u8]> = match fs::read(&full) {
+            Ok(bytes) => bytes.into(),
+            Err(err) => return Err(AssetError::Io(path.to_owned(), err)),
+        };
+
+        self.files.insert(path.to_owned(), bytes.clone());
+        Ok(bytes)
+    }
+
+    pub fn text(&mut self, path: &str) -> Result<String, AssetError> {
+        let bytes = self.bytes(path)?;
+        let text = String::from_utf8(bytes.to_vec());
+        text.map_err(|_| AssetError::Utf8(path.to_owned()))
+    }
+
+    // Paths of the files in a directory ending in `extension`, in name order.
+    // Files being added or removed change the directory, unlike files being written
+    pub fn list(&mut self, dir: &str, extension: &str) -> Result<Vec<String>, AssetError> {
+        self.depend(dir);
+        let full = self.root.join(dir);
+        self.modified.insert(dir.to_owned(), modified(&full));
+
+        let io = |err| AssetError::Io(dir.to_owned(), err);
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(&full).map_err(io)? {
+            let path = entry.map_err(io)?.path();
+            if path.extension().is_some_and(|other| other == extension) {
+                let name = path.file_name().unwrap().to_string_lossy();
+                paths.push(format!("{}/{name}", dir.trim_end_matches('/')));
+            }
+        }
+
+        paths.sort_unstable();
+        Ok(paths)
+    }
+
+    // Whatever `build` makes out of the files and assets it loads through here,
+    // built once until any of them changes. Every asset loaded at `path` must be
+    // of the same type
+    pub fn load<T: Any + Send + Sync>(
+        &mut self,
+        path: &str,
+        build: impl FnOnce(&mut Self) -> Result<T, AssetError>,
+    ) -> Result<Arc<T>, AssetError> {
+        self.depend(path);
+        if let Some(built) = self.built.get(path) {
+            let built = built.clone().downcast();
+            return built.map_err(|_| AssetError::Type(path.to_owned()));
+        }
+
+        self.building.push(path.to_owned());
+        let built = build(self);
+        self.building.pop();
+
+        let built = Arc::new(built?);
+        self.built.insert(path.to_owned(), built.clone());
+        Ok(built)
+    }
+
+    // A WGSL shader, named after its path
+    pub fn shader(&mut self, gfx: &Gfx, path: &str) -> Result<Arc<ShaderModule>, AssetError> {
+        self.load(path, |assets| {
+            let source = assets.text(path)?;
+            let descriptor = ShaderModuleDescriptor {
+                label: Some(path),
+                source: ShaderSource::Wgsl(source.into()),
+            };
+
+            Ok(gfx.device.create_shader_module(descriptor))
+        })
+    }
+
+    // Every file and directory changed since read, along with every asset built
+    // out of any of them, in order. All of them are dropped, to be loaded again
+    pub fn changed(&mut self) -> Vec<String> {
+        let mut stale: Vec<_> = self
+            .modified
+            .iter()
+            .filter(|(path, &then)| modified(&self.root.join(path)) != then)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut changed = HashSet::new();
+        while let Some(path) = stale.pop() {
+            if !changed.insert(path.clone()) {
+                continue;
+            }
+
+            self.modified.remove(&path);
+            self.files.remove(&path);
+            self.built.remove(&path);
+            stale.extend(self.dependents.remove(&path).into_iter().flatten());
+        }
+
+        let mut changed: Vec<_> = changed.into_iter().collect();
+        changed.sort_unstable();
+        changed
+    }
+
+    // Whatever is being built depends on `path`
+    fn depend(&mut self, path: &str) {
+        if let Some(building) = self.building.last() {
+            let dependents = self.dependents.entry(path.to_owned()).or_default();
+            dependents.insert(building.clone());
+        }
+    }
+}
+
+// Missing files have no time, so they change once they show up
+fn modified(path: &Path) -> Option<SystemTime> {
+    let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+    modified.ok()
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(String, io::Error),
+    Utf8(String),
+
+    // Loaded before as some other type
+    Type(String),
+
+    // Read fine, but what was in it could not be built into anything
+    Build(String, Box<dyn Error + Send + Sync>),
+}
+
+impl Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "cannot read {path}: {err}"),
+            Self::Utf8(path) => write!(f, "{path} is not UTF-8 text"),
+            Self::Type(path) => write!(f, "{path} was loaded as something else before"),
+            Self::Build(path, err) => write!(f, "cannot load {path}: {err}"),
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Build(_, err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    // As if written by something else a while later, as far as its time tells
+    fn touch(path: &Path, text: &str) {
+        fs::write(path, text).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(10);
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(later).unwrap();
+    }
+
+    #[test]
+    fn changes_drop_everything_built_out_of_them() {
+        let dir = std::env::temp_dir().join(format!("axial-assets-{}", std::process::id()));
+        fs::create_dir_all(dir.join("defs")).unwrap();
+        fs::write(dir.join("defs/stone.txt"), "1").unwrap();
+        fs::write(dir.join("defs/dirt.txt"), "2").unwrap();
+        fs::write(dir.join("scale.txt"), "10").unwrap();
+
+        // Every definition, scaled by another asset
+        let sum = |assets: &mut Assets| {
+            assets.load("sum", |assets| {
+                let scale = assets.load("scale", |assets| {
+                    let text = assets.text("scale.txt")?;
+                    Ok(text.parse::<u32>().unwrap())
+                })?;
+
+                let mut sum = 0;
+                for path in assets.list("defs", "txt")? {
+                    sum += assets.text(&path)?.parse::<u32>().unwrap() * *scale;
+                }
+
+                Ok(sum)
+            })
+        };
+
+        let mut assets = Assets::new(&dir);
+        assert_eq!(*sum(&mut assets).unwrap(), 30);
+        assert!(assets.changed().is_empty());
+        let other = assets.load::<u8>("sum", |_| Ok(0));
+        assert!(matches!(other, Err(AssetError::Type(_))));
+
+        touch(&dir.join("scale.txt"), "100");
+        assert_eq!(assets.changed(), ["scale", "scale.txt", "sum"]);
+        assert_eq!(*sum(&mut assets).unwrap(), 300);
+
+        touch(&dir.join("defs/dirt.txt"), "3");
+        assert_eq!(assets.changed(), ["defs/dirt.txt", "sum"]);
+        assert_eq!(*sum(&mut assets).unwrap(), 400);
+
+        fs::remove_file(dir.join("defs/stone.txt")).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(20);
+        let defs = fs::File::open(dir.join("defs")).unwrap();
+        defs.set_modified(later).unwrap();
+        assert_eq!(assets.changed(), ["defs", "defs/stone.txt", "sum"]);
+        assert_eq!(*sum(&mut assets).unwrap(), 300);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,213 @@
+// Results of the benchmark modes in a shape tools can read, for runs to be tracked
+// and plotted over time. Written as JSON if the file is named so, CSV otherwise,
+// a row or object per operation timed
+
+pub mod churn;
+pub mod fill;
+
+use std::{fmt::Write as _, fs, io, path::Path, time::Duration};
+
+use bytemuck::Pod;
+
+use crate::buddy::{Buddy, Metrics};
+
+// Columns of every row, in order, as the keys of every object in JSON
+const COLUMNS: [&str; 10] = [
+    "operation",
+    "count",
+    "wall_seconds",
+    "per_second",
+    "used",
+    "peak_used",
+    "capacity",
+    "allocs",
+    "frees",
+    "failed_allocs",
+];
+
+// Something done `count` times over, say allocations or frames,
+// taking `wall` in all
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub operation: String,
+    pub count: usize,
+    pub wall: Duration,
+
+    // What the allocator it went through was left at, if any
+    pub buddy: Option<BuddyStats>,
+}
+
+impl Record {
+    pub fn new(operation: impl Into<String>, count: usize, wall: Duration) -> Self {
+        Self {
+            operation: operation.into(),
+            count,
+            wall,
+            buddy: None,
+        }
+    }
+
+    // Along with what `buddy` went through
+    pub fn with_buddy<T: Pod>(self, buddy: &Buddy<T>) -> Self {
+        let stats = BuddyStats::new(buddy.metrics(), buddy.capacity());
+
+        Self {
+            buddy: Some(stats),
+            ..self
+        }
+    }
+
+    // Times done a second
+    pub fn per_second(&self) -> f64 {
+        self.count as f64 / self.wall.as_secs_f64().max(f64::EPSILON)
+    }
+
+    // Every column but the operation, empty where there are no stats
+    fn values(&self) -> [String; 9] {
+        let stats = self.buddy.as_ref();
+        let stat = |f: fn(&BuddyStats) -> usize| stats.map(f).map(|n| n.to_string());
+        let stat = |f| stat(f).unwrap_or_default();
+
+        [
+            self.count.to_string(),
+            self.wall.as_secs_f64().to_string(),
+            self.per_second().to_string(),
+            stat(|stats| stats.used),
+            stat(|stats| stats.peak_used),
+            stat(|stats| stats.capacity),
+            stat(|stats| stats.allocs),
+            stat(|stats| stats.frees),
+            stat(|stats| stats.failed_allocs),
+        ]
+    }
+}
+
+// Allocator metrics summed over every order, in units of whatever it hands out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BuddyStats {
+    pub used: usize,
+    pub peak_used: usize,
+    pub capacity: usize,
+    pub allocs: usize,
+    pub frees: usize,
+    pub failed_allocs: usize,
+}
+
+impl BuddyStats {
+    pub fn new(metrics: &Metrics, capacity: usize) -> Self {
+        Self {
+            used: metrics.used,
+            peak_used: metrics.peak_used,
+            capacity,
+            allocs: metrics.allocs_per_order.iter().sum(),
+            frees: metrics.frees_per_order.iter().sum(),
+            failed_allocs: metrics.failed_allocs,
+        }
+    }
+}
+
+// Records of a run, in the order they were taken
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Results {
+    pub records: Vec<Record>,
+}
+
+impl Results {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    // As JSON if `path` ends in .json, CSV otherwise
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let extension = path.extension();
+        let text = match extension.is_some_and(|extension| extension == "json") {
+            true => self.to_json(),
+            false => self.to_csv(),
+        };
+
+        fs::write(path, text)
+    }
+
+    // A header, then a row per record
+    pub fn to_csv(&self) -> String {
+        let mut csv = COLUMNS.join(",");
+        csv.push('\n');
+
+        for record in &self.records {
+            let operation = record.operation.replace('"', "\"\"");
+            let values = record.values().join(",");
+            let _ = writeln!(csv, "\"{operation}\",{values}");
+        }
+
+        csv
+    }
+
+    // An array of objects, with stats left out as null
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+
+        for (index, record) in self.records.iter().enumerate() {
+            let operation = record.operation.replace('\\', "\\\\").replace('"', "\\\"");
+            let values = record.values().map(|value| match value.is_empty() {
+                true => "null".to_owned(),
+                false => value,
+            });
+
+            let separator = if index == 0 { "" } else { "," };
+            let _ = write!(json, "{separator}\n  {{\"operation\": \"{operation}\"");
+            for (column, value) in COLUMNS[1..].iter().zip(values) {
+                let _ = write!(json, ", \"{column}\": {value}");
+            }
+
+            json.push('}');
+        }
+
+        json.push_str("\n]\n");
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results() -> Results {
+        let mut fill = Record::new("buddy fill", 4, Duration::from_millis(500));
+        fill.buddy = Some(BuddyStats {
+            used: 1024,
+            peak_used: 1024,
+            capacity: 4096,
+            allocs: 4,
+            frees: 0,
+            failed_allocs: 1,
+        });
+
+        let mesh = Record::new("mesh \"greedy\"", 10, Duration::from_secs(2));
+        Results {
+            records: vec![fill, mesh],
+        }
+    }
+
+    #[test]
+    fn records_come_out_as_csv_and_json() {
+        let results = results();
+        assert_eq!(results.records[0].per_second(), 8.0);
+
+        let csv = results.to_csv();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], COLUMNS.join(","));
+        assert_eq!(lines[1], "\"buddy fill\",4,0.5,8,1024,1024,4096,4,0,1");
+        assert_eq!(lines[2], "\"mesh \"\"greedy\"\"\",10,2,5,,,,,,");
+
+        let json = results.to_json();
+        let second = json.lines().nth(2).unwrap();
+        assert!(json.starts_with("[\n  {\"operation\": \"buddy fill\", \"count\": 4,"));
+        assert!(second.starts_with("  {\"operation\": \"mesh \\\"greedy\\\"\", \"count\": 10"));
+        assert!(second.ends_with("\"frees\": null, \"failed_allocs\": null}"));
+        assert!(json.ends_with("\n]\n"));
+    }
+}
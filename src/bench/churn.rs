@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Chances a step has of the camera teleporting, and of a chunk loaded being edited
+const TELEPORT: f64 = 1.0 / 500.0;
+const EDIT: f64 = 0.25;
+
+// Chunks loaded and unloaded a step as the camera flies along, at most
+const FLIGHT: usize = 4;
+
+// Something happening to the quads of a chunk, each chunk by a number of its own
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    // Loaded with a mesh of this many quads
+    Load(usize, usize),
+
+    // Edited, its mesh taking this many quads from then on in place of the last
+    Remesh(usize, usize),
+
+    Unload(usize),
+
+    // The camera jumped elsewhere, every chunk loaded about to be unloaded
+    // and as many loaded right after
+    Teleport,
+}
+
+// Chunks going through their lives as they do around a camera flying over the world,
+// to be replayed against an allocator: a few loaded ahead of the camera and as many
+// unloaded behind it every step, meshed again when edited, and all of them at once
+// whenever the camera teleports, followed by a burst of new ones. Mesh sizes are drawn
+// from those given, say out of meshing chunks, each as often as it comes up among them
+#[derive(Clone, Debug)]
+pub struct Workload {
+    rng: StdRng,
+    sizes: Vec<usize>,
+
+    // Chunks kept loaded once filled up, and those loaded, oldest first
+    resident: usize,
+    loaded: VecDeque<usize>,
+    next: usize,
+}
+
+impl Workload {
+    // Out of `seed`, the same events every time. Panics if there are no sizes
+    pub fn new(seed: u64, sizes: Vec<usize>, resident: usize) -> Self {
+        assert!(!sizes.is_empty(), "no mesh sizes to draw from");
+
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            sizes,
+            resident,
+            loaded: VecDeque::new(),
+            next: 0,
+        }
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.loaded.len()
+    }
+
+    // Whatever happens over the next step
+    pub fn step(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        if !self.loaded.is_empty() && self.rng.gen_bool(TELEPORT) {
+            events.push(Event::Teleport);
+            events.extend(self.loaded.drain(..).map(Event::Unload));
+        }
+
+        // Left behind by the camera, not quite in the order they were loaded
+        let flown = self.rng.gen_range(0..=FLIGHT);
+        let behind = (self.loaded.len() + flown).saturating_sub(self.resident);
+        for _ in 0..behind.min(self.loaded.len()) {
+            let oldest = self.rng.gen_range(0..self.loaded.len().min(FLIGHT * 4));
+            let chunk = self.loaded.swap_remove_front(oldest).unwrap();
+            events.push(Event::Unload(chunk));
+        }
+
+        // Filling up at once after starting or teleporting, a few at a time otherwise
+        let missing = self.resident.saturating_sub(self.loaded.len());
+        for _ in 0..missing.max(flown).min(self.resident) {
+            let chunk = self.next;
+            self.next += 1;
+            self.loaded.push_back(chunk);
+            events.push(Event::Load(chunk, self.size()));
+        }
+
+        if !self.loaded.is_empty() && self.rng.gen_bool(EDIT) {
+            let chunk = self.loaded[self.rng.gen_range(0..self.loaded.len())];
+            events.push(Event::Remesh(chunk, self.size()));
+        }
+
+        events
+    }
+
+    fn size(&mut self) -> usize {
+        self.sizes[self.rng.gen_range(0..self.sizes.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn chunks_stay_resident_through_teleports() {
+        let sizes = vec![256, 1024, 3000];
+        let mut workload = Workload::new(7, sizes.clone(), 100);
+        let mut loaded = HashSet::new();
+        let mut teleports = 0;
+
+        for step in 0..5000 {
+            for event in workload.step() {
+                match event {
+                    Event::Load(chunk, size) => {
+                        assert!(sizes.contains(&size));
+                        assert!(loaded.insert(chunk), "loaded twice");
+                    }
+                    Event::Remesh(chunk, _) => assert!(loaded.contains(&chunk)),
+                    Event::Unload(chunk) => assert!(loaded.remove(&chunk), "never loaded"),
+                    Event::Teleport => teleports += 1,
+                }
+            }
+
+            assert_eq!(loaded.len(), 100, "step {step}");
+            assert_eq!(workload.loaded(), 100);
+        }
+
+        assert!(teleports > 0);
+
+        // Out of the same seed, the very same events
+        let first = |seed| Workload::new(seed, sizes.clone(), 100).step();
+        assert_eq!(first(7), first(7));
+        assert_ne!(first(7), first(8));
+    }
+}
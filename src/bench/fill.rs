@@ -0,0 +1,49 @@
+use std::time::Instant;
+
+use super::Record;
+use crate::{buddy::Buddy, gfx::Gfx, mesh::QuadRef};
+
+// Fill a quad buddy of `capacity` quads with `chunk_size` allocations until it runs
+// out, then free them all, checking it is left as it was, along with a record of each.
+// Timings of the tree alone live in `benches/buddy.rs`, these take the real buffer along
+pub fn fill_and_empty(gfx: &Gfx, capacity: usize, min_order: u8, chunk_size: usize) -> [Record; 2] {
+    let min_alloc = 1 << min_order;
+    tracing::info!(capacity, min_alloc, chunk_size, "quad buddy");
+
+    let untouched_quad_buddy = Buddy::<QuadRef>::new(gfx, capacity, min_order);
+    let mut quad_buddy = Buddy::<QuadRef>::new(gfx, capacity, min_order);
+
+    // Perform as many allocations as possible
+    let span = tracing::info_span!("fill").entered();
+    let start = Instant::now();
+    let mut handles = Vec::new();
+    while let Some(handle) = quad_buddy.alloc(chunk_size) {
+        handles.push(handle);
+    }
+
+    let allocated = start.elapsed();
+    span.exit();
+    let filled = Record::new("buddy fill", handles.len(), allocated).with_buddy(&quad_buddy);
+
+    // Minimum size allocations must take up the whole buffer
+    if chunk_size == 1 << min_order {
+        assert_eq!(handles.len(), capacity >> min_order);
+        assert!(quad_buddy.alloc(1).is_none());
+    }
+
+    let count = handles.len();
+    let span = tracing::info_span!("empty").entered();
+    let start = Instant::now();
+    for handle in handles {
+        quad_buddy.free(handle);
+    }
+
+    let freed = start.elapsed();
+    span.exit();
+    tracing::info!(count, ?allocated, ?freed, "quad buddy filled and emptied");
+
+    // The buddy must be left in the same state as it was after its creation
+    assert!(untouched_quad_buddy.check_is_same(&quad_buddy));
+    let emptied = Record::new("buddy empty", count, freed).with_buddy(&quad_buddy);
+    [filled, emptied]
+}
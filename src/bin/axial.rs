@@ -0,0 +1,2514 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    env,
+    error::Error,
+    f32::consts::TAU,
+    ffi::OsStr,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    mem,
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use clap::{Args, Parser, Subcommand};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tracing::{error, info, info_span};
+use tracing_chrome::{ChromeLayerBuilder, FlushGuard};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use wgpu::{Maintain, PresentMode, Texture, TextureFormat, TextureView, TextureViewDescriptor};
+use winit::{
+    dpi::PhysicalSize,
+    error::{EventLoopError, OsError},
+    event::{DeviceEvent, ElementState, Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget},
+    window::{Window, WindowBuilder},
+};
+
+use rust_playground::{
+    assets::Assets,
+    bench::{
+        churn::{Event, Workload},
+        fill, Record, Results,
+    },
+    buddy::Buddy,
+    camera::{Camera, FlyCamera},
+    config::{Config, ConfigError, Redraw},
+    demo,
+    entity::{Entities, FixedStep},
+    gfx::{
+        AdapterChoice, DynamicResolution, FrameRecorder, FrameTimes, Gfx, GfxError, Graph, Summary,
+        HDR_FORMAT,
+    },
+    input::{Action, Bindings, Gamepads, Input, Playback, Recorded, Replay, ReplayError},
+    mesh::{
+        self, debug, export, greedy, pick::is_pickable, quad_material, BlockId, Chunk, Facing,
+        Layers, Mesh, QuadRef, AIR, GLASS, LAMP, LOG, WATER,
+    },
+    net::{
+        protocol::{ToClient, ToServer},
+        Client, NetError, Server,
+    },
+    player::Player,
+    renderer::{self, Antialiasing, ChunkDraw, DebugView, Renderer, RendererError},
+    screen::Screen,
+    state::AppState,
+    textures::BlockTextures,
+    world::{
+        terrain, Biome, ChunkMap, Generator, Journal, RegionStore, Snapshot, Stored, Streaming,
+        Workers,
+    },
+};
+
+// Running unless told otherwise, taking the arguments of `run` as they are
+#[derive(Debug, Parser)]
+#[command(name = "axial", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Open the window and fly around
+    Run(RunArgs),
+
+    /// Fill the quad buffer with allocations and free them all, timing both
+    BenchBuddy(BenchBuddyArgs),
+
+    /// Mesh chunks of generated hills with every mesher, timing each
+    BenchMesh(BenchMeshArgs),
+
+    /// Fly along a path out of the seed through hills out of the same seed,
+    /// loading chunks in the same order every time, and report frame times
+    /// and what the quad buffer went through
+    BenchFly(BenchFlyArgs),
+
+    /// Load, mesh again and unload chunks in the quad buffer as flying around does,
+    /// teleporting every so often, with mesh sizes out of hills meshed first,
+    /// and report how often it ran out and how fragmented it got
+    BenchChurn(BenchChurnArgs),
+
+    /// Generate chunks for clients connecting as they ask for them, and pass
+    /// block edits along between them, with no window
+    Serve(ServeArgs),
+
+    /// Greedy mesh a few slices and a hill, showing what every step leaves,
+    /// then dig into the hill rewriting only the quads that changed
+    Demo,
+}
+
+#[derive(Debug, Args)]
+struct BufferArgs {
+    /// Size of the quad buffer in bytes, in decimal or 0x hex
+    #[arg(long, default_value = "0x1000_0000", value_parser = parse_size)]
+    buffer_size: usize,
+
+    /// Smallest block the quad buffer hands out, as a power of two of quads
+    #[arg(long, default_value_t = 8)]
+    min_order: u8,
+}
+
+#[derive(Debug, Args)]
+struct RunArgs {
+    #[command(flatten)]
+    buffer: BufferArgs,
+
+    /// Seed of the hills generated around the camera
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Chunks around the camera to generate, and unload past,
+    /// in place of the render distance in the settings
+    #[arg(long)]
+    render_distance: Option<i32>,
+
+    /// Log frame time averages and lows about once a second
+    #[arg(long)]
+    log_frame_times: bool,
+
+    /// Get chunks from the server at this address, as started by `serve`,
+    /// rather than generating them, sharing block edits with all connected
+    #[arg(long)]
+    connect: Option<String>,
+
+    /// Settings to start with, written back on exit if any of them changed
+    #[arg(long, default_value = "axial.toml")]
+    config: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct ResultsArgs {
+    /// Also write what got timed into this file, as JSON if it ends in .json
+    /// and as CSV otherwise, for runs to be tracked over time
+    #[arg(long)]
+    results: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct BenchBuddyArgs {
+    #[command(flatten)]
+    buffer: BufferArgs,
+
+    /// Quads in every allocation, as many as a chunk mesh may hold,
+    /// the smallest block by default
+    #[arg(long)]
+    chunk_size: Option<usize>,
+
+    #[command(flatten)]
+    output: ResultsArgs,
+}
+
+#[derive(Debug, Args)]
+struct BenchMeshArgs {
+    /// Seed of the hills meshed
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// How many chunks to mesh
+    #[arg(long, default_value_t = 64)]
+    chunks: usize,
+
+    /// Any of the mesher names, all of them if not given
+    #[arg(long)]
+    mesher: Option<String>,
+
+    #[command(flatten)]
+    output: ResultsArgs,
+}
+
+#[derive(Debug, Args)]
+struct BenchFlyArgs {
+    #[command(flatten)]
+    buffer: BufferArgs,
+
+    /// Seed of the hills, and of the path flown over them
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Frames to draw, at 60 frames per second of flight whatever they take
+    #[arg(long, default_value_t = 3600)]
+    frames: u32,
+
+    /// Chunks around the camera to load, and unload past
+    #[arg(long, default_value_t = 6)]
+    render_distance: i32,
+
+    /// Draw offscreen rather than into a window, for machines with no display
+    /// or GPU, as on CI or over SSH. Frames are waited on one by one
+    #[arg(long)]
+    headless: bool,
+
+    #[command(flatten)]
+    output: ResultsArgs,
+}
+
+#[derive(Debug, Args)]
+struct BenchChurnArgs {
+    #[command(flatten)]
+    buffer: BufferArgs,
+
+    /// Seed of the hills meshed, and of what happens to chunks
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Chunks of hills to mesh, for mesh sizes to be drawn out of
+    #[arg(long, default_value_t = 256)]
+    samples: usize,
+
+    /// Chunks kept loaded, as many as the render distance takes in
+    #[arg(long, default_value_t = 4096)]
+    resident: usize,
+
+    /// Steps to go through, each loading and unloading a few chunks
+    #[arg(long, default_value_t = 100_000)]
+    steps: usize,
+
+    #[command(flatten)]
+    output: ResultsArgs,
+}
+
+#[derive(Debug, Args)]
+struct ServeArgs {
+    /// Seed of the hills generated
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Address to listen on, only reachable from this machine by default
+    #[arg(long, default_value = "127.0.0.1:24680")]
+    listen: String,
+}
+
+// Sizes as in 268435456, 0x1000_0000 or 0x10000000
+fn parse_size(text: &str) -> Result<usize, ParseIntError> {
+    let text = text.replace('_', "");
+    match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    }
+}
+
+// Whatever keeps a command from going on, logged by `main` on the way out
+#[derive(Debug)]
+enum AxialError {
+    Config(PathBuf, ConfigError),
+    EventLoop(EventLoopError),
+    Window(OsError),
+    Gfx(GfxError),
+    Renderer(RendererError),
+
+    // Not any of `mesh::MESHERS`
+    Mesher(String),
+
+    Replay(PathBuf, ReplayError),
+    Net(NetError),
+}
+
+impl Display for AxialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Config(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::EventLoop(err) => write!(f, "event loop failed: {err}"),
+            Self::Window(err) => write!(f, "cannot open window: {err}"),
+            Self::Gfx(err) => write!(f, "{err}"),
+            Self::Renderer(err) => write!(f, "{err}"),
+            Self::Mesher(name) => write!(f, "unknown mesher {name}"),
+            Self::Replay(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Net(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for AxialError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Config(_, err) => Some(err),
+            Self::EventLoop(err) => Some(err),
+            Self::Window(err) => Some(err),
+            Self::Gfx(err) => Some(err),
+            Self::Renderer(err) => Some(err),
+            Self::Mesher(_) => None,
+            Self::Replay(_, err) => Some(err),
+            Self::Net(err) => Some(err),
+        }
+    }
+}
+
+#[pollster::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let _trace = init_tracing();
+
+    let result = match cli.command.unwrap_or(Command::Run(cli.run)) {
+        Command::Run(args) => run(args).await,
+        Command::BenchBuddy(args) => bench_buddy(&args).await,
+        Command::BenchMesh(args) => bench_mesh(&args),
+        Command::BenchFly(args) => bench_fly(args).await,
+        Command::BenchChurn(args) => bench_churn(&args).await,
+        Command::Serve(args) => serve(&args),
+        Command::Demo => demo().await,
+    };
+
+    // Returned rather than exited with, for the trace to be written out
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Logging to stderr whatever `RUST_LOG` lets through, info and up by default,
+// and every span into a Chrome trace at `AXIAL_TRACE` if set, to be opened
+// in chrome://tracing or Perfetto. The trace is written out as the guard drops.
+// Built with the tracy feature, spans also go to Tracy as they happen
+fn init_tracing() -> Option<FlushGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let log = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+
+    let (chrome, guard) = match env::var_os("AXIAL_TRACE") {
+        Some(path) => {
+            let (layer, guard) = ChromeLayerBuilder::new().file(path).build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    // Chrome traces take every span, not only those logged
+    let registry = tracing_subscriber::registry().with(chrome);
+
+    #[cfg(feature = "tracy")]
+    let registry = registry.with(tracing_tracy::TracyLayer::default());
+
+    registry.with(log.with_filter(filter)).init();
+    guard
+}
+
+async fn bench_buddy(args: &BenchBuddyArgs) -> Result<(), AxialError> {
+    let gfx = Gfx::headless(1, 1, TextureFormat::Rgba8Unorm).await;
+    let gfx = gfx.map_err(AxialError::Gfx)?;
+
+    print!("{}", gfx.report);
+    let BufferArgs {
+        buffer_size,
+        min_order,
+    } = args.buffer;
+
+    let capacity = buffer_size / mem::size_of::<QuadRef>();
+    let chunk_size = args.chunk_size.unwrap_or(1 << min_order);
+    let records = fill::fill_and_empty(&gfx, capacity, min_order, chunk_size);
+    let results = Results {
+        records: records.into(),
+    };
+
+    write_results(&results, args.output.results.as_deref());
+    Ok(())
+}
+
+// Allocations failing and free space fragmenting as chunks come and go,
+// rather than how fast it all goes, which the other benchmarks are about
+async fn bench_churn(args: &BenchChurnArgs) -> Result<(), AxialError> {
+    let gfx = Gfx::headless(1, 1, TextureFormat::Rgba8Unorm).await;
+    let gfx = gfx.map_err(AxialError::Gfx)?;
+    print!("{}", gfx.report);
+
+    let sizes = mesh_sizes(args.seed, args.samples);
+    if sizes.is_empty() {
+        error!("no chunk meshed into any quads, so there are no sizes to go by");
+        return Ok(());
+    }
+
+    let average = sizes.iter().sum::<usize>() / sizes.len();
+    info!(meshes = sizes.len(), average, "mesh sizes");
+
+    let BufferArgs {
+        buffer_size,
+        min_order,
+    } = args.buffer;
+
+    let capacity = buffer_size / mem::size_of::<QuadRef>();
+    let mut quads = Buddy::<QuadRef>::new(&gfx, capacity, min_order);
+    let mut workload = Workload::new(args.seed, sizes, args.resident);
+    let mut handles = HashMap::new();
+
+    let (mut allocs, mut failed, mut teleports) = (0, 0, 0);
+    let (mut fragmentation, mut worst) = (0.0, 0.0_f32);
+    let start = Instant::now();
+
+    for _ in 0..args.steps {
+        for event in workload.step() {
+            let (chunk, len) = match event {
+                Event::Load(chunk, len) | Event::Remesh(chunk, len) => (chunk, len),
+                Event::Unload(chunk) => {
+                    if let Some(handle) = handles.remove(&chunk) {
+                        quads.free(handle);
+                    }
+
+                    continue;
+                }
+                Event::Teleport => {
+                    teleports += 1;
+                    continue;
+                }
+            };
+
+            // Meshed again, its last quads go first, as they do while playing
+            if let Some(handle) = handles.remove(&chunk) {
+                quads.free(handle);
+            }
+
+            allocs += 1;
+            match quads.alloc(len) {
+                Some(handle) => {
+                    handles.insert(chunk, handle);
+                }
+                None => failed += 1,
+            }
+        }
+
+        let step = fragmented(&quads);
+        fragmentation += step;
+        worst = worst.max(step);
+    }
+
+    let elapsed = start.elapsed();
+    let percent = |share: f32| share * 100.0;
+    let average = percent(fragmentation / args.steps.max(1) as f32);
+    let failures = percent(failed as f32 / allocs.max(1) as f32);
+    let worst = percent(worst);
+    let (seed, steps, resident) = (args.seed, args.steps, args.resident);
+
+    println!();
+    println!("seed {seed}, {steps} steps, {resident} chunks resident");
+    println!("{allocs} allocs, {failures:.2}% failed, {teleports} teleports");
+    println!("fragmented {average:.1}% on average, {worst:.1}% at worst");
+    report_buddy(&quads);
+
+    let record = Record::new("churn allocs", allocs, elapsed).with_buddy(&quads);
+    let results = Results {
+        records: vec![record],
+    };
+
+    write_results(&results, args.output.results.as_deref());
+    for handle in handles.into_values() {
+        quads.free(handle);
+    }
+
+    Ok(())
+}
+
+// Quads of every layer of chunks of hills out of `seed`, from around the ground,
+// leaving out layers with none
+fn mesh_sizes(seed: u64, samples: usize) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut sizes = Vec::new();
+
+    for _ in 0..samples {
+        let (x, z) = (rng.gen_range(-64..64), rng.gen_range(-64..64));
+        let mesh = greedy::mesh_chunk(&terrain(seed, [x, rng.gen_range(-1..=1), z]).0);
+        let Layers {
+            opaque,
+            translucent,
+            water,
+        } = Layers::split(mesh);
+
+        let layers = [opaque, translucent, water];
+        let lens = layers.map(|layer| layer.iter().map(Vec::len).sum());
+        sizes.extend(lens.into_iter().filter(|&len| len > 0));
+    }
+
+    sizes
+}
+
+// Share of the free space of a buddy not in its biggest free block,
+// none with all of it in one block, nearing all as it gets split up
+fn fragmented(quads: &Buddy<QuadRef>) -> f32 {
+    match quads.capacity() - quads.metrics().used {
+        0 => 0.0,
+        free => 1.0 - quads.largest_free() as f32 / free as f32,
+    }
+}
+
+// Serve chunks and edits for as long as listening works
+fn serve(args: &ServeArgs) -> Result<(), AxialError> {
+    let seed = args.seed;
+    let generator: Arc<Generator> = Arc::new(move |pos| terrain(seed, pos));
+    let server = Server::bind(&args.listen, seed, generator);
+    let server = server.map_err(|err| AxialError::Net(err.into()))?;
+
+    if let Ok(addr) = server.local_addr() {
+        info!("serving seed {seed} on {addr}");
+    }
+
+    server.run().map_err(|err| AxialError::Net(err.into()))
+}
+
+fn bench_mesh(args: &BenchMeshArgs) -> Result<(), AxialError> {
+    let names = match &args.mesher {
+        Some(name) => vec![name.as_str()],
+        None => mesh::MESHERS.to_vec(),
+    };
+
+    let chunks: Vec<_> = (0..args.chunks as i32)
+        .map(|x| terrain(args.seed, [x, 0, 0]).0)
+        .collect();
+
+    let mut results = Results::new();
+
+    for name in names {
+        let Some(mesher) = mesh::mesher(name) else {
+            return Err(AxialError::Mesher(name.to_owned()));
+        };
+
+        let _span = info_span!("bench_mesh", mesher = name).entered();
+        let start = Instant::now();
+        let meshes: Vec<_> = chunks.iter().map(|chunk| mesher.mesh(chunk)).collect();
+        let elapsed = start.elapsed();
+
+        let quads: usize = meshes.iter().flatten().map(Vec::len).sum();
+        let per_chunk = elapsed / chunks.len().max(1) as u32;
+        info!(quads, ?elapsed, ?per_chunk, "meshed {name}");
+        results.push(Record::new(format!("mesh {name}"), chunks.len(), elapsed));
+    }
+
+    write_results(&results, args.output.results.as_deref());
+    Ok(())
+}
+
+// Frames and chunks all follow from the seed, and so do the allocations behind
+// them, leaving the frame times alone to change from run to run
+async fn bench_fly(args: BenchFlyArgs) -> Result<(), AxialError> {
+    if args.headless {
+        return bench_fly_headless(args).await;
+    }
+
+    let event_loop = EventLoop::new().map_err(AxialError::EventLoop)?;
+    event_loop.set_control_flow(ControlFlow::Poll);
+
+    let window = WindowBuilder::new()
+        .with_title("aXial benchmark")
+        .with_inner_size(PhysicalSize::new(1280, 720))
+        .with_resizable(false)
+        .build(&event_loop);
+    let window = Arc::new(window.map_err(AxialError::Window)?);
+
+    let gfx = Gfx::new(window.clone()).await;
+    let mut gfx = gfx.map_err(AxialError::Gfx)?;
+
+    // Not held back by the display, where it can
+    if let Err(err) = gfx.set_present_mode(PresentMode::Immediate) {
+        error!("{err}");
+    }
+
+    print!("{}", gfx.report);
+    let mut bench = FlyBench::new(&gfx, &args).map_err(AxialError::Renderer)?;
+
+    let result = event_loop.run(move |event, target| match event {
+        Event::WindowEvent { event, .. } => match event {
+            WindowEvent::CloseRequested => target.exit(),
+            WindowEvent::RedrawRequested => {
+                if bench.is_done() {
+                    bench.finish();
+                    target.exit();
+                    return;
+                }
+
+                let Some(output) = gfx.acquire_frame() else {
+                    return;
+                };
+
+                let view = TextureViewDescriptor::default();
+                bench.draw(&gfx, &output.texture.create_view(&view));
+                output.present();
+            }
+            _ => {}
+        },
+        Event::AboutToWait => window.request_redraw(),
+        _ => {}
+    });
+
+    result.map_err(AxialError::EventLoop)
+}
+
+// Same as `bench_fly`, drawing offscreen at the same size with no window at all,
+// on whatever adapter there is, down to a software one
+async fn bench_fly_headless(args: BenchFlyArgs) -> Result<(), AxialError> {
+    let gfx = Gfx::headless(1280, 720, TextureFormat::Rgba8UnormSrgb).await;
+    let gfx = gfx.map_err(AxialError::Gfx)?;
+
+    print!("{}", gfx.report);
+    let mut bench = FlyBench::new(&gfx, &args).map_err(AxialError::Renderer)?;
+    let (_, view) = gfx.offscreen.as_ref().expect("headless without a target");
+
+    while !bench.is_done() {
+        bench.draw(&gfx, view);
+
+        // Nothing presents frames to hold the next one back, so every frame is waited on
+        gfx.device.poll(Maintain::Wait);
+    }
+
+    bench.finish();
+    Ok(())
+}
+
+// What a `bench_fly` run keeps from frame to frame, be it drawn into a window or not
+struct FlyBench {
+    seed: u64,
+    frames: u32,
+    quads: Buddy<QuadRef>,
+    renderer: Renderer,
+    world: ChunkMap,
+    streaming: Streaming,
+    mesher: greedy::Greedy,
+    flight: Flight,
+    camera: Camera,
+    times: FrameTimes,
+    started: Instant,
+    drawn: Instant,
+    frame: u32,
+    loaded: usize,
+    unloaded: usize,
+
+    // Where to write results into, if anywhere
+    results: Option<PathBuf>,
+}
+
+impl FlyBench {
+    fn new(gfx: &Gfx, args: &BenchFlyArgs) -> Result<Self, RendererError> {
+        let BufferArgs {
+            buffer_size,
+            min_order,
+        } = args.buffer;
+
+        let capacity = buffer_size / mem::size_of::<QuadRef>();
+        let quads = Buddy::<QuadRef>::new(gfx, capacity, min_order);
+        let renderer = Renderer::new(gfx, &quads, 1 << 16)?;
+        tint_biomes(gfx, &renderer);
+
+        Ok(Self {
+            seed: args.seed,
+            frames: args.frames,
+            quads,
+            renderer,
+            world: ChunkMap::new(),
+            streaming: Streaming::new(args.render_distance),
+            mesher: greedy::Greedy::default(),
+            flight: Flight::new(args.seed, args.frames),
+            camera: Camera::new([0.0; 3]),
+            times: FrameTimes::new(),
+            started: Instant::now(),
+            drawn: Instant::now(),
+            frame: 0,
+            loaded: 0,
+            unloaded: 0,
+            results: args.output.results.clone(),
+        })
+    }
+
+    fn is_done(&self) -> bool {
+        self.frame == self.frames
+    }
+
+    // The next frame, into `view`
+    fn draw(&mut self, gfx: &Gfx, view: &TextureView) {
+        let Self {
+            quads,
+            renderer,
+            world,
+            camera,
+            frame,
+            ..
+        } = self;
+
+        let interval = mem::replace(&mut self.drawn, Instant::now()).elapsed();
+        let frame_start = Instant::now();
+        let gpu = gfx.profiler().map(|profiler| profiler.times().to_vec());
+        let gpu = gpu.filter(|times| !times.is_empty());
+        let gpu = gpu.map(|times| times.iter().map(|(_, time)| *time).sum());
+
+        self.flight.place(camera, *frame);
+
+        // Water ripples as it would at 60 frames per second, whatever the frame rate
+        renderer.time = *frame as f32 / 60.0;
+
+        // Chunks come in right away, in the order streaming wants them
+        let mut wanted = Vec::new();
+        let request = |pos| wanted.push(pos);
+        self.unloaded += self.streaming.update_with(world, quads, camera, request);
+
+        for &pos in &wanted {
+            let (blocks, biome) = terrain(self.seed, pos);
+            world.insert(pos, blocks);
+            world.set_biome(pos, biome);
+        }
+
+        self.loaded += wanted.len();
+        world.mesh_dirty(gfx, quads, &self.mesher, wanted.len());
+        quads.flush_staged(gfx, usize::MAX);
+
+        let mut graph = Graph::new();
+        let frame_slot = graph.import(view);
+        let chunks: Vec<_> = world.renderable().collect();
+        renderer.declare(gfx, &mut graph, frame_slot, quads, camera, &chunks);
+        gfx.run(graph);
+
+        self.times.push(interval, frame_start.elapsed(), gpu);
+        *frame += 1;
+    }
+
+    // Frame times, and what the quad buddy went through
+    fn finish(&mut self) {
+        println!();
+        println!("seed {}, {} frames", self.seed, self.frame);
+        let times = &self.times;
+        let summaries = [
+            times.interval().map(|summary| describe("frame", &summary)),
+            times.cpu().map(|summary| describe("cpu", &summary)),
+            times.gpu().map(|summary| describe("gpu", &summary)),
+        ];
+
+        for line in summaries.into_iter().flatten() {
+            println!("{line}");
+        }
+
+        println!("{} chunks loaded, {} unloaded", self.loaded, self.unloaded);
+        report_buddy(&self.quads);
+
+        // Frames and chunks loaded a second, over the whole flight
+        let wall = self.started.elapsed();
+        let frames = Record::new("fly frames", self.frame as usize, wall);
+        let loaded = Record::new("fly chunks loaded", self.loaded, wall);
+        let results = Results {
+            records: vec![frames.with_buddy(&self.quads), loaded],
+        };
+
+        write_results(&results, self.results.as_deref());
+        self.world.clear(&mut self.quads);
+    }
+}
+
+// A flight through the air over the hills, going from one waypoint to the next
+// at a steady speed, each a turn of up to 45 degrees away from the last
+struct Flight {
+    waypoints: Vec<[f32; 3]>,
+}
+
+impl Flight {
+    // Blocks flown per frame, and between waypoints
+    const SPEED: f32 = 0.5;
+    const LEG: f32 = 96.0;
+
+    // Enough waypoints to last `frames` frames
+    fn new(seed: u64, frames: u32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let legs = (frames as f32 * Self::SPEED / Self::LEG).ceil() as usize + 2;
+
+        let mut heading: f32 = rng.gen_range(0.0..TAU);
+        let mut point = [16.0, 48.0, 16.0];
+        let mut waypoints = vec![point];
+
+        for _ in 0..legs {
+            heading += rng.gen_range(-TAU / 8.0..TAU / 8.0);
+            point = [
+                point[0] + heading.cos() * Self::LEG,
+                rng.gen_range(40.0..64.0),
+                point[2] + heading.sin() * Self::LEG,
+            ];
+
+            waypoints.push(point);
+        }
+
+        Self { waypoints }
+    }
+
+    // Where the camera is at `frame`, looking ahead to the next waypoint
+    fn place(&self, camera: &mut Camera, frame: u32) {
+        let along = frame as f32 * Self::SPEED / Self::LEG;
+        let (leg, t) = (along as usize, along.fract());
+        let (from, to) = (self.waypoints[leg], self.waypoints[leg + 1]);
+
+        camera.eye = std::array::from_fn(|axis| from[axis] + (to[axis] - from[axis]) * t);
+        camera.look_at(to);
+    }
+}
+
+// What a quad buddy went through, in the same terms every run
+fn report_buddy(quads: &Buddy<QuadRef>) {
+    let metrics = quads.metrics();
+    let allocs: usize = metrics.allocs_per_order.iter().sum();
+    let frees: usize = metrics.frees_per_order.iter().sum();
+
+    let (used, peak, failed) = (metrics.used, metrics.peak_used, metrics.failed_allocs);
+    let capacity = quads.capacity();
+
+    println!("quad buddy: {used} of {capacity} quads used, {peak} at peak");
+    println!("{allocs} allocs, {frees} frees, {failed} failed");
+
+    let orders = metrics.allocs_per_order.iter().enumerate();
+    for (order, &count) in orders.filter(|&(_, &count)| count > 0) {
+        println!("order {order}: {count} allocs");
+    }
+}
+
+// Write `results` into the file asked for, if any
+fn write_results(results: &Results, path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+
+    match results.write(path) {
+        Ok(()) => info!("wrote results into {}", path.display()),
+        Err(err) => error!("{}: {err}", path.display()),
+    }
+}
+
+// Chunks around the camera to ask servers for, unless given a render distance
+const CONNECTED_RENDER_DISTANCE: i32 = 8;
+
+// How often chunks edited since are saved, when saving at all
+const AUTOSAVE: Duration = Duration::from_secs(30);
+
+// How often gamepads are polled while drawing on demand and sitting idle
+const GAMEPAD_POLL: Duration = Duration::from_millis(16);
+
+async fn run(args: RunArgs) -> Result<(), AxialError> {
+    let event_loop = EventLoop::new().map_err(AxialError::EventLoop)?;
+    let mut app = App::new(args, &event_loop).await?;
+    let result = event_loop.run(|event, target| app.handle(event, target));
+    result.map_err(AxialError::EventLoop)?;
+
+    match app.failed {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+// Blocks under the crosshair no farther than this are outlined, to be broken
+// or built against
+const REACH: f32 = 64.0;
+
+// Blocks that can be placed, stone to begin with
+const PLACEABLE: [BlockId; 3] = [1, GLASS, LAMP];
+
+// What `run` keeps from one event to the next. Events go through `handle`,
+// and actions pressed through `route`, as the state the game is in decides
+struct App {
+    window: Arc<Window>,
+    gfx: Gfx<'static>,
+
+    // Settings as they are now and as loaded, written back into `config_path`
+    // on exit if any of them changed
+    config: Config,
+    loaded: Config,
+    config_path: PathBuf,
+
+    // Drawing on demand sleeps until something calls for a frame,
+    // rather than going from one frame to the next
+    on_demand: bool,
+
+    // Bytes of quads written a frame
+    upload_budget: usize,
+
+    // Chunks and edits shared with a server rather than generated here,
+    // its seed taking the place of the one given
+    client: Option<Client>,
+    seed: u64,
+
+    // Orbited around, drawn straight out of the quad buddy.
+    // All of it lives in the device, so it is built again along with it,
+    // out of the blocks as edited by then, untextured until textures change
+    scene: Rc<RefCell<Scene>>,
+
+    // Why the scene could not be built again, if so, leaving nothing to draw with
+    unrecovered: Rc<Cell<Option<RendererError>>>,
+
+    // Why the event loop was left, if it went wrong, to be returned out of `run`
+    failed: Option<AxialError>,
+
+    // Block textures and anything else read while running, loaded again as their
+    // files change. Blocks keep their made-up colors going without
+    assets: Assets,
+    assets_polled: Instant,
+
+    // Moved back whenever a snapshot is restored, for the time of day to be as it was
+    start: Instant,
+
+    // Going from windowed to borderless to exclusive fullscreen, and back
+    screen: Screen,
+
+    // The first frame drawn, as a PNG, and any other one asked for later on
+    capture: Option<PathBuf>,
+
+    // Every frame from when asked to until asked again, as numbered PNGs
+    recorder: Option<FrameRecorder>,
+
+    // Where the frame being drawn came out of, saved into this file when asked to
+    // and restored out of it later on, in this run or any other
+    snapshot_path: PathBuf,
+
+    // Seconds the sun takes to go around, standing still if unset
+    day: Option<f32>,
+
+    // GPU time of every pass, about once a second
+    profile: bool,
+    reported: Instant,
+
+    // Frame times over the last few thousand frames, shown on the HUD
+    // and logged along with GPU times if asked to
+    times: FrameTimes,
+    log_frame_times: bool,
+
+    // Which of `PLACEABLE` gets placed
+    placing: usize,
+
+    // Items dropped by broken blocks, moved along 60 times a second whatever the frame rate
+    entities: Entities,
+    ticks: FixedStep,
+    tick: u64,
+
+    // Input played back out of a file, in place of any coming in,
+    // against the seed and from where it was recorded
+    playback: Option<Playback>,
+
+    // Hills all around as far as the render distance, generated as the camera
+    // flies towards them and unloaded once too many are loaded
+    streaming: Option<(Streaming, Workers)>,
+
+    saving: Option<Saving>,
+
+    // Chunks loaded or left dirty are meshed here, unless streaming
+    mesher: greedy::Greedy,
+
+    // Frame times and what is being drawn, shown over the frame
+    hud: bool,
+
+    // Actions bound to keys and buttons
+    bindings: Bindings,
+
+    // Actions pressed or released by any input since the last frame,
+    // and how far the mouse moved while grabbed
+    actions: Vec<(Action, ElementState)>,
+    looked: [f64; 2],
+
+    // Flying around with sticks and pressing actions with buttons, if there are gamepads
+    gamepads: Option<Gamepads>,
+
+    // Orbiting around the hill until clicked into, then flown around
+    camera: Camera,
+    fly: FlyCamera,
+    orbiting: bool,
+
+    // Walking rather than flying, looking around as flying does
+    walking: Option<Player>,
+
+    // Moving the camera on fixed steps rather than every frame, as recording
+    // and playing back do, for it to go the same way whatever the frame rate
+    stepped: bool,
+
+    state: AppState,
+
+    // Input recorded into the file named on exit, to be played back later on
+    input_log: Option<(PathBuf, Replay)>,
+
+    // Sticks as last recorded, only recorded again once moved
+    steered: [[f32; 2]; 2],
+
+    // Frames per second to keep up by drawing the scene at a lower resolution
+    resolution: Option<DynamicResolution>,
+    drawn: Instant,
+
+    // Whether to draw another frame when drawing on demand, set by input
+    // and by anything still moving or loading as of the last frame
+    redraw: bool,
+}
+
+impl App {
+    async fn new(args: RunArgs, event_loop: &EventLoop<()>) -> Result<Self, AxialError> {
+        let config = Config::load(&args.config);
+        let config = config.map_err(|err| AxialError::Config(args.config.clone(), err))?;
+        let loaded = config.clone();
+
+        let connected = args.connect.as_ref().map(Client::connect);
+        let client = connected.transpose().map_err(AxialError::Net)?;
+
+        let on_demand = config.redraw == Redraw::OnDemand;
+        let upload_budget = config.upload_budget << 10;
+        event_loop.set_control_flow(match on_demand {
+            true => ControlFlow::Wait,
+            false => ControlFlow::Poll,
+        });
+
+        let (window, mut gfx) = open_window(&config, event_loop).await?;
+
+        let min_order = args.buffer.min_order;
+        let capacity = args.buffer.buffer_size / mem::size_of::<QuadRef>();
+        let quad_buddy = Buddy::<QuadRef>::new(&gfx, capacity, min_order);
+
+        let mut assets = Assets::new(".");
+        let textures = match BlockTextures::load_assets(&gfx, &mut assets, "textures") {
+            Ok(textures) => Some(textures),
+            Err(err) => {
+                error!("{err}");
+                None
+            }
+        };
+
+        // The hill, with a pond next to it seen through its glass wall,
+        // unless every chunk comes from a server
+        let mut world = ChunkMap::new();
+        if client.is_none() {
+            world.insert([0, 0, 0], Box::new(demo::hill()));
+            world.insert([0, 0, 1], Box::new(demo::pond()));
+        }
+
+        let scene = Scene::new(&gfx, quad_buddy, world);
+        let mut scene = scene.map_err(AxialError::Renderer)?;
+        if let Some(textures) = textures {
+            texture_blocks(&gfx, &mut scene.renderer, &textures);
+            info!("{} block texture layers", textures.layers());
+        }
+
+        let scene = Rc::new(RefCell::new(scene));
+        info!("drawing with {:?}", scene.borrow().renderer.path());
+
+        let recreated = scene.clone();
+        let unrecovered = Rc::new(Cell::new(None));
+        let failed = unrecovered.clone();
+        gfx.on_recreate(move |gfx| {
+            // Nothing left to draw with if the new device has no room for a pass,
+            // the scene left as it was for its world to be around until the end
+            let quads = Buddy::<QuadRef>::new(gfx, capacity, min_order);
+            let renderer = match Renderer::new(gfx, &quads, CHUNK_QUADS) {
+                Ok(renderer) => renderer,
+                Err(err) => return failed.set(Some(err)),
+            };
+
+            let mut scene = recreated.borrow_mut();
+            let mut world = mem::take(&mut scene.world);
+            world.release(&mut scene.quads);
+            *scene = Scene::with_renderer(gfx, quads, world, renderer);
+        });
+
+        let snapshot_path = env::var_os("AXIAL_SNAPSHOT").map(PathBuf::from);
+        let snapshot_path = snapshot_path.unwrap_or_else(|| PathBuf::from("axial.snapshot"));
+
+        let day = env::var("AXIAL_DAY")
+            .ok()
+            .and_then(|day| day.parse::<f32>().ok());
+
+        let playback = match env::var_os("AXIAL_REPLAY").map(PathBuf::from) {
+            Some(path) => match Replay::load(&path) {
+                Ok(replay) => Some(Playback::new(replay)),
+                Err(err) => return Err(AxialError::Replay(path, err)),
+            },
+            None => None,
+        };
+
+        let served = client.as_ref().map(Client::seed);
+        let replayed = playback.as_ref().map(|playback| playback.replay().seed);
+        let seed = served.or(replayed).unwrap_or(args.seed);
+
+        // Chunks only come from servers as streaming asks for them
+        let connected_distance = client.as_ref().map(|_| CONNECTED_RENDER_DISTANCE);
+        let render_distance = args.render_distance.or(config.render_distance);
+        let render_distance = render_distance.or(connected_distance);
+        let streaming = render_distance.map(|radius| {
+            let generator: Arc<Generator> = Arc::new(move |pos| terrain(seed, pos));
+
+            let threads = thread::available_parallelism().map_or(1, |count| count.get() - 1);
+            let workers = Workers::new(threads, "greedy", generator);
+            let workers = workers.ok_or_else(|| AxialError::Mesher("greedy".into()));
+            workers.map(|workers| (Streaming::new(radius), workers))
+        });
+
+        let streaming = streaming.transpose()?;
+
+        // Playing back leaves the world unsaved, so there is nothing to journal
+        let saving = env::var_os("AXIAL_SAVE").map(PathBuf::from);
+        let saving = saving.map(|dir| Saving::open(dir, playback.is_none()));
+
+        // As in the file named, as in the settings otherwise
+        let bindings = match env::var_os("AXIAL_BINDINGS") {
+            Some(path) => Bindings::load(Path::new(&path)).unwrap_or_else(|err| {
+                error!("{err}");
+                Bindings::default()
+            }),
+            None => config.key_bindings().unwrap_or_default(),
+        };
+
+        let gamepads = Gamepads::new().inspect_err(|err| error!("{err}")).ok();
+
+        let mut camera = Camera::new([0.0; 3]);
+        camera.fov_y = config.fov.to_radians();
+        let mut fly = FlyCamera::new(window.clone());
+        let mut orbiting = true;
+
+        // Blocks per second to fly at
+        let fly_speed = env::var("AXIAL_FLY_SPEED").ok();
+        if let Some(speed) = fly_speed.and_then(|speed| speed.parse().ok()) {
+            fly.speed = speed;
+        }
+
+        // Recording and playing back skip orbiting
+        let record = env::var_os("AXIAL_RECORD").map(PathBuf::from);
+        if let Some(playback) = &playback {
+            let replay = playback.replay();
+            (camera.eye, camera.yaw, camera.pitch) = (replay.eye, replay.yaw, replay.pitch);
+            fly.speed = replay.speed;
+            orbiting = false;
+        } else if record.is_some() {
+            orbit(&mut camera, 0.0);
+            orbiting = false;
+        }
+
+        // Loading the world around the camera before playing, unless playing back,
+        // for input to come in between the same steps it was recorded between
+        let state = match playback {
+            Some(_) => AppState::Playing,
+            None => AppState::Loading,
+        };
+        let input_log = record.map(|path| {
+            let replay = Replay::new(seed, fly.speed, camera.eye, camera.yaw, camera.pitch);
+            (path, replay)
+        });
+
+        let resolution = env::var("AXIAL_TARGET_FPS")
+            .ok()
+            .and_then(|fps| fps.parse::<f32>().ok())
+            .map(|fps| DynamicResolution::new(Duration::from_secs_f32(1.0 / fps)));
+
+        Ok(Self {
+            screen: Screen::new(window.clone()),
+            window,
+            gfx,
+            config,
+            loaded,
+            config_path: args.config,
+            on_demand,
+            upload_budget,
+            client,
+            seed,
+            scene,
+            unrecovered,
+            failed: None,
+            assets,
+            assets_polled: Instant::now(),
+            start: Instant::now(),
+            capture: env::var_os("AXIAL_CAPTURE").map(PathBuf::from),
+            recorder: None,
+            snapshot_path,
+            day,
+            profile: env::var_os("AXIAL_PROFILE").is_some(),
+            reported: Instant::now(),
+            times: FrameTimes::new(),
+            log_frame_times: args.log_frame_times,
+            placing: 0,
+            entities: Entities::new(),
+            ticks: FixedStep::new(Duration::from_secs(1) / 60),
+            tick: 0,
+            playback,
+            streaming,
+            saving,
+            mesher: greedy::Greedy::default(),
+            hud: false,
+            bindings,
+            actions: Vec::new(),
+            looked: [0.0; 2],
+            gamepads,
+            camera,
+            fly,
+            stepped: !orbiting,
+            orbiting,
+            walking: None,
+            state,
+            input_log,
+            steered: [[0.0; 2]; 2],
+            resolution,
+            drawn: Instant::now(),
+            redraw: true,
+        })
+    }
+
+    fn handle(&mut self, event: Event<()>, target: &EventLoopWindowTarget<()>) {
+        match event {
+            Event::WindowEvent { event, .. } => self.window_event(event, target),
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (dx, dy) },
+                ..
+            } if self.fly.is_grabbed() && self.playback.is_none() => {
+                self.looked[0] += dx;
+                self.looked[1] += dy;
+            }
+            Event::AboutToWait => self.about_to_wait(target),
+            _ => {}
+        }
+    }
+
+    fn window_event(&mut self, event: WindowEvent, target: &EventLoopWindowTarget<()>) {
+        match event {
+            WindowEvent::CloseRequested => self.close(target),
+            WindowEvent::Resized(size) => {
+                // Kept for the next run, unless taking up the whole screen
+                if self.window.fullscreen().is_none() {
+                    self.config.window = [size.width, size.height];
+                }
+
+                self.gfx.resize_viewport(size);
+                self.redraw = true;
+            }
+
+            // The window keeps its logical size, so its physical size may not follow
+            // with a `Resized` on every platform
+            WindowEvent::ScaleFactorChanged { .. } => {
+                self.gfx.resize_viewport(self.window.inner_size());
+                self.redraw = true;
+            }
+
+            // Giving the pointer back as releasing it does, for recordings to let go of keys too
+            WindowEvent::Focused(false) if self.playback.is_none() => {
+                let action = (Action::ReleasePointer, ElementState::Pressed);
+                self.actions.push(action);
+            }
+            input @ (WindowEvent::KeyboardInput { .. } | WindowEvent::MouseInput { .. })
+                if self.playback.is_none() =>
+            {
+                self.actions.extend(self.bindings.translate(&input));
+            }
+            WindowEvent::RedrawRequested => self.draw(target),
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, target: &EventLoopWindowTarget<()>) {
+        if let Some(saving) = &mut self.saving {
+            saving.save_all(&self.scene.borrow().world);
+        }
+
+        if self.config != self.loaded {
+            if let Err(err) = self.config.save(&self.config_path) {
+                error!("{}: {err}", self.config_path.display());
+            }
+        }
+
+        self.finish_recordings();
+        target.exit();
+    }
+
+    // Frames still on their way written out, and input recorded saved
+    fn finish_recordings(&mut self) {
+        let recorder = self.recorder.take();
+        let finished = recorder.map(|recording| recording.finish(&self.gfx));
+        if let Some(Err(err)) = finished {
+            error!("{err}");
+        }
+
+        if let Some(input_log) = self.input_log.take() {
+            save_input(input_log, self.tick);
+        }
+    }
+
+    fn draw(&mut self, target: &EventLoopWindowTarget<()>) {
+        let _span = info_span!("frame").entered();
+        if self.gfx.is_lost() {
+            self.failed = match pollster::block_on(self.gfx.recover()) {
+                Err(err) => Some(AxialError::Gfx(err)),
+                Ok(()) => self.unrecovered.take().map(AxialError::Renderer),
+            };
+
+            if self.failed.is_some() {
+                target.exit();
+            }
+
+            return;
+        }
+
+        let Some(frame) = self.gfx.acquire_frame() else {
+            return;
+        };
+
+        // Time between frames, and on the GPU where it can be told
+        let interval = mem::replace(&mut self.drawn, Instant::now()).elapsed();
+        let frame_start = Instant::now();
+        let profiler = self.gfx.profiler();
+        let gpu = profiler.map(|profiler| profiler.times().to_vec());
+        let gpu = gpu.filter(|times| !times.is_empty()).unwrap_or_default();
+        let gpu_total = (!gpu.is_empty()).then(|| gpu.iter().map(|(_, time)| *time).sum());
+
+        self.move_camera(interval);
+
+        let view = frame.texture.create_view(&TextureViewDescriptor::default());
+        let mut graph = Graph::new();
+        let frame_slot = graph.import(&view);
+
+        let scene = self.scene.clone();
+        let mut scene = scene.borrow_mut();
+        let Scene {
+            quads,
+            world,
+            renderer,
+        } = &mut *scene;
+
+        self.stream(quads, world, renderer);
+
+        if let Some(saving) = &mut self.saving {
+            if saving.poll(&self.gfx, quads, world, self.seed) {
+                load_voxels(&self.gfx, renderer, world);
+            }
+
+            saving.autosave(world);
+        }
+
+        if self.streaming.is_none() {
+            world.mesh_dirty(&self.gfx, quads, &self.mesher, 4);
+        }
+
+        if let Some(day) = self.day {
+            let angle = self.start.elapsed().as_secs_f32() / day * TAU;
+            renderer.sun = [angle.cos(), angle.sin(), 0.3];
+        }
+
+        renderer.time = self.start.elapsed().as_secs_f32();
+        self.reload_textures(renderer);
+        self.step(world, interval, target);
+
+        if let Some(player) = &self.walking {
+            self.camera.eye = player.eye();
+        }
+
+        renderer.entities = self.entities.draws();
+
+        let hit = world.raycast(self.camera.eye, self.camera.forward(), REACH);
+        renderer.selected = hit.map(|hit| hit.location.map(|c| c as f32));
+
+        // Whatever got meshed, as much of it as fits this frame
+        quads.flush_staged(&self.gfx, self.upload_budget);
+        let chunks: Vec<_> = world.renderable().collect();
+
+        renderer.outlines.clear();
+        if renderer.view == DebugView::Chunks {
+            renderer.outlines.extend(world.outlines());
+        }
+
+        renderer.hud.clear();
+        renderer.occupancy = self.hud;
+        renderer.hud.extend(self.status(world));
+        if self.hud {
+            let lines = self.overlay(quads, world, renderer, &chunks, &gpu);
+            renderer.hud.extend(lines);
+        }
+
+        let camera = &self.camera;
+        renderer.declare(&self.gfx, &mut graph, frame_slot, quads, camera, &chunks);
+        self.gfx.run(graph);
+
+        self.times.push(interval, frame_start.elapsed(), gpu_total);
+        self.report();
+
+        // Time on the GPU where it can be told, or else between frames
+        if let Some(resolution) = &mut self.resolution {
+            let time = gpu_total.unwrap_or(interval);
+            let scale = resolution.update(time);
+            if scale != self.gfx.render_scale() {
+                self.gfx.set_render_scale(scale);
+            }
+        }
+
+        if let Some(path) = self.capture.take() {
+            save_frame(&self.gfx, &frame.texture, &path);
+        }
+
+        if let Some(recording) = &mut self.recorder {
+            if let Err(err) = recording.record(&self.gfx, &frame.texture) {
+                error!("{err}, stopped recording");
+                self.recorder = None;
+            }
+        }
+
+        frame.present();
+        self.settle(quads, world);
+
+        // Tracy splits its timeline into frames by these
+        #[cfg(feature = "tracy")]
+        if let Some(client) = tracing_tracy::client::Client::running() {
+            client.frame_mark();
+        }
+    }
+
+    // Along with the frame, unless moved on fixed steps in `step`
+    fn move_camera(&mut self, interval: Duration) {
+        if self.orbiting {
+            orbit(&mut self.camera, self.start.elapsed().as_secs_f32());
+        } else if self.stepped || !self.state.moves_camera() {
+            // Along with the fixed steps, if at all
+        } else if self.walking.is_some() {
+            self.fly.turn(&mut self.camera, interval);
+        } else {
+            self.fly.update(&mut self.camera, interval);
+        }
+    }
+
+    // Chunks and edits from the server, in the order it sent them, then chunks
+    // streamed in around the camera, asked of the server or generated here
+    fn stream(
+        &mut self,
+        quads: &mut Buddy<QuadRef>,
+        world: &mut ChunkMap,
+        renderer: &mut Renderer,
+    ) {
+        // Edits made here come back as well, already made
+        let mut edited = false;
+        let mut disconnected = false;
+        for message in self.client.iter().flat_map(Client::poll) {
+            match message {
+                Ok(ToClient::Chunk(pos, blocks)) => {
+                    world.insert(pos, blocks);
+                    world.set_biome(pos, Biome::at(self.seed, pos));
+                }
+                Ok(ToClient::Edit(location, block)) => {
+                    if world.block(location).is_some_and(|old| old != block) {
+                        match world.set_block(&self.gfx, quads, location, block) {
+                            Ok(_) => edited = true,
+                            Err(err) => error!("{err}"),
+                        }
+
+                        if let Some(saving) = &mut self.saving {
+                            saving.journal(location, block);
+                        }
+                    }
+                }
+                Ok(ToClient::Welcome { .. }) => {}
+                Err(err) => {
+                    error!("{err}, generating chunks here from now on");
+                    disconnected = true;
+                }
+            }
+        }
+
+        if edited {
+            load_voxels(&self.gfx, renderer, world);
+        }
+
+        if let Some((streaming, workers)) = &mut self.streaming {
+            if disconnected {
+                self.client = None;
+                streaming.forget_requests();
+            }
+
+            let camera = &self.camera;
+            match &self.client {
+                Some(client) => streaming.update_with(world, quads, camera, |pos| {
+                    client.send(&ToServer::Request(pos));
+                }),
+                None => streaming.update(world, quads, workers, camera),
+            };
+
+            workers.mesh_dirty(world, streaming.in_flight);
+            workers.finish(&self.gfx, quads, world);
+        }
+    }
+
+    // Block textures loaded again once their files change, looked at about once a second
+    fn reload_textures(&mut self, renderer: &mut Renderer) {
+        if self.assets_polled.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+
+        self.assets_polled = Instant::now();
+        let changed = self.assets.changed();
+        if changed.iter().any(|path| path == "textures") {
+            match BlockTextures::load_assets(&self.gfx, &mut self.assets, "textures") {
+                Ok(textures) => {
+                    texture_blocks(&self.gfx, renderer, &textures);
+                    info!("{} block texture layers reloaded", textures.layers());
+                }
+                Err(err) => error!("{err}"),
+            }
+        }
+    }
+
+    // Everything moving along as many fixed steps as `interval` makes up,
+    // exiting once done playing back
+    fn step(&mut self, world: &ChunkMap, interval: Duration, target: &EventLoopWindowTarget<()>) {
+        let solid = |location| world.block(location).is_some_and(is_pickable);
+        let mut steps = self.ticks.advance(interval);
+
+        // Standing still while paused, with no steps to catch up on afterwards
+        if !self.state.ticks() {
+            steps = 0;
+        }
+
+        // Replays stop short of the next step input came in before,
+        // for it to come in between the same steps as it did when recorded
+        if let Some(next) = self.playback.as_ref().and_then(Playback::next_tick) {
+            let until = next.saturating_sub(self.tick).min(steps.into()) as u32;
+            self.ticks.defer(steps - until);
+            steps = until;
+        }
+
+        for _ in 0..steps {
+            if self.stepped && self.walking.is_some() {
+                self.fly.turn(&mut self.camera, self.ticks.step());
+            } else if self.stepped {
+                self.fly.update(&mut self.camera, self.ticks.step());
+            }
+
+            self.entities.step(self.ticks.seconds(), solid);
+
+            if let Some(player) = &mut self.walking {
+                let movement = self.fly.movement();
+                player.step(world, movement, self.camera.yaw, self.ticks.seconds());
+            }
+
+            self.tick += 1;
+        }
+
+        // Done playing back, leaving the world unsaved for the replay to be played again
+        let done = |playback: &Playback| playback.is_done(self.tick);
+        if self.playback.as_ref().is_some_and(done) {
+            info!("replayed {} steps", self.tick);
+            if let Some(summary) = self.times.interval() {
+                info!("{}", describe("frame", &summary));
+            }
+
+            self.finish_recordings();
+            self.playback = None;
+            target.exit();
+        }
+    }
+
+    // What the state the game is in shows over the frame
+    fn status(&self, world: &ChunkMap) -> Vec<String> {
+        match self.state {
+            AppState::Loading => {
+                let (ready, total) = match &self.streaming {
+                    Some((streaming, _)) => streaming.progress(world, &self.camera),
+                    None => (world.meshed(), world.len()),
+                };
+
+                vec![format!("loading, {ready} of {total} chunks ready")]
+            }
+            AppState::Paused => vec!["paused, click to go on".into()],
+            AppState::Menu => menu(&self.bindings),
+            AppState::Playing => Vec::new(),
+        }
+    }
+
+    // Frame times and what is being drawn, a line each
+    fn overlay(
+        &self,
+        quads: &Buddy<QuadRef>,
+        world: &ChunkMap,
+        renderer: &Renderer,
+        chunks: &[ChunkDraw],
+        gpu: &[(&str, Duration)],
+    ) -> Vec<String> {
+        let ms = |time: Duration| time.as_secs_f32() * 1000.0;
+        let [x, y, z] = self.camera.eye;
+        let ranges = chunks.iter().flat_map(|chunk| chunk.facings);
+        let quad_count: usize = ranges.map(|range| range.len()).sum();
+        let used = quads.metrics().used as f32 / quads.capacity() as f32;
+        let culling = renderer.culling();
+
+        let times = &self.times;
+        let summaries = [
+            times.interval().map(|summary| describe("frame", &summary)),
+            times.cpu().map(|summary| describe("cpu", &summary)),
+            times.gpu().map(|summary| describe("gpu", &summary)),
+        ];
+
+        let mut lines: Vec<_> = summaries.into_iter().flatten().collect();
+        lines.extend([
+            format!("eye {x:.1} {y:.1} {z:.1}"),
+            format!("{} chunks, {quad_count} quads", chunks.len()),
+            format!("{} chunks drawn, {} culled", culling.drawn, culling.culled),
+            format!("{} entities", self.entities.len()),
+            format!("{} KiB of blocks and light", world.block_bytes() >> 10),
+            format!("quad buddy {:.1}% used", used * 100.0),
+            format!("{} KiB of quads staged", quads.staged_bytes() >> 10),
+            format!("scene at {:.0}%", self.gfx.render_scale() * 100.0),
+            format!("{:?} view", renderer.view),
+        ]);
+
+        for (label, time) in gpu {
+            lines.push(format!("{label} {:.3} ms", ms(*time)));
+        }
+
+        lines
+    }
+
+    // GPU time of every pass and frame times, logged about once a second if asked to
+    fn report(&mut self) {
+        if self.reported.elapsed().as_secs() < 1 {
+            return;
+        }
+
+        if let Some(profiler) = self.gfx.profiler().filter(|_| self.profile) {
+            for (pass, time) in profiler.times() {
+                info!(target: "gpu", pass, ?time);
+            }
+        }
+
+        if self.log_frame_times {
+            let summaries = [
+                ("frame", self.times.interval()),
+                ("cpu", self.times.cpu()),
+                ("gpu", self.times.gpu()),
+            ];
+
+            for (name, summary) in summaries {
+                let Some(Summary {
+                    average,
+                    low,
+                    lowest,
+                }) = summary
+                else {
+                    continue;
+                };
+
+                info!(target: "frame_times", ?average, ?low, ?lowest, "{name}");
+            }
+        }
+
+        self.reported = Instant::now();
+    }
+
+    // Drawing on demand, frames go on for as long as anything moves
+    // or is on its way in, and stop once all of it settles
+    fn settle(&mut self, quads: &Buddy<QuadRef>, world: &ChunkMap) {
+        let streamed = self.streaming.as_ref().is_some_and(|(streaming, workers)| {
+            let pending = workers.pending() > 0;
+            !streaming.is_settled() || pending
+        });
+
+        let stored = self.saving.as_ref().is_some_and(Saving::is_busy);
+        let moving = self.orbiting || self.fly.is_moving() || self.walking.is_some();
+        let recording = self.input_log.is_some() || self.recorder.is_some();
+        let playing = self.playback.is_some() || recording;
+        let changing = !self.entities.is_empty() || self.day.is_some() || self.client.is_some();
+        let meshing = !world.is_meshed() || quads.staged_bytes() > 0;
+        let loading = streamed || stored || meshing;
+        self.redraw = moving || playing || changing || loading;
+
+        if !loading {
+            self.state = self.state.loaded();
+        }
+    }
+
+    fn about_to_wait(&mut self, target: &EventLoopWindowTarget<()>) {
+        self.poll_input();
+
+        // Input coming in after sitting idle starts frames over, the time idle
+        // not counting towards the first one, and so does an autosave coming due
+        let idle = !self.redraw;
+        let due = self.saving.as_ref().is_some_and(Saving::is_due);
+        let input = !self.actions.is_empty() || self.looked != [0.0; 2];
+        self.redraw |= input || self.fly.is_moving() || due;
+        if self.on_demand && idle && self.redraw {
+            self.drawn = Instant::now();
+        }
+
+        if self.looked != [0.0; 2] {
+            self.fly.look(&mut self.camera, self.looked);
+            let looked = mem::take(&mut self.looked);
+            self.record(Recorded::Look(looked));
+        }
+
+        for (action, pressed) in mem::take(&mut self.actions) {
+            self.route(action, pressed);
+        }
+
+        if !self.on_demand || self.redraw {
+            self.window.request_redraw();
+        }
+
+        // Sleeping on demand until input comes in, woken up in time
+        // for gamepads to be polled and autosaves to be made, if any
+        if self.on_demand {
+            let polled = self.gamepads.as_ref();
+            let polled = polled.map(|_| Instant::now() + GAMEPAD_POLL);
+            let saved = self.saving.as_ref().and_then(Saving::next_autosave);
+            target.set_control_flow(match polled.into_iter().chain(saved).min() {
+                Some(wake) => ControlFlow::WaitUntil(wake),
+                None => ControlFlow::Wait,
+            });
+        }
+    }
+
+    // Sticks, looks and actions, in that order, as played back,
+    // or else out of gamepads
+    fn poll_input(&mut self) {
+        if let Some(playback) = &mut self.playback {
+            for event in playback.due(self.tick) {
+                match event {
+                    Recorded::Sticks(sticks) => self.fly.steer(sticks),
+                    Recorded::Look(delta) => self.fly.look(&mut self.camera, delta),
+                    Recorded::Action(action, true) => {
+                        self.actions.push((action, ElementState::Pressed));
+                    }
+                    Recorded::Action(action, false) => {
+                        self.actions.push((action, ElementState::Released));
+                    }
+                }
+            }
+
+            return;
+        }
+
+        let Some(gamepads) = &mut self.gamepads else {
+            return;
+        };
+
+        self.actions.extend(gamepads.poll(&self.bindings));
+
+        // Touching a stick takes over the camera, as clicking into the window does
+        let sticks = gamepads.sticks();
+        self.orbiting &= sticks == [[0.0; 2]; 2];
+        if sticks != self.steered {
+            self.steered = sticks;
+            self.fly.steer(sticks);
+            self.record(Recorded::Sticks(sticks));
+        }
+    }
+
+    // Input as it came in at this step, if recording it
+    fn record(&mut self, event: Recorded) {
+        if let Some((_, replay)) = &mut self.input_log {
+            replay.push(self.tick, event);
+        }
+    }
+
+    // Pressing may take the game to another state, which decides whether
+    // the action goes on. Replays look around without grabbing the pointer,
+    // so clicking into the window to grab it is left out of recordings,
+    // unless it goes back to playing
+    fn route(&mut self, action: Action, pressed: ElementState) {
+        let (next, routed) = match pressed {
+            ElementState::Pressed => self.state.press(action),
+            ElementState::Released => (self.state, true),
+        };
+
+        let grabbing = action == Action::Break && !self.fly.is_grabbed() && self.playback.is_none();
+        if next != self.state || (routed && !grabbing) {
+            self.record(Recorded::Action(action, pressed.is_pressed()));
+        }
+
+        if next != self.state {
+            self.enter(next);
+        }
+
+        if !routed {
+            return;
+        }
+
+        // Moving goes on for as long as held, anything else happens once pressed
+        if self.fly.action(action, pressed) || pressed != ElementState::Pressed {
+            return;
+        }
+
+        self.act(action, grabbing);
+    }
+
+    // Anything but playing gives the pointer back, and coming back grabs it
+    fn enter(&mut self, next: AppState) {
+        match next {
+            AppState::Playing if self.playback.is_none() => self.grab(),
+            AppState::Playing => {}
+            _ => self.fly.release(),
+        }
+
+        info!("{next:?}");
+        self.state = next;
+    }
+
+    fn grab(&mut self) {
+        match self.fly.grab() {
+            Ok(()) => self.orbiting = false,
+            Err(err) => error!("{err}"),
+        }
+    }
+
+    // Whatever `action` does once pressed, `grabbing` the pointer
+    // rather than breaking anything if not grabbed yet
+    fn act(&mut self, action: Action, grabbing: bool) {
+        match action {
+            Action::ToggleFullscreen => {
+                let mode = self.screen.mode().next();
+                match self.screen.set_mode(mode) {
+                    Ok(()) => info!("switched to {mode:?} mode"),
+                    Err(err) => error!("{err}"),
+                }
+            }
+            Action::NextView => {
+                // Going through every debug view, and back to shaded
+                let mut scene = self.scene.borrow_mut();
+                let view = scene.renderer.view.next();
+                scene.renderer.view = view;
+                info!("showing {view:?}");
+            }
+            Action::ToggleWireframe => {
+                let mut scene = self.scene.borrow_mut();
+                let view = match scene.renderer.view {
+                    DebugView::Wireframe => DebugView::Shaded,
+                    _ => DebugView::Wireframe,
+                };
+
+                scene.renderer.view = view;
+                info!("showing {view:?}");
+            }
+            Action::ToggleHud => self.hud = !self.hud,
+            Action::Screenshot => self.capture = Some(timestamped().with_extension("png")),
+            Action::ToggleRecording => self.toggle_recording(),
+            Action::NextBlock => {
+                self.placing = (self.placing + 1) % PLACEABLE.len();
+                info!("placing block {}", PLACEABLE[self.placing]);
+            }
+            Action::ReportMemory => {
+                // To tell how close chunks are to running out of room
+                let scene = self.scene.borrow();
+                let mut report = self.gfx.memory_report();
+                scene.quads.report_memory(&mut report, "quads");
+                scene.renderer.report_memory(&mut report);
+                print!("{report}");
+            }
+            Action::RenderFarther | Action::RenderNearer => match &mut self.streaming {
+                // Kept from run to run, as when set in the settings
+                Some((streaming, _)) => {
+                    let step = match action {
+                        Action::RenderFarther => 1,
+                        _ => -1,
+                    };
+
+                    let radius = streaming.set_radius(streaming.radius + step);
+                    self.config.render_distance = Some(radius);
+                    info!("rendering {radius} chunks away");
+                }
+                None => info!("not streaming, so there is no render distance"),
+            },
+            Action::SaveSnapshot => self.save_snapshot(),
+            Action::LoadSnapshot => self.load_snapshot(),
+            Action::ReleasePointer => self.fly.release(),
+            Action::ToggleWalk => self.toggle_walk(),
+
+            // Clicking into the window grabs the pointer before anything gets broken
+            Action::Break if grabbing => self.grab(),
+
+            Action::Break | Action::Place => self.edit(action),
+            _ => {}
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some(recording) => {
+                let dir = recording.dir().display().to_string();
+                match recording.finish(&self.gfx) {
+                    Ok(frames) => info!("recorded {frames} frames into {dir}"),
+                    Err(err) => error!("{err}"),
+                }
+            }
+            None => match FrameRecorder::new(timestamped()) {
+                Ok(recording) => {
+                    info!("recording into {}", recording.dir().display());
+                    self.recorder = Some(recording);
+                }
+                Err(err) => error!("{err}"),
+            },
+        }
+    }
+
+    fn save_snapshot(&self) {
+        let scene = self.scene.borrow();
+        let snapshot = Snapshot {
+            seed: self.seed,
+            eye: self.camera.eye,
+            yaw: self.camera.yaw,
+            pitch: self.camera.pitch,
+            sun: scene.renderer.sun,
+            time: scene.renderer.time,
+            capacity: scene.quads.capacity(),
+            min_order: scene.quads.min_order(),
+            chunks: scene.world.placements(&scene.quads),
+        };
+
+        let path = self.snapshot_path.display();
+        match snapshot.save(&self.snapshot_path) {
+            Ok(()) => info!("{} chunks saved into {path}", snapshot.chunks.len()),
+            Err(err) => error!("cannot save snapshot: {err}"),
+        }
+    }
+
+    fn load_snapshot(&mut self) {
+        let snapshot = Snapshot::load(&self.snapshot_path);
+        let mut scene = self.scene.borrow_mut();
+        let Scene {
+            quads,
+            world,
+            renderer,
+        } = &mut *scene;
+
+        let seed = self.seed;
+        let checked = snapshot.and_then(|snapshot| {
+            snapshot.check(seed, quads)?;
+            Ok(snapshot)
+        });
+
+        let snapshot = match checked {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                error!("{err}");
+                return;
+            }
+        };
+
+        // Chunks of the snapshot not loaded any more come out of the seed,
+        // all of them meshed again before long into the blocks they were in
+        let missed = world.restore(quads, &snapshot, |pos| terrain(seed, pos));
+        load_voxels(&self.gfx, renderer, world);
+
+        let camera = &mut self.camera;
+        (camera.eye, camera.yaw, camera.pitch) = (snapshot.eye, snapshot.yaw, snapshot.pitch);
+        renderer.sun = snapshot.sun;
+        let time = Duration::try_from_secs_f32(snapshot.time).unwrap_or_default();
+        self.start = Instant::now().checked_sub(time).unwrap_or(self.start);
+        self.walking = None;
+        self.orbiting = false;
+
+        let chunks = snapshot.chunks.len();
+        info!("{chunks} chunks restored, {missed} blocks of them taken already");
+    }
+
+    fn toggle_walk(&mut self) {
+        if self.walking.take().is_some() {
+            info!("flying");
+            return;
+        }
+
+        // Standing right where the camera is, as long as there is room
+        let player = Player::new(self.camera.eye);
+        if self.scene.borrow().world.collides(&player.aabb()) {
+            info!("no room to walk here");
+            return;
+        }
+
+        self.walking = Some(player);
+        self.orbiting = false;
+        info!("walking");
+    }
+
+    // Break the block under the crosshair, or place one against it
+    fn edit(&mut self, action: Action) {
+        let mut scene = self.scene.borrow_mut();
+        let Scene {
+            quads,
+            world,
+            renderer,
+        } = &mut *scene;
+
+        let Some(hit) = world.raycast(self.camera.eye, self.camera.forward(), REACH) else {
+            return;
+        };
+
+        // Where the block goes and what it is, for the server to be told
+        let (location, block) = match action {
+            Action::Break => (Some(hit.location), AIR),
+            _ => (hit.adjacent(), PLACEABLE[self.placing]),
+        };
+
+        let broken = world.block(hit.location);
+        let edited = match action {
+            Action::Break => world.break_block(&self.gfx, quads, &hit),
+            _ => world.place_block(&self.gfx, quads, &hit, block),
+        };
+
+        if let (Action::Break, Ok(_), Some(block)) = (action, &edited, broken) {
+            drop_item(&mut self.entities, hit.location, block);
+        }
+
+        let told = self.client.as_ref().filter(|_| edited.is_ok());
+        if let (Some(client), Some(location)) = (told, location) {
+            client.send(&ToServer::Edit(location, block));
+        }
+
+        // Blocks placed or broken, even if left out until meshed again
+        let is_made = |&location: &[i32; 3]| world.block(location) == Some(block);
+        if let (Some(saving), Some(location)) = (&mut self.saving, location.filter(is_made)) {
+            saving.journal(location, block);
+        }
+
+        // Out of the blocks as they are now, to check the edit against
+        match edited {
+            Ok(_) => load_voxels(&self.gfx, renderer, world),
+            Err(err) => error!("{err}"),
+        }
+    }
+}
+
+// The window, and the device drawing into it as the settings and environment say
+async fn open_window(
+    config: &Config,
+    event_loop: &EventLoop<()>,
+) -> Result<(Arc<Window>, Gfx<'static>), AxialError> {
+    let [width, height] = config.window;
+    let window = WindowBuilder::new()
+        .with_title("aXial")
+        .with_inner_size(PhysicalSize::new(width, height))
+        .build(event_loop);
+    let window = Arc::new(window.map_err(AxialError::Window)?);
+
+    // Present unbounded colors to HDR displays, if the surface can
+    let format = env::var_os("AXIAL_HDR").map(|_| HDR_FORMAT);
+
+    // By index, part of the name or backend, as listed below
+    let adapter = env::var("AXIAL_ADAPTER").ok();
+    let adapter = adapter.map(|text| AdapterChoice::parse(&text));
+    let gfx = Gfx::with_options(window.clone(), format, adapter).await;
+    let mut gfx = gfx.map_err(AxialError::Gfx)?;
+
+    // Any of fifo, mailbox or immediate, vsync otherwise, in place of the settings
+    let mode = match env::var("AXIAL_PRESENT_MODE").as_deref() {
+        Ok("fifo") => PresentMode::Fifo,
+        Ok("mailbox") => PresentMode::Mailbox,
+        Ok("immediate") => PresentMode::Immediate,
+        Ok(_) => PresentMode::AutoVsync,
+        Err(_) => config.present.mode(),
+    };
+
+    if let Err(err) = gfx.set_present_mode(mode) {
+        error!("{err}");
+    }
+
+    // Smoothing edges in a post pass takes the place of multisampling
+    match antialiasing() {
+        Antialiasing::Multisample => gfx.set_sample_count(config.msaa),
+        _ => gfx.set_sample_count(1),
+    };
+
+    for (index, listing) in gfx.enumerate_adapters().iter().enumerate() {
+        info!("adapter {index}: {listing}");
+    }
+
+    print!("{}", gfx.report);
+    info!("{} samples per pixel", gfx.sample_count());
+    info!("presenting in {:?} mode", gfx.present_mode());
+    info!("present modes: {:?}", gfx.supported_present_modes());
+
+    Ok((window, gfx))
+}
+
+// Chunks saved into region files in a directory on exit, and loaded back out of
+// them on start, in place of those there. Regions changed by other tools
+// are loaded again as they change
+struct Saving {
+    store: RegionStore,
+    watched: Instant,
+
+    // Edits are journaled next to the regions as they are made, and the chunks
+    // they went into saved every `AUTOSAVE`, so that crashing loses neither the world
+    // nor what was done since it was saved. Edits a crash kept from being saved
+    // last time are made again once the regions they go over are loaded
+    journal: Option<Journal>,
+    recovered: Vec<([i32; 3], BlockId)>,
+
+    // Regions the last autosave is still writing, and whether any autosave failed,
+    // in which case the journal is kept whole until everything gets saved on exit
+    autosaved: Instant,
+    autosaving: usize,
+    autosave_failed: bool,
+}
+
+impl Saving {
+    fn open(dir: PathBuf, journaled: bool) -> Self {
+        let mut store = RegionStore::new(dir.clone());
+        if let Err(err) = store.load_all() {
+            error!("{err}");
+        }
+
+        let opened = journaled.then(|| Journal::open(dir));
+        let (journal, recovered) = match opened {
+            Some(Ok((journal, recovered))) => (Some(journal), recovered),
+            Some(Err(err)) => {
+                error!("cannot journal edits: {err}");
+                (None, Vec::new())
+            }
+            None => (None, Vec::new()),
+        };
+
+        if !recovered.is_empty() {
+            info!("{} edits not saved last time recovered", recovered.len());
+        }
+
+        Self {
+            store,
+            watched: Instant::now(),
+            journal,
+            recovered,
+            autosaved: Instant::now(),
+            autosaving: 0,
+            autosave_failed: false,
+        }
+    }
+
+    // Whether anything is still being loaded or saved
+    fn is_busy(&self) -> bool {
+        self.store.pending() > 0 || self.autosaving > 0
+    }
+
+    // When the next autosave is due, if journaling
+    fn next_autosave(&self) -> Option<Instant> {
+        self.journal.as_ref().map(|_| self.autosaved + AUTOSAVE)
+    }
+
+    fn is_due(&self) -> bool {
+        self.journal.is_some() && self.autosaved.elapsed() >= AUTOSAVE
+    }
+
+    // Journal an edit, for it to be made again should the game crash before it gets saved
+    fn journal(&mut self, location: [i32; 3], block: BlockId) {
+        if let Some(Err(err)) = self.journal.as_mut().map(|log| log.record(location, block)) {
+            error!("{err}");
+        }
+    }
+
+    // Chunks loaded since last polled put into `world`, along with the edits
+    // recovered that can be made by now. True if any blocks changed
+    fn poll(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        world: &mut ChunkMap,
+        seed: u64,
+    ) -> bool {
+        // Edits here not saved yet are lost to chunks loaded over them
+        if self.watched.elapsed() >= Duration::from_secs(1) {
+            self.watched = Instant::now();
+            if let Err(err) = self.store.load_changed() {
+                error!("{err}");
+            }
+        }
+
+        let mut loaded = false;
+        for stored in self.store.poll() {
+            match stored {
+                Ok(Stored::Loaded(chunks)) => {
+                    // Biomes are not saved, but come out the same from the seed.
+                    // Chunks the same as before are left alone, not meshed again
+                    for (pos, blocks) in chunks {
+                        if world.blocks(pos).as_ref() == Some(&blocks) {
+                            continue;
+                        }
+
+                        world.insert(pos, blocks);
+                        world.set_biome(pos, Biome::at(seed, pos));
+                    }
+
+                    loaded = true;
+                }
+                Ok(Stored::Saved(_)) => self.autosaving = self.autosaving.saturating_sub(1),
+                Err(err) => {
+                    error!("{err}");
+                    self.autosave_failed |= self.autosaving > 0;
+                    self.autosaving = self.autosaving.saturating_sub(1);
+                }
+            }
+        }
+
+        // Edits recovered go in as soon as nothing is left to be loaded over them,
+        // those into chunks not there yet waiting for them to be
+        if self.store.pending() == 0 && !self.recovered.is_empty() {
+            let count = self.recovered.len();
+            self.recovered.retain(|&(location, block)| {
+                if world.block(location).is_none() {
+                    return true;
+                }
+
+                if let Err(err) = world.set_block(gfx, quads, location, block) {
+                    error!("{err}");
+                }
+
+                false
+            });
+
+            loaded |= self.recovered.len() < count;
+        }
+
+        loaded
+    }
+
+    // Every so often, once the last autosave got written, edits journaled
+    // before it are let go of, and chunks edited since are saved, the journal
+    // started over right before. Edits recovered and not made yet go on into it
+    fn autosave(&mut self, world: &mut ChunkMap) {
+        let due = self.autosaved.elapsed() >= AUTOSAVE && self.autosaving == 0;
+        if let Some(journal) = self.journal.as_mut().filter(|_| due) {
+            self.autosaved = Instant::now();
+            if !self.autosave_failed {
+                if let Err(err) = journal.settle() {
+                    error!("{err}");
+                }
+            }
+
+            let unsaved = world.take_unsaved();
+            if !unsaved.is_empty() {
+                if let Err(err) = journal.rotate() {
+                    error!("{err}");
+                    self.autosave_failed = true;
+                }
+
+                for &(location, block) in &self.recovered {
+                    if let Err(err) = journal.record(location, block) {
+                        error!("{err}");
+                    }
+                }
+
+                self.autosaving = self.store.save_chunks(unsaved);
+            }
+        }
+
+        if let Some(Err(err)) = self.journal.as_mut().map(Journal::sync) {
+            error!("{err}");
+        }
+    }
+
+    // Waiting for the save to be written, or edits would be lost.
+    // Once it is, the journal goes, unless it holds edits never made
+    fn save_all(&mut self, world: &ChunkMap) {
+        self.store.save(world);
+        let errors = self.store.wait().into_iter().filter_map(Result::err);
+        let failed = errors.inspect(|err| error!("{err}")).count() > 0;
+
+        let journal = self.journal.take();
+        let saved = journal.filter(|_| !failed && self.recovered.is_empty());
+        if let Some(Err(err)) = saved.map(Journal::clear) {
+            error!("{err}");
+        }
+    }
+}
+
+// Every action and the inputs bound to it, a line each, as the menu lists them
+fn menu(bindings: &Bindings) -> Vec<String> {
+    let mut lines = vec!["bindings".to_owned()];
+    for action in Action::ALL {
+        let mut inputs: Vec<_> = bindings.inputs(action).filter_map(Input::name).collect();
+        inputs.sort_unstable();
+
+        // The font has no underscores
+        let line = format!("{} = {}", action.name(), inputs.join(", "));
+        lines.push(line.replace('_', " "));
+    }
+
+    lines
+}
+
+// Average time and frame rate, and the 1% and 0.1% lows, as in
+// "frame 16.67 ms, 60 fps, lows 52 and 31 fps"
+fn describe(name: &str, summary: &Summary) -> String {
+    let fps = |time: Duration| 1.0 / time.as_secs_f32().max(f32::EPSILON);
+    let (average, low, lowest) = (summary.average, summary.low, summary.lowest);
+    let ms = average.as_secs_f32() * 1000.0;
+
+    format!(
+        "{name} {ms:.2} ms, {:.0} fps, lows {:.0} and {:.0} fps",
+        fps(average),
+        fps(low),
+        fps(lowest)
+    )
+}
+
+// Write `frame` out as a PNG at `path`, making the directory it goes in if need be
+fn save_frame(gfx: &Gfx, frame: &Texture, path: &Path) {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let made = dir.map_or(Ok(()), fs::create_dir_all);
+    let file = made.and_then(|()| File::create(path));
+
+    match file.map(|file| gfx.capture_frame(frame, BufWriter::new(file))) {
+        Ok(Ok(())) => info!("saved {}", path.display()),
+        Ok(Err(err)) => error!("{err}"),
+        Err(err) => error!("{}: {err}", path.display()),
+    }
+}
+
+// Move `camera` around the hill, as far along as it gets after `seconds`
+fn orbit(camera: &mut Camera, seconds: f32) {
+    let angle = seconds * 0.3;
+    camera.eye = [16.0 + 48.0 * angle.cos(), 40.0, 16.0 + 48.0 * angle.sin()];
+    camera.look_at([16.0, 12.0, 16.0]);
+}
+
+// Write input recorded over `ticks` steps out into the file it goes in
+fn save_input((path, mut replay): (PathBuf, Replay), ticks: u64) {
+    replay.length = ticks;
+    match replay.save(&path) {
+        Ok(()) => info!("recorded {ticks} steps of input into {}", path.display()),
+        Err(err) => error!("{}: {err}", path.display()),
+    }
+}
+
+// Screenshots and recordings are named after when they were taken, in seconds
+// since the epoch, and go in `AXIAL_SCREENSHOTS` or else in screenshots
+fn timestamped() -> PathBuf {
+    let dir = env::var_os("AXIAL_SCREENSHOTS").map_or("screenshots".into(), PathBuf::from);
+    let since = SystemTime::now().duration_since(UNIX_EPOCH);
+    let since = since.unwrap_or_default();
+    let (seconds, millis) = (since.as_secs(), since.subsec_millis());
+    dir.join(format!("axial-{seconds}-{millis:03}"))
+}
+
+// Either fxaa or taa, smoothing edges by multisampling alone if unset
+fn antialiasing() -> Antialiasing {
+    match env::var("AXIAL_AA").as_deref() {
+        Ok("fxaa") => Antialiasing::Fxaa,
+        Ok("taa") => Antialiasing::Taa,
+        _ => Antialiasing::Multisample,
+    }
+}
+
+// Blocks of every chunk as they are, for the raymarched debug view
+// to check the meshes against
+fn load_voxels(gfx: &Gfx, renderer: &mut Renderer, world: &ChunkMap) {
+    let origin = |pos: [i32; 3]| pos.map(|c| c as f32 * 32.0);
+    let chunks: Vec<_> = world.iter().collect();
+    let voxels = chunks.iter().map(|(pos, blocks)| (origin(*pos), &**blocks));
+    let voxels: Vec<_> = voxels.collect();
+    renderer.load_voxels(gfx, &voxels);
+}
+
+// What gets drawn, with the renderer drawing it
+// Quads a chunk drawn may hold at most
+const CHUNK_QUADS: usize = 1 << 16;
+
+struct Scene {
+    quads: Buddy<QuadRef>,
+    world: ChunkMap,
+    renderer: Renderer,
+}
+
+impl Scene {
+    // Meshing every chunk of `world` into `quads`
+    fn new(gfx: &Gfx, quads: Buddy<QuadRef>, world: ChunkMap) -> Result<Self, RendererError> {
+        let renderer = Renderer::new(gfx, &quads, CHUNK_QUADS)?;
+        Ok(Self::with_renderer(gfx, quads, world, renderer))
+    }
+
+    // As `new`, drawn by a renderer already made for `quads`
+    fn with_renderer(
+        gfx: &Gfx,
+        mut quads: Buddy<QuadRef>,
+        mut world: ChunkMap,
+        mut renderer: Renderer,
+    ) -> Self {
+        tint_biomes(gfx, &renderer);
+        load_items(gfx, &mut renderer);
+        load_voxels(gfx, &mut renderer, &world);
+
+        // Drawing depth first, to compare against drawing everything in one pass
+        renderer.prepass = env::var_os("AXIAL_PREPASS").is_some();
+        renderer.antialiasing = antialiasing();
+        renderer.crosshair = true;
+
+        let mesher = greedy::Greedy::default();
+        let meshed = world.mesh_dirty(gfx, &mut quads, &mesher, world.len());
+        assert_eq!(meshed, world.len(), "out of room for quads");
+        quads.flush_staged(gfx, usize::MAX);
+
+        Self {
+            quads,
+            world,
+            renderer,
+        }
+    }
+}
+
+async fn demo() -> Result<(), AxialError> {
+    // Any of mesh::MESHERS, greedy by default
+    let name = env::var("AXIAL_MESHER").unwrap_or_else(|_| "greedy".into());
+    let mesher = mesh::mesher(&name).ok_or_else(|| AxialError::Mesher(name.clone()))?;
+    let mesh = demo::greedy(&name, &*mesher);
+
+    // Dump the hill for a closer look in Blender
+    if let Some(path) = env::var_os("AXIAL_OBJ") {
+        let written = dump(&path, |out| export::write_obj(out, &mesh));
+        if let Err(err) = written {
+            error!("{}: {err}", Path::new(&path).display());
+        }
+    }
+
+    // Its top faces seen from above, as an image
+    if let Some(path) = env::var_os("AXIAL_PPM") {
+        let top = &mesh[Facing::PosY as usize];
+        let image = debug::rasterize(top, 32, 8, quad_material);
+        if let Err(err) = dump(&path, |out| image.write_ppm(out)) {
+            error!("{}: {err}", Path::new(&path).display());
+        }
+    }
+
+    let gfx = Gfx::headless(1, 1, TextureFormat::Rgba8Unorm).await;
+    let gfx = gfx.map_err(AxialError::Gfx)?;
+    let mut quad_buddy = Buddy::<QuadRef>::new(&gfx, 1 << 20, 8);
+    demo::dig_hill(&gfx, &mut quad_buddy);
+    demo::recolor_hill(&gfx);
+    Ok(())
+}
+
+// Write a file at `path` out of whatever `write` puts into it
+fn dump(
+    path: &OsStr,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write(&mut out)?;
+    out.flush()
+}
+
+// Blocks that drop an item once broken, in the colors quads have them untinted
+const ITEMS: [(BlockId, [u8; 3]); 7] = [
+    (1, [89, 89, 94]),
+    (mesh::GRASS, [51, 115, 26]),
+    (3, [89, 56, 31]),
+    (mesh::SAND, [217, 199, 140]),
+    (mesh::SNOW, [235, 240, 247]),
+    (GLASS, [191, 217, 230]),
+    (LAMP, [255, 217, 140]),
+];
+
+// A small cube for every kind of item, loaded as the block it is
+fn load_items(gfx: &Gfx, renderer: &mut Renderer) {
+    for (block, color) in ITEMS {
+        let loaded = renderer.load_model(gfx, block as u32, &renderer::cube(0.25, color));
+        assert!(loaded, "out of room for models");
+    }
+}
+
+// An item popping out of the block broken at `location`, falling to the ground
+// and gone after a while. Blocks that drop nothing, like water, are left out
+fn drop_item(entities: &mut Entities, location: [i32; 3], block: BlockId) {
+    if !ITEMS.iter().any(|&(item, _)| item == block) {
+        return;
+    }
+
+    let mut rng = rand::thread_rng();
+    let item = entities.spawn();
+    let [x, y, z] = location.map(|c| c as f32 + 0.5);
+    entities.positions.insert(item, [x, y - 0.125, z]);
+    let mut spread = || rng.gen_range(-1.5..1.5);
+    entities.velocities.insert(item, [spread(), 5.0, spread()]);
+    entities.models.insert(item, block as u32);
+    entities.falling.insert(item, ());
+    entities.lifetimes.insert(item, 60.0);
+}
+
+// Every biome's row of the material palette, for chunks to be tinted as their biome has them
+fn tint_biomes(gfx: &Gfx, renderer: &Renderer) {
+    for biome in Biome::ALL {
+        renderer.write_palette(gfx, biome as u32, &biome.palette());
+    }
+}
+
+// Images blocks are drawn with, named as in the textures directory
+const BLOCK_TEXTURES: [(BlockId, &str); 10] = [
+    (1, "stone"),
+    (mesh::GRASS, "grass"),
+    (3, "dirt"),
+    (mesh::SAND, "sand"),
+    (mesh::SNOW, "snow"),
+    (LOG, "log"),
+    (WATER, "water"),
+    (GLASS, "glass"),
+    (LAMP, "lamp"),
+    (mesh::LEAVES, "leaves"),
+];
+
+// Blocks drawn with their images, tinted as their biome has them and animated
+// if the image has frames. Blocks with no image keep their made-up color
+fn texture_blocks(gfx: &Gfx, renderer: &mut Renderer, textures: &BlockTextures) {
+    renderer.set_textures(gfx, textures);
+
+    for biome in Biome::ALL {
+        let mut row = biome.palette();
+        for (block, name) in BLOCK_TEXTURES {
+            if let Some(layer) = textures.layer(name) {
+                row[block as usize] |= layer;
+            }
+        }
+
+        renderer.write_palette(gfx, biome as u32, &row);
+    }
+
+    for (_, name) in BLOCK_TEXTURES {
+        let layer = textures.layer(name);
+        let animation = layer.zip(textures.animation(name));
+        if let Some((layer, animation)) = animation {
+            renderer.write_animation(gfx, layer, animation);
+        }
+    }
+}
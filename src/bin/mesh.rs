@@ -0,0 +1,182 @@
+// Mesh a chunk from a file without opening a window:
+//
+//     cargo run --bin mesh -- <chunk> [--mesher <name>] [--obj <path>] [--ppm <path>]
+//         [--quads <path>]
+//
+// Chunks are either raw dumps of 32³ little-endian block ids, in `[z][y][x]` order,
+// or MagicaVoxel `.vox` models, whose color indices are taken as block ids as they are.
+// Quads are written tagged with the version they are packed with, see `mesh::format`.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter},
+    path::{Path, PathBuf},
+    process,
+};
+
+use rust_playground::mesh::{
+    self, debug, export, format, quad_material, stats::MeshStats, vox, BlockId, Borders, Chunk,
+    Facing,
+};
+
+const USAGE: &str =
+    "usage: mesh <chunk> [--mesher <name>] [--obj <path>] [--ppm <path>] [--quads <path>]";
+
+#[derive(Debug, Default)]
+struct Args {
+    chunk: PathBuf,
+    mesher: Option<String>,
+    obj: Option<PathBuf>,
+    ppm: Option<PathBuf>,
+    quads: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut chunk = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+
+            match arg.as_str() {
+                "--mesher" => parsed.mesher = Some(value()?),
+                "--obj" => parsed.obj = Some(value()?.into()),
+                "--ppm" => parsed.ppm = Some(value()?.into()),
+                "--quads" => parsed.quads = Some(value()?.into()),
+                _ if arg.starts_with("--") => return Err(format!("unknown option {arg}")),
+                _ if chunk.is_none() => chunk = Some(arg.into()),
+                _ => return Err(format!("unexpected argument {arg}")),
+            }
+        }
+
+        parsed.chunk = chunk.ok_or("missing chunk file")?;
+        Ok(parsed)
+    }
+}
+
+// Every chunk of every model in the file, along with the borders
+// the rest of the model leaves it. Raw chunks are alone
+fn read_chunks(path: &Path) -> io::Result<Vec<(Box<Chunk>, Borders)>> {
+    let bytes = fs::read(path)?;
+
+    if path.extension().is_some_and(|ext| ext == "vox") {
+        let vox = vox::read(&bytes, BlockId::from)?;
+        let chunks = vox.models.iter().flat_map(|model| {
+            let chunks = model.chunks.iter();
+            chunks.map(|(&pos, chunk)| (chunk.clone(), model.borders(pos)))
+        });
+
+        return Ok(chunks.collect());
+    }
+
+    Ok(vec![(read_chunk(&bytes)?, Borders::NONE)])
+}
+
+fn read_chunk(bytes: &[u8]) -> io::Result<Box<Chunk>> {
+    let len = 2 * 32 * 32 * 32;
+    if bytes.len() != len {
+        let message = format!("expected {len} bytes of chunk, got {}", bytes.len());
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    let mut chunk = Box::new([[[0; 32]; 32]; 32]);
+    let blocks = chunk.iter_mut().flatten().flatten();
+
+    for (block, bytes) in blocks.zip(bytes.chunks_exact(2)) {
+        *block = BlockId::from_le_bytes([bytes[0], bytes[1]]);
+    }
+
+    Ok(chunk)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let name = args.mesher.as_deref().unwrap_or("greedy");
+    let mesher = mesh::mesher(name).ok_or_else(|| {
+        let known = mesh::MESHERS.join(", ");
+        format!("unknown mesher {name}, expected one of {known}")
+    })?;
+
+    let path = args.chunk.display();
+    let chunks = read_chunks(&args.chunk).map_err(|err| format!("{path}: {err}"))?;
+
+    let exports = [&args.obj, &args.ppm, &args.quads];
+    if chunks.len() != 1 && exports.iter().any(|path| path.is_some()) {
+        let len = chunks.len();
+        return Err(format!(
+            "{path}: {len} chunks, can only export a single one"
+        ));
+    }
+
+    // Faces against the rest of the model are culled, by meshers that look across borders
+    let mut counts = [(0, 0); 6];
+    let mut stats = MeshStats::default();
+    let mut mesh = Default::default();
+
+    for (chunk, borders) in &chunks {
+        let culled = mesh::cull_with_borders(chunk, borders);
+        mesh = mesher.mesh_with_borders(chunk, borders);
+        stats += MeshStats::new(&culled, &mesh);
+
+        for (count, (before, after)) in counts.iter_mut().zip(culled.iter().zip(&mesh)) {
+            count.0 += before.len();
+            count.1 += after.len();
+        }
+    }
+
+    println!("{path} meshed with {name}, {} chunks", chunks.len());
+    println!();
+
+    for (facing, (before, after)) in Facing::ALL.into_iter().zip(counts) {
+        println!("{:?}:\t{} -> {} rects", facing, before, after);
+    }
+
+    println!();
+    print!("{stats}");
+
+    if let Some(path) = &args.obj {
+        let write = |path| -> io::Result<()> {
+            let mut out = BufWriter::new(File::create(path)?);
+            export::write_obj(&mut out, &mesh)
+        };
+
+        write(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+
+    if let Some(path) = &args.quads {
+        let write = |path| -> io::Result<()> {
+            let mut out = BufWriter::new(File::create(path)?);
+            format::write_mesh(&mut out, &mesh)
+        };
+
+        write(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+
+    // Top faces seen from above
+    if let Some(path) = &args.ppm {
+        let write = |path| -> io::Result<()> {
+            let top = &mesh[Facing::PosY as usize];
+            let image = debug::rasterize(top, 32, 8, quad_material);
+            let mut out = BufWriter::new(File::create(path)?);
+            image.write_ppm(&mut out)
+        };
+
+        write(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = Args::parse(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        eprintln!("{USAGE}");
+        process::exit(2);
+    });
+
+    if let Err(message) = run(args) {
+        eprintln!("{message}");
+        process::exit(1);
+    }
+}
@@ -1,13 +1,30 @@
+mod tree;
+
 use std::{
+    collections::{HashMap, VecDeque},
+    iter,
     marker::PhantomData,
-    mem::{self, MaybeUninit},
-    num::NonZeroUsize,
+    mem,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    time::Instant,
 };
 
 use bytemuck::Pod;
-use wgpu::{Buffer, BufferDescriptor, BufferUsages};
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferAsyncError, BufferBinding,
+    BufferBindingType, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor,
+    Maintain, MapMode, ShaderStages, SubmissionIndex,
+};
+
+use crate::gfx::{Gfx, MemoryReport};
 
-use crate::gfx::Gfx;
+pub use self::tree::Layout;
+use self::tree::Tree;
 
 // Inspired by
 // https://nickmcd.me/2021/04/04/high-performance-voxel-engine/#voxel-data-rendering-systems,
@@ -16,8 +33,35 @@ use crate::gfx::Gfx;
 #[derive(Debug)]
 pub struct Buddy<T: Pod> {
     buffer: Buffer,
-    min_order: u8,
-    pub alloc_tree: Box<[i8]>,
+    tree: Tree,
+    metrics: Metrics,
+
+    // Bumped on `reset`, so stale handles can be told apart
+    generation: u32,
+
+    // Blocks tagged with a group id, and the reverse mapping
+    groups: HashMap<u64, Vec<usize>>,
+    group_of: HashMap<usize, u64>,
+
+    // Order every outstanding block was handed out in, and when,
+    // counting from the first block ever handed out
+    allocated: HashMap<usize, (u64, Instant)>,
+    serial: u64,
+
+    // Writes waiting for `flush_staged`, oldest first,
+    // and how many of them each block has waiting
+    staged: VecDeque<Staged>,
+    staged_blocks: HashMap<usize, usize>,
+
+    // Ticket for the next write, whether any write awaits submission,
+    // and the first ticket not yet known to have reached the GPU
+    next_upload: u64,
+    unflushed: bool,
+    completed_uploads: Arc<AtomicU64>,
+
+    // Outstanding blocks and their debug labels, reported on drop
+    #[cfg(debug_assertions)]
+    live: HashMap<usize, Option<&'static str>>,
 
     // `buffer` holds items of type T
     _casper: PhantomData<T>,
@@ -25,40 +69,63 @@ pub struct Buddy<T: Pod> {
 
 impl<T: Pod> Buddy<T> {
     const STRIDE: usize = mem::size_of::<T>();
-    const USED: i8 = i8::MIN;
 
     pub const fn capacity(&self) -> usize {
-        (self.alloc_tree.len() / 2) << self.min_order
+        1 << self.max_order()
+    }
+
+    // Into `report`, with the bytes of every allocated block
+    pub fn report_memory(&self, report: &mut MemoryReport, label: &'static str) {
+        let stride = Self::STRIDE as u64;
+        let used = self.metrics.used as u64 * stride;
+        report.add_buffer(label, used, self.buffer.size());
     }
 
     pub const fn max_order(&self) -> u8 {
-        self.capacity().ilog2() as _
+        self.tree.max_order()
+    }
+
+    pub const fn min_order(&self) -> u8 {
+        self.tree.min_order()
+    }
+
+    // Items in the biggest block left to hand out, none if it is full
+    pub fn largest_free(&self) -> usize {
+        self.tree.largest_free().map_or(0, |order| 1 << order)
     }
 
     pub fn new(gfx: &Gfx, capacity: usize, min_order: u8) -> Self {
+        Self::with_layout(gfx, capacity, min_order, Layout::default())
+    }
+
+    pub fn with_layout(gfx: &Gfx, capacity: usize, min_order: u8, layout: Layout) -> Self {
+        Self::build(gfx, capacity, min_order, layout, BufferUsages::STORAGE)
+    }
+
+    // Same as `new`, but the buffer is bound as something other than storage,
+    // say vertices or indices. Copies in and out stay allowed for uploads and moves
+    pub fn with_usage(gfx: &Gfx, capacity: usize, min_order: u8, usage: BufferUsages) -> Self {
+        Self::build(gfx, capacity, min_order, Layout::default(), usage)
+    }
+
+    fn build(
+        gfx: &Gfx,
+        capacity: usize,
+        min_order: u8,
+        layout: Layout,
+        usage: BufferUsages,
+    ) -> Self {
         let capacity = capacity.next_power_of_two();
         let max_order = capacity.ilog2() as u8;
 
         // Allocate tree to keep track of used/free blocks
-        let num_nodes = (2 * capacity) >> min_order;
-        let mut uninit_alloc_tree = Box::new_uninit_slice(num_nodes);
-
-        // Initialize with a single block covering the entire buffer
-        for level in 0..=(max_order - min_order) {
-            let order = max_order - level;
-            let level = level as usize;
-            let slice = &mut uninit_alloc_tree[1 << level..2 << level];
-            slice.fill(MaybeUninit::new(order as _));
-        }
-
-        // Initialize the first unused value of the array to avoid UB
-        uninit_alloc_tree[0].write(i8::MIN);
+        let tree = Tree::new(layout, max_order, min_order);
 
         // Allocate buffer to hold items
         let descriptor = BufferDescriptor {
             label: None,
             size: Self::STRIDE as u64 * capacity as u64,
-            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            usage: usage | BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         };
 
@@ -66,108 +133,734 @@ impl<T: Pod> Buddy<T> {
 
         Self {
             buffer,
-            min_order,
-            alloc_tree: unsafe { uninit_alloc_tree.assume_init() },
+            tree,
+            metrics: Metrics::new(max_order),
+            generation: 0,
+            groups: HashMap::new(),
+            group_of: HashMap::new(),
+            allocated: HashMap::new(),
+            serial: 0,
+            staged: VecDeque::new(),
+            staged_blocks: HashMap::new(),
+            next_upload: 0,
+            unflushed: false,
+            completed_uploads: Arc::default(),
+            #[cfg(debug_assertions)]
+            live: HashMap::new(),
             _casper: PhantomData,
         }
     }
 
-    fn update_parents(&mut self, mut block: usize) {
-        while block > 1 {
-            let a = self.alloc_tree[block ^ 0];
-            let b = self.alloc_tree[block ^ 1];
-            let merge = a == b;
-            block >>= 1;
+    // Block index behind a handle, which must not outlive a `reset`
+    fn block_of(&self, handle: &Handle<T>) -> usize {
+        assert_eq!(handle.generation, self.generation, "stale buddy handle");
+        handle.inner.get()
+    }
 
-            let old_value = self.alloc_tree[block];
-            let new_value = i8::max(a, b) + merge as i8;
-            self.alloc_tree[block] = new_value;
+    // Element offset and length of the span covered by a block
+    fn block_span(&self, block: usize) -> (usize, usize) {
+        let order = self.max_order() - block.ilog2() as u8;
+        let bias = 1 << block.ilog2();
+        let offset = (block - bias) << order;
+        (offset, 1 << order)
+    }
 
-            // If the value was not changed,
-            // no further parent will not be affected
-            if old_value == new_value {
-                break;
-            }
-        }
+    // Guaranteed byte alignment of any block of the given order,
+    // as a block of order n always starts at a multiple of 2^n items
+    pub const fn alignment(order: u8) -> usize {
+        1 << (Self::STRIDE.trailing_zeros() + order as u32)
     }
 
     pub fn alloc(&mut self, len: usize) -> Option<Handle<T>> {
-        // Calculate block order needed,
-        // capped to the minimum size available
+        // Calculate block order needed
         let len = len.next_power_of_two();
-        let target_order = u8::max(len.ilog2() as u8, self.min_order);
+        self.alloc_order(len.ilog2() as _)
+    }
 
-        // Early exit if there is no big enough block
-        if self.alloc_tree[1] < target_order as i8 {
+    // Allocate a block whose byte offset is a multiple of `align`
+    pub fn alloc_aligned(&mut self, len: usize, align: usize) -> Option<Handle<T>> {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+
+        // Raise the order until the block alignment is enough
+        let len = len.next_power_of_two();
+        let stride_align = Self::alignment(0);
+        let align_order = (align / stride_align).max(1).ilog2();
+        let order = u32::max(len.ilog2(), align_order);
+        let handle = self.alloc_order(order as _)?;
+
+        debug_assert_eq!(self.byte_offset(&handle) % align as u64, 0);
+        Some(handle)
+    }
+
+    pub fn alloc_order(&mut self, order: u8) -> Option<Handle<T>> {
+        let _span = tracing::trace_span!("alloc", order).entered();
+
+        // Cap to the minimum size available
+        let target_order = u8::max(order, self.min_order());
+
+        let Some(block) = self.tree.alloc(target_order) else {
+            self.metrics.failed_allocs += 1;
             return None;
-        }
+        };
+
+        self.hand_out(block, target_order)
+    }
 
-        // Walk down the tree,
-        // looking for a suitable block
-        let mut block = 1;
-        let levels_down = self.max_order() - target_order;
-        for _ in 0..levels_down {
-            block <<= 1;
+    // Allocate exactly the block at `offset` that `len` items round up to,
+    // both in items, as `offset` and `len` gave them for a handle of the same buddy.
+    // Lays blocks out again the way they were, say restoring a snapshot
+    pub fn alloc_at(&mut self, offset: usize, len: usize) -> Option<Handle<T>> {
+        let len = len.next_power_of_two();
+        let order = u8::max(len.ilog2() as u8, self.min_order());
+
+        // Blocks of order n start at multiples of 2^n items
+        let fits = order <= self.max_order() && offset < self.capacity();
+        let aligned = offset % (1 << order) == 0;
+        let bias = 1 << self.max_order().saturating_sub(order);
+        let block = bias + (offset >> order);
 
-            // jmi2k: when both are suitable,
-            //        choose the smallest one to mitigate fragmentation
-            let suitable = self.alloc_tree[block] >= target_order as i8;
-            block ^= !suitable as usize;
+        if !fits || !aligned || !self.tree.claim(block) {
+            self.metrics.failed_allocs += 1;
+            return None;
         }
 
-        // Claim block
-        let handle = Handle::new(block);
-        self.alloc_tree[block] = Self::USED;
-        self.update_parents(block);
+        self.hand_out(block, order)
+    }
+
+    // Bookkeeping for a block just claimed out of the tree
+    fn hand_out(&mut self, block: usize, order: u8) -> Option<Handle<T>> {
+        let handle = Handle::new(block, self.generation);
+        self.metrics.record_alloc(order);
+        self.allocated.insert(block, (self.serial, Instant::now()));
+        self.serial += 1;
+
+        #[cfg(debug_assertions)]
+        self.live.insert(block, None);
+
         handle
     }
 
+    // Same as `alloc`, but the block is named in leak reports
+    pub fn alloc_labeled(&mut self, len: usize, label: &'static str) -> Option<Handle<T>> {
+        let handle = self.alloc(len)?;
+
+        #[cfg(debug_assertions)]
+        self.live.insert(handle.inner.get(), Some(label));
+
+        #[cfg(not(debug_assertions))]
+        let _ = label;
+
+        Some(handle)
+    }
+
+    // Same as `alloc`, but the block can be freed along its group
+    pub fn alloc_grouped(&mut self, len: usize, group: u64) -> Option<Handle<T>> {
+        let handle = self.alloc(len)?;
+        self.join_group(handle.inner.get(), group);
+        Some(handle)
+    }
+
+    fn join_group(&mut self, block: usize, group: u64) {
+        self.groups.entry(group).or_default().push(block);
+        self.group_of.insert(block, group);
+    }
+
+    fn leave_group(&mut self, block: usize) {
+        let Some(group) = self.group_of.remove(&block) else {
+            return;
+        };
+
+        let blocks = self.groups.get_mut(&group).unwrap();
+        let index = blocks.iter().position(|&other| other == block).unwrap();
+        blocks.swap_remove(index);
+
+        if blocks.is_empty() {
+            self.groups.remove(&group);
+        }
+    }
+
+    // Free every block in a group in a single pass over the tree.
+    // Outstanding handles to those blocks must not be used afterwards
+    pub fn free_group(&mut self, group: u64) {
+        let Some(blocks) = self.groups.remove(&group) else {
+            return;
+        };
+
+        for &block in &blocks {
+            let order = self.max_order() - block.ilog2() as u8;
+            self.metrics.record_free(order);
+            self.group_of.remove(&block);
+            self.allocated.remove(&block);
+            self.unstage(block);
+
+            #[cfg(debug_assertions)]
+            self.live.remove(&block);
+        }
+
+        self.tree.free_many(blocks);
+    }
+
+    // Offset of the first item of a block, in items
+    pub fn offset(&self, handle: &Handle<T>) -> usize {
+        self.block_span(self.block_of(handle)).0
+    }
+
+    pub fn byte_offset(&self, handle: &Handle<T>) -> u64 {
+        (Self::STRIDE * self.offset(handle)) as _
+    }
+
+    // Number of items a block can hold
+    pub fn len(&self, handle: &Handle<T>) -> usize {
+        self.block_span(self.block_of(handle)).1
+    }
+
     pub fn free(&mut self, handle: Handle<T>) {
-        let block = handle.inner.get();
+        let _span = tracing::trace_span!("free").entered();
+        let block = self.block_of(&handle);
         let order = self.max_order() - block.ilog2() as u8;
-        self.alloc_tree[block] = order as i8;
-        self.update_parents(block);
+        self.tree.free(block);
+        self.metrics.record_free(order);
+        self.leave_group(block);
+        self.allocated.remove(&block);
+        self.unstage(block);
+
+        #[cfg(debug_assertions)]
+        self.live.remove(&block);
     }
 
-    pub fn write(&mut self, gfx: &Gfx, handle: &Handle<T>, data: &[T]) {
-        let block = handle.inner.get();
-        let order = self.max_order() - block.ilog2() as u8;
-        let bias = 1 << block.ilog2();
-        let offset = (block - bias) << order;
+    // Rebuild the handle of a block from its byte offset and length,
+    // checking that exactly such a block is handed out. The original handle
+    // must be forgotten, or the block would end up being freed twice
+    pub fn handle_from_offset(&self, offset: u64, len: usize) -> Option<Handle<T>> {
+        let len = len.next_power_of_two();
+        let order = u8::max(len.ilog2() as u8, self.min_order());
+
+        if order > self.max_order() || offset % Self::STRIDE as u64 != 0 {
+            return None;
+        }
+
+        // Blocks of order n start at multiples of 2^n items
+        let offset = (offset / Self::STRIDE as u64) as usize;
+        if offset % (1 << order) != 0 || offset >= self.capacity() {
+            return None;
+        }
+
+        let bias = 1 << (self.max_order() - order);
+        let block = bias + (offset >> order);
+
+        if !self.tree.is_used(block) {
+            return None;
+        }
+
+        Handle::new(block, self.generation)
+    }
+
+    // Free a block given only its byte offset and length,
+    // returning whether such a block was actually allocated
+    pub fn free_by_offset(&mut self, offset: u64, len: usize) -> bool {
+        let Some(handle) = self.handle_from_offset(offset, len) else {
+            return false;
+        };
+
+        self.free(handle);
+        true
+    }
+
+    // Free every block at once, invalidating all outstanding handles
+    pub fn reset(&mut self) {
+        self.tree.reset();
+        self.metrics.record_reset();
+        self.groups.clear();
+        self.group_of.clear();
+        self.allocated.clear();
+        self.staged.clear();
+        self.staged_blocks.clear();
+        self.generation = self.generation.wrapping_add(1);
+
+        #[cfg(debug_assertions)]
+        self.live.clear();
+    }
+
+    pub const fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Every outstanding block, oldest first
+    pub fn iter_allocations(&self) -> impl Iterator<Item = Allocation> + '_ {
+        let mut blocks: Vec<_> = self.allocated.iter().collect();
+        blocks.sort_unstable_by_key(|(_, &(serial, _))| serial);
+
+        blocks.into_iter().map(|(&block, &(serial, since))| {
+            let (offset, len) = self.block_span(block);
+            Allocation {
+                offset,
+                len,
+                serial,
+                since,
+            }
+        })
+    }
+
+    pub fn write(&mut self, gfx: &Gfx, handle: &Handle<T>, data: &[T]) -> Upload {
+        self.write_at(gfx, handle, 0, data)
+    }
+
+    // Write items `offset` items into a block, to update only part of it
+    pub fn write_at(&mut self, gfx: &Gfx, handle: &Handle<T>, offset: usize, data: &[T]) -> Upload {
+        let _span = tracing::trace_span!("write", len = data.len()).entered();
+        let fits = offset + data.len() <= self.len(handle);
+        assert!(fits, "write past the end of the block");
+
+        let offset = self.byte_offset(handle) + (Self::STRIDE * offset) as u64;
         let blob = bytemuck::cast_slice(data);
-        gfx.queue.write_buffer(&self.buffer, offset as _, blob);
+        gfx.queue.write_buffer(&self.buffer, offset, blob);
+
+        let upload = Upload(self.next_upload);
+        self.next_upload += 1;
+        self.unflushed = true;
+        upload
+    }
+
+    // Same as `write_at`, but left waiting for `flush_staged` to write it
+    // along with the rest within its budget. Writes issued right away to a block
+    // with writes waiting would get written over once these are, so they
+    // are better staged as well until `is_staged` says they are gone
+    pub fn stage_at(&mut self, handle: &Handle<T>, offset: usize, data: &[T]) {
+        let fits = offset + data.len() <= self.len(handle);
+        assert!(fits, "write past the end of the block");
+
+        let block = self.block_of(handle);
+        let staged = Staged {
+            block,
+            offset: (Self::STRIDE * offset) as u64,
+            data: bytemuck::cast_slice(data).to_vec(),
+        };
+
+        self.staged.push_back(staged);
+        *self.staged_blocks.entry(block).or_default() += 1;
+    }
+
+    // Whether a block has writes waiting, leaving what it holds not worth drawing yet
+    pub fn is_staged(&self, handle: &Handle<T>) -> bool {
+        self.staged_blocks.contains_key(&self.block_of(handle))
+    }
+
+    // Bytes left waiting by `stage_at`
+    pub fn staged_bytes(&self) -> usize {
+        self.staged.iter().map(|staged| staged.data.len()).sum()
+    }
+
+    // Write whatever `stage_at` left waiting, oldest first, as long as it adds up
+    // to no more than `budget` bytes, leaving the rest for the next flush.
+    // The oldest always gets written, however large, for every write to get
+    // through in the end. All of them get copied out of a single staging buffer
+    // in a single submission. Returns how many bytes got written
+    pub fn flush_staged(&mut self, gfx: &Gfx, budget: usize) -> usize {
+        let mut count = 0;
+        let mut size = 0;
+        for staged in &self.staged {
+            if count > 0 && size + staged.data.len() > budget {
+                break;
+            }
+
+            count += 1;
+            size += staged.data.len();
+        }
+
+        if count == 0 {
+            return 0;
+        }
+
+        let _span = tracing::debug_span!("flush_staged", count, size).entered();
+        let descriptor = BufferDescriptor {
+            label: Some("staged writes"),
+            size: size as u64,
+            usage: BufferUsages::COPY_SRC,
+            mapped_at_creation: true,
+        };
+
+        let staging = gfx.device.create_buffer(&descriptor);
+        let descriptor = CommandEncoderDescriptor {
+            label: Some("staged writes"),
+        };
+
+        let mut encoder = gfx.device.create_command_encoder(&descriptor);
+        let mut mapped = staging.slice(..).get_mapped_range_mut();
+        let mut start = 0;
+
+        let flushed: Vec<_> = self.staged.drain(..count).collect();
+        for staged in flushed {
+            let end = start + staged.data.len();
+            mapped[start..end].copy_from_slice(&staged.data);
+
+            let (offset, _) = self.block_span(staged.block);
+            let offset = (Self::STRIDE * offset) as u64 + staged.offset;
+            let len = staged.data.len() as u64;
+            encoder.copy_buffer_to_buffer(&staging, start as u64, &self.buffer, offset, len);
+            start = end;
+
+            let waiting = self.staged_blocks.get_mut(&staged.block).unwrap();
+            *waiting -= 1;
+            if *waiting == 0 {
+                self.staged_blocks.remove(&staged.block);
+            }
+        }
+
+        drop(mapped);
+        staging.unmap();
+        gfx.queue.submit([encoder.finish()]);
+        size
+    }
+
+    // Forget the writes a block has waiting, as it is no longer handed out
+    fn unstage(&mut self, block: usize) {
+        if self.staged_blocks.remove(&block).is_some() {
+            self.staged.retain(|staged| staged.block != block);
+        }
+    }
+
+    // Submit pending writes (they ride along the next submission anyway)
+    // and get notified once the GPU is done with them
+    fn flush_uploads(&mut self, gfx: &Gfx) -> SubmissionIndex {
+        let index = gfx.queue.submit(iter::empty());
+
+        if self.unflushed {
+            let completed_uploads = self.completed_uploads.clone();
+            let next_upload = self.next_upload;
+            self.unflushed = false;
+
+            gfx.queue.on_submitted_work_done(move || {
+                completed_uploads.fetch_max(next_upload, Ordering::Release);
+            });
+        }
+
+        index
+    }
+
+    // Advance upload tracking without blocking
+    pub fn poll_uploads(&mut self, gfx: &Gfx) -> SubmissionIndex {
+        let index = self.flush_uploads(gfx);
+        gfx.device.poll(Maintain::Poll);
+        index
     }
 
-    pub fn load(&mut self, gfx: &Gfx, data: &[T]) -> Option<Handle<T>> {
+    // Block until every write issued so far has completed
+    pub fn wait_uploads(&mut self, gfx: &Gfx) {
+        let _span = tracing::debug_span!("wait_uploads").entered();
+        let index = self.flush_uploads(gfx);
+        gfx.device.poll(Maintain::WaitForSubmissionIndex(index));
+    }
+
+    // Whole block as the GPU has it, every write issued so far included.
+    // Waits for it to be copied back, so it is meant for tests and debugging
+    pub fn read(&self, gfx: &Gfx, handle: &Handle<T>) -> Result<Vec<T>, BufferAsyncError> {
+        let size = (Self::STRIDE * self.len(handle)) as u64;
+        let descriptor = BufferDescriptor {
+            label: Some("read back"),
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+
+        let staging = gfx.device.create_buffer(&descriptor);
+        let descriptor = CommandEncoderDescriptor {
+            label: Some("read back"),
+        };
+
+        let mut encoder = gfx.device.create_command_encoder(&descriptor);
+        let offset = self.byte_offset(handle);
+        encoder.copy_buffer_to_buffer(&self.buffer, offset, &staging, 0, size);
+        let index = gfx.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        let slice = staging.slice(..);
+        slice.map_async(MapMode::Read, move |result| sender.send(result).unwrap());
+        gfx.device.poll(Maintain::WaitForSubmissionIndex(index));
+
+        // Mapping is done by the time the device is done with the copy
+        receiver.recv().unwrap()?;
+        let items = bytemuck::pod_collect_to_vec(&slice.get_mapped_range());
+        staging.unmap();
+        Ok(items)
+    }
+
+    // Whether the data of a write is already in GPU memory,
+    // as of the last `poll_uploads` or `wait_uploads`
+    pub fn is_uploaded(&self, upload: Upload) -> bool {
+        self.completed_uploads.load(Ordering::Acquire) > upload.0
+    }
+
+    // The old block is free for reuse as soon as this returns,
+    // but queue writes are staged ahead of any recorded command:
+    // submit `encoder` before loading new data into the buddy
+    pub fn move_block(
+        &mut self,
+        gfx: &Gfx,
+        encoder: &mut CommandEncoder,
+        handle: Handle<T>,
+    ) -> Result<Handle<T>, Handle<T>> {
+        let (src_offset, len) = self.block_span(self.block_of(&handle));
+
+        // Claim the destination before releasing the source,
+        // so that both blocks never overlap
+        let Some(new_handle) = self.alloc(len) else {
+            return Err(handle);
+        };
+
+        let (dst_offset, _) = self.block_span(new_handle.inner.get());
+        let size = (Self::STRIDE * len) as u64;
+
+        // WebGPU forbids copying within the same buffer,
+        // so bounce the contents through a scratch buffer
+        let descriptor = BufferDescriptor {
+            label: None,
+            size,
+            usage: BufferUsages::COPY_SRC | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+
+        let scratch = gfx.device.create_buffer(&descriptor);
+        let src_offset = (Self::STRIDE * src_offset) as u64;
+        let dst_offset = (Self::STRIDE * dst_offset) as u64;
+        encoder.copy_buffer_to_buffer(&self.buffer, src_offset, &scratch, 0, size);
+        encoder.copy_buffer_to_buffer(&scratch, 0, &self.buffer, dst_offset, size);
+
+        // The group, label and age follow the contents
+        if let Some(&group) = self.group_of.get(&handle.inner.get()) {
+            self.join_group(new_handle.inner.get(), group);
+        }
+
+        let allocated = self.allocated[&handle.inner.get()];
+        self.allocated.insert(new_handle.inner.get(), allocated);
+
+        // Writes waiting land on the copy, after it is made
+        if let Some(waiting) = self.staged_blocks.remove(&handle.inner.get()) {
+            let staged = self.staged.iter_mut();
+            let moved = staged.filter(|staged| staged.block == handle.inner.get());
+            moved.for_each(|staged| staged.block = new_handle.inner.get());
+            self.staged_blocks.insert(new_handle.inner.get(), waiting);
+        }
+
+        #[cfg(debug_assertions)]
+        if let Some(label) = self.live[&handle.inner.get()] {
+            self.live.insert(new_handle.inner.get(), Some(label));
+        }
+
+        self.free(handle);
+        Ok(new_handle)
+    }
+
+    pub fn load(&mut self, gfx: &Gfx, data: &[T]) -> Option<(Handle<T>, Upload)> {
         let handle = self.alloc(data.len())?;
-        self.write(gfx, &handle, data);
-        Some(handle)
+        let upload = self.write(gfx, &handle, data);
+        Some((handle, upload))
+    }
+
+    pub const fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    pub fn as_binding(&self) -> BindingResource<'_> {
+        self.buffer.as_entire_binding()
+    }
+
+    // Read-only storage binding of a window of `len` items,
+    // which `bind` then points at individual blocks via dynamic offsets
+    pub fn create_binding(&self, gfx: &Gfx, visibility: ShaderStages, len: usize) -> Binding {
+        let window = (Self::STRIDE * len) as u64;
+
+        let entry = BindGroupLayoutEntry {
+            binding: 0,
+            visibility,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: true,
+                min_binding_size: NonZeroU64::new(window),
+            },
+            count: None,
+        };
+
+        let descriptor = BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[entry],
+        };
+
+        let layout = gfx.device.create_bind_group_layout(&descriptor);
+
+        let buffer_binding = BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: NonZeroU64::new(window),
+        };
+
+        let entry = BindGroupEntry {
+            binding: 0,
+            resource: BindingResource::Buffer(buffer_binding),
+        };
+
+        let descriptor = BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[entry],
+        };
+
+        let group = gfx.device.create_bind_group(&descriptor);
+
+        Binding {
+            layout,
+            group,
+            window,
+        }
+    }
+
+    // Allocate a block that can be bound through a dynamic offset
+    pub fn alloc_bindable(&mut self, gfx: &Gfx, len: usize) -> Option<Handle<T>> {
+        let align = gfx.device.limits().min_storage_buffer_offset_alignment;
+        self.alloc_aligned(len, align as _)
+    }
+
+    // Bind group and dynamic offset pointing `binding` at a block,
+    // if the block is suitably aligned and the window fits in the buffer
+    pub fn bind<'b>(
+        &self,
+        gfx: &Gfx,
+        binding: &'b Binding,
+        handle: &Handle<T>,
+    ) -> Option<(&'b BindGroup, u32)> {
+        let offset = self.byte_offset(handle);
+        let align = gfx.device.limits().min_storage_buffer_offset_alignment;
+        let fits = offset + binding.window <= self.buffer.size();
+
+        if offset % align as u64 != 0 || !fits {
+            return None;
+        }
+
+        let offset = u32::try_from(offset).ok()?;
+        Some((&binding.group, offset))
     }
 
     pub fn check_is_same(&self, other: &Self) -> bool {
-        for i in 0..self.alloc_tree.len() {
-            if self.alloc_tree[i] != other.alloc_tree[i] {
-                return false;
-            }
+        self.tree == other.tree
+    }
+}
+
+// Write left waiting by `Buddy::stage_at`, `offset` bytes into its block
+#[derive(Debug)]
+struct Staged {
+    block: usize,
+    offset: u64,
+    data: Vec<u8>,
+}
+
+// Ticket for a write, ordered by issue time.
+// Writes complete in order, so newer tickets imply older ones
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Upload(u64);
+
+#[derive(Debug)]
+pub struct Binding {
+    pub layout: BindGroupLayout,
+    pub group: BindGroup,
+
+    // Bytes visible through the binding
+    window: u64,
+}
+
+#[cfg(debug_assertions)]
+impl<T: Pod> Drop for Buddy<T> {
+    fn drop(&mut self) {
+        if self.live.is_empty() {
+            return;
         }
 
-        true
+        tracing::warn!("buddy dropped with {} outstanding blocks", self.live.len());
+
+        let mut leaks: Vec<_> = self.live.iter().collect();
+        leaks.sort_unstable();
+
+        for (&block, label) in leaks {
+            let (offset, len) = self.block_span(block);
+            let label = label.unwrap_or("<unlabeled>");
+            tracing::warn!(label, len, offset, "leaked block");
+        }
+    }
+}
+
+// Block handed out, as seen by `Buddy::iter_allocations`
+#[derive(Clone, Copy, Debug)]
+pub struct Allocation {
+    // Span covered, in items
+    pub offset: usize,
+    pub len: usize,
+
+    // How many blocks were handed out before it, and when it was
+    pub serial: u64,
+    pub since: Instant,
+}
+
+// Lifetime statistics, all sizes measured in items of type T
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    pub used: usize,
+    pub peak_used: usize,
+    pub failed_allocs: usize,
+
+    // Indexed by block order
+    pub allocs_per_order: Box<[usize]>,
+    pub frees_per_order: Box<[usize]>,
+}
+
+impl Metrics {
+    fn new(max_order: u8) -> Self {
+        let num_orders = max_order as usize + 1;
+
+        Self {
+            used: 0,
+            peak_used: 0,
+            failed_allocs: 0,
+            allocs_per_order: vec![0; num_orders].into_boxed_slice(),
+            frees_per_order: vec![0; num_orders].into_boxed_slice(),
+        }
+    }
+
+    fn record_alloc(&mut self, order: u8) {
+        self.used += 1 << order;
+        self.peak_used = usize::max(self.peak_used, self.used);
+        self.allocs_per_order[order as usize] += 1;
+    }
+
+    fn record_free(&mut self, order: u8) {
+        self.used -= 1 << order;
+        self.frees_per_order[order as usize] += 1;
+    }
+
+    fn record_reset(&mut self) {
+        self.used = 0;
+        self.frees_per_order.clone_from(&self.allocs_per_order);
+    }
+
+    // Blocks of each order currently handed out
+    pub fn live_per_order(&self) -> impl Iterator<Item = usize> + '_ {
+        let allocs = self.allocs_per_order.iter();
+        let frees = self.frees_per_order.iter();
+        allocs.zip(frees).map(|(allocs, frees)| allocs - frees)
     }
 }
 
-#[repr(transparent)]
 #[derive(Debug)]
 pub struct Handle<T: Pod> {
     inner: NonZeroUsize,
+    generation: u32,
 
     // Handle comes from a `Buddy<T>`
     _casper: PhantomData<T>,
 }
 
 impl<T: Pod> Handle<T> {
-    fn new(block: usize) -> Option<Self> {
+    fn new(block: usize, generation: u32) -> Option<Self> {
         let elf = Self {
             inner: NonZeroUsize::new(block)?,
+            generation,
             _casper: PhantomData,
         };
 
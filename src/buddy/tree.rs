@@ -0,0 +1,520 @@
+use std::{collections::BinaryHeap, convert, mem::MaybeUninit};
+
+// Node storage backing the buddy allocator.
+// Nodes are laid out as an implicit binary tree starting at index 1,
+// so the children of node `n` are `2n` and `2n + 1`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+    // One byte per node holding the largest free order below it
+    #[default]
+    Orders,
+
+    // Two bits per node holding its occupancy state, 4x smaller,
+    // but finding a block requires a depth-first search
+    Bitmap,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Tree {
+    Orders(OrderTree),
+    Bitmap(BitmapTree),
+}
+
+impl Tree {
+    pub fn new(layout: Layout, max_order: u8, min_order: u8) -> Self {
+        match layout {
+            Layout::Orders => Self::Orders(OrderTree::new(max_order, min_order)),
+            Layout::Bitmap => Self::Bitmap(BitmapTree::new(max_order, min_order)),
+        }
+    }
+
+    pub const fn max_order(&self) -> u8 {
+        match self {
+            Self::Orders(tree) => tree.max_order,
+            Self::Bitmap(tree) => tree.max_order,
+        }
+    }
+
+    pub const fn min_order(&self) -> u8 {
+        match self {
+            Self::Orders(tree) => tree.min_order,
+            Self::Bitmap(tree) => tree.min_order,
+        }
+    }
+
+    // Claim a block of exactly `order`, returning its node index
+    pub fn alloc(&mut self, order: u8) -> Option<usize> {
+        match self {
+            Self::Orders(tree) => tree.alloc(order),
+            Self::Bitmap(tree) => tree.alloc(order),
+        }
+    }
+
+    pub fn free(&mut self, block: usize) {
+        match self {
+            Self::Orders(tree) => tree.free(block),
+            Self::Bitmap(tree) => tree.free(block),
+        }
+    }
+
+    // Claim a given block, splitting whatever free block it lies in.
+    // False if any of it is handed out already
+    pub fn claim(&mut self, block: usize) -> bool {
+        match self {
+            Self::Orders(tree) => tree.claim(block),
+            Self::Bitmap(tree) => tree.claim(block),
+        }
+    }
+
+    // Whether a block is currently handed out as a whole
+    pub fn is_used(&self, block: usize) -> bool {
+        let is_claimed = |node| match self {
+            Self::Orders(tree) => tree.nodes[node] == OrderTree::USED,
+            Self::Bitmap(tree) => tree.get(node) == BitmapTree::USED,
+        };
+
+        // Nodes below a claimed block are stale, so check the ancestry
+        let mut ancestor = block >> 1;
+        while ancestor > 0 {
+            if is_claimed(ancestor) {
+                return false;
+            }
+
+            ancestor >>= 1;
+        }
+
+        is_claimed(block)
+    }
+
+    // Order of the biggest block left to hand out, if any
+    pub fn largest_free(&self) -> Option<u8> {
+        match self {
+            Self::Orders(tree) => tree.largest_free(),
+            Self::Bitmap(tree) => tree.largest_free(),
+        }
+    }
+
+    // Free many blocks, repairing every affected parent only once
+    pub fn free_many(&mut self, blocks: impl IntoIterator<Item = usize>) {
+        let mut dirty = BinaryHeap::new();
+
+        for block in blocks {
+            match self {
+                Self::Orders(tree) => tree.release(block),
+                Self::Bitmap(tree) => tree.release(block),
+            }
+
+            dirty.push(block >> 1);
+        }
+
+        // Parents have smaller indices than their children,
+        // so popping the largest first repairs the tree bottom-up
+        let mut last = 0;
+        while let Some(node) = dirty.pop() {
+            if node == last || node == 0 {
+                continue;
+            }
+
+            last = node;
+            let changed = match self {
+                Self::Orders(tree) => tree.repair(node),
+                Self::Bitmap(tree) => tree.repair(node),
+            };
+
+            if changed {
+                dirty.push(node >> 1);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Self::Orders(tree) => tree.reset(),
+            Self::Bitmap(tree) => tree.reset(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrderTree {
+    max_order: u8,
+    min_order: u8,
+    nodes: Box<[i8]>,
+}
+
+impl OrderTree {
+    const USED: i8 = i8::MIN;
+
+    // Nothing below is free, though the node itself was not handed out
+    const FULL: i8 = i8::MIN + 1;
+
+    // The 2^6 nodes this many levels below any node lie in 64 contiguous bytes.
+    // Nodes are not aligned to cache lines, so those may straddle two of them
+    const PREFETCH_LEVELS: u32 = 6;
+
+    fn new(max_order: u8, min_order: u8) -> Self {
+        let num_nodes = 2 << (max_order - min_order);
+        let mut uninit_nodes = Box::new_uninit_slice(num_nodes);
+
+        // Initialize with a single block covering the entire buffer
+        fill_levels(&mut uninit_nodes, max_order, min_order, MaybeUninit::new);
+
+        // Initialize the first unused value of the array to avoid UB
+        uninit_nodes[0].write(i8::MIN);
+
+        Self {
+            max_order,
+            min_order,
+            nodes: unsafe { uninit_nodes.assume_init() },
+        }
+    }
+
+    // Recompute a node from its children, returning whether it changed
+    fn repair(&mut self, node: usize) -> bool {
+        let a = self.nodes[2 * node];
+        let b = self.nodes[2 * node + 1];
+
+        // Only two entirely free children merge into a bigger block
+        let child_order = self.max_order - (2 * node).ilog2() as u8;
+        let merge = a == b && a == child_order as i8;
+
+        let old_value = self.nodes[node];
+        let new_value = match i8::max(a, b) {
+            Self::USED => Self::FULL,
+            order => order + merge as i8,
+        };
+
+        self.nodes[node] = new_value;
+        old_value != new_value
+    }
+
+    fn update_parents(&mut self, mut block: usize) {
+        while block > 1 {
+            block >>= 1;
+
+            // If the value was not changed,
+            // no further parent will not be affected
+            if !self.repair(block) {
+                break;
+            }
+        }
+    }
+
+    fn alloc(&mut self, target_order: u8) -> Option<usize> {
+        // Early exit if there is no big enough block
+        if self.nodes[1] < target_order as i8 {
+            return None;
+        }
+
+        // Walk down the tree,
+        // looking for a suitable block
+        let mut block = 1;
+        let levels_down = self.max_order - target_order;
+        for _ in 0..levels_down {
+            // Every node the walk can reach a few levels down lies within 64 bytes,
+            // so keep the first of them in flight instead of stalling on each level
+            // in turn. Often that is all of them, but it takes alignment by chance
+            prefetch(&self.nodes, block << Self::PREFETCH_LEVELS);
+            block <<= 1;
+
+            // jmi2k: when both are suitable,
+            //        choose the smallest one to mitigate fragmentation
+            let suitable = self.nodes[block] >= target_order as i8;
+            block ^= !suitable as usize;
+        }
+
+        // Claim block
+        self.nodes[block] = Self::USED;
+        self.update_parents(block);
+        Some(block)
+    }
+
+    // The root already holds it
+    fn largest_free(&self) -> Option<u8> {
+        let order = self.nodes[1];
+        (order >= self.min_order as i8).then_some(order as u8)
+    }
+
+    // Nodes below a free block hold their own order, as do those below a claimed one,
+    // which are stale, so none of the ancestry may have been claimed
+    fn claim(&mut self, block: usize) -> bool {
+        let order = self.max_order - block.ilog2() as u8;
+        let mut ancestor = block >> 1;
+        while ancestor > 0 {
+            if self.nodes[ancestor] == Self::USED {
+                return false;
+            }
+
+            ancestor >>= 1;
+        }
+
+        if self.nodes[block] != order as i8 {
+            return false;
+        }
+
+        self.nodes[block] = Self::USED;
+        self.update_parents(block);
+        true
+    }
+
+    // Mark a block as free without touching its parents
+    fn release(&mut self, block: usize) {
+        let order = self.max_order - block.ilog2() as u8;
+        self.nodes[block] = order as i8;
+    }
+
+    fn free(&mut self, block: usize) {
+        self.release(block);
+        self.update_parents(block);
+    }
+
+    fn reset(&mut self) {
+        let (max_order, min_order) = (self.max_order, self.min_order);
+        fill_levels(&mut self.nodes, max_order, min_order, convert::identity);
+    }
+}
+
+#[inline(always)]
+fn prefetch(nodes: &[i8], index: usize) {
+    #[cfg(target_arch = "x86_64")]
+    if index < nodes.len() {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+
+        // Prefetching has no architectural effect, so it cannot break anything
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(nodes.as_ptr().add(index)) };
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = (nodes, index);
+}
+
+// Write the order of every node, from the root down to the minimum blocks
+fn fill_levels<E: Clone>(tree: &mut [E], max_order: u8, min_order: u8, wrap: impl Fn(i8) -> E) {
+    for level in 0..=(max_order - min_order) {
+        let order = max_order - level;
+        let level = level as usize;
+        let slice = &mut tree[1 << level..2 << level];
+        slice.fill(wrap(order as _));
+    }
+}
+
+// Restioson-style bitmap tree, packing 32 nodes per word.
+// Children of a free node are never read, so splitting a node
+// must explicitly mark both of its children as free
+#[derive(Debug, PartialEq, Eq)]
+pub struct BitmapTree {
+    max_order: u8,
+    min_order: u8,
+    words: Box<[u64]>,
+}
+
+impl BitmapTree {
+    // The whole subtree is free
+    const FREE: u8 = 0b00;
+
+    // Some, but not all, of the subtree is in use
+    const PARTIAL: u8 = 0b01;
+
+    // The node itself was handed out
+    const USED: u8 = 0b10;
+
+    // Both children are used or full
+    const FULL: u8 = 0b11;
+
+    fn new(max_order: u8, min_order: u8) -> Self {
+        let num_nodes = 2usize << (max_order - min_order);
+        let num_words = num_nodes.div_ceil(32);
+
+        Self {
+            max_order,
+            min_order,
+            words: vec![0; num_words].into_boxed_slice(),
+        }
+    }
+
+    fn get(&self, node: usize) -> u8 {
+        let shift = node % 32 * 2;
+        (self.words[node / 32] >> shift) as u8 & 0b11
+    }
+
+    fn set(&mut self, node: usize, state: u8) {
+        let shift = node % 32 * 2;
+        let word = &mut self.words[node / 32];
+        *word = *word & !(0b11 << shift) | (state as u64) << shift;
+    }
+
+    // Recompute a node from its children, returning whether it changed
+    fn repair(&mut self, node: usize) -> bool {
+        let a = self.get(2 * node);
+        let b = self.get(2 * node + 1);
+
+        // USED and FULL both have the high bit set
+        let new_state = match (a, b) {
+            (Self::FREE, Self::FREE) => Self::FREE,
+            _ if a & b & 0b10 != 0 => Self::FULL,
+            _ => Self::PARTIAL,
+        };
+
+        let changed = self.get(node) != new_state;
+        self.set(node, new_state);
+        changed
+    }
+
+    fn update_parents(&mut self, mut block: usize) {
+        while block > 1 {
+            block >>= 1;
+
+            // If the state was not changed,
+            // no further parent will be affected
+            if !self.repair(block) {
+                break;
+            }
+        }
+    }
+
+    fn alloc(&mut self, target_order: u8) -> Option<usize> {
+        if target_order > self.max_order {
+            return None;
+        }
+
+        let target_level = self.max_order - target_order;
+        let mut block = 1;
+        let mut level = 0;
+
+        // Depth-first search for the leftmost free subtree
+        // that is at least as big as requested
+        loop {
+            match self.get(block) {
+                Self::FREE => break,
+                Self::PARTIAL if level < target_level => {
+                    block <<= 1;
+                    level += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            // Backtrack up to the next unexplored right sibling
+            while block & 1 == 1 {
+                if block == 1 {
+                    return None;
+                }
+
+                block >>= 1;
+                level -= 1;
+            }
+
+            block += 1;
+        }
+
+        // Split the free subtree down to the requested order
+        while level < target_level {
+            block <<= 1;
+            level += 1;
+            self.set(block ^ 0, Self::FREE);
+            self.set(block ^ 1, Self::FREE);
+        }
+
+        // Claim block
+        self.set(block, Self::USED);
+        self.update_parents(block);
+        Some(block)
+    }
+
+    // Breadth-first, down to the first level with a free node
+    fn largest_free(&self) -> Option<u8> {
+        let mut level = vec![1];
+
+        for order in (self.min_order..=self.max_order).rev() {
+            if level.iter().any(|&node| self.get(node) == Self::FREE) {
+                return Some(order);
+            }
+
+            let is_partial = |&node: &usize| self.get(node) == Self::PARTIAL;
+            let partial = level.into_iter().filter(is_partial);
+            level = partial.flat_map(|node| [2 * node, 2 * node + 1]).collect();
+        }
+
+        None
+    }
+
+    // Walk down to the block, splitting free nodes along the way as `alloc` does
+    fn claim(&mut self, block: usize) -> bool {
+        let depth = block.ilog2();
+        for level in 0..depth {
+            let node = block >> (depth - level);
+            match self.get(node) {
+                Self::FREE => {
+                    self.set(2 * node, Self::FREE);
+                    self.set(2 * node + 1, Self::FREE);
+                }
+                Self::PARTIAL => {}
+                _ => return false,
+            }
+        }
+
+        if self.get(block) != Self::FREE {
+            return false;
+        }
+
+        self.set(block, Self::USED);
+        self.update_parents(block);
+        true
+    }
+
+    // Mark a block as free without touching its parents
+    fn release(&mut self, block: usize) {
+        self.set(block, Self::FREE);
+    }
+
+    fn free(&mut self, block: usize) {
+        self.release(block);
+        self.update_parents(block);
+    }
+
+    fn reset(&mut self) {
+        self.words.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // First of the blocks of order 4, in trees of order 6 down to order 2
+    const FIRST: usize = 4;
+
+    #[test]
+    fn siblings_both_used_are_found() {
+        for layout in [Layout::Orders, Layout::Bitmap] {
+            let mut tree = Tree::new(layout, 6, 2);
+            let a = tree.alloc(4).unwrap();
+            let b = tree.alloc(4).unwrap();
+            assert_eq!(a ^ 1, b, "{layout:?} split the first block");
+
+            // Their parent has nothing free below, yet was never handed out
+            assert!(tree.is_used(a), "{layout:?}");
+            assert!(tree.is_used(b), "{layout:?}");
+            assert!(!tree.is_used(a >> 1), "{layout:?}");
+            assert!(!tree.is_used(FIRST + 2), "{layout:?}");
+
+            tree.free(a);
+            assert!(!tree.is_used(a), "{layout:?}");
+            assert!(tree.is_used(b), "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn claimed_siblings_are_found() {
+        for layout in [Layout::Orders, Layout::Bitmap] {
+            let mut tree = Tree::new(layout, 6, 2);
+            assert!(tree.claim(FIRST + 3));
+            assert!(tree.claim(FIRST + 2));
+            assert!(!tree.claim(FIRST + 2), "{layout:?} claimed a block twice");
+
+            assert!(tree.is_used(FIRST + 2), "{layout:?}");
+            assert!(tree.is_used(FIRST + 3), "{layout:?}");
+            assert_eq!(tree.largest_free(), Some(5), "{layout:?}");
+        }
+    }
+}
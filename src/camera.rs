@@ -0,0 +1,117 @@
+mod fly;
+
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+pub use self::fly::{FlyCamera, Movement};
+
+// Just shy of straight up or down, where right and up stop being defined
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+// A camera at `eye` turned by `yaw` around y, then tilted by `pitch`, y up.
+// The aspect ratio is left to whatever gets drawn into, as the window and
+// render targets seldom agree on it, so every method taking one is given it
+// as the target is right then, following the window as it gets resized
+#[derive(Clone, Copy, Debug)]
+pub struct Camera {
+    pub eye: [f32; 3],
+
+    // In radians, looking down +x with both at zero, yaw turning towards +z
+    // and pitch towards +y
+    pub yaw: f32,
+    pub pitch: f32,
+
+    // Vertical field of view in radians
+    pub fov_y: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    // Looking down +x, with about 57° of vertical field of view
+    pub const fn new(eye: [f32; 3]) -> Self {
+        Self {
+            eye,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov_y: 1.0,
+            near: 0.1,
+            far: 500.0,
+        }
+    }
+
+    // Turn towards `target`, as long as it is not right above or below
+    pub fn look_at(&mut self, target: [f32; 3]) {
+        let [x, y, z] = [0, 1, 2].map(|axis| target[axis] - self.eye[axis]);
+        self.yaw = z.atan2(x);
+        self.pitch = y.atan2(x.hypot(z)).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // Turn by as much as given, pitch stopping short of straight up or down
+    pub fn turn(&mut self, yaw: f32, pitch: f32) {
+        self.yaw = (self.yaw + yaw).rem_euclid(TAU);
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // Direction the camera looks in, normalized
+    pub fn forward(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [cos_pitch * cos_yaw, sin_pitch, cos_pitch * sin_yaw]
+    }
+
+    // Column-major view-projection matrix, depth going from 0 to 1
+    pub fn view_proj(&self, aspect: f32) -> [[f32; 4]; 4] {
+        let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+
+        // Right, up and backwards, as rows of the view matrix
+        let [s, u, f] = self.basis();
+
+        let g = 1.0 / (self.fov_y / 2.0).tan();
+        let (near, far) = (self.near, self.far);
+        let depth = far / (near - far);
+
+        let view = [
+            [s[0], s[1], s[2], -dot(s, self.eye)],
+            [u[0], u[1], u[2], -dot(u, self.eye)],
+            [-f[0], -f[1], -f[2], dot(f, self.eye)],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+
+        let proj = [
+            [g / aspect, 0.0, 0.0, 0.0],
+            [0.0, g, 0.0, 0.0],
+            [0.0, 0.0, depth, near * depth],
+            [0.0, 0.0, -1.0, 0.0],
+        ];
+
+        // Product of both, transposed into columns
+        let mut view_proj = [[0.0; 4]; 4];
+        for (column, out) in view_proj.iter_mut().enumerate() {
+            for (row, out) in out.iter_mut().enumerate() {
+                *out = (0..4).map(|k| proj[row][k] * view[k][column]).sum();
+            }
+        }
+
+        view_proj
+    }
+
+    // Direction through the center of the view, and how far right and up
+    // the edges of the view are from it
+    pub fn rays(&self, aspect: f32) -> [[f32; 3]; 3] {
+        let [right, up, forward] = self.basis();
+        let height = (self.fov_y / 2.0).tan();
+        let width = height * aspect;
+        [forward, right.map(|c| c * width), up.map(|c| c * height)]
+    }
+
+    // Right, up and forward, normalized
+    fn basis(&self) -> [[f32; 3]; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+
+        let forward = self.forward();
+        let right = [-sin_yaw, 0.0, cos_yaw];
+        let up = [-sin_pitch * cos_yaw, cos_pitch, -sin_pitch * sin_yaw];
+        [right, up, forward]
+    }
+}
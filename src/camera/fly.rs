@@ -0,0 +1,205 @@
+use std::{sync::Arc, time::Duration};
+
+use winit::{
+    error::ExternalError,
+    event::ElementState,
+    window::{CursorGrabMode, Window},
+};
+
+use super::Camera;
+use crate::input::Action;
+
+// Movement keys held down right now
+#[derive(Clone, Copy, Debug, Default)]
+struct Held {
+    forward: bool,
+    back: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    sprint: bool,
+}
+
+// How far movement actions and the first stick push to move, along and across
+// the view and up, each from -1 to 1, or a bit past it with both at once
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Movement {
+    pub advance: f32,
+    pub strafe: f32,
+    pub rise: f32,
+    pub sprint: bool,
+}
+
+// First person free camera, moving along the view and rising or sinking
+// as the movement actions say, and turning along with the mouse.
+// Looking around takes grabbing the pointer, which hides it away in the window
+#[derive(Debug)]
+pub struct FlyCamera {
+    window: Arc<Window>,
+
+    // In blocks per second
+    pub speed: f32,
+
+    // How many times faster to go while sprinting
+    pub sprint: f32,
+
+    // Radians turned per pixel the mouse moves
+    pub sensitivity: f32,
+
+    // Radians turned per second with a stick all the way out
+    pub turn_speed: f32,
+
+    held: Held,
+
+    // Moving and looking around as gamepad sticks say, x right and y up
+    sticks: [[f32; 2]; 2],
+    grabbed: bool,
+}
+
+impl FlyCamera {
+    pub fn new(window: Arc<Window>) -> Self {
+        Self {
+            window,
+            speed: 10.0,
+            sprint: 4.0,
+            sensitivity: 0.002,
+            turn_speed: 2.5,
+            held: Held::default(),
+            sticks: [[0.0; 2]; 2],
+            grabbed: false,
+        }
+    }
+
+    pub const fn is_grabbed(&self) -> bool {
+        self.grabbed
+    }
+
+    // Hide the pointer and keep it within the window, locked in place
+    // where the platform allows and confined to the window elsewhere
+    pub fn grab(&mut self) -> Result<(), ExternalError> {
+        let window = &self.window;
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))?;
+
+        window.set_cursor_visible(false);
+        self.grabbed = true;
+        Ok(())
+    }
+
+    // Give the pointer back, letting go of every key as well,
+    // as their releases go elsewhere once the window loses focus
+    pub fn release(&mut self) {
+        // Nothing else to fall back to
+        let _ = self.window.set_cursor_grab(CursorGrabMode::None);
+
+        self.window.set_cursor_visible(true);
+        self.held = Held::default();
+        self.grabbed = false;
+    }
+
+    // Keep track of a movement action, returning whether it was one
+    pub fn action(&mut self, action: Action, state: ElementState) -> bool {
+        let held = match action {
+            Action::MoveForward => &mut self.held.forward,
+            Action::MoveBack => &mut self.held.back,
+            Action::MoveLeft => &mut self.held.left,
+            Action::MoveRight => &mut self.held.right,
+            Action::MoveUp => &mut self.held.up,
+            Action::MoveDown => &mut self.held.down,
+            Action::Sprint => &mut self.held.sprint,
+            _ => return false,
+        };
+
+        *held = state.is_pressed();
+        true
+    }
+
+    // Turn along with the mouse, given how far it moved as in `DeviceEvent::MouseMotion`,
+    // grabbed or not, as replays look around without it
+    pub fn look(&self, camera: &mut Camera, [dx, dy]: [f64; 2]) {
+        let turn = |delta: f64| delta as f32 * self.sensitivity;
+        camera.turn(turn(dx), -turn(dy));
+    }
+
+    // Move along the first stick and look around along the second from now on,
+    // as given by `Gamepads::sticks`
+    pub fn steer(&mut self, sticks: [[f32; 2]; 2]) {
+        self.sticks = sticks;
+    }
+
+    // Movement as held and as the first stick says right now
+    pub fn movement(&self) -> Movement {
+        let [strafe, advance] = self.sticks[0];
+        let axis = |plus: bool, minus: bool| plus as i32 as f32 - minus as i32 as f32;
+        let Held {
+            forward,
+            back,
+            left,
+            right,
+            up,
+            down,
+            sprint,
+        } = self.held;
+
+        Movement {
+            advance: axis(forward, back) + advance,
+            strafe: axis(right, left) + strafe,
+            rise: axis(up, down),
+            sprint,
+        }
+    }
+
+    // Whether held actions or sticks would move or turn the camera on `update`
+    pub fn is_moving(&self) -> bool {
+        let Movement {
+            advance,
+            strafe,
+            rise,
+            ..
+        } = self.movement();
+
+        [advance, strafe, rise] != [0.0; 3] || self.sticks[1] != [0.0; 2]
+    }
+
+    // Turn for as long as `elapsed` as the second stick says, slower with it partway out
+    pub fn turn(&self, camera: &mut Camera, elapsed: Duration) {
+        let [yaw, pitch] = self.sticks[1];
+        let turn = self.turn_speed * elapsed.as_secs_f32();
+        camera.turn(yaw * turn, pitch * turn);
+    }
+
+    // Move and turn for as long as `elapsed`, as fast in every direction as straight ahead,
+    // and slower with sticks partway out
+    pub fn update(&self, camera: &mut Camera, elapsed: Duration) {
+        self.turn(camera, elapsed);
+
+        let Movement {
+            advance,
+            strafe,
+            rise,
+            sprint,
+        } = self.movement();
+
+        let (sin_yaw, cos_yaw) = camera.yaw.sin_cos();
+        let ahead = camera.forward().map(|c| c * advance);
+        let aside = [-sin_yaw, 0.0, cos_yaw].map(|c| c * strafe);
+
+        let direction = [
+            ahead[0] + aside[0],
+            ahead[1] + aside[1] + rise,
+            ahead[2] + aside[2],
+        ];
+        let length = direction.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+        if length > 0.0 {
+            let speed = self.speed * if sprint { self.sprint } else { 1.0 };
+            let distance = speed * elapsed.as_secs_f32() / length.max(1.0);
+
+            for (eye, c) in camera.eye.iter_mut().zip(direction) {
+                *eye += c * distance;
+            }
+        }
+    }
+}
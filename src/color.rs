@@ -0,0 +1,56 @@
+// Colors in linear light, the only space anything is lit, blended or filtered in.
+// Colors authored as seen, like image texels and palette tints, are sRGB-encoded
+// and must come in through `from_srgb` or `from_srgb8`, so the two don't get mixed up.
+// Frames are only encoded back at the very end, by the surface or the tonemap pass
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinearColor([f32; 3]);
+
+impl LinearColor {
+    pub const BLACK: Self = Self([0.0; 3]);
+    pub const WHITE: Self = Self([1.0; 3]);
+
+    // Out of components already linear, which may go past 1
+    pub const fn new(rgb: [f32; 3]) -> Self {
+        Self(rgb)
+    }
+
+    pub fn from_srgb(rgb: [f32; 3]) -> Self {
+        Self(rgb.map(srgb_to_linear))
+    }
+
+    pub fn from_srgb8(rgb: [u8; 3]) -> Self {
+        Self::from_srgb(rgb.map(|c| c as f32 / 255.0))
+    }
+
+    // Components as shaders take them
+    pub const fn to_array(self) -> [f32; 3] {
+        self.0
+    }
+
+    // Clamped to what can be encoded
+    pub fn to_srgb(self) -> [f32; 3] {
+        self.0.map(|c| linear_to_srgb(c.clamp(0.0, 1.0)))
+    }
+
+    pub fn to_srgb8(self) -> [u8; 3] {
+        self.to_srgb().map(|c| (c * 255.0).round() as u8)
+    }
+}
+
+// As sRGB texture formats decode texels, and `linear` in the quad shader
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// As `srgb` in the tonemap shader
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
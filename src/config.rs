@@ -0,0 +1,215 @@
+use std::{collections::BTreeMap, error::Error, fmt::Display, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use wgpu::PresentMode;
+
+use crate::input::{Action, Bindings, Input};
+
+// How frames are presented, by the names written in the file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Present {
+    // Whatever waits for vertical sync on the platform
+    #[default]
+    Vsync,
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl Present {
+    pub const fn mode(self) -> PresentMode {
+        match self {
+            Self::Vsync => PresentMode::AutoVsync,
+            Self::Fifo => PresentMode::Fifo,
+            Self::Mailbox => PresentMode::Mailbox,
+            Self::Immediate => PresentMode::Immediate,
+        }
+    }
+}
+
+// When frames are drawn, by the names written in the file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Redraw {
+    // One after the other, as fast as presenting lets them
+    #[default]
+    Continuous,
+
+    // Only after input, window events or anything still moving or loading,
+    // sleeping in between, for the game to sit idle without keeping a core busy
+    OnDemand,
+}
+
+// Settings kept from run to run, as in
+//
+//     window = [1280, 720]
+//     present = "mailbox"
+//     redraw = "on_demand"
+//     render_distance = 8
+//     upload_budget = 2048
+//     fov = 70.0
+//     msaa = 4
+//
+//     [bindings]
+//     move_forward = ["w", "up"]
+//
+// Anything left out takes its default, and bindings left out keep theirs
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Inner size, in physical pixels
+    pub window: [u32; 2],
+    pub present: Present,
+    pub redraw: Redraw,
+
+    // In chunks, streaming nothing in if not set
+    pub render_distance: Option<i32>,
+
+    // Kibibytes of quads written to the GPU a frame, the rest left for the next ones
+    pub upload_budget: usize,
+
+    // Vertical field of view, in degrees
+    pub fov: f32,
+
+    // Samples per pixel when multisampling, as many as supported up to this
+    pub msaa: u32,
+
+    // Inputs bound to each action, by their names in bindings files
+    pub bindings: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            window: [854, 480],
+            present: Present::default(),
+            redraw: Redraw::default(),
+            render_distance: None,
+            upload_budget: 4096,
+            fov: 57.3,
+            msaa: 4,
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    // The defaults if there is no such file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(ConfigError::Io(err)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text).map_err(ConfigError::Parse)?;
+
+        // Caught now rather than once bindings are asked for
+        config.key_bindings()?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        let text = toml::to_string_pretty(self).map_err(ConfigError::Write)?;
+        fs::write(path, text).map_err(ConfigError::Io)
+    }
+
+    // The default bindings, with the actions in the file bound as it says instead
+    pub fn key_bindings(&self) -> Result<Bindings, ConfigError> {
+        let mut bindings = Bindings::default();
+
+        for (name, inputs) in &self.bindings {
+            let action = Action::by_name(name);
+            let action = action.ok_or_else(|| ConfigError::Action(name.clone()))?;
+
+            let inputs = inputs.iter().map(|name| {
+                let input = Input::by_name(name);
+                input.ok_or_else(|| ConfigError::Input(name.clone()))
+            });
+
+            bindings.rebind(action, &inputs.collect::<Result<Vec<_>, _>>()?);
+        }
+
+        Ok(bindings)
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Write(toml::ser::Error),
+
+    // Bound in the file, but not known
+    Action(String),
+    Input(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot access config: {err}"),
+            Self::Parse(err) => write!(f, "cannot read config: {err}"),
+            Self::Write(err) => write!(f, "cannot write config: {err}"),
+            Self::Action(name) => write!(f, "config binds unknown action {name}"),
+            Self::Input(name) => write!(f, "config binds unknown input {name}"),
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+            Self::Write(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+
+    #[test]
+    fn missing_settings_take_defaults() {
+        let config = Config::parse("fov = 90.0\n[bindings]\nmove_up = [\"e\"]").unwrap();
+        assert_eq!(config.fov, 90.0);
+        assert_eq!(config.window, Config::default().window);
+
+        let bindings = config.key_bindings().unwrap();
+        let key = |code| bindings.action(Input::Key(code));
+        assert_eq!(key(KeyCode::KeyE), Some(Action::MoveUp));
+        assert_eq!(key(KeyCode::Space), None);
+        assert_eq!(key(KeyCode::KeyW), Some(Action::MoveForward));
+    }
+
+    #[test]
+    fn configs_round_trip() {
+        let mut config = Config {
+            present: Present::Mailbox,
+            redraw: Redraw::OnDemand,
+            render_distance: Some(8),
+            ..Config::default()
+        };
+
+        let inputs = vec!["mouse_left".into(), "f".into()];
+        config.bindings.insert("break".into(), inputs);
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(Config::parse(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn unknown_names_are_refused() {
+        let config = Config::parse("[bindings]\njump = [\"space\"]");
+        assert!(matches!(config, Err(ConfigError::Action(name)) if name == "jump"));
+
+        assert!(Config::parse("vsync = true").is_err());
+    }
+}
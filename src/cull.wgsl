@@ -0,0 +1,95 @@
+// Zeroes the instance count of chunk draws hidden behind last frame's depth pyramid,
+// leaving those of the rest as they were
+
+struct Cull {
+    // Of last frame, which the pyramid was built from
+    view_proj: mat4x4<f32>,
+
+    // Chunks packed
+    count: u32,
+}
+
+var<push_constant> cull: Cull;
+
+// As in quad.wgsl
+struct Chunk {
+    origin: vec3<f32>,
+    first: u32,
+    ends: array<u32, 6>,
+    palette: u32,
+    lod: u32,
+}
+
+// As in `DrawIndirectArgs`
+struct DrawArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<storage, read> chunks: array<Chunk>;
+@group(0) @binding(1) var<storage, read_write> draws: array<DrawArgs>;
+@group(0) @binding(2) var pyramid: texture_2d<f32>;
+
+const CHUNK_SIZE = 32.0;
+
+// Whether any part of the chunk box may be in front of the depth it covers
+fn is_visible(origin: vec3<f32>) -> bool {
+    var low = vec3(1e30);
+    var high = vec3(-1e30);
+
+    for (var corner = 0u; corner < 8u; corner++) {
+        let step = vec3(corner & 1u, (corner >> 1u) & 1u, corner >> 2u);
+        let clip = cull.view_proj * vec4(origin + vec3<f32>(step) * CHUNK_SIZE, 1.0);
+
+        // Crossing the near plane, too close to tell
+        if clip.w <= 0.0 {
+            return true;
+        }
+
+        let ndc = clip.xyz / clip.w;
+        low = min(low, ndc);
+        high = max(high, ndc);
+    }
+
+    // Off screen last frame, so nothing is known about what hides it
+    if any(low.xy > vec2(1.0)) || any(high.xy < vec2(-1.0)) {
+        return true;
+    }
+
+    // Screen rectangle covered, y going down
+    let top_left = clamp(vec2(low.x, -high.y) * 0.5 + 0.5, vec2(0.0), vec2(1.0));
+    let bottom_right = clamp(vec2(high.x, -low.y) * 0.5 + 0.5, vec2(0.0), vec2(1.0));
+
+    // Coarsest level where the rectangle spans at most 2x2 texels
+    let extent = (bottom_right - top_left) * vec2<f32>(textureDimensions(pyramid));
+    let levels = i32(textureNumLevels(pyramid));
+    let texels = max(max(extent.x, extent.y), 1.0);
+    let level = clamp(i32(ceil(log2(texels))), 0, levels - 1);
+
+    let size = textureDimensions(pyramid, level);
+    let first = min(vec2<u32>(top_left * vec2<f32>(size)), size - 1u);
+    let last = min(vec2<u32>(bottom_right * vec2<f32>(size)), size - 1u);
+
+    let farthest = max(
+        max(textureLoad(pyramid, first, level).r, textureLoad(pyramid, last, level).r),
+        max(
+            textureLoad(pyramid, vec2(first.x, last.y), level).r,
+            textureLoad(pyramid, vec2(last.x, first.y), level).r,
+        ),
+    );
+
+    return low.z <= farthest;
+}
+
+@compute @workgroup_size(64)
+fn cull_chunks(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= cull.count {
+        return;
+    }
+
+    let visible = is_visible(chunks[index].origin);
+    draws[index].instance_count = select(0u, draws[index].instance_count, visible);
+}
@@ -0,0 +1,231 @@
+// Scenes made by hand, and walkthroughs of the meshers and the quad buddy on them,
+// for the demo mode of the binaries to show what every step leaves
+
+use wgpu::BufferUsages;
+
+use crate::{
+    buddy::Buddy,
+    geometry,
+    gfx::Gfx,
+    mesh::{
+        self, block_kind,
+        debug::{self, CLEAN_SCREEN},
+        geometry::Geometry,
+        greedy,
+        palette::{self, Palette},
+        quad_ref, remesh,
+        stats::MeshStats,
+        upload::Packed,
+        with_state, Axis, Borders, Chunk, Facing, Half, Mesh, Mesher, QuadLayout, QuadRef, AIR,
+        GLASS, GRASS, LOG, SLAB, WATER,
+    },
+};
+
+// A stone hill with a grass layer on top
+pub fn hill() -> Chunk {
+    let mut chunk = [[[AIR; 32]; 32]; 32];
+    for z in 0..32 {
+        for x in 0..32 {
+            let (dx, dz) = (x as i32 - 16, z as i32 - 16);
+            let height = 20 - (dx * dx + dz * dz) / 24;
+
+            for y in 0..height.max(1) as usize {
+                chunk[z][y][x] = 1;
+            }
+
+            chunk[z][height.max(1) as usize][x] = 2;
+        }
+    }
+
+    chunk
+}
+
+// Water in a basin walled off by glass on one side, with a ledge of slabs
+// along its far shore, a log fallen next to it and another one standing
+pub fn pond() -> Chunk {
+    let mut chunk = [[[AIR; 32]; 32]; 32];
+    for z in 4..28 {
+        for x in 4..28 {
+            for y in 0..3 {
+                chunk[z][y][x] = WATER;
+            }
+        }
+    }
+
+    for x in 4..28 {
+        for y in 0..6 {
+            chunk[3][y][x] = GLASS;
+        }
+    }
+
+    for x in 4..28 {
+        chunk[28][0][x] = 1;
+        chunk[28][1][x] = 1;
+        chunk[28][2][x] = with_state(SLAB, Axis::Y, Half::Bottom);
+    }
+
+    for x in 6..16 {
+        chunk[29][0][x] = with_state(LOG, Axis::X, Half::Whole);
+    }
+
+    for y in 0..5 {
+        chunk[30][y][24] = LOG;
+    }
+
+    chunk
+}
+
+// Greedy meshing a few slices of quads, along rows first and then across slices,
+// and `mesher` meshing the hill, against culling alone and greedy meshing with
+// columns merged, logging what every step leaves. Returns the mesh of the hill
+pub fn greedy(name: &str, mesher: &dyn Mesher) -> Mesh {
+    #[rustfmt::skip]
+    let plane = vec![
+        quad_ref(3, (0, 0, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 0, 0), 0, 0, 4, 0),
+        quad_ref(4, (6, 0, 0), 0, 0, 1, 0),
+
+        quad_ref(3, (0, 1, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 1, 0), 0, 0, 4, 0),
+        quad_ref(4, (6, 1, 0), 0, 0, 1, 0),
+
+        quad_ref(3, (0, 2, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 2, 0), 0, 0, 0, 0),
+        quad_ref(1, (4, 2, 0), 0, 0, 1, 0),
+        quad_ref(4, (6, 2, 0), 0, 0, 1, 0),
+
+        quad_ref(3, (0, 3, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 3, 0), 0, 0, 1, 0),
+        quad_ref(2, (5, 3, 0), 0, 0, 2, 0),
+
+        quad_ref(3, (0, 4, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 4, 0), 0, 0, 2, 0),
+        quad_ref(2, (4, 4, 0), 0, 0, 3, 0),
+
+        quad_ref(3, (0, 5, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 5, 0), 0, 0, 1, 0),
+        quad_ref(1, (5, 5, 0), 0, 0, 2, 0),
+
+        quad_ref(3, (0, 6, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 6, 0), 0, 0, 2, 0),
+        quad_ref(2, (4, 6, 0), 0, 0, 3, 0),
+
+        quad_ref(3, (0, 7, 0), 0, 0, 0, 0),
+        quad_ref(1, (1, 7, 0), 0, 0, 2, 0),
+        quad_ref(2, (4, 7, 0), 0, 0, 3, 0),
+    ];
+
+    // The same slice seen from the front, and two slices seen from the right
+    let depth = 3 << QuadLayout::DEFAULT.z_shift();
+    let deeper_plane = plane.iter().map(|&qref| qref | depth);
+
+    let mut mesh = Mesh::default();
+    mesh[Facing::PosZ as usize] = plane.clone();
+    mesh[Facing::PosX as usize] = plane.iter().copied().chain(deeper_plane).collect();
+
+    let rects: usize = mesh.iter().map(Vec::len).sum();
+    let mut screen = CLEAN_SCREEN;
+    debug::render(&mesh[Facing::PosZ as usize], &mut screen);
+    let slice = debug::display(&screen);
+    tracing::info!("1D greedy meshing ({rects} rects)\n{slice}");
+
+    greedy::greedy3d(&mut mesh);
+
+    for facing in Facing::ALL {
+        let quads = &mesh[facing as usize];
+        if !quads.is_empty() {
+            tracing::info!("{:?}: {} rects", facing, quads.len());
+        }
+    }
+
+    let rects: usize = mesh.iter().map(Vec::len).sum();
+    let mut screen = CLEAN_SCREEN;
+    debug::render(&mesh[Facing::PosZ as usize], &mut screen);
+    let slice = debug::display(&screen);
+    tracing::info!("3D greedy meshing ({rects} rects)\n{slice}");
+
+    let chunk = hill();
+
+    let culled = mesh::cull(&chunk);
+    let mesh = mesher.mesh(&chunk);
+    let stats = MeshStats::new(&culled, &mesh);
+    let columns = greedy::Greedy {
+        merge_columns: true,
+        ..greedy::Greedy::default()
+    };
+
+    let (columns, column_stats) = columns.mesh_with_stats(&chunk);
+
+    tracing::info!("chunk meshing with {name} (culled -> merged -> greedy with columns)");
+    for facing in Facing::ALL {
+        let before = culled[facing as usize].len();
+        let after = mesh[facing as usize].len();
+        let columns = columns[facing as usize].len();
+        tracing::info!("{facing:?}: {before} -> {after} -> {columns} rects");
+    }
+
+    tracing::info!("{}", stats.to_string().trim_end());
+    tracing::info!("with columns: {}", column_stats.to_string().trim_end());
+    mesh
+}
+
+// The hill dug into, rewriting the few quads that changed rather than the whole mesh,
+// with its mesh loaded as the plain triangles shadow and picking passes draw
+pub fn dig_hill(gfx: &Gfx, quad_buddy: &mut Buddy<QuadRef>) {
+    let mut chunk = hill();
+    let mut mesh = greedy::mesh_chunk(&chunk);
+    let mut packed = Packed::new(&mesh);
+    let (handle, _) = quad_buddy.load(gfx, &packed.quads).unwrap();
+
+    chunk[16][20][16] = AIR;
+    remesh::remesh_block(&mut mesh, &chunk, &Borders::NONE, (16, 20, 16));
+    let ranges = packed.update(&mesh, 16);
+    assert!(packed.quads.len() <= quad_buddy.len(&handle));
+
+    for range in &ranges {
+        quad_buddy.write_at(gfx, &handle, range.start, &packed.quads[range.clone()]);
+    }
+
+    let rewritten: usize = ranges.iter().map(|range| range.len()).sum();
+    tracing::info!("{rewritten} of {} quads rewritten", packed.quads.len());
+    quad_buddy.wait_uploads(gfx);
+    quad_buddy.free(handle);
+
+    let mut vertex_buddy = Buddy::with_usage(gfx, 1 << 20, 8, BufferUsages::VERTEX);
+    let mut index_buddy = Buddy::with_usage(gfx, 1 << 20, 8, BufferUsages::INDEX);
+    let geometry = Geometry::new(&mesh);
+    let blocks = geometry::load(gfx, &mut vertex_buddy, &mut index_buddy, &geometry).unwrap();
+
+    let (vertices, indices) = (geometry.vertices.len(), geometry.indices.len());
+    tracing::info!("{vertices} vertices, {indices} indices");
+
+    vertex_buddy.wait_uploads(gfx);
+    index_buddy.wait_uploads(gfx);
+    vertex_buddy.free(blocks.vertices);
+    index_buddy.free(blocks.indices);
+}
+
+// The hill's palette loaded into a buffer of its own, then recolored for another
+// biome, rewriting its few palette entries and none of its quads
+pub fn recolor_hill(gfx: &Gfx) {
+    let mut palette_buddy = Buddy::<u32>::new(gfx, 1 << 16, 4);
+    let chunk = hill();
+    let mut mesh = greedy::mesh_chunk(&chunk);
+    let palette = Palette::index(&chunk, &mut mesh);
+
+    let plains = |block| match block {
+        GRASS => palette::entry(GRASS as u8, [96, 160, 64]),
+        _ => palette::entry(block_kind(block) as u8, [255; 3]),
+    };
+
+    let desert = |block| match block {
+        GRASS => palette::entry(GRASS as u8, [176, 160, 96]),
+        _ => plains(block),
+    };
+
+    let (handle, _) = palette_buddy.load(gfx, &palette.entries(plains)).unwrap();
+    palette_buddy.write(gfx, &handle, &palette.entries(desert));
+    tracing::info!("{} palette entries recolored", palette.len());
+    palette_buddy.wait_uploads(gfx);
+    palette_buddy.free(handle);
+}
@@ -0,0 +1,349 @@
+// Whatever is in the world but blocks, dropped items and mobs alike. Entities are
+// no more than ids, with every component kept in a store of its own indexed by them,
+// so systems go over the stores they care about and nothing else
+
+use std::time::Duration;
+
+use crate::renderer::EntityDraw;
+
+// Blocks per second squared, pulling down whatever falls
+const GRAVITY: f32 = 24.0;
+
+// Fastest anything falls, in blocks per second
+const TERMINAL_VELOCITY: f32 = 40.0;
+
+// Share of its speed along the ground something resting on it loses every second
+const FRICTION: f32 = 6.0;
+
+// Steps caught up on at most, so a long stall is not followed by a longer one
+const MAX_STEPS: u32 = 8;
+
+// An entity as handed out by `Entities::spawn`, good until despawned. Its slot may
+// be handed out again afterwards, but to an entity of a later generation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    const fn new(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+}
+
+// Components of a kind, at most one per entity. Stale entities find nothing,
+// even where their slot went to another entity
+#[derive(Clone, Debug)]
+pub struct Components<T> {
+    slots: Vec<Option<(u32, T)>>,
+}
+
+impl<T> Components<T> {
+    pub fn new() -> Self {
+        Self { slots: Vec::new() }
+    }
+
+    // Give `entity` its component, returning the one it had before
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let index = entity.index as usize;
+        if self.slots.len() <= index {
+            self.slots.resize_with(index + 1, || None);
+        }
+
+        let old = self.slots[index].replace((entity.generation, component));
+        old.and_then(|(generation, old)| (generation == entity.generation).then_some(old))
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let slot = self.slots.get_mut(entity.index as usize)?;
+        match slot {
+            Some((generation, _)) if *generation == entity.generation => {
+                slot.take().map(|(_, component)| component)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index as usize)? {
+            Some((generation, component)) if *generation == entity.generation => Some(component),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        let slots = self.slots.iter().enumerate();
+        slots.filter_map(|(index, slot)| {
+            let (generation, component) = slot.as_ref()?;
+            Some((Entity::new(index as u32, *generation), component))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        let slots = self.slots.iter_mut().enumerate();
+        slots.filter_map(|(index, slot)| {
+            let (generation, component) = slot.as_mut()?;
+            Some((Entity::new(index as u32, *generation), component))
+        })
+    }
+}
+
+impl<T> Default for Components<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Every entity, and the components they have. Positions are in world coordinates,
+// where their models stand, and velocities in blocks per second
+#[derive(Clone, Debug, Default)]
+pub struct Entities {
+    // Generation of every slot, and whether an entity is in it
+    slots: Vec<(u32, bool)>,
+    free: Vec<u32>,
+
+    pub positions: Components<[f32; 3]>,
+    pub velocities: Components<[f32; 3]>,
+
+    // Model drawn at its position, by the id it was loaded into the renderer with
+    pub models: Components<u32>,
+
+    // Pulled down by gravity onto the blocks under it
+    pub falling: Components<()>,
+
+    // Seconds left until despawned
+    pub lifetimes: Components<f32>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A new entity with no components at all
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.1 = true;
+            return Entity::new(index, slot.0);
+        }
+
+        self.slots.push((0, true));
+        Entity::new(self.slots.len() as u32 - 1, 0)
+    }
+
+    // Take out `entity` along with its components, false if it was not alive
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        self.positions.remove(entity);
+        self.velocities.remove(entity);
+        self.models.remove(entity);
+        self.falling.remove(entity);
+        self.lifetimes.remove(entity);
+
+        let slot = &mut self.slots[entity.index as usize];
+        *slot = (slot.0.wrapping_add(1), false);
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let slot = self.slots.get(entity.index as usize);
+        slot == Some(&(entity.generation, true))
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Move everything along by `dt` seconds, blocks for which `solid` holds
+    // stopping it. Entities are as small as points, moved an axis at a time
+    // so they slide along whatever they run into, and come to rest on top of it
+    pub fn step(&mut self, dt: f32, solid: impl Fn([i32; 3]) -> bool) {
+        let expired: Vec<_> = self
+            .lifetimes
+            .iter_mut()
+            .filter_map(|(entity, lifetime)| {
+                *lifetime -= dt;
+                (*lifetime <= 0.0).then_some(entity)
+            })
+            .collect();
+
+        for entity in expired {
+            self.despawn(entity);
+        }
+
+        for (entity, velocity) in self.velocities.iter_mut() {
+            let Some(position) = self.positions.get_mut(entity) else {
+                continue;
+            };
+
+            if self.falling.contains(entity) {
+                velocity[1] = (velocity[1] - GRAVITY * dt).max(-TERMINAL_VELOCITY);
+            }
+
+            let mut grounded = false;
+            for axis in [1, 0, 2] {
+                let mut moved = *position;
+                moved[axis] += velocity[axis] * dt;
+
+                if !solid(moved.map(|c| c.floor() as i32)) {
+                    *position = moved;
+                    continue;
+                }
+
+                // Landing right on top of the block, rather than short of it
+                if axis == 1 && velocity[1] < 0.0 {
+                    position[1] = moved[1].floor() + 1.0;
+                    grounded = true;
+                }
+
+                velocity[axis] = 0.0;
+            }
+
+            if grounded {
+                let kept = (1.0 - FRICTION * dt).max(0.0);
+                velocity[0] *= kept;
+                velocity[2] *= kept;
+            }
+        }
+    }
+
+    // Every entity with a model, where it is
+    pub fn draws(&self) -> Vec<EntityDraw> {
+        let models = self.models.iter();
+        models
+            .filter_map(|(entity, &model)| {
+                let position = *self.positions.get(entity)?;
+                Some(EntityDraw { model, position })
+            })
+            .collect()
+    }
+}
+
+// Time cut into steps of the same length, for updates to come out the same
+// however fast frames are drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedStep {
+    step: Duration,
+    accumulated: Duration,
+}
+
+impl FixedStep {
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    // Steps to take after `elapsed` more time went by, the rest carried over
+    // to the next call. Time past `MAX_STEPS` steps is dropped
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulated += elapsed;
+        let steps = (self.accumulated.as_nanos() / self.step.as_nanos()) as u32;
+        self.accumulated -= self.step * steps;
+
+        if steps > MAX_STEPS {
+            self.accumulated = Duration::ZERO;
+        }
+
+        steps.min(MAX_STEPS)
+    }
+
+    // Give `steps` taken from `advance` back, to be taken on a later call
+    pub fn defer(&mut self, steps: u32) {
+        self.accumulated += self.step * steps;
+    }
+
+    pub const fn step(&self) -> Duration {
+        self.step
+    }
+
+    // Length of a step, in seconds
+    pub fn seconds(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn despawned_entities_stay_dead() {
+        let mut entities = Entities::new();
+        let first = entities.spawn();
+        entities.positions.insert(first, [1.0; 3]);
+
+        assert!(entities.despawn(first));
+        assert!(!entities.despawn(first));
+
+        let second = entities.spawn();
+        assert_ne!(first, second);
+        assert!(!entities.is_alive(first));
+        assert_eq!(entities.positions.get(first), None);
+        assert_eq!(entities.positions.get(second), None);
+        assert_eq!(entities.len(), 1);
+    }
+
+    #[test]
+    fn things_fall_onto_the_ground() {
+        let mut entities = Entities::new();
+        let item = entities.spawn();
+        entities.positions.insert(item, [0.5, 10.0, 0.5]);
+        entities.velocities.insert(item, [6.0, 0.0, 0.0]);
+        entities.falling.insert(item, ());
+
+        // Solid below y = 4, and past x = 3, run into before landing
+        let solid = |[x, y, _]: [i32; 3]| y < 4 || x >= 3;
+        for _ in 0..120 {
+            entities.step(1.0 / 60.0, solid);
+        }
+
+        let [x, y, _] = *entities.positions.get(item).unwrap();
+        assert_eq!(y, 4.0);
+        assert!(x < 3.0);
+        assert_eq!(entities.velocities.get(item), Some(&[0.0; 3]));
+    }
+
+    #[test]
+    fn lifetimes_run_out() {
+        let mut entities = Entities::new();
+        let item = entities.spawn();
+        entities.lifetimes.insert(item, 0.5);
+
+        entities.step(0.25, |_| false);
+        assert!(entities.is_alive(item));
+        entities.step(0.25, |_| false);
+        assert!(!entities.is_alive(item));
+    }
+
+    #[test]
+    fn fixed_steps_carry_time_over() {
+        let mut fixed = FixedStep::new(Duration::from_millis(10));
+        assert_eq!(fixed.advance(Duration::from_millis(25)), 2);
+        assert_eq!(fixed.advance(Duration::from_millis(5)), 1);
+        assert_eq!(fixed.advance(Duration::from_secs(10)), MAX_STEPS);
+        assert_eq!(fixed.advance(Duration::ZERO), 0);
+    }
+}
@@ -0,0 +1,79 @@
+// Edges of the scene blurred along their direction, drawn as a single triangle
+// covering the frame. Contrast is measured on tonemapped brightness,
+// so edges against bright skies don't get blurred more than the rest
+
+@group(0) @binding(0) var scene: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+
+// Edges fainter than this, relative to the brightest around, are left alone
+const EDGE_THRESHOLD = 0.125;
+const EDGE_THRESHOLD_MIN = 0.0312;
+
+// Keeps the direction from blowing up where there is hardly any contrast
+const REDUCE_MUL = 0.125;
+const REDUCE_MIN = 0.0078125;
+
+// Farthest to blur along an edge, in pixels
+const SPAN_MAX = 8.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> VertexOutput {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    let position = vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+    return VertexOutput(position, uv);
+}
+
+fn color(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(scene, scene_sampler, uv, 0.0).rgb;
+}
+
+// Brightness squeezed below 1, as it would be once tonemapped
+fn luma(color: vec3<f32>) -> f32 {
+    let brightness = dot(color, vec3(0.299, 0.587, 0.114));
+    return brightness / (1.0 + brightness);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(scene));
+    let center = color(in.uv);
+
+    let m = luma(center);
+    let nw = luma(color(in.uv + vec2(-1.0, -1.0) * texel));
+    let ne = luma(color(in.uv + vec2(1.0, -1.0) * texel));
+    let sw = luma(color(in.uv + vec2(-1.0, 1.0) * texel));
+    let se = luma(color(in.uv + vec2(1.0, 1.0) * texel));
+
+    let lowest = min(m, min(min(nw, ne), min(sw, se)));
+    let highest = max(m, max(max(nw, ne), max(sw, se)));
+
+    if highest - lowest < max(EDGE_THRESHOLD_MIN, highest * EDGE_THRESHOLD) {
+        return vec4(center, 1.0);
+    }
+
+    // Across the steepest change in brightness, so along the edge
+    var direction = vec2(-((nw + ne) - (sw + se)), (nw + sw) - (ne + se));
+    let reduce = max((nw + ne + sw + se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(direction.x), abs(direction.y)) + reduce);
+    direction = clamp(direction * scale, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    // Close taps along the edge, then farther ones too unless they overshoot
+    let near = 0.5 * (
+        color(in.uv + direction * (1.0 / 3.0 - 0.5)) +
+        color(in.uv + direction * (2.0 / 3.0 - 0.5))
+    );
+
+    let far = near * 0.5 + 0.25 * (
+        color(in.uv - direction * 0.5) +
+        color(in.uv + direction * 0.5)
+    );
+
+    let far_luma = luma(far);
+    let overshoots = far_luma < lowest || far_luma > highest;
+    return vec4(select(far, near, overshoots), 1.0);
+}
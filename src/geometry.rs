@@ -0,0 +1,50 @@
+use std::mem;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{vertex_attr_array, BufferAddress, VertexAttribute, VertexBufferLayout, VertexStepMode};
+
+use crate::{
+    buddy::{Buddy, Handle},
+    gfx::Gfx,
+    mesh::geometry::{Geometry, Vertex},
+};
+
+// The mesher forbids unsafe code, so vertices are vouched for here instead.
+// They are all 4-byte fields, leaving no padding in between
+unsafe impl Zeroable for Vertex {}
+unsafe impl Pod for Vertex {}
+
+const ATTRIBUTES: [VertexAttribute; 4] =
+    vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Uint32];
+
+// Position, normal, texture coordinates and material at locations 0 to 3
+pub const VERTEX_LAYOUT: VertexBufferLayout<'static> = VertexBufferLayout {
+    array_stride: mem::size_of::<Vertex>() as BufferAddress,
+    step_mode: VertexStepMode::Vertex,
+    attributes: &ATTRIBUTES,
+};
+
+// Vertices and indices of a geometry, each in a block of its own
+#[derive(Debug)]
+pub struct Blocks {
+    pub vertices: Handle<Vertex>,
+    pub indices: Handle<u32>,
+}
+
+// Upload a geometry, or nothing at all if either buddy is full.
+// Indices count from the start of the vertex block, so draw with its offset as base vertex
+pub fn load(
+    gfx: &Gfx,
+    vertex_buddy: &mut Buddy<Vertex>,
+    index_buddy: &mut Buddy<u32>,
+    geometry: &Geometry,
+) -> Option<Blocks> {
+    let (vertices, _) = vertex_buddy.load(gfx, &geometry.vertices)?;
+
+    let Some((indices, _)) = index_buddy.load(gfx, &geometry.indices) else {
+        vertex_buddy.free(vertices);
+        return None;
+    };
+
+    Some(Blocks { vertices, indices })
+}
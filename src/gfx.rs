@@ -1,59 +1,173 @@
-use std::sync::Arc;
+mod adapter;
+mod bindings;
+mod cache;
+mod capture;
+mod graph;
+mod memory;
+mod profiler;
+mod push;
+mod resolution;
+mod target;
+mod timing;
+mod window;
+
+use std::{
+    cell::{Cell, Ref, RefCell},
+    error::Error,
+    fmt::Display,
+    io::Write,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use wgpu::{
-    Device, DeviceDescriptor, Features, Instance, Limits, PowerPreference, PresentMode, Queue,
-    RequestAdapterOptions, Surface, SurfaceCapabilities, SurfaceConfiguration, TextureUsages,
+    Adapter, AdapterInfo, Backends, BindGroup, BindGroupDescriptor, BindGroupEntry,
+    BindGroupLayout, BindGroupLayoutEntry, BindingResource, CompositeAlphaMode, ComputePipeline,
+    CreateSurfaceError, Device, DeviceDescriptor, Extent3d, Features, Instance, Limits,
+    MultisampleState, PipelineLayout, PowerPreference, PresentMode, PushConstantRange, Queue,
+    RenderPipeline, RequestAdapterOptions, RequestDeviceError, ShaderModule, Surface,
+    SurfaceCapabilities, SurfaceConfiguration, SurfaceError, SurfaceTexture, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView,
+    TextureViewDescriptor,
 };
 use winit::{dpi::PhysicalSize, window::Window};
 
+pub use self::{
+    adapter::{AdapterChoice, AdapterListing},
+    bindings::Bindings,
+    cache::RenderState,
+    capture::{CaptureError, FrameRecorder},
+    graph::{ComputeNode, FrameTargets, Graph, Pass, RenderNode, Slot, Transient, Views},
+    memory::MemoryReport,
+    profiler::GpuProfiler,
+    push::{PushConstants, PushConstantsError, Pushed},
+    resolution::DynamicResolution,
+    target::RenderTarget,
+    timing::{FrameTimes, Summary},
+    window::WindowSurface,
+};
+use self::{cache::Cache, graph::PooledTexture};
+
+// Features asked for, though any the adapter lacks are done without
+const WANTED_FEATURES: Features = Features::empty()
+    .union(Features::PUSH_CONSTANTS)
+    .union(Features::POLYGON_MODE_LINE)
+    .union(Features::MULTI_DRAW_INDIRECT)
+    .union(Features::INDIRECT_FIRST_INSTANCE)
+    .union(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+    .union(Features::TIMESTAMP_QUERY);
+
+const WANTED_PUSH_CONSTANT_SIZE: u32 = 128;
+
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+// Scenes are drawn with room for more than the frame can show, see `hdr_targets`
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+// Smooths voxel edges well enough, and is the only count besides 1 WebGPU guarantees
+const DEFAULT_SAMPLE_COUNT: u32 = 4;
+
 pub struct Gfx<'win> {
-    pub surface: Surface<'win>,
+    // Unless headless, in which case frames go into `offscreen`,
+    // sized like the surface would be and recreated along with the other targets
+    pub surface: Option<Surface<'win>>,
+    pub offscreen: Option<(Texture, TextureView)>,
     pub device: Device,
     pub queue: Queue,
     pub config: SurfaceConfiguration,
-}
 
-impl<'win> Gfx<'win> {
-    pub async fn new(window: Arc<Window>) -> Self {
-        let instance = Instance::default();
-        let surface = instance.create_surface(window.clone()).unwrap();
+    // What the adapter could actually provide
+    pub report: Report,
 
-        let options = RequestAdapterOptions {
-            power_preference: PowerPreference::HighPerformance,
-            compatible_surface: Some(&surface),
-            force_fallback_adapter: false,
-        };
+    // Those the surface can present in, see `supported_present_modes`
+    present_modes: Vec<PresentMode>,
 
-        let adapter = instance.request_adapter(&options).await.unwrap();
+    // Sample counts both the HDR and depth formats render with, in ascending order
+    pub sample_counts: Vec<u32>,
+    sample_count: u32,
 
-        let required_limits = Limits {
-            max_push_constant_size: 128,
-            ..Limits::default()
-        };
+    // Share of the surface size along each side the scene is drawn at, see `render_size`
+    render_scale: f32,
 
-        let descriptor = DeviceDescriptor {
-            label: None,
-            required_features: Features::empty()
-                | Features::PUSH_CONSTANTS
-                | Features::POLYGON_MODE_LINE
-                | Features::MULTI_DRAW_INDIRECT
-                | Features::INDIRECT_FIRST_INSTANCE,
-            required_limits,
-        };
+    // Sized like the scene is drawn, and recreated along with the surface
+    pub depth_texture: Texture,
+    pub depth_view: TextureView,
 
-        let (device, queue) = adapter.request_device(&descriptor, None).await.unwrap();
+    // Transient textures of the last graph run, for the next one to reuse
+    transients: RefCell<Vec<PooledTexture>>,
+    cache: RefCell<Cache>,
+
+    // Times graph passes, if timestamps can be queried
+    profiler: RefCell<Option<GpuProfiler>>,
+
+    // Graphs run so far, see `frame`
+    frame: Cell<u64>,
+
+    // Set once the device is gone, see `recover`
+    instance: Arc<Instance>,
+
+    // Kept for other windows to create their surfaces with, see `WindowSurface`
+    adapter: Adapter,
+
+    // Taken over the power preference, and again when recovering
+    chosen_adapter: Option<AdapterChoice>,
+    lost: Arc<AtomicBool>,
+    recreators: Vec<Box<dyn FnMut(&Gfx<'win>) + 'win>>,
+}
+
+impl<'win> Gfx<'win> {
+    pub async fn new(window: Arc<Window>) -> Result<Self, GfxError> {
+        Self::with_format(window, None).await
+    }
+
+    // Same as `new`, but presenting in `format` if the surface supports it
+    pub async fn with_format(
+        window: Arc<Window>,
+        format: Option<TextureFormat>,
+    ) -> Result<Self, GfxError> {
+        Self::with_options(window, format, None).await
+    }
+
+    // Same as `with_format`, but drawing with `chosen_adapter` if given
+    pub async fn with_options(
+        window: Arc<Window>,
+        format: Option<TextureFormat>,
+        chosen_adapter: Option<AdapterChoice>,
+    ) -> Result<Self, GfxError> {
+        let instance = Arc::new(Instance::default());
+        let surface = instance
+            .create_surface(window.clone())
+            .map_err(GfxError::Surface)?;
+
+        let chosen = chosen_adapter.as_ref();
+        let adapter = request_adapter(&instance, Some(&surface), chosen).await?;
+        let (device, queue, report) = request_device(&adapter).await?;
 
         let SurfaceCapabilities {
             formats,
             alpha_modes,
+            usages,
             ..
         } = surface.get_capabilities(&adapter);
 
+        if formats.is_empty() {
+            return Err(GfxError::IncompatibleSurface);
+        }
+
         let PhysicalSize { width, height } = window.inner_size();
+        let chosen = pick_format(&formats, format);
+
+        if let Some(format) = format.filter(|&format| format != chosen) {
+            tracing::warn!("surface format {format:?} unsupported, presenting in {chosen:?}");
+        }
 
+        // Frames are copied from when captured, where the surface allows it
         let config = SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
-            format: formats[0],
+            usage: TextureUsages::RENDER_ATTACHMENT | (usages & TextureUsages::COPY_SRC),
+            format: chosen,
             width,
             height,
             present_mode: PresentMode::AutoVsync,
@@ -62,35 +176,722 @@ impl<'win> Gfx<'win> {
         };
 
         surface.configure(&device, &config);
+        let surface = Some(surface);
+        let mut gfx = Self::from_parts(instance, adapter, device, queue, report, surface, config);
+        gfx.chosen_adapter = chosen_adapter;
+        Ok(gfx)
+    }
+
+    // Without a window, rendering into `offscreen` instead of a surface.
+    // It can be bound and copied out of, for tests and image baking alike
+    pub async fn headless(
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> Result<Self, GfxError> {
+        let instance = Arc::new(Instance::default());
+        let adapter = request_adapter(&instance, None, None).await?;
+        let (device, queue, report) = request_device(&adapter).await?;
+
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT
+                | TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+
+        let gfx = Self::from_parts(instance, adapter, device, queue, report, None, config);
+        Ok(gfx)
+    }
+
+    fn from_parts(
+        instance: Arc<Instance>,
+        adapter: Adapter,
+        device: Device,
+        queue: Queue,
+        report: Report,
+        surface: Option<Surface<'win>>,
+        config: SurfaceConfiguration,
+    ) -> Self {
+        // Past the counts every adapter has, support depends on the adapter
+        let (features, capabilities) = (report.features, report.capabilities());
+        let format_features = |format: TextureFormat| {
+            if capabilities.adapter_format_features {
+                adapter.get_texture_format_features(format).flags
+            } else {
+                format.guaranteed_format_features(features).flags
+            }
+        };
+
+        let (color, depth) = (format_features(HDR_FORMAT), format_features(DEPTH_FORMAT));
+        let sample_counts: Vec<_> = [1, 2, 4, 8]
+            .into_iter()
+            .filter(|&count| color.sample_count_supported(count))
+            .filter(|&count| depth.sample_count_supported(count))
+            .collect();
+
+        let sample_count = pick_sample_count(&sample_counts, DEFAULT_SAMPLE_COUNT);
+        let size = scaled_size(&config, 1.0);
+        let (depth_texture, depth_view) = create_depth(&device, size, sample_count);
+        let profiles = capabilities.timestamps;
+        let profiler = profiles.then(|| GpuProfiler::new(&device, &queue));
+        let headless = surface.is_none();
+        let offscreen = headless.then(|| create_offscreen(&device, &config));
+        let lost = watch_loss(&device);
+
+        let present_modes = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter).present_modes,
+            None => Vec::new(),
+        };
 
         Self {
             surface,
+            offscreen,
             device,
             queue,
             config,
+            report,
+            present_modes,
+            sample_counts,
+            sample_count,
+            render_scale: 1.0,
+            depth_texture,
+            depth_view,
+            transients: RefCell::default(),
+            cache: RefCell::default(),
+            profiler: RefCell::new(profiler),
+            frame: Cell::new(0),
+            instance,
+            adapter,
+            chosen_adapter: None,
+            lost,
+            recreators: Vec::new(),
         }
     }
 
+    // Whether the device was lost, be it reset, removed or out of memory.
+    // Nothing drawn with it shows up any more until `recover` is called
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    // Have `callback` called after `recover`, to create again whatever lived in the lost device
+    pub fn on_recreate(&mut self, callback: impl FnMut(&Gfx<'win>) + 'win) {
+        self.recreators.push(Box::new(callback));
+    }
+
+    // Start over on a new device, likely from another adapter,
+    // keeping the surface and as much of the settings as still supported.
+    // Every resource created out of the old device is useless by now
+    pub async fn recover(&mut self) -> Result<(), GfxError> {
+        let (instance, surface) = (&self.instance, self.surface.as_ref());
+        let chosen = self.chosen_adapter.as_ref();
+
+        // The chosen adapter may well be what went away
+        let adapter = match request_adapter(instance, surface, chosen).await {
+            Err(GfxError::NoChosenAdapter(choice)) => {
+                tracing::warn!("no {choice} any more, falling back to any other");
+                request_adapter(instance, surface, None).await?
+            }
+            adapter => adapter?,
+        };
+
+        let (device, queue, report) = request_device(&adapter).await?;
+        let mut config = self.config.clone();
+
+        if let Some(surface) = &self.surface {
+            let SurfaceCapabilities {
+                formats,
+                present_modes,
+                ..
+            } = surface.get_capabilities(&adapter);
+
+            if formats.is_empty() {
+                return Err(GfxError::IncompatibleSurface);
+            }
+
+            config.format = pick_format(&formats, Some(config.format));
+
+            if !is_supported(&present_modes, config.present_mode) {
+                config.present_mode = PresentMode::AutoVsync;
+            }
+
+            surface.configure(&device, &config);
+        }
+
+        let (instance, surface) = (self.instance.clone(), self.surface.take());
+        let gfx = Self::from_parts(instance, adapter, device, queue, report, surface, config);
+        let (sample_count, render_scale) = (self.sample_count, self.render_scale);
+        let chosen_adapter = self.chosen_adapter.take();
+        let mut recreators = mem::take(&mut self.recreators);
+
+        *self = gfx;
+        self.render_scale = render_scale;
+        self.chosen_adapter = chosen_adapter;
+        self.set_sample_count(sample_count);
+
+        for recreate in &mut recreators {
+            recreate(self);
+        }
+
+        // Keeping any registered along the way
+        recreators.append(&mut self.recreators);
+        self.recreators = recreators;
+        Ok(())
+    }
+
+    // The surface texture to draw the next frame into, if any.
+    // Lost or outdated surfaces are configured again, skipping the frame,
+    // and running out of memory for it is taken as losing the device
+    pub fn acquire_frame(&self) -> Option<SurfaceTexture> {
+        let surface = self.surface.as_ref()?;
+
+        match surface.get_current_texture() {
+            Ok(frame) => Some(frame),
+            Err(SurfaceError::Timeout) => None,
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.configure_surface();
+                None
+            }
+            Err(SurfaceError::OutOfMemory) => {
+                self.lost.store(true, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    pub const fn format(&self) -> TextureFormat {
+        self.config.format
+    }
+
+    // Whether presenting encodes colors, so shaders must write them linear
+    pub fn is_srgb(&self) -> bool {
+        self.config.format.is_srgb()
+    }
+
     pub fn resize_viewport(&mut self, new_size: PhysicalSize<u32>) {
         let PhysicalSize { width, height } = new_size;
 
         if width * height > 0 {
             self.config.width = width;
             self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+            self.configure_surface();
+            self.create_targets();
         }
     }
 
-    pub fn toggle_vsync(&mut self) {
-        self.config.present_mode = match self.config.present_mode {
-            PresentMode::AutoVsync => PresentMode::AutoNoVsync,
-            PresentMode::AutoNoVsync => PresentMode::AutoVsync,
-            _ => {
-                // No other present mode than the ones listed is used
-                unreachable!()
-            }
+    // Features granted, see `GfxCapabilities`
+    pub fn capabilities(&self) -> GfxCapabilities {
+        self.report.capabilities()
+    }
+
+    pub const fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    // Render with up to `count` samples per pixel, as many as supported,
+    // returning how many were settled on. Pipelines must be created again to match
+    pub fn set_sample_count(&mut self, count: u32) -> u32 {
+        self.sample_count = pick_sample_count(&self.sample_counts, count);
+        self.create_targets();
+        self.sample_count
+    }
+
+    // For pipelines drawing into `hdr_targets`
+    pub const fn multisample_state(&self) -> MultisampleState {
+        MultisampleState {
+            count: self.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }
+    }
+
+    pub const fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    // Draw the scene at `scale` times the surface size along each side,
+    // to be scaled up when brought into the frame. Past 1 it is clamped
+    pub fn set_render_scale(&mut self, scale: f32) {
+        self.render_scale = scale.clamp(0.0, 1.0);
+        self.create_targets();
+    }
+
+    // What the scene is drawn at, never less than a pixel
+    pub fn render_size(&self) -> Extent3d {
+        scaled_size(&self.config, self.render_scale)
+    }
+
+    fn create_targets(&mut self) {
+        let (device, config) = (&self.device, &self.config);
+        let size = self.render_size();
+        (self.depth_texture, self.depth_view) = create_depth(device, size, self.sample_count);
+
+        // Transients no longer match the targets, so there is no point keeping them around
+        self.transients.take();
+
+        if self.offscreen.is_some() {
+            self.offscreen = Some(create_offscreen(device, config));
+        }
+    }
+
+    // Targets to draw the scene into, in linear light and unbounded,
+    // to be brought into the frame by a later pass
+    pub fn hdr_targets<'a>(&'a self, graph: &mut Graph<'a>) -> FrameTargets {
+        let size = self.render_size();
+        let color = graph.transient(Transient {
+            format: HDR_FORMAT,
+            sample_count: self.sample_count,
+            size,
+        });
+
+        // Multisampled color is only needed until resolved
+        let resolve = (self.sample_count > 1).then(|| {
+            graph.transient(Transient {
+                format: HDR_FORMAT,
+                sample_count: 1,
+                size,
+            })
+        });
+
+        FrameTargets {
+            color,
+            resolve,
+            depth: graph.import(&self.depth_view),
+            format: HDR_FORMAT,
+            size,
+        }
+    }
+
+    // Record every pass of `graph` into a single submission
+    pub fn run(&self, graph: Graph) {
+        let _span = tracing::debug_span!("render").entered();
+        graph.execute(self);
+    }
+
+    // Graphs run so far, for anything written once a frame to tell frames apart
+    pub fn frame(&self) -> u64 {
+        self.frame.get()
+    }
+
+    // GPU times of the passes of a recent graph run, if timestamps can be queried
+    pub fn profiler(&self) -> Option<Ref<'_, GpuProfiler>> {
+        Ref::filter_map(self.profiler.borrow(), Option::as_ref).ok()
+    }
+
+    // Bytes held for the surface targets and transients, for the owners of anything
+    // else to add theirs to, along with how many buffers and textures wgpu counts
+    pub fn memory_report(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        report.add_texture("depth", &self.depth_texture);
+
+        if let Some((texture, _)) = &self.offscreen {
+            report.add_texture("offscreen", texture);
+        }
+
+        let transients = self.transients.borrow();
+        report.transients = transients.iter().map(PooledTexture::bytes).sum();
+
+        let backend = self.report.adapter.backend;
+        report.live = self.instance.generate_report().map(|global| {
+            let hub = global.hub_report(backend);
+            (hub.buffers.num_allocated, hub.textures.num_allocated)
+        });
+
+        report
+    }
+
+    // Write `frame` out as a PNG, be it the surface texture about to be presented
+    // or the offscreen target. Waits for the GPU to be done drawing it
+    pub fn capture_frame(&self, frame: &Texture, out: impl Write) -> Result<(), CaptureError> {
+        capture::capture(self, frame, out)
+    }
+
+    // Bind group of `layout` binding `resources`, numbered in order
+    pub fn bind_group<'a>(
+        &self,
+        label: &str,
+        layout: &BindGroupLayout,
+        resources: impl IntoIterator<Item = BindingResource<'a>>,
+    ) -> BindGroup {
+        let entries: Vec<_> = resources
+            .into_iter()
+            .enumerate()
+            .map(|(binding, resource)| BindGroupEntry {
+                binding: binding as u32,
+                resource,
+            })
+            .collect();
+
+        let descriptor = BindGroupDescriptor {
+            label: Some(label),
+            layout,
+            entries: &entries,
+        };
+
+        self.device.create_bind_group(&descriptor)
+    }
+
+    // Layouts and pipelines below are created once and handed out again
+    // whenever asked for the same, see `cache::Cache`
+    pub fn bind_group_layout(&self, entries: &[BindGroupLayoutEntry]) -> Arc<BindGroupLayout> {
+        let mut cache = self.cache.borrow_mut();
+        cache.bind_group_layout(&self.device, entries)
+    }
+
+    pub fn pipeline_layout(
+        &self,
+        bind_group_layouts: &[&BindGroupLayout],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> Arc<PipelineLayout> {
+        let mut cache = self.cache.borrow_mut();
+        cache.pipeline_layout(&self.device, bind_group_layouts, push_constant_ranges)
+    }
+
+    pub fn render_pipeline(
+        &self,
+        module: &ShaderModule,
+        layout: Option<&PipelineLayout>,
+        state: &RenderState,
+    ) -> Arc<RenderPipeline> {
+        let mut cache = self.cache.borrow_mut();
+        cache.render_pipeline(&self.device, module, layout, state)
+    }
+
+    pub fn compute_pipeline(
+        &self,
+        module: &ShaderModule,
+        layout: Option<&PipelineLayout>,
+        entry_point: &'static str,
+    ) -> Arc<ComputePipeline> {
+        let mut cache = self.cache.borrow_mut();
+        cache.compute_pipeline(&self.device, module, layout, entry_point)
+    }
+
+    // Present modes the surface supports, none when headless.
+    // Either automatic mode is supported as well, falling back to `Fifo`
+    pub fn supported_present_modes(&self) -> &[PresentMode] {
+        &self.present_modes
+    }
+
+    // Every adapter there is, in the order `AdapterChoice::Index` counts them
+    pub fn enumerate_adapters(&self) -> Vec<AdapterListing> {
+        let adapters = self.instance.enumerate_adapters(Backends::all());
+        adapters.iter().map(AdapterListing::new).collect()
+    }
+
+    pub const fn present_mode(&self) -> PresentMode {
+        self.config.present_mode
+    }
+
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> Result<(), GfxError> {
+        if !is_supported(&self.present_modes, mode) {
+            return Err(GfxError::PresentMode(mode));
+        }
+
+        self.config.present_mode = mode;
+        self.configure_surface();
+        Ok(())
+    }
+
+    // Apply `config` to the surface, also needed after losing it
+    pub fn configure_surface(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+}
+
+// Whether a surface presenting in `modes` can present in `mode`
+fn is_supported(modes: &[PresentMode], mode: PresentMode) -> bool {
+    let automatic = matches!(mode, PresentMode::AutoVsync | PresentMode::AutoNoVsync);
+    (automatic && !modes.is_empty()) || modes.contains(&mode)
+}
+
+// Largest supported count not above the wanted one, 1 always being supported
+fn pick_sample_count(counts: &[u32], wanted: u32) -> u32 {
+    let counts = counts.iter().copied();
+    counts.filter(|&count| count <= wanted).max().unwrap_or(1)
+}
+
+// Depth texture matching the surface, also bindable for passes reading depth back
+fn create_depth(device: &Device, size: Extent3d, sample_count: u32) -> (Texture, TextureView) {
+    let usage = TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING;
+    create_target(device, size, DEPTH_FORMAT, sample_count, usage, "depth")
+}
+
+fn create_offscreen(device: &Device, config: &SurfaceConfiguration) -> (Texture, TextureView) {
+    let size = scaled_size(config, 1.0);
+    create_target(device, size, config.format, 1, config.usage, "offscreen")
+}
+
+// Surface size times `scale` along each side, never less than a pixel
+fn scaled_size(config: &SurfaceConfiguration, scale: f32) -> Extent3d {
+    let scale = |side: u32| ((side as f32 * scale).round() as u32).max(1);
+
+    Extent3d {
+        width: scale(config.width),
+        height: scale(config.height),
+        depth_or_array_layers: 1,
+    }
+}
+
+fn create_target(
+    device: &Device,
+    size: Extent3d,
+    format: TextureFormat,
+    sample_count: u32,
+    usage: TextureUsages,
+    label: &str,
+) -> (Texture, TextureView) {
+    let descriptor = TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format,
+        usage,
+        view_formats: &[],
+    };
+
+    let texture = device.create_texture(&descriptor);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    (texture, view)
+}
+
+// Formats of a surface are listed in no particular order, and the first one
+// may well not be sRGB, washing out every color. Prefer the wanted format,
+// then the common 8-bit sRGB ones, then any sRGB one
+fn pick_format(formats: &[TextureFormat], wanted: Option<TextureFormat>) -> TextureFormat {
+    let preferred = [TextureFormat::Bgra8UnormSrgb, TextureFormat::Rgba8UnormSrgb];
+
+    wanted
+        .into_iter()
+        .chain(preferred)
+        .find(|format| formats.contains(format))
+        .or_else(|| formats.iter().copied().find(TextureFormat::is_srgb))
+        .unwrap_or(formats[0])
+}
+
+// The one chosen if any, or else a discrete GPU first,
+// then an integrated one, then a software one
+async fn request_adapter(
+    instance: &Instance,
+    surface: Option<&Surface<'_>>,
+    chosen: Option<&AdapterChoice>,
+) -> Result<Adapter, GfxError> {
+    if let Some(choice) = chosen {
+        let adapter = choice.pick(instance, surface);
+        return adapter.ok_or_else(|| GfxError::NoChosenAdapter(choice.clone()));
+    }
+
+    let attempts = [
+        (PowerPreference::HighPerformance, false),
+        (PowerPreference::LowPower, false),
+        (PowerPreference::None, true),
+    ];
+
+    for (power_preference, force_fallback_adapter) in attempts {
+        let options = RequestAdapterOptions {
+            power_preference,
+            compatible_surface: surface,
+            force_fallback_adapter,
         };
 
-        self.surface.configure(&self.device, &self.config);
+        if let Some(adapter) = instance.request_adapter(&options).await {
+            return Ok(adapter);
+        }
+    }
+
+    Err(GfxError::NoAdapter)
+}
+
+// Flag raised once `device` is lost or runs out of memory.
+// Validation errors are bugs, so they still panic
+fn watch_loss(device: &Device) -> Arc<AtomicBool> {
+    let lost = Arc::new(AtomicBool::new(false));
+
+    let flag = lost.clone();
+    device.set_device_lost_callback(move |reason, message| {
+        tracing::error!("device lost ({reason:?}): {message}");
+        flag.store(true, Ordering::Relaxed);
+    });
+
+    let flag = lost.clone();
+    device.on_uncaptured_error(Box::new(move |err| match err {
+        wgpu::Error::OutOfMemory { .. } => {
+            tracing::error!("{err}");
+            flag.store(true, Ordering::Relaxed);
+        }
+        _ => panic!("{err}"),
+    }));
+
+    lost
+}
+
+// With every wanted feature the adapter has, and the best limits it can meet
+async fn request_device(adapter: &Adapter) -> Result<(Device, Queue, Report), GfxError> {
+    let supported = adapter.limits();
+
+    // Make do with lesser limits on older or software adapters
+    let limits = [Limits::default(), Limits::downlevel_defaults()]
+        .into_iter()
+        .find(|limits| limits.check_limits(&supported))
+        .unwrap_or_else(Limits::downlevel_webgl2_defaults);
+
+    // Without push constants, passes write their constants into uniform buffers
+    // instead, see `PushConstants`, so the device opens all the same
+    let required_features = WANTED_FEATURES & adapter.features();
+    let push_constants = required_features.contains(Features::PUSH_CONSTANTS);
+    let max_push_constant_size = match push_constants {
+        true => WANTED_PUSH_CONSTANT_SIZE.min(supported.max_push_constant_size),
+        false => 0,
+    };
+
+    let required_limits = Limits {
+        max_push_constant_size,
+        ..limits
+    };
+
+    let descriptor = DeviceDescriptor {
+        label: None,
+        required_features,
+        required_limits: required_limits.clone(),
+    };
+
+    let (device, queue) = adapter
+        .request_device(&descriptor, None)
+        .await
+        .map_err(GfxError::Device)?;
+
+    let report = Report {
+        adapter: adapter.get_info(),
+        features: required_features,
+        missing_features: WANTED_FEATURES - required_features,
+        limits: required_limits,
+    };
+
+    Ok((device, queue, report))
+}
+
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub adapter: AdapterInfo,
+    pub features: Features,
+
+    // Wanted but unsupported, so whatever relies on them must be skipped
+    pub missing_features: Features,
+    pub limits: Limits,
+}
+
+impl Report {
+    pub fn capabilities(&self) -> GfxCapabilities {
+        GfxCapabilities::new(self.features, &self.limits)
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let AdapterInfo {
+            name,
+            backend,
+            device_type,
+            ..
+        } = &self.adapter;
+
+        writeln!(f, "{name} ({device_type:?}, {backend:?})")?;
+        writeln!(f, "features: {:?}", self.features)?;
+
+        if !self.missing_features.is_empty() {
+            writeln!(f, "missing features: {:?}", self.missing_features)?;
+        }
+
+        match self.limits.max_push_constant_size {
+            0 => writeln!(f, "push constants: none, written into uniform buffers"),
+            size => writeln!(f, "push constants: {size} bytes"),
+        }
+    }
+}
+
+// What the device was granted out of the wanted features, for higher layers
+// to pick their code paths by instead of failing where they are missing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GfxCapabilities {
+    // Zero bytes of room without the feature
+    pub push_constant_size: u32,
+    pub line_polygons: bool,
+    pub multi_draw_indirect: bool,
+    pub indirect_first_instance: bool,
+
+    // Format support beyond the guaranteed, such as more sample counts
+    pub adapter_format_features: bool,
+    pub timestamps: bool,
+}
+
+impl GfxCapabilities {
+    pub fn new(features: Features, limits: &Limits) -> Self {
+        let push_constants = features.contains(Features::PUSH_CONSTANTS);
+        let adapter_specific = Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+
+        Self {
+            push_constant_size: match push_constants {
+                true => limits.max_push_constant_size,
+                false => 0,
+            },
+            line_polygons: features.contains(Features::POLYGON_MODE_LINE),
+            multi_draw_indirect: features.contains(Features::MULTI_DRAW_INDIRECT),
+            indirect_first_instance: features.contains(Features::INDIRECT_FIRST_INSTANCE),
+            adapter_format_features: features.contains(adapter_specific),
+            timestamps: features.contains(Features::TIMESTAMP_QUERY),
+        }
+    }
+
+    pub const fn push_constants(&self) -> bool {
+        self.push_constant_size > 0
+    }
+}
+
+#[derive(Debug)]
+pub enum GfxError {
+    Surface(CreateSurfaceError),
+    NoAdapter,
+
+    // None matches, or none of those can present to the window
+    NoChosenAdapter(AdapterChoice),
+    Device(RequestDeviceError),
+
+    // The adapter cannot present to the window at all
+    IncompatibleSurface,
+
+    // Unsupported by the surface, if there is one at all
+    PresentMode(PresentMode),
+}
+
+impl Display for GfxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Surface(err) => write!(f, "cannot create surface: {err}"),
+            Self::NoAdapter => write!(f, "no graphics adapter found"),
+            Self::NoChosenAdapter(choice) => write!(f, "no {choice} found"),
+            Self::Device(err) => write!(f, "cannot open device: {err}"),
+            Self::IncompatibleSurface => write!(f, "adapter cannot present to the window"),
+            Self::PresentMode(mode) => write!(f, "cannot present in {mode:?} mode"),
+        }
+    }
+}
+
+impl Error for GfxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Surface(err) => Some(err),
+            Self::Device(err) => Some(err),
+            _ => None,
+        }
     }
 }
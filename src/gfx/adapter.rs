@@ -0,0 +1,105 @@
+use std::fmt::Display;
+
+use wgpu::{Adapter, AdapterInfo, Backend, Backends, Instance, Limits, Surface};
+
+// Which adapter to draw with, overriding the power preference.
+// Either its place in `Gfx::enumerate_adapters`, part of its name or its backend
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdapterChoice {
+    Index(usize),
+    Name(String),
+    Backend(Backend),
+}
+
+impl AdapterChoice {
+    // Numbers are indices and backend names are backends, anything else is part of a name
+    pub fn parse(text: &str) -> Self {
+        if let Ok(index) = text.parse() {
+            return Self::Index(index);
+        }
+
+        let backend = match text.to_lowercase().as_str() {
+            "vulkan" | "vk" => Backend::Vulkan,
+            "metal" => Backend::Metal,
+            "dx12" | "d3d12" => Backend::Dx12,
+            "gl" | "gles" | "opengl" => Backend::Gl,
+            "webgpu" => Backend::BrowserWebGpu,
+            _ => return Self::Name(text.to_owned()),
+        };
+
+        Self::Backend(backend)
+    }
+
+    fn matches(&self, index: usize, info: &AdapterInfo) -> bool {
+        match self {
+            Self::Index(wanted) => index == *wanted,
+            Self::Name(part) => info.name.to_lowercase().contains(&part.to_lowercase()),
+            Self::Backend(backend) => info.backend == *backend,
+        }
+    }
+
+    // First adapter of `instance` chosen, among those able to present to `surface`
+    pub(super) fn pick(
+        &self,
+        instance: &Instance,
+        surface: Option<&Surface<'_>>,
+    ) -> Option<Adapter> {
+        let adapters = instance.enumerate_adapters(Backends::all());
+
+        adapters
+            .into_iter()
+            .enumerate()
+            .filter(|(index, adapter)| self.matches(*index, &adapter.get_info()))
+            .map(|(_, adapter)| adapter)
+            .find(|adapter| surface.map_or(true, |surface| adapter.is_surface_supported(surface)))
+    }
+}
+
+impl Display for AdapterChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "adapter {index}"),
+            Self::Name(part) => write!(f, "adapter named like {part:?}"),
+            Self::Backend(backend) => write!(f, "{backend:?} adapter"),
+        }
+    }
+}
+
+// An adapter there is to choose from, see `Gfx::enumerate_adapters`
+#[derive(Clone, Debug)]
+pub struct AdapterListing {
+    pub info: AdapterInfo,
+    pub limits: Limits,
+}
+
+impl AdapterListing {
+    pub(super) fn new(adapter: &Adapter) -> Self {
+        Self {
+            info: adapter.get_info(),
+            limits: adapter.limits(),
+        }
+    }
+}
+
+impl Display for AdapterListing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let AdapterInfo {
+            name,
+            backend,
+            device_type,
+            ..
+        } = &self.info;
+
+        let Limits {
+            max_texture_dimension_2d,
+            max_buffer_size,
+            max_push_constant_size,
+            ..
+        } = &self.limits;
+
+        write!(f, "{name} ({device_type:?}, {backend:?}), ")?;
+        write!(f, "textures up to {max_texture_dimension_2d} px, ")?;
+        write!(f, "buffers up to {max_buffer_size} bytes, ")?;
+        write!(f, "{max_push_constant_size} bytes of push constants")
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use wgpu::{
+    BindGroup, BindGroupLayout, BindGroupLayoutEntry, BindingResource, BindingType,
+    BufferBindingType, SamplerBindingType, ShaderStages, TextureSampleType, TextureViewDimension,
+};
+
+use super::Gfx;
+
+// Bind group layout described binding by binding, numbered in the order
+// they are added and all seen by the same stages. Its bind groups take
+// the resources in that same order, see `Gfx::bind_group`
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    visibility: ShaderStages,
+    entries: Vec<BindGroupLayoutEntry>,
+}
+
+impl Bindings {
+    pub const fn new(visibility: ShaderStages) -> Self {
+        Self {
+            visibility,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, ty: BindingType) -> Self {
+        self.entries.push(BindGroupLayoutEntry {
+            binding: self.entries.len() as u32,
+            visibility: self.visibility,
+            ty,
+            count: None,
+        });
+
+        self
+    }
+
+    pub fn storage(self, read_only: bool) -> Self {
+        self.with(BindingType::Buffer {
+            ty: BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        })
+    }
+
+    pub fn uniform(self) -> Self {
+        self.with(BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        })
+    }
+
+    pub fn texture(self, sample_type: TextureSampleType) -> Self {
+        self.with(BindingType::Texture {
+            sample_type,
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        })
+    }
+
+    // Filterable, to be sampled along with a sampler
+    pub fn texture_array(self) -> Self {
+        self.with(BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2Array,
+            multisampled: false,
+        })
+    }
+
+    pub fn sampler(self, ty: SamplerBindingType) -> Self {
+        self.with(BindingType::Sampler(ty))
+    }
+
+    pub fn layout(&self, gfx: &Gfx) -> Arc<BindGroupLayout> {
+        gfx.bind_group_layout(&self.entries)
+    }
+
+    // The layout along with a bind group of it binding `resources`
+    pub fn create<'a>(
+        &self,
+        gfx: &Gfx,
+        label: &str,
+        resources: impl IntoIterator<Item = BindingResource<'a>>,
+    ) -> (Arc<BindGroupLayout>, BindGroup) {
+        let layout = self.layout(gfx);
+        let group = gfx.bind_group(label, &layout, resources);
+        (layout, group)
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, sync::Arc};
+
+use wgpu::{
+    BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, ColorTargetState,
+    ComputePipeline, ComputePipelineDescriptor, DepthStencilState, Device, FragmentState, Id,
+    MultisampleState, PipelineLayout, PipelineLayoutDescriptor, PrimitiveState, PushConstantRange,
+    RenderPipeline, RenderPipelineDescriptor, ShaderModule, VertexState,
+};
+
+// Everything a render pipeline is made of besides its module and layout.
+// Vertices are pulled out of storage buffers, so there are no vertex buffers
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderState {
+    pub label: &'static str,
+    pub vertex: &'static str,
+
+    // Entry point drawing into `targets`, if any
+    pub fragment: Option<&'static str>,
+    pub targets: Vec<Option<ColorTargetState>>,
+    pub primitive: PrimitiveState,
+    pub depth_stencil: Option<DepthStencilState>,
+    pub multisample: MultisampleState,
+}
+
+type PipelineLayoutKey = (Vec<Id<BindGroupLayout>>, Vec<PushConstantRange>);
+type RenderPipelineKey = (Id<ShaderModule>, Option<Id<PipelineLayout>>, RenderState);
+type ComputePipelineKey = (Id<ShaderModule>, Option<Id<PipelineLayout>>, &'static str);
+
+// Layouts and pipelines created on first request and kept along with the device,
+// so passes can ask for them every frame. Modules and layouts are told apart
+// by identity, so keep them around for as long as their pipelines are wanted
+#[derive(Debug, Default)]
+pub(super) struct Cache {
+    bind_group_layouts: HashMap<Vec<BindGroupLayoutEntry>, Arc<BindGroupLayout>>,
+    pipeline_layouts: HashMap<PipelineLayoutKey, Arc<PipelineLayout>>,
+    render_pipelines: HashMap<RenderPipelineKey, Arc<RenderPipeline>>,
+    compute_pipelines: HashMap<ComputePipelineKey, Arc<ComputePipeline>>,
+}
+
+impl Cache {
+    pub fn bind_group_layout(
+        &mut self,
+        device: &Device,
+        entries: &[BindGroupLayoutEntry],
+    ) -> Arc<BindGroupLayout> {
+        let layouts = &mut self.bind_group_layouts;
+
+        if let Some(layout) = layouts.get(entries) {
+            return layout.clone();
+        }
+
+        let descriptor = BindGroupLayoutDescriptor {
+            label: None,
+            entries,
+        };
+
+        let layout = Arc::new(device.create_bind_group_layout(&descriptor));
+        layouts.insert(entries.to_vec(), layout.clone());
+        layout
+    }
+
+    pub fn pipeline_layout(
+        &mut self,
+        device: &Device,
+        bind_group_layouts: &[&BindGroupLayout],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> Arc<PipelineLayout> {
+        let ids = bind_group_layouts.iter().map(|layout| layout.global_id());
+        let key = (ids.collect(), push_constant_ranges.to_vec());
+
+        let layout = self.pipeline_layouts.entry(key).or_insert_with(|| {
+            let descriptor = PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts,
+                push_constant_ranges,
+            };
+
+            Arc::new(device.create_pipeline_layout(&descriptor))
+        });
+
+        layout.clone()
+    }
+
+    pub fn render_pipeline(
+        &mut self,
+        device: &Device,
+        module: &ShaderModule,
+        layout: Option<&PipelineLayout>,
+        state: &RenderState,
+    ) -> Arc<RenderPipeline> {
+        let key = (
+            module.global_id(),
+            layout.map(PipelineLayout::global_id),
+            state.clone(),
+        );
+
+        let pipeline = self.render_pipelines.entry(key).or_insert_with(|| {
+            let fragment = state.fragment.map(|entry_point| FragmentState {
+                module,
+                entry_point,
+                targets: &state.targets,
+            });
+
+            let descriptor = RenderPipelineDescriptor {
+                label: Some(state.label),
+                layout,
+                vertex: VertexState {
+                    module,
+                    entry_point: state.vertex,
+                    buffers: &[],
+                },
+                primitive: state.primitive,
+                depth_stencil: state.depth_stencil.clone(),
+                multisample: state.multisample,
+                fragment,
+                multiview: None,
+            };
+
+            Arc::new(device.create_render_pipeline(&descriptor))
+        });
+
+        pipeline.clone()
+    }
+
+    pub fn compute_pipeline(
+        &mut self,
+        device: &Device,
+        module: &ShaderModule,
+        layout: Option<&PipelineLayout>,
+        entry_point: &'static str,
+    ) -> Arc<ComputePipeline> {
+        let key = (
+            module.global_id(),
+            layout.map(PipelineLayout::global_id),
+            entry_point,
+        );
+
+        let pipeline = self.compute_pipelines.entry(key).or_insert_with(|| {
+            let descriptor = ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout,
+                module,
+                entry_point,
+            };
+
+            Arc::new(device.create_compute_pipeline(&descriptor))
+        });
+
+        pipeline.clone()
+    }
+}
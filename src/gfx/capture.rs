@@ -0,0 +1,328 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+use wgpu::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoderDescriptor,
+    ImageCopyBuffer, ImageCopyTexture, ImageDataLayout, Maintain, MapMode, Origin3d,
+    SubmissionIndex, Texture, TextureAspect, TextureFormat, TextureUsages,
+    COPY_BYTES_PER_ROW_ALIGNMENT,
+};
+
+use super::Gfx;
+
+// Frames copied back and not written out yet, at most. Past that, recording
+// waits for the oldest of them rather than falling ever further behind
+const IN_FLIGHT: usize = 4;
+
+// Copy `frame` back from the GPU and write it out as an 8-bit RGBA PNG.
+// Whatever was submitted before drawing into it is waited for
+pub(super) fn capture(gfx: &Gfx, frame: &Texture, out: impl Write) -> Result<(), CaptureError> {
+    let (readback, index) = Readback::new(gfx, frame, None)?;
+    gfx.device.poll(Maintain::WaitForSubmissionIndex(index));
+
+    // Mapping is done by the time the device is done with the copy
+    let mapped = readback.mapped.recv().unwrap();
+    mapped.map_err(CaptureError::Map)?;
+    write_png(out, readback.width, readback.height, &readback.pixels())
+}
+
+// Consecutive frames written out as numbered PNGs into a directory, to make
+// videos out of. Frames are copied back without waiting for them and encoded
+// on a thread of their own, so recording holds drawing up as little as it can
+#[derive(Debug)]
+pub struct FrameRecorder {
+    dir: PathBuf,
+    frames: u32,
+    in_flight: VecDeque<(PathBuf, Readback)>,
+
+    // Buffers of frames written out, to copy later frames into
+    free: Vec<Buffer>,
+
+    encode: Option<Sender<(PathBuf, u32, u32, Vec<u8>)>>,
+    failed: Receiver<CaptureError>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl FrameRecorder {
+    // Recording into `dir`, made if not there already
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let (encode, queue) = mpsc::channel();
+        let (sender, failed) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("frame recorder".into())
+            .spawn(move || encode_frames(&queue, &sender))?;
+
+        Ok(Self {
+            dir,
+            frames: 0,
+            in_flight: VecDeque::new(),
+            free: Vec::new(),
+            encode: Some(encode),
+            failed,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Frames recorded so far, written out or not
+    pub const fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // Copy `frame` back, to be written out once the GPU is done drawing it.
+    // Errors of frames recorded before come out here too
+    pub fn record(&mut self, gfx: &Gfx, frame: &Texture) -> Result<(), CaptureError> {
+        if let Ok(err) = self.failed.try_recv() {
+            return Err(err);
+        }
+
+        self.collect(gfx, self.in_flight.len() >= IN_FLIGHT)?;
+
+        let (readback, _) = Readback::new(gfx, frame, self.free.pop())?;
+        let path = self.dir.join(format!("{:06}.png", self.frames));
+        self.in_flight.push_back((path, readback));
+        self.frames += 1;
+        Ok(())
+    }
+
+    // Write out every frame still in flight, waiting for all of them,
+    // returning how many frames were recorded
+    pub fn finish(mut self, gfx: &Gfx) -> Result<u32, CaptureError> {
+        while !self.in_flight.is_empty() {
+            self.collect(gfx, true)?;
+        }
+
+        self.stop();
+        match self.failed.try_recv() {
+            Ok(err) => Err(err),
+            Err(_) => Ok(self.frames),
+        }
+    }
+
+    // Hand frames done copying back over to be encoded, in order,
+    // waiting for the GPU to be done with all of them if `wait`
+    fn collect(&mut self, gfx: &Gfx, wait: bool) -> Result<(), CaptureError> {
+        let maintain = if wait { Maintain::Wait } else { Maintain::Poll };
+        gfx.device.poll(maintain);
+
+        while let Some((path, readback)) = self.in_flight.pop_front() {
+            let Ok(mapped) = readback.mapped.try_recv() else {
+                self.in_flight.push_front((path, readback));
+                break;
+            };
+
+            mapped.map_err(CaptureError::Map)?;
+            let (width, height) = (readback.width, readback.height);
+            let pixels = readback.pixels();
+            self.free.push(readback.buffer);
+
+            // Gone only along with its errors, which come out on the next frame
+            if let Some(encode) = &self.encode {
+                let _ = encode.send((path, width, height, pixels));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Frames already handed over are still written out
+    fn stop(&mut self) {
+        drop(self.encode.take());
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn encode_frames(queue: &Receiver<(PathBuf, u32, u32, Vec<u8>)>, failed: &Sender<CaptureError>) {
+    for (path, width, height, pixels) in queue {
+        let file = File::create(path).map_err(CaptureError::Io);
+        let written = file.and_then(|file| write_png(BufWriter::new(file), width, height, &pixels));
+
+        if let Err(err) = written {
+            let _ = failed.send(err);
+        }
+    }
+}
+
+// A frame on its way back from the GPU, into a buffer to be read once `mapped`
+#[derive(Debug)]
+struct Readback {
+    buffer: Buffer,
+    mapped: Receiver<Result<(), BufferAsyncError>>,
+    width: u32,
+    height: u32,
+    bgra: bool,
+}
+
+impl Readback {
+    // Copy `frame` into `reused` if given and the right size, into a new buffer otherwise,
+    // along with the submission of the copy
+    fn new(
+        gfx: &Gfx,
+        frame: &Texture,
+        reused: Option<Buffer>,
+    ) -> Result<(Self, SubmissionIndex), CaptureError> {
+        let format = frame.format();
+        let bgra = match format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => false,
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => true,
+            _ => return Err(CaptureError::Format(format)),
+        };
+
+        if !frame.usage().contains(TextureUsages::COPY_SRC) {
+            return Err(CaptureError::NotCopyable);
+        }
+
+        // Copied rows must be aligned, so every row is padded up to it
+        let (width, height) = (frame.width(), frame.height());
+        let padded_row = padded_row(width);
+        let size = padded_row as u64 * height as u64;
+
+        let descriptor = BufferDescriptor {
+            label: Some("capture"),
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        };
+
+        let reused = reused.filter(|buffer| buffer.size() == size);
+        let buffer = reused.unwrap_or_else(|| gfx.device.create_buffer(&descriptor));
+
+        let source = ImageCopyTexture {
+            texture: frame,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        };
+
+        let destination = ImageCopyBuffer {
+            buffer: &buffer,
+            layout: ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        };
+
+        let descriptor = CommandEncoderDescriptor {
+            label: Some("capture"),
+        };
+
+        let mut encoder = gfx.device.create_command_encoder(&descriptor);
+        encoder.copy_texture_to_buffer(source, destination, frame.size());
+        let index = gfx.queue.submit([encoder.finish()]);
+
+        // Frames dropped while in flight are not waited for
+        let (sender, mapped) = mpsc::channel();
+        let slice = buffer.slice(..);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        let readback = Self {
+            buffer,
+            mapped,
+            width,
+            height,
+            bgra,
+        };
+
+        Ok((readback, index))
+    }
+
+    // Rows without their padding, as RGBA, unmapping the buffer for it to be reused.
+    // Only once mapped
+    fn pixels(&self) -> Vec<u8> {
+        let row = 4 * self.width as usize;
+        let mut pixels = Vec::with_capacity(row * self.height as usize);
+        let mapped = self.buffer.slice(..).get_mapped_range();
+        for padded in mapped.chunks_exact(padded_row(self.width) as usize) {
+            pixels.extend_from_slice(&padded[..row]);
+        }
+
+        drop(mapped);
+        self.buffer.unmap();
+
+        if self.bgra {
+            pixels.chunks_exact_mut(4).for_each(|bgra| bgra.swap(0, 2));
+        }
+
+        pixels
+    }
+}
+
+fn padded_row(width: u32) -> u32 {
+    (4 * width).next_multiple_of(COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+fn write_png(out: impl Write, width: u32, height: u32, pixels: &[u8]) -> Result<(), CaptureError> {
+    let mut encoder = Encoder::new(out, width, height);
+    encoder.set_color(ColorType::Rgba);
+    encoder.set_depth(BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(CaptureError::Encode)?;
+    writer
+        .write_image_data(pixels)
+        .map_err(CaptureError::Encode)
+}
+
+#[derive(Debug)]
+pub enum CaptureError {
+    // Only 8-bit RGBA and BGRA frames can be captured
+    Format(TextureFormat),
+
+    // The frame was created without `COPY_SRC`
+    NotCopyable,
+    Map(BufferAsyncError),
+    Encode(EncodingError),
+
+    // Recorded frames cannot be written into their directory
+    Io(io::Error),
+}
+
+impl Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Format(format) => write!(f, "cannot capture frames in {format:?}"),
+            Self::NotCopyable => write!(f, "frame cannot be copied from"),
+            Self::Map(err) => write!(f, "cannot read frame back: {err}"),
+            Self::Encode(err) => write!(f, "cannot encode frame: {err}"),
+            Self::Io(err) => write!(f, "cannot write frame: {err}"),
+        }
+    }
+}
+
+impl Error for CaptureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Map(err) => Some(err),
+            Self::Encode(err) => Some(err),
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use std::ops::Index;
+
+use wgpu::{
+    Color, CommandEncoderDescriptor, ComputePass, ComputePassDescriptor,
+    ComputePassTimestampWrites, Extent3d, LoadOp, Operations, RenderPass,
+    RenderPassColorAttachment, RenderPassDepthStencilAttachment, RenderPassDescriptor,
+    RenderPassTimestampWrites, StoreOp, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+};
+
+use super::Gfx;
+
+// A texture within a frame graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Slot(usize);
+
+// A texture only needed within a frame. Its usage follows from the passes using it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Transient {
+    pub format: TextureFormat,
+    pub sample_count: u32,
+    pub size: Extent3d,
+}
+
+// Surface-sized targets a scene draws into, see `Gfx::hdr_targets`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameTargets {
+    pub color: Slot,
+
+    // Single sampled color to resolve into, if `color` is multisampled
+    pub resolve: Option<Slot>,
+    pub depth: Slot,
+
+    // Of `color`, for pipelines drawing into it
+    pub format: TextureFormat,
+
+    // Of every target, for transients drawn along with them
+    pub size: Extent3d,
+}
+
+impl FrameTargets {
+    // Where color ends up once drawn
+    pub fn output(&self) -> Slot {
+        self.resolve.unwrap_or(self.color)
+    }
+}
+
+// Render passes are recorded in two steps: with `prepare` the graph hands
+// over the views of every texture, to create whatever `record` then borrows
+pub trait RenderNode {
+    fn prepare(&mut self, _gfx: &Gfx, _views: &Views) {}
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>);
+}
+
+// Same as `RenderNode`, for compute passes
+pub trait ComputeNode {
+    fn prepare(&mut self, _gfx: &Gfx, _views: &Views) {}
+    fn record<'p>(&'p self, pass: &mut ComputePass<'p>);
+}
+
+enum Node<'a> {
+    Render(Box<dyn RenderNode + 'a>),
+    Compute(Box<dyn ComputeNode + 'a>),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct ColorTarget {
+    slot: Slot,
+    resolve: Option<Slot>,
+    load: LoadOp<Color>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct DepthTarget {
+    slot: Slot,
+    load: LoadOp<f32>,
+}
+
+// A pass as declared, along with every texture it touches
+pub struct Pass<'a> {
+    label: &'static str,
+    node: Node<'a>,
+    colors: Vec<ColorTarget>,
+    depth: Option<DepthTarget>,
+    reads: Vec<Slot>,
+    writes: Vec<Slot>,
+}
+
+impl Pass<'_> {
+    // Attachments are written, and read too unless cleared
+    pub fn color(&mut self, slot: Slot, load: LoadOp<Color>) -> &mut Self {
+        self.color_resolved(slot, None, load)
+    }
+
+    // Color target resolved into `resolve` if multisampled, its samples being discarded
+    pub fn color_resolved(
+        &mut self,
+        slot: Slot,
+        resolve: Option<Slot>,
+        load: LoadOp<Color>,
+    ) -> &mut Self {
+        self.colors.push(ColorTarget {
+            slot,
+            resolve,
+            load,
+        });
+
+        self.touch(slot, matches!(load, LoadOp::Load));
+        self.writes.extend(resolve);
+        self
+    }
+
+    pub fn depth(&mut self, slot: Slot, load: LoadOp<f32>) -> &mut Self {
+        self.depth = Some(DepthTarget { slot, load });
+        self.touch(slot, matches!(load, LoadOp::Load));
+        self
+    }
+
+    // Draw into the targets of the frame, clearing depth
+    pub fn frame(&mut self, targets: FrameTargets, color: LoadOp<Color>) -> &mut Self {
+        self.color_resolved(targets.color, targets.resolve, color);
+        self.depth(targets.depth, LoadOp::Clear(1.0))
+    }
+
+    // Bound as a texture
+    pub fn read(&mut self, slot: Slot) -> &mut Self {
+        self.reads.push(slot);
+        self
+    }
+
+    // Bound as a storage texture
+    pub fn write(&mut self, slot: Slot) -> &mut Self {
+        self.writes.push(slot);
+        self
+    }
+
+    fn touch(&mut self, slot: Slot, loads: bool) {
+        if loads {
+            self.reads.push(slot);
+        }
+
+        self.writes.push(slot);
+    }
+
+    fn is_attached(&self, slot: Slot) -> bool {
+        let mut colors = self.colors.iter();
+        let is_color = colors.any(|color| color.slot == slot || color.resolve == Some(slot));
+        is_color || self.depth.is_some_and(|depth| depth.slot == slot)
+    }
+}
+
+enum GraphTexture<'a> {
+    Imported(&'a TextureView),
+    Transient(Transient),
+}
+
+// Passes of a frame, run by `Gfx::run` in the order they were declared.
+// Passes whose writes are never read are skipped, unless they write imported
+// textures or nothing at all, as then their effects are out of sight of the graph
+#[derive(Default)]
+pub struct Graph<'a> {
+    textures: Vec<GraphTexture<'a>>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A texture living outside the graph, such as the frame
+    pub fn import(&mut self, view: &'a TextureView) -> Slot {
+        self.textures.push(GraphTexture::Imported(view));
+        Slot(self.textures.len() - 1)
+    }
+
+    // A texture created for the frame, shared with other transients not in use at the same time
+    pub fn transient(&mut self, transient: Transient) -> Slot {
+        self.textures.push(GraphTexture::Transient(transient));
+        Slot(self.textures.len() - 1)
+    }
+
+    pub fn render(&mut self, label: &'static str, node: impl RenderNode + 'a) -> &mut Pass<'a> {
+        self.add(label, Node::Render(Box::new(node)))
+    }
+
+    pub fn compute(&mut self, label: &'static str, node: impl ComputeNode + 'a) -> &mut Pass<'a> {
+        self.add(label, Node::Compute(Box::new(node)))
+    }
+
+    fn add(&mut self, label: &'static str, node: Node<'a>) -> &mut Pass<'a> {
+        self.passes.push(Pass {
+            label,
+            node,
+            colors: Vec::new(),
+            depth: None,
+            reads: Vec::new(),
+            writes: Vec::new(),
+        });
+
+        self.passes.last_mut().unwrap()
+    }
+
+    // Whether every pass is needed, walking back from those that surely are
+    fn live_passes(&self) -> Vec<bool> {
+        let mut read = vec![false; self.textures.len()];
+        let mut live = vec![false; self.passes.len()];
+
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let is_needed = |&Slot(slot): &Slot| match self.textures[slot] {
+                GraphTexture::Imported(_) => true,
+                GraphTexture::Transient(_) => read[slot],
+            };
+
+            live[index] = pass.writes.is_empty() || pass.writes.iter().any(is_needed);
+
+            if live[index] {
+                for &Slot(slot) in &pass.reads {
+                    read[slot] = true;
+                }
+            }
+        }
+
+        live
+    }
+
+    pub(super) fn execute(self, gfx: &Gfx) {
+        let live = self.live_passes();
+        let mut passes: Vec<_> = self
+            .passes
+            .into_iter()
+            .zip(live)
+            .filter_map(|(pass, live)| live.then_some(pass))
+            .collect();
+
+        // Passes a transient is used from and until, and how
+        let mut spans = vec![None; self.textures.len()];
+        let mut usages = vec![TextureUsages::empty(); self.textures.len()];
+
+        for (index, pass) in passes.iter().enumerate() {
+            for &slot in pass.reads.iter().chain(&pass.writes) {
+                let first = spans[slot.0].map_or(index, |(first, _)| first);
+                spans[slot.0] = Some((first, index));
+
+                usages[slot.0] |= match (pass.is_attached(slot), &pass.node) {
+                    (true, _) => TextureUsages::RENDER_ATTACHMENT,
+                    (false, _) if pass.reads.contains(&slot) => TextureUsages::TEXTURE_BINDING,
+                    (false, Node::Compute(_)) => TextureUsages::STORAGE_BINDING,
+                    (false, Node::Render(_)) => TextureUsages::empty(),
+                };
+            }
+        }
+
+        // Hand every transient a texture, reusing those freed by earlier passes
+        // and, failing that, those left over from the previous frame
+        let mut pool = gfx.transients.take();
+        let mut textures: Vec<PooledTexture> = Vec::new();
+        let mut assigned = vec![None; self.textures.len()];
+
+        for index in 0..passes.len() {
+            for (slot, texture) in self.textures.iter().enumerate() {
+                let GraphTexture::Transient(transient) = *texture else {
+                    continue;
+                };
+
+                let Some((first, last)) = spans[slot] else {
+                    continue;
+                };
+
+                if first == index {
+                    let key = (transient, usages[slot]);
+                    let is_free = |pooled: &PooledTexture| pooled.free_after < index;
+
+                    let reused = textures
+                        .iter()
+                        .position(|pooled| pooled.key == key && is_free(pooled));
+
+                    let physical = reused.unwrap_or_else(|| {
+                        let kept = pool.iter().position(|pooled| pooled.key == key);
+                        let pooled = match kept {
+                            Some(kept) => pool.swap_remove(kept),
+                            None => PooledTexture::new(gfx, key),
+                        };
+
+                        textures.push(pooled);
+                        textures.len() - 1
+                    });
+
+                    textures[physical].free_after = last;
+                    assigned[slot] = Some(physical);
+                }
+            }
+        }
+
+        let views: Vec<_> = self
+            .textures
+            .iter()
+            .zip(&assigned)
+            .map(|(texture, assigned)| match (texture, assigned) {
+                (GraphTexture::Imported(view), _) => Some(*view),
+                (GraphTexture::Transient(_), &Some(physical)) => Some(&textures[physical].view),
+                (GraphTexture::Transient(_), None) => None,
+            })
+            .collect();
+
+        let views = Views(views);
+
+        for pass in &mut passes {
+            let _span = tracing::trace_span!("prepare", pass = pass.label).entered();
+            match &mut pass.node {
+                Node::Render(node) => node.prepare(gfx, &views),
+                Node::Compute(node) => node.prepare(gfx, &views),
+            }
+        }
+
+        let descriptor = CommandEncoderDescriptor {
+            label: Some("frame"),
+        };
+
+        let mut encoder = gfx.device.create_command_encoder(&descriptor);
+
+        // Every pass is timestamped at both ends, when profiling
+        let mut profiler = gfx.profiler.borrow_mut();
+        let queries = match profiler.as_mut() {
+            Some(profiler) => profiler.begin(&gfx.device, passes.len()),
+            None => None,
+        };
+
+        let profiled = queries.is_some();
+
+        for (index, pass) in passes.iter().enumerate() {
+            let _span = tracing::debug_span!("pass", pass = pass.label).entered();
+            let (beginning, end) = (2 * index as u32, 2 * index as u32 + 1);
+
+            match &pass.node {
+                Node::Render(node) => {
+                    let colors: Vec<_> = pass
+                        .colors
+                        .iter()
+                        .map(|color| {
+                            // Samples are only needed until resolved
+                            let store = match color.resolve {
+                                Some(_) => StoreOp::Discard,
+                                None => StoreOp::Store,
+                            };
+
+                            Some(RenderPassColorAttachment {
+                                view: &views[color.slot],
+                                resolve_target: color.resolve.map(|slot| &views[slot]),
+                                ops: Operations {
+                                    load: color.load,
+                                    store,
+                                },
+                            })
+                        })
+                        .collect();
+
+                    let depth = pass.depth.map(|depth| RenderPassDepthStencilAttachment {
+                        view: &views[depth.slot],
+                        depth_ops: Some(Operations {
+                            load: depth.load,
+                            store: StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    });
+
+                    let timestamp_writes = queries.map(|query_set| RenderPassTimestampWrites {
+                        query_set,
+                        beginning_of_pass_write_index: Some(beginning),
+                        end_of_pass_write_index: Some(end),
+                    });
+
+                    let descriptor = RenderPassDescriptor {
+                        label: Some(pass.label),
+                        color_attachments: &colors,
+                        depth_stencil_attachment: depth,
+                        timestamp_writes,
+                        occlusion_query_set: None,
+                    };
+
+                    let mut render_pass = encoder.begin_render_pass(&descriptor);
+                    node.record(&mut render_pass);
+                }
+                Node::Compute(node) => {
+                    let timestamp_writes = queries.map(|query_set| ComputePassTimestampWrites {
+                        query_set,
+                        beginning_of_pass_write_index: Some(beginning),
+                        end_of_pass_write_index: Some(end),
+                    });
+
+                    let descriptor = ComputePassDescriptor {
+                        label: Some(pass.label),
+                        timestamp_writes,
+                    };
+
+                    let mut compute_pass = encoder.begin_compute_pass(&descriptor);
+                    node.record(&mut compute_pass);
+                }
+            }
+        }
+
+        if let Some(profiler) = profiler.as_mut().filter(|_| profiled) {
+            let labels = passes.iter().map(|pass| pass.label).collect();
+            profiler.resolve(&mut encoder, labels);
+        }
+
+        gfx.queue.submit([encoder.finish()]);
+        gfx.frame.set(gfx.frame.get() + 1);
+
+        if let Some(profiler) = profiler.as_mut() {
+            profiler.read_back();
+        }
+
+        // Whatever went unused this frame is dropped
+        gfx.transients.replace(textures);
+    }
+}
+
+// Views of the textures of a graph, by slot
+pub struct Views<'v>(Vec<Option<&'v TextureView>>);
+
+impl<'v> Index<Slot> for Views<'v> {
+    type Output = TextureView;
+
+    // Panics for transients no pass uses
+    fn index(&self, Slot(slot): Slot) -> &TextureView {
+        self.0[slot].expect("transient used by no pass")
+    }
+}
+
+type PoolKey = (Transient, TextureUsages);
+
+#[derive(Debug)]
+pub(super) struct PooledTexture {
+    key: PoolKey,
+    view: TextureView,
+
+    // Last pass using it, within the frame being run
+    free_after: usize,
+    texture: Texture,
+}
+
+impl PooledTexture {
+    fn new(gfx: &Gfx, key: PoolKey) -> Self {
+        let (transient, usage) = key;
+
+        let descriptor = TextureDescriptor {
+            label: Some("transient"),
+            size: transient.size,
+            mip_level_count: 1,
+            sample_count: transient.sample_count,
+            dimension: TextureDimension::D2,
+            format: transient.format,
+            usage,
+            view_formats: &[],
+        };
+
+        let texture = gfx.device.create_texture(&descriptor);
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        Self {
+            key,
+            view,
+            free_after: 0,
+            texture,
+        }
+    }
+
+    pub(super) fn bytes(&self) -> u64 {
+        super::memory::texture_bytes(&self.texture)
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Display;
+
+use wgpu::Texture;
+
+// Bytes held on the GPU by what is kept track of, see `Gfx::memory_report`.
+// Drivers pad and align allocations on top of that, so take it as a lower bound
+#[derive(Clone, Debug, Default)]
+pub struct MemoryReport {
+    // Buffers by label, with the bytes in use out of their size
+    pub buffers: Vec<(&'static str, u64, u64)>,
+
+    // Textures kept across frames, by label
+    pub textures: Vec<(&'static str, u64)>,
+
+    // Pooled between frames, see `Graph::transient`
+    pub transients: u64,
+
+    // Buffers and textures wgpu itself counts as alive, if it can tell
+    pub live: Option<(usize, usize)>,
+}
+
+impl MemoryReport {
+    pub fn add_buffer(&mut self, label: &'static str, used: u64, size: u64) {
+        self.buffers.push((label, used, size));
+    }
+
+    pub fn add_texture(&mut self, label: &'static str, texture: &Texture) {
+        self.textures.push((label, texture_bytes(texture)));
+    }
+
+    pub fn total(&self) -> u64 {
+        let buffers = self.buffers.iter().map(|&(_, _, size)| size);
+        let textures = self.textures.iter().map(|&(_, size)| size);
+        buffers.chain(textures).sum::<u64>() + self.transients
+    }
+}
+
+impl Display for MemoryReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mib = |bytes: u64| bytes as f64 / (1 << 20) as f64;
+
+        for &(label, used, size) in &self.buffers {
+            writeln!(f, "{label}: {:.1} of {:.1} MiB", mib(used), mib(size))?;
+        }
+
+        for &(label, size) in &self.textures {
+            writeln!(f, "{label}: {:.1} MiB", mib(size))?;
+        }
+
+        writeln!(f, "transients: {:.1} MiB", mib(self.transients))?;
+
+        if let Some((buffers, textures)) = self.live {
+            writeln!(f, "live: {buffers} buffers, {textures} textures")?;
+        }
+
+        writeln!(f, "total: {:.1} MiB", mib(self.total()))
+    }
+}
+
+// Every mip level of every layer and sample, as tightly packed as the format allows
+pub(super) fn texture_bytes(texture: &Texture) -> u64 {
+    let format = texture.format();
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4) as u64;
+    let size = texture.size();
+
+    let levels = (0..texture.mip_level_count()).map(|level| {
+        let width = (size.width >> level).max(1).div_ceil(block_width);
+        let height = (size.height >> level).max(1).div_ceil(block_height);
+        width as u64 * height as u64 * block_size
+    });
+
+    let layers = size.depth_or_array_layers as u64 * texture.sample_count() as u64;
+    levels.sum::<u64>() * layers
+}
@@ -0,0 +1,169 @@
+use std::{
+    mem,
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use wgpu::{
+    Buffer, BufferAsyncError, BufferDescriptor, BufferUsages, CommandEncoder, Device, Maintain,
+    MapMode, QuerySet, QuerySetDescriptor, QueryType, Queue,
+};
+
+const TIMESTAMP_SIZE: u64 = mem::size_of::<u64>() as u64;
+
+// GPU time spent in every pass of a graph run, timestamped at both ends.
+// Timestamps come back a frame or more late, and graphs run while
+// the previous ones are being read back go without
+#[derive(Debug)]
+pub struct GpuProfiler {
+    queries: QuerySet,
+    resolve: Buffer,
+    readback: Buffer,
+
+    // Passes there are queries for
+    capacity: usize,
+
+    // Passes resolved but not submitted yet, and then those being read back
+    // along with the outcome of mapping their timestamps
+    resolved: Vec<&'static str>,
+    in_flight: Option<(Vec<&'static str>, Receiver<Result<(), BufferAsyncError>>)>,
+    times: Vec<(&'static str, Duration)>,
+
+    // Nanoseconds per timestamp tick
+    period: f32,
+}
+
+impl GpuProfiler {
+    pub(super) fn new(device: &Device, queue: &Queue) -> Self {
+        let capacity = 16;
+        let (queries, resolve, readback) = create_queries(device, capacity);
+
+        Self {
+            queries,
+            resolve,
+            readback,
+            capacity,
+            resolved: Vec::new(),
+            in_flight: None,
+            times: Vec::new(),
+            period: queue.get_timestamp_period(),
+        }
+    }
+
+    // Time taken by every pass of the last graph run read back, in order
+    pub fn times(&self) -> &[(&'static str, Duration)] {
+        &self.times
+    }
+
+    // Queries to timestamp `passes` passes with, two per pass,
+    // unless the previous ones are still being read back
+    pub(super) fn begin(&mut self, device: &Device, passes: usize) -> Option<&QuerySet> {
+        self.collect(device);
+
+        if self.in_flight.is_some() {
+            return None;
+        }
+
+        if passes > self.capacity {
+            self.capacity = passes.next_power_of_two();
+            (self.queries, self.resolve, self.readback) = create_queries(device, self.capacity);
+        }
+
+        Some(&self.queries)
+    }
+
+    // Copy the timestamps of the passes in `labels` out of the queries, once recorded
+    pub(super) fn resolve(&mut self, encoder: &mut CommandEncoder, labels: Vec<&'static str>) {
+        if labels.is_empty() {
+            return;
+        }
+
+        let count = 2 * labels.len() as u32;
+        let size = count as u64 * TIMESTAMP_SIZE;
+        encoder.resolve_query_set(&self.queries, 0..count, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, size);
+        self.resolved = labels;
+    }
+
+    // Start reading back what `resolve` copied, once submitted
+    pub(super) fn read_back(&mut self) {
+        if self.resolved.is_empty() {
+            return;
+        }
+
+        let labels = mem::take(&mut self.resolved);
+        let size = 2 * labels.len() as u64 * TIMESTAMP_SIZE;
+        let (sender, receiver) = mpsc::channel();
+
+        let slice = self.readback.slice(..size);
+        slice.map_async(MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+
+        self.in_flight = Some((labels, receiver));
+    }
+
+    // Read back the timestamps in flight, if mapped already
+    fn collect(&mut self, device: &Device) {
+        let Some((_, receiver)) = &self.in_flight else {
+            return;
+        };
+
+        device.poll(Maintain::Poll);
+
+        let Ok(mapped) = receiver.try_recv() else {
+            return;
+        };
+
+        let (labels, _) = self.in_flight.take().unwrap();
+
+        // Lost along with the device, most likely
+        if mapped.is_err() {
+            return;
+        }
+
+        let size = 2 * labels.len() as u64 * TIMESTAMP_SIZE;
+        let range = self.readback.slice(..size).get_mapped_range();
+        let ticks: &[u64] = bytemuck::cast_slice(&range);
+        let period = self.period as f64;
+
+        self.times = labels
+            .into_iter()
+            .zip(ticks.chunks_exact(2))
+            .map(|(label, ticks)| {
+                let nanos = ticks[1].saturating_sub(ticks[0]) as f64 * period;
+                (label, Duration::from_nanos(nanos as u64))
+            })
+            .collect();
+
+        drop(range);
+        self.readback.unmap();
+    }
+}
+
+fn create_queries(device: &Device, capacity: usize) -> (QuerySet, Buffer, Buffer) {
+    let count = 2 * capacity as u32;
+
+    let descriptor = QuerySetDescriptor {
+        label: Some("profiler"),
+        ty: QueryType::Timestamp,
+        count,
+    };
+
+    let queries = device.create_query_set(&descriptor);
+
+    let buffer = |usage| {
+        let descriptor = BufferDescriptor {
+            label: Some("profiler"),
+            size: count as u64 * TIMESTAMP_SIZE,
+            usage,
+            mapped_at_creation: false,
+        };
+
+        device.create_buffer(&descriptor)
+    };
+
+    let resolve = buffer(BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC);
+    let readback = buffer(BufferUsages::MAP_READ | BufferUsages::COPY_DST);
+    (queries, resolve, readback)
+}
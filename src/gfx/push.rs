@@ -0,0 +1,278 @@
+use std::{
+    borrow::Cow, cell::RefCell, error::Error, fmt::Display, marker::PhantomData, mem,
+    num::NonZeroU64, sync::Arc,
+};
+
+use bytemuck::Pod;
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, BindingType, Buffer, BufferBinding,
+    BufferBindingType, BufferDescriptor, BufferUsages, ComputePass, PipelineLayout,
+    PushConstantRange, RenderPass, ShaderModule, ShaderModuleDescriptor, ShaderSource,
+    ShaderStages,
+};
+
+use super::{Bindings, Gfx};
+
+// Slots the uniform fallback starts with room for, doubled whenever a frame needs more
+const INITIAL_SLOTS: u64 = 64;
+
+// Constants holding a `T`, seen by `stages`. They are pushed where the device
+// has room for them, and otherwise written into a uniform buffer bound in
+// `group`, one slot per write, for shaders to find them there instead, see
+// `source`. The device is checked up front, so setting them cannot go wrong later
+#[derive(Debug)]
+pub struct PushConstants<T> {
+    stages: ShaderStages,
+    group: u32,
+    fallback: Option<Fallback>,
+    constants: PhantomData<T>,
+}
+
+#[derive(Debug)]
+struct Fallback {
+    layout: Arc<BindGroupLayout>,
+
+    // Bound in the groups before `group` the pipeline leaves unused, see `pad`
+    empty: BindGroup,
+
+    // Bytes between slots, as dynamic offsets have to be aligned, and within each
+    stride: u64,
+    size: u32,
+    ring: RefCell<Ring>,
+}
+
+#[derive(Debug)]
+struct Ring {
+    buffer: Buffer,
+    group: Arc<BindGroup>,
+    slots: u64,
+
+    // Slots written during `frame`, started over on the next one
+    written: u64,
+    frame: u64,
+}
+
+// Constants written for a pass to set, see `PushConstants::write`
+#[derive(Debug)]
+pub enum Pushed<T> {
+    Constants(T),
+
+    // Bound along with where in the buffer they were written
+    Uniform(Arc<BindGroup>, u32),
+}
+
+impl<T: Pod> PushConstants<T> {
+    pub fn new(gfx: &Gfx, stages: ShaderStages, group: u32) -> Result<Self, PushConstantsError> {
+        let size = mem::size_of::<T>() as u32;
+        let capabilities = gfx.capabilities();
+        assert!(size % 4 == 0, "push constants must take whole words");
+
+        let native = capabilities.push_constants() && size <= capabilities.push_constant_size;
+        let fallback = match native {
+            true => None,
+            false => Some(Fallback::new(gfx, stages, group, size)?),
+        };
+
+        Ok(Self {
+            stages,
+            group,
+            fallback,
+            constants: PhantomData,
+        })
+    }
+
+    // Whether they end up in a uniform buffer rather than pushed
+    pub fn is_fallback(&self) -> bool {
+        self.fallback.is_some()
+    }
+
+    // Layout of pipelines using them along with `groups`, which have to come before `group`
+    pub fn pipeline_layout(&self, gfx: &Gfx, groups: &[&BindGroupLayout]) -> Arc<PipelineLayout> {
+        let Some(fallback) = &self.fallback else {
+            let range = PushConstantRange {
+                stages: self.stages,
+                range: 0..mem::size_of::<T>() as u32,
+            };
+
+            return gfx.pipeline_layout(groups, &[range]);
+        };
+
+        let empty = Bindings::new(self.stages).layout(gfx);
+        let padding = (groups.len()..self.group as usize).map(|_| &*empty);
+        let mut groups: Vec<_> = groups.iter().copied().chain(padding).collect();
+        groups.push(&fallback.layout);
+        gfx.pipeline_layout(&groups, &[])
+    }
+
+    // Shader source declaring them with `var<push_constant>`, made to read
+    // them out of the uniform buffer instead when falling back to it
+    pub fn source<'s>(&self, source: &'s str) -> Cow<'s, str> {
+        match self.fallback {
+            Some(_) => {
+                let uniform = format!("@group({}) @binding(0) var<uniform>", self.group);
+                Cow::Owned(source.replace("var<push_constant>", &uniform))
+            }
+            None => Cow::Borrowed(source),
+        }
+    }
+
+    pub fn module(&self, gfx: &Gfx, label: &str, source: &str) -> ShaderModule {
+        gfx.device.create_shader_module(ShaderModuleDescriptor {
+            label: Some(label),
+            source: ShaderSource::Wgsl(self.source(source)),
+        })
+    }
+
+    // Falling back, `constants` are written into a slot of their own, so
+    // every write has to be done while declaring the frame they are set in
+    pub fn write(&self, gfx: &Gfx, constants: T) -> Pushed<T> {
+        let Some(fallback) = &self.fallback else {
+            return Pushed::Constants(constants);
+        };
+
+        let mut ring = fallback.ring.borrow_mut();
+        if ring.frame != gfx.frame() {
+            ring.written = 0;
+            ring.frame = gfx.frame();
+        }
+
+        // Slots written earlier on keep the buffer they were written into
+        if ring.written == ring.slots {
+            let (layout, stride, size) = (&fallback.layout, fallback.stride, fallback.size);
+            ring.slots *= 2;
+            (ring.buffer, ring.group) = create_ring(gfx, layout, stride, size, ring.slots);
+        }
+
+        let offset = ring.written * fallback.stride;
+        let bytes = bytemuck::bytes_of(&constants);
+        gfx.queue.write_buffer(&ring.buffer, offset, bytes);
+        ring.written += 1;
+        Pushed::Uniform(ring.group.clone(), offset as u32)
+    }
+
+    pub fn set<'p>(&self, pass: &mut RenderPass<'p>, pushed: &'p Pushed<T>) {
+        match pushed {
+            Pushed::Constants(constants) => {
+                let bytes = bytemuck::bytes_of(constants);
+                pass.set_push_constants(self.stages, 0, bytes);
+            }
+            Pushed::Uniform(group, offset) => pass.set_bind_group(self.group, group, &[*offset]),
+        }
+    }
+
+    pub fn set_compute<'p>(&self, pass: &mut ComputePass<'p>, pushed: &'p Pushed<T>) {
+        match pushed {
+            Pushed::Constants(constants) => {
+                pass.set_push_constants(0, bytemuck::bytes_of(constants));
+            }
+            Pushed::Uniform(group, offset) => pass.set_bind_group(self.group, group, &[*offset]),
+        }
+    }
+
+    // Bind nothing in every group from `from` on up to `group`, for pipelines
+    // laid out with fewer groups than the fallback is bound after
+    pub fn pad<'p>(&'p self, pass: &mut RenderPass<'p>, from: u32) {
+        if let Some(fallback) = &self.fallback {
+            for index in from..self.group {
+                pass.set_bind_group(index, &fallback.empty, &[]);
+            }
+        }
+    }
+}
+
+impl Fallback {
+    fn new(
+        gfx: &Gfx,
+        stages: ShaderStages,
+        group: u32,
+        size: u32,
+    ) -> Result<Self, PushConstantsError> {
+        let limits = gfx.device.limits();
+        if group >= limits.max_bind_groups {
+            let limit = limits.max_bind_groups;
+            return Err(PushConstantsError::NoGroup { group, limit });
+        }
+
+        if size > limits.max_uniform_buffer_binding_size {
+            let limit = limits.max_uniform_buffer_binding_size;
+            return Err(PushConstantsError::TooLarge { size, limit });
+        }
+
+        let uniform = BindingType::Buffer {
+            ty: BufferBindingType::Uniform,
+            has_dynamic_offset: true,
+            min_binding_size: NonZeroU64::new(size as u64),
+        };
+
+        let layout = Bindings::new(stages).with(uniform).layout(gfx);
+        let empty = Bindings::new(stages).create(gfx, "empty", []).1;
+        let alignment = limits.min_uniform_buffer_offset_alignment as u64;
+        let stride = (size as u64).next_multiple_of(alignment);
+        let (buffer, group) = create_ring(gfx, &layout, stride, size, INITIAL_SLOTS);
+
+        let ring = Ring {
+            buffer,
+            group,
+            slots: INITIAL_SLOTS,
+            written: 0,
+            frame: gfx.frame(),
+        };
+
+        Ok(Self {
+            layout,
+            empty,
+            stride,
+            size,
+            ring: RefCell::new(ring),
+        })
+    }
+}
+
+// Buffer of `slots` slots `stride` bytes apart, bound `size` bytes at a time
+fn create_ring(
+    gfx: &Gfx,
+    layout: &BindGroupLayout,
+    stride: u64,
+    size: u32,
+    slots: u64,
+) -> (Buffer, Arc<BindGroup>) {
+    let buffer = gfx.device.create_buffer(&BufferDescriptor {
+        label: Some("push constants"),
+        size: stride * slots,
+        usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+
+    let binding = BindingResource::Buffer(BufferBinding {
+        buffer: &buffer,
+        offset: 0,
+        size: NonZeroU64::new(size as u64),
+    });
+
+    let group = gfx.bind_group("push constants", layout, [binding]);
+    (buffer, Arc::new(group))
+}
+
+#[derive(Debug)]
+pub enum PushConstantsError {
+    // Falling back to a uniform buffer, past the groups the device has
+    NoGroup { group: u32, limit: u32 },
+
+    // More bytes than the device has room for, be it pushed or in a uniform buffer
+    TooLarge { size: u32, limit: u32 },
+}
+
+impl Display for PushConstantsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoGroup { group, limit } => {
+                write!(f, "no group {group} for push constants, only {limit}")
+            }
+            Self::TooLarge { size, limit } => {
+                write!(f, "{size} bytes of push constants, only {limit} allowed")
+            }
+        }
+    }
+}
+
+impl Error for PushConstantsError {}
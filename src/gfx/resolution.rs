@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+// Fewest pixels drawn, as a share of the surface along each side
+const MIN_SCALE: f32 = 0.5;
+
+// Scales are kept to these steps, so targets are not recreated every frame
+const STEP: f32 = 0.05;
+
+// Weight of every new frame time in the running average
+const SMOOTHING: f32 = 0.1;
+
+// Frames are aimed a bit under budget, so spikes don't go over it right away
+const HEADROOM: f32 = 0.9;
+
+// Most the scale goes up by at once, while it goes down as far as needed
+const MAX_RISE: f32 = STEP;
+
+// Share of the surface the scene is drawn at, following how long frames take
+// against `budget`. Pixels drawn go with the square of the scale, so the scale
+// goes with the square root of the time to spare. It drops quickly when frames
+// run late, but only creeps back up so it doesn't keep going back and forth
+#[derive(Clone, Copy, Debug)]
+pub struct DynamicResolution {
+    budget: Duration,
+    average: Option<f32>,
+    scale: f32,
+}
+
+impl DynamicResolution {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            average: None,
+            scale: 1.0,
+        }
+    }
+
+    // Account for a frame at the current scale taking `time`, and get the scale to draw at next
+    pub fn update(&mut self, time: Duration) -> f32 {
+        let time = time.as_secs_f32();
+        let average = match self.average {
+            Some(average) => average + (time - average) * SMOOTHING,
+            None => time,
+        };
+
+        self.average = Some(average);
+
+        if average <= 0.0 {
+            return self.scale;
+        }
+
+        let budget = self.budget.as_secs_f32() * HEADROOM;
+        let wanted = self.scale * (budget / average).sqrt();
+        let wanted = wanted.min(self.scale + MAX_RISE).clamp(MIN_SCALE, 1.0);
+        let wanted = (wanted / STEP).round() * STEP;
+
+        // Frames at the new scale are guessed to take as long as their pixels
+        if (wanted - self.scale).abs() >= STEP / 2.0 {
+            let pixels = (wanted / self.scale).powi(2);
+            self.average = Some(average * pixels);
+            self.scale = wanted;
+        }
+
+        self.scale
+    }
+}
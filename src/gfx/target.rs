@@ -0,0 +1,89 @@
+use wgpu::{Extent3d, Texture, TextureFormat, TextureUsages, TextureView};
+
+use super::{create_depth, create_target, FrameTargets, Gfx, Graph};
+
+// Offscreen target a scene gets drawn into, to be sampled by later passes,
+// such as water reflections or block icons. Unlike transients it outlives the frame,
+// and is multisampled like `Gfx::hdr_targets`, so the same pipelines draw into either.
+// Create it again after changing the sample count
+#[derive(Debug)]
+pub struct RenderTarget {
+    label: &'static str,
+    format: TextureFormat,
+    size: Extent3d,
+    color: (Texture, TextureView),
+
+    // Single sampled color to resolve into, if `color` is multisampled
+    resolve: Option<(Texture, TextureView)>,
+    depth: (Texture, TextureView),
+}
+
+impl RenderTarget {
+    pub fn new(
+        gfx: &Gfx,
+        label: &'static str,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+    ) -> Self {
+        let (device, sample_count) = (&gfx.device, gfx.sample_count());
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        // Whichever ends up holding the color is read from and copied out of
+        let output = TextureUsages::RENDER_ATTACHMENT
+            | TextureUsages::TEXTURE_BINDING
+            | TextureUsages::COPY_SRC;
+
+        let (color, resolve) = match sample_count {
+            1 => (create_target(device, size, format, 1, output, label), None),
+            _ => {
+                let usage = TextureUsages::RENDER_ATTACHMENT;
+                let color = create_target(device, size, format, sample_count, usage, label);
+                let resolve = create_target(device, size, format, 1, output, label);
+                (color, Some(resolve))
+            }
+        };
+
+        Self {
+            label,
+            format,
+            size,
+            color,
+            resolve,
+            depth: create_depth(device, size, sample_count),
+        }
+    }
+
+    // To draw into within `graph`, as the scene would into `hdr_targets`
+    pub fn targets<'a>(&'a self, graph: &mut Graph<'a>) -> FrameTargets {
+        FrameTargets {
+            color: graph.import(&self.color.1),
+            resolve: self.resolve.as_ref().map(|(_, view)| graph.import(view)),
+            depth: graph.import(&self.depth.1),
+            format: self.format,
+            size: self.size,
+        }
+    }
+
+    // What got drawn, once resolved, to be bound or copied out of
+    pub fn output(&self) -> (&Texture, &TextureView) {
+        let (texture, view) = self.resolve.as_ref().unwrap_or(&self.color);
+        (texture, view)
+    }
+
+    pub const fn label(&self) -> &'static str {
+        self.label
+    }
+
+    pub const fn size(&self) -> Extent3d {
+        self.size
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.size.width as f32 / self.size.height as f32
+    }
+}
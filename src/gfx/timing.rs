@@ -0,0 +1,89 @@
+use std::{collections::VecDeque, time::Duration};
+
+// Frames kept, enough for the 0.1% lows to stand for more than a single frame
+const WINDOW: usize = 2048;
+
+// How long a run of frames took, over the last `WINDOW` of them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub average: Duration,
+
+    // Frame times only 1% and 0.1% of frames took longer than,
+    // as the frame rates behind "1% lows" and "0.1% lows"
+    pub low: Duration,
+    pub lowest: Duration,
+}
+
+impl Summary {
+    // Out of the frame times alone, in no particular order
+    fn new(times: &VecDeque<Duration>) -> Option<Self> {
+        let count = times.len() as u32;
+        let average = times.iter().sum::<Duration>().checked_div(count)?;
+
+        let mut sorted: Vec<_> = times.iter().copied().collect();
+        sorted.sort_unstable();
+
+        let percentile = |share: f32| {
+            let index = (sorted.len() as f32 * share) as usize;
+            sorted[index.min(sorted.len() - 1)]
+        };
+
+        Some(Self {
+            average,
+            low: percentile(0.99),
+            lowest: percentile(0.999),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Series {
+    times: VecDeque<Duration>,
+}
+
+impl Series {
+    fn push(&mut self, time: Duration) {
+        if self.times.len() == WINDOW {
+            self.times.pop_front();
+        }
+
+        self.times.push_back(time);
+    }
+}
+
+// Time between frames, spent on the CPU drawing each of them, and on the GPU
+// where it can be told, over the last few thousand frames
+#[derive(Clone, Debug, Default)]
+pub struct FrameTimes {
+    interval: Series,
+    cpu: Series,
+    gpu: Series,
+}
+
+impl FrameTimes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, interval: Duration, cpu: Duration, gpu: Option<Duration>) {
+        self.interval.push(interval);
+        self.cpu.push(cpu);
+
+        if let Some(gpu) = gpu {
+            self.gpu.push(gpu);
+        }
+    }
+
+    pub fn interval(&self) -> Option<Summary> {
+        Summary::new(&self.interval.times)
+    }
+
+    pub fn cpu(&self) -> Option<Summary> {
+        Summary::new(&self.cpu.times)
+    }
+
+    // None until some frame got timed on the GPU
+    pub fn gpu(&self) -> Option<Summary> {
+        Summary::new(&self.gpu.times)
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::{atomic::Ordering, Arc};
+
+use wgpu::{
+    PresentMode, Surface, SurfaceCapabilities, SurfaceConfiguration, SurfaceError, SurfaceTexture,
+    TextureFormat, TextureUsages,
+};
+use winit::{dpi::PhysicalSize, window::Window};
+
+use super::{pick_format, Gfx, GfxError};
+
+// Another window drawn into with the device of a `Gfx`, such as a map or an inspector.
+// Its surface is configured on its own, but `Gfx::hdr_targets` and friends follow
+// the main window, so scenes meant for this one go through a `RenderTarget` sized like it.
+// Configure it again after `Gfx::recover`, which leaves it on the lost device
+#[derive(Debug)]
+pub struct WindowSurface<'win> {
+    window: Arc<Window>,
+    surface: Surface<'win>,
+    config: SurfaceConfiguration,
+}
+
+impl<'win> WindowSurface<'win> {
+    // Presenting in the format of the main window if it can, so pipelines carry over
+    pub fn new(gfx: &Gfx<'win>, window: Arc<Window>) -> Result<Self, GfxError> {
+        let surface = gfx
+            .instance
+            .create_surface(window.clone())
+            .map_err(GfxError::Surface)?;
+
+        let SurfaceCapabilities {
+            formats,
+            alpha_modes,
+            ..
+        } = surface.get_capabilities(&gfx.adapter);
+
+        if formats.is_empty() {
+            return Err(GfxError::IncompatibleSurface);
+        }
+
+        let PhysicalSize { width, height } = window.inner_size();
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: pick_format(&formats, Some(gfx.format())),
+            width: width.max(1),
+            height: height.max(1),
+            present_mode: PresentMode::AutoVsync,
+            alpha_mode: alpha_modes[0],
+            view_formats: vec![],
+        };
+
+        let window_surface = Self {
+            window,
+            surface,
+            config,
+        };
+
+        window_surface.configure(gfx);
+        Ok(window_surface)
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub const fn format(&self) -> TextureFormat {
+        self.config.format
+    }
+
+    pub const fn size(&self) -> PhysicalSize<u32> {
+        PhysicalSize::new(self.config.width, self.config.height)
+    }
+
+    // Same as `Gfx::resize_viewport`, for this window alone
+    pub fn resize(&mut self, gfx: &Gfx, new_size: PhysicalSize<u32>) {
+        let PhysicalSize { width, height } = new_size;
+
+        if width * height > 0 {
+            self.config.width = width;
+            self.config.height = height;
+            self.configure(gfx);
+        }
+    }
+
+    pub fn configure(&self, gfx: &Gfx) {
+        self.surface.configure(&gfx.device, &self.config);
+    }
+
+    // Same as `Gfx::acquire_frame`, for this window alone
+    pub fn acquire_frame(&self, gfx: &Gfx) -> Option<SurfaceTexture> {
+        match self.surface.get_current_texture() {
+            Ok(frame) => Some(frame),
+            Err(SurfaceError::Timeout) => None,
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.configure(gfx);
+                None
+            }
+            Err(SurfaceError::OutOfMemory) => {
+                gfx.lost.store(true, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+}
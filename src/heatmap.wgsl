@@ -0,0 +1,55 @@
+// Occupancy of a buddy buffer, a texel per block of the smallest order
+// in rows of `columns` going up from the bottom left corner of the frame
+
+struct Heatmap {
+    // In pixels
+    frame: vec2<f32>,
+
+    // Texels in total, and in each row
+    cells: u32,
+    columns: u32,
+}
+
+var<push_constant> heatmap: Heatmap;
+
+// RGBA, 8 bits each with red the lowest
+@group(0) @binding(0) var<storage, read> colors: array<u32>;
+
+// Pixels between the frame edges and the heatmap
+const MARGIN = 4.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+
+    // Texels into the heatmap, rows going up
+    @location(0) texel: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> VertexOutput {
+    let rows = (heatmap.cells + heatmap.columns - 1u) / heatmap.columns;
+    let size = vec2(f32(heatmap.columns), f32(rows));
+    let corner = vec2(f32(vertex & 1u), f32(vertex >> 1u));
+
+    let texel = corner * size;
+    let screen = vec2(MARGIN + texel.x, heatmap.frame.y - MARGIN - texel.y);
+    let ndc = screen / heatmap.frame * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+
+    var out: VertexOutput;
+    out.position = vec4(ndc, 0.0, 1.0);
+    out.texel = texel;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = vec2<u32>(floor(in.texel));
+    let cell = texel.y * heatmap.columns + texel.x;
+
+    // The last row may be left short
+    if cell >= heatmap.cells {
+        discard;
+    }
+
+    return unpack4x8unorm(colors[cell]);
+}
@@ -0,0 +1,26 @@
+// Halves a level of the depth pyramid, keeping the farthest depth of every texel covered.
+// Prefixed with the `source` to read from, its `source_size` and a `load` of its texels
+
+@group(0) @binding(1) var destination: texture_storage_2d<r32float, write>;
+
+@compute @workgroup_size(8, 8)
+fn reduce(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(destination);
+    if any(id.xy >= size) {
+        return;
+    }
+
+    // Odd sizes leave a third row or column to the last texels
+    let source = source_size();
+    let low = id.xy * source / size;
+    let high = ((id.xy + 1u) * source + size - 1u) / size;
+
+    var farthest = 0.0;
+    for (var y = low.y; y < high.y; y++) {
+        for (var x = low.x; x < high.x; x++) {
+            farthest = max(farthest, load(vec2(x, y)));
+        }
+    }
+
+    textureStore(destination, id.xy, vec4(farthest));
+}
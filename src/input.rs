@@ -0,0 +1,407 @@
+mod gamepad;
+mod replay;
+
+use std::{collections::HashMap, error::Error, fmt::Display, fs, io, path::Path};
+
+use gilrs::Button;
+use winit::{
+    event::{ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+pub use self::{
+    gamepad::Gamepads,
+    replay::{Playback, Recorded, Replay, ReplayError},
+};
+
+// Whatever a key or button can be bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+
+    // Held along with moving, to go faster
+    Sprint,
+
+    Break,
+    Place,
+
+    // Go through the blocks that can be placed
+    NextBlock,
+
+    // Give back the pointer grabbed for looking around, pausing
+    ReleasePointer,
+
+    // Open and close the menu listing every binding
+    ToggleMenu,
+
+    // Go from flying to walking, rising being jumping, and back
+    ToggleWalk,
+
+    ToggleWireframe,
+    ToggleHud,
+    NextView,
+    ToggleFullscreen,
+    ReportMemory,
+
+    // Stream chunks in a chunk farther away, or a chunk less far
+    RenderFarther,
+    RenderNearer,
+
+    // Save the next frame as a PNG, or every frame until told to stop
+    Screenshot,
+    ToggleRecording,
+
+    // Save the camera, time of day and chunks with their quads as they are,
+    // or go back to how they were when last saved
+    SaveSnapshot,
+    LoadSnapshot,
+}
+
+impl Action {
+    pub const ALL: [Self; 24] = [
+        Self::MoveForward,
+        Self::MoveBack,
+        Self::MoveLeft,
+        Self::MoveRight,
+        Self::MoveUp,
+        Self::MoveDown,
+        Self::Sprint,
+        Self::Break,
+        Self::Place,
+        Self::NextBlock,
+        Self::ReleasePointer,
+        Self::ToggleMenu,
+        Self::ToggleWalk,
+        Self::ToggleWireframe,
+        Self::ToggleHud,
+        Self::NextView,
+        Self::ToggleFullscreen,
+        Self::ReportMemory,
+        Self::RenderFarther,
+        Self::RenderNearer,
+        Self::Screenshot,
+        Self::ToggleRecording,
+        Self::SaveSnapshot,
+        Self::LoadSnapshot,
+    ];
+
+    // As written in bindings files
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::MoveForward => "move_forward",
+            Self::MoveBack => "move_back",
+            Self::MoveLeft => "move_left",
+            Self::MoveRight => "move_right",
+            Self::MoveUp => "move_up",
+            Self::MoveDown => "move_down",
+            Self::Sprint => "sprint",
+            Self::Break => "break",
+            Self::Place => "place",
+            Self::NextBlock => "next_block",
+            Self::ReleasePointer => "release_pointer",
+            Self::ToggleMenu => "toggle_menu",
+            Self::ToggleWalk => "toggle_walk",
+            Self::ToggleWireframe => "toggle_wireframe",
+            Self::ToggleHud => "toggle_hud",
+            Self::NextView => "next_view",
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::ReportMemory => "report_memory",
+            Self::RenderFarther => "render_farther",
+            Self::RenderNearer => "render_nearer",
+            Self::Screenshot => "screenshot",
+            Self::ToggleRecording => "toggle_recording",
+            Self::SaveSnapshot => "save_snapshot",
+            Self::LoadSnapshot => "load_snapshot",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+// A key, by where it is rather than what it types, a mouse button or a gamepad button
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Pad(Button),
+}
+
+impl Input {
+    // Keys and gamepad buttons by their names in `KEYS` and `PAD_BUTTONS`,
+    // mouse buttons as mouse_left, mouse_right or mouse_middle
+    pub fn by_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+
+        let button = match name.as_str() {
+            "mouse_left" => MouseButton::Left,
+            "mouse_right" => MouseButton::Right,
+            "mouse_middle" => MouseButton::Middle,
+            _ => {
+                let key = KEYS.iter().find(|&&(key, _)| key == name);
+                let key = key.map(|&(_, code)| Self::Key(code));
+                let pad = PAD_BUTTONS.iter().find(|&&(pad, _)| pad == name);
+                return key.or(pad.map(|&(_, button)| Self::Pad(button)));
+            }
+        };
+
+        Some(Self::Mouse(button))
+    }
+
+    // As `by_name` takes it, if it has a name at all
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Key(code) => {
+                let key = KEYS.iter().find(|&&(_, key)| key == code);
+                key.map(|&(name, _)| name)
+            }
+            Self::Mouse(MouseButton::Left) => Some("mouse_left"),
+            Self::Mouse(MouseButton::Right) => Some("mouse_right"),
+            Self::Mouse(MouseButton::Middle) => Some("mouse_middle"),
+            Self::Mouse(_) => None,
+            Self::Pad(button) => {
+                let pad = PAD_BUTTONS.iter().find(|&&(_, pad)| pad == button);
+                pad.map(|&(name, _)| name)
+            }
+        }
+    }
+}
+
+// Names of the keys that can be bound, as on a US layout
+#[rustfmt::skip]
+const KEYS: &[(&str, KeyCode)] = &[
+    ("a", KeyCode::KeyA), ("b", KeyCode::KeyB), ("c", KeyCode::KeyC), ("d", KeyCode::KeyD),
+    ("e", KeyCode::KeyE), ("f", KeyCode::KeyF), ("g", KeyCode::KeyG), ("h", KeyCode::KeyH),
+    ("i", KeyCode::KeyI), ("j", KeyCode::KeyJ), ("k", KeyCode::KeyK), ("l", KeyCode::KeyL),
+    ("m", KeyCode::KeyM), ("n", KeyCode::KeyN), ("o", KeyCode::KeyO), ("p", KeyCode::KeyP),
+    ("q", KeyCode::KeyQ), ("r", KeyCode::KeyR), ("s", KeyCode::KeyS), ("t", KeyCode::KeyT),
+    ("u", KeyCode::KeyU), ("v", KeyCode::KeyV), ("w", KeyCode::KeyW), ("x", KeyCode::KeyX),
+    ("y", KeyCode::KeyY), ("z", KeyCode::KeyZ),
+
+    ("0", KeyCode::Digit0), ("1", KeyCode::Digit1), ("2", KeyCode::Digit2),
+    ("3", KeyCode::Digit3), ("4", KeyCode::Digit4), ("5", KeyCode::Digit5),
+    ("6", KeyCode::Digit6), ("7", KeyCode::Digit7), ("8", KeyCode::Digit8),
+    ("9", KeyCode::Digit9),
+
+    ("f1", KeyCode::F1), ("f2", KeyCode::F2), ("f3", KeyCode::F3), ("f4", KeyCode::F4),
+    ("f5", KeyCode::F5), ("f6", KeyCode::F6), ("f7", KeyCode::F7), ("f8", KeyCode::F8),
+    ("f9", KeyCode::F9), ("f10", KeyCode::F10), ("f11", KeyCode::F11), ("f12", KeyCode::F12),
+
+    ("space", KeyCode::Space), ("enter", KeyCode::Enter), ("tab", KeyCode::Tab),
+    ("escape", KeyCode::Escape), ("backspace", KeyCode::Backspace),
+    ("backquote", KeyCode::Backquote),
+
+    ("left_shift", KeyCode::ShiftLeft), ("right_shift", KeyCode::ShiftRight),
+    ("left_ctrl", KeyCode::ControlLeft), ("right_ctrl", KeyCode::ControlRight),
+    ("left_alt", KeyCode::AltLeft), ("right_alt", KeyCode::AltRight),
+
+    ("up", KeyCode::ArrowUp), ("down", KeyCode::ArrowDown),
+    ("left", KeyCode::ArrowLeft), ("right", KeyCode::ArrowRight),
+
+    ("insert", KeyCode::Insert), ("delete", KeyCode::Delete),
+    ("home", KeyCode::Home), ("end", KeyCode::End),
+    ("page_up", KeyCode::PageUp), ("page_down", KeyCode::PageDown),
+];
+
+// Names of the gamepad buttons that can be bound, with the face buttons
+// by where they are, as they are labeled differently on every gamepad
+#[rustfmt::skip]
+const PAD_BUTTONS: &[(&str, Button)] = &[
+    ("pad_south", Button::South), ("pad_east", Button::East),
+    ("pad_north", Button::North), ("pad_west", Button::West),
+
+    ("pad_left_bumper", Button::LeftTrigger), ("pad_right_bumper", Button::RightTrigger),
+    ("pad_left_trigger", Button::LeftTrigger2), ("pad_right_trigger", Button::RightTrigger2),
+    ("pad_left_stick", Button::LeftThumb), ("pad_right_stick", Button::RightThumb),
+
+    ("pad_select", Button::Select), ("pad_start", Button::Start),
+
+    ("pad_up", Button::DPadUp), ("pad_down", Button::DPadDown),
+    ("pad_left", Button::DPadLeft), ("pad_right", Button::DPadRight),
+];
+
+#[rustfmt::skip]
+const DEFAULTS: &[(Action, &str)] = &[
+    (Action::MoveForward, "w"),
+    (Action::MoveBack, "s"),
+    (Action::MoveLeft, "a"),
+    (Action::MoveRight, "d"),
+    (Action::MoveUp, "space"),
+    (Action::MoveDown, "left_shift"),
+    (Action::Sprint, "left_ctrl"),
+    (Action::Break, "mouse_left"),
+    (Action::Place, "mouse_right"),
+    (Action::NextBlock, "q"),
+    (Action::ReleasePointer, "escape"),
+    (Action::ToggleMenu, "tab"),
+    (Action::ToggleWalk, "f"),
+    (Action::ToggleWireframe, "f1"),
+    (Action::ToggleHud, "h"),
+    (Action::Screenshot, "f2"),
+    (Action::NextView, "f3"),
+    (Action::ReportMemory, "f4"),
+    (Action::RenderFarther, "page_up"),
+    (Action::RenderNearer, "page_down"),
+    (Action::SaveSnapshot, "f5"),
+    (Action::LoadSnapshot, "f8"),
+    (Action::ToggleRecording, "f9"),
+    (Action::ToggleFullscreen, "f11"),
+
+    // Sticks are not bound, flying and looking around as they are
+    (Action::MoveUp, "pad_south"),
+    (Action::MoveDown, "pad_east"),
+    (Action::Sprint, "pad_left_stick"),
+    (Action::Break, "pad_right_trigger"),
+    (Action::Place, "pad_left_trigger"),
+    (Action::NextBlock, "pad_west"),
+    (Action::ToggleWalk, "pad_right_stick"),
+    (Action::ToggleWireframe, "pad_north"),
+    (Action::ToggleHud, "pad_start"),
+    (Action::NextView, "pad_select"),
+];
+
+// Which action every bound input stands for. Any number of inputs
+// can stand for the same action, but each input stands for one action alone
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    actions: HashMap<Input, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let actions = DEFAULTS.iter().map(|&(action, name)| {
+            let input = Input::by_name(name).expect("default bound to an unknown input");
+            (input, action)
+        });
+
+        Self {
+            actions: actions.collect(),
+        }
+    }
+}
+
+impl Bindings {
+    // The defaults, with the actions in the file bound as it says instead
+    pub fn load(path: &Path) -> Result<Self, BindingsError> {
+        let text = fs::read_to_string(path).map_err(BindingsError::Io)?;
+        Self::parse(&text)
+    }
+
+    // The defaults, with the actions in `text` bound as it says instead.
+    // Every line binds an action to a list of inputs, as in
+    //
+    //     # Arrows to move around
+    //     move_forward = up, w
+    //     break = mouse_left
+    //
+    // Actions bound to nothing at all, as in `place =`, are left unbound
+    pub fn parse(text: &str) -> Result<Self, BindingsError> {
+        let mut bindings = Self::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((name, inputs)) = line.split_once('=') else {
+                return Err(BindingsError::Syntax(number));
+            };
+
+            let name = name.trim();
+            let action = Action::by_name(name);
+            let action = action.ok_or_else(|| BindingsError::Action(number, name.to_owned()))?;
+
+            let mut bound = Vec::new();
+            let inputs = inputs.split(',').map(str::trim);
+            for name in inputs.filter(|name| !name.is_empty()) {
+                let input = Input::by_name(name);
+                let input = input.ok_or_else(|| BindingsError::Input(number, name.to_owned()))?;
+                bound.push(input);
+            }
+
+            bindings.rebind(action, &bound);
+        }
+
+        Ok(bindings)
+    }
+
+    // Bind `action` to `inputs` alone, taking them from whatever they stood for
+    pub fn rebind(&mut self, action: Action, inputs: &[Input]) {
+        self.actions.retain(|_, bound| *bound != action);
+        for &input in inputs {
+            self.actions.insert(input, action);
+        }
+    }
+
+    pub fn action(&self, input: Input) -> Option<Action> {
+        self.actions.get(&input).copied()
+    }
+
+    // Inputs bound to `action`, in no particular order
+    pub fn inputs(&self, action: Action) -> impl Iterator<Item = Input> + '_ {
+        let actions = self.actions.iter();
+        actions.filter_map(move |(&input, &bound)| (bound == action).then_some(input))
+    }
+
+    // The action pressed or released by `event`, if it is bound to any.
+    // Keys held down repeating themselves press nothing again
+    pub fn translate(&self, event: &WindowEvent) -> Option<(Action, ElementState)> {
+        let (input, state) = match event {
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        repeat: false,
+                        ..
+                    },
+                ..
+            } => (Input::Key(*code), *state),
+            WindowEvent::MouseInput { state, button, .. } => (Input::Mouse(*button), *state),
+            _ => return None,
+        };
+
+        Some((self.action(input)?, state))
+    }
+}
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(io::Error),
+
+    // Line number of a line with no `=` in it
+    Syntax(usize),
+
+    // Line number, and the action or input in it that does not exist
+    Action(usize, String),
+    Input(usize, String),
+}
+
+impl Display for BindingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read bindings: {err}"),
+            Self::Syntax(line) => write!(f, "bindings line {line}: expected action = inputs"),
+            Self::Action(line, name) => write!(f, "bindings line {line}: unknown action {name}"),
+            Self::Input(line, name) => write!(f, "bindings line {line}: unknown input {name}"),
+        }
+    }
+}
+
+impl Error for BindingsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use gilrs::{Axis, Event, EventType, Gilrs};
+use winit::event::ElementState;
+
+use super::{Action, Bindings, Input};
+
+// Sticks closer to the center than this are taken as centered
+const DEAD_ZONE: f32 = 0.15;
+
+// Every gamepad plugged in, as one. Buttons go through the same bindings
+// as keys do, while sticks are read as they are, for flying and looking around
+#[derive(Debug)]
+pub struct Gamepads {
+    gilrs: Gilrs,
+
+    // Left and right sticks, x right and y up, from -1 to 1
+    sticks: [[f32; 2]; 2],
+}
+
+impl Gamepads {
+    pub fn new() -> Result<Self, gilrs::Error> {
+        let gamepads = Self {
+            gilrs: Gilrs::new()?,
+            sticks: [[0.0; 2]; 2],
+        };
+
+        Ok(gamepads)
+    }
+
+    // Actions pressed or released since last time, as bound in `bindings`,
+    // keeping track of the sticks along the way
+    pub fn poll(&mut self, bindings: &Bindings) -> Vec<(Action, ElementState)> {
+        let mut actions = Vec::new();
+
+        while let Some(Event { event, .. }) = self.gilrs.next_event() {
+            let (button, state) = match event {
+                EventType::ButtonPressed(button, _) => (button, ElementState::Pressed),
+                EventType::ButtonReleased(button, _) => (button, ElementState::Released),
+                EventType::AxisChanged(axis, value, _) => {
+                    let (stick, component) = match axis {
+                        Axis::LeftStickX => (0, 0),
+                        Axis::LeftStickY => (0, 1),
+                        Axis::RightStickX => (1, 0),
+                        Axis::RightStickY => (1, 1),
+                        _ => continue,
+                    };
+
+                    self.sticks[stick][component] = value;
+                    continue;
+                }
+
+                // Nothing is left to hold sticks away from the center
+                EventType::Disconnected => {
+                    self.sticks = [[0.0; 2]; 2];
+                    continue;
+                }
+                _ => continue,
+            };
+
+            if let Some(action) = bindings.action(Input::Pad(button)) {
+                actions.push((action, state));
+            }
+        }
+
+        actions
+    }
+
+    // Left and right sticks, past the dead zone
+    pub fn sticks(&self) -> [[f32; 2]; 2] {
+        let dead_zone = |c: f32| if c.abs() < DEAD_ZONE { 0.0 } else { c };
+        self.sticks.map(|stick| stick.map(dead_zone))
+    }
+}
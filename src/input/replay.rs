@@ -0,0 +1,341 @@
+use std::{
+    error::Error,
+    fmt::{Display, Write as _},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+use super::Action;
+
+// Input as it came in between two fixed steps, already translated into actions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Recorded {
+    // Pressed if true, released otherwise
+    Action(Action, bool),
+
+    // How far the mouse moved, as in `DeviceEvent::MouseMotion`
+    Look([f64; 2]),
+
+    // Both sticks from then on, as `Gamepads::sticks` has them
+    Sticks([[f32; 2]; 2]),
+}
+
+// Input recorded along with the fixed step it came in before, and what it
+// started out from: the seed of the world and where the camera was. Played back
+// step by step, it flies the same way whatever the frame rate.
+//
+// Written out as text, a line at a time, as in
+//
+//     seed 42
+//     speed 10
+//     eye 64 40 16
+//     facing 3.1415927 -0.5
+//     length 300
+//     0 press move_forward
+//     12 look -3 1.5
+//     20 sticks 0 1 0 0
+//     45 release move_forward
+//
+// where events go in order, after the step they came in before
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u64,
+
+    // Flying speed, in blocks per second
+    pub speed: f32,
+
+    pub eye: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+
+    // Steps taken by the time recording stopped
+    pub length: u64,
+
+    pub events: Vec<(u64, Recorded)>,
+}
+
+impl Replay {
+    pub const fn new(seed: u64, speed: f32, eye: [f32; 3], yaw: f32, pitch: f32) -> Self {
+        Self {
+            seed,
+            speed,
+            eye,
+            yaw,
+            pitch,
+            length: 0,
+            events: Vec::new(),
+        }
+    }
+
+    // Record `event` as coming in before step `tick`, no earlier than any recorded before
+    pub fn push(&mut self, tick: u64, event: Recorded) {
+        debug_assert!(self.events.last().is_none_or(|&(last, _)| last <= tick));
+        self.length = self.length.max(tick);
+        self.events.push((tick, event));
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let text = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let mut header = Header::default();
+        let mut events: Vec<(u64, Recorded)> = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(first) = words.next() else {
+                continue;
+            };
+
+            let words: Vec<_> = words.collect();
+            let syntax = ReplayError::Syntax(number);
+            let Ok(tick) = first.parse::<u64>() else {
+                header.set(first, &words).ok_or(syntax)?;
+                continue;
+            };
+
+            let event = match words[..] {
+                ["press" | "release", name] => {
+                    let action = Action::by_name(name);
+                    let action = action.ok_or_else(|| ReplayError::Action(number, name.into()))?;
+                    Recorded::Action(action, words[0] == "press")
+                }
+                ["look", ..] => Recorded::Look(numbers(&words[1..]).ok_or(syntax)?),
+                ["sticks", ..] => {
+                    let [a, b, c, d] = numbers(&words[1..]).ok_or(syntax)?;
+                    Recorded::Sticks([[a, b], [c, d]])
+                }
+                _ => return Err(syntax),
+            };
+
+            if events.last().is_some_and(|&(last, _)| last > tick) {
+                return Err(ReplayError::Order(number));
+            }
+
+            events.push((tick, event));
+        }
+
+        let Header {
+            seed: Some(seed),
+            speed: Some(speed),
+            eye: Some(eye),
+            facing: Some([yaw, pitch]),
+            length,
+        } = header
+        else {
+            return Err(ReplayError::Header);
+        };
+
+        let mut replay = Self::new(seed, speed, eye, yaw, pitch);
+        replay.events = events;
+        replay.length = length.unwrap_or_default();
+        if let Some(&(last, _)) = replay.events.last() {
+            replay.length = replay.length.max(last);
+        }
+
+        Ok(replay)
+    }
+}
+
+// Floats are written out in full, so they read back to the same bits
+impl Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y, z] = self.eye;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "speed {}", self.speed)?;
+        writeln!(f, "eye {x} {y} {z}")?;
+        writeln!(f, "facing {} {}", self.yaw, self.pitch)?;
+        writeln!(f, "length {}", self.length)?;
+
+        let mut line = String::new();
+        for (tick, event) in &self.events {
+            line.clear();
+            match event {
+                Recorded::Action(action, pressed) => {
+                    let verb = if *pressed { "press" } else { "release" };
+                    write!(line, "{verb} {}", action.name())?;
+                }
+                Recorded::Look([dx, dy]) => write!(line, "look {dx} {dy}")?,
+                Recorded::Sticks([[a, b], [c, d]]) => write!(line, "sticks {a} {b} {c} {d}")?,
+            }
+
+            writeln!(f, "{tick} {line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct Header {
+    seed: Option<u64>,
+    speed: Option<f32>,
+    eye: Option<[f32; 3]>,
+    facing: Option<[f32; 2]>,
+    length: Option<u64>,
+}
+
+impl Header {
+    fn set(&mut self, key: &str, words: &[&str]) -> Option<()> {
+        match (key, words) {
+            ("seed", [seed]) => self.seed = Some(seed.parse().ok()?),
+            ("speed", [speed]) => self.speed = Some(speed.parse().ok()?),
+            ("length", [length]) => self.length = Some(length.parse().ok()?),
+            ("eye", _) => self.eye = Some(numbers(words)?),
+            ("facing", _) => self.facing = Some(numbers(words)?),
+            _ => return None,
+        }
+
+        Some(())
+    }
+}
+
+// Exactly `N` numbers, or none
+fn numbers<T: FromStr + Copy + Default, const N: usize>(words: &[&str]) -> Option<[T; N]> {
+    if words.len() != N {
+        return None;
+    }
+
+    let mut numbers = [T::default(); N];
+    for (number, word) in numbers.iter_mut().zip(words) {
+        *number = word.parse().ok()?;
+    }
+
+    Some(numbers)
+}
+
+// A replay being played, handing events out as the steps they came in before are reached
+#[derive(Debug)]
+pub struct Playback {
+    replay: Replay,
+    next: usize,
+}
+
+impl Playback {
+    pub const fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+
+    pub const fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    // Step the next events not handed out yet came in before, if any are left
+    pub fn next_tick(&self) -> Option<u64> {
+        self.replay.events.get(self.next).map(|&(tick, _)| tick)
+    }
+
+    // Events not handed out yet that came in before step `tick` or earlier, in order.
+    // Input comes in batches of sticks and looks followed by actions, which are
+    // handed out one at a time, for actions to see the camera as they did
+    pub fn due(&mut self, tick: u64) -> impl Iterator<Item = Recorded> + '_ {
+        let left = &self.replay.events[self.next..];
+        let mut acted = false;
+        let batch = left.iter().position(|&(at, event)| {
+            let action = matches!(event, Recorded::Action(..));
+            let past = at > tick || (acted && !action);
+            acted |= action;
+            past
+        });
+
+        let due = batch.unwrap_or(left.len());
+        self.next += due;
+        left[..due].iter().map(|&(_, event)| event)
+    }
+
+    // Whether every event was handed out and `tick` is as far as recording went
+    pub fn is_done(&self, tick: u64) -> bool {
+        self.next == self.replay.events.len() && tick >= self.replay.length
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+
+    // Line number of a line that is neither part of the header nor an event
+    Syntax(usize),
+
+    // Line number, and the action in it that does not exist
+    Action(usize, String),
+
+    // Line number of an event coming before the one above it
+    Order(usize),
+
+    // Seed, speed, eye or facing missing
+    Header,
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read replay: {err}"),
+            Self::Syntax(line) => write!(f, "replay line {line}: expected a setting or an event"),
+            Self::Action(line, name) => write!(f, "replay line {line}: unknown action {name}"),
+            Self::Order(line) => write!(f, "replay line {line}: event out of order"),
+            Self::Header => write!(f, "replay is missing its seed, speed, eye or facing"),
+        }
+    }
+}
+
+impl Error for ReplayError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new(42, 10.0, [64.0, 40.0, 16.0], 3.0, -0.1);
+        replay.push(0, Recorded::Action(Action::MoveForward, true));
+        replay.push(3, Recorded::Sticks([[0.0, 1.0 / 3.0], [-0.25, 0.0]]));
+        replay.push(3, Recorded::Look([-3.0, 0.1]));
+        replay.push(3, Recorded::Action(Action::Break, true));
+        replay.push(3, Recorded::Look([1.0, 0.0]));
+        replay.push(9, Recorded::Action(Action::MoveForward, false));
+        replay.length = 20;
+        replay
+    }
+
+    #[test]
+    fn replays_read_back_as_written() {
+        let replay = replay();
+        assert_eq!(Replay::parse(&replay.to_string()).unwrap(), replay);
+
+        let swapped = "seed 1\nspeed 1\neye 0 0 0\nfacing 0 0\n5 press break\n4 press place\n";
+        assert!(matches!(Replay::parse(swapped), Err(ReplayError::Order(6))));
+        let headless = Replay::parse("seed 1\n");
+        assert!(matches!(headless, Err(ReplayError::Header)));
+    }
+
+    #[test]
+    fn events_are_handed_out_once_due() {
+        let mut playback = Playback::new(replay());
+        assert_eq!(playback.due(0).count(), 1);
+        assert_eq!(playback.due(2).count(), 0);
+        assert_eq!(playback.next_tick(), Some(3));
+        assert_eq!(playback.due(8).count(), 3);
+        let looked: Vec<_> = playback.due(8).collect();
+        assert_eq!(looked, [Recorded::Look([1.0, 0.0])]);
+        assert_eq!(playback.due(9).count(), 1);
+
+        assert_eq!(playback.next_tick(), None);
+        assert!(!playback.is_done(19));
+        assert!(playback.is_done(20));
+    }
+}
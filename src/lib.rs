@@ -0,0 +1,27 @@
+// The pieces of a voxel engine, for the binaries here or any other project to build on:
+// a buddy allocator for GPU buffers, meshers for chunks of blocks, the wgpu plumbing
+// and the renderer put together out of all of them
+
+#![feature(iter_collect_into)]
+#![feature(new_uninit)]
+
+pub mod assets;
+pub mod bench;
+pub mod buddy;
+pub mod camera;
+pub mod color;
+pub mod config;
+pub mod demo;
+pub mod entity;
+pub mod geometry;
+pub mod gfx;
+pub mod input;
+pub mod math;
+pub mod mesh;
+pub mod net;
+pub mod player;
+pub mod renderer;
+pub mod screen;
+pub mod state;
+pub mod textures;
+pub mod world;
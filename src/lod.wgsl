@@ -0,0 +1,53 @@
+// Picks a level of detail for every chunk drawn indirectly by how far it is from the eye,
+// drawing the level picked in full and the others not at all
+
+struct Lod {
+    eye: vec3<f32>,
+
+    // Chunks packed, a record per level of each
+    count: u32,
+
+    // Blocks from the eye to where every coarser level takes over from the one before
+    distance: f32,
+}
+
+var<push_constant> lod: Lod;
+
+// As in quad.wgsl
+struct Chunk {
+    origin: vec3<f32>,
+    first: u32,
+    ends: array<u32, 6>,
+    palette: u32,
+    lod: u32,
+}
+
+// As in `DrawIndirectArgs`
+struct DrawArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+@group(0) @binding(0) var<storage, read> chunks: array<Chunk>;
+@group(0) @binding(1) var<storage, read_write> draws: array<DrawArgs>;
+
+const CHUNK_SIZE = 32.0;
+
+@compute @workgroup_size(64)
+fn pick_lods(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= lod.count {
+        return;
+    }
+
+    let chunk = chunks[index];
+    let level = chunk.lod & 0xFFu;
+    let levels = chunk.lod >> 8u;
+
+    let center = chunk.origin + vec3(CHUNK_SIZE / 2.0);
+    let steps = u32(distance(center, lod.eye) / lod.distance);
+    let picked = min(steps, levels - 1u);
+    draws[index].instance_count = select(0u, chunk.ends[5], level == picked);
+}
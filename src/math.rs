@@ -0,0 +1,117 @@
+// Small bits of linear algebra shared by the camera, the renderer and the world
+
+use std::ops::Range;
+
+// What a view-projection matrix sees, as the planes bounding it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    // Left, right, bottom, top, near and far, as (a, b, c, d) with
+    // a x + b y + c z + d >= 0 for every point inside, normals left unnormalized
+    planes: [[f32; 4]; 6],
+}
+
+impl Frustum {
+    // Out of a column-major matrix with depth going from 0 to 1,
+    // as `Camera::view_proj` makes them
+    pub fn new(view_proj: [[f32; 4]; 4]) -> Self {
+        let row = |row: usize| view_proj.map(|column| column[row]);
+        let [x, y, z, w] = [row(0), row(1), row(2), row(3)];
+
+        let add = |a: [f32; 4], b: [f32; 4]| std::array::from_fn(|i| a[i] + b[i]);
+        let sub = |a: [f32; 4], b: [f32; 4]| std::array::from_fn(|i| a[i] - b[i]);
+
+        Self {
+            planes: [add(w, x), sub(w, x), add(w, y), sub(w, y), z, sub(w, z)],
+        }
+    }
+
+    // Whether any of the box from `min` to `max` may be inside. Boxes just
+    // outside past an edge may be taken as inside, but never the other way around
+    pub fn intersects(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner farthest along the normal
+            let corner: [f32; 3] = std::array::from_fn(|axis| match plane[axis] >= 0.0 {
+                true => max[axis],
+                false => min[axis],
+            });
+
+            let distance: f32 = corner.iter().zip(plane).map(|(c, p)| c * p).sum();
+            distance + plane[3] >= 0.0
+        })
+    }
+
+    // Same as `intersects`, for the box as swept along `sweep`,
+    // say to catch whatever casts shadows into view
+    pub fn intersects_swept(&self, min: [f32; 3], max: [f32; 3], sweep: [f32; 3]) -> bool {
+        let min = std::array::from_fn(|axis| min[axis] + sweep[axis].min(0.0));
+        let max = std::array::from_fn(|axis| max[axis] + sweep[axis].max(0.0));
+        self.intersects(min, max)
+    }
+}
+
+// Box with its faces along the axes, from `min` to `max`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Aabb {
+    // `width` wide along x and z and `height` tall, standing centered on `feet`
+    pub fn standing(feet: [f32; 3], width: f32, height: f32) -> Self {
+        let [x, y, z] = feet;
+        let half = width / 2.0;
+        Self {
+            min: [x - half, y, z - half],
+            max: [x + half, y + height, z + half],
+        }
+    }
+
+    pub fn translated(self, offset: [f32; 3]) -> Self {
+        Self {
+            min: std::array::from_fn(|axis| self.min[axis] + offset[axis]),
+            max: std::array::from_fn(|axis| self.max[axis] + offset[axis]),
+        }
+    }
+
+    // Blocks it overlaps along `axis`, those it only touches left out
+    pub fn blocks(&self, axis: usize) -> Range<i32> {
+        self.min[axis].floor() as i32..self.max[axis].ceil() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Camera;
+
+    // Looking down +x from the origin, 500 blocks deep
+    fn frustum() -> Frustum {
+        Frustum::new(Camera::new([0.0; 3]).view_proj(16.0 / 9.0))
+    }
+
+    #[test]
+    fn boxes_ahead_are_inside() {
+        let frustum = frustum();
+        assert!(frustum.intersects([10.0, -1.0, -1.0], [12.0, 1.0, 1.0]));
+
+        // Around the eye, straddling the near plane
+        assert!(frustum.intersects([-1.0; 3], [1.0; 3]));
+    }
+
+    #[test]
+    fn boxes_elsewhere_are_outside() {
+        let frustum = frustum();
+        assert!(!frustum.intersects([-12.0, -1.0, -1.0], [-10.0, 1.0, 1.0]));
+        assert!(!frustum.intersects([10.0, 40.0, -1.0], [12.0, 42.0, 1.0]));
+        assert!(!frustum.intersects([600.0, -1.0, -1.0], [602.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn swept_boxes_reach_into_view() {
+        let frustum = frustum();
+        let (min, max) = ([10.0, 40.0, -1.0], [12.0, 42.0, 1.0]);
+        assert!(frustum.intersects_swept(min, max, [0.0, -40.0, 0.0]));
+        assert!(!frustum.intersects_swept(min, max, [0.0, 40.0, 0.0]));
+    }
+}
@@ -0,0 +1,843 @@
+// Meshing stays usable from crates that forbid unsafe code
+#![forbid(unsafe_code)]
+
+pub mod binary;
+pub mod border;
+pub mod cleanup;
+pub mod debug;
+pub mod export;
+pub mod format;
+pub mod geometry;
+pub mod greedy;
+mod layout;
+pub mod light;
+pub mod lod;
+pub mod palette;
+pub mod pick;
+pub mod remesh;
+pub mod stats;
+pub mod upload;
+pub mod vox;
+pub mod watertight;
+pub mod wide;
+
+use std::fmt::Display;
+
+pub use self::{
+    border::Borders,
+    layout::{Encoding, QuadLayout},
+    light::LightGrid,
+};
+
+/// A packed quad, as read by the GPU.
+///
+/// Laid out as described by a [`QuadLayout`], [`QuadLayout::DEFAULT`] unless stated otherwise:
+///
+/// | bits  | field                                   |
+/// |-------|-----------------------------------------|
+/// | 0-10  | offset                                  |
+/// | 11-14 | state, as [`face_state`] packs it       |
+/// | 15-22 | material                                |
+/// | 23-30 | ambient occlusion, 2 bits per corner    |
+/// | 31-45 | location (x, y, z), 5 bits each         |
+/// | 46-49 | sky exposure                            |
+/// | 50-53 | block light                             |
+/// | 54-58 | width, minus one                        |
+/// | 59-63 | height, minus one                       |
+///
+/// Quads packed by earlier versions of this table can be told apart and
+/// converted with [`format::QuadVersion`].
+pub type QuadRef = u64;
+
+/// One quad list per facing, indexed as in [`Facing::ALL`].
+pub type Mesh = [Vec<QuadRef>; 6];
+
+/// Kind of a block in the low byte, which doubles as its material,
+/// and its state in the high one: the [axis](block_axis) it runs along in bits 8-9
+/// and the [half](block_half) of the block it fills in bits 10-11.
+pub type BlockId = u16;
+
+pub const AIR: BlockId = 0;
+
+// The only translucent blocks for now
+pub const WATER: BlockId = 8;
+pub const GLASS: BlockId = 9;
+
+/// Gives off [block light](light::emitted_light).
+pub const LAMP: BlockId = 10;
+
+/// Ground cover, picked by biome.
+pub const GRASS: BlockId = 2;
+pub const SAND: BlockId = 4;
+pub const SNOW: BlockId = 5;
+
+/// Bark along its [axis](block_axis), rings on both ends.
+pub const LOG: BlockId = 6;
+
+/// Crowns of the trees the world grows.
+pub const LEAVES: BlockId = 11;
+
+/// Stone filling the [half](block_half) of its block it is set to, all of it by default.
+pub const SLAB: BlockId = 7;
+
+/// Which way the block's axis runs across a face, the low 2 bits of [`face_state`].
+/// Faces of blocks that are not oriented, and those along v, have no bits set.
+pub const GRAIN_U: u8 = 1;
+pub const GRAIN_NORMAL: u8 = 2;
+
+/// Which part of its block face a face covers, the high 2 bits of [`face_state`].
+/// Inset faces lie halfway through their block, halves of the other two
+/// cover the lower or upper half of it along v.
+pub const SHAPE_MASK: u8 = 0b1100;
+pub const SHAPE_FULL: u8 = 0;
+pub const SHAPE_INSET: u8 = 1 << 2;
+pub const SHAPE_LOWER_HALF: u8 = 2 << 2;
+pub const SHAPE_UPPER_HALF: u8 = 3 << 2;
+
+/// Voxels of a chunk, indexed as `[z][y][x]` so that rows along x are contiguous.
+pub type Chunk = [[[BlockId; 32]; 32]; 32];
+
+/// Quads of a chunk, split by whether they need blending.
+///
+/// Translucent quads must be drawn after the opaque ones,
+/// in the order given by [`Layers::back_to_front`]. Water is translucent
+/// too, but kept apart to be drawn with surfaces of its own.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Layers {
+    pub opaque: Mesh,
+    pub translucent: Mesh,
+    pub water: Mesh,
+}
+
+impl Layers {
+    /// Sort quads into layers by their material, which must be a block id.
+    pub fn split(mesh: Mesh) -> Self {
+        let layout = QuadLayout::DEFAULT;
+        let mut layers = Self::default();
+
+        for (facing, quads) in mesh.into_iter().enumerate() {
+            for qref in quads {
+                let layer = match layout.material(qref) as BlockId {
+                    WATER => &mut layers.water,
+                    block if is_translucent(block) => &mut layers.translucent,
+                    _ => &mut layers.opaque,
+                };
+
+                layer[facing].push(qref);
+            }
+        }
+
+        layers
+    }
+
+    /// Translucent quads of every facing, farthest from the camera first.
+    /// The camera sits at chunk coordinates, in blocks.
+    pub fn back_to_front(&self, camera: (f32, f32, f32)) -> Vec<(Facing, QuadRef)> {
+        let layout = QuadLayout::DEFAULT;
+        let mut quads = Vec::new();
+
+        for facing in Facing::ALL {
+            let [u_axis, v_axis, depth_axis] = facing.axes();
+            let (nx, ny, nz) = facing.normal();
+            let front = (nx + ny + nz > 0) as i32 as f32;
+
+            for &qref in &self.translucent[facing as usize] {
+                let (u, v, depth) = layout.location(qref);
+                let (w, h) = layout.extent(qref);
+
+                let mut center = [0.0; 3];
+                center[u_axis] = u as f32 + (w as f32 + 1.0) / 2.0;
+                center[v_axis] = v as f32 + (h as f32 + 1.0) / 2.0;
+                center[depth_axis] = depth as f32 + front;
+
+                let d = [
+                    center[0] - camera.0,
+                    center[1] - camera.1,
+                    center[2] - camera.2,
+                ];
+                let distance2 = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+                quads.push((distance2, facing, qref));
+            }
+        }
+
+        quads.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+        quads
+            .into_iter()
+            .map(|(_, facing, qref)| (facing, qref))
+            .collect()
+    }
+}
+
+/// A way of turning a chunk into quads.
+pub trait Mesher {
+    fn mesh(&self, chunk: &Chunk) -> Mesh;
+
+    /// Like [`Mesher::mesh`], with the block light of every quad filled in.
+    /// Meshers that cannot light their quads leave them dark.
+    fn mesh_lit(&self, chunk: &Chunk, light: &LightGrid) -> Mesh {
+        let _ = light;
+        self.mesh(chunk)
+    }
+
+    /// Like [`Mesher::mesh`], culling boundary faces against the neighbor chunks.
+    /// Meshers that cannot look past the chunk keep every boundary face.
+    fn mesh_with_borders(&self, chunk: &Chunk, borders: &Borders) -> Mesh {
+        let _ = borders;
+        self.mesh(chunk)
+    }
+
+    /// Like [`Mesher::mesh_lit`], culling boundary faces as [`Mesher::mesh_with_borders`] does.
+    fn mesh_lit_with_borders(&self, chunk: &Chunk, borders: &Borders, light: &LightGrid) -> Mesh {
+        let _ = borders;
+        self.mesh_lit(chunk, light)
+    }
+}
+
+/// No merging at all, a quad per visible face as [`cull`] emits them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Culled;
+
+impl Mesher for Culled {
+    fn mesh(&self, chunk: &Chunk) -> Mesh {
+        cull(chunk)
+    }
+
+    fn mesh_with_borders(&self, chunk: &Chunk, borders: &Borders) -> Mesh {
+        cull_with_borders(chunk, borders)
+    }
+
+    fn mesh_lit(&self, chunk: &Chunk, light: &LightGrid) -> Mesh {
+        self.mesh_lit_with_borders(chunk, &Borders::NONE, light)
+    }
+
+    fn mesh_lit_with_borders(&self, chunk: &Chunk, borders: &Borders, light: &LightGrid) -> Mesh {
+        let mut mesh = cull_with_borders(chunk, borders);
+        light::light_quads(&mut mesh, light);
+        mesh
+    }
+}
+
+/// Names meshers are picked by, say from a config file or the command line.
+pub const MESHERS: [&str; 4] = ["culled", "greedy", "binary", "watertight"];
+
+/// A mesher by one of the [`MESHERS`] names.
+pub fn mesher(name: &str) -> Option<Box<dyn Mesher>> {
+    let mesher: Box<dyn Mesher> = match name {
+        "culled" => Box::new(Culled),
+        "greedy" => Box::new(greedy::Greedy::default()),
+        "binary" => Box::new(binary::Binary),
+        "watertight" => Box::new(watertight::Watertight(greedy::Greedy::default())),
+        _ => return None,
+    };
+
+    Some(mesher)
+}
+
+/// Unpacked [`QuadRef`], validating every field on the way in.
+///
+/// Unlike in the packed form, width and height are stored as is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quad {
+    layout: QuadLayout,
+    offset: u32,
+    state: u8,
+    material: u32,
+    ao: [u8; 4],
+    location: (u8, u8, u8),
+    sky_exposure: u8,
+    block_light: u8,
+    width: u8,
+    height: u8,
+}
+
+/// A [`Quad`] field was given a value it cannot hold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfRange(pub &'static str);
+
+impl Display for OutOfRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "quad {} out of range", self.0)
+    }
+}
+
+impl Quad {
+    /// A 1x1 quad at the chunk origin.
+    pub const fn new(offset: u32) -> Self {
+        Self::new_in(QuadLayout::DEFAULT, offset)
+    }
+
+    /// Panics if the offset does not fit in the layout.
+    pub const fn new_in(layout: QuadLayout, offset: u32) -> Self {
+        assert!(
+            offset as u64 <= layout.offset_mask(),
+            "quad offset out of range"
+        );
+
+        Self {
+            layout,
+            offset,
+            state: 0,
+            material: 0,
+            ao: [0; 4],
+            location: (0, 0, 0),
+            sky_exposure: 0,
+            block_light: 0,
+            width: 1,
+            height: 1,
+        }
+    }
+
+    pub const fn from_ref(quad_ref: QuadRef) -> Self {
+        Self::from_ref_in(QuadLayout::DEFAULT, quad_ref)
+    }
+
+    pub const fn from_ref_in(layout: QuadLayout, quad_ref: QuadRef) -> Self {
+        let (x, y, z) = layout.location(quad_ref);
+        let (width, height) = layout.extent(quad_ref);
+
+        Self {
+            layout,
+            offset: layout.offset(quad_ref),
+            state: layout.state(quad_ref),
+            material: layout.material(quad_ref),
+            ao: layout.ao(quad_ref),
+            location: (x as _, y as _, z as _),
+            sky_exposure: layout.sky_exposure(quad_ref),
+            block_light: layout.block_light(quad_ref),
+            width: width + 1,
+            height: height + 1,
+        }
+    }
+
+    pub const fn to_ref(self) -> QuadRef {
+        let (x, y, z) = self.location;
+        let location = (x as _, y as _, z as _);
+        let (width, height) = (self.width - 1, self.height - 1);
+
+        let (offset, material) = (self.offset, self.material);
+        let quad_ref =
+            self.layout
+                .pack(offset, material, location, self.sky_exposure, width, height);
+        let quad_ref = self.layout.with_state(quad_ref, self.state);
+        let quad_ref = self.layout.with_ao(quad_ref, self.ao);
+        self.layout.with_block_light(quad_ref, self.block_light)
+    }
+
+    pub const fn layout(&self) -> QuadLayout {
+        self.layout
+    }
+
+    pub const fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    /// Grain and shape, see [`face_state`].
+    pub const fn state(&self) -> u8 {
+        self.state
+    }
+
+    pub const fn material(&self) -> u32 {
+        self.material
+    }
+
+    /// Occlusion of each corner, numbered `u + 2v`.
+    pub const fn ao(&self) -> [u8; 4] {
+        self.ao
+    }
+
+    pub const fn location(&self) -> (i32, i32, i32) {
+        let (x, y, z) = self.location;
+        (x as _, y as _, z as _)
+    }
+
+    pub const fn sky_exposure(&self) -> u8 {
+        self.sky_exposure
+    }
+
+    pub const fn block_light(&self) -> u8 {
+        self.block_light
+    }
+
+    pub const fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u8 {
+        self.height
+    }
+
+    pub fn set_offset(&mut self, offset: u32) -> Result<(), OutOfRange> {
+        if offset as u64 > self.layout.offset_mask() {
+            return Err(OutOfRange("offset"));
+        }
+
+        self.offset = offset;
+        Ok(())
+    }
+
+    pub fn set_state(&mut self, state: u8) -> Result<(), OutOfRange> {
+        if state >= 16 {
+            return Err(OutOfRange("state"));
+        }
+
+        self.state = state;
+        Ok(())
+    }
+
+    pub fn set_material(&mut self, material: u32) -> Result<(), OutOfRange> {
+        if material as u64 > self.layout.material_mask() {
+            return Err(OutOfRange("material"));
+        }
+
+        self.material = material;
+        Ok(())
+    }
+
+    pub fn set_ao(&mut self, ao: [u8; 4]) -> Result<(), OutOfRange> {
+        if ao.iter().any(|&corner| corner > 3) {
+            return Err(OutOfRange("ambient occlusion"));
+        }
+
+        self.ao = ao;
+        Ok(())
+    }
+
+    /// Move the quad, which must stay inside the chunk.
+    pub fn set_location(&mut self, (x, y, z): (i32, i32, i32)) -> Result<(), OutOfRange> {
+        let range = 0..self.layout.size() as i32;
+
+        if !(range.contains(&x) && range.contains(&y) && range.contains(&z)) {
+            return Err(OutOfRange("location"));
+        }
+
+        self.location = (x as _, y as _, z as _);
+        Ok(())
+    }
+
+    pub fn set_sky_exposure(&mut self, sky_exposure: u8) -> Result<(), OutOfRange> {
+        if sky_exposure >= 16 {
+            return Err(OutOfRange("sky exposure"));
+        }
+
+        self.sky_exposure = sky_exposure;
+        Ok(())
+    }
+
+    pub fn set_block_light(&mut self, block_light: u8) -> Result<(), OutOfRange> {
+        if block_light >= 16 {
+            return Err(OutOfRange("block light"));
+        }
+
+        self.block_light = block_light;
+        Ok(())
+    }
+
+    /// Resize the quad to between 1 block and the chunk size wide.
+    pub fn set_width(&mut self, width: u8) -> Result<(), OutOfRange> {
+        if !(1..=self.layout.size()).contains(&(width as u32)) {
+            return Err(OutOfRange("width"));
+        }
+
+        self.width = width;
+        Ok(())
+    }
+
+    /// Resize the quad to between 1 block and the chunk size tall.
+    pub fn set_height(&mut self, height: u8) -> Result<(), OutOfRange> {
+        if !(1..=self.layout.size()).contains(&(height as u32)) {
+            return Err(OutOfRange("height"));
+        }
+
+        self.height = height;
+        Ok(())
+    }
+}
+
+/// Pack a quad. Coordinates and extents wrap to the 32³ chunk.
+/// Panics if the offset does not fit [`QuadLayout::DEFAULT`].
+pub fn quad_ref(
+    offset: usize,
+    location: (i32, i32, i32),
+    sky_exposure: u8,
+    block_light: u8,
+    width: u8,
+    height: u8,
+) -> QuadRef {
+    debug_assert!(sky_exposure < 16, "sky exposure out of bounds");
+    debug_assert!(block_light < 16, "block light out of bounds");
+    let layout = QuadLayout::DEFAULT;
+    let offset = u32::try_from(offset).expect("quad offset out of range");
+    let quad_ref = layout.pack(offset, 0, location, sky_exposure, width, height);
+    layout.with_block_light(quad_ref, block_light)
+}
+
+/// Grow a quad by one block along x.
+pub fn extend_quad_ref_w(quad_ref: &mut QuadRef) {
+    QuadLayout::DEFAULT.extend_w(quad_ref);
+}
+
+/// Grow a quad by one block along y.
+pub fn extend_quad_ref_h(quad_ref: &mut QuadRef) {
+    QuadLayout::DEFAULT.extend_h(quad_ref);
+}
+
+pub const fn quad_offset(quad_ref: QuadRef) -> u32 {
+    QuadLayout::DEFAULT.offset(quad_ref)
+}
+
+pub const fn quad_state(quad_ref: QuadRef) -> u8 {
+    QuadLayout::DEFAULT.state(quad_ref)
+}
+
+pub const fn quad_material(quad_ref: QuadRef) -> u32 {
+    QuadLayout::DEFAULT.material(quad_ref)
+}
+
+pub const fn quad_ao(quad_ref: QuadRef) -> [u8; 4] {
+    QuadLayout::DEFAULT.ao(quad_ref)
+}
+
+pub const fn quad_location(quad_ref: QuadRef) -> (i32, i32, i32) {
+    QuadLayout::DEFAULT.location(quad_ref)
+}
+
+pub const fn quad_sky_exposure(quad_ref: QuadRef) -> u8 {
+    QuadLayout::DEFAULT.sky_exposure(quad_ref)
+}
+
+pub const fn quad_block_light(quad_ref: QuadRef) -> u8 {
+    QuadLayout::DEFAULT.block_light(quad_ref)
+}
+
+/// Width and height of a quad, minus one.
+pub const fn quad_extent(quad_ref: QuadRef) -> (u8, u8) {
+    QuadLayout::DEFAULT.extent(quad_ref)
+}
+
+/// Axis an oriented block runs along, upright unless turned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    Y,
+    X,
+    Z,
+}
+
+impl Axis {
+    /// Index of the axis within (x, y, z).
+    pub const fn index(self) -> usize {
+        match self {
+            Self::X => 0,
+            Self::Y => 1,
+            Self::Z => 2,
+        }
+    }
+}
+
+/// Part of its block a slab fills.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Half {
+    Whole,
+    Bottom,
+    Top,
+}
+
+/// Kind of a block, its state left out.
+pub const fn block_kind(block: BlockId) -> BlockId {
+    block & 0xFF
+}
+
+pub const fn block_axis(block: BlockId) -> Axis {
+    match block >> 8 & 3 {
+        1 => Axis::X,
+        2 => Axis::Z,
+        _ => Axis::Y,
+    }
+}
+
+pub const fn block_half(block: BlockId) -> Half {
+    match block >> 10 & 3 {
+        1 => Half::Bottom,
+        2 => Half::Top,
+        _ => Half::Whole,
+    }
+}
+
+/// A block of the kind of `block`, turned to `axis` and filling `half` of it.
+pub const fn with_state(block: BlockId, axis: Axis, half: Half) -> BlockId {
+    block_kind(block) | (axis as BlockId) << 8 | (half as BlockId) << 10
+}
+
+/// Whether faces of a block show which way its axis runs.
+pub const fn is_oriented(block: BlockId) -> bool {
+    block_kind(block) == LOG
+}
+
+/// Direction a quad faces.
+///
+/// Quads store face-local coordinates: u in the x field,
+/// v in the y field and the depth of their slice in the z field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facing {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Facing {
+    pub const ALL: [Self; 6] = [
+        Self::PosX,
+        Self::NegX,
+        Self::PosY,
+        Self::NegY,
+        Self::PosZ,
+        Self::NegZ,
+    ];
+
+    /// Indices of the u, v and depth axes within (x, y, z).
+    pub const fn axes(self) -> [usize; 3] {
+        match self {
+            Self::PosX | Self::NegX => [2, 1, 0],
+            Self::PosY | Self::NegY => [0, 2, 1],
+            Self::PosZ | Self::NegZ => [0, 1, 2],
+        }
+    }
+
+    pub const fn normal(self) -> (i32, i32, i32) {
+        match self {
+            Self::PosX => (1, 0, 0),
+            Self::NegX => (-1, 0, 0),
+            Self::PosY => (0, 1, 0),
+            Self::NegY => (0, -1, 0),
+            Self::PosZ => (0, 0, 1),
+            Self::NegZ => (0, 0, -1),
+        }
+    }
+
+    /// Turn face-local (u, v, depth) coordinates into chunk coordinates.
+    pub fn to_world(self, local: (i32, i32, i32)) -> (i32, i32, i32) {
+        let mut location = [0; 3];
+        let [u, v, depth] = self.axes();
+        location[u] = local.0;
+        location[v] = local.1;
+        location[depth] = local.2;
+        (location[0], location[1], location[2])
+    }
+
+    /// Corners of a face as steps along (u, v), counter-clockwise seen from the front,
+    /// so that counter-clockwise front faces cull the back of every facing alike.
+    pub fn winding(self) -> [(i32, i32); 4] {
+        let mut corners = [(0, 0), (1, 0), (1, 1), (0, 1)];
+
+        // Does u × v point along the normal?
+        let (u, v) = (self.to_world((1, 0, 0)), self.to_world((0, 1, 0)));
+        let cross = (
+            u.1 * v.2 - u.2 * v.1,
+            u.2 * v.0 - u.0 * v.2,
+            u.0 * v.1 - u.1 * v.0,
+        );
+
+        if cross != self.normal() {
+            corners.reverse();
+        }
+
+        corners
+    }
+
+    /// Chunk coordinates of the corners of a quad facing this way, in [winding](Self::winding) order.
+    /// Faces lie on the side of their block the normal points to.
+    pub fn quad_corners(self, quad_ref: QuadRef) -> [(i32, i32, i32); 4] {
+        let (u, v, depth) = quad_location(quad_ref);
+        let (w, h) = quad_extent(quad_ref);
+        let (w, h) = (w as i32 + 1, h as i32 + 1);
+
+        let (nx, ny, nz) = self.normal();
+        let depth = depth + (nx + ny + nz > 0) as i32;
+
+        self.winding()
+            .map(|(cu, cv)| self.to_world((u + cu * w, v + cv * h, depth)))
+    }
+}
+
+/// Lowest and highest corners of every quad of a mesh, in chunk coordinates,
+/// or nothing if it has no quads at all.
+pub fn mesh_bounds(mesh: &Mesh) -> Option<([i32; 3], [i32; 3])> {
+    let facings = Facing::ALL.into_iter().zip(mesh);
+    let quads = facings.flat_map(|(facing, quads)| quads.iter().map(move |&quad| (facing, quad)));
+    let corners = quads.flat_map(|(facing, quad)| facing.quad_corners(quad));
+
+    corners.fold(None, |bounds, (x, y, z)| {
+        let corner = [x, y, z];
+        let (min, max) = bounds.unwrap_or((corner, corner));
+        let min = std::array::from_fn(|axis| min[axis].min(corner[axis]));
+        let max = std::array::from_fn(|axis| max[axis].max(corner[axis]));
+        Some((min, max))
+    })
+}
+
+/// Blocks of a chunk looked up one at a time, for them to be meshed around
+/// a single block without having to be laid out as a [`Chunk`].
+pub trait ChunkBlocks {
+    /// Block at the given chunk coordinates, every one of them within `0..32`.
+    fn block(&self, local: [usize; 3]) -> BlockId;
+}
+
+impl ChunkBlocks for Chunk {
+    fn block(&self, [x, y, z]: [usize; 3]) -> BlockId {
+        self[z][y][x]
+    }
+}
+
+/// Block at the given chunk coordinates, air if outside the chunk.
+pub fn block_at(chunk: &impl ChunkBlocks, (x, y, z): (i32, i32, i32)) -> BlockId {
+    let range = 0..32;
+
+    if !(range.contains(&x) && range.contains(&y) && range.contains(&z)) {
+        return AIR;
+    }
+
+    chunk.block([x, y, z].map(|c| c as usize))
+}
+
+pub const fn is_translucent(block: BlockId) -> bool {
+    matches!(block_kind(block), WATER | GLASS)
+}
+
+/// Whether light goes through, be it air or a translucent block.
+pub const fn is_transparent(block: BlockId) -> bool {
+    block == AIR || is_translucent(block)
+}
+
+/// Whether a block face facing `facing` is seen from its neighbor in front.
+/// Translucent blocks hide their faces against the same kind of block,
+/// and halves of slabs against the same half next to them. Inset faces always show.
+pub const fn is_face_visible(block: BlockId, neighbor: BlockId, facing: Facing) -> bool {
+    let shape = face_state(block, facing) & SHAPE_MASK;
+
+    match (is_transparent(block), is_transparent(neighbor)) {
+        _ if block == AIR => false,
+        _ if shape == SHAPE_INSET => true,
+        (true, true) => neighbor != block,
+        (false, false) if shape != SHAPE_FULL && neighbor == block => false,
+        _ => !covers(neighbor, facing),
+    }
+}
+
+/// Whether the side of `block` seen from a neighbor looking `facing` is solid all over,
+/// hiding the neighbor's face behind it.
+const fn covers(block: BlockId, facing: Facing) -> bool {
+    if is_transparent(block) {
+        return false;
+    }
+
+    match (block_kind(block), block_half(block)) {
+        (SLAB, Half::Bottom) => matches!(facing, Facing::PosY),
+        (SLAB, Half::Top) => matches!(facing, Facing::NegY),
+        _ => true,
+    }
+}
+
+/// State of the quad of a block face: its grain, which way the axis of an [oriented](is_oriented)
+/// block runs across it, and its shape, which part of the block face it covers.
+/// Slab faces halfway through their block are inset, and their sides halved.
+pub const fn face_state(block: BlockId, facing: Facing) -> u8 {
+    let [u, _, depth] = facing.axes();
+    let axis = block_axis(block).index();
+
+    let grain = match is_oriented(block) {
+        true if axis == depth => GRAIN_NORMAL,
+        true if axis == u => GRAIN_U,
+        _ => 0,
+    };
+
+    let shape = match (block_kind(block), block_half(block), facing) {
+        (SLAB, Half::Bottom, Facing::PosY) | (SLAB, Half::Top, Facing::NegY) => SHAPE_INSET,
+        (SLAB, Half::Bottom, Facing::NegY) | (SLAB, Half::Top, Facing::PosY) => SHAPE_FULL,
+        (SLAB, Half::Bottom, _) => SHAPE_LOWER_HALF,
+        (SLAB, Half::Top, _) => SHAPE_UPPER_HALF,
+        _ => SHAPE_FULL,
+    };
+
+    grain | shape
+}
+
+/// Occlusion of the corners of a face, from the blocks around the one in front of it.
+pub fn face_ao(
+    chunk: &impl ChunkBlocks,
+    borders: &Borders,
+    facing: Facing,
+    front: (i32, i32, i32),
+) -> [u8; 4] {
+    let is_solid = |du, dv| {
+        let (dx, dy, dz) = facing.to_world((du, dv, 0));
+        let block = borders.block_at(chunk, (front.0 + dx, front.1 + dy, front.2 + dz));
+        !is_transparent(block)
+    };
+
+    let mut ao = [0; 4];
+    for (corner, ao) in ao.iter_mut().enumerate() {
+        let du = if corner & 1 == 0 { -1 } else { 1 };
+        let dv = if corner & 2 == 0 { -1 } else { 1 };
+        let (side_u, side_v) = (is_solid(du, 0), is_solid(0, dv));
+
+        // Both sides already hide the corner block
+        *ao = match side_u && side_v {
+            true => 3,
+            false => side_u as u8 + side_v as u8 + is_solid(du, dv) as u8,
+        };
+    }
+
+    ao
+}
+
+/// The 1x1 quad of a block face at face-local (u, v, depth) coordinates, if it is visible.
+/// Block kinds double as materials for now, with the rest of the block in the quad state.
+/// Faces on the chunk boundary are checked against its borders.
+pub fn face_quad(
+    chunk: &impl ChunkBlocks,
+    borders: &Borders,
+    facing: Facing,
+    local: (i32, i32, i32),
+) -> Option<QuadRef> {
+    let (nx, ny, nz) = facing.normal();
+    let (x, y, z) = facing.to_world(local);
+    let block = block_at(chunk, (x, y, z));
+    let front = (x + nx, y + ny, z + nz);
+
+    if !is_face_visible(block, borders.block_at(chunk, front), facing) {
+        return None;
+    }
+
+    let layout = QuadLayout::DEFAULT;
+    let qref = layout.pack(0, block_kind(block) as _, local, 0, 0, 0);
+    let qref = layout.with_state(qref, face_state(block, facing));
+    let ao = face_ao(chunk, borders, facing, front);
+    Some(layout.with_ao(qref, ao))
+}
+
+/// Emit a 1x1 quad for every visible block face,
+/// already sorted the way [`greedy::greedy3d`] wants them.
+pub fn cull(chunk: &Chunk) -> Mesh {
+    cull_with_borders(chunk, &Borders::NONE)
+}
+
+/// Like [`cull`], also hiding boundary faces against solid neighbor blocks.
+pub fn cull_with_borders(chunk: &Chunk, borders: &Borders) -> Mesh {
+    let mut mesh = Mesh::default();
+
+    for facing in Facing::ALL {
+        let quads = &mut mesh[facing as usize];
+
+        for depth in 0..32 {
+            for v in 0..32 {
+                for u in 0..32 {
+                    quads.extend(face_quad(chunk, borders, facing, (u, v, depth)));
+                }
+            }
+        }
+    }
+
+    mesh
+}
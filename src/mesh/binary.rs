@@ -0,0 +1,246 @@
+use super::{
+    block_at, block_kind, face_ao, face_quad, face_state,
+    greedy::{greedy1d, greedy3d, sort_quads},
+    is_translucent, is_transparent, Borders, Chunk, Facing, Mesh, Mesher, QuadLayout, QuadRef,
+    SHAPE_MASK,
+};
+
+/// Binary greedy meshing: faces are found a whole column at a time from bit masks,
+/// then merged by scanning rows of each slice with trailing zero counts.
+///
+/// Only opaque blocks go through the bit masks. Translucent ones, far fewer,
+/// are culled one at a time as [`cull`](super::cull) does and merged apart.
+/// Oriented blocks keep their grain, but opaque slabs come out as whole blocks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Binary;
+
+impl Mesher for Binary {
+    fn mesh(&self, chunk: &Chunk) -> Mesh {
+        mesh_chunk(chunk)
+    }
+}
+
+/// Rows along u of the faces in a slice sharing the same material, state and occlusion.
+struct Plane {
+    material: u32,
+    state: u8,
+    ao: [u8; 4],
+    rows: [u32; 32],
+}
+
+pub fn mesh_chunk(chunk: &Chunk) -> Mesh {
+    let layout = QuadLayout::DEFAULT;
+    let solid = solid_columns(chunk);
+    let mut mesh = Mesh::default();
+
+    for facing in Facing::ALL {
+        let (nx, ny, nz) = facing.normal();
+        let [u_axis, v_axis, depth_axis] = facing.axes();
+        let positive = nx + ny + nz > 0;
+
+        // faces[depth][v] holds a bit for every u with a visible face
+        let mut faces = [[0u32; 32]; 32];
+        for a in 0..32 {
+            for b in 0..32 {
+                let column = solid[depth_axis][a][b];
+
+                // A face is visible where the next block along the normal is empty
+                let mut visible = match positive {
+                    true => column & !(column >> 1),
+                    false => column & !(column << 1),
+                };
+
+                let (u, v) = match u_axis < v_axis {
+                    true => (a, b),
+                    false => (b, a),
+                };
+
+                while visible != 0 {
+                    let depth = visible.trailing_zeros() as usize;
+                    visible &= visible - 1;
+                    faces[depth][v] |= 1 << u;
+                }
+            }
+        }
+
+        let quads = &mut mesh[facing as usize];
+        let mut planes = Vec::new();
+
+        for (depth, rows) in faces.iter().enumerate() {
+            split_planes(chunk, facing, depth as i32, rows, &mut planes);
+
+            for plane in planes.drain(..) {
+                merge_plane(layout, plane, depth as i32, quads);
+            }
+        }
+    }
+
+    let mut translucent = translucent_faces(chunk);
+    translucent.iter_mut().for_each(greedy1d);
+    greedy3d(&mut translucent);
+
+    for (quads, faces) in mesh.iter_mut().zip(translucent) {
+        quads.extend(faces);
+        sort_quads(layout, quads);
+    }
+
+    mesh
+}
+
+// Visible faces of translucent blocks, as `cull` finds them
+fn translucent_faces(chunk: &Chunk) -> Mesh {
+    let mut mesh = Mesh::default();
+
+    for facing in Facing::ALL {
+        let quads = &mut mesh[facing as usize];
+
+        for depth in 0..32 {
+            for v in 0..32 {
+                for u in 0..32 {
+                    let block = block_at(chunk, facing.to_world((u, v, depth)));
+                    if is_translucent(block) {
+                        quads.extend(face_quad(chunk, &Borders::NONE, facing, (u, v, depth)));
+                    }
+                }
+            }
+        }
+    }
+
+    mesh
+}
+
+// Bit masks of solid blocks, solid[axis][a][b] running along `axis`
+// and indexed by the other two axes in (x, y, z) order
+fn solid_columns(chunk: &Chunk) -> Box<[[[u32; 32]; 32]; 3]> {
+    let mut solid = Box::new([[[0u32; 32]; 32]; 3]);
+
+    for z in 0..32 {
+        for y in 0..32 {
+            for x in 0..32 {
+                if is_transparent(chunk[z][y][x]) {
+                    continue;
+                }
+
+                solid[0][y][z] |= 1 << x;
+                solid[1][x][z] |= 1 << y;
+                solid[2][x][y] |= 1 << z;
+            }
+        }
+    }
+
+    solid
+}
+
+// Sort the visible faces of a slice by material and occlusion
+fn split_planes(
+    chunk: &Chunk,
+    facing: Facing,
+    depth: i32,
+    rows: &[u32; 32],
+    planes: &mut Vec<Plane>,
+) {
+    let (nx, ny, nz) = facing.normal();
+
+    for (v, &row) in rows.iter().enumerate() {
+        let mut row = row;
+
+        while row != 0 {
+            let u = row.trailing_zeros() as i32;
+            row &= row - 1;
+
+            let (x, y, z) = facing.to_world((u, v as i32, depth));
+            let block = block_at(chunk, (x, y, z));
+            let material = block_kind(block) as u32;
+            let state = face_state(block, facing) & !SHAPE_MASK;
+            let ao = face_ao(chunk, &Borders::NONE, facing, (x + nx, y + ny, z + nz));
+
+            // Slices hold a handful of distinct faces, a linear search is enough
+            let key = (material, state, ao);
+            let same = |plane: &Plane| (plane.material, plane.state, plane.ao) == key;
+            let index = match planes.iter().position(same) {
+                Some(index) => index,
+                None => {
+                    let rows = [0; 32];
+                    planes.push(Plane {
+                        material,
+                        state,
+                        ao,
+                        rows,
+                    });
+                    planes.len() - 1
+                }
+            };
+
+            planes[index].rows[v] |= 1 << u;
+        }
+    }
+}
+
+// Greedily cover a plane with quads, growing each run of faces upward
+fn merge_plane(layout: QuadLayout, mut plane: Plane, depth: i32, quads: &mut Vec<QuadRef>) {
+    let [a, b, c, d] = plane.ao;
+
+    // Occlusion varying along an axis forbids growing along it
+    let flat_w = a == b && c == d;
+    let flat_h = a == c && b == d;
+
+    for v in 0..32 {
+        while plane.rows[v] != 0 {
+            let row = plane.rows[v];
+            let u = row.trailing_zeros();
+            let width = match flat_w {
+                true => (row >> u).trailing_ones(),
+                false => 1,
+            };
+
+            let run = (u32::MAX >> (32 - width)) << u;
+            plane.rows[v] &= !run;
+
+            let mut height = 1;
+            while flat_h && v + height < 32 && plane.rows[v + height] & run == run {
+                plane.rows[v + height] &= !run;
+                height += 1;
+            }
+
+            let location = (u as i32, v as i32, depth);
+            let (width, height) = (width as u8 - 1, height as u8 - 1);
+            let qref = layout.pack(0, plane.material, location, 0, width, height);
+            let qref = layout.with_state(qref, plane.state);
+            quads.push(layout.with_ao(qref, plane.ao));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{cull, Layers, AIR, GLASS, WATER};
+
+    // Blocks covered by every quad of a mesh
+    fn area(mesh: &Mesh) -> u32 {
+        let layout = QuadLayout::DEFAULT;
+        let extents = mesh.iter().flatten().map(|&quad| layout.extent(quad));
+        extents.map(|(w, h)| (w as u32 + 1) * (h as u32 + 1)).sum()
+    }
+
+    #[test]
+    fn translucent_faces_are_kept() {
+        // A stone basin of water, with a glass pane over it
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for z in 2..12 {
+            for x in 2..12 {
+                chunk[z][1][x] = 1;
+                chunk[z][2][x] = WATER;
+                chunk[z][3][x] = WATER;
+                chunk[z][6][x] = GLASS;
+            }
+        }
+
+        let binary = Layers::split(Binary.mesh(&chunk));
+        let culled = Layers::split(cull(&chunk));
+        assert!(area(&binary.water) > 0 && area(&binary.translucent) > 0);
+        assert_eq!(area(&binary.water), area(&culled.water));
+        assert_eq!(area(&binary.translucent), area(&culled.translucent));
+        assert_eq!(area(&binary.opaque), area(&culled.opaque));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{BlockId, Chunk, ChunkBlocks, Facing, AIR};
+
+/// The layers of the six neighbor chunks touching a chunk,
+/// so that faces on its boundary can be culled against them.
+///
+/// Border blocks are indexed as `[facing][v][u]`, in the face-local axes of the facing
+/// pointing at the neighbor they come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Borders {
+    blocks: [[[BlockId; 32]; 32]; 6],
+}
+
+impl Borders {
+    /// Air all around, as if the chunk were alone.
+    pub const NONE: Self = Self {
+        blocks: [[[AIR; 32]; 32]; 6],
+    };
+
+    /// Borders taken from neighbor chunks indexed as in [`Facing::ALL`],
+    /// missing ones left as air.
+    pub fn from_neighbors(neighbors: [Option<&Chunk>; 6]) -> Self {
+        Self::from_fn(|facing, [x, y, z]| {
+            let neighbor = neighbors[facing as usize]?;
+            Some(neighbor[z][y][x])
+        })
+    }
+
+    /// Borders looked up a block at a time, for neighbors however they are stored.
+    ///
+    /// `block` is given the facing pointing at the neighbor and the chunk coordinates
+    /// within it, and returns `None` where there is no neighbor, left as air.
+    pub fn from_fn(mut block: impl FnMut(Facing, [usize; 3]) -> Option<BlockId>) -> Self {
+        let mut borders = Self::NONE;
+
+        for facing in Facing::ALL {
+            // The neighbor layer right against this chunk
+            let (nx, ny, nz) = facing.normal();
+            let depth = if nx + ny + nz > 0 { 0 } else { 31 };
+
+            for v in 0..32 {
+                for u in 0..32 {
+                    let (x, y, z) = facing.to_world((u, v, depth));
+                    let local = [x, y, z].map(|c| c as usize);
+                    if let Some(block) = block(facing, local) {
+                        borders.blocks[facing as usize][v as usize][u as usize] = block;
+                    }
+                }
+            }
+        }
+
+        borders
+    }
+
+    /// Block at the given chunk coordinates, looking into the borders
+    /// one block past the chunk sides. Edges and corners beyond that are air.
+    pub fn block_at(&self, chunk: &impl ChunkBlocks, (x, y, z): (i32, i32, i32)) -> BlockId {
+        let range = 0..32;
+        let inside = [x, y, z].map(|c| range.contains(&c));
+
+        let facing = match (x, y, z) {
+            _ if inside == [true; 3] => return chunk.block([x, y, z].map(|c| c as usize)),
+            (32, ..) if inside[1] && inside[2] => Facing::PosX,
+            (-1, ..) if inside[1] && inside[2] => Facing::NegX,
+            (_, 32, _) if inside[0] && inside[2] => Facing::PosY,
+            (_, -1, _) if inside[0] && inside[2] => Facing::NegY,
+            (.., 32) if inside[0] && inside[1] => Facing::PosZ,
+            (.., -1) if inside[0] && inside[1] => Facing::NegZ,
+            _ => return AIR,
+        };
+
+        let location = [x, y, z];
+        let [u, v, _] = facing.axes();
+        self.blocks[facing as usize][location[v] as usize][location[u] as usize]
+    }
+}
+
+impl Default for Borders {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
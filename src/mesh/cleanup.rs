@@ -0,0 +1,38 @@
+use super::{QuadLayout, QuadRef};
+
+/// Drop quads no cell of which would show, as later quads of the same slice
+/// cover all of them again, be it one quad or several together.
+///
+/// Later quads win, as when [rendering](super::debug::render) a list,
+/// so what remains draws exactly like the whole list did, in the same order.
+/// Packed extents count from one, so there are no empty quads to drop besides.
+pub fn remove_hidden(quads: &mut Vec<QuadRef>) {
+    remove_hidden_in(QuadLayout::DEFAULT, quads);
+}
+
+/// Like [`remove_hidden`], for quads packed with any layout.
+pub fn remove_hidden_in(layout: QuadLayout, quads: &mut Vec<QuadRef>) {
+    let size = layout.size() as usize;
+
+    // covered[depth * size + y] has a bit for every x already drawn over
+    let mut covered = vec![0u128; size * size];
+    let mut visible = vec![false; quads.len()];
+
+    for (index, &qref) in quads.iter().enumerate().rev() {
+        let (x, y, depth) = layout.location(qref);
+        let (w, h) = layout.extent(qref);
+
+        // Rows past the chunk edge hold nothing to draw
+        let run = (u128::MAX >> (127 - w as u32)) << x;
+        let rows = y as usize..(y as usize + h as usize + 1).min(size);
+        let slice = &mut covered[depth as usize * size..][..size][rows];
+
+        for row in slice {
+            visible[index] |= *row & run != run;
+            *row |= run;
+        }
+    }
+
+    let mut visible = visible.into_iter();
+    quads.retain(|_| visible.next().unwrap());
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use super::{Quad, QuadRef};
+
+/// Framebuffer of colored cells for [`render`] and [`display`].
+pub type Screen<const N: usize = 8> = [[(u16, Kind); N]; N];
+
+pub const CLEAN_SCREEN: Screen = clean_screen();
+
+pub const fn clean_screen<const N: usize>() -> Screen<N> {
+    [[(0, Kind::Initial); N]; N]
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Kind {
+    Initial,
+    Inside,
+    Final,
+    Single,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match *self {
+            Self::Initial => "██",
+            Self::Inside => "░░",
+            Self::Final => "▒▒",
+            Self::Single => "█▒",
+        };
+
+        f.write_str(str)
+    }
+}
+
+/// Draw a single slice of quads, overwriting whatever was there.
+pub fn render<const N: usize>(mesh: &[QuadRef], screen: &mut Screen<N>) {
+    for &qref in mesh.iter() {
+        let quad = Quad::from_ref(qref);
+        let color = quad.offset();
+        let (x0, y0, _) = quad.location();
+        let width = quad.width() as i32 - 1;
+        let height = quad.height() as i32 - 1;
+
+        for y in y0..=y0 + height {
+            for x in x0..=x0 + width {
+                let mut kind = Kind::Inside;
+                if (x, y) == (x0, y0) {
+                    kind = Kind::Initial;
+                }
+                if (x, y) == (x0 + width, y0 + height) {
+                    kind = Kind::Final;
+                }
+                if (width, height) == (0, 0) {
+                    kind = Kind::Single;
+                }
+
+                screen[y as usize][x as usize] = (color as u16, kind);
+            }
+        }
+    }
+}
+
+/// A screen with ANSI colors, a line of text per row, to be printed or logged.
+pub fn display<const N: usize>(screen: &Screen<N>) -> String {
+    let lines = screen.iter().map(|line| {
+        let cells = line.iter().map(|(color, kind)| match *color {
+            0 => format!("\x1B[1;3{}m  \x1B[0m", color),
+            _ => format!("\x1B[1;3{}m{}\x1B[0m", color, kind),
+        });
+
+        cells.collect::<String>()
+    });
+
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+/// ANSI terminal colors, as used by [`display`].
+pub const PALETTE: [[u8; 3]; 8] = [
+    [0x00, 0x00, 0x00],
+    [0xCD, 0x31, 0x31],
+    [0x0D, 0xBC, 0x79],
+    [0xE5, 0xE5, 0x10],
+    [0x24, 0x72, 0xC8],
+    [0xBC, 0x3F, 0xBC],
+    [0x11, 0xA8, 0xCD],
+    [0xE5, 0xE5, 0xE5],
+];
+
+/// RGB image, stored row by row from the top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![PALETTE[0]; width * height],
+        }
+    }
+
+    /// Write a binary PPM, which most image viewers open as is.
+    pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(self.pixels.as_flattened())
+    }
+}
+
+/// Draw quads over a `size`² slice, each block `scale` pixels wide,
+/// overwriting whatever was there like [`render`] does.
+///
+/// Quads are filled with the [`PALETTE`] entry `color` picks for them
+/// and outlined in a darker shade, so that merges stand out.
+pub fn rasterize(
+    mesh: &[QuadRef],
+    size: usize,
+    scale: usize,
+    color: impl Fn(QuadRef) -> u32,
+) -> Image {
+    let mut image = Image::new(size * scale, size * scale);
+
+    for &qref in mesh {
+        let quad = Quad::from_ref(qref);
+        let (x0, y0, _) = quad.location();
+        let (x0, y0) = (x0 as usize * scale, y0 as usize * scale);
+        let (width, height) = (quad.width() as usize, quad.height() as usize);
+        let (x1, y1) = (x0 + width * scale, y0 + height * scale);
+
+        let fill = PALETTE[color(qref) as usize % PALETTE.len()];
+        let outline = fill.map(|channel| channel / 2);
+
+        for y in y0..y1.min(image.height) {
+            for x in x0..x1.min(image.width) {
+                let edge = x == x0 || y == y0 || x == x1 - 1 || y == y1 - 1;
+                image.pixels[y * image.width + x] = if edge { outline } else { fill };
+            }
+        }
+    }
+
+    image
+}
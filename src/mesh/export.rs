@@ -0,0 +1,51 @@
+use std::io::{self, Write};
+
+use super::{Facing, Mesh, QuadLayout};
+
+/// Write a mesh as a Wavefront OBJ file, one face per quad.
+///
+/// Texture coordinates count blocks, so a repeating texture tiles once per block,
+/// and quads are grouped under a `block<material>` material.
+pub fn write_obj(out: &mut impl Write, mesh: &Mesh) -> io::Result<()> {
+    let layout = QuadLayout::DEFAULT;
+
+    for facing in Facing::ALL {
+        let (nx, ny, nz) = facing.normal();
+        writeln!(out, "vn {nx} {ny} {nz}")?;
+    }
+
+    let mut vertex = 1;
+    let mut material = None;
+
+    for facing in Facing::ALL {
+        let normal = facing as usize + 1;
+
+        for &qref in &mesh[facing as usize] {
+            let (w, h) = layout.extent(qref);
+            let (w, h) = (w as i32 + 1, h as i32 + 1);
+
+            let quad_material = layout.material(qref);
+            if material != Some(quad_material) {
+                material = Some(quad_material);
+                writeln!(out, "usemtl block{quad_material}")?;
+            }
+
+            let corners = facing.winding().into_iter().zip(facing.quad_corners(qref));
+
+            for ((cu, cv), (x, y, z)) in corners {
+                writeln!(out, "v {x} {y} {z}")?;
+                writeln!(out, "vt {} {}", cu * w, cv * h)?;
+            }
+
+            write!(out, "f")?;
+            for index in vertex..vertex + 4 {
+                write!(out, " {index}/{index}/{normal}")?;
+            }
+
+            writeln!(out)?;
+            vertex += 4;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use std::io::{self, Write};
+
+use super::{Mesh, QuadRef};
+
+// Every field a quad has ever had, whether or not a version has them
+const FIELDS: usize = 11;
+
+// Meshes written by `write_mesh` start with these, then the version of their quads
+const MAGIC: &[u8; 4] = b"AXMS";
+
+/// Ways quads of 32³ chunks have been packed into a [`QuadRef`], oldest first.
+///
+/// Every version packs the fields it has in the same order: offset, state, material,
+/// ambient occlusion, location (x, y, z), sky exposure, block light, then width and
+/// height minus one. Later versions add fields and narrow the offset to make room,
+/// so quads kept around, say in files, must be [converted](QuadVersion::convert)
+/// before being read with [`QuadLayout::DEFAULT`](super::QuadLayout::DEFAULT).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QuadVersion {
+    /// Offset, location, sky exposure and extent alone.
+    Plain = 1,
+
+    /// With a 12-bit material after the offset.
+    Material,
+
+    /// With ambient occlusion after the material, narrowed to 8 bits.
+    Occlusion,
+
+    /// With block light after sky exposure.
+    BlockLight,
+
+    /// With the state of the face before the material,
+    /// as [`QuadLayout::CHUNK_32`](super::QuadLayout::CHUNK_32) packs quads.
+    State,
+}
+
+impl QuadVersion {
+    pub const ALL: [Self; 5] = [
+        Self::Plain,
+        Self::Material,
+        Self::Occlusion,
+        Self::BlockLight,
+        Self::State,
+    ];
+
+    /// Version quads are packed with nowadays.
+    pub const CURRENT: Self = Self::State;
+
+    /// Number a version is stored as.
+    pub const fn tag(self) -> u16 {
+        self as u16
+    }
+
+    /// Version stored as `tag`, if there is one.
+    pub const fn from_tag(tag: u16) -> Option<Self> {
+        match tag {
+            1 => Some(Self::Plain),
+            2 => Some(Self::Material),
+            3 => Some(Self::Occlusion),
+            4 => Some(Self::BlockLight),
+            5 => Some(Self::State),
+            _ => None,
+        }
+    }
+
+    /// Bits of every field, none for fields the version does not have.
+    const fn widths(self) -> [u32; FIELDS] {
+        let (offset, state, material, ao, block_light) = match self {
+            Self::Plain => (32, 0, 0, 0, 0),
+            Self::Material => (20, 0, 12, 0, 0),
+            Self::Occlusion => (19, 0, 8, 8, 0),
+            Self::BlockLight => (15, 0, 8, 8, 4),
+            Self::State => (11, 4, 8, 8, 4),
+        };
+
+        [offset, state, material, ao, 5, 5, 5, 4, block_light, 5, 5]
+    }
+
+    fn unpack(self, quad_ref: QuadRef) -> [u64; FIELDS] {
+        let mut fields = [0; FIELDS];
+        let mut shift = 0;
+
+        for (field, width) in fields.iter_mut().zip(self.widths()) {
+            *field = quad_ref >> shift & ((1 << width) - 1);
+            shift += width;
+        }
+
+        fields
+    }
+
+    /// None if any field does not fit, fields the version lacks fitting only if 0.
+    fn pack(self, fields: [u64; FIELDS]) -> Option<QuadRef> {
+        let mut quad_ref = 0;
+        let mut shift = 0;
+
+        for (field, width) in fields.into_iter().zip(self.widths()) {
+            if field >> width != 0 {
+                return None;
+            }
+
+            quad_ref |= field << shift;
+            shift += width;
+        }
+
+        Some(quad_ref)
+    }
+
+    /// A quad packed with this version, packed with `to` instead.
+    ///
+    /// Fields `to` lacks must be 0, and every field must fit in `to`, or there is
+    /// no quad to be had: going forward, the offset or material may be too large
+    /// for the narrower fields, and going back, occlusion, block light or state would be lost.
+    /// Fields this version lacks come out as 0: unoccluded, unlit and a whole block face.
+    /// Quads with bits set past every field were not packed with this version at all.
+    pub fn convert(self, quad_ref: QuadRef, to: Self) -> Option<QuadRef> {
+        let bits = self.widths().iter().sum();
+        if quad_ref.checked_shr(bits).unwrap_or(0) != 0 {
+            return None;
+        }
+
+        to.pack(self.unpack(quad_ref))
+    }
+
+    /// Convert every quad of a mesh, as [`convert`](Self::convert) does,
+    /// leaving it as it was if any of them cannot be.
+    pub fn convert_mesh(self, mesh: &mut Mesh, to: Self) -> Result<(), QuadRef> {
+        let mut converted = mesh.clone();
+        for (quads, from) in converted.iter_mut().zip(mesh.iter()) {
+            for (quad, &quad_ref) in quads.iter_mut().zip(from) {
+                *quad = self.convert(quad_ref, to).ok_or(quad_ref)?;
+            }
+        }
+
+        *mesh = converted;
+        Ok(())
+    }
+}
+
+/// Write a mesh tagged with the version its quads are packed with, the current one:
+///
+/// ```text
+/// magic           AXMS
+/// version         u16
+/// quad counts     u32 per facing
+/// quads           u64 each, facing after facing
+/// ```
+///
+/// All of it little endian, and facings in the order of [`Facing::ALL`](super::Facing::ALL).
+pub fn write_mesh(out: &mut impl Write, mesh: &Mesh) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&QuadVersion::CURRENT.tag().to_le_bytes())?;
+
+    for quads in mesh {
+        out.write_all(&(quads.len() as u32).to_le_bytes())?;
+    }
+
+    for &quad_ref in mesh.iter().flatten() {
+        out.write_all(&quad_ref.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Read a mesh written by [`write_mesh`] by this or any earlier version,
+/// its quads converted to the current one.
+///
+/// Meshes of later versions are refused, as are older ones with quads
+/// that cannot be [converted](QuadVersion::convert).
+pub fn read_mesh(bytes: &[u8]) -> io::Result<Mesh> {
+    let (magic, bytes) = split(bytes, 4)?;
+    if magic != MAGIC {
+        return Err(invalid("not a mesh file"));
+    }
+
+    let (tag, mut bytes) = split(bytes, 2)?;
+    let tag = u16::from_le_bytes([tag[0], tag[1]]);
+    let version = QuadVersion::from_tag(tag).ok_or_else(|| {
+        let message = format!("unknown quad version {tag}");
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    })?;
+
+    let mut counts = [0; 6];
+    for count in &mut counts {
+        let (taken, rest) = split(bytes, 4)?;
+        *count = u32::from_le_bytes(taken.try_into().unwrap()) as usize;
+        bytes = rest;
+    }
+
+    let mut mesh = Mesh::default();
+    for (quads, count) in mesh.iter_mut().zip(counts) {
+        let (taken, rest) = split(bytes, count.checked_mul(8).ok_or_else(eof)?)?;
+        let taken = taken.chunks_exact(8);
+        quads.extend(taken.map(|bytes| QuadRef::from_le_bytes(bytes.try_into().unwrap())));
+        bytes = rest;
+    }
+
+    if !bytes.is_empty() {
+        return Err(invalid("trailing bytes after the quads"));
+    }
+
+    let converted = version.convert_mesh(&mut mesh, QuadVersion::CURRENT);
+    converted.map_err(|_| invalid("quads that do not fit the current version"))?;
+    Ok(mesh)
+}
+
+fn split(bytes: &[u8], len: usize) -> io::Result<(&[u8], &[u8])> {
+    match bytes.len() >= len {
+        true => Ok(bytes.split_at(len)),
+        false => Err(eof()),
+    }
+}
+
+fn eof() -> io::Error {
+    io::ErrorKind::UnexpectedEof.into()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuadVersion::*, *};
+    use crate::mesh::{quad_ref, QuadLayout, GRAIN_U, SHAPE_LOWER_HALF};
+
+    #[test]
+    fn the_current_version_packs_as_the_layout() {
+        let layout = QuadLayout::CHUNK_32;
+        let state = GRAIN_U | SHAPE_LOWER_HALF;
+        let quad = quad_ref(2000, (3, 17, 31), 9, 12, 4, 30);
+        let quad = layout.with_ao(layout.with_state(quad, state), [1, 2, 3, 0]);
+        let quad = quad | 201 << layout.material_shift();
+
+        // Offset, state, material, occlusion, location, sky exposure, block light, extent
+        let fields = State.unpack(quad);
+        let ao = 1 | 2 << 2 | 3 << 4;
+        let state = state as u64;
+        assert_eq!(fields, [2000, state, 201, ao, 3, 17, 31, 9, 12, 4, 30]);
+        assert_eq!(State.pack(fields), Some(quad));
+
+        let widths = QuadVersion::ALL.map(|version| version.widths().iter().sum::<u32>());
+        assert!(widths.iter().all(|&bits| bits <= QuadRef::BITS));
+    }
+
+    #[test]
+    fn older_quads_are_converted_forward_and_back() {
+        let layout = QuadLayout::CHUNK_32;
+
+        // Offset, location (x, y, z), sky exposure, then extent
+        let plain: QuadRef = 700 | 3 << 32 | 17 << 37 | 31 << 42 | 9 << 47 | 4 << 51 | 30 << 56;
+        let quad = Plain.convert(plain, State).unwrap();
+        assert_eq!(layout.offset(quad), 700);
+        assert_eq!(layout.location(quad), (3, 17, 31));
+        assert_eq!(layout.sky_exposure(quad), 9);
+        assert_eq!(layout.extent(quad), (4, 30));
+        assert_eq!((layout.material(quad), layout.block_light(quad)), (0, 0));
+        assert_eq!(State.convert(quad, Plain), Some(plain));
+
+        // Too large for the narrower offset and material, and losing block light going back
+        let lit = layout.with_block_light(quad, 3);
+        let far = Plain.convert(plain | 4096, State);
+        let material = Material.convert(300 << 20, Occlusion);
+        let dark = State.convert(lit, Occlusion);
+        assert_eq!((far, material, dark), (None, None, None));
+    }
+
+    #[test]
+    fn meshes_read_back_from_any_version() {
+        let mut mesh = Mesh::default();
+        mesh[0].push(quad_ref(5, (1, 2, 3), 4, 0, 0, 0));
+        mesh[4].push(quad_ref(6, (31, 0, 0), 15, 7, 31, 31));
+
+        let mut bytes = Vec::new();
+        write_mesh(&mut bytes, &mesh).unwrap();
+        assert_eq!(bytes.len(), 4 + 2 + 6 * 4 + 2 * 8);
+        assert_eq!(read_mesh(&bytes).unwrap(), mesh);
+
+        // The same mesh as written before block light, with the light left out
+        let mut old = mesh.clone();
+        old[4][0] = QuadLayout::CHUNK_32.with_block_light(old[4][0], 0);
+        let mut expected = old.clone();
+        State.convert_mesh(&mut old, Occlusion).unwrap();
+
+        let mut bytes = Vec::new();
+        write_mesh(&mut bytes, &old).unwrap();
+        bytes[4..6].copy_from_slice(&Occlusion.tag().to_le_bytes());
+        assert_eq!(read_mesh(&bytes).unwrap(), expected);
+
+        // Unknown versions, and quads that do not fit, are refused
+        bytes[4..6].copy_from_slice(&99u16.to_le_bytes());
+        assert!(read_mesh(&bytes).is_err());
+        expected[0][0] |= 1 << 62;
+        assert!(Plain.convert_mesh(&mut expected, State).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::{
+    Facing, Mesh, QuadLayout, SHAPE_INSET, SHAPE_LOWER_HALF, SHAPE_MASK, SHAPE_UPPER_HALF,
+};
+
+/// A quad corner the way a regular vertex buffer holds it, with no padding.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    /// Chunk coordinates.
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+
+    /// Counts blocks, so that a repeating texture tiles once per block.
+    pub uv: [f32; 2],
+    pub material: u32,
+}
+
+/// A mesh expanded into indexed triangles, for passes such as shadows or picking
+/// that want plain geometry rather than packed quads.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Geometry {
+    pub vertices: Vec<Vertex>,
+
+    /// Two counter-clockwise triangles per quad, counting from the first vertex.
+    pub indices: Vec<u32>,
+}
+
+impl Geometry {
+    /// Four vertices and six indices for every quad, whichever mesher made them.
+    pub fn new(mesh: &Mesh) -> Self {
+        let layout = QuadLayout::DEFAULT;
+        let len = mesh.iter().map(Vec::len).sum::<usize>();
+        let mut vertices = Vec::with_capacity(4 * len);
+        let mut indices = Vec::with_capacity(6 * len);
+
+        for facing in Facing::ALL {
+            let (nx, ny, nz) = facing.normal();
+            let normal = [nx as f32, ny as f32, nz as f32];
+            let [_, v_axis, depth_axis] = facing.axes();
+
+            for &qref in &mesh[facing as usize] {
+                let (w, h) = layout.extent(qref);
+                let (w, h) = (w as f32 + 1.0, h as f32 + 1.0);
+                let material = layout.material(qref);
+
+                // Halved quads span half their block along v, never more,
+                // and inset ones sit halfway through it
+                let shape = layout.state(qref) & SHAPE_MASK;
+                let (bottom, top) = match shape {
+                    SHAPE_LOWER_HALF => (0.0, 0.5),
+                    SHAPE_UPPER_HALF => (0.5, 1.0),
+                    _ => (0.0, h),
+                };
+
+                let inset = match shape {
+                    SHAPE_INSET => -0.5 * (nx + ny + nz) as f32,
+                    _ => 0.0,
+                };
+
+                let first = vertices.len() as u32;
+                indices.extend([0, 1, 2, 0, 2, 3].map(|corner| first + corner));
+
+                let corners = facing.winding().into_iter().zip(facing.quad_corners(qref));
+
+                for ((cu, cv), (x, y, z)) in corners {
+                    let mut position = [x as f32, y as f32, z as f32];
+                    let v = if cv == 0 { bottom } else { top };
+                    position[v_axis] += v - cv as f32 * h;
+                    position[depth_axis] += inset;
+
+                    vertices.push(Vertex {
+                        position,
+                        normal,
+                        uv: [cu as f32 * w, v],
+                        material,
+                    });
+                }
+            }
+        }
+
+        Self { vertices, indices }
+    }
+}
@@ -0,0 +1,476 @@
+use super::{
+    cull, cull_with_borders, layout::Encoding, light::light_quads, stats::MeshStats, Borders,
+    Chunk, Layers, LightGrid, Mesh, Mesher, QuadLayout, QuadRef,
+};
+
+/// Culling followed by [`greedy1d`] and [`greedy3d`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Greedy {
+    /// Widest and tallest a merged quad may grow, in blocks, so that tiled textures
+    /// and per-corner lighting keep their resolution across large faces.
+    pub max_extent: Option<u32>,
+
+    /// Whether to finish with [`merge_columns`], for the columns [`greedy3d`] leaves
+    /// behind, at the cost of another pass over every quad. Rows of quads are merged
+    /// before [`greedy3d`] stacks them, so there are seldom any left.
+    pub merge_columns: bool,
+}
+
+impl Greedy {
+    /// Like [`Mesher::mesh`], also measuring how much merging helped.
+    pub fn mesh_with_stats(&self, chunk: &Chunk) -> (Mesh, MeshStats) {
+        let culled = cull(chunk);
+        let mut mesh = culled.clone();
+        let columns = self.merge(&mut mesh);
+
+        let stats = MeshStats {
+            columns,
+            ..MeshStats::new(&culled, &mesh)
+        };
+
+        (mesh, stats)
+    }
+
+    /// Returns how many quads the column pass merged away.
+    fn merge(&self, mesh: &mut Mesh) -> usize {
+        let layout = QuadLayout::DEFAULT;
+        let max_extent = self.max_extent.unwrap_or(u32::MAX);
+
+        for quads in mesh.iter_mut() {
+            greedy1d_capped_in(layout, quads, max_extent);
+        }
+
+        greedy3d_capped_in(layout, mesh, max_extent);
+        if !self.merge_columns {
+            return 0;
+        }
+
+        // As merge_columns does, but keeping to the cap
+        let before: usize = mesh.iter().map(Vec::len).sum();
+        for quads in mesh.iter_mut() {
+            greedy1d_capped_in(layout, quads, max_extent);
+        }
+
+        before - mesh.iter().map(Vec::len).sum::<usize>()
+    }
+}
+
+impl Mesher for Greedy {
+    fn mesh(&self, chunk: &Chunk) -> Mesh {
+        self.mesh_with_borders(chunk, &Borders::NONE)
+    }
+
+    fn mesh_with_borders(&self, chunk: &Chunk, borders: &Borders) -> Mesh {
+        let mut mesh = cull_with_borders(chunk, borders);
+        self.merge(&mut mesh);
+        mesh
+    }
+
+    fn mesh_lit(&self, chunk: &Chunk, light: &LightGrid) -> Mesh {
+        self.mesh_lit_with_borders(chunk, &Borders::NONE, light)
+    }
+
+    fn mesh_lit_with_borders(&self, chunk: &Chunk, borders: &Borders, light: &LightGrid) -> Mesh {
+        let mut mesh = cull_with_borders(chunk, borders);
+        light_quads(&mut mesh, light);
+        self.merge(&mut mesh);
+        mesh
+    }
+}
+
+/// Cull a chunk and merge the resulting faces.
+pub fn mesh_chunk(chunk: &Chunk) -> Mesh {
+    mesh_chunk_with_borders(chunk, &Borders::NONE)
+}
+
+/// Like [`mesh_chunk`], culling boundary faces against the neighbor chunks first.
+pub fn mesh_chunk_with_borders(chunk: &Chunk, borders: &Borders) -> Mesh {
+    let mut mesh = cull_with_borders(chunk, borders);
+    mesh.iter_mut().for_each(greedy1d);
+    greedy3d(&mut mesh);
+    mesh
+}
+
+/// Like [`mesh_chunk`], keeping translucent quads apart.
+pub fn mesh_layers(chunk: &Chunk) -> Layers {
+    Layers::split(mesh_chunk(chunk))
+}
+
+/// Like [`mesh_chunk`], also measuring how much merging helped.
+pub fn mesh_chunk_with_stats(chunk: &Chunk) -> (Mesh, MeshStats) {
+    Greedy::default().mesh_with_stats(chunk)
+}
+
+/// Sort quads by depth, then y, then x, the order every pass here expects.
+/// Already sorted lists, as [`cull`] emits them, are only checked.
+pub fn sort_quads<L: Encoding>(layout: L, mesh: &mut [L::Ref]) {
+    let key = |&qref: &L::Ref| layout.location_key(qref);
+
+    if !mesh.is_sorted_by_key(key) {
+        mesh.sort_unstable_by_key(key);
+    }
+}
+
+/// Merge runs of quads along x.
+/// The list is sorted by depth, then y, then x first if needed.
+pub fn greedy1d(mesh: &mut Vec<QuadRef>) {
+    greedy1d_in(QuadLayout::DEFAULT, mesh);
+}
+
+/// Like [`greedy1d`], for quads packed with any layout.
+pub fn greedy1d_in<L: Encoding>(layout: L, mesh: &mut Vec<L::Ref>) {
+    greedy1d_capped_in(layout, mesh, u32::MAX);
+}
+
+/// Like [`greedy1d_in`], never merging quads wider than `max_extent` blocks.
+pub fn greedy1d_capped_in<L: Encoding>(layout: L, mesh: &mut Vec<L::Ref>, max_extent: u32) {
+    let fm = (1 << layout.coord_bits()) - 1;
+    let bound = fm.min(max_extent.saturating_sub(1));
+    let mut dest = 0;
+
+    sort_quads(layout, mesh);
+
+    for back in 0..mesh.len() {
+        let qref = mesh[back];
+
+        if dest > 0 {
+            let last = &mut mesh[dest - 1];
+            let x = layout.location_key(qref) & fm;
+            let last_x = layout.location_key(*last) & fm;
+            let (last_w, _) = layout.extent(*last);
+            let (w, _) = layout.extent(qref);
+            let (last_w, w) = (last_w as u32, w as u32);
+
+            // Same row and attributes, starting right where the last quad ends.
+            // Equal occlusion that does not vary along the row leaves no seam
+            let same = layout.matches_w(qref, *last);
+            let flat = layout.is_ao_flat_w(qref);
+            if same && flat && last_x + last_w + 1 == x && last_w + w < bound {
+                *last = layout.grow_w(*last, w + 1);
+                continue;
+            }
+        }
+
+        mesh[dest] = qref;
+        dest += 1;
+    }
+
+    mesh.truncate(dest);
+}
+
+/// Merge every face of a chunk.
+/// Each list is sorted by depth, then y, then x first if needed,
+/// and quads from different slices never merge.
+pub fn greedy3d(mesh: &mut Mesh) {
+    greedy3d_in(QuadLayout::DEFAULT, mesh);
+}
+
+/// Like [`greedy3d`], for quads packed with any layout.
+pub fn greedy3d_in<L: Encoding>(layout: L, mesh: &mut [Vec<L::Ref>; 6]) {
+    greedy3d_capped_in(layout, mesh, u32::MAX);
+}
+
+/// Like [`greedy3d_in`], never merging quads taller than `max_extent` blocks.
+pub fn greedy3d_capped_in<L: Encoding>(layout: L, mesh: &mut [Vec<L::Ref>; 6], max_extent: u32) {
+    let depth = |qref| layout.location_key(qref) >> (2 * layout.coord_bits());
+
+    for quads in mesh.iter_mut() {
+        sort_quads(layout, quads);
+        let mut merged = Vec::with_capacity(quads.len());
+        let mut start = 0;
+
+        while start < quads.len() {
+            let slice_depth = depth(quads[start]);
+            let len = quads[start..]
+                .iter()
+                .take_while(|&&qref| depth(qref) == slice_depth)
+                .count();
+
+            let mut slice = quads[start..start + len].to_vec();
+            greedy2d_capped_in(layout, &mut slice, max_extent);
+            merged.append(&mut slice);
+            start += len;
+        }
+
+        *quads = merged;
+    }
+}
+
+/// Merge side by side quads of equal height along x,
+/// catching the columns [`greedy2d`] leaves behind when it grows rows upward.
+pub fn merge_columns(mesh: &mut Mesh) {
+    merge_columns_in(QuadLayout::DEFAULT, mesh);
+}
+
+/// Like [`merge_columns`], for quads packed with any layout.
+pub fn merge_columns_in<L: Encoding>(layout: L, mesh: &mut [Vec<L::Ref>; 6]) {
+    // Equally tall quads in a row are already next to each other once sorted,
+    // and merging along x only asks for their heights to match
+    for quads in mesh.iter_mut() {
+        greedy1d_in(layout, quads);
+    }
+}
+
+/// Merge rows of quads of a single slice with matching extent into taller quads.
+/// The list is sorted by y, then x first if needed.
+pub fn greedy2d(mesh: &mut Vec<QuadRef>) {
+    greedy2d_in(QuadLayout::DEFAULT, mesh);
+}
+
+/// Like [`greedy2d`], for quads packed with any layout.
+pub fn greedy2d_in<L: Encoding>(layout: L, mesh: &mut Vec<L::Ref>) {
+    greedy2d_capped_in(layout, mesh, u32::MAX);
+}
+
+/// Like [`greedy2d_in`], never merging quads taller than `max_extent` blocks.
+/// Quads going in are left as wide as they are.
+#[inline(never)]
+pub fn greedy2d_capped_in<L: Encoding>(layout: L, mesh: &mut Vec<L::Ref>, max_extent: u32) {
+    let mut dest = 0;
+    let mut back = 0;
+    let mut lead = 0;
+
+    let cb = layout.coord_bits();
+    let fm = (1 << cb) - 1;
+    let bound = fm.min(max_extent.saturating_sub(1));
+
+    // y and x packed together compare in list order
+    let yx_mask = (1 << (2 * cb)) - 1;
+
+    sort_quads(layout, mesh);
+
+    // Each step compares the lead against the spot right on top of back,
+    // both packed as one number, instead of unpacking and comparing y and x apart.
+    // Selecting the outcome without branches measured slower, as every load
+    // then has to wait for the previous step to settle which quads come next.
+    //
+    // The cursors keep dest <= back <= lead, so indexing only checks lead against
+    // the length the loop just compared it with, which costs nothing measurable
+    while lead < mesh.len() {
+        debug_assert!(dest <= back && back <= lead);
+
+        let b = mesh[back];
+        let l = mesh[lead];
+
+        let bh = layout.extent(b).1 as u32;
+        let lh = layout.extent(l).1 as u32;
+        let above = (layout.location_key(b) & yx_mask) + ((bh + 1) << cb);
+        let lyx = layout.location_key(l) & yx_mask;
+
+        if lyx < above {
+            lead += 1;
+            continue;
+        }
+
+        // Matching fields include x and width, so merging quads line up
+        let fits = layout.matches_h(b, l) & layout.is_ao_flat_h(b) & (bh + lh < bound);
+
+        if lyx == above && fits {
+            mesh[lead] = layout.grow_h(b, lh + 1);
+            lead += 1;
+        } else {
+            mesh[dest] = b;
+            dest += 1;
+        }
+
+        back += 1;
+    }
+
+    // Nothing is left to stack on the rest
+    mesh.copy_within(back.., dest);
+    mesh.truncate(dest + mesh.len() - back);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+    use super::*;
+    use crate::mesh::{
+        debug::{clean_screen, render},
+        mesh_bounds, quad_ref, with_state, Axis, Facing, Half, Quad, AIR, GRAIN_U, LOG,
+        SHAPE_LOWER_HALF, SLAB,
+    };
+
+    // Color of every cell, and how many quads cover it
+    fn rasterize(mesh: &[QuadRef]) -> ([[u16; 32]; 32], [[u8; 32]; 32]) {
+        let mut screen = clean_screen::<32>();
+        let mut coverage = [[0; 32]; 32];
+
+        render(mesh, &mut screen);
+
+        for &qref in mesh {
+            let quad = Quad::from_ref(qref);
+            let (x0, y0, _) = quad.location();
+            let (x0, y0) = (x0 as usize, y0 as usize);
+
+            for row in &mut coverage[y0..][..quad.height() as usize] {
+                for cell in &mut row[x0..][..quad.width() as usize] {
+                    *cell += 1;
+                }
+            }
+        }
+
+        (screen.map(|row| row.map(|(color, _)| color)), coverage)
+    }
+
+    // Rows of runs of random colors, with holes in between
+    fn random_slice(rng: &mut impl Rng) -> Vec<QuadRef> {
+        let mut mesh = Vec::new();
+        let colors = rng.gen_range(1..=4);
+
+        for y in 0..32 {
+            let mut x = 0;
+
+            while x < 32 {
+                let len: i32 = rng.gen_range(1..=8);
+                let len = len.min(32 - x);
+                let color = rng.gen_range(0..=colors);
+
+                if color != 0 {
+                    let width = len as u8 - 1;
+                    mesh.push(quad_ref(color, (x, y, 0), 0, 0, width, 0));
+                }
+
+                x += len;
+            }
+        }
+
+        mesh
+    }
+
+    #[test]
+    fn greedy2d_preserves_coverage() {
+        let mut rng = StdRng::seed_from_u64(0x9E3779B9);
+
+        for round in 0..500 {
+            let mut mesh = random_slice(&mut rng);
+            let (colors, coverage) = rasterize(&mesh);
+
+            // Half of the time, exercise the sorting fallback too
+            if round % 2 == 1 {
+                mesh.shuffle(&mut rng);
+            }
+
+            let len = mesh.len();
+            greedy2d(&mut mesh);
+            let (merged_colors, merged_coverage) = rasterize(&mesh);
+
+            assert!(mesh.len() <= len, "merging added quads");
+            assert_eq!(merged_colors, colors, "coverage changed in round {round}");
+            assert_eq!(merged_coverage, coverage, "quads overlap in round {round}");
+        }
+    }
+
+    #[test]
+    fn capped_merging_keeps_quads_small() {
+        let mut rng = StdRng::seed_from_u64(0x85EBCA6B);
+
+        for round in 0..500 {
+            let max_extent = rng.gen_range(1..=8);
+            let mut mesh = random_slice(&mut rng);
+
+            // One block wide quads, so that only merging can grow them past the cap
+            mesh = mesh
+                .iter()
+                .flat_map(|&qref| {
+                    let quad = Quad::from_ref(qref);
+                    let (x, y, z) = quad.location();
+                    let color = quad.offset() as usize;
+                    let width = quad.width() as i32;
+                    (x..x + width).map(move |x| quad_ref(color, (x, y, z), 0, 0, 0, 0))
+                })
+                .collect();
+
+            let (colors, coverage) = rasterize(&mesh);
+
+            let layout = QuadLayout::DEFAULT;
+            greedy1d_capped_in(layout, &mut mesh, max_extent);
+            greedy2d_capped_in(layout, &mut mesh, max_extent);
+            let (merged_colors, merged_coverage) = rasterize(&mesh);
+
+            assert_eq!(merged_colors, colors, "coverage changed in round {round}");
+            assert_eq!(merged_coverage, coverage, "quads overlap in round {round}");
+
+            for &qref in &mesh {
+                let quad = Quad::from_ref(qref);
+                let extent = quad.width().max(quad.height()) as u32;
+                assert!(
+                    extent <= max_extent,
+                    "{extent} past {max_extent} in round {round}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_in_other_states_keep_apart() {
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        let layout = QuadLayout::DEFAULT;
+
+        // A row of logs lying along x, then along z
+        for x in 0..8 {
+            let axis = if x < 4 { Axis::X } else { Axis::Z };
+            chunk[0][0][x] = with_state(LOG, axis, Half::Whole);
+        }
+
+        // A stack of bottom slabs, with gaps between them
+        for y in 0..3 {
+            chunk[8][y][8] = with_state(SLAB, Axis::Y, Half::Bottom);
+        }
+
+        let mesh = mesh_chunk(&chunk);
+        let of = |facing: Facing, block| {
+            let quads = mesh[facing as usize].iter().copied();
+            let quads = quads.filter(move |&qref| layout.material(qref) == block as u32);
+            quads.map(Quad::from_ref).collect::<Vec<_>>()
+        };
+
+        // Grain runs along u on top of the first logs, along v on top of the rest
+        let grain = |quad: &Quad| (quad.state(), quad.width());
+        let tops: Vec<_> = of(Facing::PosY, LOG).iter().map(grain).collect();
+        assert_eq!(tops, [(GRAIN_U, 4), (0, 4)]);
+
+        // Slab sides stay one each, and their inset tops all show
+        let sides = of(Facing::PosX, SLAB);
+        assert_eq!(sides.len(), 3);
+        assert!(sides.iter().all(|quad| quad.state() == SHAPE_LOWER_HALF));
+        assert_eq!(of(Facing::PosY, SLAB).len(), 3);
+        assert_eq!(of(Facing::NegY, SLAB).len(), 3);
+    }
+
+    #[test]
+    fn column_merging_is_counted() {
+        let mut rng = StdRng::seed_from_u64(0xC2B2AE35);
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for (z, plane) in chunk.iter_mut().enumerate() {
+            for x in 0..32 {
+                let height = rng.gen_range(1..=4) + (x + z) / 8;
+                (0..height).for_each(|y| plane[y][x] = 1);
+            }
+        }
+
+        let (plain, plain_stats) = Greedy::default().mesh_with_stats(&chunk);
+        let columns = Greedy {
+            merge_columns: true,
+            ..Greedy::default()
+        };
+
+        let (merged, stats) = columns.mesh_with_stats(&chunk);
+        let count = |mesh: &Mesh| mesh.iter().map(Vec::len).sum::<usize>();
+        assert_eq!(plain_stats.columns, 0);
+        assert_eq!(count(&merged) + stats.columns, count(&plain));
+        assert_eq!(stats.output, count(&merged));
+    }
+
+    #[test]
+    fn bounds_hug_the_blocks_meshed() {
+        let mut chunk = [[[AIR; 32]; 32]; 32];
+        assert_eq!(mesh_bounds(&mesh_chunk(&chunk)), None);
+
+        chunk[4][3][2] = 1;
+        chunk[7][6][5] = 1;
+        let bounds = mesh_bounds(&mesh_chunk(&chunk));
+        assert_eq!(bounds, Some(([2, 3, 4], [6, 7, 8])));
+    }
+}
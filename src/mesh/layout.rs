@@ -0,0 +1,410 @@
+use super::{QuadRef, SHAPE_LOWER_HALF, SHAPE_MASK, SHAPE_UPPER_HALF};
+
+/// Bit layout of a [`QuadRef`], sized for a given chunk.
+///
+/// Fields are packed from the least significant bit up: offset, 4 bits of state,
+/// material, 2 bits of ambient occlusion per corner, location (x, y, z), 4 bits of sky exposure,
+/// 4 bits of block light, then width and height minus one.
+/// Coordinates and extents all take `coord_bits` bits.
+///
+/// Quads only merge when every field but their location and extent match,
+/// so quads of different materials, states or lighting never merge.
+///
+/// The state tells which way the axis of an oriented block runs across the quad
+/// in its low 2 bits, and which part of the block face the quad covers in the high 2,
+/// as [`face_state`](super::face_state) packs them.
+///
+/// Corners are numbered `u + 2v`, each holding how occluded it is, from 0 to 3.
+///
+/// Changing the fields of [`QuadLayout::CHUNK_32`] takes a new
+/// [`QuadVersion`](super::format::QuadVersion), for quads kept around to be converted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuadLayout {
+    offset_bits: u32,
+    material_bits: u32,
+    coord_bits: u32,
+}
+
+impl QuadLayout {
+    /// 16³ chunks.
+    pub const CHUNK_16: Self = Self::new(15, 8, 4);
+
+    /// 32³ chunks, the layout [`super::Chunk`] is meshed with.
+    /// The offset loses eight bits to make room for block light and state.
+    pub const CHUNK_32: Self = Self::new(11, 8, 5);
+
+    /// 62³ chunks, meshed out of 64³ voxels with one block of padding on every side.
+    /// The offset loses thirteen bits to make room for the wider fields, block light and state.
+    pub const CHUNK_62: Self = Self::new(6, 8, 6);
+
+    pub const DEFAULT: Self = Self::CHUNK_32;
+
+    const STATE_BITS: u32 = 4;
+    const AO_BITS: u32 = 8;
+    const SKY_EXPOSURE_BITS: u32 = 4;
+    const BLOCK_LIGHT_BITS: u32 = 4;
+
+    /// Panics if the fields do not fit in a [`QuadRef`].
+    pub const fn new(offset_bits: u32, material_bits: u32, coord_bits: u32) -> Self {
+        let light_bits = Self::SKY_EXPOSURE_BITS + Self::BLOCK_LIGHT_BITS;
+        let fixed_bits = Self::STATE_BITS + Self::AO_BITS + light_bits;
+        let bits = offset_bits + material_bits + 5 * coord_bits + fixed_bits;
+        assert!(bits <= QuadRef::BITS, "quad layout does not fit");
+        let fields_fit = offset_bits <= 32 && material_bits <= 32 && coord_bits <= 8;
+        assert!(fields_fit, "quad field too wide");
+
+        Self {
+            offset_bits,
+            material_bits,
+            coord_bits,
+        }
+    }
+
+    pub const fn offset_bits(&self) -> u32 {
+        self.offset_bits
+    }
+
+    pub const fn material_bits(&self) -> u32 {
+        self.material_bits
+    }
+
+    pub const fn coord_bits(&self) -> u32 {
+        self.coord_bits
+    }
+
+    /// Number of blocks along each side of the chunk.
+    pub const fn size(&self) -> u32 {
+        1 << self.coord_bits
+    }
+
+    pub const fn state_shift(&self) -> u32 {
+        self.offset_bits
+    }
+
+    pub const fn material_shift(&self) -> u32 {
+        self.state_shift() + Self::STATE_BITS
+    }
+
+    pub const fn ao_shift(&self) -> u32 {
+        self.material_shift() + self.material_bits
+    }
+
+    pub const fn x_shift(&self) -> u32 {
+        self.ao_shift() + Self::AO_BITS
+    }
+
+    pub const fn y_shift(&self) -> u32 {
+        self.x_shift() + self.coord_bits
+    }
+
+    pub const fn z_shift(&self) -> u32 {
+        self.y_shift() + self.coord_bits
+    }
+
+    pub const fn sky_exposure_shift(&self) -> u32 {
+        self.z_shift() + self.coord_bits
+    }
+
+    pub const fn block_light_shift(&self) -> u32 {
+        self.sky_exposure_shift() + Self::SKY_EXPOSURE_BITS
+    }
+
+    pub const fn width_shift(&self) -> u32 {
+        self.block_light_shift() + Self::BLOCK_LIGHT_BITS
+    }
+
+    pub const fn height_shift(&self) -> u32 {
+        self.width_shift() + self.coord_bits
+    }
+
+    /// Mask of a single coordinate or extent field, once shifted down.
+    pub const fn field_mask(&self) -> u64 {
+        (1 << self.coord_bits) - 1
+    }
+
+    pub const fn offset_mask(&self) -> u64 {
+        (1 << self.offset_bits) - 1
+    }
+
+    /// Mask of the material field, once shifted down.
+    pub const fn material_mask(&self) -> u64 {
+        (1 << self.material_bits) - 1
+    }
+
+    pub const fn state_mask(&self) -> u64 {
+        0xF << self.state_shift()
+    }
+
+    pub const fn ao_mask(&self) -> u64 {
+        0xFF << self.ao_shift()
+    }
+
+    pub const fn sky_exposure_mask(&self) -> u64 {
+        0xF << self.sky_exposure_shift()
+    }
+
+    pub const fn block_light_mask(&self) -> u64 {
+        0xF << self.block_light_shift()
+    }
+
+    pub const fn x_mask(&self) -> u64 {
+        self.field_mask() << self.x_shift()
+    }
+
+    pub const fn y_mask(&self) -> u64 {
+        self.field_mask() << self.y_shift()
+    }
+
+    pub const fn z_mask(&self) -> u64 {
+        self.field_mask() << self.z_shift()
+    }
+
+    pub const fn width_mask(&self) -> u64 {
+        self.field_mask() << self.width_shift()
+    }
+
+    pub const fn height_mask(&self) -> u64 {
+        self.field_mask() << self.height_shift()
+    }
+
+    /// Bits that must match for two quads of a row to merge along x.
+    /// Their ambient occlusion must also be [flat along x](Self::is_ao_flat_w).
+    pub const fn merge_w_mask(&self) -> u64 {
+        !(self.x_mask() | self.width_mask())
+    }
+
+    /// Bits that must match for two stacked quads of a slice to merge along y.
+    /// Their ambient occlusion must also be [flat along y](Self::is_ao_flat_h),
+    /// and neither may be [halved along y](Self::is_halved_h).
+    pub const fn merge_h_mask(&self) -> u64 {
+        !(self.y_mask() | self.height_mask())
+    }
+
+    /// Pack a quad without block light or state. Coordinates and extents wrap to the chunk.
+    /// Panics if the offset does not fit in the layout, rather than wrapping onto the data of another quad.
+    pub const fn pack(
+        &self,
+        offset: u32,
+        material: u32,
+        location: (i32, i32, i32),
+        sky_exposure: u8,
+        width: u8,
+        height: u8,
+    ) -> QuadRef {
+        assert!(
+            offset as u64 <= self.offset_mask(),
+            "quad offset out of range"
+        );
+
+        let fm = self.field_mask();
+        offset as u64
+            | (material as u64 & self.material_mask()) << self.material_shift()
+            | (location.0 as u64 & fm) << self.x_shift()
+            | (location.1 as u64 & fm) << self.y_shift()
+            | (location.2 as u64 & fm) << self.z_shift()
+            | (sky_exposure as u64 & 0xF) << self.sky_exposure_shift()
+            | (width as u64 & fm) << self.width_shift()
+            | (height as u64 & fm) << self.height_shift()
+    }
+
+    pub const fn offset(&self, quad_ref: QuadRef) -> u32 {
+        (quad_ref & self.offset_mask()) as u32
+    }
+
+    /// Material of a quad, the kind of its block, picking its entry in a row
+    /// of the material palette, see [`entry`](super::palette::entry),
+    /// or its [palette](super::palette::Palette) index once indexed.
+    pub const fn material(&self, quad_ref: QuadRef) -> u32 {
+        ((quad_ref >> self.material_shift()) & self.material_mask()) as u32
+    }
+
+    /// Grain and shape of a quad, see [`face_state`](super::face_state).
+    pub const fn state(&self, quad_ref: QuadRef) -> u8 {
+        (quad_ref >> self.state_shift()) as u8 & 0xF
+    }
+
+    /// Replace the state of a quad. Values wrap to 4 bits.
+    pub const fn with_state(&self, quad_ref: QuadRef, state: u8) -> QuadRef {
+        let state = (state & 0xF) as u64;
+        quad_ref & !self.state_mask() | state << self.state_shift()
+    }
+
+    /// Whether a quad covers only the lower or upper half of its block along y,
+    /// like the sides of a slab. Stacking such quads would cover the gap between them,
+    /// so they never merge along y.
+    pub const fn is_halved_h(&self, quad_ref: QuadRef) -> bool {
+        let shape = self.state(quad_ref) & SHAPE_MASK;
+        shape == SHAPE_LOWER_HALF || shape == SHAPE_UPPER_HALF
+    }
+
+    pub const fn ao(&self, quad_ref: QuadRef) -> [u8; 4] {
+        let ao = (quad_ref >> self.ao_shift()) as u8;
+        [ao & 3, ao >> 2 & 3, ao >> 4 & 3, ao >> 6 & 3]
+    }
+
+    /// Replace the ambient occlusion of a quad. Values wrap to 2 bits.
+    pub const fn with_ao(&self, quad_ref: QuadRef, ao: [u8; 4]) -> QuadRef {
+        let [a, b, c, d] = ao;
+        let ao = (a & 3) | (b & 3) << 2 | (c & 3) << 4 | (d & 3) << 6;
+        quad_ref & !self.ao_mask() | (ao as u64) << self.ao_shift()
+    }
+
+    /// Whether occlusion does not change along x, so that stretching the quad
+    /// interpolates it exactly like the run of quads it replaces.
+    pub const fn is_ao_flat_w(&self, quad_ref: QuadRef) -> bool {
+        let [a, b, c, d] = self.ao(quad_ref);
+        a == b && c == d
+    }
+
+    /// Whether occlusion does not change along y.
+    pub const fn is_ao_flat_h(&self, quad_ref: QuadRef) -> bool {
+        let [a, b, c, d] = self.ao(quad_ref);
+        a == c && b == d
+    }
+
+    pub const fn location(&self, quad_ref: QuadRef) -> (i32, i32, i32) {
+        let x = (quad_ref >> self.x_shift()) & self.field_mask();
+        let y = (quad_ref >> self.y_shift()) & self.field_mask();
+        let z = (quad_ref >> self.z_shift()) & self.field_mask();
+        (x as _, y as _, z as _)
+    }
+
+    pub const fn sky_exposure(&self, quad_ref: QuadRef) -> u8 {
+        (quad_ref >> self.sky_exposure_shift()) as u8 & 0xF
+    }
+
+    /// Light from nearby emitting blocks, from 0 to 15.
+    pub const fn block_light(&self, quad_ref: QuadRef) -> u8 {
+        (quad_ref >> self.block_light_shift()) as u8 & 0xF
+    }
+
+    /// Replace the block light of a quad. Values wrap to 4 bits.
+    pub const fn with_block_light(&self, quad_ref: QuadRef, block_light: u8) -> QuadRef {
+        let block_light = (block_light & 0xF) as u64;
+        quad_ref & !self.block_light_mask() | block_light << self.block_light_shift()
+    }
+
+    /// Width and height of a quad, minus one.
+    pub const fn extent(&self, quad_ref: QuadRef) -> (u8, u8) {
+        let width = (quad_ref >> self.width_shift()) & self.field_mask();
+        let height = (quad_ref >> self.height_shift()) & self.field_mask();
+        (width as _, height as _)
+    }
+
+    /// Grow a quad by one block along x.
+    pub fn extend_w(&self, quad_ref: &mut QuadRef) {
+        *quad_ref += 1 << self.width_shift();
+    }
+
+    /// Grow a quad by one block along y.
+    pub fn extend_h(&self, quad_ref: &mut QuadRef) {
+        *quad_ref += 1 << self.height_shift();
+    }
+}
+
+/// How quads are packed, as far as the merging passes care,
+/// so that they run on quads of any width without unpacking them.
+pub trait Encoding: Copy {
+    type Ref: Copy + Eq;
+
+    /// Bits of every coordinate and extent field.
+    fn coord_bits(&self) -> u32;
+
+    /// Location as one number, ordered by depth, then y, then x.
+    fn location_key(&self, quad_ref: Self::Ref) -> u32;
+
+    /// Width and height of a quad, minus one.
+    fn extent(&self, quad_ref: Self::Ref) -> (u8, u8);
+
+    /// Whether two quads match in every field but x and width.
+    fn matches_w(&self, a: Self::Ref, b: Self::Ref) -> bool;
+
+    /// Whether two quads match in every field but y and height.
+    fn matches_h(&self, a: Self::Ref, b: Self::Ref) -> bool;
+
+    fn is_ao_flat_w(&self, quad_ref: Self::Ref) -> bool;
+    fn is_ao_flat_h(&self, quad_ref: Self::Ref) -> bool;
+
+    /// Grow a quad along x by `blocks`, which must still fit in its width.
+    fn grow_w(&self, quad_ref: Self::Ref, blocks: u32) -> Self::Ref;
+
+    /// Grow a quad along y by `blocks`, which must still fit in its height.
+    fn grow_h(&self, quad_ref: Self::Ref, blocks: u32) -> Self::Ref;
+}
+
+impl Encoding for QuadLayout {
+    type Ref = QuadRef;
+
+    fn coord_bits(&self) -> u32 {
+        QuadLayout::coord_bits(self)
+    }
+
+    fn location_key(&self, quad_ref: QuadRef) -> u32 {
+        // z, y and x are packed next to each other
+        let mask = (1 << (3 * self.coord_bits)) - 1;
+        (quad_ref >> self.x_shift() & mask) as _
+    }
+
+    fn extent(&self, quad_ref: QuadRef) -> (u8, u8) {
+        QuadLayout::extent(self, quad_ref)
+    }
+
+    fn matches_w(&self, a: QuadRef, b: QuadRef) -> bool {
+        (a ^ b) & self.merge_w_mask() == 0
+    }
+
+    fn matches_h(&self, a: QuadRef, b: QuadRef) -> bool {
+        (a ^ b) & self.merge_h_mask() == 0 && !self.is_halved_h(a)
+    }
+
+    fn is_ao_flat_w(&self, quad_ref: QuadRef) -> bool {
+        QuadLayout::is_ao_flat_w(self, quad_ref)
+    }
+
+    fn is_ao_flat_h(&self, quad_ref: QuadRef) -> bool {
+        QuadLayout::is_ao_flat_h(self, quad_ref)
+    }
+
+    fn grow_w(&self, quad_ref: QuadRef, blocks: u32) -> QuadRef {
+        quad_ref + ((blocks as u64) << self.width_shift())
+    }
+
+    fn grow_h(&self, quad_ref: QuadRef, blocks: u32) -> QuadRef {
+        quad_ref + ((blocks as u64) << self.height_shift())
+    }
+}
+
+impl Default for QuadLayout {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_fill_their_field() {
+        for layout in [QuadLayout::CHUNK_32, QuadLayout::CHUNK_62] {
+            let offset = layout.offset_mask() as u32;
+            let quad_ref = layout.pack(offset, 1, (0, 0, 0), 0, 0, 0);
+            assert_eq!(layout.offset(quad_ref), offset);
+            assert_eq!(layout.material(quad_ref), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "quad offset out of range")]
+    fn offsets_past_their_field_panic() {
+        let layout = QuadLayout::CHUNK_32;
+        layout.pack(layout.offset_mask() as u32 + 1, 0, (0, 0, 0), 0, 0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "quad offset out of range")]
+    fn offsets_past_a_narrow_field_panic() {
+        let layout = QuadLayout::CHUNK_62;
+        layout.pack(layout.offset_mask() as u32 + 1, 0, (0, 0, 0), 0, 0, 0);
+    }
+}
@@ -0,0 +1,75 @@
+use super::{BlockId, Facing, Mesh, QuadLayout};
+
+/// Light level of the brightest blocks, fading by one every block away.
+pub const MAX_LIGHT: u8 = 15;
+
+/// Light a block gives off, from 0 to [`MAX_LIGHT`].
+pub const fn emitted_light(block: BlockId) -> u8 {
+    match block {
+        super::LAMP => 14,
+        _ => 0,
+    }
+}
+
+/// Block light in and around a chunk, one block past its sides included,
+/// so that faces on its boundary see the light in front of them.
+///
+/// Levels are indexed as `[z][y][x]` from -1 up to 32 along every axis.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LightGrid {
+    levels: Box<[[[u8; 34]; 34]; 34]>,
+}
+
+impl LightGrid {
+    /// No light at all, as a chunk is meshed without any.
+    pub fn dark() -> Self {
+        Self {
+            levels: Box::new([[[0; 34]; 34]; 34]),
+        }
+    }
+
+    /// Light at the given chunk coordinates, none outside the grid.
+    pub fn get(&self, (x, y, z): (i32, i32, i32)) -> u8 {
+        let range = -1..33;
+
+        if !(range.contains(&x) && range.contains(&y) && range.contains(&z)) {
+            return 0;
+        }
+
+        self.levels[(z + 1) as usize][(y + 1) as usize][(x + 1) as usize]
+    }
+
+    /// Panics outside the grid.
+    pub fn set(&mut self, (x, y, z): (i32, i32, i32), level: u8) {
+        self.levels[(z + 1) as usize][(y + 1) as usize][(x + 1) as usize] = level;
+    }
+
+    pub fn is_dark(&self) -> bool {
+        let mut levels = self.levels.iter().flatten().flatten();
+        levels.all(|&level| level == 0)
+    }
+}
+
+impl Default for LightGrid {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// Give every quad the light of the block in front of its face.
+///
+/// Quads must be 1x1, as [`super::cull`] emits them, so that merging them
+/// afterwards keeps quads lit differently apart.
+pub fn light_quads(mesh: &mut Mesh, light: &LightGrid) {
+    let layout = QuadLayout::DEFAULT;
+
+    for facing in Facing::ALL {
+        let (nx, ny, nz) = facing.normal();
+
+        for qref in &mut mesh[facing as usize] {
+            let (x, y, z) = facing.to_world(layout.location(*qref));
+            let level = light.get((x + nx, y + ny, z + nz));
+            *qref = layout.with_block_light(*qref, level);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::cmp::Reverse;
+
+use super::{block_kind, is_transparent, BlockId, Chunk, Layers, Mesh, Mesher, AIR};
+
+/// Coarser levels of detail meshed for every chunk besides the full one,
+/// each with blocks twice as large along every axis as the one before.
+pub const LODS: usize = 2;
+
+/// Blocks of a chunk merged 2×2×2 at a time, `level` times over, into its low corner,
+/// with the rest of it left empty. Cells at least half filled come out as the kind
+/// most of their blocks are, ties going to the lowest, and the rest as air.
+///
+/// Translucent blocks count as air, for far off ground to show through water
+/// drawn in full detail.
+pub fn downsample(chunk: &Chunk, level: usize) -> Box<Chunk> {
+    let mut blocks = Box::new(*chunk);
+
+    for step in 1..=level {
+        let size = 32 >> step;
+        let mut merged = Box::new([[[AIR; 32]; 32]; 32]);
+
+        for (z, plane) in merged.iter_mut().enumerate().take(size) {
+            for (y, row) in plane.iter_mut().enumerate().take(size) {
+                for (x, block) in row.iter_mut().enumerate().take(size) {
+                    *block = merge(&blocks, [x, y, z]);
+                }
+            }
+        }
+
+        blocks = merged;
+    }
+
+    blocks
+}
+
+/// Opaque quads of every coarser level of a chunk, as `mesher` meshes
+/// what [`downsample`] leaves of it. Quads are placed in blocks of their own level,
+/// to be scaled up by `2^level` when drawn, the first one being level 1.
+pub fn mesh_lods(mesher: &dyn Mesher, chunk: &Chunk) -> [Mesh; LODS] {
+    let mut blocks = Box::new(*chunk);
+
+    std::array::from_fn(|_| {
+        blocks = downsample(&blocks, 1);
+        Layers::split(mesher.mesh(&blocks)).opaque
+    })
+}
+
+// What the 2×2×2 blocks of the cell at `[x, y, z]` come out as
+fn merge(blocks: &Chunk, [x, y, z]: [usize; 3]) -> BlockId {
+    let mut kinds = Vec::with_capacity(8);
+
+    for dz in 0..2 {
+        for dy in 0..2 {
+            for dx in 0..2 {
+                let block = blocks[2 * z + dz][2 * y + dy][2 * x + dx];
+                if !is_transparent(block) {
+                    kinds.push(block_kind(block));
+                }
+            }
+        }
+    }
+
+    if kinds.len() < 4 {
+        return AIR;
+    }
+
+    kinds.sort_unstable();
+    let runs = kinds.chunk_by(|a, b| a == b);
+    let most = runs.max_by_key(|run| (run.len(), Reverse(run[0])));
+    most.map_or(AIR, |run| run[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Culled, GRASS, WATER};
+
+    #[test]
+    fn cells_take_the_kind_most_of_them_are() {
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for plane in &mut chunk[..4] {
+            for row in &mut plane[..4] {
+                row[..4].fill(1);
+            }
+        }
+
+        // Most of the first cell is grass
+        chunk[0][0][..2].fill(GRASS);
+        chunk[0][1][..2].fill(GRASS);
+        chunk[1][0][0] = GRASS;
+
+        // Three blocks are too few, water not counting
+        chunk[0][0][8..10].fill(1);
+        chunk[0][1][8] = 1;
+        chunk[1][1][9] = WATER;
+
+        let half = downsample(&chunk, 1);
+        assert_eq!(half[0][0][0], GRASS);
+        assert_eq!(half[1][1][1], 1);
+        assert_eq!(half[0][0][4], AIR);
+        assert_eq!(downsample(&chunk, 2)[0][0][0], 1);
+
+        // A cube of 2×2×2 blocks, then a single one
+        let [half, quarter] = mesh_lods(&Culled, &chunk);
+        assert_eq!(half.iter().map(Vec::len).sum::<usize>(), 24);
+        assert_eq!(quarter.iter().map(Vec::len).sum::<usize>(), 6);
+    }
+}
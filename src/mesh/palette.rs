@@ -0,0 +1,108 @@
+use super::{BlockId, Chunk, Facing, Mesh, QuadLayout};
+
+/// Blocks a chunk's mesh shows, in order of first appearance.
+///
+/// Once a mesh is [indexed](Palette::index), the material field of its quads
+/// holds an index into the palette instead of the kind of its block. The GPU looks up
+/// the actual material in a small per-chunk buffer built from [`Palette::entries`],
+/// so swapping materials, say to tint grass for another biome, only rewrites that buffer.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Palette {
+    blocks: Vec<BlockId>,
+}
+
+impl Palette {
+    /// Rewrite quad materials into palette indices, `mesh` being meshed out of `chunk`.
+    /// Merging is unaffected, as distinct blocks keep distinct indices.
+    pub fn index(chunk: &Chunk, mesh: &mut Mesh) -> Self {
+        Self::index_in(QuadLayout::DEFAULT, chunk, mesh)
+    }
+
+    /// Like [`Palette::index`], for quads packed with any layout.
+    ///
+    /// Blocks are looked up in the chunk where every quad starts, rather than read back
+    /// out of the quad, whose material field holds no more of the block than fits in it.
+    /// Panics if the chunk shows more distinct blocks than the material field can index.
+    pub fn index_in(layout: QuadLayout, chunk: &Chunk, mesh: &mut Mesh) -> Self {
+        let mut palette = Self::default();
+        let material_mask = layout.material_mask() << layout.material_shift();
+
+        for facing in Facing::ALL {
+            for qref in &mut mesh[facing as usize] {
+                let (x, y, z) = facing.to_world(layout.location(*qref));
+                let block = chunk[z as usize][y as usize][x as usize];
+                let index = match palette.blocks.iter().position(|&b| b == block) {
+                    Some(index) => index,
+                    None => {
+                        palette.blocks.push(block);
+                        palette.blocks.len() - 1
+                    }
+                };
+
+                assert!(index as u64 <= layout.material_mask(), "palette overflow");
+                *qref = *qref & !material_mask | (index as u64) << layout.material_shift();
+            }
+        }
+
+        palette
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Block behind a palette index.
+    pub fn block(&self, index: u32) -> Option<BlockId> {
+        self.blocks.get(index as usize).copied()
+    }
+
+    pub fn blocks(&self) -> &[BlockId] {
+        &self.blocks
+    }
+
+    /// Palette buffer contents, one [`entry`] per block as given by `material`.
+    pub fn entries(&self, material: impl Fn(BlockId) -> u32) -> Vec<u32> {
+        self.blocks.iter().map(|&block| material(block)).collect()
+    }
+}
+
+/// Pack an entry of the material palette: texture array layer in the low byte,
+/// RGB tint above it. Tints are sRGB-encoded, like the texels they multiply,
+/// and decoded before lighting. The renderer lays its palette out as rows of entries
+/// indexed by the material of a quad, one row per biome, while a chunk's [`Palette`]
+/// has an entry per block it shows.
+pub const fn entry(layer: u8, [r, g, b]: [u8; 3]) -> u32 {
+    layer as u32 | (r as u32) << 8 | (g as u32) << 16 | (b as u32) << 24
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{cull, with_state, Axis, Half, AIR};
+
+    #[test]
+    fn blocks_are_indexed_past_the_material_field() {
+        // Stone, and stone lying along x, whose kinds are the same
+        let lying = with_state(1, Axis::X, Half::Whole);
+        let mut chunk = [[[AIR; 32]; 32]; 32];
+        chunk[4][4][4] = 1;
+        chunk[4][4][8] = lying;
+
+        let mut mesh = cull(&chunk);
+        let palette = Palette::index(&chunk, &mut mesh);
+        assert_eq!(palette.len(), 2);
+
+        let layout = QuadLayout::DEFAULT;
+        for facing in Facing::ALL {
+            for &qref in &mesh[facing as usize] {
+                let (x, y, z) = facing.to_world(layout.location(qref));
+                let block = palette.block(layout.material(qref));
+                assert_eq!(block, Some(chunk[z as usize][y as usize][x as usize]));
+            }
+        }
+    }
+}
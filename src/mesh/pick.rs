@@ -0,0 +1,152 @@
+use super::{BlockId, Chunk, Facing, AIR, WATER};
+
+/// Where a ray first runs into a block it can be built against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// Chunk coordinates of the block hit.
+    pub location: (i32, i32, i32),
+
+    /// Face the ray went in through, `None` if it started inside the block.
+    pub facing: Option<Facing>,
+
+    /// Along the ray, in lengths of its direction.
+    pub distance: f32,
+}
+
+/// Whether rays stop at `block`. Water is looked through, like air.
+pub const fn is_pickable(block: BlockId) -> bool {
+    block != AIR && block != WATER
+}
+
+/// First pickable block of `chunk` along the ray from `origin` towards `direction`,
+/// both in chunk coordinates, up to `reach` lengths of `direction` away.
+///
+/// Rays starting outside the chunk are moved up to where they enter it first.
+/// Blocks are then stepped through as in Amanatides and Woo's traversal,
+/// so none along the ray is skipped and none is visited twice.
+pub fn raycast(chunk: &Chunk, origin: [f32; 3], direction: [f32; 3], reach: f32) -> Option<Hit> {
+    // Span of the ray within the chunk, and the axis it entered across
+    let (mut enter, mut exit, mut entered) = (0.0, reach, None);
+    for (axis, (&start, &d)) in origin.iter().zip(&direction).enumerate() {
+        if d == 0.0 {
+            if !(0.0..32.0).contains(&start) {
+                return None;
+            }
+
+            continue;
+        }
+
+        let near = (0.0 - start) / d;
+        let far = (32.0 - start) / d;
+        let (near, far) = (near.min(far), near.max(far));
+
+        if near > enter {
+            (enter, entered) = (near, Some(axis));
+        }
+
+        exit = f32::min(exit, far);
+    }
+
+    if enter > exit {
+        return None;
+    }
+
+    let step = direction.map(|d| if d < 0.0 { -1 } else { 1 });
+    let mut block: [i32; 3] = std::array::from_fn(|axis| {
+        let at = origin[axis] + direction[axis] * enter;
+        (at.floor() as i32).clamp(0, 31)
+    });
+
+    // Distance to the next boundary along each axis, and between boundaries
+    let mut next: [f32; 3] = std::array::from_fn(|axis| {
+        let boundary = (block[axis] + (step[axis] > 0) as i32) as f32;
+        match direction[axis] == 0.0 {
+            true => f32::INFINITY,
+            false => (boundary - origin[axis]) / direction[axis],
+        }
+    });
+
+    let delta = direction.map(|d| (1.0 / d).abs());
+    let mut distance = enter;
+
+    loop {
+        let [x, y, z] = block;
+        if is_pickable(chunk[z as usize][y as usize][x as usize]) {
+            let facing = entered.and_then(|axis| {
+                let mut normal = [0; 3];
+                normal[axis] = -step[axis];
+
+                let normal = (normal[0], normal[1], normal[2]);
+                let mut facings = Facing::ALL.into_iter();
+                facings.find(|facing| facing.normal() == normal)
+            });
+
+            return Some(Hit {
+                location: (x, y, z),
+                facing,
+                distance,
+            });
+        }
+
+        let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b])).unwrap();
+
+        distance = next[axis];
+        block[axis] += step[axis];
+        next[axis] += delta[axis];
+        entered = Some(axis);
+
+        if distance > exit || !(0..32).contains(&block[axis]) {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floor() -> Box<Chunk> {
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for plane in &mut chunk[..] {
+            plane[0] = [1; 32];
+        }
+
+        chunk
+    }
+
+    #[test]
+    fn looking_down_hits_the_top() {
+        let chunk = floor();
+        let hit = raycast(&chunk, [4.5, 10.0, 7.5], [0.0, -1.0, 0.0], 16.0).unwrap();
+        assert_eq!(hit.location, (4, 0, 7));
+        assert_eq!(hit.facing, Some(Facing::PosY));
+        assert_eq!(hit.distance, 9.0);
+    }
+
+    #[test]
+    fn rays_from_outside_enter_first() {
+        let chunk = floor();
+        let hit = raycast(&chunk, [-2.0, 0.5, 3.5], [1.0, 0.0, 0.0], 16.0).unwrap();
+        assert_eq!(hit.location, (0, 0, 3));
+        assert_eq!(hit.facing, Some(Facing::NegX));
+    }
+
+    #[test]
+    fn reach_and_water_are_respected() {
+        let mut chunk = floor();
+        let short = raycast(&chunk, [4.5, 10.0, 7.5], [0.0, -1.0, 0.0], 8.0);
+        assert_eq!(short, None);
+
+        chunk[7][1][4] = WATER;
+        let hit = raycast(&chunk, [4.5, 10.0, 7.5], [0.0, -1.0, 0.0], 16.0).unwrap();
+        assert_eq!(hit.location, (4, 0, 7));
+    }
+
+    #[test]
+    fn starting_inside_has_no_facing() {
+        let chunk = floor();
+        let hit = raycast(&chunk, [4.5, 0.5, 7.5], [1.0, 1.0, 0.0], 16.0).unwrap();
+        assert_eq!(hit.facing, None);
+        assert_eq!(hit.distance, 0.0);
+    }
+}
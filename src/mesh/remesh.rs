@@ -0,0 +1,151 @@
+use std::mem;
+
+use super::{
+    face_quad,
+    greedy::{greedy1d, greedy3d},
+    Borders, ChunkBlocks, Facing, Mesh, QuadLayout, QuadRef,
+};
+
+/// Update a merged mesh after the block at `location` changed,
+/// the chunk already holding its new value.
+///
+/// Only the quads around the block are split, regenerated and merged again,
+/// so the result may end up with a few more quads than meshing the whole chunk.
+/// Boundary faces are culled against `borders`, which should be those the mesh
+/// was made with. Faces of the neighbors against the block are left to them.
+pub fn remesh_block(
+    mesh: &mut Mesh,
+    chunk: &impl ChunkBlocks,
+    borders: &Borders,
+    location: (i32, i32, i32),
+) {
+    let layout = QuadLayout::DEFAULT;
+    let location = [location.0, location.1, location.2];
+    let range = 0..32;
+
+    for facing in Facing::ALL {
+        let [u_axis, v_axis, depth_axis] = facing.axes();
+        let (u, v, depth) = (location[u_axis], location[v_axis], location[depth_axis]);
+        let (nx, ny, nz) = facing.normal();
+        let behind = depth - (nx + ny + nz);
+
+        // The face of the block itself, and the faces looking into it
+        // or into its neighbors, whose occlusion may have changed
+        let mut dirty = vec![(u, v, depth)];
+        for dv in -1..=1 {
+            for du in -1..=1 {
+                dirty.push((u + du, v + dv, behind));
+            }
+        }
+
+        let in_chunk = |&(u, v, depth): &(i32, i32, i32)| {
+            range.contains(&u) && range.contains(&v) && range.contains(&depth)
+        };
+        dirty.retain(in_chunk);
+
+        // Split every quad touching a dirty cell back into cells
+        let quads = &mut mesh[facing as usize];
+        let mut cells = dirty.clone();
+        quads.retain(|&qref| {
+            let (x0, y0, z) = layout.location(qref);
+            let (w, h) = layout.extent(qref);
+            let (x1, y1) = (x0 + w as i32, y0 + h as i32);
+
+            let hit = dirty.iter().any(|&(u, v, depth)| {
+                depth == z && (x0..=x1).contains(&u) && (y0..=y1).contains(&v)
+            });
+
+            if hit {
+                for y in y0..=y1 {
+                    for x in x0..=x1 {
+                        cells.push((x, y, z));
+                    }
+                }
+            }
+
+            !hit
+        });
+
+        cells.sort_unstable();
+        cells.dedup();
+
+        let fresh = cells
+            .into_iter()
+            .filter_map(|cell| face_quad(chunk, borders, facing, cell));
+        let mut local = Mesh::default();
+        local[facing as usize].extend(fresh);
+        local.iter_mut().for_each(greedy1d);
+        greedy3d(&mut local);
+
+        // Both lists are sorted already, so merging them is enough
+        let kept = mem::take(quads);
+        let fresh = mem::take(&mut local[facing as usize]);
+        *quads = merge_sorted(layout, kept, fresh);
+    }
+}
+
+// Merge two lists sorted by depth, then y, then x
+fn merge_sorted(layout: QuadLayout, a: Vec<QuadRef>, b: Vec<QuadRef>) -> Vec<QuadRef> {
+    let key = |qref| {
+        let (x, y, z) = layout.location(qref);
+        (z, y, x)
+    };
+
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut a, mut b) = (a.into_iter().peekable(), b.into_iter().peekable());
+
+    loop {
+        let next = match (a.peek(), b.peek()) {
+            (Some(&qa), Some(&qb)) if key(qb) < key(qa) => b.next(),
+            (Some(_), _) => a.next(),
+            (None, _) => b.next(),
+        };
+
+        match next {
+            Some(qref) => merged.push(qref),
+            None => break merged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{greedy::mesh_chunk_with_borders, AIR};
+
+    // Blocks covered by the quads of every facing
+    fn areas(mesh: &Mesh) -> [u32; 6] {
+        let layout = QuadLayout::DEFAULT;
+        mesh.each_ref().map(|quads| {
+            let extents = quads.iter().map(|&quad| layout.extent(quad));
+            extents.map(|(w, h)| (w as u32 + 1) * (h as u32 + 1)).sum()
+        })
+    }
+
+    #[test]
+    fn boundary_blocks_are_culled_against_borders() {
+        // A stone floor, right against a neighbor all stone past x = 31
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for row in chunk.iter_mut().flat_map(|plane| &mut plane[..4]) {
+            row.fill(1);
+        }
+
+        let neighbor = Box::new([[[1; 32]; 32]; 32]);
+        let borders = Borders::from_neighbors([Some(&*neighbor), None, None, None, None, None]);
+        let mut mesh = mesh_chunk_with_borders(&chunk, &borders);
+
+        // A block on the floor against the neighbor, and a hole next to it
+        for ((x, y, z), block) in [((31, 4, 5), 1), ((31, 3, 6), AIR)] {
+            chunk[z as usize][y as usize][x as usize] = block;
+            remesh_block(&mut mesh, &*chunk, &borders, (x, y, z));
+            let meshed = mesh_chunk_with_borders(&chunk, &borders);
+            assert_eq!(areas(&mesh), areas(&meshed));
+        }
+
+        // Nothing on the boundary faces the neighbor
+        let layout = QuadLayout::DEFAULT;
+        let faces = &mesh[Facing::PosX as usize];
+        assert!(!faces.is_empty());
+        assert!(faces.iter().all(|&quad| layout.location(quad).2 < 31));
+    }
+}
@@ -0,0 +1,83 @@
+use std::{fmt::Display, ops::AddAssign};
+
+use super::{Mesh, QuadLayout};
+
+/// How well merging went, across every facing of a mesh.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    /// Quads going into the merging passes.
+    pub input: usize,
+
+    /// Quads coming out of them.
+    pub output: usize,
+
+    /// Output quads by area, bucket `i` counting areas in `2^i..2^(i + 1)` blocks.
+    pub areas: [usize; 11],
+
+    /// Quads the column pass merged away, already left out of the output.
+    /// None unless the mesher ran it, as [`Greedy`](super::greedy::Greedy) may.
+    pub columns: usize,
+}
+
+impl MeshStats {
+    pub fn new(input: &Mesh, output: &Mesh) -> Self {
+        let layout = QuadLayout::DEFAULT;
+        let mut areas = [0; 11];
+
+        for &qref in output.iter().flatten() {
+            let (w, h) = layout.extent(qref);
+            let area = (w as u32 + 1) * (h as u32 + 1);
+            areas[area.ilog2() as usize] += 1;
+        }
+
+        Self {
+            input: input.iter().map(Vec::len).sum(),
+            output: output.iter().map(Vec::len).sum(),
+            areas,
+            columns: 0,
+        }
+    }
+
+    /// Fraction of input quads merged away, from 0 to 1.
+    pub fn reduction(&self) -> f32 {
+        match self.input {
+            0 => 0.0,
+            input => 1.0 - self.output as f32 / input as f32,
+        }
+    }
+}
+
+/// Stats of several meshes together, say all chunks of a model.
+impl AddAssign for MeshStats {
+    fn add_assign(&mut self, other: Self) {
+        self.input += other.input;
+        self.output += other.output;
+        self.columns += other.columns;
+
+        for (bucket, count) in self.areas.iter_mut().zip(other.areas) {
+            *bucket += count;
+        }
+    }
+}
+
+impl Display for MeshStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (input, output) = (self.input, self.output);
+        let reduction = self.reduction() * 100.0;
+        writeln!(f, "{input} -> {output} quads ({reduction:.1}% fewer)")?;
+        if self.columns > 0 {
+            writeln!(f, "  {} of them merged away as columns", self.columns)?;
+        }
+
+        for (bucket, &count) in self.areas.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+
+            let (min, max) = (1 << bucket, (2 << bucket) - 1);
+            writeln!(f, "  area {min:>4}..={max:<4} {count}")?;
+        }
+
+        Ok(())
+    }
+}
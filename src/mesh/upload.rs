@@ -0,0 +1,92 @@
+use std::{collections::HashMap, mem, ops::Range};
+
+use super::{Mesh, QuadRef};
+
+/// A mesh laid out as a single buffer, the way it is written into a buddy block,
+/// along with where each facing starts and ends so that facings can be drawn,
+/// or skipped when looking away from them, on their own.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Packed {
+    pub quads: Vec<QuadRef>,
+
+    /// Instance range of every facing, indexed as in [`super::Facing::ALL`].
+    pub facings: [Range<u32>; 6],
+}
+
+impl Packed {
+    pub fn new(mesh: &Mesh) -> Self {
+        let len = mesh.iter().map(Vec::len).sum();
+        let mut quads = Vec::with_capacity(len);
+        let mut facings: [Range<u32>; 6] = Default::default();
+
+        for (range, list) in facings.iter_mut().zip(mesh) {
+            let start = quads.len() as u32;
+            quads.extend_from_slice(list);
+            *range = start..quads.len() as u32;
+        }
+
+        Self { quads, facings }
+    }
+
+    /// Change the buffer into holding `mesh`, returning the ranges that need to be
+    /// written again over the previous contents, as few quads as it can get away with.
+    ///
+    /// Each facing is drawn as instances, so the order of its quads does not matter:
+    /// those still there keep their slots, new ones take the slots of those gone,
+    /// and facings growing or shrinking only move as many quads as they shift by.
+    /// Unchanged runs shorter than `gap` quads are rewritten along with their neighbors,
+    /// as each write comes with its own overhead.
+    pub fn update(&mut self, mesh: &Mesh, gap: usize) -> Vec<Range<usize>> {
+        let old = mem::take(&mut self.quads);
+        let len = mesh.iter().map(Vec::len).sum();
+        let mut quads = vec![0; len];
+        let mut written = vec![true; len];
+        let mut start = 0;
+
+        for (range, list) in self.facings.iter_mut().zip(mesh) {
+            let target = start..start + list.len();
+
+            let mut wanted = HashMap::<QuadRef, usize>::with_capacity(list.len());
+            for &qref in list {
+                *wanted.entry(qref).or_default() += 1;
+            }
+
+            // Quads of this facing already in the right place stay untouched
+            let (old_start, old_end) = (range.start as usize, range.end as usize);
+            for index in target.start.max(old_start)..target.end.min(old_end) {
+                if let Some(count @ 1..) = wanted.get_mut(&old[index]) {
+                    *count -= 1;
+                    quads[index] = old[index];
+                    written[index] = false;
+                }
+            }
+
+            let mut rest = list.iter().filter(|qref| match wanted.get_mut(qref) {
+                Some(count @ 1..) => {
+                    *count -= 1;
+                    true
+                }
+                _ => false,
+            });
+
+            for index in target.clone().filter(|&index| written[index]) {
+                quads[index] = *rest.next().unwrap();
+            }
+
+            *range = target.start as u32..target.end as u32;
+            start = target.end;
+        }
+
+        self.quads = quads;
+
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for index in (0..len).filter(|&index| written[index]) {
+            match ranges.last_mut() {
+                Some(last) if index - last.end < gap.max(1) => last.end = index + 1,
+                _ => ranges.push(index..index + 1),
+            }
+        }
+
+        ranges
+    }
+}
@@ -0,0 +1,149 @@
+use std::{collections::BTreeMap, io};
+
+use super::{BlockId, Borders, Chunk, Facing, AIR};
+
+/// Models read from a MagicaVoxel `.vox` file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vox {
+    pub models: Vec<Volume>,
+
+    /// RGBA color of every color index, index 0 standing for empty space.
+    /// Empty when the file relies on MagicaVoxel's default palette.
+    pub palette: Vec<[u8; 4]>,
+}
+
+/// A model cut into chunks, y up.
+///
+/// MagicaVoxel models are z up, so a voxel at `(x, y, z)` in the file
+/// lands at `(x, z, size_y - 1 - y)` here, which keeps the model from being mirrored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Volume {
+    /// Extent of the model in blocks.
+    pub size: (u32, u32, u32),
+
+    /// Chunks holding any blocks, by chunk coordinates.
+    pub chunks: BTreeMap<(i32, i32, i32), Box<Chunk>>,
+}
+
+impl Volume {
+    /// Neighbors of the chunk at `location`, indexed as in [`Facing::ALL`].
+    pub fn neighbors(&self, (x, y, z): (i32, i32, i32)) -> [Option<&Chunk>; 6] {
+        Facing::ALL.map(|facing| {
+            let (nx, ny, nz) = facing.normal();
+            let chunk = self.chunks.get(&(x + nx, y + ny, z + nz));
+            chunk.map(|chunk| &**chunk)
+        })
+    }
+
+    /// Borders of the chunk at `location`, to mesh it seamlessly with the rest.
+    pub fn borders(&self, location: (i32, i32, i32)) -> Borders {
+        Borders::from_neighbors(self.neighbors(location))
+    }
+}
+
+/// Read a `.vox` file, turning color indices from 1 to 255 into blocks with `block`.
+///
+/// Only the models themselves are read: the scene graph placing them is ignored,
+/// and so are materials and layers.
+pub fn read(bytes: &[u8], block: impl Fn(u8) -> BlockId) -> io::Result<Vox> {
+    let mut reader = Reader(bytes);
+
+    if reader.bytes()? != *b"VOX " {
+        return Err(invalid("not a .vox file"));
+    }
+
+    let _version = reader.u32()?;
+
+    let (id, _) = reader.chunk()?;
+    if id != *b"MAIN" {
+        return Err(invalid("missing MAIN chunk"));
+    }
+
+    let mut vox = Vox::default();
+    let mut size = None;
+
+    while !reader.0.is_empty() {
+        let (id, mut content) = reader.chunk()?;
+
+        match &id {
+            b"SIZE" => size = Some((content.u32()?, content.u32()?, content.u32()?)),
+            b"XYZI" => {
+                let size = size
+                    .take()
+                    .ok_or_else(|| invalid("XYZI chunk without a SIZE"))?;
+                vox.models.push(read_model(size, content, &block)?);
+            }
+            b"RGBA" => {
+                vox.palette.push([0; 4]);
+                for _ in 1..256 {
+                    vox.palette.push(content.bytes()?);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(vox)
+}
+
+fn read_model(
+    (sx, sy, sz): (u32, u32, u32),
+    mut content: Reader,
+    block: impl Fn(u8) -> BlockId,
+) -> io::Result<Volume> {
+    let mut volume = Volume {
+        size: (sx, sz, sy),
+        chunks: BTreeMap::new(),
+    };
+
+    for _ in 0..content.u32()? {
+        let [x, y, z, index] = content.bytes()?;
+
+        if x as u32 >= sx || y as u32 >= sy || z as u32 >= sz {
+            return Err(invalid("voxel outside of its model"));
+        }
+
+        let (x, y, z) = (x as i32, z as i32, (sy - 1) as i32 - y as i32);
+        let location = (x >> 5, y >> 5, z >> 5);
+        let chunk = volume.chunks.entry(location);
+        let chunk = chunk.or_insert_with(|| Box::new([[[AIR; 32]; 32]; 32]));
+        chunk[z as usize & 31][y as usize & 31][x as usize & 31] = block(index);
+    }
+
+    Ok(volume)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    /// Next chunk id along with its own contents, leaving children to be read on.
+    fn chunk(&mut self) -> io::Result<([u8; 4], Reader<'a>)> {
+        let id = self.bytes()?;
+        let len = self.u32()? as usize;
+        let _children = self.u32()?;
+        let content = Reader(self.take(len)?);
+        Ok((id, content))
+    }
+}
@@ -0,0 +1,124 @@
+use super::{greedy::sort_quads, Borders, Chunk, LightGrid, Mesh, Mesher, QuadLayout, QuadRef};
+
+/// Wrap a mesher so that quads within a slice always share full edges,
+/// splitting merged quads wherever a neighbor's corner would land on their edges.
+///
+/// Such T-junctions leave pixel-sized cracks when rasterization rounds
+/// the shared edge differently for each quad.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Watertight<M>(pub M);
+
+impl<M: Mesher> Mesher for Watertight<M> {
+    fn mesh(&self, chunk: &Chunk) -> Mesh {
+        let mut mesh = self.0.mesh(chunk);
+        split_t_junctions(&mut mesh);
+        mesh
+    }
+
+    fn mesh_with_borders(&self, chunk: &Chunk, borders: &Borders) -> Mesh {
+        let mut mesh = self.0.mesh_with_borders(chunk, borders);
+        split_t_junctions(&mut mesh);
+        mesh
+    }
+
+    fn mesh_lit_with_borders(&self, chunk: &Chunk, borders: &Borders, light: &LightGrid) -> Mesh {
+        let mut mesh = self.0.mesh_lit_with_borders(chunk, borders, light);
+        split_t_junctions(&mut mesh);
+        mesh
+    }
+}
+
+/// Split quads until no corner lies inside the edge of a coplanar quad.
+/// Lists end up sorted by depth, then y, then x.
+pub fn split_t_junctions(mesh: &mut Mesh) {
+    split_t_junctions_in(QuadLayout::DEFAULT, mesh);
+}
+
+/// Like [`split_t_junctions`], for quads packed with any layout.
+pub fn split_t_junctions_in(layout: QuadLayout, mesh: &mut Mesh) {
+    for quads in mesh.iter_mut() {
+        sort_quads(layout, quads);
+        let mut split = Vec::with_capacity(quads.len());
+        let mut start = 0;
+
+        while start < quads.len() {
+            let (.., depth) = layout.location(quads[start]);
+            let len = quads[start..]
+                .iter()
+                .take_while(|&&qref| layout.location(qref).2 == depth)
+                .count();
+
+            let mut slice = quads[start..start + len].to_vec();
+            split_slice(layout, &mut slice);
+            split.append(&mut slice);
+            start += len;
+        }
+
+        sort_quads(layout, &mut split);
+        *quads = split;
+    }
+}
+
+// Cutting a quad may add corners on the far side of its neighbors,
+// so keep going until a whole round leaves the slice untouched
+fn split_slice(layout: QuadLayout, quads: &mut Vec<QuadRef>) {
+    let size = layout.size() as usize + 1;
+
+    loop {
+        // corners[y] has a bit for every x with a quad corner there
+        let mut corners = vec![0u128; size];
+        for &qref in quads.iter() {
+            let [x0, y0, x1, y1] = rect(layout, qref);
+            corners[y0] |= 1 << x0 | 1 << x1;
+            corners[y1] |= 1 << x0 | 1 << x1;
+        }
+
+        let is_corner = |x: usize, y: usize| corners[y] >> x & 1 == 1;
+        let mut split = Vec::with_capacity(quads.len());
+        let mut changed = false;
+
+        for &qref in quads.iter() {
+            let [x0, y0, x1, y1] = rect(layout, qref);
+
+            let mut xs = vec![x0];
+            xs.extend((x0 + 1..x1).filter(|&x| is_corner(x, y0) || is_corner(x, y1)));
+            xs.push(x1);
+
+            let mut ys = vec![y0];
+            ys.extend((y0 + 1..y1).filter(|&y| is_corner(x0, y) || is_corner(x1, y)));
+            ys.push(y1);
+
+            changed |= xs.len() > 2 || ys.len() > 2;
+
+            for y in ys.windows(2) {
+                for x in xs.windows(2) {
+                    split.push(with_rect(layout, qref, [x[0], y[0], x[1], y[1]]));
+                }
+            }
+        }
+
+        *quads = split;
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+// Corners of a quad, from its lower left to its upper right one exclusive
+fn rect(layout: QuadLayout, qref: QuadRef) -> [usize; 4] {
+    let (x, y, _) = layout.location(qref);
+    let (w, h) = layout.extent(qref);
+    let (x, y) = (x as usize, y as usize);
+    [x, y, x + w as usize + 1, y + h as usize + 1]
+}
+
+fn with_rect(layout: QuadLayout, qref: QuadRef, [x0, y0, x1, y1]: [usize; 4]) -> QuadRef {
+    let rect_mask = layout.x_mask() | layout.y_mask() | layout.width_mask() | layout.height_mask();
+
+    qref & !rect_mask
+        | (x0 as u64) << layout.x_shift()
+        | (y0 as u64) << layout.y_shift()
+        | ((x1 - x0 - 1) as u64) << layout.width_shift()
+        | ((y1 - y0 - 1) as u64) << layout.height_shift()
+}
@@ -0,0 +1,268 @@
+use super::Encoding;
+
+/// A packed quad with room for 64³ chunks, laid out by [`WideLayout`]:
+///
+/// | bits   | field                                   |
+/// |--------|-----------------------------------------|
+/// | 0-31   | offset                                  |
+/// | 32-47  | material                                |
+/// | 48-55  | ambient occlusion, 2 bits per corner    |
+/// | 56-73  | location (x, y, z), 6 bits each         |
+/// | 74-77  | sky exposure                            |
+/// | 78-81  | block light                             |
+/// | 82-87  | width, minus one                        |
+/// | 88-93  | height, minus one                       |
+/// | 94-127 | unused                                  |
+pub type QuadRef128 = u128;
+
+/// Bit layout of a [`QuadRef128`], for chunks too large for a [`QuadRef`](super::QuadRef)
+/// to address without giving up most of its offset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WideLayout;
+
+impl WideLayout {
+    const COORD_BITS: u32 = 6;
+
+    const MATERIAL_SHIFT: u32 = 32;
+    const AO_SHIFT: u32 = 48;
+    const X_SHIFT: u32 = 56;
+    const Y_SHIFT: u32 = Self::X_SHIFT + Self::COORD_BITS;
+    const Z_SHIFT: u32 = Self::Y_SHIFT + Self::COORD_BITS;
+    const SKY_EXPOSURE_SHIFT: u32 = 74;
+    const BLOCK_LIGHT_SHIFT: u32 = 78;
+    const WIDTH_SHIFT: u32 = 82;
+    const HEIGHT_SHIFT: u32 = Self::WIDTH_SHIFT + Self::COORD_BITS;
+
+    const FIELD_MASK: u128 = (1 << Self::COORD_BITS) - 1;
+
+    /// Bits that must match for two quads of a row to merge along x.
+    const MERGE_W_MASK: u128 =
+        !(Self::FIELD_MASK << Self::X_SHIFT | Self::FIELD_MASK << Self::WIDTH_SHIFT);
+
+    /// Bits that must match for two stacked quads of a slice to merge along y.
+    const MERGE_H_MASK: u128 =
+        !(Self::FIELD_MASK << Self::Y_SHIFT | Self::FIELD_MASK << Self::HEIGHT_SHIFT);
+
+    /// Number of blocks along each side of the chunk.
+    pub const fn size(&self) -> u32 {
+        1 << Self::COORD_BITS
+    }
+
+    /// Pack a quad without block light. Coordinates and extents wrap to the chunk.
+    pub const fn pack(
+        &self,
+        offset: u32,
+        material: u16,
+        location: (i32, i32, i32),
+        sky_exposure: u8,
+        width: u8,
+        height: u8,
+    ) -> QuadRef128 {
+        let fm = Self::FIELD_MASK;
+
+        offset as u128
+            | (material as u128) << Self::MATERIAL_SHIFT
+            | (location.0 as u128 & fm) << Self::X_SHIFT
+            | (location.1 as u128 & fm) << Self::Y_SHIFT
+            | (location.2 as u128 & fm) << Self::Z_SHIFT
+            | (sky_exposure as u128 & 0xF) << Self::SKY_EXPOSURE_SHIFT
+            | (width as u128 & fm) << Self::WIDTH_SHIFT
+            | (height as u128 & fm) << Self::HEIGHT_SHIFT
+    }
+
+    pub const fn offset(&self, quad_ref: QuadRef128) -> u32 {
+        quad_ref as u32
+    }
+
+    pub const fn material(&self, quad_ref: QuadRef128) -> u16 {
+        (quad_ref >> Self::MATERIAL_SHIFT) as u16
+    }
+
+    pub const fn ao(&self, quad_ref: QuadRef128) -> [u8; 4] {
+        let ao = (quad_ref >> Self::AO_SHIFT) as u8;
+        [ao & 3, ao >> 2 & 3, ao >> 4 & 3, ao >> 6 & 3]
+    }
+
+    /// Replace the ambient occlusion of a quad. Values wrap to 2 bits.
+    pub const fn with_ao(&self, quad_ref: QuadRef128, ao: [u8; 4]) -> QuadRef128 {
+        let [a, b, c, d] = ao;
+        let ao = (a & 3) | (b & 3) << 2 | (c & 3) << 4 | (d & 3) << 6;
+        quad_ref & !(0xFF << Self::AO_SHIFT) | (ao as u128) << Self::AO_SHIFT
+    }
+
+    pub const fn location(&self, quad_ref: QuadRef128) -> (i32, i32, i32) {
+        let x = (quad_ref >> Self::X_SHIFT) & Self::FIELD_MASK;
+        let y = (quad_ref >> Self::Y_SHIFT) & Self::FIELD_MASK;
+        let z = (quad_ref >> Self::Z_SHIFT) & Self::FIELD_MASK;
+        (x as _, y as _, z as _)
+    }
+
+    pub const fn sky_exposure(&self, quad_ref: QuadRef128) -> u8 {
+        (quad_ref >> Self::SKY_EXPOSURE_SHIFT) as u8 & 0xF
+    }
+
+    pub const fn block_light(&self, quad_ref: QuadRef128) -> u8 {
+        (quad_ref >> Self::BLOCK_LIGHT_SHIFT) as u8 & 0xF
+    }
+
+    /// Replace the block light of a quad. Values wrap to 4 bits.
+    pub const fn with_block_light(&self, quad_ref: QuadRef128, block_light: u8) -> QuadRef128 {
+        let block_light = (block_light & 0xF) as u128;
+        quad_ref & !(0xF << Self::BLOCK_LIGHT_SHIFT) | block_light << Self::BLOCK_LIGHT_SHIFT
+    }
+
+    /// Width and height of a quad, minus one.
+    pub const fn extent(&self, quad_ref: QuadRef128) -> (u8, u8) {
+        let width = (quad_ref >> Self::WIDTH_SHIFT) & Self::FIELD_MASK;
+        let height = (quad_ref >> Self::HEIGHT_SHIFT) & Self::FIELD_MASK;
+        (width as _, height as _)
+    }
+
+    pub const fn is_ao_flat_w(&self, quad_ref: QuadRef128) -> bool {
+        let [a, b, c, d] = self.ao(quad_ref);
+        a == b && c == d
+    }
+
+    pub const fn is_ao_flat_h(&self, quad_ref: QuadRef128) -> bool {
+        let [a, b, c, d] = self.ao(quad_ref);
+        a == c && b == d
+    }
+}
+
+impl Encoding for WideLayout {
+    type Ref = QuadRef128;
+
+    fn coord_bits(&self) -> u32 {
+        Self::COORD_BITS
+    }
+
+    fn location_key(&self, quad_ref: QuadRef128) -> u32 {
+        let mask = (1 << (3 * Self::COORD_BITS)) - 1;
+        (quad_ref >> Self::X_SHIFT & mask) as _
+    }
+
+    fn extent(&self, quad_ref: QuadRef128) -> (u8, u8) {
+        WideLayout::extent(self, quad_ref)
+    }
+
+    fn matches_w(&self, a: QuadRef128, b: QuadRef128) -> bool {
+        (a ^ b) & Self::MERGE_W_MASK == 0
+    }
+
+    fn matches_h(&self, a: QuadRef128, b: QuadRef128) -> bool {
+        (a ^ b) & Self::MERGE_H_MASK == 0
+    }
+
+    fn is_ao_flat_w(&self, quad_ref: QuadRef128) -> bool {
+        WideLayout::is_ao_flat_w(self, quad_ref)
+    }
+
+    fn is_ao_flat_h(&self, quad_ref: QuadRef128) -> bool {
+        WideLayout::is_ao_flat_h(self, quad_ref)
+    }
+
+    fn grow_w(&self, quad_ref: QuadRef128, blocks: u32) -> QuadRef128 {
+        quad_ref + ((blocks as u128) << Self::WIDTH_SHIFT)
+    }
+
+    fn grow_h(&self, quad_ref: QuadRef128, blocks: u32) -> QuadRef128 {
+        quad_ref + ((blocks as u128) << Self::HEIGHT_SHIFT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::mesh::greedy::{greedy1d_in, greedy2d_in};
+
+    const SIZE: usize = 64;
+
+    type Volume<T> = Box<[[[T; SIZE]; SIZE]; SIZE]>;
+
+    // Rows of runs of random materials, with holes in between. Every eighth slice is solid
+    fn random_volume(rng: &mut impl Rng) -> Volume<u16> {
+        let mut volume = Box::new([[[0; SIZE]; SIZE]; SIZE]);
+
+        for (z, slice) in volume.iter_mut().enumerate() {
+            for row in slice.iter_mut() {
+                let mut x = 0;
+
+                while x < SIZE {
+                    let len = rng.gen_range(1..=16).min(SIZE - x);
+                    let material = match z % 8 {
+                        0 => 1,
+                        _ => rng.gen_range(0..=3),
+                    };
+
+                    row[x..x + len].fill(material);
+                    x += len;
+                }
+            }
+        }
+
+        volume
+    }
+
+    // Material of every cell, and how many quads cover it
+    fn rasterize(mesh: &[QuadRef128]) -> (Volume<u16>, Volume<u8>) {
+        let layout = WideLayout;
+        let mut volume = Box::new([[[0; SIZE]; SIZE]; SIZE]);
+        let mut coverage = Box::new([[[0; SIZE]; SIZE]; SIZE]);
+
+        for &qref in mesh {
+            let (x0, y0, z) = layout.location(qref);
+            let (w, h) = layout.extent(qref);
+            let (x0, y0, z) = (x0 as usize, y0 as usize, z as usize);
+
+            for y in y0..=y0 + h as usize {
+                for x in x0..=x0 + w as usize {
+                    volume[z][y][x] = layout.material(qref);
+                    coverage[z][y][x] += 1;
+                }
+            }
+        }
+
+        (volume, coverage)
+    }
+
+    #[test]
+    fn merging_preserves_coverage_past_32() {
+        let layout = WideLayout;
+        let mut rng = StdRng::seed_from_u64(0x9E3779B9);
+        let volume = random_volume(&mut rng);
+
+        let (mut cells, mut rows, mut mesh) = (0, 0, Vec::new());
+        for (z, slice) in volume.iter().enumerate() {
+            let mut quads = Vec::new();
+            for (y, row) in slice.iter().enumerate() {
+                for (x, &material) in row.iter().enumerate() {
+                    let location = (x as i32, y as i32, z as i32);
+                    if material != 0 {
+                        quads.push(layout.pack(0, material, location, 0, 0, 0));
+                    }
+                }
+            }
+
+            // A slice at a time, as greedy2d_in expects
+            cells += quads.len();
+            greedy1d_in(layout, &mut quads);
+            rows += quads.len();
+            greedy2d_in(layout, &mut quads);
+            mesh.extend(quads);
+        }
+
+        assert!(mesh.len() < rows && rows < cells);
+
+        let (merged, coverage) = rasterize(&mesh);
+        assert_eq!(merged, volume);
+        let covered = coverage.iter().flatten().flatten();
+        let mut cells = volume.iter().flatten().flatten().zip(covered);
+        assert!(cells.all(|(&cell, &count)| count == (cell != 0) as u8));
+
+        // Solid slices end up a quad each, as wide and tall as the chunk
+        let full = mesh.iter().filter(|&&qref| layout.extent(qref) == (63, 63));
+        assert_eq!(full.count(), SIZE / 8);
+    }
+}
@@ -0,0 +1,25 @@
+// Downsamples a mip level into the next one, filtered by the sampler,
+// drawn as a single triangle covering the whole target
+
+struct Varyings {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var bilinear: sampler;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> Varyings {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+
+    var out: Varyings;
+    out.position = vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    return textureSample(source, bilinear, in.uv);
+}
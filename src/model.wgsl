@@ -0,0 +1,47 @@
+// Entity models, lists of triangles pulled straight out of the model pool,
+// each drawn where its entity is. Vertices carry faces shaded as quads are
+
+struct Draw {
+    view_proj: mat4x4<f32>,
+
+    // Where the model origin goes, w unused
+    position: vec4<f32>,
+}
+
+var<push_constant> draw: Draw;
+
+// Color is sRGB-encoded, alpha unused
+struct Vertex {
+    position: vec3<f32>,
+    color: u32,
+}
+
+@group(0) @binding(0) var<storage, read> vertices: array<Vertex>;
+
+struct Varyings {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> Varyings {
+    let vertex = vertices[index];
+    let world = vertex.position + draw.position.xyz;
+
+    var out: Varyings;
+    out.position = draw.view_proj * vec4(world, 1.0);
+    out.color = linear(unpack4x8unorm(vertex.color).rgb);
+    return out;
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    return vec4(in.color, 1.0);
+}
+
+// Decoded from sRGB, as `srgb_to_linear` on the CPU side
+fn linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, color <= vec3(0.04045));
+}
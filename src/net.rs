@@ -0,0 +1,76 @@
+// Worlds shared over TCP: a server owns the blocks of every chunk, generating them
+// as first asked for, and passes edits along to every client, which mesh and draw
+// whatever they get on their own. Both ends speak `protocol`
+
+mod client;
+pub mod protocol;
+mod server;
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Write},
+    net::{Shutdown, TcpStream},
+    sync::mpsc::Receiver,
+};
+
+use crate::world::RegionError;
+
+pub use self::{client::Client, server::Server};
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+
+    // The other end hung up between messages
+    Closed,
+
+    // Not a message, with what was being read
+    Corrupt(&'static str),
+
+    // Spoken by the server, and not by this client
+    Version(u16),
+
+    // Blocks of a chunk that would not decode
+    Chunk(RegionError),
+}
+
+impl Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "connection failed: {err}"),
+            Self::Closed => write!(f, "connection closed"),
+            Self::Corrupt(what) => write!(f, "corrupt message, bad {what}"),
+            Self::Version(version) => write!(f, "unknown protocol version {version}"),
+            Self::Chunk(err) => write!(f, "cannot read chunk: {err}"),
+        }
+    }
+}
+
+impl Error for NetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Chunk(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// Write frames out as they are queued, until the queue or the stream goes away.
+// Either way, the stream is shut down for whatever reads from it to stop as well
+fn write_frames(mut stream: TcpStream, queue: &Receiver<Vec<u8>>) {
+    for frame in queue {
+        if stream.write_all(&frame).is_err() {
+            break;
+        }
+    }
+
+    let _ = stream.shutdown(Shutdown::Both);
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::BufReader,
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use super::{
+    protocol::{ToClient, ToServer, VERSION},
+    write_frames, NetError,
+};
+
+// Connection to a server, written to and read from on threads of their own
+// so that neither sending nor receiving ever holds a frame up
+#[derive(Debug)]
+pub struct Client {
+    seed: u64,
+    outgoing: Sender<Vec<u8>>,
+    incoming: Receiver<Result<ToClient, NetError>>,
+}
+
+impl Client {
+    // Connect, and wait to be welcomed by a server speaking the same version
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, NetError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let seed = match ToClient::read(&mut reader)? {
+            ToClient::Welcome {
+                version: VERSION,
+                seed,
+            } => seed,
+            ToClient::Welcome { version, .. } => return Err(NetError::Version(version)),
+            _ => return Err(NetError::Corrupt("welcome")),
+        };
+
+        let (outgoing, queue) = mpsc::channel();
+        let (sender, incoming) = mpsc::channel();
+
+        thread::Builder::new()
+            .name("client writer".into())
+            .spawn(move || write_frames(stream, &queue))?;
+
+        thread::Builder::new()
+            .name("client reader".into())
+            .spawn(move || read_messages(reader, &sender))?;
+
+        Ok(Self {
+            seed,
+            outgoing,
+            incoming,
+        })
+    }
+
+    // Seed the server generates chunks out of
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Queue `message` up to be sent. Should sending fail, the connection
+    // gets closed, and the error comes out of `poll`
+    pub fn send(&self, message: &ToServer) {
+        let _ = self.outgoing.send(message.encode());
+    }
+
+    // Messages received since last polled, in order. Once the connection ends,
+    // the error it ended with comes last
+    pub fn poll(&self) -> impl Iterator<Item = Result<ToClient, NetError>> + '_ {
+        self.incoming.try_iter()
+    }
+
+    // The next message, waiting for at most `timeout` for it to come
+    pub fn wait(&self, timeout: Duration) -> Option<Result<ToClient, NetError>> {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(message) => Some(message),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(NetError::Closed)),
+        }
+    }
+}
+
+fn read_messages(mut reader: BufReader<TcpStream>, sender: &Sender<Result<ToClient, NetError>>) {
+    loop {
+        let message = ToClient::read(&mut reader);
+        let ended = message.is_err();
+        if sender.send(message).is_err() || ended {
+            break;
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    io::{ErrorKind, Read},
+    mem,
+};
+
+use super::NetError;
+use crate::{
+    mesh::{BlockId, Chunk},
+    world::{region, ChunkPos},
+};
+
+// Bumped whenever messages change, as clients and servers only talk
+// to those speaking the same version
+pub const VERSION: u16 = 1;
+
+// Frames longer than this are taken as corrupt rather than read in,
+// well past the longest a chunk encodes to
+const MAX_FRAME: u32 = 1 << 20;
+
+// Messages go framed as how long they are, then a tag telling them apart
+// and whatever they carry:
+//
+//     length            u32, of the tag and everything after it
+//     tag               u8
+//     chunk position    i32 each of x, y and z
+//     block location    i32 each of x, y and z, in world coordinates
+//     block             u16
+//     chunk             as `region::encode` lays it out, up to the end
+//
+// All of it little endian
+
+// Sent by clients
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToServer {
+    // Blocks of a chunk, as the server has them
+    Request(ChunkPos),
+
+    // Block set at a location, to be set on the server and every other client
+    Edit([i32; 3], BlockId),
+}
+
+// Sent by servers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ToClient {
+    // First thing sent, with the version spoken and the seed biomes come out of
+    Welcome { version: u16, seed: u64 },
+
+    Chunk(ChunkPos, Box<Chunk>),
+
+    // Block set at a location by any client, the one that set it included,
+    // in the order the server set them
+    Edit([i32; 3], BlockId),
+}
+
+impl ToServer {
+    // Framed, to be written out as is
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let tag = match self {
+            Self::Request(pos) => {
+                put_location(&mut body, *pos);
+                0
+            }
+            Self::Edit(location, block) => {
+                put_location(&mut body, *location);
+                body.extend(block.to_le_bytes());
+                1
+            }
+        };
+
+        frame(tag, &body)
+    }
+
+    pub fn read(input: &mut impl Read) -> Result<Self, NetError> {
+        let (tag, body) = read_frame(input)?;
+        let mut reader = Reader(&body);
+        let message = match tag {
+            0 => Self::Request(reader.location("position")?),
+            1 => Self::Edit(reader.location("location")?, reader.u16("block")?),
+            _ => return Err(NetError::Corrupt("tag")),
+        };
+
+        reader.end(message)
+    }
+}
+
+impl ToClient {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let tag = match self {
+            Self::Welcome { version, seed } => {
+                body.extend(version.to_le_bytes());
+                body.extend(seed.to_le_bytes());
+                0
+            }
+            Self::Chunk(pos, blocks) => {
+                put_location(&mut body, *pos);
+                body.extend(region::encode(blocks));
+                1
+            }
+            Self::Edit(location, block) => {
+                put_location(&mut body, *location);
+                body.extend(block.to_le_bytes());
+                2
+            }
+        };
+
+        frame(tag, &body)
+    }
+
+    pub fn read(input: &mut impl Read) -> Result<Self, NetError> {
+        let (tag, body) = read_frame(input)?;
+        let mut reader = Reader(&body);
+        let message = match tag {
+            0 => Self::Welcome {
+                version: reader.u16("version")?,
+                seed: u64::from_le_bytes(reader.array("seed")?),
+            },
+            1 => {
+                let pos = reader.location("position")?;
+                let blocks = region::decode(reader.rest()).map_err(NetError::Chunk)?;
+                Self::Chunk(pos, blocks)
+            }
+            2 => Self::Edit(reader.location("location")?, reader.u16("block")?),
+            _ => return Err(NetError::Corrupt("tag")),
+        };
+
+        reader.end(message)
+    }
+}
+
+fn put_location(bytes: &mut Vec<u8>, location: [i32; 3]) {
+    bytes.extend(location.iter().flat_map(|c| c.to_le_bytes()));
+}
+
+fn frame(tag: u8, body: &[u8]) -> Vec<u8> {
+    let length = body.len() as u32 + 1;
+    let mut frame = Vec::with_capacity(4 + length as usize);
+    frame.extend(length.to_le_bytes());
+    frame.push(tag);
+    frame.extend_from_slice(body);
+    frame
+}
+
+// Tag and body of the next frame. Hanging up before its first byte
+// is closing the connection, and anywhere after that cutting a frame short
+fn read_frame(input: &mut impl Read) -> Result<(u8, Vec<u8>), NetError> {
+    let mut length = [0; 4];
+    match input.read_exact(&mut length) {
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Err(NetError::Closed),
+        read => read?,
+    }
+
+    let length = u32::from_le_bytes(length);
+    if length == 0 || length > MAX_FRAME {
+        return Err(NetError::Corrupt("length"));
+    }
+
+    let mut frame = vec![0; length as usize];
+    input.read_exact(&mut frame)?;
+    let body = frame.split_off(1);
+    Ok((frame[0], body))
+}
+
+// Little endian values off the front of a frame body
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn array<const N: usize>(&mut self, what: &'static str) -> Result<[u8; N], NetError> {
+        let Some((taken, rest)) = self.0.split_first_chunk() else {
+            return Err(NetError::Corrupt(what));
+        };
+
+        self.0 = rest;
+        Ok(*taken)
+    }
+
+    fn u16(&mut self, what: &'static str) -> Result<u16, NetError> {
+        self.array(what).map(u16::from_le_bytes)
+    }
+
+    fn location(&mut self, what: &'static str) -> Result<[i32; 3], NetError> {
+        let mut location = [0; 3];
+        for c in &mut location {
+            *c = i32::from_le_bytes(self.array(what)?);
+        }
+
+        Ok(location)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        mem::take(&mut self.0)
+    }
+
+    // `message`, as long as nothing is left after it
+    fn end<T>(self, message: T) -> Result<T, NetError> {
+        match self.0.is_empty() {
+            true => Ok(message),
+            false => Err(NetError::Corrupt("length")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::AIR;
+
+    #[test]
+    fn messages_read_back_as_written() {
+        let mut blocks: Box<Chunk> = Box::new([[[AIR; 32]; 32]; 32]);
+        blocks[3][2][1] = 7;
+
+        let to_server = [
+            ToServer::Request([-1, 0, 5]),
+            ToServer::Edit([-33, 4, 70], 3),
+        ];
+
+        let to_client = [
+            ToClient::Welcome {
+                version: VERSION,
+                seed: u64::MAX - 1,
+            },
+            ToClient::Chunk([2, -1, 0], blocks),
+            ToClient::Edit([1, 2, 3], AIR),
+        ];
+
+        // Back to back, as they go down a stream
+        let stream: Vec<u8> = to_server.iter().flat_map(ToServer::encode).collect();
+        let mut input = &stream[..];
+        for message in &to_server {
+            assert_eq!(&ToServer::read(&mut input).unwrap(), message);
+        }
+
+        assert!(matches!(ToServer::read(&mut input), Err(NetError::Closed)));
+
+        let stream: Vec<u8> = to_client.iter().flat_map(ToClient::encode).collect();
+        let mut input = &stream[..];
+        for message in &to_client {
+            assert_eq!(&ToClient::read(&mut input).unwrap(), message);
+        }
+
+        // Cut short partway through
+        let edit = ToClient::Edit([1, 2, 3], AIR).encode();
+        let mut input = &edit[..edit.len() - 1];
+        assert!(matches!(ToClient::read(&mut input), Err(NetError::Io(_))));
+    }
+}
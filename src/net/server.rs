@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufReader},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use super::{
+    protocol::{ToClient, ToServer, VERSION},
+    write_frames, NetError,
+};
+use crate::{
+    mesh::{BlockId, Chunk},
+    world::{split, ChunkPos, Generator},
+};
+
+// Owns the blocks of every chunk any client asked for, generated out of the seed
+// the first time and kept as edited from then on, with no window or GPU at all.
+// Every client gets a thread reading from it and another writing to it
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    world: Arc<World>,
+}
+
+struct World {
+    seed: u64,
+    generator: Arc<Generator>,
+    chunks: Mutex<HashMap<ChunkPos, Box<Chunk>>>,
+
+    // Frames to be written to every client connected, by the order they connected in
+    clients: Mutex<HashMap<usize, Sender<Vec<u8>>>>,
+}
+
+impl Server {
+    // Listening on `addr`, with chunks generated out of `seed` as `generator` does
+    pub fn bind(
+        addr: impl ToSocketAddrs,
+        seed: u64,
+        generator: Arc<Generator>,
+    ) -> io::Result<Self> {
+        let world = World {
+            seed,
+            generator,
+            chunks: Mutex::default(),
+            clients: Mutex::default(),
+        };
+
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            world: Arc::new(world),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Take clients in as they connect, for as long as listening works
+    pub fn run(&self) -> io::Result<()> {
+        for (id, stream) in self.listener.incoming().enumerate() {
+            // Connections given up on before being taken in leave the rest alone
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) if err.kind() == io::ErrorKind::ConnectionAborted => continue,
+                Err(err) => return Err(err),
+            };
+
+            let world = self.world.clone();
+            thread::Builder::new()
+                .name(format!("client {id}"))
+                .spawn(move || match serve(&world, id, stream) {
+                    Ok(()) => tracing::info!("client {id} left"),
+                    Err(err) => tracing::warn!("client {id}: {err}"),
+                })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for World {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("World").field("seed", &self.seed).finish()
+    }
+}
+
+impl World {
+    // Run `f` on the blocks of chunk `pos`, generated first if not there yet.
+    // Generating is done without holding on to every other chunk
+    fn with_chunk<R>(&self, pos: ChunkPos, f: impl FnOnce(&mut Chunk) -> R) -> R {
+        let missing = !self.chunks.lock().unwrap().contains_key(&pos);
+        let generated = missing.then(|| (self.generator)(pos).0);
+
+        let mut chunks = self.chunks.lock().unwrap();
+        let generate = || generated.unwrap_or_else(|| (self.generator)(pos).0);
+        f(chunks.entry(pos).or_insert_with(generate))
+    }
+
+    // Set the block at `location`, in world coordinates, and pass the edit along to
+    // every client. Chunks are sent and edits passed along while holding on to the chunk,
+    // so that no client gets a chunk from before an edit after the edit itself
+    fn edit(&self, location: [i32; 3], block: BlockId) {
+        let (pos, [x, y, z]) = split(location);
+        let frame = ToClient::Edit(location, block).encode();
+
+        self.with_chunk(pos, |blocks| {
+            blocks[z][y][x] = block;
+            for sender in self.clients.lock().unwrap().values() {
+                let _ = sender.send(frame.clone());
+            }
+        });
+    }
+}
+
+// Welcome a client and answer whatever it sends, until it hangs up
+fn serve(world: &World, id: usize, stream: TcpStream) -> Result<(), NetError> {
+    stream.set_nodelay(true)?;
+    let writer = stream.try_clone()?;
+    let (sender, queue) = mpsc::channel();
+
+    thread::Builder::new()
+        .name(format!("client {id} writer"))
+        .spawn(move || write_frames(writer, &queue))?;
+
+    let welcome = ToClient::Welcome {
+        version: VERSION,
+        seed: world.seed,
+    };
+
+    let _ = sender.send(welcome.encode());
+    world.clients.lock().unwrap().insert(id, sender.clone());
+
+    let mut reader = BufReader::new(stream);
+    let result = loop {
+        match ToServer::read(&mut reader) {
+            Ok(ToServer::Request(pos)) => world.with_chunk(pos, |blocks| {
+                let chunk = ToClient::Chunk(pos, Box::new(*blocks));
+                let _ = sender.send(chunk.encode());
+            }),
+            Ok(ToServer::Edit(location, block)) => world.edit(location, block),
+            Err(NetError::Closed) => break Ok(()),
+            Err(err) => break Err(err),
+        }
+    };
+
+    // Dropping the last sender lets the writer go as well
+    world.clients.lock().unwrap().remove(&id);
+    result
+}
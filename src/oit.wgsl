@@ -0,0 +1,28 @@
+// Translucent quads as accumulated, blended over the scene behind them,
+// drawn as a single triangle covering the whole frame
+
+@group(0) @binding(0) var accum: texture_2d<f32>;
+@group(0) @binding(1) var revealage: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    return vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
+
+// Average color of the quads over the pixel, with alpha covering
+// as much as they hide of what lies behind
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let xy = vec2<u32>(position.xy);
+    let revealed = textureLoad(revealage, xy, 0).r;
+
+    // Nothing translucent over this pixel
+    if revealed >= 1.0 {
+        discard;
+    }
+
+    let sum = textureLoad(accum, xy, 0);
+    let average = sum.rgb / max(sum.a, 1e-5);
+    return vec4(average, 1.0 - revealed);
+}
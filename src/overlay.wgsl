@@ -0,0 +1,106 @@
+// Lines and marks drawn over the scene: the outline of the selected block,
+// hidden wherever the scene is in front, boxes around chunks for debugging,
+// dimmed wherever it is, and the crosshair at the center of the frame.
+// `load_depth` and the depth binding are pasted in front, see `depth_module`
+
+struct Overlay {
+    view_proj: mat4x4<f32>,
+
+    // Lowest corner of the selected block, w unused
+    block: vec4<f32>,
+
+    // In pixels
+    frame: vec2<f32>,
+}
+
+var<push_constant> overlay: Overlay;
+
+// Corners of a box and its color, RGBA with 8 bits each and red the lowest
+struct Outline {
+    min: vec3<f32>,
+    color: u32,
+    max: vec3<f32>,
+}
+
+@group(1) @binding(0) var<storage, read> boxes: array<Outline>;
+
+// Pushed out of the block a bit, so edges don't fight with its faces
+const GROW = 0.002;
+
+// Crosshair arms, in pixels from the center, and how thick they are
+const ARM = 10.0;
+const THICKNESS = 2.0;
+
+// Corner of a unit cube at either end of its 12 edges, 4 along each axis,
+// one at every corner of the other two
+fn cube_edge(vertex: u32) -> vec3<f32> {
+    let edge = vertex / 2u;
+    let axis = edge / 4u;
+    var unit = vec3(0.0);
+    unit[axis] = f32(vertex & 1u);
+    unit[(axis + 1u) % 3u] = f32(edge & 1u);
+    unit[(axis + 2u) % 3u] = f32((edge >> 1u) & 1u);
+    return unit;
+}
+
+@vertex
+fn vs_outline(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    let position = overlay.block.xyz - GROW + cube_edge(vertex) * (1.0 + 2.0 * GROW);
+    return overlay.view_proj * vec4(position, 1.0);
+}
+
+@fragment
+fn fs_outline(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    // Behind what got drawn there
+    if position.z > load_depth(vec2<i32>(position.xy)) {
+        discard;
+    }
+
+    return vec4(0.0, 0.0, 0.0, 0.6);
+}
+
+struct BoxOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) color: vec4<f32>,
+}
+
+@vertex
+fn vs_boxes(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+) -> BoxOutput {
+    let outline = boxes[instance];
+    let position = mix(outline.min, outline.max, cube_edge(vertex));
+
+    var out: BoxOutput;
+    out.position = overlay.view_proj * vec4(position, 1.0);
+    out.color = unpack4x8unorm(outline.color);
+    return out;
+}
+
+@fragment
+fn fs_boxes(in: BoxOutput) -> @location(0) vec4<f32> {
+    // Behind what got drawn there, but still there to be seen
+    let hidden = in.position.z > load_depth(vec2<i32>(in.position.xy));
+    return in.color * vec4(1.0, 1.0, 1.0, select(1.0, 0.3, hidden));
+}
+
+@vertex
+fn vs_crosshair(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    // Two bars of two triangles each, across then up
+    let index = vertex % 6u;
+    let right = index == 1u || index == 4u || index == 5u;
+    let top = index == 2u || index == 3u || index == 5u;
+    let corner = vec2(select(-1.0, 1.0, right), select(-1.0, 1.0, top));
+
+    let bar = select(vec2(THICKNESS / 2.0, ARM), vec2(ARM, THICKNESS / 2.0), vertex < 6u);
+    let pixel = floor(overlay.frame / 2.0) + corner * bar;
+    let ndc = pixel / overlay.frame * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+    return vec4(ndc, 0.0, 1.0);
+}
+
+@fragment
+fn fs_crosshair() -> @location(0) vec4<f32> {
+    // Inverting what lies below, see `INVERT`
+    return vec4(1.0);
+}
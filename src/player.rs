@@ -0,0 +1,210 @@
+// Walking around the world rather than flying over it: a box about as wide and tall
+// as a person, standing in for its body, pulled down by gravity and kept out of blocks
+
+use crate::{camera::Movement, math::Aabb, world::ChunkMap};
+
+// Across and up, in blocks
+const WIDTH: f32 = 0.6;
+const HEIGHT: f32 = 1.8;
+
+// How high up the eyes are, above the feet
+const EYE_HEIGHT: f32 = 1.62;
+
+// In blocks per second, and how many times faster to go while sprinting
+const WALK_SPEED: f32 = 4.5;
+const SPRINT: f32 = 1.5;
+
+// Blocks per second squared, and the fastest anything falls
+const GRAVITY: f32 = 32.0;
+const TERMINAL_VELOCITY: f32 = 60.0;
+
+// Enough to jump up onto a block, with a bit to spare
+const JUMP_SPEED: f32 = 9.0;
+
+// Share of the way to walking speed made up every second, standing on the ground
+// or in the air, where there is little to push against
+const GROUND_GRIP: f32 = 16.0;
+const AIR_GRIP: f32 = 3.0;
+
+// Ledges up to this high are walked up onto, without jumping
+const STEP_HEIGHT: f32 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Player {
+    // Center of the bottom of its box, in world coordinates
+    pub feet: [f32; 3],
+
+    // In blocks per second
+    pub velocity: [f32; 3],
+
+    grounded: bool,
+}
+
+impl Player {
+    // Standing still, eyes at `eye`
+    pub fn new(eye: [f32; 3]) -> Self {
+        let [x, y, z] = eye;
+        Self {
+            feet: [x, y - EYE_HEIGHT, z],
+            velocity: [0.0; 3],
+            grounded: false,
+        }
+    }
+
+    pub fn eye(&self) -> [f32; 3] {
+        let [x, y, z] = self.feet;
+        [x, y + EYE_HEIGHT, z]
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::standing(self.feet, WIDTH, HEIGHT)
+    }
+
+    // Whether standing on something, as of the last step
+    pub const fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    // Walk for `dt` seconds as `movement` says, along the ground towards `yaw`
+    // as the camera has it, jumping while rising. Walls stop it, and so do ledges
+    // unless low enough to step onto
+    pub fn step(&mut self, world: &ChunkMap, movement: Movement, yaw: f32, dt: f32) {
+        let (sin_yaw, cos_yaw) = yaw.sin_cos();
+        let (advance, strafe) = (movement.advance, movement.strafe);
+        let wish = [
+            cos_yaw * advance - sin_yaw * strafe,
+            sin_yaw * advance + cos_yaw * strafe,
+        ];
+
+        let length = wish[0].hypot(wish[1]).max(1.0);
+        let sprint = if movement.sprint { SPRINT } else { 1.0 };
+        let speed = WALK_SPEED * sprint / length;
+
+        let grip = if self.grounded { GROUND_GRIP } else { AIR_GRIP };
+        let blend = (grip * dt).min(1.0);
+
+        for (axis, wish) in [(0, wish[0]), (2, wish[1])] {
+            self.velocity[axis] += (wish * speed - self.velocity[axis]) * blend;
+        }
+
+        if movement.rise > 0.0 && self.grounded {
+            self.velocity[1] = JUMP_SPEED;
+        }
+
+        let falling = self.velocity[1] - GRAVITY * dt;
+        self.velocity[1] = falling.max(-TERMINAL_VELOCITY);
+
+        let start = self.aabb();
+        let motion = self.velocity.map(|c| c * dt);
+        let mut sweep = world.sweep(start, motion);
+
+        // Lifted, moved along and put down again, kept if it lands farther along
+        let walled = sweep.blocked[0] || sweep.blocked[2];
+        if walled && self.grounded {
+            let lifted = world.sweep(start, [0.0, STEP_HEIGHT, 0.0]);
+            let across = world.sweep(lifted.aabb, [motion[0], 0.0, motion[2]]);
+            let lift = lifted.aabb.min[1] - start.min[1];
+            let landed = world.sweep(across.aabb, [0.0, motion[1] - lift, 0.0]);
+
+            let [x, _, z] = start.min;
+            let along = |aabb: &Aabb| (aabb.min[0] - x).hypot(aabb.min[2] - z);
+            if landed.blocked[1] && along(&landed.aabb) > along(&sweep.aabb) {
+                let [blocked_x, _, blocked_z] = across.blocked;
+                sweep.aabb = landed.aabb;
+                sweep.blocked = [blocked_x, true, blocked_z];
+            }
+        }
+
+        self.grounded = sweep.blocked[1] && self.velocity[1] < 0.0;
+        for (velocity, blocked) in self.velocity.iter_mut().zip(sweep.blocked) {
+            if blocked {
+                *velocity = 0.0;
+            }
+        }
+
+        let Aabb { min, max } = sweep.aabb;
+        self.feet = [(min[0] + max[0]) / 2.0, min[1], (min[2] + max[2]) / 2.0];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+    use crate::mesh::AIR;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    // Ground up to y = 4, a block high ledge past x = 10 and a wall past z = 12
+    fn map() -> ChunkMap {
+        let mut blocks = Box::new([[[AIR; 32]; 32]; 32]);
+        for (z, plane) in blocks.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (x, block) in row.iter_mut().enumerate() {
+                    *block = match (x, y, z) {
+                        (_, 0..=3, _) | (10.., 4, _) | (_, 4..=6, 12..) => 1,
+                        _ => AIR,
+                    };
+                }
+            }
+        }
+
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], blocks);
+        map
+    }
+
+    #[test]
+    fn walks_up_ledges_but_not_walls() {
+        let map = map();
+        let mut player = Player::new([4.5, 8.0, 4.5]);
+        let ahead = Movement {
+            advance: 1.0,
+            ..Movement::default()
+        };
+
+        for _ in 0..60 {
+            player.step(&map, Movement::default(), 0.0, DT);
+        }
+
+        assert_eq!(player.feet[1], 4.0);
+        assert!(player.is_grounded());
+
+        for _ in 0..120 {
+            player.step(&map, ahead, 0.0, DT);
+        }
+
+        assert_eq!(player.feet[1], 5.0);
+        assert!(player.feet[0] > 11.0);
+
+        for _ in 0..120 {
+            player.step(&map, ahead, FRAC_PI_2, DT);
+        }
+
+        assert_eq!(player.aabb().max[2], 12.0);
+        assert_eq!(player.feet[1], 5.0);
+    }
+
+    #[test]
+    fn jumps_onto_a_block_and_lands() {
+        let map = map();
+        let mut player = Player::new([4.5, 4.0 + EYE_HEIGHT, 4.5]);
+        player.step(&map, Movement::default(), 0.0, DT);
+
+        let jump = Movement {
+            rise: 1.0,
+            ..Movement::default()
+        };
+
+        player.step(&map, jump, 0.0, DT);
+        let mut highest = player.feet[1];
+        for _ in 0..60 {
+            player.step(&map, Movement::default(), 0.0, DT);
+            highest = highest.max(player.feet[1]);
+        }
+
+        assert!(highest > 5.0 && highest < 5.5, "jumped up to {highest}");
+        assert_eq!(player.feet[1], 4.0);
+    }
+}
@@ -0,0 +1,493 @@
+// Quads are pulled straight out of the buddy buffer, one instance each,
+// and expanded into a strip of 4 vertices. The layout is QuadLayout::CHUNK_32.
+// Only `view_proj` is read when drawing indirectly
+
+struct Draw {
+    view_proj: mat4x4<f32>,
+
+    // Chunk origin in blocks, then the row of `palette` it is tinted by
+    origin: vec4<f32>,
+
+    // u, v and depth axes of the facing drawn, then flags:
+    // bit 0 when it faces along its depth axis, bit 1 when u goes backwards
+    axes: vec4<u32>,
+}
+
+var<push_constant> draw: Draw;
+
+// QuadRefs split into their low and high halves, WGSL having no 64-bit integers
+@group(0) @binding(0) var<storage, read> quads: array<vec2<u32>>;
+
+// Drawn indirectly, a level of detail of a chunk per draw, sorted by `first`.
+// Unused chunks start past every quad
+struct Chunk {
+    origin: vec3<f32>,
+
+    // Offset of its first quad into the buddy buffer
+    first: u32,
+
+    // End of every facing, counting from `first`
+    ends: array<u32, 6>,
+
+    // Row of `palette` it is tinted by
+    palette: u32,
+
+    // Level of detail in the low byte, its quads spanning blocks 2^level times as large,
+    // and how many levels the chunk has in the next one
+    lod: u32,
+}
+
+@group(0) @binding(1) var<storage, read> chunks: array<Chunk>;
+
+// Axes and flags of every facing, as in `Draw`
+@group(0) @binding(2) var<uniform> facings: array<vec4<u32>, 6>;
+
+// Shared by every draw of a frame
+struct Scene {
+    // w unused
+    eye: vec4<f32>,
+
+    // See `Fog`
+    fog_color: vec3<f32>,
+    fog_density: f32,
+    fog_height: f32,
+    fog_falloff: f32,
+
+    // Through the center of the view, and towards the sun, w unused
+    forward: vec4<f32>,
+    sun: vec4<f32>,
+
+    // Distance along `forward` where each cascade ends, then what each sees
+    splits: vec4<f32>,
+    cascades: array<mat4x4<f32>, 3>,
+
+    // One of the `VIEW_*` constants
+    view: u32,
+
+    // Seconds since water started rippling
+    time: f32,
+}
+
+// What quads are drawn as, matching `DebugView` on the CPU side
+const VIEW_SHADED: u32 = 0u;
+const VIEW_WIREFRAME: u32 = 1u;
+const VIEW_QUADS: u32 = 2u;
+const VIEW_CHUNKS: u32 = 3u;
+const VIEW_OVERDRAW: u32 = 4u;
+
+// Added up by every quad drawn over a pixel, tonemapping turning it from red to white
+const OVERDRAW = vec3(0.12, 0.04, 0.015);
+
+@group(1) @binding(0) var<uniform> scene: Scene;
+
+// A layer per cascade, holding depth seen from the sun
+@group(1) @binding(1) var shadow_maps: texture_depth_2d_array;
+@group(1) @binding(2) var shadow_sampler: sampler_comparison;
+
+// Rows of `MATERIALS` entries, one per biome, tinting every material as
+// `palette::entry` packs them: texture layer in the low byte, sRGB tint above
+@group(1) @binding(3) var<storage, read> palette: array<u32>;
+
+const MATERIALS: u32 = 256u;
+
+// Layers of block textures, picked by palette entries, and what samples them
+@group(1) @binding(4) var block_textures: texture_2d_array<f32>;
+@group(1) @binding(5) var block_sampler: sampler;
+
+// How the texture starting at every layer is animated, as `Animation` on the CPU side.
+// Frames take the layers right after the first, one after the other
+struct Animation {
+    frames: u32,
+    rate: f32,
+    padding: vec2<u32>,
+}
+
+@group(1) @binding(6) var<uniform> animations: array<Animation, MATERIALS>;
+
+// Layer of materials with no texture, showing their made-up color instead
+const BLANK: u32 = 0u;
+
+// How much light is left in the shade, coming from the sky
+const SHADOWED = 0.55;
+
+// World units surfaces are pushed out along their normal before looking them up
+const NORMAL_OFFSET = 0.08;
+
+// Color of block light at its brightest, warm as a flame
+const BLOCK_LIGHT = vec3(1.0, 0.78, 0.5);
+
+// Blocks per second water surfaces drift by along x and z
+const WATER_DRIFT = vec2(0.35, 0.2);
+
+// Waves rippling water, as a direction, then radians per block and per second
+const WAVE_LONG = vec4(0.8, 0.6, 1.7, 1.9);
+const WAVE_CROSS = vec4(-0.6, 0.8, 2.9, 2.6);
+const WAVE_SHORT = vec4(0.1, -1.0, 4.3, 3.4);
+
+// How high waves are for every radian per block, keeping ripples gentle
+const WAVE_HEIGHT = 0.012;
+
+// Position is invariant, so the prepass leaves depth exactly as shading sees it
+struct Varyings {
+    @builtin(position) @invariant position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) world: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+
+    // To tell quads and chunks apart when debugging
+    @location(3) @interpolate(flat) quad: u32,
+    @location(4) @interpolate(flat) origin: vec3<f32>,
+
+    // Below 1 for translucent materials
+    @location(5) @interpolate(flat) alpha: f32,
+
+    // Light from lamps and such reaching the face, from 0 to 1
+    @location(6) @interpolate(flat) block_light: f32,
+
+    // Entry of `palette` tinting the quad
+    @location(7) @interpolate(flat) tint: u32,
+
+    // Occlusion and shading of the face, for texels to be darkened as `color` is
+    @location(8) light: f32,
+}
+
+// Both targets translucent quads add up into, see oit.wgsl
+struct Translucent {
+    @location(0) accum: vec4<f32>,
+    @location(1) revealage: f32,
+}
+
+// Bits `shift..shift + count` of a quad, which may straddle both halves
+fn field(quad: vec2<u32>, shift: u32, count: u32) -> u32 {
+    let mask = (1u << count) - 1u;
+
+    if shift >= 32u {
+        return (quad.y >> (shift - 32u)) & mask;
+    }
+
+    if shift + count <= 32u {
+        return (quad.x >> shift) & mask;
+    }
+
+    return ((quad.x >> shift) | (quad.y << (32u - shift))) & mask;
+}
+
+// Made-up colors until there are textures, stable per material.
+// Picked as they look, so sRGB-encoded like texels would be
+fn material_color(material: u32) -> vec3<f32> {
+    switch material {
+        case 1u: { return linear(vec3(0.35, 0.35, 0.37)); }
+        case 2u: { return linear(vec3(0.20, 0.45, 0.10)); }
+        case 3u: { return linear(vec3(0.35, 0.22, 0.12)); }
+        case 4u: { return linear(vec3(0.85, 0.78, 0.55)); }
+        case 5u: { return linear(vec3(0.92, 0.94, 0.97)); }
+        case 6u: { return linear(vec3(0.30, 0.20, 0.11)); }
+        case 7u: { return linear(vec3(0.55, 0.55, 0.57)); }
+        case 8u: { return linear(vec3(0.10, 0.30, 0.55)); }
+        case 9u: { return linear(vec3(0.75, 0.85, 0.90)); }
+        case 10u: { return linear(vec3(1.00, 0.85, 0.55)); }
+        case 11u: { return linear(vec3(0.16, 0.38, 0.12)); }
+        default: { return hashed_color(material); }
+    }
+}
+
+// Ends of a log show its rings rather than its bark, their grain
+// running along the normal as `face_state` packs it
+fn face_color(material: u32, grain: u32) -> vec3<f32> {
+    if material == 6u && grain == 2u {
+        return linear(vec3(0.62, 0.48, 0.30));
+    }
+
+    return material_color(material);
+}
+
+// How much light a material stops, matching `is_translucent` on the CPU side
+fn material_alpha(material: u32) -> f32 {
+    switch material {
+        case 8u: { return 0.6; }
+        case 9u: { return 0.25; }
+        default: { return 1.0; }
+    }
+}
+
+// Tint of a palette entry, decoded from sRGB
+fn tint(index: u32) -> vec3<f32> {
+    let entry = palette[index];
+    let rgb = vec3(entry >> 8u, entry >> 16u, entry >> 24u) & vec3(0xFFu);
+    return linear(vec3<f32>(rgb) / 255.0);
+}
+
+// A color for every `key`, close keys getting unrelated colors
+fn hashed_color(key: u32) -> vec3<f32> {
+    let hash = key * 2654435761u;
+    let rgb = vec3((hash >> 8u) & 0xFFu, (hash >> 16u) & 0xFFu, hash >> 24u);
+    return linear(vec3<f32>(rgb) / 255.0);
+}
+
+// Decoded from sRGB, as `srgb_to_linear` on the CPU side
+fn linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, color <= vec3(0.04045));
+}
+
+// Corner `vertex` of the quad at `index`, facing along `axes` and placed relative to `origin`
+// in blocks `size` times as large, tinted by row `row` of the palette
+fn expand(
+    index: u32,
+    vertex: u32,
+    origin: vec3<f32>,
+    axes: vec4<u32>,
+    row: u32,
+    size: f32,
+) -> Varyings {
+    let quad = quads[index];
+    let local = vec3(field(quad, 31u, 5u), field(quad, 36u, 5u), field(quad, 41u, 5u));
+    let extent = vec2(field(quad, 54u, 5u), field(quad, 59u, 5u)) + 1u;
+    let flags = axes.w;
+
+    // Strip order 0, 1, 3, 2 of a counter-clockwise quad, mirrored along u
+    // for facings where u × v points against the normal
+    var corner = vec2(vertex & 1u, vertex >> 1u);
+    if (flags & 2u) != 0u {
+        corner.x = 1u - corner.x;
+    }
+
+    // Halved quads span half their block along v, inset ones sit halfway through it
+    let state = field(quad, 11u, 4u);
+    var v = f32(local.y + corner.y * extent.y);
+    var depth = f32(local.z + (flags & 1u));
+    switch state >> 2u {
+        case 1u: { depth = f32(local.z) + 0.5; }
+        case 2u: { v = f32(local.y) + 0.5 * f32(corner.y); }
+        case 3u: { v = f32(local.y) + 0.5 + 0.5 * f32(corner.y); }
+        default: {}
+    }
+
+    var position = origin;
+    position[axes.x] += size * f32(local.x + corner.x * extent.x);
+    position[axes.y] += size * v;
+    position[axes.z] += size * depth;
+
+    // Sky exposure is left out until meshing fills it in
+    let ao = field(quad, 23u + 2u * (corner.x | (corner.y << 1u)), 2u);
+    let occlusion = 1.0 - 0.2 * f32(ao);
+
+    // Sides a bit darker than tops, bottoms darker still
+    var shade = 0.8;
+    if axes.z == 1u {
+        shade = select(0.5, 1.0, (flags & 1u) != 0u);
+    }
+
+    let material = field(quad, 15u, 8u);
+
+    var out: Varyings;
+    out.position = draw.view_proj * vec4(position, 1.0);
+    out.color = face_color(material, state & 3u) * occlusion * shade;
+    out.light = occlusion * shade;
+    out.alpha = material_alpha(material);
+    out.block_light = f32(field(quad, 50u, 4u)) / 15.0;
+    out.tint = row * MATERIALS + material;
+    out.world = position;
+    out.normal = vec3(0.0);
+    out.normal[axes.z] = select(-1.0, 1.0, (flags & 1u) != 0u);
+    out.quad = index;
+    out.origin = origin;
+    return out;
+}
+
+// Instances count from the block bound, a draw per facing
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+) -> Varyings {
+    return expand(instance, vertex, draw.origin.xyz, draw.axes, u32(draw.origin.w), 1.0);
+}
+
+// Instances count from the start of the buffer, a draw per chunk
+@vertex
+fn vs_indirect(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+) -> Varyings {
+    // Last chunk starting at or before the quad
+    var low = 0u;
+    var high = arrayLength(&chunks);
+    while high - low > 1u {
+        let middle = (low + high) / 2u;
+        if chunks[middle].first <= instance {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    let index = instance - chunks[low].first;
+    var facing = 0u;
+    while facing < 5u && index >= chunks[low].ends[facing] {
+        facing += 1u;
+    }
+
+    let chunk = chunks[low];
+    let size = f32(1u << (chunk.lod & 0xFFu));
+    return expand(instance, vertex, chunk.origin, facings[facing], chunk.palette, size);
+}
+
+// Share of the light from `world` lost to fog on its way to the eye. Fog thins out
+// exponentially going up, so its density is integrated along the way
+fn fog(world: vec3<f32>) -> f32 {
+    let ray = world - scene.eye.xyz;
+    let above = scene.eye.y - scene.fog_height;
+    let density = scene.fog_density * exp(-above * scene.fog_falloff);
+    var thickness = density * length(ray);
+
+    // Looking almost level, density barely changes along the way
+    let rise = ray.y * scene.fog_falloff;
+    if abs(rise) > 1e-4 {
+        thickness *= (1.0 - exp(-rise)) / rise;
+    }
+
+    return 1.0 - exp(-thickness);
+}
+
+// Share of the sunlight reaching `world`, 1 past the last cascade. Lookups
+// are filtered over 3×3 texels, on top of what the sampler already does
+fn sunlight(world: vec3<f32>, normal: vec3<f32>) -> f32 {
+    let sun = normalize(scene.sun.xyz);
+    if sun.y <= 0.0 || dot(normal, sun) <= 0.0 {
+        return 0.0;
+    }
+
+    let depth = dot(world - scene.eye.xyz, scene.forward.xyz);
+    var cascade = 0u;
+    while cascade < 3u && depth > scene.splits[cascade] {
+        cascade += 1u;
+    }
+
+    if cascade == 3u {
+        return 1.0;
+    }
+
+    let offset = world + normal * NORMAL_OFFSET;
+    let light = scene.cascades[cascade] * vec4(offset, 1.0);
+    let uv = light.xy * vec2(0.5, -0.5) + 0.5;
+    let texel = 1.0 / vec2<f32>(textureDimensions(shadow_maps));
+
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let tap = uv + vec2(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(shadow_maps, shadow_sampler, tap, cascade, light.z);
+        }
+    }
+
+    return lit / 9.0;
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    switch scene.view {
+        case VIEW_QUADS: { return vec4(hashed_color(in.quad), 1.0); }
+        case VIEW_CHUNKS: {
+            let origin = vec3<i32>(floor(in.origin));
+            let key = u32(origin.x) ^ (u32(origin.y) * 73856093u) ^ (u32(origin.z) * 19349663u);
+            return vec4(hashed_color(key), 1.0);
+        }
+        case VIEW_OVERDRAW: { return vec4(OVERDRAW, 1.0); }
+        default: {}
+    }
+
+    return vec4(shade(in), 1.0);
+}
+
+@fragment
+fn fs_translucent(in: Varyings) -> Translucent {
+    return accumulate(in.world, shade(in), in.alpha);
+}
+
+// Ripples drift across the surface as a scrolling pattern of waves, tilting
+// the normal light and reflections are worked out with. Sides and bottoms
+// of water are left flat
+@fragment
+fn fs_water(in: Varyings) -> Translucent {
+    var surface = in;
+    if in.normal.y > 0.0 {
+        surface.normal = ripples(in.world.xz + WATER_DRIFT * scene.time);
+    }
+
+    // Schlick's approximation, water reflecting more of the sky looking along it
+    let eye = normalize(scene.eye.xyz - in.world);
+    let facing = max(dot(eye, surface.normal), 0.0);
+    let fresnel = 0.02 + 0.98 * pow(1.0 - facing, 5.0);
+
+    // Glints of the sun, gone in its shadow
+    let sun = normalize(scene.sun.xyz);
+    let glint = pow(max(dot(reflect(-sun, surface.normal), eye), 0.0), 96.0);
+    let sunlit = sunlight(in.world, in.normal);
+
+    let reflected = mix(shade(surface), scene.fog_color, fresnel);
+    let color = reflected + glint * sunlit;
+    return accumulate(in.world, color, mix(in.alpha, 1.0, fresnel));
+}
+
+// Normal of a level surface rippled at `uv`, in blocks, out of waves
+// running at an angle to one another
+fn ripples(uv: vec2<f32>) -> vec3<f32> {
+    let slope = wave(uv, WAVE_LONG) + wave(uv, WAVE_CROSS) + wave(uv, WAVE_SHORT);
+    return normalize(vec3(-slope.x, 1.0, -slope.y));
+}
+
+// Slope of a single wave at `uv`, along x and z
+fn wave(uv: vec2<f32>, shape: vec4<f32>) -> vec2<f32> {
+    let phase = dot(uv, shape.xy) * shape.z + scene.time * shape.w;
+    return shape.xy * cos(phase) * shape.z * WAVE_HEIGHT;
+}
+
+// Weighted by closeness, so the nearest quads show the most whatever
+// the order they are drawn in. The weight is McGuire and Bavoil's
+fn accumulate(world: vec3<f32>, color: vec3<f32>, alpha: f32) -> Translucent {
+    let distance = length(world - scene.eye.xyz);
+    let closeness = 0.03 / (1e-5 + pow(distance / 200.0, 4.0));
+    let weight = alpha * clamp(closeness, 1e-2, 3e3);
+
+    var out: Translucent;
+    out.accum = vec4(color * alpha, alpha) * weight;
+    out.revealage = alpha;
+    return out;
+}
+
+// Texture coordinates of a point on a face, a texture per block, projected along
+// the axis the face is closest to facing. Up is up on every side
+fn face_uv(world: vec3<f32>, normal: vec3<f32>) -> vec2<f32> {
+    let axis = abs(normal);
+    if axis.x >= axis.y && axis.x >= axis.z {
+        return vec2(world.z, -world.y);
+    }
+
+    if axis.z >= axis.y {
+        return vec2(world.x, -world.y);
+    }
+
+    return world.xz;
+}
+
+// Texel of the frame of its texture a quad shows by now, for materials that have one
+fn texel(in: Varyings, layer: u32) -> vec3<f32> {
+    let animation = animations[layer];
+    let frame = u32(scene.time * animation.rate) % max(animation.frames, 1u);
+    let uv = face_uv(in.world, in.normal);
+    return textureSample(block_textures, block_sampler, uv, layer + frame).rgb;
+}
+
+// Lit by the sun unless in its shadow and by nearby lamps, then faded into the fog.
+// Block light falls off squared, so it fades out well before reaching zero.
+// Textures are sampled either way, as they must be in uniform control flow
+fn shade(in: Varyings) -> vec3<f32> {
+    let sun = mix(SHADOWED, 1.0, sunlight(in.world, in.normal));
+    let layer = palette[in.tint] & 0xFFu;
+    let textured = texel(in, layer) * in.light;
+    let color = select(textured, in.color, layer == BLANK) * tint(in.tint);
+    let lit = color * (sun + BLOCK_LIGHT * in.block_light * in.block_light);
+    return mix(lit, scene.fog_color, fog(in.world));
+}
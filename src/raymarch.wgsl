@@ -0,0 +1,156 @@
+// Blocks traced straight out of the chunk data, a ray per pixel, with no mesh in between.
+// If this looks right where the quads don't, the mesher is to blame and not the blocks
+
+struct Raymarch {
+    eye: vec4<f32>,
+
+    // Direction through the center of the view, and how far right and up
+    // the edges of the view are from it, w unused
+    forward: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+
+    // Of the output, in pixels
+    size: vec2<u32>,
+
+    // Chunks loaded
+    count: u32,
+}
+
+var<push_constant> raymarch: Raymarch;
+
+// Lowest corner of every chunk, w unused
+@group(0) @binding(0) var<storage, read> origins: array<vec4<f32>>;
+
+// Block ids of every chunk one after the other, 2 to a word, lowest half first.
+// Within a chunk, ordered like `Chunk` is, z then y then x
+@group(0) @binding(1) var<storage, read> blocks: array<u32>;
+
+@group(0) @binding(2) var output: texture_storage_2d<rgba16float, write>;
+
+const AIR = 0u;
+const WORDS_PER_CHUNK = 16384u;
+
+// Whatever no ray runs into, `DAY_HORIZON` in the sky shader
+const BACKGROUND = vec3(0.45, 0.62, 0.9);
+
+struct Hit {
+    // Along the ray, in lengths of its direction
+    distance: f32,
+    block: u32,
+
+    // Crossed to get into the block
+    axis: u32,
+}
+
+fn block_at(chunk: u32, cell: vec3<i32>) -> u32 {
+    let index = u32((cell.z * 32 + cell.y) * 32 + cell.x);
+    let word = blocks[chunk * WORDS_PER_CHUNK + index / 2u];
+    return (word >> (index % 2u * 16u)) & 0xffffu;
+}
+
+// First block of `chunk` along the ray that is not air, if nearer than `nearest`.
+// Stepped through the same as `pick::raycast` does, from where the ray enters the chunk
+fn trace(chunk: u32, eye: vec3<f32>, direction: vec3<f32>, nearest: Hit) -> Hit {
+    let origin = eye - origins[chunk].xyz;
+    let inverse = 1.0 / direction;
+
+    let near = -origin * inverse;
+    let far = (32.0 - origin) * inverse;
+    let entry = min(near, far);
+    let leave = max(near, far);
+
+    let enter = max(max(entry.x, entry.y), max(entry.z, 0.0));
+    let exit = min(min(leave.x, leave.y), min(leave.z, nearest.distance));
+
+    if enter > exit {
+        return nearest;
+    }
+
+    var axis = 2u;
+    if entry.x >= entry.y && entry.x >= entry.z {
+        axis = 0u;
+    } else if entry.y >= entry.z {
+        axis = 1u;
+    }
+
+    let step = vec3<i32>(sign(direction));
+    let start = origin + direction * enter;
+    var cell = clamp(vec3<i32>(floor(start)), vec3(0), vec3(31));
+    var next = (vec3<f32>(cell + max(step, vec3(0))) - origin) * inverse;
+    let delta = abs(inverse);
+    var distance = enter;
+
+    // No ray crosses more than 3 × 32 blocks of a chunk
+    for (var i = 0; i < 96; i++) {
+        let block = block_at(chunk, cell);
+        if block != AIR {
+            return Hit(distance, block, axis);
+        }
+
+        if next.x < next.y && next.x < next.z {
+            axis = 0u;
+        } else if next.y < next.z {
+            axis = 1u;
+        } else {
+            axis = 2u;
+        }
+
+        distance = next[axis];
+        cell[axis] += step[axis];
+        next[axis] += delta[axis];
+
+        if distance > exit || cell[axis] < 0 || cell[axis] > 31 {
+            break;
+        }
+    }
+
+    return nearest;
+}
+
+// A color for every block id, close ids getting unrelated colors, as `hashed_color` does
+fn block_color(block: u32) -> vec3<f32> {
+    let hash = block * 2654435761u;
+    let rgb = vec3((hash >> 8u) & 0xffu, (hash >> 16u) & 0xffu, hash >> 24u);
+    return linear(vec3<f32>(rgb) / 255.0);
+}
+
+// Decoded from sRGB, as `linear` in the quad shader
+fn linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3(2.4));
+    return select(high, low, color <= vec3(0.04045));
+}
+
+@compute @workgroup_size(8, 8)
+fn trace_blocks(@builtin(global_invocation_id) id: vec3<u32>) {
+    if any(id.xy >= raymarch.size) {
+        return;
+    }
+
+    let uv = (vec2<f32>(id.xy) + 0.5) / vec2<f32>(raymarch.size);
+    let ndc = uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+    var direction = raymarch.forward.xyz + ndc.x * raymarch.right.xyz + ndc.y * raymarch.up.xyz;
+
+    // Rays along an axis would divide by zero
+    let tiny = abs(direction) < vec3(1e-6);
+    direction = select(direction, vec3(1e-6), tiny);
+
+    var hit = Hit(1e9, AIR, 0u);
+    for (var chunk = 0u; chunk < raymarch.count; chunk++) {
+        hit = trace(chunk, raymarch.eye.xyz, direction, hit);
+    }
+
+    var color = BACKGROUND;
+    if hit.block != AIR {
+        // Shaded like quads: sides a bit darker than tops, bottoms darker still
+        var shade = 0.8;
+        if hit.axis == 1u {
+            shade = select(1.0, 0.5, direction.y > 0.0);
+        }
+
+        color = block_color(hit.block) * shade;
+    }
+
+    textureStore(output, id.xy, vec4(color, 1.0));
+}
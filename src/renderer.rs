@@ -0,0 +1,1378 @@
+mod fullscreen;
+mod fxaa;
+mod heatmap;
+mod hiz;
+mod indirect;
+mod lod;
+mod models;
+mod oit;
+mod overlay;
+mod raymarch;
+mod shadows;
+mod sky;
+mod ssao;
+mod taa;
+mod text;
+mod tonemap;
+
+use std::{borrow::Cow, error::Error, fmt::Display, mem, ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, BindingType, BlendComponent, BlendFactor,
+    BlendOperation, BlendState, Buffer, BufferDescriptor, BufferUsages, Color, ColorTargetState,
+    ColorWrites, CompareFunction, DepthStencilState, Face, FrontFace, LoadOp, PipelineLayout,
+    PolygonMode, PrimitiveState, PrimitiveTopology, RenderPass, RenderPipeline, SamplerBindingType,
+    ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages, TextureSampleType,
+    TextureViewDimension,
+};
+
+use crate::{
+    buddy::{Binding, Buddy, Handle},
+    camera::Camera,
+    color::LinearColor,
+    gfx::{
+        Bindings, FrameTargets, Gfx, GfxCapabilities, Graph, MemoryReport, PushConstants,
+        PushConstantsError, Pushed, RenderNode, RenderState, RenderTarget, Slot, DEPTH_FORMAT,
+    },
+    math::Frustum,
+    mesh::{lod::LODS, palette, Chunk, Facing, QuadRef},
+    textures::{Animation, BlockTextures},
+};
+
+pub use self::models::{cube, EntityDraw, ModelVertex};
+
+use self::{
+    fxaa::Fxaa,
+    heatmap::Heatmap,
+    hiz::HiZ,
+    indirect::Indirect,
+    lod::Lods,
+    models::Models,
+    oit::Oit,
+    overlay::Overlay,
+    raymarch::Raymarch,
+    shadows::{Cascades, Shadows, CASCADES, CASCADE_LABELS, CASTER_REACH},
+    sky::Sky,
+    ssao::Ssao,
+    taa::Taa,
+    text::Text,
+    tonemap::Tonemap,
+};
+
+// Entries in every row of the material palette, one for every material a quad can have
+pub const MATERIALS: usize = 256;
+
+// Rows of the material palette, say one per biome
+pub const PALETTE_ROWS: usize = 16;
+
+// Leaves materials as they are
+const UNTINTED: u32 = palette::entry(0, [255; 3]);
+
+// Matches `Draw` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct DrawConstants {
+    view_proj: [[f32; 4]; 4],
+    origin: [f32; 4],
+    axes: [u32; 4],
+}
+
+// Matches `Animation` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct AnimationUniform {
+    frames: u32,
+    rate: f32,
+    _padding: [u32; 2],
+}
+
+// Matches `Scene` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SceneUniforms {
+    eye: [f32; 4],
+    fog_color: [f32; 3],
+    fog_density: f32,
+    fog_height: f32,
+    fog_falloff: f32,
+    _padding: [f32; 2],
+    forward: [f32; 4],
+    sun: [f32; 4],
+    splits: [f32; 4],
+    cascades: [[[f32; 4]; 4]; CASCADES],
+    view: u32,
+    time: f32,
+    _view_padding: [u32; 2],
+}
+
+// Fog thickening with distance, and the more so the lower it gets,
+// so chunks fade into the sky instead of popping in at the horizon
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    // Blended into, to be kept close to the sky near the horizon
+    pub color: LinearColor,
+
+    // Per block looked through at `height`
+    pub density: f32,
+    pub height: f32,
+
+    // How fast it thins out going up, per block. At 0 it is just as thick everywhere
+    pub falloff: f32,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            // `DAY_HORIZON` in the sky shader
+            color: LinearColor::new([0.45, 0.62, 0.9]),
+            density: 0.004,
+            height: 0.0,
+            falloff: 0.02,
+        }
+    }
+}
+
+// Ambient occlusion traced in screen space, over what vertices bake in
+#[derive(Clone, Copy, Debug)]
+pub struct Occlusion {
+    // Farthest occluders are looked for, in blocks
+    pub radius: f32,
+
+    // Exponent darkening occlusion further. At 0 it is left out altogether
+    pub intensity: f32,
+}
+
+impl Default for Occlusion {
+    fn default() -> Self {
+        Self {
+            radius: 1.5,
+            intensity: 1.5,
+        }
+    }
+}
+
+// What quads are drawn as, to see how chunks were meshed.
+// Matches the `VIEW_*` constants in the shader
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum DebugView {
+    #[default]
+    Shaded,
+
+    // Edges of every triangle, if the device can draw lines
+    Wireframe,
+
+    // Every quad in a color of its own
+    Quads,
+
+    // Every chunk in a color of its own
+    Chunks,
+
+    // Brighter the more quads get drawn over each pixel, hidden or not
+    Overdraw,
+
+    // Blocks traced straight out of the chunks loaded with `Renderer::load_voxels`,
+    // with no quads drawn at all. Unseen by the shader, and unavailable without compute
+    Raymarch,
+}
+
+impl DebugView {
+    // The view to switch to next, going around all of them
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Shaded => Self::Wireframe,
+            Self::Wireframe => Self::Quads,
+            Self::Quads => Self::Chunks,
+            Self::Chunks => Self::Overdraw,
+            Self::Overdraw => Self::Raymarch,
+            Self::Raymarch => Self::Shaded,
+        }
+    }
+}
+
+// How edges get smoothed, over whatever multisampling does
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Antialiasing {
+    // Left to multisampling alone, if any
+    #[default]
+    Multisample,
+
+    // Blurred along edges found in the scene, see `Fxaa`
+    Fxaa,
+
+    // Blended over jittered frames, see `Taa`
+    Taa,
+}
+
+// Which quads of a chunk a draw holds, as split by `mesh::Layers`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Opaque,
+
+    // Blended over every opaque chunk
+    Translucent,
+
+    // Blended along with translucent quads, with rippling surfaces
+    Water,
+}
+
+impl Layer {
+    pub const ALL: [Self; 3] = [Self::Opaque, Self::Translucent, Self::Water];
+}
+
+// A chunk mesh living in a bindable block of the quad buddy,
+// laid out as `upload::Packed` lays it out
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkDraw<'a> {
+    pub handle: &'a Handle<QuadRef>,
+    pub facings: &'a [Range<u32>; 6],
+
+    // Chunk position in blocks
+    pub origin: [f32; 3],
+    pub layer: Layer,
+
+    // Row of the material palette its quads are tinted by, see `Renderer::write_palette`
+    pub palette: u32,
+
+    // Coarser meshes of the chunk, as `mesh::lod::mesh_lods` makes them, drawn
+    // in its place further away when drawing indirectly. Levels past one missing are
+    // left out along with it
+    pub lods: [Option<LodDraw<'a>>; LODS],
+}
+
+// A coarser mesh of a chunk, laid out as the full one
+#[derive(Clone, Copy, Debug)]
+pub struct LodDraw<'a> {
+    pub handle: &'a Handle<QuadRef>,
+    pub facings: &'a [Range<u32>; 6],
+}
+
+impl ChunkDraw<'_> {
+    // Lowest and highest corners of the chunk, in blocks
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        (self.origin, self.origin.map(|c| c + 32.0))
+    }
+}
+
+// How many chunks the last `Renderer::declare` left out of view, and drew
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullCounts {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+// How far along a chunk is, as its outline shows it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkStatus {
+    Dirty,
+    Meshing,
+
+    // Quads in the buddy, drawn unless culled
+    Uploaded,
+}
+
+// Chunk outlined in `DebugView::Chunks`: its lowest corner, the box around
+// its quads as last meshed, if it has any, all in blocks, and how far along it is
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkOutline {
+    pub origin: [f32; 3],
+    pub quads: Option<([f32; 3], [f32; 3])>,
+    pub status: ChunkStatus,
+}
+
+// How chunks end up drawn, depending on what the device supports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawPath {
+    // A single `multi_draw_indirect` for every chunk
+    MultiDrawIndirect,
+
+    // A `draw_indirect` per chunk, out of the same arguments
+    DrawIndirect,
+
+    // A direct draw per chunk facing, placed through push constants
+    Direct,
+}
+
+impl DrawPath {
+    // Best path the negotiated features allow. Indirect draws start
+    // at the offset of each chunk, so they need a first instance
+    pub fn new(capabilities: GfxCapabilities) -> Self {
+        if !capabilities.indirect_first_instance {
+            Self::Direct
+        } else if capabilities.multi_draw_indirect {
+            Self::MultiDrawIndirect
+        } else {
+            Self::DrawIndirect
+        }
+    }
+}
+
+// Draws chunks straight out of the quad buddy: every quad is an instance,
+// expanded into 4 vertices by the shader from its packed fields alone
+#[derive(Debug)]
+pub struct Renderer {
+    path: DrawPath,
+    module: ShaderModule,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<DrawConstants>,
+    binding: Binding,
+
+    // Holds `SceneUniforms`, written on every declare
+    scene: Buffer,
+    scene_group: BindGroup,
+
+    // `PALETTE_ROWS` rows of `MATERIALS` entries, as `mesh::palette::entry` packs them
+    palette: Buffer,
+
+    // How the texture starting at every layer is animated, `MATERIALS` of them.
+    // Frames go by along with `time`, so nothing needs writing from frame to frame
+    animations: Buffer,
+
+    // Unless drawing directly
+    indirect: Option<(Arc<PipelineLayout>, Indirect)>,
+
+    // Culls indirect draws and picks the level of detail they are drawn at,
+    // if compute shaders are available
+    hiz: Option<HiZ>,
+    lods: Option<Lods>,
+    culling: CullCounts,
+
+    // Traces blocks for `DebugView::Raymarch`, likewise
+    raymarch: Option<Raymarch>,
+    fxaa: Fxaa,
+    heatmap: Heatmap,
+    models: Models,
+    oit: Oit,
+    overlay: Overlay,
+    shadows: Shadows,
+    sky: Sky,
+    ssao: Ssao,
+    taa: Taa,
+    text: Text,
+    tonemap: Tonemap,
+
+    // Towards the sun, lighting the sky
+    pub sun: [f32; 3],
+    pub fog: Fog,
+    pub occlusion: Occlusion,
+    pub view: DebugView,
+    pub antialiasing: Antialiasing,
+
+    // Entities to draw this frame, by models given to `load_model`
+    pub entities: Vec<EntityDraw>,
+
+    // Lowest corner of the block to outline, if any
+    pub selected: Option<[f32; 3]>,
+    pub crosshair: bool,
+
+    // Chunks to outline this frame, colored by how far along they are
+    // and whether they got culled
+    pub outlines: Vec<ChunkOutline>,
+
+    // Lines of text drawn over the top left corner of the frame
+    pub hud: Vec<String>,
+
+    // Occupancy of the quad buffer drawn over the bottom left corner of the frame
+    pub occupancy: bool,
+
+    // Draw depth alone first, so only visible quads get shaded
+    pub prepass: bool,
+
+    // Blocks away from the eye every coarser level of detail takes over at
+    pub lod_distance: f32,
+
+    // Brightness the scene is scaled by before tonemapping
+    pub exposure: f32,
+
+    // Seconds water surfaces have been rippling for
+    pub time: f32,
+}
+
+impl Renderer {
+    // Chunks drawn may hold up to `window` quads.
+    // Culling, ambient occlusion, antialiasing and outlines depend on the sample count,
+    // so build again after changing it
+    pub fn new(gfx: &Gfx, quads: &Buddy<QuadRef>, window: usize) -> Result<Self, RendererError> {
+        let push = push_constants(gfx, "quads", ShaderStages::VERTEX, 2)?;
+
+        let descriptor = BufferDescriptor {
+            label: Some("scene"),
+            size: mem::size_of::<SceneUniforms>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+
+        let scene = gfx.device.create_buffer(&descriptor);
+        let shadows = Shadows::new(gfx);
+
+        // Untinted until written
+        let entries = [UNTINTED; PALETTE_ROWS * MATERIALS];
+        let palette = gfx.device.create_buffer(&BufferDescriptor {
+            label: Some("palette"),
+            size: mem::size_of_val(&entries) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let blob = bytemuck::cast_slice(&entries);
+        gfx.queue.write_buffer(&palette, 0, blob);
+
+        // Still, as zeroed
+        let animations = gfx.device.create_buffer(&BufferDescriptor {
+            label: Some("animations"),
+            size: (MATERIALS * mem::size_of::<AnimationUniform>()) as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Every material shows its own color until textures are given
+        let blank = BlockTextures::blank(gfx);
+        let resources = SceneResources {
+            scene: &scene,
+            shadows: &shadows,
+            palette: &palette,
+            textures: &blank,
+            animations: &animations,
+        };
+
+        let (scene_layout, scene_group) = resources.create(gfx);
+
+        let binding = quads.create_binding(gfx, ShaderStages::VERTEX, window);
+        let module = push.module(gfx, "quads", include_str!("quad.wgsl"));
+        let layout = create_layout(gfx, &binding.layout, &scene_layout, &push);
+
+        let path = DrawPath::new(gfx.capabilities());
+        let indirect = (path != DrawPath::Direct).then(|| {
+            let indirect = Indirect::new(gfx, quads, 64);
+            let layout = create_layout(gfx, &indirect.layout, &scene_layout, &push);
+            (layout, indirect)
+        });
+
+        let culls = indirect.is_some() && HiZ::is_supported(gfx);
+        let hiz = culls.then(|| HiZ::new(gfx)).transpose()?;
+        let picks = indirect.is_some() && Lods::is_supported(gfx);
+        let lods = picks.then(|| Lods::new(gfx)).transpose()?;
+        let raymarch = Raymarch::is_supported(gfx).then(|| Raymarch::new(gfx));
+        let raymarch = raymarch.transpose()?;
+
+        Ok(Self {
+            path,
+            module,
+            layout,
+            push,
+            binding,
+            scene,
+            scene_group,
+            palette,
+            animations,
+            indirect,
+            hiz,
+            lods,
+            culling: CullCounts::default(),
+            raymarch,
+            fxaa: Fxaa::new(gfx),
+            heatmap: Heatmap::new(gfx)?,
+            models: Models::new(gfx)?,
+            oit: Oit::new(gfx),
+            overlay: Overlay::new(gfx)?,
+            shadows,
+            sky: Sky::new(gfx)?,
+            ssao: Ssao::new(gfx)?,
+            taa: Taa::new(gfx)?,
+            text: Text::new(gfx)?,
+            tonemap: Tonemap::new(gfx)?,
+            sun: [0.4, 0.6, 0.3],
+            fog: Fog::default(),
+            occlusion: Occlusion::default(),
+            view: DebugView::default(),
+            antialiasing: Antialiasing::default(),
+            entities: Vec::new(),
+            selected: None,
+            crosshair: false,
+            outlines: Vec::new(),
+            hud: Vec::new(),
+            occupancy: false,
+            prepass: false,
+            lod_distance: 128.0,
+            exposure: 1.0,
+            time: 0.0,
+        })
+    }
+
+    pub const fn path(&self) -> DrawPath {
+        self.path
+    }
+
+    // Model of entities drawn as `model`, in place of whatever was loaded as it before.
+    // Models are gone along with the renderer, so load them again after building another.
+    // False if there is no room left for them
+    pub fn load_model(&mut self, gfx: &Gfx, model: u32, vertices: &[ModelVertex]) -> bool {
+        self.models.load(gfx, model, vertices)
+    }
+
+    pub fn unload_model(&mut self, model: u32) {
+        self.models.unload(model);
+    }
+
+    // Write a row of the material palette, an entry per material.
+    // Chunks drawn with it are recolored right away, with no need to mesh them again
+    pub fn write_palette(&self, gfx: &Gfx, row: u32, entries: &[u32]) {
+        assert!((row as usize) < PALETTE_ROWS, "no palette row {row}");
+        let entries = &entries[..entries.len().min(MATERIALS)];
+        let offset = (row as usize * MATERIALS * mem::size_of::<u32>()) as u64;
+        let blob = bytemuck::cast_slice(entries);
+        gfx.queue.write_buffer(&self.palette, offset, blob);
+    }
+
+    // Block textures for palette entries to pick layers out of, in place of any before
+    pub fn set_textures(&mut self, gfx: &Gfx, textures: &BlockTextures) {
+        let resources = SceneResources {
+            scene: &self.scene,
+            shadows: &self.shadows,
+            palette: &self.palette,
+            textures,
+            animations: &self.animations,
+        };
+
+        (_, self.scene_group) = resources.create(gfx);
+    }
+
+    // Animate the texture starting at `layer`, going through the layers after it
+    // wherever palette entries pick it, `Animation::STILL` to stop
+    pub fn write_animation(&self, gfx: &Gfx, layer: u32, animation: Animation) {
+        assert!((layer as usize) < MATERIALS, "no layer {layer}");
+        let uniform = AnimationUniform {
+            frames: animation.frames,
+            rate: animation.rate,
+            _padding: [0; 2],
+        };
+
+        let offset = (layer as usize * mem::size_of::<AnimationUniform>()) as u64;
+        let blob = bytemuck::bytes_of(&uniform);
+        gfx.queue.write_buffer(&self.animations, offset, blob);
+    }
+
+    pub const fn culling(&self) -> CullCounts {
+        self.culling
+    }
+
+    // Blocks of `chunks` for `DebugView::Raymarch` to trace, each with its lowest corner
+    pub fn load_voxels(&mut self, gfx: &Gfx, chunks: &[([f32; 3], &Chunk)]) {
+        if let Some(raymarch) = &mut self.raymarch {
+            raymarch.load(gfx, chunks);
+        }
+    }
+
+    // Declare the passes drawing every chunk seen by `camera` over the sky,
+    // then bringing them into `frame`. Chunks out of view are left out first,
+    // opaque ones only once their shadows cannot fall into view either.
+    // Drawing directly, chunks whose blocks
+    // cannot be bound are skipped, see `Buddy::alloc_bindable`. Drawing
+    // indirectly, chunks past what a single binding of the buddy buffer
+    // can reach are skipped instead, and so are chunks occluded in the depth
+    // left by the previous frame, though they still cast shadows.
+    // Translucent chunks are always drawn directly, after everything else
+    pub fn declare<'a>(
+        &'a mut self,
+        gfx: &'a Gfx,
+        graph: &mut Graph<'a>,
+        frame: Slot,
+        quads: &Buddy<QuadRef>,
+        camera: &Camera,
+        chunks: &[ChunkDraw],
+    ) {
+        let aspect = gfx.config.width as f32 / gfx.config.height as f32;
+        self.text.prepare(gfx, &self.hud);
+
+        if self.occupancy {
+            self.heatmap.prepare(gfx, quads);
+        }
+
+        // Traced instead of drawn, leaving culling and history as they were
+        if self.view == DebugView::Raymarch && self.raymarch.is_some() {
+            let this: &'a Self = self;
+            if let Some(raymarch) = &this.raymarch {
+                let scene = raymarch.declare(gfx, graph, camera, aspect);
+                this.declare_overlays(gfx, graph, scene, frame);
+            }
+
+            return;
+        }
+
+        let view_proj = camera.view_proj(aspect);
+        let cascades = self.shadows.fit(camera, aspect, self.sun);
+        self.write_scene(gfx, camera, &cascades);
+
+        // Away from the sun, as far as shadows are cast
+        let frustum = Frustum::new(view_proj);
+        let length = self.sun.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let reach = CASTER_REACH / length.max(f32::MIN_POSITIVE);
+        let shadow = self.sun.map(|c| -c * reach);
+
+        // Chunks with quads still staged hold nothing worth drawing yet
+        let visible = |chunk: &ChunkDraw| {
+            let (min, max) = chunk.bounds();
+            let visible = match chunk.layer {
+                Layer::Opaque => frustum.intersects_swept(min, max, shadow),
+                _ => frustum.intersects(min, max),
+            };
+
+            visible && !quads.is_staged(chunk.handle)
+        };
+
+        let total = chunks.len();
+        let chunks: Vec<_> = chunks.iter().copied().filter(visible).collect();
+        let culled = total - chunks.len();
+        self.culling = CullCounts {
+            drawn: chunks.len(),
+            culled,
+        };
+
+        let (overlay, outlines) = (&mut self.overlay, &self.outlines);
+        overlay.prepare_outlines(gfx, outlines, &frustum);
+
+        let layer = |layer| -> Vec<_> {
+            let chunks = chunks.iter().copied();
+            chunks.filter(|chunk| chunk.layer == layer).collect()
+        };
+
+        let (opaque, translucent, water) = (
+            layer(Layer::Opaque),
+            layer(Layer::Translucent),
+            layer(Layer::Water),
+        );
+        let chunks = &opaque[..];
+
+        if let Some((_, indirect)) = &mut self.indirect {
+            indirect.pack(gfx, quads, chunks);
+        }
+
+        let hiz = self.hiz.as_mut();
+        let culled = hiz.and_then(|hiz| hiz.prepare(gfx, view_proj));
+
+        // Culling goes on with the view as it is, drawing with it jittered
+        let taa = self.antialiasing == Antialiasing::Taa;
+        let view_proj = match taa {
+            true => self.taa.prepare(gfx, camera, aspect),
+            false => view_proj,
+        };
+
+        let this: &'a Self = self;
+        let targets = gfx.hdr_targets(graph);
+
+        // Overdraw adds up over black, anything else gets drawn over the sky
+        let overdraw = this.view == DebugView::Overdraw;
+        let load = match overdraw {
+            true => LoadOp::Clear(Color::BLACK),
+            false => LoadOp::Load,
+        };
+
+        if !overdraw {
+            let (sky, sun) = (&this.sky, this.sun);
+            sky.declare(gfx, graph, targets, camera, aspect, sun);
+        }
+
+        // Levels of detail picked before anything gets drawn, shadows included
+        if let (Some((_, indirect)), Some(lods)) = (&this.indirect, &this.lods) {
+            lods.pick(gfx, graph, indirect, camera.eye, this.lod_distance);
+        }
+
+        // Before culling, which leaves out chunks that may still cast shadows
+        this.declare_shadows(gfx, graph, quads, chunks, &cascades);
+        let shadows = graph.import(this.shadows.view());
+
+        // Depth left by the prepass, if any, is kept for the main pass to test against
+        let prepass = this.prepasses();
+        let depth = match prepass {
+            true => LoadOp::Load,
+            false => LoadOp::Clear(1.0),
+        };
+
+        match &this.indirect {
+            None => {
+                let vertex = "vs_main";
+
+                if prepass {
+                    let layout = create_depth_layout(gfx, &this.binding.layout, &this.push);
+                    let node = DirectNode {
+                        pipeline: this.request_prepass_pipeline(gfx, &layout, vertex),
+                        push: &this.push,
+                        scene: None,
+                        draws: this.direct_draws(gfx, quads, view_proj, chunks),
+                    };
+
+                    let clear = LoadOp::Clear(1.0);
+                    graph.render("prepass", node).depth(targets.depth, clear);
+                }
+
+                let draws = this.direct_draws(gfx, quads, view_proj, chunks);
+                let node = DirectNode {
+                    pipeline: this.request_pipeline(gfx, targets, &this.layout, vertex),
+                    push: &this.push,
+                    scene: Some(&this.scene_group),
+                    draws,
+                };
+
+                let pass = graph.render("quads", node);
+                pass.color_resolved(targets.color, targets.resolve, load);
+                pass.depth(targets.depth, depth).read(shadows);
+            }
+            Some((layout, indirect)) => {
+                if let (Some(hiz), Some(culled)) = (&this.hiz, culled) {
+                    hiz.cull(gfx, graph, indirect, culled);
+                }
+
+                let vertex = "vs_indirect";
+                let constants = DrawConstants {
+                    view_proj,
+                    origin: [0.0; 4],
+                    axes: [0; 4],
+                };
+
+                let multi = this.path == DrawPath::MultiDrawIndirect;
+
+                if prepass {
+                    let layout = create_depth_layout(gfx, &indirect.layout, &this.push);
+                    let node = IndirectNode {
+                        pipeline: this.request_prepass_pipeline(gfx, &layout, vertex),
+                        push: &this.push,
+                        scene: None,
+                        indirect,
+                        constants: this.push.write(gfx, constants),
+                        multi,
+                    };
+
+                    let clear = LoadOp::Clear(1.0);
+                    graph.render("prepass", node).depth(targets.depth, clear);
+                }
+
+                let node = IndirectNode {
+                    pipeline: this.request_pipeline(gfx, targets, layout, vertex),
+                    push: &this.push,
+                    scene: Some(&this.scene_group),
+                    indirect,
+                    constants: this.push.write(gfx, constants),
+                    multi,
+                };
+
+                let pass = graph.render("quads", node);
+                pass.color_resolved(targets.color, targets.resolve, load);
+                pass.depth(targets.depth, depth).read(shadows);
+
+                if let Some(hiz) = &this.hiz {
+                    hiz.build(graph, targets.depth);
+                }
+            }
+        }
+
+        // Debug views leave out entities and translucent quads, and their colors as they are
+        if this.view == DebugView::Shaded {
+            let (models, entities) = (&this.models, &this.entities);
+            models.declare(gfx, graph, targets, view_proj, entities);
+
+            let (ssao, occlusion) = (&this.ssao, this.occlusion);
+            ssao.declare(gfx, graph, targets, camera, aspect, occlusion);
+
+            let draws = this.direct_draws(gfx, quads, view_proj, &translucent);
+            let water = this.direct_draws(gfx, quads, view_proj, &water);
+            this.declare_translucent(gfx, graph, targets, [draws, water], shadows);
+        }
+
+        if let Some(block) = this.selected {
+            let overlay = &this.overlay;
+            overlay.declare_outline(gfx, graph, targets, view_proj, block);
+        }
+
+        if !this.outlines.is_empty() {
+            let overlay = &this.overlay;
+            overlay.declare_chunks(gfx, graph, targets, view_proj);
+        }
+
+        // Smoothed in linear light, before the scene gets scaled into the frame
+        let scene = match this.antialiasing {
+            Antialiasing::Multisample => targets.output(),
+            Antialiasing::Fxaa => this.fxaa.declare(gfx, graph, targets),
+            Antialiasing::Taa => this.taa.declare(gfx, graph, targets),
+        };
+
+        this.declare_overlays(gfx, graph, scene, frame);
+    }
+
+    // Bring `scene` into `frame`, then draw the crosshair and text over it
+    fn declare_overlays<'a>(&'a self, gfx: &Gfx, graph: &mut Graph<'a>, scene: Slot, frame: Slot) {
+        let exposure = self.exposure;
+        self.tonemap.declare(gfx, graph, scene, frame, exposure);
+
+        let format = gfx.format();
+        if self.crosshair {
+            self.overlay.declare_crosshair(gfx, graph, frame, format);
+        }
+
+        if self.occupancy {
+            self.heatmap.declare(gfx, graph, frame, format);
+        }
+
+        self.text.declare(gfx, graph, frame, format);
+    }
+
+    // Into `report`, every texture kept from frame to frame
+    pub fn report_memory(&self, report: &mut MemoryReport) {
+        self.models.report_memory(report);
+        self.shadows.report_memory(report);
+        self.taa.report_memory(report);
+
+        if let Some(hiz) = &self.hiz {
+            hiz.report_memory(report);
+        }
+    }
+
+    // The sky and opaque chunks as seen by `camera`, drawn into `target` to be sampled
+    // by later passes. Lit as by the last `declare`, whose shadows it reuses if declared
+    // after it in the same graph. Translucent quads and post passes are left out
+    pub fn declare_view<'a>(
+        &'a self,
+        gfx: &'a Gfx,
+        graph: &mut Graph<'a>,
+        target: &'a RenderTarget,
+        quads: &Buddy<QuadRef>,
+        camera: &Camera,
+        chunks: &[ChunkDraw],
+    ) -> Slot {
+        let aspect = target.aspect();
+        let view_proj = camera.view_proj(aspect);
+        let targets = target.targets(graph);
+
+        self.sky
+            .declare(gfx, graph, targets, camera, aspect, self.sun);
+        let shadows = graph.import(self.shadows.view());
+
+        // Shadows are reused as they are, so chunks out of view can go
+        let frustum = Frustum::new(view_proj);
+        let visible = |chunk: &ChunkDraw| {
+            let (min, max) = chunk.bounds();
+            let opaque = chunk.layer == Layer::Opaque;
+            opaque && frustum.intersects(min, max) && !quads.is_staged(chunk.handle)
+        };
+
+        let opaque: Vec<_> = chunks.iter().copied().filter(visible).collect();
+        let vertex = "vs_main";
+
+        // The main pipeline tests for equal depth after a prepass, so it needs one here too
+        let prepass = self.prepasses();
+        if prepass {
+            let layout = create_depth_layout(gfx, &self.binding.layout, &self.push);
+            let node = DirectNode {
+                pipeline: self.request_prepass_pipeline(gfx, &layout, vertex),
+                push: &self.push,
+                scene: None,
+                draws: self.direct_draws(gfx, quads, view_proj, &opaque),
+            };
+
+            let clear = LoadOp::Clear(1.0);
+            graph
+                .render("view prepass", node)
+                .depth(targets.depth, clear);
+        }
+
+        let depth = match prepass {
+            true => LoadOp::Load,
+            false => LoadOp::Clear(1.0),
+        };
+
+        let node = DirectNode {
+            pipeline: self.request_pipeline(gfx, targets, &self.layout, vertex),
+            push: &self.push,
+            scene: Some(&self.scene_group),
+            draws: self.direct_draws(gfx, quads, view_proj, &opaque),
+        };
+
+        let pass = graph.render("view", node);
+        pass.color_resolved(targets.color, targets.resolve, LoadOp::Load);
+        pass.depth(targets.depth, depth).read(shadows);
+
+        targets.output()
+    }
+
+    // Translucent quads and then water blended over whatever got drawn before,
+    // tested against its depth without writing any. Water gets a pass of its own,
+    // adding up into the same targets so either shows through the other
+    fn declare_translucent<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        [translucent, water]: [Vec<DirectDraw<'a>>; 2],
+        shadows: Slot,
+    ) {
+        if translucent.is_empty() && water.is_empty() {
+            return;
+        }
+
+        let oit = Oit::targets(gfx, graph);
+        let passes = [
+            ("translucent", "fs_translucent", translucent),
+            ("water", "fs_water", water),
+        ];
+
+        let mut cleared = false;
+        for (label, fragment, draws) in passes {
+            if draws.is_empty() {
+                continue;
+            }
+
+            let node = DirectNode {
+                pipeline: self.request_translucent_pipeline(gfx, label, fragment),
+                push: &self.push,
+                scene: Some(&self.scene_group),
+                draws,
+            };
+
+            let pass = graph.render(label, node);
+            match cleared {
+                true => oit.attach_loaded(pass),
+                false => oit.attach(pass),
+            }
+
+            pass.depth(targets.depth, LoadOp::Load).read(shadows);
+            cleared = true;
+        }
+
+        let (output, format) = (targets.output(), targets.format);
+        self.oit.declare_composite(gfx, graph, oit, output, format);
+    }
+
+    // Depth of every chunk as seen from the sun, a pass per cascade
+    fn declare_shadows<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        quads: &Buddy<QuadRef>,
+        chunks: &[ChunkDraw],
+        cascades: &Cascades,
+    ) {
+        let passes = CASCADE_LABELS.into_iter().zip(self.shadows.layers());
+
+        for ((label, map), view_proj) in passes.zip(cascades.view_projs) {
+            let map = graph.import(map);
+
+            match &self.indirect {
+                None => {
+                    let vertex = "vs_main";
+                    let layout = create_depth_layout(gfx, &self.binding.layout, &self.push);
+                    let node = DirectNode {
+                        pipeline: shadows::request_pipeline(gfx, &self.module, &layout, vertex),
+                        push: &self.push,
+                        scene: None,
+                        draws: self.direct_draws(gfx, quads, view_proj, chunks),
+                    };
+
+                    graph.render(label, node).depth(map, LoadOp::Clear(1.0));
+                }
+                Some((_, indirect)) => {
+                    let vertex = "vs_indirect";
+                    let layout = create_depth_layout(gfx, &indirect.layout, &self.push);
+                    let constants = DrawConstants {
+                        view_proj,
+                        origin: [0.0; 4],
+                        axes: [0; 4],
+                    };
+
+                    let node = IndirectNode {
+                        pipeline: shadows::request_pipeline(gfx, &self.module, &layout, vertex),
+                        push: &self.push,
+                        scene: None,
+                        indirect,
+                        constants: self.push.write(gfx, constants),
+                        multi: self.path == DrawPath::MultiDrawIndirect,
+                    };
+
+                    graph.render(label, node).depth(map, LoadOp::Clear(1.0));
+                }
+            }
+        }
+    }
+
+    fn write_scene(&self, gfx: &Gfx, camera: &Camera, cascades: &Cascades) {
+        let extend = |[x, y, z]: [f32; 3]| [x, y, z, 0.0];
+        let forward = camera.forward();
+        let fog = self.fog;
+
+        let uniforms = SceneUniforms {
+            eye: extend(camera.eye),
+            fog_color: fog.color.to_array(),
+            fog_density: fog.density,
+            fog_height: fog.height,
+            fog_falloff: fog.falloff,
+            _padding: [0.0; 2],
+            forward: extend(forward),
+            sun: extend(self.sun),
+            splits: cascades.splits,
+            cascades: cascades.view_projs,
+            view: self.view as u32,
+            time: self.time,
+            _view_padding: [0; 2],
+        };
+
+        let blob = bytemuck::bytes_of(&uniforms);
+        gfx.queue.write_buffer(&self.scene, 0, blob);
+    }
+
+    // A draw per chunk facing, placed through push constants
+    fn direct_draws(
+        &self,
+        gfx: &Gfx,
+        quads: &Buddy<QuadRef>,
+        view_proj: [[f32; 4]; 4],
+        chunks: &[ChunkDraw],
+    ) -> Vec<DirectDraw<'_>> {
+        let mut draws = Vec::new();
+
+        for chunk in chunks {
+            let Some((group, offset)) = quads.bind(gfx, &self.binding, chunk.handle) else {
+                continue;
+            };
+
+            for (facing, range) in Facing::ALL.into_iter().zip(chunk.facings) {
+                if range.is_empty() {
+                    continue;
+                }
+
+                let [x, y, z] = chunk.origin;
+                let constants = DrawConstants {
+                    view_proj,
+                    origin: [x, y, z, chunk.palette as f32],
+                    axes: facing_axes(facing),
+                };
+
+                draws.push(DirectDraw {
+                    group,
+                    offset,
+                    constants: self.push.write(gfx, constants),
+                    instances: range.clone(),
+                });
+            }
+        }
+
+        draws
+    }
+
+    // Whether depth gets drawn first, then tested for equality when shading
+    fn prepasses(&self) -> bool {
+        self.prepass && self.view != DebugView::Overdraw
+    }
+
+    // Quad pipeline pulling vertices with `vertex`, drawing into `targets` as `view` shows them
+    fn request_pipeline(
+        &self,
+        gfx: &Gfx,
+        targets: FrameTargets,
+        layout: &PipelineLayout,
+        vertex: &'static str,
+    ) -> Arc<RenderPipeline> {
+        let lines = gfx.capabilities().line_polygons;
+        let polygon_mode = match self.view {
+            DebugView::Wireframe if lines => PolygonMode::Line,
+            _ => PolygonMode::Fill,
+        };
+
+        // Overdraw counts every quad drawn, so depth is neither tested nor written
+        let overdraw = self.view == DebugView::Overdraw;
+        let (blend, depth_compare) = match (overdraw, self.prepasses()) {
+            (true, _) => (Some(ADDITIVE), CompareFunction::Always),
+            (false, true) => (None, CompareFunction::Equal),
+            (false, false) => (None, CompareFunction::Less),
+        };
+
+        let target = ColorTargetState {
+            format: targets.format,
+            blend,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = RenderState {
+            label: "quads",
+            vertex,
+            fragment: Some("fs_main"),
+            targets: vec![Some(target)],
+            primitive: quad_primitive(polygon_mode),
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: depth_compare == CompareFunction::Less,
+                depth_compare,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: gfx.multisample_state(),
+        };
+
+        gfx.render_pipeline(&self.module, Some(layout), &state)
+    }
+
+    // Translucent quad pipeline shading with `fragment`, adding up into `Oit::targets`
+    fn request_translucent_pipeline(
+        &self,
+        gfx: &Gfx,
+        label: &'static str,
+        fragment: &'static str,
+    ) -> Arc<RenderPipeline> {
+        let state = RenderState {
+            label,
+            vertex: "vs_main",
+            fragment: Some(fragment),
+            targets: Oit::color_targets(),
+            primitive: quad_primitive(PolygonMode::Fill),
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: gfx.multisample_state(),
+        };
+
+        gfx.render_pipeline(&self.module, Some(&self.layout), &state)
+    }
+
+    // Depth-only quad pipeline, for the prepass
+    fn request_prepass_pipeline(
+        &self,
+        gfx: &Gfx,
+        layout: &PipelineLayout,
+        vertex: &'static str,
+    ) -> Arc<RenderPipeline> {
+        let state = RenderState {
+            label: "prepass",
+            vertex,
+            fragment: None,
+            targets: Vec::new(),
+            primitive: quad_primitive(PolygonMode::Fill),
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: gfx.multisample_state(),
+        };
+
+        gfx.render_pipeline(&self.module, Some(layout), &state)
+    }
+}
+
+#[derive(Debug)]
+struct DirectDraw<'a> {
+    group: &'a BindGroup,
+    offset: u32,
+    constants: Pushed<DrawConstants>,
+    instances: Range<u32>,
+}
+
+struct DirectNode<'a> {
+    pipeline: Arc<RenderPipeline>,
+    push: &'a PushConstants<DrawConstants>,
+
+    // Drawing shadows, the scene is left unbound
+    scene: Option<&'a BindGroup>,
+    draws: Vec<DirectDraw<'a>>,
+}
+
+impl RenderNode for DirectNode<'_> {
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+
+        match self.scene {
+            Some(scene) => pass.set_bind_group(1, scene, &[]),
+            None => self.push.pad(pass, 1),
+        }
+
+        for draw in &self.draws {
+            pass.set_bind_group(0, draw.group, &[draw.offset]);
+            self.push.set(pass, &draw.constants);
+            pass.draw(0..4, draw.instances.clone());
+        }
+    }
+}
+
+struct IndirectNode<'a> {
+    pipeline: Arc<RenderPipeline>,
+    push: &'a PushConstants<DrawConstants>,
+    scene: Option<&'a BindGroup>,
+    indirect: &'a Indirect,
+    constants: Pushed<DrawConstants>,
+    multi: bool,
+}
+
+impl RenderNode for IndirectNode<'_> {
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+
+        match self.scene {
+            Some(scene) => pass.set_bind_group(1, scene, &[]),
+            None => self.push.pad(pass, 1),
+        }
+
+        self.push.set(pass, &self.constants);
+        self.indirect.draw(pass, self.multi);
+    }
+}
+
+// Whatever the scene group binds, for it to be made again as textures change
+struct SceneResources<'a> {
+    scene: &'a Buffer,
+    shadows: &'a Shadows,
+    palette: &'a Buffer,
+    textures: &'a BlockTextures,
+    animations: &'a Buffer,
+}
+
+impl SceneResources<'_> {
+    fn create(&self, gfx: &Gfx) -> (Arc<BindGroupLayout>, BindGroup) {
+        let bindings = Bindings::new(ShaderStages::FRAGMENT)
+            .uniform()
+            .with(shadows::MAPS_BINDING)
+            .sampler(SamplerBindingType::Comparison)
+            .storage(true)
+            .texture_array()
+            .sampler(SamplerBindingType::Filtering)
+            .uniform();
+
+        let resources = [
+            self.scene.as_entire_binding(),
+            BindingResource::TextureView(self.shadows.view()),
+            BindingResource::Sampler(self.shadows.sampler()),
+            self.palette.as_entire_binding(),
+            BindingResource::TextureView(&self.textures.view),
+            BindingResource::Sampler(&self.textures.sampler),
+            self.animations.as_entire_binding(),
+        ];
+
+        bindings.create(gfx, "scene", resources)
+    }
+}
+
+// Quads bound in group 0, the scene in group 1
+fn create_layout(
+    gfx: &Gfx,
+    quads: &BindGroupLayout,
+    scene: &BindGroupLayout,
+    push: &PushConstants<DrawConstants>,
+) -> Arc<PipelineLayout> {
+    push.pipeline_layout(gfx, &[quads, scene])
+}
+
+// Quads bound in group 0 alone, for depth-only passes drawn without the scene
+fn create_depth_layout(
+    gfx: &Gfx,
+    quads: &BindGroupLayout,
+    push: &PushConstants<DrawConstants>,
+) -> Arc<PipelineLayout> {
+    push.pipeline_layout(gfx, &[quads])
+}
+
+// Back faces culled, as quads only face one way
+fn quad_primitive(polygon_mode: PolygonMode) -> PrimitiveState {
+    PrimitiveState {
+        topology: PrimitiveTopology::TriangleStrip,
+        front_face: FrontFace::Ccw,
+        cull_mode: Some(Face::Back),
+        polygon_mode,
+        ..PrimitiveState::default()
+    }
+}
+
+const ADDITIVE: BlendState = BlendState {
+    color: BlendComponent {
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    },
+    alpha: BlendComponent::REPLACE,
+};
+
+// What post passes read depth from, as drawn with one or several samples
+const DEPTH_SOURCE: &str = "
+@group(0) @binding(0) var depth: texture_depth_2d;
+
+fn load_depth(xy: vec2<i32>) -> f32 {
+    return textureLoad(depth, xy, 0);
+}
+";
+
+const MULTISAMPLED_DEPTH_SOURCE: &str = "
+@group(0) @binding(0) var depth: texture_depth_multisampled_2d;
+
+fn load_depth(xy: vec2<i32>) -> f32 {
+    return textureLoad(depth, xy, 0);
+}
+";
+
+// Post pass shader reading the depth of `hdr_targets` through `load_depth`,
+// pasted in front of `source`, along with how to bind it first in group 0.
+// Which of the two depends on the sample count, so passes built on it have
+// to be built again after the sample count changes
+fn depth_module(gfx: &Gfx, label: &str, source: &str) -> (ShaderModule, BindingType) {
+    let multisampled = gfx.sample_count() > 1;
+
+    let depth = BindingType::Texture {
+        sample_type: TextureSampleType::Depth,
+        view_dimension: TextureViewDimension::D2,
+        multisampled,
+    };
+
+    let depth_source = match multisampled {
+        true => MULTISAMPLED_DEPTH_SOURCE,
+        false => DEPTH_SOURCE,
+    };
+
+    let descriptor = ShaderModuleDescriptor {
+        label: Some(label),
+        source: ShaderSource::Wgsl(Cow::Owned([depth_source, source].concat())),
+    };
+
+    (gfx.device.create_shader_module(descriptor), depth)
+}
+
+// Constants of the pass labeled `pass`, bound in `group` if falling back
+fn push_constants<T: Pod>(
+    gfx: &Gfx,
+    pass: &'static str,
+    stages: ShaderStages,
+    group: u32,
+) -> Result<PushConstants<T>, RendererError> {
+    let push = PushConstants::new(gfx, stages, group);
+    push.map_err(|err| RendererError::PushConstants(pass, err))
+}
+
+// Axes and flags of a facing, as quad.wgsl reads them in `Draw`
+pub fn facing_axes(facing: Facing) -> [u32; 4] {
+    let [u, v, depth] = facing.axes().map(|axis| axis as u32);
+    let (nx, ny, nz) = facing.normal();
+    let front = nx + ny + nz > 0;
+    let backwards = facing.winding() != [(0, 0), (1, 0), (1, 1), (0, 1)];
+    [u, v, depth, front as u32 | (backwards as u32) << 1]
+}
+
+#[derive(Debug)]
+pub enum RendererError {
+    // Constants of the pass labeled so had no room on the device
+    PushConstants(&'static str, PushConstantsError),
+}
+
+impl Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PushConstants(pass, err) => write!(f, "cannot build the {pass} pass: {err}"),
+        }
+    }
+}
+
+impl Error for RendererError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::PushConstants(_, err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use bytemuck::Pod;
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, ColorTargetState, MultisampleState,
+    PrimitiveState, RenderPass, RenderPipeline, Sampler,
+};
+
+use crate::gfx::{Gfx, PushConstants, Pushed, RenderNode, RenderState, Slot, Views};
+
+// Pipeline state of a full-screen pass: a single triangle out of `vs_main`
+// covering the whole target, shaded by `fragment` with no depth test
+pub fn state(
+    label: &'static str,
+    fragment: &'static str,
+    target: ColorTargetState,
+    multisample: MultisampleState,
+) -> RenderState {
+    RenderState {
+        label,
+        vertex: "vs_main",
+        fragment: Some(fragment),
+        targets: vec![Some(target)],
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample,
+    }
+}
+
+// A full-screen pass, as post passes are drawn. Textures it reads are bound
+// in group 0 in order, followed by a sampler if it takes one
+pub struct FullscreenNode<'a, T = ()> {
+    label: &'static str,
+    pipeline: Arc<RenderPipeline>,
+    reads: Option<(&'a BindGroupLayout, Vec<Slot>, Option<&'a Sampler>)>,
+    constants: Option<(&'a PushConstants<T>, Pushed<T>)>,
+    group: Option<BindGroup>,
+}
+
+impl<'a, T: Pod> FullscreenNode<'a, T> {
+    pub fn new(label: &'static str, pipeline: Arc<RenderPipeline>) -> Self {
+        Self {
+            label,
+            pipeline,
+            reads: None,
+            constants: None,
+            group: None,
+        }
+    }
+
+    // Textures bound as `layout` lays them out, followed by `sampler`, if any
+    pub fn reads(
+        mut self,
+        layout: &'a BindGroupLayout,
+        reads: impl Into<Vec<Slot>>,
+        sampler: Option<&'a Sampler>,
+    ) -> Self {
+        self.reads = Some((layout, reads.into(), sampler));
+        self
+    }
+
+    pub fn constants(mut self, gfx: &Gfx, push: &'a PushConstants<T>, constants: T) -> Self {
+        self.constants = Some((push, push.write(gfx, constants)));
+        self
+    }
+}
+
+impl<T: Pod> RenderNode for FullscreenNode<'_, T> {
+    fn prepare(&mut self, gfx: &Gfx, views: &Views) {
+        if let Some((layout, reads, sampler)) = &self.reads {
+            let view = |&slot: &Slot| BindingResource::TextureView(&views[slot]);
+            let resources = reads.iter().map(view);
+            let resources = resources.chain(sampler.map(BindingResource::Sampler));
+            self.group = Some(gfx.bind_group(self.label, layout, resources));
+        }
+    }
+
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+
+        if self.reads.is_some() {
+            let group = self.group.as_ref();
+            let group = group.unwrap_or_else(|| panic!("{} not prepared", self.label));
+            pass.set_bind_group(0, group, &[]);
+        }
+
+        if let Some((push, pushed)) = &self.constants {
+            push.set(pass, pushed);
+        }
+
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use wgpu::{
+    include_wgsl, AddressMode, BindGroupLayout, Color, ColorTargetState, ColorWrites, FilterMode,
+    LoadOp, MultisampleState, PipelineLayout, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderModule, ShaderStages, TextureSampleType,
+};
+
+use super::fullscreen::{self, FullscreenNode};
+use crate::gfx::{Bindings, FrameTargets, Gfx, Graph, Slot, Transient};
+
+// Fast approximate antialiasing, after Lottes. Edges are found by contrast
+// in the resolved scene and blurred along, which catches the shading
+// aliasing multisampling leaves in, though it softens textures a bit
+#[derive(Debug)]
+pub struct Fxaa {
+    module: ShaderModule,
+    sampler: Sampler,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+}
+
+impl Fxaa {
+    pub fn new(gfx: &Gfx) -> Self {
+        let scene = TextureSampleType::Float { filterable: true };
+        let bindings = Bindings::new(ShaderStages::FRAGMENT)
+            .texture(scene)
+            .sampler(SamplerBindingType::Filtering);
+
+        let descriptor = SamplerDescriptor {
+            label: Some("fxaa"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        };
+
+        let sampler = gfx.device.create_sampler(&descriptor);
+        let group_layout = bindings.layout(gfx);
+        let layout = gfx.pipeline_layout(&[&group_layout], &[]);
+        let module = gfx
+            .device
+            .create_shader_module(include_wgsl!("../fxaa.wgsl"));
+
+        Self {
+            module,
+            sampler,
+            group_layout,
+            layout,
+        }
+    }
+
+    // Smooth the edges in the color of `targets`, once resolved, into a new target
+    pub fn declare<'a>(&'a self, gfx: &Gfx, graph: &mut Graph<'a>, targets: FrameTargets) -> Slot {
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = fullscreen::state("fxaa", "fs_main", target, MultisampleState::default());
+        let pipeline = gfx.render_pipeline(&self.module, Some(&self.layout), &state);
+
+        let smoothed = graph.transient(Transient {
+            format: targets.format,
+            sample_count: 1,
+            size: targets.size,
+        });
+
+        let scene = targets.output();
+        let node: FullscreenNode = FullscreenNode::new("fxaa", pipeline);
+        let node = node.reads(&self.group_layout, [scene], Some(&self.sampler));
+
+        let clear = LoadOp::Clear(Color::BLACK);
+        let pass = graph.render("fxaa", node);
+        pass.read(scene).color(smoothed, clear);
+
+        smoothed
+    }
+}
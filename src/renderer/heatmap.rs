@@ -0,0 +1,194 @@
+use std::{mem, sync::Arc, time::Instant};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BlendState, Buffer, BufferDescriptor, BufferUsages,
+    ColorTargetState, ColorWrites, LoadOp, MultisampleState, PipelineLayout, PrimitiveState,
+    PrimitiveTopology, RenderPass, RenderPipeline, ShaderModule, ShaderStages, TextureFormat,
+};
+
+use super::RendererError;
+use crate::{
+    buddy::Buddy,
+    gfx::{Bindings, Gfx, Graph, PushConstants, Pushed, RenderNode, RenderState, Slot},
+};
+
+// Texels per row, rows being added as the buffer needs them
+const COLUMNS: u32 = 512;
+
+// Seconds for blocks to fade halfway from freshly allocated to old
+const FADE: f32 = 10.0;
+
+// Texels with no block in them, dimmed against the frame
+const FREE: [u8; 4] = [0, 0, 0, 160];
+
+// Matches `Heatmap` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct HeatmapConstants {
+    frame: [f32; 2],
+    cells: u32,
+    columns: u32,
+}
+
+// Occupancy of a buddy buffer over the bottom left corner of the frame,
+// a texel per block of the smallest order, in rows going up. Blocks handed out
+// are colored by the order they were handed out in, darkening as they age
+#[derive(Debug)]
+pub struct Heatmap {
+    module: ShaderModule,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<HeatmapConstants>,
+
+    // Written by `prepare`, a color per texel, `cells` of them
+    colors: Buffer,
+    cells: usize,
+    group: BindGroup,
+}
+
+impl Heatmap {
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let stages = ShaderStages::VERTEX | ShaderStages::FRAGMENT;
+        let push = super::push_constants(gfx, "heatmap", stages, 1)?;
+
+        let group_layout = Bindings::new(ShaderStages::FRAGMENT)
+            .storage(true)
+            .layout(gfx);
+
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let module = push.module(gfx, "heatmap", include_str!("../heatmap.wgsl"));
+
+        let colors = create_colors(gfx, 1);
+        let group = create_group(gfx, &group_layout, &colors);
+
+        Ok(Self {
+            module,
+            group_layout,
+            layout,
+            push,
+            colors,
+            cells: 0,
+            group,
+        })
+    }
+
+    // Color in the blocks `buddy` has handed out, to be drawn by the next `declare`
+    pub fn prepare<T: Pod>(&mut self, gfx: &Gfx, buddy: &Buddy<T>) {
+        let shift = buddy.min_order();
+        let cells = buddy.capacity() >> shift;
+        let mut colors = vec![FREE; cells];
+        let now = Instant::now();
+
+        for allocation in buddy.iter_allocations() {
+            let age = now.duration_since(allocation.since).as_secs_f32();
+            let color = color(allocation.serial, age);
+            let first = allocation.offset >> shift;
+            let last = (allocation.offset + allocation.len) >> shift;
+            colors[first..last].fill(color);
+        }
+
+        if cells != self.cells {
+            self.colors = create_colors(gfx, cells);
+            self.group = create_group(gfx, &self.group_layout, &self.colors);
+            self.cells = cells;
+        }
+
+        let blob = bytemuck::cast_slice(&colors);
+        gfx.queue.write_buffer(&self.colors, 0, blob);
+    }
+
+    // Draw what `prepare` colored in over `frame`, drawn in `format`.
+    // Nothing is declared before anything was prepared
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        frame: Slot,
+        format: TextureFormat,
+    ) {
+        if self.cells == 0 {
+            return;
+        }
+
+        let target = ColorTargetState {
+            format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "heatmap",
+            vertex: "vs_main",
+            fragment: Some("fs_main"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let (width, height) = (gfx.config.width, gfx.config.height);
+        let constants = HeatmapConstants {
+            frame: [width as f32, height as f32],
+            cells: self.cells as u32,
+            columns: COLUMNS.min(self.cells as u32),
+        };
+
+        let node = HeatmapNode {
+            heatmap: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.layout), &state),
+            constants: self.push.write(gfx, constants),
+        };
+
+        graph.render("heatmap", node).color(frame, LoadOp::Load);
+    }
+}
+
+struct HeatmapNode<'a> {
+    heatmap: &'a Heatmap,
+    pipeline: Arc<RenderPipeline>,
+    constants: Pushed<HeatmapConstants>,
+}
+
+impl RenderNode for HeatmapNode<'_> {
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.heatmap.group, &[]);
+        self.heatmap.push.set(pass, &self.constants);
+        pass.draw(0..4, 0..1);
+    }
+}
+
+// Hue told apart from those of the blocks handed out right before and after,
+// stepping around the color wheel by the golden ratio, and fading with `age`
+fn color(serial: u64, age: f32) -> [u8; 4] {
+    let hue = (serial as f64 * 0.618_033_988_749_895).fract() as f32 * 6.0;
+    let value = 0.3 + 0.7 * 0.5_f32.powf(age / FADE);
+
+    // Fully saturated, so each channel is a ramp of hue
+    let channel = |n: f32| {
+        let k = (n + hue) % 6.0;
+        let c = 1.0 - k.min(4.0 - k).clamp(0.0, 1.0);
+        (c * value * 255.0) as u8
+    };
+
+    [channel(5.0), channel(3.0), channel(1.0), 255]
+}
+
+fn create_colors(gfx: &Gfx, cells: usize) -> Buffer {
+    let descriptor = BufferDescriptor {
+        label: Some("heatmap"),
+        size: (cells * mem::size_of::<[u8; 4]>()) as u64,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+
+    gfx.device.create_buffer(&descriptor)
+}
+
+fn create_group(gfx: &Gfx, layout: &BindGroupLayout, colors: &Buffer) -> BindGroup {
+    gfx.bind_group("heatmap", layout, [colors.as_entire_binding()])
+}
@@ -0,0 +1,366 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, BindingType, ComputePass, ComputePipeline,
+    Extent3d, ShaderModuleDescriptor, ShaderSource, ShaderStages, StorageTextureAccess, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureSampleType, TextureUsages,
+    TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use super::{indirect::Indirect, RendererError};
+use crate::gfx::{
+    Bindings, ComputeNode, Gfx, Graph, MemoryReport, PushConstants, Pushed, Slot, Views,
+};
+
+const PYRAMID_FORMAT: TextureFormat = TextureFormat::R32Float;
+
+// What `reduce` in hiz.wgsl reads from, either the depth buffer or a pyramid level
+const DEPTH_SOURCE: &str = "
+@group(0) @binding(0) var source: texture_depth_2d;
+
+fn source_size() -> vec2<u32> {
+    return textureDimensions(source);
+}
+
+fn load(xy: vec2<u32>) -> f32 {
+    return textureLoad(source, xy, 0);
+}
+";
+
+const MULTISAMPLED_DEPTH_SOURCE: &str = "
+@group(0) @binding(0) var source: texture_depth_multisampled_2d;
+
+fn source_size() -> vec2<u32> {
+    return textureDimensions(source);
+}
+
+fn load(xy: vec2<u32>) -> f32 {
+    var farthest = 0.0;
+    for (var index = 0u; index < textureNumSamples(source); index++) {
+        farthest = max(farthest, textureLoad(source, xy, index));
+    }
+
+    return farthest;
+}
+";
+
+const LEVEL_SOURCE: &str = "
+@group(0) @binding(0) var source: texture_2d<f32>;
+
+fn source_size() -> vec2<u32> {
+    return textureDimensions(source);
+}
+
+fn load(xy: vec2<u32>) -> f32 {
+    return textureLoad(source, xy, 0).r;
+}
+";
+
+// Matches `Cull` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct CullConstants {
+    view_proj: [[f32; 4]; 4],
+    count: u32,
+    _padding: [u32; 3],
+}
+
+// Farthest depth of every texel at half the surface size, halved again at every level
+#[derive(Debug)]
+struct Pyramid {
+    texture: Texture,
+    view: TextureView,
+    levels: Vec<TextureView>,
+}
+
+// Hierarchical depth occlusion culling. The pyramid built from the depth
+// of one frame culls the indirect draws of the next, so chunks coming out
+// from behind something may show up a frame late
+#[derive(Debug)]
+pub struct HiZ {
+    seed: (Arc<ComputePipeline>, Arc<BindGroupLayout>),
+    reduce: (Arc<ComputePipeline>, Arc<BindGroupLayout>),
+    cull: (Arc<ComputePipeline>, Arc<BindGroupLayout>),
+    push: PushConstants<CullConstants>,
+    pyramid: Option<Pyramid>,
+
+    // Of the frame the pyramid was last built from
+    view_proj: Option<[[f32; 4]; 4]>,
+}
+
+impl HiZ {
+    // Whether the device can run the compute passes involved
+    pub fn is_supported(gfx: &Gfx) -> bool {
+        let push = PushConstants::<CullConstants>::new(gfx, ShaderStages::COMPUTE, 1);
+        gfx.device.limits().max_compute_invocations_per_workgroup >= 64 && push.is_ok()
+    }
+
+    // Seeding depends on the sample count, so build again after changing it
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let multisampled = gfx.sample_count() > 1;
+
+        let depth = BindingType::Texture {
+            sample_type: TextureSampleType::Depth,
+            view_dimension: TextureViewDimension::D2,
+            multisampled,
+        };
+
+        let destination = BindingType::StorageTexture {
+            access: StorageTextureAccess::WriteOnly,
+            format: PYRAMID_FORMAT,
+            view_dimension: TextureViewDimension::D2,
+        };
+
+        let level = TextureSampleType::Float { filterable: false };
+        let compute = || Bindings::new(ShaderStages::COMPUTE);
+
+        let depth_source = match multisampled {
+            true => MULTISAMPLED_DEPTH_SOURCE,
+            false => DEPTH_SOURCE,
+        };
+
+        let hiz = include_str!("../hiz.wgsl");
+        let (seed, reduce) = ([depth_source, hiz], [LEVEL_SOURCE, hiz]);
+        let bindings = compute().with(depth).with(destination);
+        let seed = create_pipeline(gfx, &seed, "reduce", &bindings, None);
+        let bindings = compute().texture(level).with(destination);
+        let reduce = create_pipeline(gfx, &reduce, "reduce", &bindings, None);
+
+        let push = super::push_constants(gfx, "cull", ShaderStages::COMPUTE, 1)?;
+        let cull = [include_str!("../cull.wgsl")];
+        let bindings = compute().storage(true).storage(false).texture(level);
+        let cull = create_pipeline(gfx, &cull, "cull_chunks", &bindings, Some(&push));
+
+        Ok(Self {
+            seed,
+            reduce,
+            cull,
+            push,
+            pyramid: None,
+            view_proj: None,
+        })
+    }
+
+    // Get the pyramid ready for a frame drawn with `view_proj`, returning
+    // what the pyramid left by the previous frame was built with, if it is still around
+    pub fn prepare(&mut self, gfx: &Gfx, view_proj: [[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+        let size = pyramid_size(gfx);
+        let built = self.view_proj.replace(view_proj);
+
+        let pyramid = self.pyramid.as_ref();
+        if pyramid.is_some_and(|pyramid| pyramid.texture.size() == size) {
+            return built;
+        }
+
+        self.pyramid = Some(create_pyramid(gfx, size));
+        None
+    }
+
+    // Zero the instance count of packed chunks hidden from `view_proj`,
+    // the one `prepare` returned
+    pub fn cull<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        indirect: &Indirect,
+        view_proj: [[f32; 4]; 4],
+    ) {
+        let pyramid = self.pyramid.as_ref().expect("pyramid not prepared");
+
+        if indirect.count() == 0 {
+            return;
+        }
+
+        let (pipeline, layout) = &self.cull;
+        let resources = [
+            indirect.chunks().as_entire_binding(),
+            indirect.args().as_entire_binding(),
+            BindingResource::TextureView(&pyramid.view),
+        ];
+
+        let constants = CullConstants {
+            view_proj,
+            count: indirect.count(),
+            _padding: [0; 3],
+        };
+
+        let node = CullNode {
+            pipeline,
+            push: &self.push,
+            group: gfx.bind_group("cull", layout, resources),
+            count: constants.count,
+            constants: self.push.write(gfx, constants),
+        };
+
+        let pyramid = graph.import(&pyramid.view);
+        graph.compute("cull", node).read(pyramid);
+    }
+
+    // Build the pyramid out of `depth` once drawn, for the next frame to cull against
+    pub fn build<'a>(&'a self, graph: &mut Graph<'a>, depth: Slot) {
+        let pyramid = self.pyramid.as_ref().expect("pyramid not prepared");
+
+        let node = BuildNode {
+            hiz: self,
+            pyramid,
+            depth,
+            groups: Vec::new(),
+        };
+
+        let destination = graph.import(&pyramid.view);
+        graph.compute("hiz", node).read(depth).write(destination);
+    }
+
+    pub fn report_memory(&self, report: &mut MemoryReport) {
+        if let Some(pyramid) = &self.pyramid {
+            report.add_texture("hiz", &pyramid.texture);
+        }
+    }
+}
+
+struct CullNode<'a> {
+    pipeline: &'a ComputePipeline,
+    push: &'a PushConstants<CullConstants>,
+    group: BindGroup,
+
+    // Chunks to cull, one invocation each
+    count: u32,
+    constants: Pushed<CullConstants>,
+}
+
+impl ComputeNode for CullNode<'_> {
+    fn record<'p>(&'p self, pass: &mut ComputePass<'p>) {
+        pass.set_pipeline(self.pipeline);
+        pass.set_bind_group(0, &self.group, &[]);
+        self.push.set_compute(pass, &self.constants);
+        pass.dispatch_workgroups(self.count.div_ceil(64), 1, 1);
+    }
+}
+
+struct BuildNode<'a> {
+    hiz: &'a HiZ,
+    pyramid: &'a Pyramid,
+    depth: Slot,
+
+    // A level each, the first one read from depth
+    groups: Vec<BindGroup>,
+}
+
+impl BuildNode<'_> {
+    fn stage(&self, level: usize) -> &(Arc<ComputePipeline>, Arc<BindGroupLayout>) {
+        match level {
+            0 => &self.hiz.seed,
+            _ => &self.hiz.reduce,
+        }
+    }
+}
+
+impl ComputeNode for BuildNode<'_> {
+    fn prepare(&mut self, gfx: &Gfx, views: &Views) {
+        let levels = &self.pyramid.levels;
+        let sources = [&views[self.depth]].into_iter().chain(levels);
+
+        // Every level is read right after being written
+        self.groups = sources
+            .zip(levels)
+            .enumerate()
+            .map(|(level, (source, destination))| {
+                let (_, layout) = self.stage(level);
+                let resources = [
+                    BindingResource::TextureView(source),
+                    BindingResource::TextureView(destination),
+                ];
+
+                gfx.bind_group("hiz", layout, resources)
+            })
+            .collect();
+    }
+
+    fn record<'p>(&'p self, pass: &mut ComputePass<'p>) {
+        let size = self.pyramid.texture.size();
+
+        for (level, group) in self.groups.iter().enumerate() {
+            let (pipeline, _) = self.stage(level);
+            let (width, height) = (size.width >> level, size.height >> level);
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, group, &[]);
+            pass.dispatch_workgroups(width.max(1).div_ceil(8), height.max(1).div_ceil(8), 1);
+        }
+    }
+}
+
+fn pyramid_size(gfx: &Gfx) -> Extent3d {
+    let size = gfx.render_size();
+
+    Extent3d {
+        width: (size.width / 2).max(1),
+        height: (size.height / 2).max(1),
+        depth_or_array_layers: 1,
+    }
+}
+
+fn create_pyramid(gfx: &Gfx, size: Extent3d) -> Pyramid {
+    let mip_level_count = size.max_mips(TextureDimension::D2);
+
+    let descriptor = TextureDescriptor {
+        label: Some("hiz"),
+        size,
+        mip_level_count,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: PYRAMID_FORMAT,
+        usage: TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    };
+
+    let texture = gfx.device.create_texture(&descriptor);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+
+    let levels = (0..mip_level_count)
+        .map(|level| {
+            let descriptor = TextureViewDescriptor {
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..TextureViewDescriptor::default()
+            };
+
+            texture.create_view(&descriptor)
+        })
+        .collect();
+
+    Pyramid {
+        texture,
+        view,
+        levels,
+    }
+}
+
+// Compute pipeline out of WGSL `sources` pasted together, `bindings` making up group 0
+fn create_pipeline(
+    gfx: &Gfx,
+    sources: &[&str],
+    entry_point: &'static str,
+    bindings: &Bindings,
+    push: Option<&PushConstants<CullConstants>>,
+) -> (Arc<ComputePipeline>, Arc<BindGroupLayout>) {
+    let bind_group_layout = bindings.layout(gfx);
+    let source = sources.concat();
+
+    let (layout, source) = match push {
+        Some(push) => {
+            let layout = push.pipeline_layout(gfx, &[&bind_group_layout]);
+            (layout, push.source(&source).into_owned())
+        }
+        None => (gfx.pipeline_layout(&[&bind_group_layout], &[]), source),
+    };
+
+    let descriptor = ShaderModuleDescriptor {
+        label: Some(entry_point),
+        source: ShaderSource::Wgsl(source.into()),
+    };
+
+    let module = gfx.device.create_shader_module(descriptor);
+    let pipeline = gfx.compute_pipeline(&module, Some(&layout), entry_point);
+    (pipeline, bind_group_layout)
+}
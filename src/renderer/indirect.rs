@@ -0,0 +1,247 @@
+use std::{iter, mem, num::NonZeroU64, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    util::DrawIndirectArgs, BindGroup, BindGroupLayout, BindingResource, Buffer, BufferBinding,
+    BufferDescriptor, BufferUsages, RenderPass, ShaderStages,
+};
+
+use super::{facing_axes, ChunkDraw, LodDraw};
+use crate::{
+    buddy::Buddy,
+    gfx::{Bindings, Gfx},
+    mesh::{Facing, QuadRef},
+};
+
+// Matches `Chunk` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ChunkRecord {
+    origin: [f32; 3],
+    first: u32,
+
+    // End of every facing, relative to `first`, the last one ending the chunk
+    ends: [u32; 6],
+    palette: u32,
+
+    // Level of detail in the lowest byte, levels the chunk has above it
+    lod: u32,
+}
+
+// Sorts after every chunk in use, so the shader never picks it
+const UNUSED: ChunkRecord = ChunkRecord {
+    origin: [0.0; 3],
+    first: u32::MAX,
+    ends: [0; 6],
+    palette: 0,
+    lod: 0,
+};
+
+const ARGS_STRIDE: u64 = mem::size_of::<DrawIndirectArgs>() as u64;
+const RECORD_STRIDE: u64 = mem::size_of::<ChunkRecord>() as u64;
+
+// Chunk draws packed into buffers for indirect drawing, a draw per level of detail
+// of every chunk. Quads are indexed by their offset into the whole buddy buffer,
+// and the shader looks up which chunk and facing each one belongs to
+#[derive(Debug)]
+pub struct Indirect {
+    pub layout: Arc<BindGroupLayout>,
+    group: BindGroup,
+    facings: Buffer,
+    args: Buffer,
+    chunks: Buffer,
+
+    // Chunks the buffers have room for, and how many are packed
+    capacity: usize,
+    count: u32,
+
+    // Bytes of the quad buffer the binding covers, from its start
+    window: u64,
+}
+
+impl Indirect {
+    pub fn new(gfx: &Gfx, quads: &Buddy<QuadRef>, capacity: usize) -> Self {
+        let bindings = Bindings::new(ShaderStages::VERTEX)
+            .storage(true)
+            .storage(true)
+            .uniform();
+
+        let layout = bindings.layout(gfx);
+
+        let axes = Facing::ALL.map(facing_axes);
+        let size = mem::size_of_val(&axes) as u64;
+        let facings = create_buffer(gfx, "facings", size, BufferUsages::UNIFORM);
+        let blob = bytemuck::cast_slice(&axes);
+        gfx.queue.write_buffer(&facings, 0, blob);
+
+        // A single binding may not reach the whole buffer
+        let limit = gfx.device.limits().max_storage_buffer_binding_size as u64;
+        let stride = mem::size_of::<QuadRef>() as u64;
+        let window = quads.buffer().size().min(limit / stride * stride);
+
+        let capacity = capacity.max(1);
+        let (args, chunks) = create_draw_buffers(gfx, capacity);
+        let group = create_group(gfx, &layout, quads, window, &chunks, &facings);
+
+        Self {
+            layout,
+            group,
+            facings,
+            args,
+            chunks,
+            capacity,
+            count: 0,
+            window,
+        }
+    }
+
+    // Pack a draw per level of detail of every chunk into the buffers, growing them
+    // if needed. Only the full level gets drawn until levels are picked on the GPU.
+    // Meshes lying past the reach of the binding or still staged are skipped,
+    // along with every coarser level of the chunk
+    pub fn pack(&mut self, gfx: &Gfx, quads: &Buddy<QuadRef>, chunks: &[ChunkDraw]) {
+        let stride = mem::size_of::<QuadRef>() as u64;
+        let mut records = Vec::with_capacity(chunks.len());
+
+        for chunk in chunks {
+            let full = LodDraw {
+                handle: chunk.handle,
+                facings: chunk.facings,
+            };
+
+            let levels: Vec<_> = iter::once(Some(full))
+                .chain(chunk.lods)
+                .map_while(|level| {
+                    let LodDraw { handle, facings } = level?;
+                    let first = quads.offset(handle) as u64;
+                    let end = first + facings[5].end as u64;
+                    let fits = facings[5].end > 0 && end * stride <= self.window;
+                    (fits && !quads.is_staged(handle)).then_some((first as u32, facings))
+                })
+                .collect();
+
+            for (level, &(first, facings)) in levels.iter().enumerate() {
+                records.push(ChunkRecord {
+                    origin: chunk.origin,
+                    first,
+                    ends: facings.clone().map(|range| range.end),
+                    palette: chunk.palette,
+                    lod: level as u32 | (levels.len() as u32) << 8,
+                });
+            }
+        }
+
+        // The shader finds the chunk of a quad by binary search
+        records.sort_unstable_by_key(|record| record.first);
+
+        let mut args = Vec::with_capacity(records.len() * ARGS_STRIDE as usize);
+        for record in &records {
+            let draw = DrawIndirectArgs {
+                vertex_count: 4,
+                instance_count: match record.lod & 0xFF {
+                    0 => record.ends[5],
+                    _ => 0,
+                },
+                first_vertex: 0,
+                first_instance: record.first,
+            };
+
+            args.extend_from_slice(draw.as_bytes());
+        }
+
+        self.count = records.len() as u32;
+
+        if records.len() > self.capacity {
+            self.capacity = records.len().next_power_of_two();
+            (self.args, self.chunks) = create_draw_buffers(gfx, self.capacity);
+            self.group = create_group(
+                gfx,
+                &self.layout,
+                quads,
+                self.window,
+                &self.chunks,
+                &self.facings,
+            );
+        }
+
+        records.resize(self.capacity, UNUSED);
+        gfx.queue.write_buffer(&self.args, 0, &args);
+        let blob = bytemuck::cast_slice(&records);
+        gfx.queue.write_buffer(&self.chunks, 0, blob);
+    }
+
+    // Draw arguments and chunk records, as many as packed last
+    pub const fn args(&self) -> &Buffer {
+        &self.args
+    }
+
+    pub const fn chunks(&self) -> &Buffer {
+        &self.chunks
+    }
+
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    // Draw whatever was packed last, with the indirect pipeline set.
+    // Without `multi`, chunks are drawn one `draw_indirect` at a time
+    pub fn draw<'a>(&'a self, pass: &mut RenderPass<'a>, multi: bool) {
+        if self.count == 0 {
+            return;
+        }
+
+        pass.set_bind_group(0, &self.group, &[]);
+
+        if multi {
+            pass.multi_draw_indirect(&self.args, 0, self.count);
+            return;
+        }
+
+        for draw in 0..self.count as u64 {
+            pass.draw_indirect(&self.args, draw * ARGS_STRIDE);
+        }
+    }
+}
+
+fn create_buffer(gfx: &Gfx, label: &str, size: u64, usage: BufferUsages) -> Buffer {
+    let descriptor = BufferDescriptor {
+        label: Some(label),
+        size,
+        usage: usage | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+
+    gfx.device.create_buffer(&descriptor)
+}
+
+fn create_draw_buffers(gfx: &Gfx, capacity: usize) -> (Buffer, Buffer) {
+    let capacity = capacity as u64;
+    let (args_size, chunks_size) = (ARGS_STRIDE * capacity, RECORD_STRIDE * capacity);
+    let usage = BufferUsages::INDIRECT | BufferUsages::STORAGE;
+    let args = create_buffer(gfx, "indirect args", args_size, usage);
+    let chunks = create_buffer(gfx, "chunks", chunks_size, BufferUsages::STORAGE);
+    (args, chunks)
+}
+
+fn create_group(
+    gfx: &Gfx,
+    layout: &BindGroupLayout,
+    quads: &Buddy<QuadRef>,
+    window: u64,
+    chunks: &Buffer,
+    facings: &Buffer,
+) -> BindGroup {
+    let quads = BufferBinding {
+        buffer: quads.buffer(),
+        offset: 0,
+        size: NonZeroU64::new(window),
+    };
+
+    let resources = [
+        BindingResource::Buffer(quads),
+        chunks.as_entire_binding(),
+        facings.as_entire_binding(),
+    ];
+
+    gfx.bind_group("indirect quads", layout, resources)
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BindGroup, BindGroupLayout, ComputePass, ComputePipeline, ShaderStages};
+
+use super::{indirect::Indirect, RendererError};
+use crate::gfx::{Bindings, ComputeNode, Gfx, Graph, PushConstants, Pushed};
+
+// Matches `Lod` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct LodConstants {
+    eye: [f32; 3],
+    count: u32,
+    distance: f32,
+    _padding: [u32; 3],
+}
+
+// Picks which level of detail every chunk packed for indirect drawing gets drawn at,
+// right on the GPU, by writing the instance counts of its draws. Without it,
+// every chunk gets drawn in full detail, as packed
+#[derive(Debug)]
+pub struct Lods {
+    pipeline: Arc<ComputePipeline>,
+    layout: Arc<BindGroupLayout>,
+    push: PushConstants<LodConstants>,
+}
+
+impl Lods {
+    // Whether the device can run the compute pass involved
+    pub fn is_supported(gfx: &Gfx) -> bool {
+        let push = PushConstants::<LodConstants>::new(gfx, ShaderStages::COMPUTE, 1);
+        gfx.device.limits().max_compute_invocations_per_workgroup >= 64 && push.is_ok()
+    }
+
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "lod", ShaderStages::COMPUTE, 1)?;
+
+        let bindings = Bindings::new(ShaderStages::COMPUTE)
+            .storage(true)
+            .storage(false);
+
+        let layout = bindings.layout(gfx);
+        let pipeline_layout = push.pipeline_layout(gfx, &[&layout]);
+        let module = push.module(gfx, "lod", include_str!("../lod.wgsl"));
+        let pipeline = gfx.compute_pipeline(&module, Some(&pipeline_layout), "pick_lods");
+
+        Ok(Self {
+            pipeline,
+            layout,
+            push,
+        })
+    }
+
+    // Draw every chunk packed at the level `eye` is close enough for,
+    // a level coarser every `distance` blocks away, as far as it has levels
+    pub fn pick<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        indirect: &Indirect,
+        eye: [f32; 3],
+        distance: f32,
+    ) {
+        if indirect.count() == 0 {
+            return;
+        }
+
+        let resources = [
+            indirect.chunks().as_entire_binding(),
+            indirect.args().as_entire_binding(),
+        ];
+
+        let constants = LodConstants {
+            eye,
+            count: indirect.count(),
+            distance,
+            _padding: [0; 3],
+        };
+
+        let node = LodNode {
+            lods: self,
+            group: gfx.bind_group("lod", &self.layout, resources),
+            count: constants.count,
+            constants: self.push.write(gfx, constants),
+        };
+
+        graph.compute("lod", node);
+    }
+}
+
+struct LodNode<'a> {
+    lods: &'a Lods,
+    group: BindGroup,
+
+    // Chunks to pick levels for, one invocation each
+    count: u32,
+    constants: Pushed<LodConstants>,
+}
+
+impl ComputeNode for LodNode<'_> {
+    fn record<'p>(&'p self, pass: &mut ComputePass<'p>) {
+        pass.set_pipeline(&self.lods.pipeline);
+        pass.set_bind_group(0, &self.group, &[]);
+        self.lods.push.set_compute(pass, &self.constants);
+        pass.dispatch_workgroups(self.count.div_ceil(64), 1, 1);
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::HashMap, mem, ops::Range, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, ColorTargetState, ColorWrites, CompareFunction, DepthStencilState, Face, LoadOp,
+    PipelineLayout, PrimitiveState, RenderPass, RenderPipeline, ShaderModule, ShaderStages,
+};
+
+use super::RendererError;
+use crate::{
+    buddy::{Buddy, Handle},
+    color::LinearColor,
+    gfx::{
+        Bindings, FrameTargets, Gfx, Graph, MemoryReport, PushConstants, Pushed, RenderNode,
+        RenderState, DEPTH_FORMAT,
+    },
+};
+
+// Matches `Draw` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct ModelConstants {
+    view_proj: [[f32; 4]; 4],
+    position: [f32; 4],
+}
+
+// Matches `Vertex` in the shader, with its color sRGB-encoded in the low three bytes
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub color: u32,
+}
+
+// Vertices the pool has room for, enough for a few hundred cubes
+const POOL_SIZE: usize = 1 << 14;
+
+// Smallest block handed out, about what a cube takes
+const MIN_ORDER: u8 = 5;
+
+// A model drawn with its origin at `position`, by the id it was loaded with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityDraw {
+    pub model: u32,
+    pub position: [f32; 3],
+}
+
+// Models of entities, lists of triangles living in a buddy pool of their own,
+// apart from chunk quads so entities coming and going never crowd out terrain.
+// They are loaded by ids of the caller's choosing, to be loaded again
+// by the same ids whenever the renderer is built again
+#[derive(Debug)]
+pub struct Models {
+    module: ShaderModule,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<ModelConstants>,
+    pool: Buddy<ModelVertex>,
+    group: BindGroup,
+    loaded: HashMap<u32, Handle<ModelVertex>>,
+}
+
+impl Models {
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "models", ShaderStages::VERTEX, 1)?;
+
+        let pool = Buddy::new(gfx, POOL_SIZE, MIN_ORDER);
+        let bindings = Bindings::new(ShaderStages::VERTEX).storage(true);
+        let (group_layout, group) = bindings.create(gfx, "models", [pool.as_binding()]);
+
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let module = push.module(gfx, "models", include_str!("../model.wgsl"));
+
+        Ok(Self {
+            module,
+            layout,
+            push,
+            pool,
+            group,
+            loaded: HashMap::new(),
+        })
+    }
+
+    // Load `vertices` as `model`, in place of whatever was loaded as it before.
+    // False if they do not fit, leaving the model unloaded
+    pub fn load(&mut self, gfx: &Gfx, model: u32, vertices: &[ModelVertex]) -> bool {
+        self.unload(model);
+
+        let Some((handle, _)) = self.pool.load(gfx, vertices) else {
+            return false;
+        };
+
+        self.loaded.insert(model, handle);
+        true
+    }
+
+    pub fn unload(&mut self, model: u32) {
+        if let Some(handle) = self.loaded.remove(&model) {
+            self.pool.free(handle);
+        }
+    }
+
+    pub fn report_memory(&self, report: &mut MemoryReport) {
+        self.pool.report_memory(report, "models");
+    }
+
+    // Draw every entity over the color and depth of `targets`, as opaque as chunks.
+    // Entities whose model is not loaded are left out
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        view_proj: [[f32; 4]; 4],
+        entities: &[EntityDraw],
+    ) {
+        let draws: Vec<_> = entities
+            .iter()
+            .filter_map(|entity| {
+                let handle = self.loaded.get(&entity.model)?;
+                let first = self.pool.offset(handle) as u32;
+                let vertices = first..first + self.pool.len(handle) as u32;
+
+                let [x, y, z] = entity.position;
+                let constants = ModelConstants {
+                    view_proj,
+                    position: [x, y, z, 0.0],
+                };
+
+                Some((vertices, self.push.write(gfx, constants)))
+            })
+            .collect();
+
+        if draws.is_empty() {
+            return;
+        }
+
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = RenderState {
+            label: "models",
+            vertex: "vs_main",
+            fragment: Some("fs_main"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState {
+                cull_mode: Some(Face::Back),
+                ..PrimitiveState::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: gfx.multisample_state(),
+        };
+
+        let node = ModelNode {
+            models: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.layout), &state),
+            draws,
+        };
+
+        let pass = graph.render("entities", node);
+        pass.color_resolved(targets.color, targets.resolve, LoadOp::Load);
+        pass.depth(targets.depth, LoadOp::Load);
+    }
+}
+
+impl Drop for Models {
+    fn drop(&mut self) {
+        for (_, handle) in self.loaded.drain() {
+            self.pool.free(handle);
+        }
+    }
+}
+
+struct ModelNode<'a> {
+    models: &'a Models,
+    pipeline: Arc<RenderPipeline>,
+    draws: Vec<(Range<u32>, Pushed<ModelConstants>)>,
+}
+
+impl RenderNode for ModelNode<'_> {
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.models.group, &[]);
+
+        for (vertices, constants) in &self.draws {
+            self.models.push.set(pass, constants);
+            pass.draw(vertices.clone(), 0..1);
+        }
+    }
+}
+
+// A cube `size` blocks wide standing on its origin, its faces shaded as quads are:
+// tops brightest, sides a bit darker and bottoms darker still
+pub fn cube(size: f32, color: [u8; 3]) -> Vec<ModelVertex> {
+    let half = size / 2.0;
+    let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let mut vertices = Vec::with_capacity(36);
+
+    for (axis, normal) in axes.into_iter().enumerate() {
+        for sign in [1.0, -1.0] {
+            // Across the face, u × v pointing out of it so triangles wind counter-clockwise
+            let (mut u, mut v) = (axes[(axis + 1) % 3], axes[(axis + 2) % 3]);
+            if sign < 0.0 {
+                mem::swap(&mut u, &mut v);
+            }
+
+            let shade = match (axis, sign > 0.0) {
+                (1, true) => 1.0,
+                (1, false) => 0.5,
+                _ => 0.8,
+            };
+
+            let linear = LinearColor::from_srgb8(color).to_array();
+            let [r, g, b] = LinearColor::new(linear.map(|c| c * shade)).to_srgb8();
+            let color = u32::from_le_bytes([r, g, b, 255]);
+
+            // Corners from -1 to 1 along u and v
+            let corner = |[s, t]: [f32; 2]| {
+                let mut position: [f32; 3] =
+                    std::array::from_fn(|i| (normal[i] * sign + u[i] * s + v[i] * t) * half);
+                position[1] += half;
+                ModelVertex { position, color }
+            };
+
+            let corners = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+            let triangles = [0, 1, 2, 0, 2, 3].map(|index| corner(corners[index]));
+            vertices.extend(triangles);
+        }
+    }
+
+    vertices
+}
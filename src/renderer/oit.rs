@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use wgpu::{
+    include_wgsl, BindGroup, BindGroupLayout, BindingResource, BlendComponent, BlendFactor,
+    BlendOperation, BlendState, Color, ColorTargetState, ColorWrites, LoadOp, MultisampleState,
+    PipelineLayout, PrimitiveState, RenderPass, RenderPipeline, ShaderModule, ShaderStages,
+    TextureFormat, TextureSampleType,
+};
+
+use crate::gfx::{Bindings, Gfx, Graph, Pass, RenderNode, RenderState, Slot, Transient, Views};
+
+// Premultiplied color weighted by closeness, along with the weights
+const ACCUM_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+// Share of what lies behind still showing through
+const REVEALAGE_FORMAT: TextureFormat = TextureFormat::R8Unorm;
+
+// Accumulation targets of a frame, each resolved on its own if multisampled
+#[derive(Clone, Copy, Debug)]
+pub struct OitTargets {
+    accum: Slot,
+    revealage: Slot,
+    resolve: Option<(Slot, Slot)>,
+}
+
+impl OitTargets {
+    // Attach both to `pass`, cleared to nothing drawn
+    pub fn attach(&self, pass: &mut Pass) {
+        let (accum, revealage) = self.resolve.unzip();
+        pass.color_resolved(self.accum, accum, LoadOp::Clear(Color::TRANSPARENT));
+        pass.color_resolved(self.revealage, revealage, LoadOp::Clear(Color::WHITE));
+    }
+
+    // Attach both to `pass`, adding to whatever earlier passes drew into them
+    pub fn attach_loaded(&self, pass: &mut Pass) {
+        let (accum, revealage) = self.resolve.unzip();
+        pass.color_resolved(self.accum, accum, LoadOp::Load);
+        pass.color_resolved(self.revealage, revealage, LoadOp::Load);
+    }
+
+    fn outputs(&self) -> (Slot, Slot) {
+        self.resolve.unwrap_or((self.accum, self.revealage))
+    }
+}
+
+// Weighted blended order-independent transparency, after McGuire and Bavoil.
+// Translucent quads add up into targets in whatever order they are drawn,
+// to be composited over what lies behind them in a single pass
+#[derive(Debug)]
+pub struct Oit {
+    module: ShaderModule,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+}
+
+impl Oit {
+    pub fn new(gfx: &Gfx) -> Self {
+        let sample_type = TextureSampleType::Float { filterable: false };
+        let bindings = Bindings::new(ShaderStages::FRAGMENT)
+            .texture(sample_type)
+            .texture(sample_type);
+
+        let group_layout = bindings.layout(gfx);
+        let layout = gfx.pipeline_layout(&[&group_layout], &[]);
+        let module = gfx
+            .device
+            .create_shader_module(include_wgsl!("../oit.wgsl"));
+
+        Self {
+            module,
+            group_layout,
+            layout,
+        }
+    }
+
+    // Fresh targets to draw translucent quads into, sampled and sized like `hdr_targets`
+    pub fn targets(gfx: &Gfx, graph: &mut Graph) -> OitTargets {
+        let size = gfx.render_size();
+        let mut target = |format, sample_count| {
+            graph.transient(Transient {
+                format,
+                sample_count,
+                size,
+            })
+        };
+
+        let sample_count = gfx.sample_count();
+        let accum = target(ACCUM_FORMAT, sample_count);
+        let revealage = target(REVEALAGE_FORMAT, sample_count);
+
+        // Multisampled targets are only needed until resolved
+        let resolve = match sample_count {
+            1 => None,
+            _ => Some((target(ACCUM_FORMAT, 1), target(REVEALAGE_FORMAT, 1))),
+        };
+
+        OitTargets {
+            accum,
+            revealage,
+            resolve,
+        }
+    }
+
+    // Blending of translucent quads into `targets`, in the order they are attached:
+    // weighted colors add up, while revealage is scaled down by every coverage
+    pub fn color_targets() -> Vec<Option<ColorTargetState>> {
+        let add = BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        };
+
+        let reveal = BlendComponent {
+            src_factor: BlendFactor::Zero,
+            dst_factor: BlendFactor::OneMinusSrc,
+            operation: BlendOperation::Add,
+        };
+
+        let accum = ColorTargetState {
+            format: ACCUM_FORMAT,
+            blend: Some(BlendState {
+                color: add,
+                alpha: add,
+            }),
+            write_mask: ColorWrites::ALL,
+        };
+
+        let revealage = ColorTargetState {
+            format: REVEALAGE_FORMAT,
+            blend: Some(BlendState {
+                color: reveal,
+                alpha: reveal,
+            }),
+            write_mask: ColorWrites::ALL,
+        };
+
+        vec![Some(accum), Some(revealage)]
+    }
+
+    // Blend what `targets` accumulated over `output`, drawn in `format`
+    pub fn declare_composite<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: OitTargets,
+        output: Slot,
+        format: TextureFormat,
+    ) {
+        let target = ColorTargetState {
+            format,
+            blend: Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::SrcAlpha,
+                    dst_factor: BlendFactor::OneMinusSrcAlpha,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::REPLACE,
+            }),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "oit",
+            vertex: "vs_main",
+            fragment: Some("fs_main"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let (accum, revealage) = targets.outputs();
+        let node = CompositeNode {
+            oit: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.layout), &state),
+            accum,
+            revealage,
+            group: None,
+        };
+
+        let pass = graph.render("oit composite", node);
+        pass.read(accum).read(revealage);
+        pass.color(output, LoadOp::Load);
+    }
+}
+
+struct CompositeNode<'a> {
+    oit: &'a Oit,
+    pipeline: Arc<RenderPipeline>,
+    accum: Slot,
+    revealage: Slot,
+    group: Option<BindGroup>,
+}
+
+impl RenderNode for CompositeNode<'_> {
+    fn prepare(&mut self, gfx: &Gfx, views: &Views) {
+        let resources = [
+            BindingResource::TextureView(&views[self.accum]),
+            BindingResource::TextureView(&views[self.revealage]),
+        ];
+
+        let layout = &self.oit.group_layout;
+        self.group = Some(gfx.bind_group("oit", layout, resources));
+    }
+
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        let group = self.group.as_ref().expect("oit not prepared");
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,361 @@
+use std::{mem, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, BlendComponent, BlendFactor, BlendOperation,
+    BlendState, Buffer, BufferDescriptor, BufferUsages, ColorTargetState, ColorWrites, LoadOp,
+    MultisampleState, PipelineLayout, PrimitiveState, PrimitiveTopology, RenderPass,
+    RenderPipeline, ShaderModule, ShaderStages, TextureFormat,
+};
+
+use super::{ChunkOutline, ChunkStatus, RendererError};
+use crate::{
+    gfx::{
+        Bindings, FrameTargets, Gfx, Graph, PushConstants, Pushed, RenderNode, RenderState, Slot,
+        Views,
+    },
+    math::Frustum,
+};
+
+// Matches `Overlay` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct OverlayConstants {
+    view_proj: [[f32; 4]; 4],
+    block: [f32; 4],
+    frame: [f32; 2],
+    _padding: [f32; 2],
+}
+
+// Matches `Outline` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct OutlineInstance {
+    min: [f32; 3],
+    color: u32,
+    max: [f32; 3],
+    _padding: u32,
+}
+
+// Boxes there is room for at first, growing as needed
+const INITIAL_OUTLINES: usize = 256;
+
+// Chunk outlines by how far along chunks are, and for those culled
+const DIRTY: [u8; 3] = [255, 64, 64];
+const MESHING: [u8; 3] = [255, 208, 64];
+const UPLOADED: [u8; 3] = [64, 224, 96];
+const CULLED: [u8; 3] = [128, 128, 160];
+
+// One minus what lies below, so the crosshair shows over anything
+const INVERT: BlendState = BlendState {
+    color: BlendComponent {
+        src_factor: BlendFactor::OneMinusDst,
+        dst_factor: BlendFactor::Zero,
+        operation: BlendOperation::Add,
+    },
+    alpha: BlendComponent::REPLACE,
+};
+
+// Where blocks get edited: an outline around the selected block
+// and a crosshair at the center of the frame, aiming at it.
+// Chunks get outlined as well, for streaming and culling to be seen at work
+#[derive(Debug)]
+pub struct Overlay {
+    module: ShaderModule,
+    outline: (Arc<BindGroupLayout>, Arc<PipelineLayout>),
+    crosshair: Arc<PipelineLayout>,
+    push: PushConstants<OverlayConstants>,
+
+    // Boxes written by `prepare_outlines`, holding `len` out of `capacity`
+    chunks: (Arc<BindGroupLayout>, Arc<PipelineLayout>),
+    boxes: Buffer,
+    capacity: usize,
+    len: usize,
+    boxes_group: BindGroup,
+}
+
+impl Overlay {
+    // Built again after the sample count changes, see `depth_module`
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "overlay", ShaderStages::VERTEX, 2)?;
+
+        let source = push.source(include_str!("../overlay.wgsl"));
+        let (module, depth) = super::depth_module(gfx, "overlay", &source);
+
+        let group_layout = Bindings::new(ShaderStages::FRAGMENT)
+            .with(depth)
+            .layout(gfx);
+
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let crosshair = push.pipeline_layout(gfx, &[]);
+
+        let boxes_layout = Bindings::new(ShaderStages::VERTEX)
+            .storage(true)
+            .layout(gfx);
+
+        let layouts = [&*group_layout, &*boxes_layout];
+        let chunks = push.pipeline_layout(gfx, &layouts);
+        let boxes = create_boxes(gfx, INITIAL_OUTLINES);
+        let boxes_group = gfx.bind_group("outlines", &boxes_layout, [boxes.as_entire_binding()]);
+
+        Ok(Self {
+            module,
+            outline: (group_layout, layout),
+            crosshair,
+            push,
+            chunks: (boxes_layout, chunks),
+            boxes,
+            capacity: INITIAL_OUTLINES,
+            len: 0,
+            boxes_group,
+        })
+    }
+
+    // Box in the bounds of every chunk outlined, and fainter, the quads in it,
+    // to be drawn by the next `declare_chunks`. Uploaded chunks out of `frustum`
+    // are told apart as culled
+    pub fn prepare_outlines(&mut self, gfx: &Gfx, outlines: &[ChunkOutline], frustum: &Frustum) {
+        let mut instances = Vec::new();
+
+        for outline in outlines {
+            let (min, max) = (outline.origin, outline.origin.map(|c| c + 32.0));
+            let [r, g, b] = match outline.status {
+                ChunkStatus::Dirty => DIRTY,
+                ChunkStatus::Meshing => MESHING,
+                ChunkStatus::Uploaded if !frustum.intersects(min, max) => CULLED,
+                ChunkStatus::Uploaded => UPLOADED,
+            };
+
+            let color = |alpha: u8| u32::from_le_bytes([r, g, b, alpha]);
+            instances.push(OutlineInstance {
+                min,
+                color: color(255),
+                max,
+                _padding: 0,
+            });
+
+            if let Some((min, max)) = outline.quads {
+                instances.push(OutlineInstance {
+                    min,
+                    color: color(128),
+                    max,
+                    _padding: 0,
+                });
+            }
+        }
+
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.boxes = create_boxes(gfx, self.capacity);
+            let resources = [self.boxes.as_entire_binding()];
+            self.boxes_group = gfx.bind_group("outlines", &self.chunks.0, resources);
+        }
+
+        let blob = bytemuck::cast_slice(&instances);
+        gfx.queue.write_buffer(&self.boxes, 0, blob);
+        self.len = instances.len();
+    }
+
+    // Outline the block whose lowest corner is at `block` over the color of `targets`,
+    // once resolved, leaving out what lies behind their depth
+    pub fn declare_outline<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        view_proj: [[f32; 4]; 4],
+        block: [f32; 3],
+    ) {
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "outline",
+            vertex: "vs_outline",
+            fragment: Some("fs_outline"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::LineList,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let [x, y, z] = block;
+        let constants = OverlayConstants {
+            view_proj,
+            block: [x, y, z, 0.0],
+            ..OverlayConstants::zeroed()
+        };
+
+        let node = OverlayNode {
+            overlay: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.outline.1), &state),
+            depth: Some(targets.depth),
+            constants: self.push.write(gfx, constants),
+            vertices: 24,
+            outlines: false,
+            group: None,
+        };
+
+        let output = targets.output();
+        let pass = graph.render("outline", node);
+        pass.read(targets.depth).color(output, LoadOp::Load);
+    }
+
+    // Draw what `prepare_outlines` boxed in over the color of `targets`, once resolved,
+    // dimmed wherever it lies behind their depth
+    pub fn declare_chunks<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        view_proj: [[f32; 4]; 4],
+    ) {
+        if self.len == 0 {
+            return;
+        }
+
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "chunk outlines",
+            vertex: "vs_boxes",
+            fragment: Some("fs_boxes"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::LineList,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let constants = OverlayConstants {
+            view_proj,
+            ..OverlayConstants::zeroed()
+        };
+
+        let node = OverlayNode {
+            overlay: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.chunks.1), &state),
+            depth: Some(targets.depth),
+            constants: self.push.write(gfx, constants),
+            vertices: 24,
+            outlines: true,
+            group: None,
+        };
+
+        let output = targets.output();
+        let pass = graph.render("chunk outlines", node);
+        pass.read(targets.depth).color(output, LoadOp::Load);
+    }
+
+    // Crosshair at the center of `frame`, drawn in `format`
+    pub fn declare_crosshair<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        frame: Slot,
+        format: TextureFormat,
+    ) {
+        let target = ColorTargetState {
+            format,
+            blend: Some(INVERT),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "crosshair",
+            vertex: "vs_crosshair",
+            fragment: Some("fs_crosshair"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let (width, height) = (gfx.config.width, gfx.config.height);
+        let constants = OverlayConstants {
+            frame: [width as f32, height as f32],
+            ..OverlayConstants::zeroed()
+        };
+
+        let node = OverlayNode {
+            overlay: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.crosshair), &state),
+            depth: None,
+            constants: self.push.write(gfx, constants),
+            vertices: 12,
+            outlines: false,
+            group: None,
+        };
+
+        graph.render("crosshair", node).color(frame, LoadOp::Load);
+    }
+}
+
+struct OverlayNode<'a> {
+    overlay: &'a Overlay,
+    pipeline: Arc<RenderPipeline>,
+
+    // Bound for outlines to test against, unused by the crosshair
+    depth: Option<Slot>,
+    constants: Pushed<OverlayConstants>,
+    vertices: u32,
+    group: Option<BindGroup>,
+
+    // Drawing every box `prepare_outlines` wrote, rather than a single mark
+    outlines: bool,
+}
+
+impl RenderNode for OverlayNode<'_> {
+    fn prepare(&mut self, gfx: &Gfx, views: &Views) {
+        if let Some(depth) = self.depth {
+            let depth = BindingResource::TextureView(&views[depth]);
+            let layout = &self.overlay.outline.0;
+            self.group = Some(gfx.bind_group("outline", layout, [depth]));
+        }
+    }
+
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+
+        // Groups bound ahead of the constants, for the rest to be padded
+        let mut bound = 0;
+        if let Some(group) = &self.group {
+            pass.set_bind_group(0, group, &[]);
+            bound = 1;
+        }
+
+        let mut instances = 1;
+        if self.outlines {
+            pass.set_bind_group(1, &self.overlay.boxes_group, &[]);
+            instances = self.overlay.len as u32;
+            bound = 2;
+        }
+
+        self.overlay.push.pad(pass, bound);
+        self.overlay.push.set(pass, &self.constants);
+        pass.draw(0..self.vertices, 0..instances);
+    }
+}
+
+fn create_boxes(gfx: &Gfx, capacity: usize) -> Buffer {
+    let descriptor = BufferDescriptor {
+        label: Some("outlines"),
+        size: (capacity * mem::size_of::<OutlineInstance>()) as u64,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+
+    gfx.device.create_buffer(&descriptor)
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BindingResource, BindingType, Buffer, BufferDescriptor,
+    BufferUsages, ComputePass, ComputePipeline, ShaderStages, StorageTextureAccess,
+    TextureViewDimension,
+};
+
+use super::RendererError;
+use crate::{
+    camera::Camera,
+    gfx::{
+        Bindings, ComputeNode, Gfx, Graph, PushConstants, Pushed, Slot, Transient, Views,
+        HDR_FORMAT,
+    },
+    mesh::Chunk,
+};
+
+// Chunks there is room for at once
+const MAX_CHUNKS: usize = 16;
+
+// Block ids of a chunk, 2 to a word
+const WORDS_PER_CHUNK: usize = 32 * 32 * 32 / 2;
+
+// Matches `Raymarch` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct RaymarchConstants {
+    eye: [f32; 4],
+    forward: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    size: [u32; 2],
+    count: u32,
+    _padding: u32,
+}
+
+// Blocks traced a ray per pixel in a compute pass, out of the chunk data itself
+// instead of meshes, to check the blocks apart from the mesher
+#[derive(Debug)]
+pub struct Raymarch {
+    pipeline: Arc<ComputePipeline>,
+    group_layout: Arc<BindGroupLayout>,
+    push: PushConstants<RaymarchConstants>,
+
+    // Lowest corner of every chunk, then their blocks, in the same order
+    origins: Buffer,
+    blocks: Buffer,
+    count: u32,
+}
+
+impl Raymarch {
+    // Whether the device can run the compute pass involved
+    pub fn is_supported(gfx: &Gfx) -> bool {
+        let push = PushConstants::<RaymarchConstants>::new(gfx, ShaderStages::COMPUTE, 1);
+        gfx.device.limits().max_compute_invocations_per_workgroup >= 64 && push.is_ok()
+    }
+
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "raymarch", ShaderStages::COMPUTE, 1)?;
+
+        let output = BindingType::StorageTexture {
+            access: StorageTextureAccess::WriteOnly,
+            format: HDR_FORMAT,
+            view_dimension: TextureViewDimension::D2,
+        };
+
+        let group_layout = Bindings::new(ShaderStages::COMPUTE)
+            .storage(true)
+            .storage(true)
+            .with(output)
+            .layout(gfx);
+
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let module = push.module(gfx, "raymarch", include_str!("../raymarch.wgsl"));
+        let pipeline = gfx.compute_pipeline(&module, Some(&layout), "trace_blocks");
+
+        let origins = create_buffer(gfx, (MAX_CHUNKS * 16) as u64);
+        let blocks = create_buffer(gfx, (MAX_CHUNKS * WORDS_PER_CHUNK * 4) as u64);
+
+        Ok(Self {
+            pipeline,
+            group_layout,
+            push,
+            origins,
+            blocks,
+            count: 0,
+        })
+    }
+
+    // Blocks of `chunks`, each with its lowest corner, in place of those there were.
+    // Panics past `MAX_CHUNKS`
+    pub fn load(&mut self, gfx: &Gfx, chunks: &[([f32; 3], &Chunk)]) {
+        assert!(chunks.len() <= MAX_CHUNKS, "too many chunks to raymarch");
+
+        let origins: Vec<_> = chunks
+            .iter()
+            .map(|&([x, y, z], _)| [x, y, z, 0.0])
+            .collect();
+        gfx.queue
+            .write_buffer(&self.origins, 0, bytemuck::cast_slice(&origins));
+
+        for (index, (_, chunk)) in chunks.iter().enumerate() {
+            let offset = (index * WORDS_PER_CHUNK * 4) as u64;
+            gfx.queue
+                .write_buffer(&self.blocks, offset, bytemuck::bytes_of(*chunk));
+        }
+
+        self.count = chunks.len() as u32;
+    }
+
+    // Trace the loaded chunks as seen by `camera` into a new target, drawn
+    // at the size the scene would be, to be brought into the frame as the scene is
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        camera: &Camera,
+        aspect: f32,
+    ) -> Slot {
+        let size = gfx.render_size();
+        let output = graph.transient(Transient {
+            format: HDR_FORMAT,
+            sample_count: 1,
+            size,
+        });
+
+        let [forward, right, up] = camera.rays(aspect);
+        let extend = |[x, y, z]: [f32; 3]| [x, y, z, 0.0];
+
+        let constants = RaymarchConstants {
+            eye: extend(camera.eye),
+            forward: extend(forward),
+            right: extend(right),
+            up: extend(up),
+            size: [size.width, size.height],
+            count: self.count,
+            _padding: 0,
+        };
+
+        let node = RaymarchNode {
+            raymarch: self,
+            output,
+            group: None,
+            size: constants.size,
+            constants: self.push.write(gfx, constants),
+        };
+
+        graph.compute("raymarch", node).write(output);
+        output
+    }
+}
+
+struct RaymarchNode<'a> {
+    raymarch: &'a Raymarch,
+    output: Slot,
+    group: Option<BindGroup>,
+
+    // Texels traced along each side, one invocation each
+    size: [u32; 2],
+    constants: Pushed<RaymarchConstants>,
+}
+
+impl ComputeNode for RaymarchNode<'_> {
+    fn prepare(&mut self, gfx: &Gfx, views: &Views) {
+        let raymarch = self.raymarch;
+        let resources = [
+            raymarch.origins.as_entire_binding(),
+            raymarch.blocks.as_entire_binding(),
+            BindingResource::TextureView(&views[self.output]),
+        ];
+
+        let group = gfx.bind_group("raymarch", &raymarch.group_layout, resources);
+        self.group = Some(group);
+    }
+
+    fn record<'p>(&'p self, pass: &mut ComputePass<'p>) {
+        let group = self.group.as_ref().expect("raymarch not prepared");
+        let [width, height] = self.size;
+
+        pass.set_pipeline(&self.raymarch.pipeline);
+        pass.set_bind_group(0, group, &[]);
+        self.raymarch.push.set_compute(pass, &self.constants);
+        pass.dispatch_workgroups(width.div_ceil(8), height.div_ceil(8), 1);
+    }
+}
+
+fn create_buffer(gfx: &Gfx, size: u64) -> Buffer {
+    let descriptor = BufferDescriptor {
+        label: Some("raymarch"),
+        size,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+
+    gfx.device.create_buffer(&descriptor)
+}
@@ -0,0 +1,291 @@
+use std::sync::Arc;
+
+use wgpu::{
+    AddressMode, BindingType, CompareFunction, DepthBiasState, DepthStencilState, Extent3d,
+    FilterMode, PipelineLayout, PrimitiveState, PrimitiveTopology, RenderPipeline, Sampler,
+    SamplerDescriptor, ShaderModule, Texture, TextureDescriptor, TextureDimension,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::{
+    camera::Camera,
+    gfx::{Gfx, MemoryReport, RenderState, DEPTH_FORMAT},
+};
+
+// Slices of the view, each shadowed by its own map
+pub const CASCADES: usize = 3;
+
+pub const CASCADE_LABELS: [&str; CASCADES] = ["shadows 0", "shadows 1", "shadows 2"];
+
+// Every map, as bound to be sampled through a comparison sampler
+pub const MAPS_BINDING: BindingType = BindingType::Texture {
+    sample_type: TextureSampleType::Depth,
+    view_dimension: TextureViewDimension::D2Array,
+    multisampled: false,
+};
+
+// Texels along each side of every map
+const MAP_SIZE: u32 = 2048;
+
+// Farthest shadows are cast, in blocks from the eye
+const SHADOW_DISTANCE: f32 = 160.0;
+
+// Blend of logarithmic and even splits, giving near cascades more detail
+const SPLIT_LAMBDA: f32 = 0.75;
+
+// How far from a cascade casters are still caught, towards the sun
+pub const CASTER_REACH: f32 = 256.0;
+
+// Where every cascade begins and ends, and how the sun sees it
+#[derive(Clone, Copy, Debug)]
+pub struct Cascades {
+    // Column-major view-projection matrix of each cascade, depth going from 0 to 1
+    pub view_projs: [[[f32; 4]; 4]; CASCADES],
+
+    // Distance along the view where each cascade ends, padded with 0
+    pub splits: [f32; 4],
+}
+
+// Depth of the chunks seen from the sun, in cascades covering the view
+// up to `SHADOW_DISTANCE`. Cascades are fit to spheres around their
+// slice and snapped to texels, so shadows hold still as the eye turns
+#[derive(Debug)]
+pub struct Shadows {
+    // Every cascade as an array, then each alone to draw into
+    view: TextureView,
+    layers: Vec<TextureView>,
+    sampler: Sampler,
+    texture: Texture,
+}
+
+impl Shadows {
+    pub fn new(gfx: &Gfx) -> Self {
+        let descriptor = TextureDescriptor {
+            label: Some("shadows"),
+            size: Extent3d {
+                width: MAP_SIZE,
+                height: MAP_SIZE,
+                depth_or_array_layers: CASCADES as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        };
+
+        let texture = gfx.device.create_texture(&descriptor);
+
+        let descriptor = TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..TextureViewDescriptor::default()
+        };
+
+        let view = texture.create_view(&descriptor);
+
+        let layers = (0..CASCADES as u32)
+            .map(|layer| {
+                let descriptor = TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..TextureViewDescriptor::default()
+                };
+
+                texture.create_view(&descriptor)
+            })
+            .collect();
+
+        // Filtered comparisons soften every tap over 2×2 texels
+        let descriptor = SamplerDescriptor {
+            label: Some("shadows"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            compare: Some(CompareFunction::LessEqual),
+            ..SamplerDescriptor::default()
+        };
+
+        let sampler = gfx.device.create_sampler(&descriptor);
+
+        Self {
+            view,
+            layers,
+            sampler,
+            texture,
+        }
+    }
+
+    pub fn view(&self) -> &TextureView {
+        &self.view
+    }
+
+    pub fn layers(&self) -> &[TextureView] {
+        &self.layers
+    }
+
+    pub fn sampler(&self) -> &Sampler {
+        &self.sampler
+    }
+
+    // Split the view of `camera` into cascades, seen from towards `sun`
+    pub fn fit(&self, camera: &Camera, aspect: f32, sun: [f32; 3]) -> Cascades {
+        let near = camera.near;
+        let far = camera.far.min(SHADOW_DISTANCE);
+        let [forward, right, up] = camera.rays(aspect);
+
+        let mut splits = [0.0; 4];
+        for (index, split) in splits[..CASCADES].iter_mut().enumerate() {
+            let share = (index + 1) as f32 / CASCADES as f32;
+            let log = near * (far / near).powf(share);
+            let even = near + (far - near) * share;
+            *split = SPLIT_LAMBDA * log + (1.0 - SPLIT_LAMBDA) * even;
+        }
+
+        let texels = MAP_SIZE as f32;
+        let view_projs = std::array::from_fn(|index| {
+            let start = if index == 0 { near } else { splits[index - 1] };
+            let end = splits[index];
+
+            // Corners of the slice, then the sphere around them
+            let corners: Vec<_> = [start, end]
+                .into_iter()
+                .flat_map(|depth| {
+                    [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].map(|(x, y)| {
+                        let ray = add(forward, add(scale(right, x), scale(up, y)));
+                        add(camera.eye, scale(ray, depth))
+                    })
+                })
+                .collect();
+
+            let sum = corners
+                .iter()
+                .fold([0.0; 3], |sum, &corner| add(sum, corner));
+            let center = scale(sum, 1.0 / corners.len() as f32);
+            let radius = corners
+                .iter()
+                .map(|&corner| length(sub(corner, center)))
+                .fold(0.0, f32::max)
+                .ceil();
+
+            light_view_proj(center, radius, sun, texels)
+        });
+
+        Cascades { view_projs, splits }
+    }
+
+    pub fn report_memory(&self, report: &mut MemoryReport) {
+        report.add_texture("shadows", &self.texture);
+    }
+}
+
+// Depth-only pipeline for the quads pulled with `vertex`, with slope-scaled bias
+// keeping surfaces from shadowing themselves
+pub fn request_pipeline(
+    gfx: &Gfx,
+    module: &ShaderModule,
+    layout: &PipelineLayout,
+    vertex: &'static str,
+) -> Arc<RenderPipeline> {
+    let state = RenderState {
+        label: "shadows",
+        vertex,
+        fragment: None,
+        targets: Vec::new(),
+        primitive: PrimitiveState {
+            topology: PrimitiveTopology::TriangleStrip,
+            ..PrimitiveState::default()
+        },
+        depth_stencil: Some(DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: CompareFunction::Less,
+            stencil: Default::default(),
+            bias: DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0,
+            },
+        }),
+        multisample: Default::default(),
+    };
+
+    gfx.render_pipeline(module, Some(layout), &state)
+}
+
+// Orthographic view from towards `sun` of the sphere at `center`, reaching back
+// for casters in between. Its center moves in whole texels of a `texels` wide map
+fn light_view_proj(center: [f32; 3], radius: f32, sun: [f32; 3], texels: f32) -> [[f32; 4]; 4] {
+    let forward = normalize(scale(sun, -1.0));
+
+    // Looking straight down, y cannot be up
+    let up = match forward[1].abs() > 0.99 {
+        true => [0.0, 0.0, 1.0],
+        false => [0.0, 1.0, 0.0],
+    };
+
+    let right = normalize(cross(forward, up));
+    let up = cross(right, forward);
+
+    let texel = 2.0 * radius / texels;
+    let snap = |axis| (dot(axis, center) / texel).floor() * texel;
+    let (x, y, z) = (snap(right), snap(up), dot(forward, center));
+
+    // Depth from `CASTER_REACH` before the sphere to right past it
+    let (near, far) = (-radius - CASTER_REACH, radius);
+    let depth = 1.0 / (far - near);
+
+    let rows = [
+        [
+            right[0] / radius,
+            right[1] / radius,
+            right[2] / radius,
+            -x / radius,
+        ],
+        [up[0] / radius, up[1] / radius, up[2] / radius, -y / radius],
+        [
+            forward[0] * depth,
+            forward[1] * depth,
+            forward[2] * depth,
+            (-z - near) * depth,
+        ],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    // Transposed into columns
+    std::array::from_fn(|column| std::array::from_fn(|row| rows[row][column]))
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], factor: f32) -> [f32; 3] {
+    a.map(|c| c * factor)
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    scale(a, 1.0 / length(a))
+}
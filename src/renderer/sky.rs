@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    Color, ColorTargetState, ColorWrites, LoadOp, PipelineLayout, ShaderModule, ShaderStages,
+};
+
+use super::{
+    fullscreen::{self, FullscreenNode},
+    RendererError,
+};
+use crate::{
+    camera::Camera,
+    gfx::{FrameTargets, Gfx, Graph, PushConstants},
+};
+
+// Matches `Sky` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SkyConstants {
+    forward: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    sun: [f32; 4],
+}
+
+// Procedural sky filling the targets before anything is drawn over it
+#[derive(Debug)]
+pub struct Sky {
+    module: ShaderModule,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<SkyConstants>,
+}
+
+impl Sky {
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "sky", ShaderStages::FRAGMENT, 0)?;
+        let layout = push.pipeline_layout(gfx, &[]);
+        let module = push.module(gfx, "sky", include_str!("../sky.wgsl"));
+
+        Ok(Self {
+            module,
+            layout,
+            push,
+        })
+    }
+
+    // Clear the color of `targets` to the sky seen by `camera`, lit from `sun`
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        camera: &Camera,
+        aspect: f32,
+        sun: [f32; 3],
+    ) {
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = fullscreen::state("sky", "fs_main", target, gfx.multisample_state());
+        let pipeline = gfx.render_pipeline(&self.module, Some(&self.layout), &state);
+
+        let [forward, right, up] = camera.rays(aspect);
+        let extend = |[x, y, z]: [f32; 3]| [x, y, z, 0.0];
+        let constants = SkyConstants {
+            forward: extend(forward),
+            right: extend(right),
+            up: extend(up),
+            sun: extend(sun),
+        };
+
+        let node = FullscreenNode::new("sky", pipeline).constants(gfx, &self.push, constants);
+        let clear = LoadOp::Clear(Color::BLACK);
+        graph.render("sky", node).color(targets.color, clear);
+    }
+}
@@ -0,0 +1,155 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroupLayout, BlendComponent, BlendFactor, BlendOperation, BlendState, Color,
+    ColorTargetState, ColorWrites, LoadOp, MultisampleState, PipelineLayout, RenderPipeline,
+    ShaderModule, ShaderStages, TextureFormat, TextureSampleType,
+};
+
+use super::{
+    fullscreen::{self, FullscreenNode},
+    Occlusion, RendererError,
+};
+use crate::{
+    camera::Camera,
+    gfx::{Bindings, FrameTargets, Gfx, Graph, PushConstants, Transient},
+};
+
+const OCCLUSION_FORMAT: TextureFormat = TextureFormat::R8Unorm;
+
+// Matches `Ssao` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct SsaoConstants {
+    forward: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+    near: f32,
+    far: f32,
+    radius: f32,
+    intensity: f32,
+}
+
+// Screen-space ambient occlusion out of the depth of the scene, darkening
+// creases that vertex occlusion misses across merged quads. Occlusion is
+// traced at every pixel, then blurred and multiplied into the scene
+#[derive(Debug)]
+pub struct Ssao {
+    module: ShaderModule,
+    trace: (Arc<BindGroupLayout>, Arc<PipelineLayout>),
+    composite: (Arc<BindGroupLayout>, Arc<PipelineLayout>),
+    push: PushConstants<SsaoConstants>,
+}
+
+impl Ssao {
+    // Built again after the sample count changes, see `depth_module`
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "ssao", ShaderStages::FRAGMENT, 1)?;
+
+        let source = push.source(include_str!("../ssao.wgsl"));
+        let (module, depth) = super::depth_module(gfx, "ssao", &source);
+
+        let occlusion = TextureSampleType::Float { filterable: false };
+        let fragment = || Bindings::new(ShaderStages::FRAGMENT).with(depth);
+        let layouts = |group: Arc<BindGroupLayout>| {
+            let layout = push.pipeline_layout(gfx, &[&group]);
+            (group, layout)
+        };
+
+        Ok(Self {
+            module,
+            trace: layouts(fragment().layout(gfx)),
+            composite: layouts(fragment().texture(occlusion).layout(gfx)),
+            push,
+        })
+    }
+
+    // Darken the color of `targets`, once resolved, where `camera` sees
+    // its depth crease. Nothing is declared if `occlusion` has no intensity
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        targets: FrameTargets,
+        camera: &Camera,
+        aspect: f32,
+        occlusion: Occlusion,
+    ) {
+        if occlusion.intensity <= 0.0 {
+            return;
+        }
+
+        let [forward, right, up] = camera.rays(aspect);
+        let extend = |[x, y, z]: [f32; 3]| [x, y, z, 0.0];
+
+        let constants = SsaoConstants {
+            forward: extend(forward),
+            right: extend(right),
+            up: extend(up),
+            near: camera.near,
+            far: camera.far,
+            radius: occlusion.radius,
+            intensity: occlusion.intensity,
+        };
+
+        let traced = graph.transient(Transient {
+            format: OCCLUSION_FORMAT,
+            sample_count: 1,
+            size: targets.size,
+        });
+
+        let target = ColorTargetState {
+            format: OCCLUSION_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let pipeline = self.request_pipeline(gfx, "fs_trace", target, &self.trace.1);
+        let node = FullscreenNode::new("ssao", pipeline)
+            .reads(&self.trace.0, [targets.depth], None)
+            .constants(gfx, &self.push, constants);
+
+        let clear = LoadOp::Clear(Color::WHITE);
+        let pass = graph.render("ssao", node);
+        pass.read(targets.depth).color(traced, clear);
+
+        // Scene color times occlusion, leaving alpha alone
+        let multiply = BlendComponent {
+            src_factor: BlendFactor::Dst,
+            dst_factor: BlendFactor::Zero,
+            operation: BlendOperation::Add,
+        };
+
+        let target = ColorTargetState {
+            format: targets.format,
+            blend: Some(BlendState {
+                color: multiply,
+                alpha: BlendComponent::REPLACE,
+            }),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let pipeline = self.request_pipeline(gfx, "fs_composite", target, &self.composite.1);
+        let node = FullscreenNode::new("ssao composite", pipeline)
+            .reads(&self.composite.0, [targets.depth, traced], None)
+            .constants(gfx, &self.push, constants);
+
+        let output = targets.output();
+        let pass = graph.render("ssao composite", node);
+        pass.read(targets.depth).read(traced);
+        pass.color(output, LoadOp::Load);
+    }
+
+    // Fullscreen pipeline running `fragment`, drawing single sampled into `target`
+    fn request_pipeline(
+        &self,
+        gfx: &Gfx,
+        fragment: &'static str,
+        target: ColorTargetState,
+        layout: &PipelineLayout,
+    ) -> Arc<RenderPipeline> {
+        let state = fullscreen::state("ssao", fragment, target, MultisampleState::default());
+        gfx.render_pipeline(&self.module, Some(layout), &state)
+    }
+}
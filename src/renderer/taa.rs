@@ -0,0 +1,245 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    AddressMode, BindGroupLayout, Color, ColorTargetState, ColorWrites, Extent3d, FilterMode,
+    LoadOp, MultisampleState, PipelineLayout, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderModule, ShaderStages, Texture, TextureDescriptor, TextureDimension, TextureSampleType,
+    TextureUsages, TextureView, TextureViewDescriptor,
+};
+
+use super::{
+    fullscreen::{self, FullscreenNode},
+    RendererError,
+};
+use crate::{
+    camera::Camera,
+    gfx::{Bindings, FrameTargets, Gfx, Graph, MemoryReport, PushConstants, Slot, HDR_FORMAT},
+};
+
+// Jitter goes around this many positions, that many frames adding up to a pixel
+const JITTER_PHASES: u32 = 8;
+
+// Matches `Taa` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct TaaConstants {
+    // Where the eye and the rays through the current view land
+    // in the clip space of the previous frame, see `reproject`
+    eye: [f32; 4],
+    forward: [f32; 4],
+    right: [f32; 4],
+    up: [f32; 4],
+
+    // Offset of the current frame in clip space
+    jitter: [f32; 2],
+    near: f32,
+    far: f32,
+
+    // Whether history holds nothing to blend with
+    reset: u32,
+    _padding: [u32; 3],
+}
+
+// Temporal antialiasing: every frame is drawn shifted by a fraction of a pixel,
+// then blended with the frames before it, reprojected by how the camera moved.
+// History is clamped to the colors around each pixel, so whatever was not
+// seen last frame does not leave ghosts behind
+#[derive(Debug)]
+pub struct Taa {
+    module: ShaderModule,
+    sampler: Sampler,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<TaaConstants>,
+
+    // Blended frames, each read while the other is drawn into,
+    // sized like the scene is drawn and recreated along with it
+    history: Option<[(Texture, TextureView); 2]>,
+
+    // Frames blended into history so far
+    frame: u32,
+
+    // What the last frame was seen with, unjittered
+    view_proj: Option<[[f32; 4]; 4]>,
+    constants: TaaConstants,
+}
+
+impl Taa {
+    // Built again after the sample count changes, see `depth_module`
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "taa", ShaderStages::FRAGMENT, 1)?;
+
+        let source = push.source(include_str!("../taa.wgsl"));
+        let (module, depth) = super::depth_module(gfx, "taa", &source);
+
+        let color = TextureSampleType::Float { filterable: true };
+        let bindings = Bindings::new(ShaderStages::FRAGMENT)
+            .with(depth)
+            .texture(color)
+            .texture(color)
+            .sampler(SamplerBindingType::Filtering);
+
+        let descriptor = SamplerDescriptor {
+            label: Some("taa"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        };
+
+        let sampler = gfx.device.create_sampler(&descriptor);
+        let group_layout = bindings.layout(gfx);
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+
+        Ok(Self {
+            module,
+            sampler,
+            group_layout,
+            layout,
+            push,
+            history: None,
+            frame: 0,
+            view_proj: None,
+            constants: TaaConstants::zeroed(),
+        })
+    }
+
+    // Get history ready for a frame seen by `camera`, returning its view-projection
+    // matrix shifted by the jitter of the frame. History is dropped if the scene
+    // is drawn at another size than it was
+    pub fn prepare(&mut self, gfx: &Gfx, camera: &Camera, aspect: f32) -> [[f32; 4]; 4] {
+        let size = gfx.render_size();
+
+        let history = self.history.as_ref();
+        if !history.is_some_and(|[(texture, _), _]| texture.size() == size) {
+            self.history = Some([0, 1].map(|_| create_history(gfx, size)));
+            self.view_proj = None;
+        }
+
+        // Halton points, in pixels around the center
+        let phase = self.frame % JITTER_PHASES + 1;
+        let offset = [halton(phase, 2) - 0.5, halton(phase, 3) - 0.5];
+        let jitter = [
+            offset[0] * 2.0 / size.width as f32,
+            offset[1] * -2.0 / size.height as f32,
+        ];
+
+        let view_proj = camera.view_proj(aspect);
+        let previous = self.view_proj.replace(view_proj);
+        let [forward, right, up] = camera.rays(aspect);
+
+        self.constants = match previous {
+            Some(previous) => TaaConstants {
+                eye: reproject(previous, camera.eye, 1.0),
+                forward: reproject(previous, forward, 0.0),
+                right: reproject(previous, right, 0.0),
+                up: reproject(previous, up, 0.0),
+                jitter,
+                near: camera.near,
+                far: camera.far,
+                reset: 0,
+                _padding: [0; 3],
+            },
+            None => TaaConstants {
+                jitter,
+                near: camera.near,
+                far: camera.far,
+                reset: 1,
+                ..TaaConstants::zeroed()
+            },
+        };
+
+        self.frame = self.frame.wrapping_add(1);
+        shift(view_proj, jitter)
+    }
+
+    // Blend the color of `targets`, once resolved, into history, returning
+    // where it went. Must follow `prepare`, which picks what to blend with
+    pub fn declare<'a>(&'a self, gfx: &Gfx, graph: &mut Graph<'a>, targets: FrameTargets) -> Slot {
+        let history = self.history.as_ref().expect("taa not prepared");
+        let current = (self.frame % 2) as usize;
+        let (previous, output) = (&history[1 - current].1, &history[current].1);
+
+        let target = ColorTargetState {
+            format: HDR_FORMAT,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = fullscreen::state("taa", "fs_main", target, MultisampleState::default());
+        let pipeline = gfx.render_pipeline(&self.module, Some(&self.layout), &state);
+
+        let (scene, previous) = (targets.output(), graph.import(previous));
+        let output = graph.import(output);
+
+        // Depth, then the scene, then history, bound in order
+        let reads = [targets.depth, scene, previous];
+        let node = FullscreenNode::new("taa", pipeline)
+            .reads(&self.group_layout, reads, Some(&self.sampler))
+            .constants(gfx, &self.push, self.constants);
+
+        let clear = LoadOp::Clear(Color::BLACK);
+        let pass = graph.render("taa", node);
+        pass.read(targets.depth).read(scene).read(previous);
+        pass.color(output, clear);
+
+        output
+    }
+
+    pub fn report_memory(&self, report: &mut MemoryReport) {
+        for (texture, _) in self.history.iter().flatten() {
+            report.add_texture("taa history", texture);
+        }
+    }
+}
+
+fn create_history(gfx: &Gfx, size: Extent3d) -> (Texture, TextureView) {
+    let descriptor = TextureDescriptor {
+        label: Some("taa history"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: HDR_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    };
+
+    let texture = gfx.device.create_texture(&descriptor);
+    let view = texture.create_view(&TextureViewDescriptor::default());
+    (texture, view)
+}
+
+// `index`th point of the Halton sequence in `base`, between 0 and 1
+fn halton(mut index: u32, base: u32) -> f32 {
+    let (mut fraction, mut point) = (1.0, 0.0);
+
+    while index > 0 {
+        fraction /= base as f32;
+        point += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    point
+}
+
+// Column-major `view_proj` moved by `jitter` in clip space, after the perspective divide
+fn shift(mut view_proj: [[f32; 4]; 4], [x, y]: [f32; 2]) -> [[f32; 4]; 4] {
+    for column in &mut view_proj {
+        column[0] += x * column[3];
+        column[1] += y * column[3];
+    }
+
+    view_proj
+}
+
+// Column-major `view_proj` times `v` extended with `w`. Points along a ray of the view
+// land on the same line in clip space, so the shader need only scale these
+fn reproject(view_proj: [[f32; 4]; 4], v: [f32; 3], w: f32) -> [f32; 4] {
+    std::array::from_fn(|row| {
+        let [x, y, z, t] = [0, 1, 2, 3].map(|column| view_proj[column][row]);
+        x * v[0] + y * v[1] + z * v[2] + t * w
+    })
+}
@@ -0,0 +1,282 @@
+use std::{mem, sync::Arc};
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    BindGroup, BindGroupLayout, BlendState, Buffer, BufferDescriptor, BufferUsages,
+    ColorTargetState, ColorWrites, LoadOp, MultisampleState, PipelineLayout, PrimitiveState,
+    PrimitiveTopology, RenderPass, RenderPipeline, ShaderModule, ShaderStages, TextureFormat,
+};
+
+use super::RendererError;
+use crate::gfx::{Bindings, Gfx, Graph, PushConstants, Pushed, RenderNode, RenderState, Slot};
+
+// Screen pixels per font pixel
+const SCALE: f32 = 2.0;
+
+// Glyphs there is room for at first, growing as needed
+const INITIAL_CAPACITY: usize = 256;
+
+// Glyph of characters the font lacks
+const MISSING: char = '?';
+
+// 5×7 pixels per glyph, a row per byte and the leftmost pixel in the highest bit.
+// Lowercase letters are drawn as uppercase ones
+#[rustfmt::skip]
+const FONT: [(char, [u8; 7]); 50] = [
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (',', [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('=', [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('(', [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010]),
+    (')', [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000]),
+    ('[', [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110]),
+    (']', [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
+
+// Matches `Text` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct TextConstants {
+    frame: [f32; 2],
+    scale: f32,
+    _padding: f32,
+}
+
+// Matches `Glyph` in the shader: where it goes in columns and lines, and which it is
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct GlyphInstance {
+    cell: u32,
+    glyph: u32,
+}
+
+// Lines of text over the top left corner of the frame, each glyph an instance
+// expanded into a quad and shaded out of the font bits, over a dark backdrop
+#[derive(Debug)]
+pub struct Text {
+    module: ShaderModule,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<TextConstants>,
+
+    // Index into `FONT` of every ASCII character
+    glyphs: [u8; 128],
+    font: Buffer,
+
+    // Written by `prepare`, holding `len` glyphs out of `capacity`
+    instances: Buffer,
+    capacity: usize,
+    len: usize,
+    group: BindGroup,
+}
+
+impl Text {
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let push = super::push_constants(gfx, "text", ShaderStages::VERTEX, 1)?;
+
+        let group_layout = Bindings::new(ShaderStages::VERTEX)
+            .storage(true)
+            .storage(true)
+            .layout(gfx);
+
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let module = push.module(gfx, "text", include_str!("../text.wgsl"));
+
+        // Rows of 5 bits one after the other, split across two words
+        let bits = FONT.map(|(_, rows)| {
+            let bits = rows.iter().enumerate();
+            let bits = bits.fold(0, |bits, (row, &line)| bits | (line as u64) << (row * 5));
+            [bits as u32, (bits >> 32) as u32]
+        });
+
+        let descriptor = BufferDescriptor {
+            label: Some("font"),
+            size: mem::size_of_val(&bits) as u64,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        };
+
+        let font = gfx.device.create_buffer(&descriptor);
+        let blob = bytemuck::cast_slice(&bits);
+        gfx.queue.write_buffer(&font, 0, blob);
+
+        let missing = FONT.iter().position(|&(c, _)| c == MISSING).unwrap();
+        let mut glyphs = [missing as u8; 128];
+        for (index, &(c, _)) in FONT.iter().enumerate() {
+            glyphs[c as usize] = index as u8;
+            glyphs[c.to_ascii_lowercase() as usize] = index as u8;
+        }
+
+        let instances = create_instances(gfx, INITIAL_CAPACITY);
+        let group = create_group(gfx, &group_layout, &font, &instances);
+
+        Ok(Self {
+            module,
+            group_layout,
+            layout,
+            push,
+            glyphs,
+            font,
+            instances,
+            capacity: INITIAL_CAPACITY,
+            len: 0,
+            group,
+        })
+    }
+
+    // Lay out `lines` to be drawn by the next `declare`, a line each
+    pub fn prepare(&mut self, gfx: &Gfx, lines: &[String]) {
+        let mut instances = Vec::new();
+
+        for (line, text) in lines.iter().enumerate() {
+            for (column, c) in text.chars().enumerate() {
+                let glyph = match c.is_ascii() {
+                    true => self.glyphs[c as usize],
+                    false => self.glyphs[MISSING as usize],
+                };
+
+                instances.push(GlyphInstance {
+                    cell: column as u32 | (line as u32) << 16,
+                    glyph: glyph as u32,
+                });
+            }
+        }
+
+        if instances.len() > self.capacity {
+            self.capacity = instances.len().next_power_of_two();
+            self.instances = create_instances(gfx, self.capacity);
+            let (layout, font) = (&self.group_layout, &self.font);
+            self.group = create_group(gfx, layout, font, &self.instances);
+        }
+
+        let blob = bytemuck::cast_slice(&instances);
+        gfx.queue.write_buffer(&self.instances, 0, blob);
+        self.len = instances.len();
+    }
+
+    // Draw what `prepare` laid out over `frame`, drawn in `format`.
+    // Nothing is declared if there is no text
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        frame: Slot,
+        format: TextureFormat,
+    ) {
+        if self.len == 0 {
+            return;
+        }
+
+        let target = ColorTargetState {
+            format,
+            blend: Some(BlendState::ALPHA_BLENDING),
+            write_mask: ColorWrites::COLOR,
+        };
+
+        let state = RenderState {
+            label: "text",
+            vertex: "vs_main",
+            fragment: Some("fs_main"),
+            targets: vec![Some(target)],
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                ..PrimitiveState::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+        };
+
+        let (width, height) = (gfx.config.width, gfx.config.height);
+        let constants = TextConstants {
+            frame: [width as f32, height as f32],
+            scale: SCALE,
+            _padding: 0.0,
+        };
+
+        let node = TextNode {
+            text: self,
+            pipeline: gfx.render_pipeline(&self.module, Some(&self.layout), &state),
+            constants: self.push.write(gfx, constants),
+        };
+
+        graph.render("text", node).color(frame, LoadOp::Load);
+    }
+}
+
+struct TextNode<'a> {
+    text: &'a Text,
+    pipeline: Arc<RenderPipeline>,
+    constants: Pushed<TextConstants>,
+}
+
+impl RenderNode for TextNode<'_> {
+    fn record<'p>(&'p self, pass: &mut RenderPass<'p>) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.text.group, &[]);
+        self.text.push.set(pass, &self.constants);
+        pass.draw(0..4, 0..self.text.len as u32);
+    }
+}
+
+fn create_instances(gfx: &Gfx, capacity: usize) -> Buffer {
+    let descriptor = BufferDescriptor {
+        label: Some("text"),
+        size: (capacity * mem::size_of::<GlyphInstance>()) as u64,
+        usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    };
+
+    gfx.device.create_buffer(&descriptor)
+}
+
+fn create_group(
+    gfx: &Gfx,
+    layout: &BindGroupLayout,
+    font: &Buffer,
+    instances: &Buffer,
+) -> BindGroup {
+    let resources = [font.as_entire_binding(), instances.as_entire_binding()];
+    gfx.bind_group("text", layout, resources)
+}
@@ -0,0 +1,128 @@
+use std::sync::Arc;
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::{
+    AddressMode, BindGroupLayout, Color, ColorTargetState, ColorWrites, FilterMode, LoadOp,
+    MultisampleState, PipelineLayout, Sampler, SamplerBindingType, SamplerDescriptor, ShaderModule,
+    ShaderStages, TextureFormat, TextureSampleType,
+};
+
+use super::{
+    fullscreen::{self, FullscreenNode},
+    RendererError,
+};
+use crate::gfx::{Bindings, Gfx, Graph, PushConstants, Slot};
+
+// How colors end up in the frame, matching the constants in the shader
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+enum Output {
+    // Tonemapped, for sRGB frames to encode
+    Linear,
+
+    // Tonemapped and encoded, for frames that don't encode by themselves
+    Srgb,
+
+    // Left as is, for floating point frames shown by HDR displays
+    Hdr,
+}
+
+impl Output {
+    fn new(format: TextureFormat) -> Self {
+        if format.is_srgb() {
+            Self::Linear
+        } else if matches!(format, TextureFormat::Rgba16Float) {
+            Self::Hdr
+        } else {
+            Self::Srgb
+        }
+    }
+}
+
+// Matches `Tonemap` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct TonemapConstants {
+    exposure: f32,
+    output: u32,
+}
+
+// Post pass bringing the scene, drawn in linear HDR, into the frame.
+// The scene is filtered, so it may be drawn smaller than the frame
+#[derive(Debug)]
+pub struct Tonemap {
+    module: ShaderModule,
+    sampler: Sampler,
+    group_layout: Arc<BindGroupLayout>,
+    layout: Arc<PipelineLayout>,
+    push: PushConstants<TonemapConstants>,
+}
+
+impl Tonemap {
+    pub fn new(gfx: &Gfx) -> Result<Self, RendererError> {
+        let scene = TextureSampleType::Float { filterable: true };
+        let bindings = Bindings::new(ShaderStages::FRAGMENT)
+            .texture(scene)
+            .sampler(SamplerBindingType::Filtering);
+
+        let descriptor = SamplerDescriptor {
+            label: Some("tonemap"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        };
+
+        let sampler = gfx.device.create_sampler(&descriptor);
+
+        let push = super::push_constants(gfx, "tonemap", ShaderStages::FRAGMENT, 1)?;
+
+        let group_layout = bindings.layout(gfx);
+        let layout = push.pipeline_layout(gfx, &[&group_layout]);
+        let module = push.module(gfx, "tonemap", include_str!("../tonemap.wgsl"));
+
+        Ok(Self {
+            module,
+            sampler,
+            group_layout,
+            layout,
+            push,
+        })
+    }
+
+    // Map `scene` into `frame`, scaling brightness by `exposure` first
+    // and stretching it over the frame if drawn at another size
+    pub fn declare<'a>(
+        &'a self,
+        gfx: &Gfx,
+        graph: &mut Graph<'a>,
+        scene: Slot,
+        frame: Slot,
+        exposure: f32,
+    ) {
+        let format = gfx.format();
+        let target = ColorTargetState {
+            format,
+            blend: None,
+            write_mask: ColorWrites::ALL,
+        };
+
+        let state = fullscreen::state("tonemap", "fs_main", target, MultisampleState::default());
+        let pipeline = gfx.render_pipeline(&self.module, Some(&self.layout), &state);
+        let constants = TonemapConstants {
+            exposure,
+            output: Output::new(format) as u32,
+        };
+
+        let node = FullscreenNode::new("tonemap", pipeline)
+            .reads(&self.group_layout, [scene], Some(&self.sampler))
+            .constants(gfx, &self.push, constants);
+
+        let clear = LoadOp::Clear(Color::BLACK);
+        graph
+            .render("tonemap", node)
+            .read(scene)
+            .color(frame, clear);
+    }
+}
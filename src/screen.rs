@@ -0,0 +1,112 @@
+use std::{error::Error, fmt::Display, sync::Arc};
+
+use winit::{
+    dpi::PhysicalSize,
+    monitor::VideoMode,
+    window::{Fullscreen, Window},
+};
+
+// How the window takes up the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScreenMode {
+    Windowed,
+
+    // Covering the monitor the window is in, without changing its video mode
+    Borderless,
+
+    // Taking over the monitor the window is in, in its largest video mode
+    Exclusive,
+}
+
+impl ScreenMode {
+    // The mode to switch to next, going around all of them
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Windowed => Self::Borderless,
+            Self::Borderless => Self::Exclusive,
+            Self::Exclusive => Self::Windowed,
+        }
+    }
+}
+
+// Switches the window between screen modes, bringing it back to the size it had
+// when leaving windowed mode. The window resizes on its own, so the surface
+// gets configured again on `WindowEvent::Resized`
+#[derive(Debug)]
+pub struct Screen {
+    window: Arc<Window>,
+    mode: ScreenMode,
+    windowed_size: PhysicalSize<u32>,
+}
+
+impl Screen {
+    pub fn new(window: Arc<Window>) -> Self {
+        let windowed_size = window.inner_size();
+
+        Self {
+            window,
+            mode: ScreenMode::Windowed,
+            windowed_size,
+        }
+    }
+
+    pub const fn mode(&self) -> ScreenMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ScreenMode) -> Result<(), ScreenError> {
+        if mode == self.mode {
+            return Ok(());
+        }
+
+        let fullscreen = match mode {
+            ScreenMode::Windowed => None,
+            ScreenMode::Borderless => Some(Fullscreen::Borderless(None)),
+            ScreenMode::Exclusive => Some(Fullscreen::Exclusive(self.video_mode()?)),
+        };
+
+        if self.mode == ScreenMode::Windowed {
+            self.windowed_size = self.window.inner_size();
+        }
+
+        self.window.set_fullscreen(fullscreen);
+
+        if mode == ScreenMode::Windowed {
+            let _ = self.window.request_inner_size(self.windowed_size);
+        }
+
+        self.mode = mode;
+        Ok(())
+    }
+
+    // Largest video mode of the monitor the window is in, refreshing the fastest
+    fn video_mode(&self) -> Result<VideoMode, ScreenError> {
+        let monitor = self.window.current_monitor();
+        let monitor = monitor.ok_or(ScreenError::NoMonitor)?;
+
+        let video_mode = monitor.video_modes().max_by_key(|video_mode| {
+            let PhysicalSize { width, height } = video_mode.size();
+            (width * height, video_mode.refresh_rate_millihertz())
+        });
+
+        video_mode.ok_or(ScreenError::NoVideoMode)
+    }
+}
+
+#[derive(Debug)]
+pub enum ScreenError {
+    // The window is not in any monitor that can be found
+    NoMonitor,
+    NoVideoMode,
+}
+
+impl Display for ScreenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoMonitor => write!(f, "cannot find the monitor of the window"),
+            Self::NoVideoMode => write!(f, "monitor has no video modes to go fullscreen in"),
+        }
+    }
+}
+
+impl Error for ScreenError {}
@@ -0,0 +1,73 @@
+// Sky behind everything else, a gradient from the horizon up lit by the sun,
+// drawn as a single triangle covering the whole frame. Colors are linear and
+// may go past 1, to be tonemapped later
+
+struct Sky {
+    // Direction through the center of the view, and how far right and up
+    // the edges of the view are from it, w unused
+    forward: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+
+    // Towards the sun, w unused
+    sun: vec4<f32>,
+}
+
+var<push_constant> sky: Sky;
+
+// Of the sky straight up and at the horizon, with the sun high up, setting and gone
+const DAY_ZENITH = vec3(0.08, 0.22, 0.65);
+const DAY_HORIZON = vec3(0.45, 0.62, 0.9);
+const DUSK_ZENITH = vec3(0.06, 0.08, 0.22);
+const DUSK_HORIZON = vec3(1.0, 0.38, 0.12);
+const NIGHT_ZENITH = vec3(0.002, 0.003, 0.01);
+const NIGHT_HORIZON = vec3(0.01, 0.015, 0.035);
+
+const SUN_COLOR = vec3(1.0, 0.9, 0.75);
+const SUN_RADIANCE = 40.0;
+
+// Cosine of the angular radius of the sun, larger than the real one
+const SUN_SIZE = 0.9995;
+
+struct Varyings {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> Varyings {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    let ndc = uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+    return Varyings(vec4(ndc, 0.0, 1.0), ndc);
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    let ray = sky.forward.xyz + in.ndc.x * sky.right.xyz + in.ndc.y * sky.up.xyz;
+    let direction = normalize(ray);
+    let sun = normalize(sky.sun.xyz);
+
+    // How much of the day is left, the sun setting in between
+    let day = smoothstep(0.0, 0.35, sun.y);
+    let night = 1.0 - smoothstep(-0.25, 0.0, sun.y);
+    let dusk = 1.0 - day - night;
+
+    // Sunsets only glow on the side of the sun
+    let cosine = dot(direction, sun);
+    let glow = mix(DUSK_ZENITH, DUSK_HORIZON, 0.5 + 0.5 * cosine);
+
+    let zenith = day * DAY_ZENITH + dusk * DUSK_ZENITH + night * NIGHT_ZENITH;
+    let horizon = day * DAY_HORIZON + dusk * glow + night * NIGHT_HORIZON;
+
+    // Below the horizon, the sky fades into its own darker reflection
+    let height = pow(1.0 - abs(direction.y), 4.0);
+    let below = select(1.0, 0.4, direction.y < 0.0);
+    var color = mix(zenith, horizon, height) * below;
+
+    let shown = 1.0 - night;
+    let disc = smoothstep(SUN_SIZE, SUN_SIZE + 0.0002, cosine) * SUN_RADIANCE;
+    let halo = pow(max(cosine, 0.0), 64.0) * 0.5;
+    color += (disc + halo) * shown * SUN_COLOR;
+
+    return vec4(color, 1.0);
+}
@@ -0,0 +1,161 @@
+// Ambient occlusion traced against the depth of the scene, then blurred
+// and multiplied into it, each drawn as a single triangle covering the frame.
+// `load_depth` and the depth binding are pasted in front, see `depth_module`
+
+struct Ssao {
+    // Direction through the center of the view, and how far right and up
+    // the edges of the view are from it, w unused
+    forward: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+
+    // Clip planes of the view
+    near: f32,
+    far: f32,
+
+    // How far occluders reach, in blocks, and how dark they make it
+    radius: f32,
+    intensity: f32,
+}
+
+var<push_constant> ssao: Ssao;
+
+// As traced, only read by `fs_composite`
+@group(0) @binding(1) var traced: texture_2d<f32>;
+
+const SAMPLES = 12u;
+
+// Golden angle, spreading samples evenly around the normal
+const SPIRAL = 2.39996323;
+
+// Occluders must be this much in front, in blocks, so flat surfaces stay lit
+const BIAS = 0.03;
+
+// Occlusion fades out between these distances, before fog covers it anyway
+const FADE_START = 48.0;
+const FADE_END = 96.0;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    return vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
+
+// Distance along the view of what depth buffer value `depth` holds
+fn linear_depth(depth: f32) -> f32 {
+    return ssao.far * ssao.near / (ssao.far + depth * (ssao.near - ssao.far));
+}
+
+fn size() -> vec2<i32> {
+    return vec2<i32>(textureDimensions(depth));
+}
+
+// Where the pixel at `xy` lies relative to the eye, depth being `distance` along the view
+fn position(xy: vec2<i32>, distance: f32) -> vec3<f32> {
+    let ndc = (vec2<f32>(xy) + 0.5) / vec2<f32>(size()) * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+    return distance * (ssao.forward.xyz + ndc.x * ssao.right.xyz + ndc.y * ssao.up.xyz);
+}
+
+// Pixel the point `offset` from the eye lands on, and its distance along the view
+fn project(offset: vec3<f32>) -> vec3<f32> {
+    let distance = dot(offset, ssao.forward.xyz);
+    let right = dot(offset, ssao.right.xyz) / dot(ssao.right.xyz, ssao.right.xyz);
+    let up = dot(offset, ssao.up.xyz) / dot(ssao.up.xyz, ssao.up.xyz);
+    let uv = vec2(right, up) / distance * vec2(0.5, -0.5) + 0.5;
+    return vec3(uv * vec2<f32>(size()), distance);
+}
+
+// Difference to the neighbor along `step` closest in depth, trying both ways,
+// so normals do not bend over edges
+fn slope(xy: vec2<i32>, step: vec2<i32>, distance: f32) -> vec3<f32> {
+    let limit = size() - 1;
+    let ahead = clamp(xy + step, vec2(0), limit);
+    let behind = clamp(xy - step, vec2(0), limit);
+    let ahead_distance = linear_depth(load_depth(ahead));
+    let behind_distance = linear_depth(load_depth(behind));
+    let center = position(xy, distance);
+
+    if abs(ahead_distance - distance) < abs(behind_distance - distance) {
+        return position(ahead, ahead_distance) - center;
+    }
+
+    return center - position(behind, behind_distance);
+}
+
+// Interleaved gradient noise, rotating samples from pixel to pixel
+fn noise(xy: vec2<i32>) -> f32 {
+    let magic = vec3(0.06711056, 0.00583715, 52.9829189);
+    return fract(magic.z * fract(dot(vec2<f32>(xy), magic.xy)));
+}
+
+@fragment
+fn fs_trace(@builtin(position) position_in: vec4<f32>) -> @location(0) vec4<f32> {
+    let xy = vec2<i32>(position_in.xy);
+    let depth = load_depth(xy);
+
+    // Nothing drawn but the sky
+    if depth >= 1.0 {
+        return vec4(1.0);
+    }
+
+    let distance = linear_depth(depth);
+    let center = position(xy, distance);
+    let dx = slope(xy, vec2(1, 0), distance);
+    let dy = slope(xy, vec2(0, 1), distance);
+    var normal = normalize(cross(dy, dx));
+    if dot(normal, center) > 0.0 {
+        normal = -normal;
+    }
+
+    // Any basis around the normal, turned by the noise
+    let helper = select(vec3(0.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), abs(normal.y) > 0.9);
+    let tangent = normalize(cross(helper, normal));
+    let bitangent = cross(normal, tangent);
+    let turn = noise(xy) * 6.28318531;
+
+    var occlusion = 0.0;
+    for (var index = 0u; index < SAMPLES; index++) {
+        // Spiraling out over the hemisphere, denser near the surface
+        let share = (f32(index) + 0.5) / f32(SAMPLES);
+        let angle = f32(index) * SPIRAL + turn;
+        let spread = sqrt(share);
+        let direction = vec3(cos(angle) * spread, sin(angle) * spread, sqrt(1.0 - share));
+        let reach = ssao.radius * mix(0.1, 1.0, share * share);
+        let around = tangent * direction.x + bitangent * direction.y + normal * direction.z;
+
+        let projected = project(center + around * reach);
+        let texel = clamp(vec2<i32>(projected.xy), vec2(0), size() - 1);
+        let occluder = linear_depth(load_depth(texel));
+
+        // Occluders far in front are something else altogether
+        let range = smoothstep(0.0, 1.0, ssao.radius / abs(distance - occluder));
+        occlusion += select(0.0, range, occluder < projected.z - BIAS);
+    }
+
+    let fade = smoothstep(FADE_START, FADE_END, distance);
+    let lit = pow(1.0 - occlusion / f32(SAMPLES), ssao.intensity);
+    return vec4(mix(lit, 1.0, fade));
+}
+
+@fragment
+fn fs_composite(@builtin(position) position_in: vec4<f32>) -> @location(0) vec4<f32> {
+    let xy = vec2<i32>(position_in.xy);
+    let distance = linear_depth(load_depth(xy));
+    let limit = size() - 1;
+
+    // Noise averages out over 4×4 pixels, leaving out those too far in front or behind
+    var sum = 0.0;
+    var weight = 0.0;
+    for (var y = -2; y < 2; y++) {
+        for (var x = -2; x < 2; x++) {
+            let tap = clamp(xy + vec2(x, y), vec2(0), limit);
+            let other = linear_depth(load_depth(tap));
+            let close = select(0.0, 1.0, abs(other - distance) < 0.05 * distance);
+            sum += textureLoad(traced, tap, 0).r * close;
+            weight += close;
+        }
+    }
+
+    let occlusion = select(1.0, sum / weight, weight > 0.0);
+    return vec4(vec3(occlusion), 1.0);
+}
@@ -0,0 +1,113 @@
+use crate::input::Action;
+
+// Where the game is at, deciding which actions pressed go on to do what they do
+// and what moves on from frame to frame. Starts out loading the world around
+// the camera, playing once it is there
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AppState {
+    // Chunks around the camera being generated and meshed, with the progress shown
+    // and nothing but the camera orbiting moving on
+    #[default]
+    Loading,
+
+    Playing,
+
+    // Standing still with the pointer given back, until clicked into again
+    Paused,
+
+    // Bindings listed over the frame with the pointer given back,
+    // everything going on behind it as it was
+    Menu,
+}
+
+impl AppState {
+    // State an action pressed leads to, and whether the action goes on to do
+    // what it does while playing. Released actions always go on, for anything
+    // held to be let go of
+    pub fn press(self, action: Action) -> (Self, bool) {
+        if is_global(action) {
+            return (self, true);
+        }
+
+        match (self, action) {
+            (Self::Loading, _) => (self, false),
+
+            (Self::Playing, Action::ToggleMenu) => (Self::Menu, false),
+            (Self::Playing, Action::ReleasePointer) => (Self::Paused, true),
+            (Self::Playing, _) => (self, true),
+
+            // Clicking back into the window takes the click, leaving blocks alone
+            (Self::Paused, Action::Break) => (Self::Playing, false),
+            (Self::Paused, Action::ToggleMenu) => (Self::Menu, false),
+            (Self::Paused, _) => (self, false),
+
+            (Self::Menu, Action::ToggleMenu | Action::ReleasePointer) => (Self::Paused, false),
+            (Self::Menu, _) => (self, false),
+        }
+    }
+
+    // Playing once the world around the camera got loaded
+    pub fn loaded(self) -> Self {
+        match self {
+            Self::Loading => Self::Playing,
+            _ => self,
+        }
+    }
+
+    // Whether the fixed steps entities, walking and recorded input go on
+    pub const fn ticks(self) -> bool {
+        matches!(self, Self::Playing | Self::Menu)
+    }
+
+    // Whether movement actions and sticks move the camera
+    pub const fn moves_camera(self) -> bool {
+        matches!(self, Self::Playing)
+    }
+}
+
+// Actions about the window and what is drawn, going on whatever the state
+fn is_global(action: Action) -> bool {
+    matches!(
+        action,
+        Action::ToggleWireframe
+            | Action::ToggleHud
+            | Action::NextView
+            | Action::ToggleFullscreen
+            | Action::ReportMemory
+            | Action::RenderFarther
+            | Action::RenderNearer
+            | Action::Screenshot
+            | Action::ToggleRecording
+            | Action::SaveSnapshot
+            | Action::LoadSnapshot
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pausing_and_the_menu_hold_actions_back() {
+        let state = AppState::default();
+        assert_eq!(state.press(Action::Break), (AppState::Loading, false));
+        assert_eq!(state.press(Action::ToggleHud), (AppState::Loading, true));
+
+        let state = state.loaded();
+        assert_eq!(state.press(Action::Break), (AppState::Playing, true));
+
+        // Pausing releases the pointer as releasing it did, clicking goes back to playing
+        let (state, routed) = state.press(Action::ReleasePointer);
+        assert_eq!((state, routed), (AppState::Paused, true));
+        assert!(!state.ticks());
+        assert_eq!(state.press(Action::Place), (AppState::Paused, false));
+        assert_eq!(state.press(Action::Break), (AppState::Playing, false));
+
+        // Closing the menu leaves the game paused, the world going on meanwhile
+        let (state, _) = state.press(Action::ToggleMenu);
+        assert!(state.ticks() && !state.moves_camera());
+        assert_eq!(state.press(Action::MoveForward), (AppState::Menu, false));
+        assert_eq!(state.press(Action::ToggleMenu), (AppState::Paused, false));
+        assert_eq!(state.loaded(), AppState::Menu);
+    }
+}
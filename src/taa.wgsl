@@ -0,0 +1,93 @@
+// The current frame blended into those before it, reprojected through its depth,
+// drawn as a single triangle covering the frame.
+// `load_depth` and the depth binding are pasted in front, see `depth_module`
+
+struct Taa {
+    // Eye and rays of the current view, in the clip space of the previous frame
+    eye: vec4<f32>,
+    forward: vec4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+
+    // Offset of the current frame in clip space
+    jitter: vec2<f32>,
+
+    // Clip planes of the view
+    near: f32,
+    far: f32,
+
+    // Nonzero if there is no history to blend with
+    reset: u32,
+}
+
+var<push_constant> taa: Taa;
+
+@group(0) @binding(1) var scene: texture_2d<f32>;
+@group(0) @binding(2) var history: texture_2d<f32>;
+@group(0) @binding(3) var history_sampler: sampler;
+
+// Share of the current frame in what gets blended, the rest coming from history
+const BLEND = 0.1;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    return vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+}
+
+// Distance along the view of what depth buffer value `depth` holds
+fn linear_depth(depth: f32) -> f32 {
+    return taa.far * taa.near / (taa.far + depth * (taa.near - taa.far));
+}
+
+// Colors weighted down the brighter they are, so fireflies don't smear over
+fn weight(color: vec3<f32>) -> f32 {
+    return 1.0 / (1.0 + dot(color, vec3(0.299, 0.587, 0.114)));
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let size = vec2<i32>(textureDimensions(scene));
+    let xy = vec2<i32>(position.xy);
+    let current = textureLoad(scene, xy, 0).rgb;
+
+    // Range of colors around, and the closest depth among them
+    // so edges move along with whatever is in front
+    var lowest = current;
+    var highest = current;
+    var depth = 1.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let tap = clamp(xy + vec2(x, y), vec2(0), size - 1);
+            let color = textureLoad(scene, tap, 0).rgb;
+            lowest = min(lowest, color);
+            highest = max(highest, color);
+            depth = min(depth, load_depth(tap));
+        }
+    }
+
+    if taa.reset != 0u {
+        return vec4(current, 1.0);
+    }
+
+    // Through the center of the pixel as drawn, so without the jitter,
+    // out to what was drawn there. The sky is taken to be at the far plane
+    let uv = position.xy / vec2<f32>(size);
+    let ndc = uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0) - taa.jitter;
+    let ray = taa.forward + ndc.x * taa.right + ndc.y * taa.up;
+    let clip = taa.eye + linear_depth(depth) * ray;
+    let previous = clip.xy / clip.w * vec2(0.5, -0.5) + 0.5;
+
+    // Newly seen, nothing to blend with
+    if clip.w <= 0.0 || any(previous < vec2(0.0)) || any(previous > vec2(1.0)) {
+        return vec4(current, 1.0);
+    }
+
+    let sampled = textureSampleLevel(history, history_sampler, previous, 0.0).rgb;
+    let past = clamp(sampled, lowest, highest);
+
+    let current_weight = BLEND * weight(current);
+    let past_weight = (1.0 - BLEND) * weight(past);
+    let blended = (current * current_weight + past * past_weight) / (current_weight + past_weight);
+    return vec4(blended, 1.0);
+}
@@ -0,0 +1,70 @@
+// Glyphs of text drawn as instanced quads, one per character, in cells
+// of 6×9 font pixels: the glyph itself, a pixel to the right and one above and below
+
+struct Text {
+    // In pixels
+    frame: vec2<f32>,
+
+    // Screen pixels per font pixel
+    scale: f32,
+}
+
+// Column in the low half of `cell` and line in the high half, and the index into `font`
+struct Glyph {
+    cell: u32,
+    glyph: u32,
+}
+
+var<push_constant> text: Text;
+
+// 5 bits per row of 7, one row after the other, the lowest 32 bits in x.
+// The highest bit of every row is its leftmost pixel
+@group(0) @binding(0) var<storage, read> font: array<vec2<u32>>;
+@group(0) @binding(1) var<storage, read> glyphs: array<Glyph>;
+
+const CELL = vec2(6.0, 9.0);
+
+// Font pixels between the frame edges and the text
+const MARGIN = 2.0;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+
+    // Font pixels into the cell, rows going down
+    @location(0) pixel: vec2<f32>,
+    @location(1) @interpolate(flat) bits: vec2<u32>,
+}
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex: u32,
+    @builtin(instance_index) instance: u32,
+) -> VertexOutput {
+    let glyph = glyphs[instance];
+    let cell = vec2(f32(glyph.cell & 0xffffu), f32(glyph.cell >> 16u));
+    let corner = vec2(f32(vertex & 1u), f32(vertex >> 1u));
+
+    let pixel = corner * CELL;
+    let screen = (MARGIN + cell * CELL + pixel) * text.scale;
+    let ndc = screen / text.frame * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
+
+    var out: VertexOutput;
+    out.position = vec4(ndc, 0.0, 1.0);
+    out.pixel = pixel;
+    out.bits = font[glyph.glyph];
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Glyphs sit a pixel down into their cell
+    let pixel = vec2<i32>(floor(in.pixel)) - vec2(0, 1);
+    let inside = all(pixel >= vec2(0)) && all(pixel < vec2(5, 7));
+
+    let index = u32(pixel.y * 5 + 4 - pixel.x);
+    let word = select(in.bits.y, in.bits.x, index < 32u);
+    let lit = inside && ((word >> (index % 32u)) & 1u) != 0u;
+
+    // Over a dark backdrop, so text reads over anything
+    return select(vec4(0.0, 0.0, 0.0, 0.5), vec4(1.0), lit);
+}
@@ -0,0 +1,371 @@
+mod mipmaps;
+
+use std::{error::Error, fmt::Display, fs, io, iter, path::Path, sync::Arc};
+
+use png::{ColorType, Decoder, DecodingError, Transformations};
+use wgpu::{
+    AddressMode, BindGroup, BindGroupLayout, BindingResource, Extent3d, FilterMode,
+    ImageCopyTexture, ImageDataLayout, Origin3d, Sampler, SamplerBindingType, SamplerDescriptor,
+    ShaderStages, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+};
+
+use crate::{
+    assets::{AssetError, Assets},
+    gfx::{Bindings, Gfx},
+    mesh::QuadLayout,
+};
+
+// Block images are authored in sRGB, so sampling hands shaders linear colors
+pub const TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+// Layer of materials with no texture of their own, plain white
+// so their color shows as it is
+pub const BLANK: u32 = 0;
+
+// Frames a second animated textures go through
+pub const FRAME_RATE: f32 = 8.0;
+
+// How a texture of many frames, in as many layers one after the other,
+// goes from one to the next
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Animation {
+    pub frames: u32,
+
+    // Frames a second
+    pub rate: f32,
+}
+
+impl Animation {
+    // A single frame, as textures that are not animated have
+    pub const STILL: Self = Self {
+        frames: 1,
+        rate: 0.0,
+    };
+}
+
+// An 8-bit RGBA image, rows top to bottom
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    // Decode a PNG of any color type and bit depth
+    pub fn read_png(bytes: &[u8]) -> Result<Self, DecodingError> {
+        let mut decoder = Decoder::new(bytes);
+        decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let buffer = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            ColorType::Rgba => buffer
+                .chunks_exact(4)
+                .map(|rgba| [rgba[0], rgba[1], rgba[2], rgba[3]])
+                .collect(),
+            ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            ColorType::Grayscale => buffer.iter().map(|&g| [g, g, g, 255]).collect(),
+            ColorType::Indexed => {
+                // Expanded into RGB or RGBA when decoding
+                unreachable!()
+            }
+        };
+
+        let image = Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        };
+
+        Ok(image)
+    }
+
+    // Images a whole number of squares tall are strips of square frames,
+    // top to bottom. Anything else is a single frame
+    pub const fn frames(&self) -> u32 {
+        match self.height > self.width && self.height % self.width == 0 {
+            true => self.height / self.width,
+            false => 1,
+        }
+    }
+}
+
+// Block images stacked into the layers of a texture array, after a blank one,
+// the layer of a block being what goes in the material field of its quads.
+// Animated images take a layer per frame
+#[derive(Debug)]
+pub struct BlockTextures {
+    pub texture: Texture,
+    pub view: TextureView,
+    pub sampler: Sampler,
+
+    // Name of every layer, in order, frames of the same image sharing it.
+    // The blank layer has none
+    names: Vec<String>,
+}
+
+impl BlockTextures {
+    // Every PNG in `dir`, named after their file names without the extension.
+    // Files are taken in name order, so layers stay put as long as no file is added
+    pub fn load(gfx: &Gfx, dir: &Path) -> Result<Self, TextureError> {
+        let mut paths = Vec::new();
+
+        for entry in fs::read_dir(dir).map_err(TextureError::Io)? {
+            let path = entry.map_err(TextureError::Io)?.path();
+
+            if path.extension().is_some_and(|extension| extension == "png") {
+                paths.push(path);
+            }
+        }
+
+        paths.sort_unstable();
+
+        let mut images = Vec::with_capacity(paths.len());
+        for path in paths {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let bytes = fs::read(&path).map_err(TextureError::Io)?;
+
+            match Image::read_png(&bytes) {
+                Ok(image) => images.push((name, image)),
+                Err(err) => return Err(TextureError::Decode(name, err)),
+            }
+        }
+
+        Self::new(gfx, images)
+    }
+
+    // Same as `load`, out of `dir` as `assets` has it, built again once any image
+    // in it changes or one is added or removed
+    pub fn load_assets(gfx: &Gfx, assets: &mut Assets, dir: &str) -> Result<Arc<Self>, AssetError> {
+        assets.load(dir, |assets| {
+            let mut images = Vec::new();
+            for path in assets.list(dir, "png")? {
+                let name = Path::new(&path).file_stem().unwrap();
+                let name = name.to_string_lossy().into_owned();
+                let bytes = assets.bytes(&path)?;
+
+                match Image::read_png(&bytes) {
+                    Ok(image) => images.push((name, image)),
+                    Err(err) => {
+                        let err = TextureError::Decode(name, err);
+                        return Err(AssetError::Build(path, Box::new(err)));
+                    }
+                }
+            }
+
+            let textures = Self::new(gfx, images);
+            textures.map_err(|err| AssetError::Build(dir.to_owned(), Box::new(err)))
+        })
+    }
+
+    // A layer per frame of every image, all of them as big as the first one
+    pub fn new(gfx: &Gfx, images: Vec<(String, Image)>) -> Result<Self, TextureError> {
+        let Some((_, first)) = images.first() else {
+            return Err(TextureError::Empty);
+        };
+
+        let (width, height) = (first.width, first.height / first.frames());
+        Self::stack(gfx, (width, height), images)
+    }
+
+    // The blank layer alone, for materials to show their color until there are textures
+    pub fn blank(gfx: &Gfx) -> Self {
+        let blank = Self::stack(gfx, (1, 1), Vec::new());
+        blank.expect("a blank layer always fits")
+    }
+
+    // Layers of `size`, the blank one then every frame of every image
+    fn stack(
+        gfx: &Gfx,
+        (width, height): (u32, u32),
+        images: Vec<(String, Image)>,
+    ) -> Result<Self, TextureError> {
+        let frames = images.iter().map(|(_, image)| image.frames() as usize);
+        let layers = 1 + frames.sum::<usize>();
+
+        // Materials must be able to refer to every layer
+        let materials = QuadLayout::DEFAULT.material_mask() as usize + 1;
+        let max_layers = gfx.device.limits().max_texture_array_layers as usize;
+
+        if layers > materials.min(max_layers) {
+            return Err(TextureError::TooMany(layers));
+        }
+
+        let texels = width as usize * height as usize;
+        let mut pixels = Vec::with_capacity(texels * layers);
+        let mut names = Vec::with_capacity(layers);
+        pixels.resize(texels, [255; 4]);
+        names.push(String::new());
+
+        // Frames follow one another in the strip as they do in the layers
+        for (name, image) in images {
+            let frames = image.frames();
+            if (image.width, image.height / frames) != (width, height) {
+                let size = (image.width, image.height / frames);
+                let expected = (width, height);
+                return Err(TextureError::Size(name, size, expected));
+            }
+
+            pixels.extend_from_slice(&image.pixels);
+            names.extend(iter::repeat(name).take(frames as usize));
+        }
+
+        let size = Extent3d {
+            width,
+            height,
+            depth_or_array_layers: layers as u32,
+        };
+
+        let descriptor = TextureDescriptor {
+            label: Some("blocks"),
+            size,
+            mip_level_count: size.max_mips(TextureDimension::D2),
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TEXTURE_FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        };
+
+        let texture = gfx.device.create_texture(&descriptor);
+
+        let destination = ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        };
+
+        let layout = ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        };
+
+        let blob = pixels.as_flattened();
+        gfx.queue.write_texture(destination, blob, layout, size);
+        mipmaps::generate(gfx, &texture);
+
+        let descriptor = TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..TextureViewDescriptor::default()
+        };
+
+        let view = texture.create_view(&descriptor);
+
+        // Crisp texels up close, repeating once per block across merged quads,
+        // and blended between mip levels far away so that distant faces don't shimmer
+        let descriptor = SamplerDescriptor {
+            label: Some("blocks"),
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..SamplerDescriptor::default()
+        };
+
+        let sampler = gfx.device.create_sampler(&descriptor);
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+            names,
+        })
+    }
+
+    pub fn layers(&self) -> usize {
+        self.names.len()
+    }
+
+    // Layer of the image called `name`, to be used as a material.
+    // Animated images start at their first frame
+    pub fn layer(&self, name: &str) -> Option<u32> {
+        let layer = self.names.iter().position(|other| other == name)?;
+        Some(layer as _)
+    }
+
+    // How the image called `name` is animated, still if it is a single frame
+    pub fn animation(&self, name: &str) -> Option<Animation> {
+        let frames = self.names.iter().filter(|&other| other == name).count();
+        match frames {
+            0 => None,
+            1 => Some(Animation::STILL),
+            _ => Some(Animation {
+                frames: frames as u32,
+                rate: FRAME_RATE,
+            }),
+        }
+    }
+
+    // Texture array at binding 0 and its sampler at binding 1
+    pub fn create_binding(
+        &self,
+        gfx: &Gfx,
+        visibility: ShaderStages,
+    ) -> (Arc<BindGroupLayout>, BindGroup) {
+        let bindings = Bindings::new(visibility)
+            .texture_array()
+            .sampler(SamplerBindingType::Filtering);
+
+        let resources = [
+            BindingResource::TextureView(&self.view),
+            BindingResource::Sampler(&self.sampler),
+        ];
+
+        bindings.create(gfx, "blocks", resources)
+    }
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(io::Error),
+    Decode(String, DecodingError),
+
+    // Named image, its size, and the size of the first image
+    Size(String, (u32, u32), (u32, u32)),
+
+    // More layers than the device or the material field allow
+    TooMany(usize),
+    Empty,
+}
+
+impl Display for TextureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read block textures: {err}"),
+            Self::Decode(name, err) => write!(f, "cannot decode block texture {name}: {err}"),
+            Self::Size(name, (width, height), (expected_width, expected_height)) => write!(
+                f,
+                "block texture {name} is {width}x{height}, not {expected_width}x{expected_height}"
+            ),
+            Self::TooMany(layers) => write!(f, "too many block textures ({layers})"),
+            Self::Empty => write!(f, "no block textures found"),
+        }
+    }
+}
+
+impl Error for TextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Decode(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use wgpu::{
+    include_wgsl, BindGroupDescriptor, BindGroupEntry, BindingResource, Color,
+    CommandEncoderDescriptor, FilterMode, FragmentState, LoadOp, Operations, PrimitiveState,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, SamplerDescriptor,
+    StoreOp, Texture, TextureViewDescriptor, TextureViewDimension, VertexState,
+};
+
+use crate::gfx::Gfx;
+
+// Fill every mip level of every layer of `texture` from the level above,
+// level 0 having been written already. WebGPU has no way of doing it by itself.
+// The texture must be renderable, and filterable in its own format
+pub fn generate(gfx: &Gfx, texture: &Texture) {
+    let descriptor = include_wgsl!("../mipmap.wgsl");
+    let module = gfx.device.create_shader_module(descriptor);
+    let format = texture.format();
+
+    let descriptor = RenderPipelineDescriptor {
+        label: Some("mipmaps"),
+        layout: None,
+        vertex: VertexState {
+            module: &module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: Default::default(),
+        fragment: Some(FragmentState {
+            module: &module,
+            entry_point: "fs_main",
+            targets: &[Some(format.into())],
+        }),
+        multiview: None,
+    };
+
+    let pipeline = gfx.device.create_render_pipeline(&descriptor);
+    let layout = pipeline.get_bind_group_layout(0);
+
+    // Halving a level, every target texel lands right between 2x2 source texels
+    let descriptor = SamplerDescriptor {
+        label: Some("mipmaps"),
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..SamplerDescriptor::default()
+    };
+
+    let sampler = gfx.device.create_sampler(&descriptor);
+
+    let descriptor = CommandEncoderDescriptor {
+        label: Some("mipmaps"),
+    };
+
+    let mut encoder = gfx.device.create_command_encoder(&descriptor);
+
+    for layer in 0..texture.depth_or_array_layers() {
+        let level_view = |level| {
+            let descriptor = TextureViewDescriptor {
+                dimension: Some(TextureViewDimension::D2),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..TextureViewDescriptor::default()
+            };
+
+            texture.create_view(&descriptor)
+        };
+
+        for level in 1..texture.mip_level_count() {
+            let (source, target) = (level_view(level - 1), level_view(level));
+
+            let entries = [
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&source),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+            ];
+
+            let descriptor = BindGroupDescriptor {
+                label: Some("mipmaps"),
+                layout: &layout,
+                entries: &entries,
+            };
+
+            let group = gfx.device.create_bind_group(&descriptor);
+
+            // Every texel gets drawn over, so there is nothing to load
+            let attachment = RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::TRANSPARENT),
+                    store: StoreOp::Store,
+                },
+            };
+
+            let descriptor = RenderPassDescriptor {
+                label: Some("mipmaps"),
+                color_attachments: &[Some(attachment)],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            };
+
+            let mut pass = encoder.begin_render_pass(&descriptor);
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+
+    gfx.queue.submit([encoder.finish()]);
+}
@@ -0,0 +1,62 @@
+// Maps the scene, drawn in linear light and unbounded, onto what the frame can show,
+// drawn as a single triangle covering the whole frame
+
+// How colors end up in the frame, matching `Output` on the CPU side
+const OUTPUT_LINEAR: u32 = 0u;
+const OUTPUT_SRGB: u32 = 1u;
+const OUTPUT_HDR: u32 = 2u;
+
+struct Tonemap {
+    exposure: f32,
+    output: u32,
+}
+
+var<push_constant> tonemap: Tonemap;
+
+@group(0) @binding(0) var scene: texture_2d<f32>;
+@group(0) @binding(1) var scene_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+
+    // Across the scene, which may be drawn at another size than the frame
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32) -> VertexOutput {
+    let uv = vec2(f32((vertex << 1u) & 2u), f32(vertex & 2u));
+    let position = vec4(uv * vec2(2.0, -2.0) + vec2(-1.0, 1.0), 0.0, 1.0);
+    return VertexOutput(position, uv);
+}
+
+// Narkowicz's fit of the ACES filmic curve, brightness going up to 1
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let numerator = color * (2.51 * color + 0.03);
+    let denominator = color * (2.43 * color + 0.59) + 0.14;
+    return saturate(numerator / denominator);
+}
+
+fn srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3(0.0031308));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSampleLevel(scene, scene_sampler, in.uv, 0.0).rgb * tonemap.exposure;
+
+    // Frames able to go past 1 show the scene as it is
+    if tonemap.output == OUTPUT_HDR {
+        return vec4(color, 1.0);
+    }
+
+    let mapped = aces(color);
+
+    if tonemap.output == OUTPUT_SRGB {
+        return vec4(srgb(mapped), 1.0);
+    }
+
+    return vec4(mapped, 1.0);
+}
@@ -0,0 +1,739 @@
+pub mod biome;
+mod bulk;
+mod collide;
+mod edit;
+mod journal;
+mod light;
+mod packed;
+mod pick;
+pub mod region;
+mod snapshot;
+mod streaming;
+mod terrain;
+mod workers;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    mem,
+};
+
+use crate::{
+    buddy::{Buddy, Handle},
+    gfx::Gfx,
+    mesh::{
+        lod::{mesh_lods, LODS},
+        mesh_bounds,
+        upload::Packed,
+        BlockId, Borders, Chunk, ChunkBlocks, Layers, Mesh, Mesher, QuadRef, AIR,
+    },
+    renderer::{ChunkDraw, ChunkOutline, ChunkStatus, Layer, LodDraw},
+};
+
+use self::light::neighbors;
+
+pub use self::{
+    biome::{Biome, Climate},
+    bulk::{BlockBox, BulkEdit, Schematic},
+    collide::Sweep,
+    edit::EditError,
+    journal::Journal,
+    packed::{PackedChunk, PackedLight},
+    pick::Hit,
+    region::{RegionError, RegionStore, Stored},
+    snapshot::{Snapshot, SnapshotError},
+    streaming::Streaming,
+    terrain::terrain,
+    workers::{Generator, Workers},
+};
+
+// Where a chunk sits, counted in chunks rather than blocks
+pub type ChunkPos = [i32; 3];
+
+// Offset and length in quads of the block of the quad buddy every layer of a chunk
+// is in, if any: opaque, translucent and water, then every coarser level of detail
+pub type Placement = [Option<(usize, usize)>; 3 + LODS];
+
+// Chunk a block is in, given in world coordinates, and where in it
+pub fn split(location: [i32; 3]) -> (ChunkPos, [usize; 3]) {
+    let pos = location.map(|c| c.div_euclid(32));
+    let local = location.map(|c| c.rem_euclid(32) as usize);
+    (pos, local)
+}
+
+// How far along a chunk is on its way from blocks to quads
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshState {
+    // Blocks changed since last meshed, or never meshed at all
+    Dirty,
+
+    // Handed out by `ChunkMap::next_dirty`, not back yet
+    Meshing,
+
+    // Quads in the buddy match the blocks
+    Ready,
+}
+
+// A layer of a chunk mesh as laid out in its bindable block of the quad buddy.
+// Layers without a single quad take no block at all, and neither do those
+// that did not fit, which are left out until meshed again
+#[derive(Debug, Default)]
+struct ChunkLayer {
+    packed: Packed,
+    handle: Option<Handle<QuadRef>>,
+
+    // Set aside for the quads of the next mesh, as restored out of a snapshot
+    reserved: Option<Handle<QuadRef>>,
+}
+
+// Blocks of a chunk, packed once meshed and unpacked to be meshed again
+#[derive(Debug)]
+enum Blocks {
+    Loose(Box<Chunk>),
+    Packed(PackedChunk),
+}
+
+impl Blocks {
+    fn get(&self, [x, y, z]: [usize; 3]) -> BlockId {
+        match self {
+            Self::Loose(blocks) => blocks[z][y][x],
+            Self::Packed(packed) => packed.get([x, y, z]),
+        }
+    }
+
+    fn set(&mut self, [x, y, z]: [usize; 3], block: BlockId) {
+        match self {
+            Self::Loose(blocks) => blocks[z][y][x] = block,
+            Self::Packed(packed) => packed.set([x, y, z], block),
+        }
+    }
+
+    // Unpacked in place, if packed
+    fn loose(&mut self) -> &mut Chunk {
+        if let Self::Packed(packed) = self {
+            *self = Self::Loose(packed.unpack());
+        }
+
+        match self {
+            Self::Loose(blocks) => blocks,
+            Self::Packed(_) => unreachable!("just unpacked"),
+        }
+    }
+
+    fn pack(&mut self) {
+        if let Self::Loose(blocks) = self {
+            *self = Self::Packed(PackedChunk::pack(blocks));
+        }
+    }
+
+    fn to_chunk(&self) -> Box<Chunk> {
+        match self {
+            Self::Loose(blocks) => blocks.clone(),
+            Self::Packed(packed) => packed.unpack(),
+        }
+    }
+
+    fn into_chunk(self) -> Box<Chunk> {
+        match self {
+            Self::Loose(blocks) => blocks,
+            Self::Packed(packed) => packed.unpack(),
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        match self {
+            Self::Loose(_) => mem::size_of::<Chunk>(),
+            Self::Packed(packed) => packed.bytes(),
+        }
+    }
+}
+
+// Meshed around as they are, for edits to leave packed blocks packed
+impl ChunkBlocks for Blocks {
+    fn block(&self, local: [usize; 3]) -> BlockId {
+        self.get(local)
+    }
+}
+
+#[derive(Debug)]
+struct ChunkEntry {
+    blocks: Blocks,
+    light: PackedLight,
+    state: MeshState,
+
+    // Edited since last taken to be saved
+    unsaved: bool,
+
+    // Picks the row of the material palette its quads are tinted by
+    biome: Biome,
+
+    // Whatever got meshed last, kept drawn until the next mesh takes its place,
+    // and kept around for edits to remesh around the blocks they change,
+    // along with the box around its quads, in chunk coordinates
+    mesh: Option<Mesh>,
+    bounds: Option<([i32; 3], [i32; 3])>,
+
+    // Opaque, translucent and water, as in `Layer`
+    layers: [ChunkLayer; 3],
+
+    // Opaque quads of every coarser level of detail, as `mesh::lod::mesh_lods` makes them.
+    // Left out whenever its blocks change, until meshed again along with them
+    lods: [ChunkLayer; LODS],
+}
+
+impl ChunkEntry {
+    // Left dirty, and queued for `next_dirty` unless it already was
+    fn mark_dirty(&mut self, pos: ChunkPos, dirty: &mut VecDeque<ChunkPos>) {
+        if self.state != MeshState::Dirty {
+            self.state = MeshState::Dirty;
+            dirty.push_back(pos);
+        }
+    }
+}
+
+// Every chunk loaded, with its blocks and the quads they were meshed into.
+// The quad buddy stays with the caller, to be shared with the renderer,
+// so whatever allocates or frees quads takes it along
+#[derive(Debug, Default)]
+pub struct ChunkMap {
+    chunks: HashMap<ChunkPos, ChunkEntry>,
+
+    // Every dirty chunk, in the order they got dirty, for `next_dirty` to
+    // take them without going through every chunk loaded. Chunks no longer
+    // dirty or loaded may still be in it, to be skipped once taken
+    dirty: VecDeque<ChunkPos>,
+}
+
+impl ChunkMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    // Blocks of a chunk, to be meshed along with the rest of the dirty ones,
+    // returning those it replaces. Quads of the replaced blocks stay drawn until then.
+    // The chunk gets lit right away, along with its neighbors. Those whose side against it
+    // changed, in blocks or light, are left dirty to cull and light their faces against it
+    pub fn insert(&mut self, pos: ChunkPos, blocks: Box<Chunk>) -> Option<Box<Chunk>> {
+        let sides = neighbors(pos).map(|neighbor| self.side(pos, neighbor));
+        let replaced = match self.chunks.get_mut(&pos) {
+            Some(entry) => {
+                entry.mark_dirty(pos, &mut self.dirty);
+                entry.unsaved = false;
+                let blocks = Blocks::Loose(blocks);
+                Some(mem::replace(&mut entry.blocks, blocks).into_chunk())
+            }
+            None => {
+                let entry = ChunkEntry {
+                    blocks: Blocks::Loose(blocks),
+                    light: PackedLight::dark(),
+                    state: MeshState::Dirty,
+                    unsaved: false,
+                    biome: Biome::default(),
+                    mesh: None,
+                    bounds: None,
+                    layers: Default::default(),
+                    lods: Default::default(),
+                };
+
+                self.chunks.insert(pos, entry);
+                self.dirty.push_back(pos);
+                None
+            }
+        };
+
+        self.relight_chunk(pos);
+        for (neighbor, side) in neighbors(pos).into_iter().zip(sides) {
+            if self.side(pos, neighbor) != side {
+                self.mark_dirty(neighbor);
+            }
+        }
+
+        replaced
+    }
+
+    // Unload a chunk, freeing its quads. Its neighbors are left dirty,
+    // for the faces they culled against it to show again
+    pub fn remove(&mut self, quads: &mut Buddy<QuadRef>, pos: ChunkPos) -> Option<Box<Chunk>> {
+        let mut entry = self.chunks.remove(&pos)?;
+        self.mark_neighbors_dirty(pos);
+        free_layers(quads, &mut entry.layers);
+        free_layers(quads, &mut entry.lods);
+        free_reserved(quads, &mut entry.layers);
+        free_reserved(quads, &mut entry.lods);
+        Some(entry.blocks.into_chunk())
+    }
+
+    // Unload every chunk, freeing their quads
+    pub fn clear(&mut self, quads: &mut Buddy<QuadRef>) {
+        let positions: Vec<_> = self.chunks.keys().copied().collect();
+
+        for pos in positions {
+            self.remove(quads, pos);
+        }
+    }
+
+    // Free the quads of every chunk while keeping their blocks, leaving them all dirty,
+    // say to be meshed again into the buddy of a device recreated after being lost
+    pub fn release(&mut self, quads: &mut Buddy<QuadRef>) {
+        for (&pos, entry) in &mut self.chunks {
+            free_layers(quads, &mut entry.layers);
+            free_layers(quads, &mut entry.lods);
+            free_reserved(quads, &mut entry.layers);
+            free_reserved(quads, &mut entry.lods);
+            entry.mesh = None;
+            entry.mark_dirty(pos, &mut self.dirty);
+        }
+    }
+
+    // Every chunk loaded along with its blocks, unpacked one after another,
+    // in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (ChunkPos, Box<Chunk>)> + '_ {
+        let chunks = self.chunks.iter();
+        chunks.map(|(&pos, entry)| (pos, entry.blocks.to_chunk()))
+    }
+
+    // Every chunk loaded, in no particular order
+    pub fn positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn contains(&self, pos: ChunkPos) -> bool {
+        self.chunks.contains_key(&pos)
+    }
+
+    // Blocks of a chunk, unpacked. Blocks are better looked up through `block`
+    // one at a time
+    pub fn blocks(&self, pos: ChunkPos) -> Option<Box<Chunk>> {
+        self.chunks.get(&pos).map(|entry| entry.blocks.to_chunk())
+    }
+
+    // Bytes the blocks of every chunk take, packed or not, their light included
+    pub fn block_bytes(&self) -> usize {
+        let bytes = |entry: &ChunkEntry| entry.blocks.bytes() + entry.light.bytes();
+        self.chunks.values().map(bytes).sum()
+    }
+
+    pub fn biome(&self, pos: ChunkPos) -> Option<Biome> {
+        self.chunks.get(&pos).map(|entry| entry.biome)
+    }
+
+    // Recolor a chunk as `biome` tints it, with no need to mesh it again.
+    // False if the chunk is not loaded. Chunks are loaded as `Biome::default()`
+    pub fn set_biome(&mut self, pos: ChunkPos, biome: Biome) -> bool {
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return false;
+        };
+
+        entry.biome = biome;
+        true
+    }
+
+    // Block at `location` in world coordinates, if its chunk is loaded
+    pub fn block(&self, location: [i32; 3]) -> Option<BlockId> {
+        let (pos, local) = split(location);
+        self.chunks.get(&pos).map(|entry| entry.blocks.get(local))
+    }
+
+    // Blocks of a chunk to be edited, marking it dirty and unsaved right away, along
+    // with its neighbors. Light is left as it was, for `relight_block` or `relight_chunk`
+    // to bring up to date
+    pub fn blocks_mut(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
+        if self.contains(pos) {
+            self.mark_neighbors_dirty(pos);
+        }
+
+        let entry = self.chunks.get_mut(&pos)?;
+        entry.mark_dirty(pos, &mut self.dirty);
+        entry.unsaved = true;
+        Some(entry.blocks.loose())
+    }
+
+    // Blocks of every chunk edited since last taken, to be saved,
+    // taken as saved from then on. Chunks inserted over are taken as saved too
+    pub fn take_unsaved(&mut self) -> Vec<(ChunkPos, Box<Chunk>)> {
+        let unsaved = self.chunks.iter_mut().filter(|(_, entry)| entry.unsaved);
+        let unsaved = unsaved.map(|(&pos, entry)| {
+            entry.unsaved = false;
+            (pos, entry.blocks.to_chunk())
+        });
+
+        unsaved.collect()
+    }
+
+    pub fn state(&self, pos: ChunkPos) -> Option<MeshState> {
+        self.chunks.get(&pos).map(|entry| entry.state)
+    }
+
+    // Whether the quads of every chunk match its blocks
+    pub fn is_meshed(&self) -> bool {
+        self.meshed() == self.len()
+    }
+
+    // Chunks whose quads match their blocks
+    pub fn meshed(&self) -> usize {
+        let states = self.chunks.values().map(|entry| entry.state);
+        states.filter(|&state| state == MeshState::Ready).count()
+    }
+
+    // Have a chunk meshed again, say after its neighbors changed.
+    // False if there is no such chunk
+    pub fn mark_dirty(&mut self, pos: ChunkPos) -> bool {
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return false;
+        };
+
+        entry.mark_dirty(pos, &mut self.dirty);
+        true
+    }
+
+    fn mark_neighbors_dirty(&mut self, pos: ChunkPos) {
+        for neighbor in neighbors(pos) {
+            self.mark_dirty(neighbor);
+        }
+    }
+
+    // Blocks and light of a chunk on its side against `toward`, which the chunk there
+    // is culled and lit against. Air and dark if the chunk is not loaded
+    fn side(&self, pos: ChunkPos, toward: ChunkPos) -> Vec<(BlockId, u8)> {
+        let axis = (0..3).find(|&axis| pos[axis] != toward[axis]).unwrap_or(0);
+        let depth = match toward[axis] > pos[axis] {
+            true => 31,
+            false => 0,
+        };
+
+        let Some(entry) = self.chunks.get(&pos) else {
+            return vec![(AIR, 0); 32 * 32];
+        };
+
+        let layer = (0..32).flat_map(|v| (0..32).map(move |u| (u, v)));
+        let side = layer.map(|(u, v)| {
+            let mut local = [depth; 3];
+            local[(axis + 1) % 3] = u;
+            local[(axis + 2) % 3] = v;
+            (entry.blocks.get(local), entry.light.get(local))
+        });
+
+        side.collect()
+    }
+
+    // Layers of the neighbors of a chunk right against it, for faces on its
+    // boundary to be culled against them. Those not loaded are taken as air
+    pub fn borders(&self, pos: ChunkPos) -> Borders {
+        let neighbors = neighbors(pos).map(|neighbor| self.chunks.get(&neighbor));
+        Borders::from_fn(|facing, local| Some(neighbors[facing as usize]?.blocks.get(local)))
+    }
+
+    // Any dirty chunk, marked as being meshed until its mesh is handed back
+    // through `finish_meshing`. Chunks edited in between are dirty once again.
+    // Its blocks are unpacked to be meshed, until packed again once meshed
+    pub fn next_dirty(&mut self) -> Option<(ChunkPos, &Chunk)> {
+        let pos = loop {
+            let pos = self.dirty.pop_front()?;
+            let state = self.chunks.get(&pos).map(|entry| entry.state);
+            if state == Some(MeshState::Dirty) {
+                break pos;
+            }
+        };
+
+        let entry = self.chunks.get_mut(&pos)?;
+        entry.state = MeshState::Meshing;
+        Some((pos, &*entry.blocks.loose()))
+    }
+
+    // Stage the mesh of a chunk into the quad buddy in place of its previous quads,
+    // drawn once `Buddy::flush_staged` writes them. Meshes of chunks edited while
+    // being meshed are still staged, being closer than what was there, but leave
+    // the chunk dirty. False if the chunk is gone, or its quads do not fit,
+    // in which case it is left dirty to be tried again later
+    pub fn finish_meshing(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        pos: ChunkPos,
+        mesh: Mesh,
+    ) -> bool {
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return false;
+        };
+
+        let _span = tracing::debug_span!("upload", ?pos).entered();
+        let Layers {
+            opaque,
+            translucent,
+            water,
+        } = Layers::split(mesh.clone());
+
+        free_layers(quads, &mut entry.layers);
+        free_layers(quads, &mut entry.lods);
+        entry.bounds = mesh_bounds(&mesh);
+        entry.mesh = Some(mesh);
+
+        let mut fits = true;
+        for (layer, mesh) in entry.layers.iter_mut().zip([opaque, translucent, water]) {
+            layer.packed = Packed::new(&mesh);
+            fits = fits && upload(gfx, quads, layer);
+        }
+
+        // No layer is any good without the others
+        if !fits {
+            free_layers(quads, &mut entry.layers);
+            entry.mesh = None;
+            entry.mark_dirty(pos, &mut self.dirty);
+            return false;
+        }
+
+        if entry.state == MeshState::Meshing {
+            entry.state = MeshState::Ready;
+            entry.blocks.pack();
+        }
+
+        true
+    }
+
+    // Stage the coarser levels of detail of a chunk in place of its previous ones,
+    // meant to go along with `finish_meshing` of the same blocks. Those not fitting
+    // are left out, along with every level past them. False if the chunk is gone
+    pub fn finish_lods(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        pos: ChunkPos,
+        lods: [Mesh; LODS],
+    ) -> bool {
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return false;
+        };
+
+        free_layers(quads, &mut entry.lods);
+        for (layer, mesh) in entry.lods.iter_mut().zip(lods) {
+            layer.packed = Packed::new(&mesh);
+            if !upload(gfx, quads, layer) {
+                break;
+            }
+        }
+
+        true
+    }
+
+    // Same as `insert` and `finish_meshing` in one go, for blocks meshed as they were made,
+    // dark and alone. Those are left dirty only if there is any light on them, or any
+    // block other than air right against them, for their mesh to take them in
+    pub fn insert_meshed(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        pos: ChunkPos,
+        blocks: Box<Chunk>,
+        mesh: Mesh,
+    ) -> bool {
+        self.insert_alone(pos, blocks);
+        self.finish_meshing(gfx, quads, pos, mesh)
+    }
+
+    // The part of `insert_meshed` before its mesh is staged. The chunk is taken as being
+    // meshed unless its mesh would not match, in which case it is left dirty
+    fn insert_alone(&mut self, pos: ChunkPos, blocks: Box<Chunk>) {
+        self.insert(pos, blocks);
+
+        let alone = self.borders(pos) == Borders::NONE && self.light_grid(pos).is_dark();
+        if let (true, Some(entry)) = (alone, self.chunks.get_mut(&pos)) {
+            entry.state = MeshState::Meshing;
+        }
+    }
+
+    // Mesh up to `budget` dirty chunks right here, their quads staged as in
+    // `finish_meshing`, returning how many got meshed. Faces against neighbors
+    // loaded are culled, as far as the mesher can, and kept against the rest
+    pub fn mesh_dirty(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        mesher: &dyn Mesher,
+        budget: usize,
+    ) -> usize {
+        let mut meshed = 0;
+
+        while meshed < budget {
+            let Some((pos, _)) = self.next_dirty() else {
+                break;
+            };
+
+            let light = self.light_grid(pos);
+            let borders = self.borders(pos);
+            let Blocks::Loose(blocks) = &self.chunks[&pos].blocks else {
+                unreachable!("unpacked by next_dirty");
+            };
+
+            let mesh = || {
+                let mesh = mesher.mesh_lit_with_borders(blocks, &borders, &light);
+                (mesh, mesh_lods(mesher, blocks))
+            };
+
+            let (mesh, lods) = tracing::debug_span!("mesh", ?pos).in_scope(mesh);
+            if !self.finish_meshing(gfx, quads, pos, mesh) {
+                break;
+            }
+
+            self.finish_lods(gfx, quads, pos, lods);
+
+            meshed += 1;
+        }
+
+        meshed
+    }
+
+    // Outlines of every chunk, for `DebugView::Chunks` to show how far along they are
+    pub fn outlines(&self) -> impl Iterator<Item = ChunkOutline> + '_ {
+        self.chunks.iter().map(|(pos, entry)| {
+            let origin = pos.map(|c| c as f32 * 32.0);
+            let offset = |corner: [i32; 3]| std::array::from_fn(|i| origin[i] + corner[i] as f32);
+            let quads = entry.mesh.as_ref().and(entry.bounds);
+
+            ChunkOutline {
+                origin,
+                quads: quads.map(|(min, max)| (offset(min), offset(max))),
+                status: match entry.state {
+                    MeshState::Dirty => ChunkStatus::Dirty,
+                    MeshState::Meshing => ChunkStatus::Meshing,
+                    MeshState::Ready => ChunkStatus::Uploaded,
+                },
+            }
+        })
+    }
+
+    // Draws for every chunk with quads to show, opaque layers first, whatever their state
+    pub fn renderable(&self) -> impl Iterator<Item = ChunkDraw<'_>> {
+        Layer::ALL.into_iter().flat_map(|layer| self.draws(layer))
+    }
+
+    fn draws(&self, layer: Layer) -> impl Iterator<Item = ChunkDraw<'_>> {
+        self.chunks.iter().filter_map(move |(&[x, y, z], entry)| {
+            let quads = &entry.layers[layer as usize];
+            let lods = entry.lods.each_ref().map(|lod| {
+                let handle = lod.handle.as_ref()?;
+                let facings = &lod.packed.facings;
+                (layer == Layer::Opaque).then_some(LodDraw { handle, facings })
+            });
+
+            let draw = ChunkDraw {
+                handle: quads.handle.as_ref()?,
+                facings: &quads.packed.facings,
+                origin: [x as f32 * 32.0, y as f32 * 32.0, z as f32 * 32.0],
+                layer,
+                palette: entry.biome as u32,
+                lods,
+            };
+
+            Some(draw)
+        })
+    }
+}
+
+// Stage a layer with no block into a new one, if it has any quads,
+// to be written once the quad buddy flushes them. False if they do not fit.
+// Any block set aside for the layer is taken, if the quads still fit in it
+fn upload(gfx: &Gfx, quads: &mut Buddy<QuadRef>, layer: &mut ChunkLayer) -> bool {
+    let packed = &layer.packed;
+    let reserved = layer.reserved.take();
+    let len = packed.quads.len();
+
+    let handle = match reserved {
+        Some(handle) if len > 0 && len <= quads.len(&handle) => Some(handle),
+        Some(handle) => {
+            quads.free(handle);
+            None
+        }
+        None => None,
+    };
+
+    if len == 0 {
+        return true;
+    }
+
+    let Some(handle) = handle.or_else(|| quads.alloc_bindable(gfx, len)) else {
+        return false;
+    };
+
+    quads.stage_at(&handle, 0, &packed.quads);
+    layer.handle = Some(handle);
+    true
+}
+
+fn free_layers(quads: &mut Buddy<QuadRef>, layers: &mut [ChunkLayer]) {
+    for handle in layers.iter_mut().filter_map(|layer| layer.handle.take()) {
+        quads.free(handle);
+    }
+}
+
+fn free_reserved(quads: &mut Buddy<QuadRef>, layers: &mut [ChunkLayer]) {
+    for handle in layers.iter_mut().filter_map(|layer| layer.reserved.take()) {
+        quads.free(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::LAMP;
+
+    fn filled(block: BlockId) -> Box<Chunk> {
+        Box::new([[[block; 32]; 32]; 32])
+    }
+
+    // Chunks streamed in as workers make them, meshed dark and alone, then every
+    // chunk left dirty meshed again after each. Returns how many meshes it took
+    fn stream(
+        map: &mut ChunkMap,
+        chunks: impl IntoIterator<Item = (ChunkPos, Box<Chunk>)>,
+    ) -> usize {
+        let mut meshes = 0;
+
+        for (pos, blocks) in chunks {
+            map.insert_alone(pos, blocks);
+            meshes += 1;
+
+            // As `finish_meshing` does
+            let entry = map.chunks.get_mut(&pos).unwrap();
+            if entry.state == MeshState::Meshing {
+                entry.state = MeshState::Ready;
+            }
+
+            while let Some((pos, _)) = map.next_dirty() {
+                map.chunks.get_mut(&pos).unwrap().state = MeshState::Ready;
+                meshes += 1;
+            }
+        }
+
+        meshes
+    }
+
+    #[test]
+    fn streaming_a_column_meshes_only_what_changed() {
+        // Ground with air above, only the air right on top of it meshed twice
+        let ground = |y| match y {
+            0 => 1,
+            _ => AIR,
+        };
+        let column = || (0..4).map(|y| ([0, y, 0], filled(ground(y))));
+
+        let mut map = ChunkMap::new();
+        assert_eq!(stream(&mut map, column()), 5);
+        assert!(map.is_meshed());
+
+        let mut down = ChunkMap::new();
+        assert_eq!(stream(&mut down, column().rev()), 5);
+
+        // A lamp meshed in the dark, and the chunk below it lights up
+        let mut lamp = filled(AIR);
+        lamp[16][0][16] = LAMP;
+        assert_eq!(stream(&mut map, [([0, 4, 0], lamp)]), 3);
+        assert!(map.is_meshed());
+    }
+}
@@ -0,0 +1,143 @@
+use super::ChunkPos;
+use crate::{
+    mesh::{palette, BlockId, GRASS, LEAVES, SAND, SNOW, WATER},
+    renderer::MATERIALS,
+};
+
+// Chunks between points of the climate noise, so biomes span several chunks
+const CLIMATE_SCALE: i32 = 6;
+
+// How hot and how wet it is over a chunk column, both from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+impl Climate {
+    // Smooth noise of its own for either, the same for every seed and column
+    pub fn at(seed: u64, [x, _, z]: ChunkPos) -> Self {
+        Self {
+            temperature: noise(seed, x, z),
+            humidity: noise(seed ^ 0x5DEE_CE66_D1CE_5EED, x, z),
+        }
+    }
+}
+
+// Which blocks a chunk column is made of, and how they are tinted.
+// Also a row of the material palette, see `Biome::palette`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum Biome {
+    #[default]
+    Plains,
+    Forest,
+    Desert,
+    Tundra,
+    Swamp,
+}
+
+impl Biome {
+    pub const ALL: [Self; 5] = [
+        Self::Plains,
+        Self::Forest,
+        Self::Desert,
+        Self::Tundra,
+        Self::Swamp,
+    ];
+
+    // Biome of a chunk column, out of its climate
+    pub fn at(seed: u64, pos: ChunkPos) -> Self {
+        Self::from_climate(Climate::at(seed, pos))
+    }
+
+    pub fn from_climate(climate: Climate) -> Self {
+        match (climate.temperature, climate.humidity) {
+            (t, _) if t < 0.3 => Self::Tundra,
+            (t, h) if t > 0.65 && h < 0.45 => Self::Desert,
+            (_, h) if h > 0.7 => Self::Swamp,
+            (_, h) if h > 0.5 => Self::Forest,
+            _ => Self::Plains,
+        }
+    }
+
+    // Block on top of the ground, then the one filling it in under it
+    pub const fn blocks(self) -> (BlockId, BlockId) {
+        match self {
+            Self::Plains | Self::Forest | Self::Swamp => (GRASS, 3),
+            Self::Desert => (SAND, SAND),
+            Self::Tundra => (SNOW, 3),
+        }
+    }
+
+    // What `block` gets multiplied by, sRGB-encoded. White leaves it as it is
+    pub const fn tint(self, block: BlockId) -> [u8; 3] {
+        match (self, block) {
+            (Self::Forest, GRASS | LEAVES) => [150, 200, 120],
+            (Self::Swamp, GRASS | LEAVES) => [160, 170, 90],
+            (Self::Tundra, GRASS | LEAVES) => [200, 220, 210],
+            (Self::Swamp, WATER) => [150, 170, 110],
+            (Self::Tundra, WATER) => [210, 235, 255],
+            _ => [255; 3],
+        }
+    }
+
+    // Its row of the material palette, an entry per block id. Rewriting
+    // the row recolors every chunk of the biome without meshing any again
+    pub fn palette(self) -> Vec<u32> {
+        let tints = (0..MATERIALS as BlockId).map(|block| self.tint(block));
+        tints.map(|tint| palette::entry(0, tint)).collect()
+    }
+}
+
+// Value noise from 0 to 1, bilinearly blended between points `CLIMATE_SCALE`
+// chunks apart, each at random
+fn noise(seed: u64, x: i32, z: i32) -> f32 {
+    let (cell_x, cell_z) = (x.div_euclid(CLIMATE_SCALE), z.div_euclid(CLIMATE_SCALE));
+    let scale = CLIMATE_SCALE as f32;
+    let u = smooth(x.rem_euclid(CLIMATE_SCALE) as f32 / scale);
+    let v = smooth(z.rem_euclid(CLIMATE_SCALE) as f32 / scale);
+
+    let corner = |dx, dz| point(seed, cell_x + dx, cell_z + dz);
+    let near = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * u;
+    let far = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * u;
+    near + (far - near) * v
+}
+
+pub(super) fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// From 0 to 1, the same for every seed and point
+fn point(seed: u64, x: i32, z: i32) -> f32 {
+    let x = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let z = (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    let mut hash = seed ^ x ^ z.rotate_left(32);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash ^= hash >> 33;
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn biomes_span_several_chunks() {
+        let biomes: Vec<_> = (0..64).map(|x| Biome::at(7, [x, 0, 0])).collect();
+        let changes = biomes.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        assert!(changes < 64 / 3, "{changes} biome changes over 64 chunks");
+
+        assert_eq!(Biome::at(7, [5, 0, 9]), Biome::at(7, [5, 3, 9]));
+    }
+
+    #[test]
+    fn every_biome_shows_up() {
+        let area = (-64..64).flat_map(|x| (-64..64).map(move |z| [x, 0, z]));
+        let found: HashSet<_> = area.map(|pos| Biome::at(1, pos)).collect();
+        assert_eq!(found.len(), Biome::ALL.len());
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashSet;
+
+use super::{split, ChunkMap, ChunkPos};
+use crate::mesh::BlockId;
+
+// Box of blocks in world coordinates, from `min` up to but not including `max`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockBox {
+    pub min: [i32; 3],
+    pub max: [i32; 3],
+}
+
+impl BlockBox {
+    // From one corner block to the other, both included, in any order
+    pub fn new(a: [i32; 3], b: [i32; 3]) -> Self {
+        Self {
+            min: std::array::from_fn(|axis| a[axis].min(b[axis])),
+            max: std::array::from_fn(|axis| a[axis].max(b[axis]) + 1),
+        }
+    }
+
+    // `size` blocks along every axis, from `origin` up
+    pub fn sized(origin: [i32; 3], size: [usize; 3]) -> Self {
+        Self {
+            min: origin,
+            max: std::array::from_fn(|axis| origin[axis] + size[axis] as i32),
+        }
+    }
+
+    pub fn size(&self) -> [usize; 3] {
+        std::array::from_fn(|axis| (self.max[axis] - self.min[axis]).max(0) as usize)
+    }
+
+    pub fn volume(&self) -> usize {
+        self.size().iter().product()
+    }
+
+    pub fn contains(&self, location: [i32; 3]) -> bool {
+        (0..3).all(|axis| (self.min[axis]..self.max[axis]).contains(&location[axis]))
+    }
+
+    // Every chunk with any block of the box in it, loaded or not
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkPos> {
+        let empty = self.volume() == 0;
+        let (min, _) = split(self.min);
+        let (max, _) = split(self.max.map(|c| c - 1));
+        let range = move |axis: usize| match empty {
+            true => 0..0,
+            false => min[axis]..max[axis] + 1,
+        };
+
+        let (xs, ys, zs) = (range(0), range(1), range(2));
+        zs.flat_map(move |z| {
+            let xs = xs.clone();
+            let row = move |y| xs.clone().map(move |x| [x, y, z]);
+            ys.clone().flat_map(row)
+        })
+    }
+}
+
+// Blocks copied out of the world, to be pasted back somewhere else.
+// Cells left empty keep whatever is there when pasted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schematic {
+    size: [usize; 3],
+
+    // Indexed as `[z][y][x]`, as chunks are, x varying fastest
+    blocks: Vec<Option<BlockId>>,
+}
+
+impl Schematic {
+    // `size` blocks along every axis, every one of them empty
+    pub fn new(size: [usize; 3]) -> Self {
+        Self {
+            size,
+            blocks: vec![None; size.iter().product()],
+        }
+    }
+
+    pub const fn size(&self) -> [usize; 3] {
+        self.size
+    }
+
+    pub fn get(&self, [x, y, z]: [usize; 3]) -> Option<BlockId> {
+        self.blocks[self.index([x, y, z])?]
+    }
+
+    // Panics if `at` is past the size
+    pub fn set(&mut self, at: [usize; 3], block: Option<BlockId>) {
+        let index = self.index(at).expect("block outside of the schematic");
+        self.blocks[index] = block;
+    }
+
+    fn index(&self, [x, y, z]: [usize; 3]) -> Option<usize> {
+        let [width, height, depth] = self.size;
+        let inside = x < width && y < height && z < depth;
+        inside.then_some((z * height + y) * width + x)
+    }
+}
+
+// What a bulk edit did: how many blocks it changed, and the chunks they are in,
+// in order. Those are dirty and unsaved, along with any whose light changed
+// and their neighbors, whose faces against them may change, to be meshed again as every dirty chunk is and saved along with the rest
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BulkEdit {
+    pub blocks: usize,
+    pub chunks: Vec<ChunkPos>,
+}
+
+// Edits of many blocks at once, as for building or terraforming. Rather than going
+// through `set_block` block after block, remeshing and rewriting quads around every
+// one of them, blocks are changed a chunk at a time and every chunk changed is left
+// dirty, to be meshed once in full. Light is brought up to date once for all of them
+impl ChunkMap {
+    // Set every block in `area` to `block`. Blocks in chunks not loaded are left out
+    pub fn fill(&mut self, area: BlockBox, block: BlockId) -> BulkEdit {
+        self.edit_area(area, |_, _| Some(block))
+    }
+
+    // Set every `from` block in `area` to `to`, leaving every other block as it is
+    pub fn replace(&mut self, area: BlockBox, from: BlockId, to: BlockId) -> BulkEdit {
+        self.edit_area(area, |_, old| (old == from).then_some(to))
+    }
+
+    // Blocks of `schematic` with its lowest corner at `origin`, leaving
+    // those under its empty cells as they are
+    pub fn paste(&mut self, schematic: &Schematic, origin: [i32; 3]) -> BulkEdit {
+        let area = BlockBox::sized(origin, schematic.size());
+        self.edit_area(area, |location, _| {
+            let at = std::array::from_fn(|axis| (location[axis] - origin[axis]) as usize);
+            schematic.get(at)
+        })
+    }
+
+    // Blocks in `area`, those in chunks not loaded left empty
+    pub fn copy(&self, area: BlockBox) -> Schematic {
+        let mut schematic = Schematic::new(area.size());
+        for pos in area.chunks() {
+            let Some(entry) = self.chunks.get(&pos) else {
+                continue;
+            };
+
+            for_each_block(area, pos, |location, local| {
+                let at = std::array::from_fn(|axis| (location[axis] - area.min[axis]) as usize);
+                schematic.set(at, Some(entry.blocks.get(local)));
+            });
+        }
+
+        schematic
+    }
+
+    // Change blocks in `area` to whatever `edit` gives for them out of their
+    // location and the block there, if anything
+    fn edit_area(
+        &mut self,
+        area: BlockBox,
+        mut edit: impl FnMut([i32; 3], BlockId) -> Option<BlockId>,
+    ) -> BulkEdit {
+        let mut changed = HashSet::new();
+        let mut blocks = 0;
+
+        for pos in area.chunks() {
+            let Some(entry) = self.chunks.get_mut(&pos) else {
+                continue;
+            };
+
+            let before = blocks;
+            // Packed or not, blocks are changed in place
+            for_each_block(area, pos, |location, local| {
+                let old = entry.blocks.get(local);
+                match edit(location, old) {
+                    Some(new) if new != old => {
+                        entry.blocks.set(local, new);
+                        blocks += 1;
+                    }
+                    _ => {}
+                }
+            });
+
+            // Meshes on their way back are out of date as well
+            if blocks > before {
+                entry.mark_dirty(pos, &mut self.dirty);
+                entry.unsaved = true;
+                changed.insert(pos);
+            }
+        }
+
+        if !changed.is_empty() {
+            self.relight_chunks(&changed);
+        }
+
+        for &pos in &changed {
+            self.mark_neighbors_dirty(pos);
+        }
+
+        let mut chunks: Vec<_> = changed.into_iter().collect();
+        chunks.sort_unstable();
+        BulkEdit { blocks, chunks }
+    }
+}
+
+// Every block of `area` in the chunk at `pos`, in world and chunk coordinates
+fn for_each_block(area: BlockBox, pos: ChunkPos, mut f: impl FnMut([i32; 3], [usize; 3])) {
+    let origin = pos.map(|c| c * 32);
+    let range = |axis: usize| {
+        let min = (area.min[axis] - origin[axis]).clamp(0, 32);
+        let max = (area.max[axis] - origin[axis]).clamp(0, 32);
+        min as usize..max as usize
+    };
+
+    for z in range(2) {
+        for y in range(1) {
+            for x in range(0) {
+                let local = [x, y, z];
+                let location = std::array::from_fn(|axis| origin[axis] + local[axis] as i32);
+                f(location, local);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mesh::{Chunk, AIR, LAMP, WATER},
+        world::MeshState,
+    };
+
+    fn empty() -> Box<Chunk> {
+        Box::new([[[AIR; 32]; 32]; 32])
+    }
+
+    #[test]
+    fn bulk_edits_touch_every_chunk_once() {
+        let mut map = ChunkMap::new();
+        for x in -1..=1 {
+            map.insert([x, 0, 0], empty());
+        }
+
+        // Across both borders, and into a chunk not loaded
+        let area = BlockBox::new([40, 4, 0], [-10, 9, 40]);
+        assert_eq!(area.size(), [51, 6, 41]);
+        assert_eq!(area.chunks().count(), 3 * 2);
+
+        let filled = map.fill(area, 1);
+        assert_eq!(filled.blocks, 51 * 6 * 32);
+        assert_eq!(filled.chunks, vec![[-1, 0, 0], [0, 0, 0], [1, 0, 0]]);
+        assert_eq!(map.block([-10, 4, 31]), Some(1));
+        assert_eq!(map.block([40, 9, 0]), Some(1));
+        assert_eq!(map.block([41, 9, 0]), Some(AIR));
+        assert_eq!(map.state([0, 0, 0]), Some(MeshState::Dirty));
+        assert_eq!(map.take_unsaved().len(), 3);
+
+        // Nothing to do is nothing done
+        assert_eq!(map.fill(area, 1), BulkEdit::default());
+        let replaced = map.replace(BlockBox::new([0, 0, 0], [31, 31, 31]), 1, WATER);
+        assert_eq!(replaced.blocks, 32 * 6 * 32);
+        assert_eq!(replaced.chunks, vec![[0, 0, 0]]);
+    }
+
+    #[test]
+    fn pastes_put_back_what_was_copied() {
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], empty());
+        map.insert([1, 0, 0], empty());
+        map.fill(BlockBox::new([2, 0, 2], [4, 1, 4]), 1);
+        map.fill(BlockBox::new([3, 3, 3], [3, 3, 3]), LAMP);
+
+        let mut copied = map.copy(BlockBox::new([2, 0, 2], [4, 3, 4]));
+        assert_eq!(copied.size(), [3, 4, 3]);
+        assert_eq!(copied.get([1, 3, 1]), Some(LAMP));
+        assert_eq!(copied.get([3, 0, 0]), None);
+
+        // Across a border, air pasted over air changing nothing
+        // and empty cells keeping what is there
+        copied.set([0, 0, 0], None);
+        map.fill(BlockBox::new([30, 0, 2], [30, 0, 2]), WATER);
+        let pasted = map.paste(&copied, [30, 0, 2]);
+        assert_eq!(pasted.blocks, 3 * 2 * 3 - 1 + 1);
+        assert_eq!(pasted.chunks, vec![[0, 0, 0], [1, 0, 0]]);
+        assert_eq!(map.block([30, 0, 2]), Some(WATER));
+        assert_eq!(map.block([32, 1, 4]), Some(1));
+
+        // The lamp pasted lights its surroundings, the chunk past it too
+        assert_eq!(map.block([31, 3, 3]), Some(LAMP));
+        assert_eq!(map.light([32, 3, 3]), 13);
+
+        // Gone along with it, on both sides
+        map.replace(BlockBox::new([0, 0, 0], [63, 31, 31]), LAMP, AIR);
+        assert_eq!(map.light([31, 3, 3]), 0);
+        assert_eq!(map.light([32, 3, 3]), 0);
+    }
+}
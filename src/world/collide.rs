@@ -0,0 +1,170 @@
+use super::{split, Blocks, ChunkMap, ChunkPos};
+use crate::{math::Aabb, mesh::pick::is_pickable};
+
+// Where a box got to, and the axes along which blocks stopped it short
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sweep {
+    pub aabb: Aabb,
+    pub blocked: [bool; 3],
+}
+
+// Boxes collide with whatever rays stop at, so water is moved through and glass is not.
+// Chunks not loaded are taken as solid, for nothing to fall out of the world
+// while the chunks under it are still on their way
+impl ChunkMap {
+    // Whether `aabb` overlaps any block it collides with
+    pub fn collides(&self, aabb: &Aabb) -> bool {
+        let mut solid = Solid::new(self);
+        aabb.blocks(2).any(|z| {
+            let mut rows = aabb.blocks(1);
+            rows.any(|y| aabb.blocks(0).any(|x| solid.at([x, y, z])))
+        })
+    }
+
+    // Move `aabb` by `motion` for as far as blocks let it, along y first and then
+    // along x and z, so it slides along floors and walls rather than sticking to them.
+    // Blocks already overlapped are let through, for boxes stuck in them to get out
+    pub fn sweep(&self, mut aabb: Aabb, motion: [f32; 3]) -> Sweep {
+        let mut solid = Solid::new(self);
+        let mut blocked = [false; 3];
+
+        for axis in [1, 0, 2] {
+            let distance = motion[axis];
+            if distance == 0.0 {
+                continue;
+            }
+
+            let Some(face) = solid.sweep(&aabb, axis, distance) else {
+                let mut offset = [0.0; 3];
+                offset[axis] = distance;
+                aabb = aabb.translated(offset);
+                continue;
+            };
+
+            // Right against the block, not a rounding error into it
+            let size = aabb.max[axis] - aabb.min[axis];
+            if distance > 0.0 {
+                (aabb.min[axis], aabb.max[axis]) = (face - size, face);
+            } else {
+                (aabb.min[axis], aabb.max[axis]) = (face, face + size);
+            }
+
+            blocked[axis] = true;
+        }
+
+        Sweep { aabb, blocked }
+    }
+}
+
+// Blocks looked up across chunks, the chunk last looked up kept at hand,
+// as most blocks a box goes through are in the same chunk as the one before
+struct Solid<'a> {
+    map: &'a ChunkMap,
+    last: Option<(ChunkPos, Option<&'a Blocks>)>,
+}
+
+impl<'a> Solid<'a> {
+    fn new(map: &'a ChunkMap) -> Self {
+        Self { map, last: None }
+    }
+
+    fn at(&mut self, location: [i32; 3]) -> bool {
+        let (pos, local) = split(location);
+        let blocks = match self.last {
+            Some((last, blocks)) if last == pos => blocks,
+            _ => {
+                let blocks = self.map.chunks.get(&pos).map(|entry| &entry.blocks);
+                self.last.insert((pos, blocks)).1
+            }
+        };
+
+        blocks.is_none_or(|blocks| is_pickable(blocks.get(local)))
+    }
+
+    // Face of the first block in the way of `aabb` moving `distance` along `axis`,
+    // going through the layers of blocks ahead of it one after another
+    fn sweep(&mut self, aabb: &Aabb, axis: usize, distance: f32) -> Option<f32> {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let forward = distance > 0.0;
+
+        let (face, mut layer) = match forward {
+            true => (aabb.max[axis], aabb.max[axis].ceil() as i32),
+            false => (aabb.min[axis], aabb.min[axis].floor() as i32 - 1),
+        };
+
+        let reached = face + distance;
+        loop {
+            // Face of the layer the box would run into
+            let near = (layer + !forward as i32) as f32;
+            if (forward && near >= reached) || (!forward && near <= reached) {
+                return None;
+            }
+
+            let hit = aabb.blocks(u).any(|a| {
+                aabb.blocks(v).any(|b| {
+                    let mut location = [layer; 3];
+                    (location[u], location[v]) = (a, b);
+                    self.at(location)
+                })
+            });
+
+            if hit {
+                return Some(near);
+            }
+
+            layer += if forward { 1 } else { -1 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{AIR, WATER};
+
+    // Ground up to y = 4, a wall along x = 8 and water over z = 4 and beyond
+    fn map() -> ChunkMap {
+        let mut blocks = Box::new([[[AIR; 32]; 32]; 32]);
+        for (z, plane) in blocks.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (x, block) in row.iter_mut().enumerate() {
+                    *block = match (x, y, z) {
+                        (_, 0..=3, _) | (8, _, _) => 1,
+                        (_, 4, 4..) => WATER,
+                        _ => AIR,
+                    };
+                }
+            }
+        }
+
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], blocks);
+        map
+    }
+
+    #[test]
+    fn boxes_stop_against_blocks() {
+        let map = map();
+        let aabb = Aabb::standing([4.5, 6.3, 1.5], 0.6, 1.8);
+
+        let fallen = map.sweep(aabb, [0.0, -3.0, 0.0]);
+        assert_eq!(fallen.aabb.min[1], 4.0);
+        assert_eq!(fallen.blocked, [false, true, false]);
+
+        // Sliding along the wall, into the water
+        let moved = map.sweep(fallen.aabb, [5.0, 0.0, 5.0]);
+        assert_eq!(moved.aabb.max[0], 8.0);
+        assert!((moved.aabb.min[2] - 6.2).abs() < 1e-5);
+        assert_eq!(moved.blocked, [true, false, false]);
+        assert!(!map.collides(&moved.aabb));
+    }
+
+    #[test]
+    fn unloaded_chunks_are_solid() {
+        let map = map();
+        let aabb = Aabb::standing([4.5, 30.0, 1.5], 0.6, 1.8);
+        let sweep = map.sweep(aabb, [0.0, 3.0, 0.0]);
+        assert_eq!(sweep.aabb.max[1], 32.0);
+        assert!(map.collides(&Aabb::standing([-0.5, 10.0, 1.5], 0.6, 1.8)));
+    }
+}
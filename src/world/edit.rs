@@ -0,0 +1,315 @@
+use std::{error::Error, fmt::Display, ops::Range};
+
+use super::{
+    free_layers, neighbors, split, ChunkEntry, ChunkLayer, ChunkMap, ChunkPos, Hit, MeshState,
+};
+use crate::{
+    buddy::Buddy,
+    gfx::Gfx,
+    mesh::{
+        mesh_bounds, pick::is_pickable, remesh::remesh_block, upload::Packed, BlockId, Borders,
+        ChunkBlocks, Layers, Mesh, QuadRef, AIR,
+    },
+};
+
+// Unchanged runs of quads shorter than this are rewritten along with their neighbors
+const GAP: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditError {
+    // Chunk the block would be in
+    NotLoaded(ChunkPos),
+
+    // Where a block would be placed, taken by another
+    Occupied([i32; 3]),
+
+    // Ray started inside the block hit, so there is no face to place against
+    NoFace,
+
+    // The chunk is left out until meshed again
+    OutOfQuads,
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotLoaded([x, y, z]) => write!(f, "chunk {x} {y} {z} is not loaded"),
+            Self::Occupied([x, y, z]) => write!(f, "block at {x} {y} {z} is taken"),
+            Self::NoFace => write!(f, "no face to place a block against"),
+            Self::OutOfQuads => write!(f, "out of room for quads"),
+        }
+    }
+}
+
+impl Error for EditError {}
+
+impl ChunkMap {
+    // Change the block at `location`, in world coordinates, remeshing right away
+    // around it alone and rewriting only the quads that changed, for the edit
+    // to show up the very next frame. Returns how many quads got written.
+    // Chunks never meshed are just left dirty, and so are those being meshed,
+    // whose meshes on their way back are already out of date. Chunks whose light
+    // changed along are left dirty too, to be meshed again in full, and so are
+    // the neighbors a block on the boundary is against, to cull their faces anew
+    pub fn set_block(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        location: [i32; 3],
+        block: BlockId,
+    ) -> Result<usize, EditError> {
+        let (pos, local) = split(location);
+        let borders = self.borders(pos);
+        let entry = self.chunks.get_mut(&pos).ok_or(EditError::NotLoaded(pos))?;
+        let was = entry.state;
+        let written = write_block(gfx, quads, entry, &borders, local, block);
+
+        // Queued for `next_dirty`, if left dirty
+        if was != MeshState::Dirty && entry.state == MeshState::Dirty {
+            self.dirty.push_back(pos);
+        }
+
+        for (neighbor, _) in neighbors(location).map(split) {
+            if neighbor != pos {
+                self.mark_dirty(neighbor);
+            }
+        }
+
+        self.relight_block(location);
+        written
+    }
+
+    // Break the block hit, as `set_block` does
+    pub fn break_block(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        hit: &Hit,
+    ) -> Result<usize, EditError> {
+        self.set_block(gfx, quads, hit.location, AIR)
+    }
+
+    // Place `block` against the face hit, as `set_block` does,
+    // as long as nothing but air or water is there
+    pub fn place_block(
+        &mut self,
+        gfx: &Gfx,
+        quads: &mut Buddy<QuadRef>,
+        hit: &Hit,
+        block: BlockId,
+    ) -> Result<usize, EditError> {
+        let location = hit.adjacent().ok_or(EditError::NoFace)?;
+        if self.block(location).is_some_and(is_pickable) {
+            return Err(EditError::Occupied(location));
+        }
+
+        self.set_block(gfx, quads, location, block)
+    }
+}
+
+// Set a block of a chunk, remeshing around it and rewriting what changed if it has
+// a mesh, leaving it dirty otherwise. Its coarser levels of detail are let go of
+// until meshed again in full. Returns how many quads got written
+fn write_block(
+    gfx: &Gfx,
+    quads: &mut Buddy<QuadRef>,
+    entry: &mut ChunkEntry,
+    borders: &Borders,
+    local: [usize; 3],
+    block: BlockId,
+) -> Result<usize, EditError> {
+    entry.unsaved = true;
+    entry.blocks.set(local, block);
+    free_layers(quads, &mut entry.lods);
+
+    let Some(mesh) = &mut entry.mesh else {
+        entry.state = MeshState::Dirty;
+        return Ok(0);
+    };
+
+    if entry.state == MeshState::Meshing {
+        entry.state = MeshState::Dirty;
+    }
+
+    let packed = entry.layers.each_mut().map(|layer| &mut layer.packed);
+    let ranges = remesh(&entry.blocks, borders, mesh, packed, local);
+    entry.bounds = mesh_bounds(mesh);
+
+    let mut written = 0;
+    for (layer, ranges) in entry.layers.iter_mut().zip(ranges) {
+        match rewrite(gfx, quads, layer, &ranges) {
+            Some(count) => written += count,
+            None => {
+                entry.state = MeshState::Dirty;
+                return Err(EditError::OutOfQuads);
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+// Remesh a meshed chunk around a block already set, packed or not,
+// bringing every layer along. Returns the ranges of each layer to write again
+fn remesh(
+    blocks: &impl ChunkBlocks,
+    borders: &Borders,
+    mesh: &mut Mesh,
+    packed: [&mut Packed; 3],
+    [x, y, z]: [usize; 3],
+) -> [Vec<Range<usize>>; 3] {
+    remesh_block(mesh, blocks, borders, (x as i32, y as i32, z as i32));
+
+    let Layers {
+        opaque,
+        translucent,
+        water,
+    } = Layers::split(mesh.clone());
+
+    let [opaque_packed, translucent_packed, water_packed] = packed;
+    let opaque = opaque_packed.update(&opaque, GAP);
+    let translucent = translucent_packed.update(&translucent, GAP);
+    let water = water_packed.update(&water, GAP);
+    [opaque, translucent, water]
+}
+
+// Write the ranges of a layer that changed in place, or the whole layer
+// into a new block if it outgrew its own or had none. Returns how many quads
+// got written, None if they do not fit, leaving the layer with no block
+fn rewrite(
+    gfx: &Gfx,
+    quads: &mut Buddy<QuadRef>,
+    layer: &mut ChunkLayer,
+    ranges: &[Range<usize>],
+) -> Option<usize> {
+    let len = layer.packed.quads.len();
+
+    match &layer.handle {
+        Some(handle) if len > 0 && len <= quads.len(handle) => {
+            // Behind the whole layer, if it is still waiting to be written
+            let staged = quads.is_staged(handle);
+            for range in ranges {
+                let changed = &layer.packed.quads[range.clone()];
+                match staged {
+                    true => quads.stage_at(handle, range.start, changed),
+                    false => {
+                        quads.write_at(gfx, handle, range.start, changed);
+                    }
+                }
+            }
+
+            Some(ranges.iter().map(Range::len).sum())
+        }
+        _ => {
+            if let Some(handle) = layer.handle.take() {
+                quads.free(handle);
+            }
+
+            // Written right away rather than staged, for edits to show at once
+            if len > 0 {
+                let handle = quads.alloc_bindable(gfx, len)?;
+                quads.write(gfx, &handle, &layer.packed.quads);
+                layer.handle = Some(handle);
+            }
+
+            Some(len)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+    use crate::mesh::{greedy, Chunk, Mesher, WATER};
+
+    // Well under a frame at 60 frames per second, even unoptimized
+    const BUDGET: Duration = Duration::from_millis(4);
+
+    // Rolling stone hills, with a pool of water in between
+    fn terrain() -> Box<Chunk> {
+        let mut blocks = Box::new([[[AIR; 32]; 32]; 32]);
+
+        for z in 0..32 {
+            for x in 0..32 {
+                let height = 8.0 + (x as f32 * 0.4).sin() * 3.0 + (z as f32 * 0.3).cos() * 3.0;
+                for y in 0..height as usize {
+                    blocks[z][y][x] = 1;
+                }
+
+                for y in height as usize..6 {
+                    blocks[z][y][x] = WATER;
+                }
+            }
+        }
+
+        blocks
+    }
+
+    // The terrain meshed, and its layers packed
+    fn meshed(blocks: &Chunk) -> (Mesh, [Packed; 3]) {
+        let mesh = greedy::Greedy::default().mesh(blocks);
+        let Layers {
+            opaque,
+            translucent,
+            water,
+        } = Layers::split(mesh.clone());
+
+        let packed = [&opaque, &translucent, &water].map(Packed::new);
+        (mesh, packed)
+    }
+
+    // Digging and filling back in, going down a column of the hills
+    fn edits() -> impl Iterator<Item = ([usize; 3], BlockId)> {
+        (0..8).flat_map(|y| [([12, 8 - y, 17], AIR), ([13, 8 - y, 16], 1)])
+    }
+
+    #[test]
+    fn edits_rewrite_few_quads() {
+        let mut blocks = terrain();
+        let (mut mesh, [mut opaque, mut translucent, mut water]) = meshed(&blocks);
+
+        for (local, block) in edits() {
+            let [x, y, z] = local;
+            blocks[z][y][x] = block;
+            let packed = [&mut opaque, &mut translucent, &mut water];
+            let ranges = remesh(&*blocks, &Borders::NONE, &mut mesh, packed, local);
+            let rewritten: usize = ranges.iter().flatten().map(Range::len).sum();
+            assert!(rewritten < 256, "{rewritten} quads rewritten for one block");
+
+            // Packed layers hold the very same quads as the mesh, in any order
+            let layers = Layers::split(mesh.clone());
+            let pairs = [
+                (&opaque, &layers.opaque),
+                (&translucent, &layers.translucent),
+                (&water, &layers.water),
+            ];
+            for (packed, layer) in pairs {
+                let mut quads = packed.quads.clone();
+                let mut expected = layer.concat();
+                quads.sort_unstable();
+                expected.sort_unstable();
+                assert_eq!(quads, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn edits_fit_the_budget() {
+        let mut blocks = terrain();
+        let (mut mesh, [mut opaque, mut translucent, mut water]) = meshed(&blocks);
+        let mut slowest = Duration::ZERO;
+
+        for (local, block) in edits() {
+            let start = Instant::now();
+            let [x, y, z] = local;
+            blocks[z][y][x] = block;
+            let packed = [&mut opaque, &mut translucent, &mut water];
+            remesh(&*blocks, &Borders::NONE, &mut mesh, packed, local);
+            slowest = slowest.max(start.elapsed());
+        }
+
+        assert!(slowest < BUDGET, "slowest edit took {slowest:?}");
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::mesh::BlockId;
+
+// Every journal starts with these, then the version it was written with
+const MAGIC: &[u8; 4] = b"AXJL";
+const VERSION: u16 = 1;
+
+// Bytes of every edit: x, y and z as i32, then the block as u16
+const EDIT_LEN: usize = 14;
+
+// Block set at a location, in world coordinates
+pub type Edit = ([i32; 3], BlockId);
+
+// Edits being made, and those made before the save on its way started
+const CURRENT: &str = "journal.axj";
+const PREVIOUS: &str = "journal.old.axj";
+
+// Edits made since the world was last saved, appended to a file next to its regions
+// as they are made, for whatever a crash kept from being saved to be made again
+// on the next start. Laid out as
+//
+//     magic      AXJL
+//     version    u16
+//     edits      x, y and z as i32, then the block as u16, oldest first
+//
+// All of it little endian. A crash may cut the last edit short, which is left out.
+// Starting a save starts a new journal, the last one kept until the save is written
+#[derive(Debug)]
+pub struct Journal {
+    dir: PathBuf,
+    file: BufWriter<File>,
+
+    // Edits written since last synced
+    unsynced: bool,
+}
+
+impl Journal {
+    // Journal of the world saved in `dir`, along with the edits left over
+    // by the last session to use it, if it ended before saving them
+    pub fn open(dir: impl Into<PathBuf>) -> io::Result<(Self, Vec<Edit>)> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut edits = read(&dir.join(PREVIOUS))?;
+        edits.extend(read(&dir.join(CURRENT))?);
+
+        // Left over edits go first into a journal of their own, written whole
+        // before the ones it replaces are gone
+        let temporary = dir.join(CURRENT).with_extension("tmp");
+        let mut journal = Self {
+            file: create(&temporary)?,
+            dir,
+            unsynced: false,
+        };
+
+        for &(location, block) in &edits {
+            journal.record(location, block)?;
+        }
+
+        journal.sync()?;
+        fs::rename(temporary, journal.dir.join(CURRENT))?;
+        remove(&journal.dir.join(PREVIOUS))?;
+        Ok((journal, edits))
+    }
+
+    pub fn record(&mut self, [x, y, z]: [i32; 3], block: BlockId) -> io::Result<()> {
+        let mut edit = [0; EDIT_LEN];
+        edit[0..4].copy_from_slice(&x.to_le_bytes());
+        edit[4..8].copy_from_slice(&y.to_le_bytes());
+        edit[8..12].copy_from_slice(&z.to_le_bytes());
+        edit[12..14].copy_from_slice(&block.to_le_bytes());
+
+        self.unsynced = true;
+        self.file.write_all(&edit)
+    }
+
+    // Make sure every edit recorded so far would outlive a crash.
+    // Meant to be called every frame, and cheap unless something was recorded
+    pub fn sync(&mut self) -> io::Result<()> {
+        if !self.unsynced {
+            return Ok(());
+        }
+
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        self.unsynced = false;
+        Ok(())
+    }
+
+    // Start over right before saving, keeping the edits so far until `settle`
+    // is told the save got written. Edits kept from a save not written
+    // stay kept along with these
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.sync()?;
+        let (current, previous) = (self.dir.join(CURRENT), self.dir.join(PREVIOUS));
+
+        match previous.exists() {
+            true => {
+                let edits = fs::read(&current)?;
+                let mut file = File::options().append(true).open(&previous)?;
+                file.write_all(edits.get(MAGIC.len() + 2..).unwrap_or_default())?;
+                file.sync_data()?;
+            }
+            false => fs::rename(&current, &previous)?,
+        }
+
+        self.file = create(&current)?;
+        Ok(())
+    }
+
+    // The save started by the last `rotate` got written, so the edits before it are gone
+    pub fn settle(&mut self) -> io::Result<()> {
+        remove(&self.dir.join(PREVIOUS))
+    }
+
+    // Every edit got saved, as on exiting, so the journal is gone
+    pub fn clear(mut self) -> io::Result<()> {
+        self.settle()?;
+        self.file.flush()?;
+        remove(&self.dir.join(CURRENT))
+    }
+}
+
+// A new journal with no edits yet, synced for it to be there after a crash
+fn create(path: &Path) -> io::Result<BufWriter<File>> {
+    let mut file = File::create(path)?;
+    file.write_all(MAGIC)?;
+    file.write_all(&VERSION.to_le_bytes())?;
+    file.sync_all()?;
+    Ok(BufWriter::new(file))
+}
+
+// Edits in a journal, none if there is no journal at all
+fn read(path: &Path) -> io::Result<Vec<Edit>> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    // Cut short before its version, as when a crash comes right as it is created
+    let Some(edits) = bytes.get(MAGIC.len() + 2..) else {
+        return Ok(Vec::new());
+    };
+
+    let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if &bytes[..4] != MAGIC || version != VERSION {
+        let message = format!("{} is not a journal this version reads", path.display());
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+
+    let edits = edits.chunks_exact(EDIT_LEN).map(|edit| {
+        let c = |i: usize| i32::from_le_bytes(edit[i..i + 4].try_into().unwrap());
+        ([c(0), c(4), c(8)], u16::from_le_bytes([edit[12], edit[13]]))
+    });
+
+    Ok(edits.collect())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_outlive_a_crash_until_saved() {
+        let dir = std::env::temp_dir().join(format!("axial-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let (mut journal, edits) = Journal::open(&dir).unwrap();
+        assert!(edits.is_empty());
+        journal.record([1, -2, 3], 7).unwrap();
+        journal.rotate().unwrap();
+        journal.record([-40, 5, 6], 0).unwrap();
+        journal.sync().unwrap();
+
+        // Crashing before the save gets written, half way through another edit
+        drop(journal);
+        let current = dir.join(CURRENT);
+        let mut file = File::options().append(true).open(&current).unwrap();
+        file.write_all(&[1, 2, 3]).unwrap();
+
+        let (mut journal, edits) = Journal::open(&dir).unwrap();
+        assert_eq!(edits, [([1, -2, 3], 7), ([-40, 5, 6], 0)]);
+
+        // Saved this time, with an edit made while saving
+        journal.rotate().unwrap();
+        journal.record([0, 0, 0], 2).unwrap();
+        journal.settle().unwrap();
+        journal.sync().unwrap();
+        drop(journal);
+
+        let (journal, edits) = Journal::open(&dir).unwrap();
+        assert_eq!(edits, [([0, 0, 0], 2)]);
+        journal.clear().unwrap();
+
+        let (_, edits) = Journal::open(&dir).unwrap();
+        assert!(edits.is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
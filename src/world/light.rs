@@ -0,0 +1,277 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    mem,
+};
+
+use super::{packed::PackedLight, split, ChunkMap, ChunkPos};
+use crate::mesh::{
+    is_transparent,
+    light::{emitted_light, LightGrid},
+};
+
+const NEIGHBORS: [[i32; 3]; 6] = [
+    [1, 0, 0],
+    [-1, 0, 0],
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+];
+
+// Light spreads from lamps through air, water and glass, one level dimmer every
+// block, by flooding out from whatever changed rather than lighting chunks anew.
+// It stops at chunks not loaded, and is left as it is in the neighbors of chunks
+// unloaded, so lamps on the other side of one keep lighting it until it is back
+impl ChunkMap {
+    // Block light at `location`, in world coordinates, none in chunks not loaded
+    pub fn light(&self, location: [i32; 3]) -> u8 {
+        let (pos, local) = split(location);
+        let entry = self.chunks.get(&pos);
+        entry.map_or(0, |entry| entry.light.get(local))
+    }
+
+    // Light in and around a chunk, for it to be meshed with
+    pub fn light_grid(&self, pos: ChunkPos) -> LightGrid {
+        let mut grid = LightGrid::dark();
+        let Some(entry) = self.chunks.get(&pos) else {
+            return grid;
+        };
+
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let level = entry.light.get([x, y, z]);
+                    grid.set((x as i32, y as i32, z as i32), level);
+                }
+            }
+        }
+
+        // Faces on the boundary only ever look straight out of it
+        let origin = pos.map(|c| c * 32);
+        for local in boundary() {
+            let location = std::array::from_fn(|axis| origin[axis] + local[axis]);
+            grid.set(local.into(), self.light(location));
+        }
+
+        grid
+    }
+
+    // Light a chunk anew out of its lamps and the light of its neighbors spilling
+    // into it, marking dirty every chunk whose light changed but itself.
+    // Light it spilled into its neighbors before is left as it was
+    pub fn relight_chunk(&mut self, pos: ChunkPos) -> HashSet<ChunkPos> {
+        let mut changed = HashSet::new();
+        let mut lit = VecDeque::new();
+
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return changed;
+        };
+
+        let old = mem::replace(&mut entry.light, PackedLight::dark());
+        let origin = pos.map(|c| c * 32);
+
+        for z in 0..32 {
+            for y in 0..32 {
+                for x in 0..32 {
+                    let level = emitted_light(entry.blocks.get([x, y, z]));
+                    if level > 0 {
+                        entry.light.set([x, y, z], level);
+                        let local = [x, y, z].map(|c| c as i32);
+                        lit.push_back(std::array::from_fn(|axis| origin[axis] + local[axis]));
+                    }
+                }
+            }
+        }
+
+        if entry.light != old {
+            changed.insert(pos);
+        }
+
+        for local in boundary() {
+            let location = std::array::from_fn(|axis| origin[axis] + local[axis]);
+            if self.light(location) > 0 {
+                lit.push_back(location);
+            }
+        }
+
+        self.spread(lit, &mut changed);
+        self.mark_relit(&changed, Some(pos));
+        changed
+    }
+
+    // Light anew chunks whose blocks changed all over, say by a bulk edit, along with
+    // every neighbor of theirs. Chunks are darkened first, for light their lamps
+    // spilled into their neighbors to go along with them, as light never reaches
+    // further than a chunk. Marks every chunk whose light changed as dirty, returning them
+    pub fn relight_chunks(&mut self, chunks: &HashSet<ChunkPos>) -> HashSet<ChunkPos> {
+        for pos in chunks {
+            if let Some(entry) = self.chunks.get_mut(pos) {
+                entry.light = PackedLight::dark();
+            }
+        }
+
+        let around = chunks.iter().flat_map(|&pos| neighbors(pos));
+        let around: HashSet<_> = around.filter(|pos| !chunks.contains(pos)).collect();
+
+        let mut changed = chunks.clone();
+        for pos in around.iter().chain(chunks) {
+            changed.extend(self.relight_chunk(*pos));
+        }
+
+        self.mark_relit(&changed, None);
+        changed
+    }
+
+    // Bring light up to date after the block at `location` changed, darkening
+    // whatever it lit before and letting light in again from around it.
+    // Marks every chunk whose light changed as dirty, returning them
+    pub fn relight_block(&mut self, location: [i32; 3]) -> HashSet<ChunkPos> {
+        let mut changed = HashSet::new();
+        let mut darkened = VecDeque::new();
+        let mut lit = VecDeque::new();
+
+        let old = self.light(location);
+        if old > 0 {
+            self.set_light(location, 0, &mut changed);
+            darkened.push_back((location, old));
+        }
+
+        // Whatever was lit from here goes dark, while light from elsewhere
+        // met on the way is spread again, along with lamps darkened on the way
+        while let Some((at, level)) = darkened.pop_front() {
+            for neighbor in neighbors(at) {
+                let neighbor_level = self.light(neighbor);
+
+                if neighbor_level == 0 {
+                    continue;
+                }
+
+                if neighbor_level >= level {
+                    lit.push_back(neighbor);
+                    continue;
+                }
+
+                self.set_light(neighbor, 0, &mut changed);
+                darkened.push_back((neighbor, neighbor_level));
+
+                let emitted = self.block(neighbor).map_or(0, emitted_light);
+                if emitted > 0 {
+                    self.set_light(neighbor, emitted, &mut changed);
+                    lit.push_back(neighbor);
+                }
+            }
+        }
+
+        let emitted = self.block(location).map_or(0, emitted_light);
+        if emitted > 0 {
+            self.set_light(location, emitted, &mut changed);
+            lit.push_back(location);
+        }
+
+        let around = neighbors(location).into_iter();
+        lit.extend(around.filter(|&neighbor| self.light(neighbor) > 0));
+
+        self.spread(lit, &mut changed);
+        self.mark_relit(&changed, None);
+        changed
+    }
+
+    // Flood light out of every block in `lit`, into blocks it brightens
+    fn spread(&mut self, mut lit: VecDeque<[i32; 3]>, changed: &mut HashSet<ChunkPos>) {
+        while let Some(at) = lit.pop_front() {
+            let level = self.light(at);
+            if level <= 1 {
+                continue;
+            }
+
+            for neighbor in neighbors(at) {
+                let Some(block) = self.block(neighbor) else {
+                    continue;
+                };
+
+                if is_transparent(block) && self.light(neighbor) < level - 1 {
+                    self.set_light(neighbor, level - 1, changed);
+                    lit.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    fn set_light(&mut self, location: [i32; 3], level: u8, changed: &mut HashSet<ChunkPos>) {
+        let (pos, local) = split(location);
+        let Some(entry) = self.chunks.get_mut(&pos) else {
+            return;
+        };
+
+        if entry.light.get(local) != level {
+            entry.light.set(local, level);
+            changed.insert(pos);
+        }
+    }
+
+    // Have chunks whose light changed meshed again, all but `except`
+    fn mark_relit(&mut self, changed: &HashSet<ChunkPos>, except: Option<ChunkPos>) {
+        for &pos in changed.iter().filter(|&&pos| Some(pos) != except) {
+            self.mark_dirty(pos);
+        }
+    }
+}
+
+// Blocks or chunks sharing a side with the one at `location`, as in `Facing::ALL`
+pub(super) fn neighbors(location: [i32; 3]) -> [[i32; 3]; 6] {
+    NEIGHBORS.map(|offset| std::array::from_fn(|axis| location[axis] + offset[axis]))
+}
+
+// Blocks of the neighbors right against the sides of a chunk, in chunk coordinates
+fn boundary() -> impl Iterator<Item = [i32; 3]> {
+    let sides = (0..32).flat_map(|v| (0..32).map(move |u| (u, v)));
+    sides.flat_map(|(u, v)| {
+        let outside = [-1, 32].into_iter();
+        outside.flat_map(move |out| [[out, u, v], [u, out, v], [u, v, out]])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Chunk, AIR, LAMP};
+
+    fn empty() -> Box<Chunk> {
+        Box::new([[[AIR; 32]; 32]; 32])
+    }
+
+    #[test]
+    fn lamps_light_across_chunks() {
+        let mut map = ChunkMap::new();
+        let mut blocks = empty();
+        blocks[4][4][30] = LAMP;
+
+        map.insert([1, 0, 0], empty());
+        map.insert([0, 0, 0], blocks);
+
+        assert_eq!(map.light([30, 4, 4]), 14);
+        assert_eq!(map.light([33, 4, 4]), 11);
+        assert_eq!(map.light([30, 4, 4 + 13]), 1);
+        assert_eq!(map.light([30, 4, 4 + 14]), 0);
+    }
+
+    #[test]
+    fn edits_relight_what_changed() {
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], empty());
+
+        map.blocks_mut([0, 0, 0]).unwrap()[8][8][8] = LAMP;
+        map.relight_block([8, 8, 8]);
+        assert_eq!(map.light([8, 8, 10]), 12);
+
+        // Walled in on one side, light goes around the wall
+        map.blocks_mut([0, 0, 0]).unwrap()[9][8][8] = 1;
+        map.relight_block([8, 8, 9]);
+        assert_eq!(map.light([8, 8, 9]), 0);
+        assert_eq!(map.light([8, 8, 10]), 10);
+
+        map.blocks_mut([0, 0, 0]).unwrap()[8][8][8] = AIR;
+        map.relight_block([8, 8, 8]);
+        assert!(map.light_grid([0, 0, 0]).is_dark());
+    }
+}
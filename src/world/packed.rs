@@ -0,0 +1,381 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    mem,
+};
+
+use crate::mesh::{BlockId, Chunk};
+
+// Blocks along every axis of a section, 8 of them to a chunk
+const SECTION: usize = 16;
+const SECTIONS: usize = (32 / SECTION).pow(3);
+const SECTION_BLOCKS: usize = SECTION.pow(3);
+
+// Of every light level, up to 15
+const LEVEL_BITS: u32 = 4;
+
+// Blocks of a chunk packed in a fraction of the 64 KiB they take loose, for every
+// chunk loaded to be kept around. Every block the chunk has is in its palette,
+// and every section of it is either a single block all over or the index into
+// the palette of each of its blocks, packed into as few bits as the palette needs.
+// Blocks are still at hand one at a time, to be set in place or meshed around,
+// or unpacked in full to be meshed
+#[derive(Clone, Debug)]
+pub struct PackedChunk {
+    palette: Vec<BlockId>,
+
+    // Indices into the palette, in the order of the blocks they are of,
+    // for blocks set to be found in it without going through it all
+    sorted: Vec<u16>,
+
+    // Of every index, a power of two so no index is split across words
+    bits: u32,
+
+    sections: [Section; SECTIONS],
+}
+
+#[derive(Clone, Debug)]
+enum Section {
+    // As most of the sky and the deep underground are
+    Uniform(BlockId),
+
+    // Indexed as chunks are, x varying fastest, lowest bits first
+    Indices(Box<[u64]>),
+}
+
+// Block light of a chunk, packed in sections as its blocks are, though with
+// every level taking 4 bits and no palette to go through. Sections lit all
+// over the same, as those in the dark are, take no room beyond that level
+#[derive(Clone, Debug)]
+pub struct PackedLight {
+    sections: [LightSection; SECTIONS],
+}
+
+#[derive(Clone, Debug)]
+enum LightSection {
+    Uniform(u8),
+
+    // Indexed as blocks are
+    Levels(Box<[u64]>),
+}
+
+impl PackedChunk {
+    pub fn pack(blocks: &Chunk) -> Self {
+        let mut palette = Vec::new();
+        let mut indices = HashMap::new();
+        let mut uniform = [true; SECTIONS];
+        let corners: [_; SECTIONS] = std::array::from_fn(|section| {
+            let [x, y, z] = corner(section);
+            blocks[z][y][x]
+        });
+
+        for_each_local(|[x, y, z]| {
+            let block = blocks[z][y][x];
+            let (section, _) = locate([x, y, z]);
+            uniform[section] &= block == corners[section];
+            if let Entry::Vacant(entry) = indices.entry(block) {
+                entry.insert(palette.len());
+                palette.push(block);
+            }
+        });
+
+        palette.shrink_to_fit();
+        let mut sorted: Vec<_> = (0..palette.len() as u16).collect();
+        sorted.sort_unstable_by_key(|&index| palette[index as usize]);
+        let bits = index_bits(palette.len());
+        let sections = std::array::from_fn(|section| match uniform[section] {
+            true => Section::Uniform(corners[section]),
+            false => Section::Indices(filled(bits, 0)),
+        });
+
+        let mut packed = Self {
+            palette,
+            sorted,
+            bits,
+            sections,
+        };
+
+        for_each_local(|[x, y, z]| {
+            let (section, at) = locate([x, y, z]);
+            if let Section::Indices(words) = &mut packed.sections[section] {
+                write(words, bits, at, indices[&blocks[z][y][x]]);
+            }
+        });
+
+        packed
+    }
+
+    pub fn unpack(&self) -> Box<Chunk> {
+        let mut blocks = Box::new([[[0; 32]; 32]; 32]);
+        for_each_local(|[x, y, z]| blocks[z][y][x] = self.get([x, y, z]));
+        blocks
+    }
+
+    pub fn get(&self, local: [usize; 3]) -> BlockId {
+        let (section, at) = locate(local);
+        match &self.sections[section] {
+            Section::Uniform(block) => *block,
+            Section::Indices(words) => self.palette[read(words, self.bits, at)],
+        }
+    }
+
+    // Sections set all over to a single block are left as they were until packed again,
+    // and so are blocks no longer there left in the palette
+    pub fn set(&mut self, local: [usize; 3], block: BlockId) {
+        let (section, at) = locate(local);
+        if let Section::Uniform(old) = self.sections[section] {
+            if old == block {
+                return;
+            }
+
+            let old = self.index(old);
+            self.sections[section] = Section::Indices(filled(self.bits, old));
+        }
+
+        let index = self.index(block);
+        if let Section::Indices(words) = &mut self.sections[section] {
+            write(words, self.bits, at, index);
+        }
+    }
+
+    // Bytes taken in all, those of the palette and indices included
+    pub fn bytes(&self) -> usize {
+        let words = self.sections.iter().map(|section| match section {
+            Section::Uniform(_) => 0,
+            Section::Indices(words) => mem::size_of_val(&**words),
+        });
+
+        let palette = self.palette.capacity() * mem::size_of::<BlockId>();
+        let sorted = self.sorted.capacity() * mem::size_of::<u16>();
+        mem::size_of::<Self>() + palette + sorted + words.sum::<usize>()
+    }
+
+    // Index of `block` in the palette, added to it if not there yet,
+    // widening every index if there are too many blocks for them
+    fn index(&mut self, block: BlockId) -> usize {
+        let block_of = |&index: &u16| self.palette[index as usize];
+        let found = self.sorted.binary_search_by_key(&block, block_of);
+        let slot = match found {
+            Ok(slot) => return self.sorted[slot] as usize,
+            Err(slot) => slot,
+        };
+
+        self.sorted.insert(slot, self.palette.len() as u16);
+        self.palette.push(block);
+        let bits = index_bits(self.palette.len());
+        if bits != self.bits {
+            for section in &mut self.sections {
+                if let Section::Indices(words) = section {
+                    let mut wide = filled(bits, 0);
+                    for at in 0..SECTION_BLOCKS {
+                        write(&mut wide, bits, at, read(words, self.bits, at));
+                    }
+
+                    *words = wide;
+                }
+            }
+
+            self.bits = bits;
+        }
+
+        self.palette.len() - 1
+    }
+}
+
+impl PackedLight {
+    pub fn dark() -> Self {
+        Self {
+            sections: std::array::from_fn(|_| LightSection::Uniform(0)),
+        }
+    }
+
+    pub fn get(&self, local: [usize; 3]) -> u8 {
+        let (section, at) = locate(local);
+        self.sections[section].level(at)
+    }
+
+    // Sections lit all over the same again are left as they were
+    pub fn set(&mut self, local: [usize; 3], level: u8) {
+        let (section, at) = locate(local);
+        let section = &mut self.sections[section];
+        if let LightSection::Uniform(old) = *section {
+            if old == level {
+                return;
+            }
+
+            *section = LightSection::Levels(filled(LEVEL_BITS, old as usize));
+        }
+
+        if let LightSection::Levels(words) = section {
+            write(words, LEVEL_BITS, at, level as usize);
+        }
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.sections.iter().all(|section| match section {
+            LightSection::Uniform(level) => *level == 0,
+            LightSection::Levels(words) => words.iter().all(|&word| word == 0),
+        })
+    }
+
+    pub fn bytes(&self) -> usize {
+        let words = self.sections.iter().map(|section| match section {
+            LightSection::Uniform(_) => 0,
+            LightSection::Levels(words) => mem::size_of_val(&**words),
+        });
+
+        mem::size_of::<Self>() + words.sum::<usize>()
+    }
+}
+
+// Equal as long as every level is, however their sections are packed
+impl PartialEq for PackedLight {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pairs = self.sections.iter().zip(&other.sections);
+        pairs.all(|pair| match pair {
+            (LightSection::Uniform(a), LightSection::Uniform(b)) => a == b,
+            (a, b) => (0..SECTION_BLOCKS).all(|at| a.level(at) == b.level(at)),
+        })
+    }
+}
+
+impl LightSection {
+    fn level(&self, at: usize) -> u8 {
+        match self {
+            Self::Uniform(level) => *level,
+            Self::Levels(words) => read(words, LEVEL_BITS, at) as u8,
+        }
+    }
+}
+
+// Bits every index takes for a palette of `len` blocks
+fn index_bits(len: usize) -> u32 {
+    let bits = usize::BITS - len.saturating_sub(1).leading_zeros();
+    bits.max(1).next_power_of_two()
+}
+
+// Section a block is in, and where in it
+fn locate([x, y, z]: [usize; 3]) -> (usize, usize) {
+    let section = (z / SECTION * 2 + y / SECTION) * 2 + x / SECTION;
+    let [x, y, z] = [x, y, z].map(|c| c % SECTION);
+    (section, (z * SECTION + y) * SECTION + x)
+}
+
+// Lowest block of a section, in chunk coordinates
+fn corner(section: usize) -> [usize; 3] {
+    [section % 2, section / 2 % 2, section / 4].map(|c| c * SECTION)
+}
+
+fn for_each_local(mut f: impl FnMut([usize; 3])) {
+    for z in 0..32 {
+        for y in 0..32 {
+            for x in 0..32 {
+                f([x, y, z]);
+            }
+        }
+    }
+}
+
+// Indices of a whole section, every one of them `index`
+fn filled(bits: u32, index: usize) -> Box<[u64]> {
+    let per_word = u64::BITS / bits;
+    let word = (0..per_word).fold(0, |word, slot| word | (index as u64) << (slot * bits));
+    vec![word; SECTION_BLOCKS / per_word as usize].into_boxed_slice()
+}
+
+fn read(words: &[u64], bits: u32, at: usize) -> usize {
+    let per_word = (u64::BITS / bits) as usize;
+    let shift = (at % per_word) as u32 * bits;
+    let mask = u64::MAX >> (u64::BITS - bits);
+    (words[at / per_word] >> shift & mask) as usize
+}
+
+fn write(words: &mut [u64], bits: u32, at: usize, index: usize) {
+    let per_word = (u64::BITS / bits) as usize;
+    let shift = (at % per_word) as u32 * bits;
+    let mask = u64::MAX >> (u64::BITS - bits);
+    let word = &mut words[at / per_word];
+    *word = *word & !(mask << shift) | (index as u64) << shift;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{AIR, LAMP, WATER};
+
+    // Stone under water under air, with a lamp in the water
+    fn terrain() -> Box<Chunk> {
+        let mut blocks = Box::new([[[AIR; 32]; 32]; 32]);
+        for (y, row) in blocks.iter_mut().flatten().enumerate() {
+            let y = y % 32;
+            let block = match y {
+                0..8 => 1,
+                8..12 => WATER,
+                _ => AIR,
+            };
+
+            row.fill(block);
+        }
+
+        blocks[3][9][5] = LAMP;
+        blocks
+    }
+
+    #[test]
+    fn packed_chunks_unpack_as_they_were() {
+        let blocks = terrain();
+        let packed = PackedChunk::pack(&blocks);
+        assert_eq!(packed.unpack(), blocks);
+        assert_eq!(packed.get([5, 9, 3]), LAMP);
+        assert_eq!(packed.get([31, 31, 31]), AIR);
+
+        // Four blocks, two bits each, in the bottom sections alone
+        assert_eq!(packed.bits, 2);
+        let uniform = |section: &&Section| matches!(section, Section::Uniform(_));
+        assert_eq!(packed.sections.iter().filter(uniform).count(), 4);
+        assert!(packed.bytes() < 64 * 1024 / 8);
+
+        let air = PackedChunk::pack(&[[[AIR; 32]; 32]; 32]);
+        assert!(air.bytes() < 256);
+    }
+
+    #[test]
+    fn blocks_set_widen_indices_as_needed() {
+        let mut blocks = terrain();
+        let mut packed = PackedChunk::pack(&blocks);
+
+        // Into a section all air, then past what two bits can tell apart
+        for (i, block) in [7, 8, 9, 10, 300].into_iter().enumerate() {
+            let local = [i * 7, 31 - i, 20];
+            packed.set(local, block);
+            blocks[20][31 - i][i * 7] = block;
+            assert_eq!(packed.get(local), block);
+        }
+
+        assert_eq!(packed.bits, 4);
+        assert_eq!(packed.unpack(), blocks);
+        assert_eq!(packed.palette.len(), packed.sorted.len());
+        packed.set([5, 9, 3], WATER);
+        assert_eq!(packed.get([5, 9, 3]), WATER);
+        assert_eq!(packed.get([6, 9, 3]), WATER);
+    }
+
+    #[test]
+    fn light_packs_sections_lit_the_same() {
+        let mut light = PackedLight::dark();
+        assert!(light.is_dark());
+        assert!(light.bytes() < 256);
+
+        light.set([5, 9, 3], 14);
+        light.set([6, 9, 3], 13);
+        assert_eq!(light.get([5, 9, 3]), 14);
+        assert_eq!(light.get([6, 9, 3]), 13);
+        assert_eq!(light.get([7, 9, 3]), 0);
+        assert!(light.bytes() < 4096);
+
+        // Dark again, though packed otherwise than a chunk never lit
+        light.set([5, 9, 3], 0);
+        light.set([6, 9, 3], 0);
+        assert!(light.is_dark());
+        assert_eq!(light, PackedLight::dark());
+    }
+}
@@ -0,0 +1,129 @@
+use super::{ChunkMap, ChunkPos};
+use crate::mesh::{pick::is_pickable, Facing};
+
+// Where a ray first runs into a block it can be built against, across chunks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    // World coordinates of the block hit, in blocks
+    pub location: [i32; 3],
+
+    // Face the ray went in through, `None` if it started inside the block
+    pub facing: Option<Facing>,
+
+    // Along the ray, in lengths of its direction
+    pub distance: f32,
+}
+
+impl Hit {
+    // Chunk the block hit is in, and where in it
+    pub fn chunk(&self) -> (ChunkPos, [usize; 3]) {
+        super::split(self.location)
+    }
+
+    // Block in front of the face hit, where a block built against it would go
+    pub fn adjacent(&self) -> Option<[i32; 3]> {
+        let (nx, ny, nz) = self.facing?.normal();
+        let [x, y, z] = self.location;
+        Some([x + nx, y + ny, z + nz])
+    }
+}
+
+impl ChunkMap {
+    // First pickable block along the ray from `origin` towards `direction`, both
+    // in world coordinates, up to `reach` lengths of `direction` away.
+    // Blocks are stepped through as `mesh::pick::raycast` does, chunk after chunk,
+    // with chunks not loaded taken as air
+    pub fn raycast(&self, origin: [f32; 3], direction: [f32; 3], reach: f32) -> Option<Hit> {
+        let step = direction.map(|d| if d < 0.0 { -1 } else { 1 });
+        let mut block = origin.map(|c| c.floor() as i32);
+
+        // Distance to the next boundary along each axis, and between boundaries
+        let mut next: [f32; 3] = std::array::from_fn(|axis| {
+            let boundary = (block[axis] + (step[axis] > 0) as i32) as f32;
+            match direction[axis] == 0.0 {
+                true => f32::INFINITY,
+                false => (boundary - origin[axis]) / direction[axis],
+            }
+        });
+
+        let delta = direction.map(|d| (1.0 / d).abs());
+        let (mut distance, mut entered) = (0.0, None);
+
+        loop {
+            if self.block(block).is_some_and(is_pickable) {
+                let facing = entered.and_then(|axis| {
+                    let mut normal = [0; 3];
+                    normal[axis] = -step[axis];
+
+                    let normal = (normal[0], normal[1], normal[2]);
+                    let mut facings = Facing::ALL.into_iter();
+                    facings.find(|facing| facing.normal() == normal)
+                });
+
+                return Some(Hit {
+                    location: block,
+                    facing,
+                    distance,
+                });
+            }
+
+            let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b])).unwrap();
+
+            distance = next[axis];
+            if distance > reach {
+                return None;
+            }
+
+            block[axis] += step[axis];
+            next[axis] += delta[axis];
+            entered = Some(axis);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::AIR;
+
+    // Two chunks side by side along x, floored in the second alone
+    fn map() -> ChunkMap {
+        let mut floor = Box::new([[[AIR; 32]; 32]; 32]);
+        for plane in &mut floor[..] {
+            plane[0] = [1; 32];
+        }
+
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], Box::new([[[AIR; 32]; 32]; 32]));
+        map.insert([1, 0, 0], floor);
+        map
+    }
+
+    #[test]
+    fn rays_go_across_chunks() {
+        let map = map();
+        let hit = map.raycast([20.5, 4.5, 3.5], [1.0, -0.25, 0.0], 64.0);
+        let hit = hit.unwrap();
+        assert_eq!(hit.location, [34, 0, 3]);
+        assert_eq!(hit.facing, Some(Facing::PosY));
+        assert_eq!(hit.chunk(), ([1, 0, 0], [2, 0, 3]));
+        assert_eq!(hit.adjacent(), Some([34, 1, 3]));
+    }
+
+    #[test]
+    fn chunks_not_loaded_are_air() {
+        let map = map();
+        let hit = map.raycast([-10.5, 0.5, 3.5], [1.0, 0.0, 0.0], 64.0);
+        let hit = hit.unwrap();
+        assert_eq!(hit.location, [32, 0, 3]);
+        assert_eq!(hit.facing, Some(Facing::NegX));
+        assert_eq!(hit.distance, 42.5);
+    }
+
+    #[test]
+    fn reach_is_respected() {
+        let map = map();
+        let hit = map.raycast([40.5, 10.0, 7.5], [0.0, -1.0, 0.0], 8.0);
+        assert_eq!(hit, None);
+    }
+}
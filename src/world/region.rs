@@ -0,0 +1,579 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+use super::{ChunkMap, ChunkPos};
+use crate::mesh::{BlockId, Chunk};
+
+// Every region file starts with these, then the version it was written with
+const MAGIC: &[u8; 4] = b"AXRG";
+
+// Bumped whenever the layout changes, older versions being read still
+pub const VERSION: u16 = 1;
+
+// Chunk columns along x and z in every region
+pub const REGION_SIZE: i32 = 32;
+
+// Where a region sits, counted in regions along x and z
+pub type RegionPos = [i32; 2];
+
+// Region a chunk column belongs in
+pub fn region_of([x, _, z]: ChunkPos) -> RegionPos {
+    [x.div_euclid(REGION_SIZE), z.div_euclid(REGION_SIZE)]
+}
+
+pub fn region_path(dir: &Path, [x, z]: RegionPos) -> PathBuf {
+    dir.join(format!("r.{x}.{z}.axr"))
+}
+
+#[derive(Debug)]
+pub enum RegionError {
+    Io(io::Error),
+
+    // Not a region file at all
+    Magic,
+
+    // Written by a later version, unknown to this one
+    Version(u16),
+
+    // Cut short or otherwise mangled, with what was being read
+    Corrupt(&'static str),
+}
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot access region: {err}"),
+            Self::Magic => write!(f, "not a region file"),
+            Self::Version(version) => write!(f, "unknown region version {version}"),
+            Self::Corrupt(what) => write!(f, "corrupt region, bad {what}"),
+        }
+    }
+}
+
+impl Error for RegionError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RegionError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+// Blocks of a chunk as every distinct block in it, then runs of blocks
+// as indices into those, one byte wide if there are few enough of them:
+//
+//     palette length    u16
+//     palette           u16 each
+//     run count         u32
+//     runs              length minus one as u16, then index as u8 or u16
+//
+// All of it little endian, and blocks in the order `Chunk` lays them out
+pub fn encode(blocks: &Chunk) -> Vec<u8> {
+    let blocks = blocks.iter().flatten().flatten().copied();
+
+    let mut palette: Vec<BlockId> = Vec::new();
+    let mut runs: Vec<(u16, u16)> = Vec::new();
+
+    for block in blocks {
+        let index = match palette.iter().position(|&entry| entry == block) {
+            Some(index) => index as u16,
+            None => {
+                palette.push(block);
+                palette.len() as u16 - 1
+            }
+        };
+
+        match runs.last_mut() {
+            Some((length, last)) if *last == index => *length += 1,
+            _ => runs.push((0, index)),
+        }
+    }
+
+    let wide = palette.len() > 256;
+    let mut bytes = Vec::new();
+
+    bytes.extend((palette.len() as u16).to_le_bytes());
+    bytes.extend(palette.iter().flat_map(|block| block.to_le_bytes()));
+    bytes.extend((runs.len() as u32).to_le_bytes());
+
+    for (length, index) in runs {
+        bytes.extend(length.to_le_bytes());
+        match wide {
+            true => bytes.extend(index.to_le_bytes()),
+            false => bytes.push(index as u8),
+        }
+    }
+
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Box<Chunk>, RegionError> {
+    let mut reader = Reader(bytes);
+
+    let len = reader.u16("palette")? as usize;
+    let palette = (0..len).map(|_| reader.u16("palette"));
+    let palette: Vec<BlockId> = palette.collect::<Result<_, _>>()?;
+    let wide = palette.len() > 256;
+
+    let mut blocks = Box::new([[[0; 32]; 32]; 32]);
+    let mut cells = blocks.iter_mut().flatten().flatten();
+
+    for _ in 0..reader.u32("runs")? {
+        let length = reader.u16("runs")? as usize + 1;
+        let index = match wide {
+            true => reader.u16("runs")?,
+            false => reader.u8("runs")? as u16,
+        };
+
+        let block = palette.get(index as usize).copied();
+        let block = block.ok_or(RegionError::Corrupt("palette index"))?;
+        for _ in 0..length {
+            *cells.next().ok_or(RegionError::Corrupt("runs"))? = block;
+        }
+    }
+
+    if cells.next().is_some() || !reader.0.is_empty() {
+        return Err(RegionError::Corrupt("runs"));
+    }
+
+    Ok(blocks)
+}
+
+// Every chunk in a region file, laid out as
+//
+//     magic             AXRG
+//     version           u16
+//     chunk count       u32
+//     chunks            x, y and z as i32, length as u32, then `encode`d blocks
+pub fn read_region(path: &Path) -> Result<Vec<(ChunkPos, Box<Chunk>)>, RegionError> {
+    let bytes = fs::read(path)?;
+    let mut reader = Reader(&bytes);
+
+    if reader.take(4, "magic")? != MAGIC {
+        return Err(RegionError::Magic);
+    }
+
+    match reader.u16("version")? {
+        VERSION => {}
+        version => return Err(RegionError::Version(version)),
+    }
+
+    let count = reader.u32("chunk count")?;
+    let mut chunks = Vec::new();
+
+    for _ in 0..count {
+        let mut coordinate = || reader.u32("chunk position").map(|c| c as i32);
+        let pos = [coordinate()?, coordinate()?, coordinate()?];
+
+        let len = reader.u32("chunk length")? as usize;
+        let blocks = decode(reader.take(len, "chunk")?)?;
+        chunks.push((pos, blocks));
+    }
+
+    Ok(chunks)
+}
+
+// Write a region file whole, through a temporary file moved over it afterwards
+// so that a region is never left half written. Both are synced before moving on,
+// for a crash or power cut right after to find the region as written
+pub fn write_region(path: &Path, chunks: &[(ChunkPos, &Chunk)]) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    bytes.extend(MAGIC);
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend((chunks.len() as u32).to_le_bytes());
+
+    for (pos, blocks) in chunks {
+        let encoded = encode(blocks);
+        bytes.extend(pos.iter().flat_map(|c| c.to_le_bytes()));
+        bytes.extend((encoded.len() as u32).to_le_bytes());
+        bytes.extend(encoded);
+    }
+
+    let temporary = path.with_extension("tmp");
+    let mut file = File::create(&temporary)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(temporary, path)?;
+
+    // Directories cannot be opened to be synced everywhere, nor need to be
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+enum Job {
+    Save(PathBuf, Vec<(ChunkPos, Box<Chunk>)>),
+    Load(PathBuf),
+}
+
+// Whatever a job sent to the store came back with
+#[derive(Debug)]
+pub enum Stored {
+    Saved(PathBuf),
+    Loaded(Vec<(ChunkPos, Box<Chunk>)>),
+}
+
+// Region files in a directory, read and written on a thread of their own
+// so that saving and loading never hold up a frame
+#[derive(Debug)]
+pub struct RegionStore {
+    dir: PathBuf,
+    jobs: Option<Sender<Job>>,
+    done: Receiver<Result<Stored, RegionError>>,
+    thread: Option<JoinHandle<()>>,
+
+    // Jobs given out and not back yet
+    pending: usize,
+
+    // When region files were last modified, as of loading or saving them last,
+    // for files changed since by anything else to be told apart
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl RegionStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let (jobs, queue) = mpsc::channel();
+        let (sender, done) = mpsc::channel();
+
+        let thread = thread::Builder::new()
+            .name("region store".into())
+            .spawn(move || store(&queue, &sender))
+            .expect("cannot spawn region store");
+
+        Self {
+            dir: dir.into(),
+            jobs: Some(jobs),
+            done,
+            thread: Some(thread),
+            pending: 0,
+            modified: HashMap::new(),
+        }
+    }
+
+    pub const fn pending(&self) -> usize {
+        self.pending
+    }
+
+    // Save every chunk loaded, over whatever their regions held for them.
+    // Chunks saved before and not loaded now are kept as they were
+    pub fn save(&mut self, map: &ChunkMap) {
+        self.save_chunks(map.iter());
+    }
+
+    // Save just `chunks`, say those edited since last saved, as `save` does.
+    // Returns how many regions are being written, each coming back on its own
+    pub fn save_chunks(
+        &mut self,
+        chunks: impl IntoIterator<Item = (ChunkPos, Box<Chunk>)>,
+    ) -> usize {
+        let mut regions = BTreeMap::<RegionPos, Vec<_>>::new();
+        for (pos, blocks) in chunks {
+            let region = regions.entry(region_of(pos)).or_default();
+            region.push((pos, blocks));
+        }
+
+        let count = regions.len();
+        for (region, chunks) in regions {
+            let path = region_path(&self.dir, region);
+            self.send(Job::Save(path, chunks));
+        }
+
+        count
+    }
+
+    pub fn load(&mut self, region: RegionPos) {
+        let path = region_path(&self.dir, region);
+        self.load_file(path);
+    }
+
+    // Load every region in the directory, returning how many there are.
+    // None there at all is the same as an empty directory
+    pub fn load_all(&mut self) -> io::Result<usize> {
+        let regions = self.regions()?;
+        let count = regions.len();
+        regions.into_iter().for_each(|path| self.load_file(path));
+        Ok(count)
+    }
+
+    // Load again every region modified since it was last loaded or saved here,
+    // along with any new ones, returning how many. Meant to be called every now
+    // and then, for regions edited by other tools to show up while running
+    pub fn load_changed(&mut self) -> io::Result<usize> {
+        let mut regions = self.regions()?;
+        regions.retain(|path| {
+            let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+            modified.ok() != self.modified.get(path).copied()
+        });
+
+        let count = regions.len();
+        regions.into_iter().for_each(|path| self.load_file(path));
+        Ok(count)
+    }
+
+    // Whatever finished since last time, without waiting on the rest
+    pub fn poll(&mut self) -> Vec<Result<Stored, RegionError>> {
+        let done: Vec<_> = self.done.try_iter().collect();
+        self.finish(&done);
+        done
+    }
+
+    // Everything still pending, waiting for it all, say before exiting
+    pub fn wait(&mut self) -> Vec<Result<Stored, RegionError>> {
+        let done: Vec<_> = self.done.iter().take(self.pending).collect();
+        self.finish(&done);
+        done
+    }
+
+    // Paths of every region file in the directory
+    fn regions(&self) -> io::Result<Vec<PathBuf>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+
+        let mut regions = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "axr") {
+                regions.push(path);
+            }
+        }
+
+        Ok(regions)
+    }
+
+    fn load_file(&mut self, path: PathBuf) {
+        self.remember(&path);
+        self.send(Job::Load(path));
+    }
+
+    // Regions saved here are not taken as changed by anything else
+    fn finish(&mut self, done: &[Result<Stored, RegionError>]) {
+        self.pending -= done.len();
+        for stored in done {
+            if let Ok(Stored::Saved(path)) = stored {
+                self.remember(path);
+            }
+        }
+    }
+
+    fn remember(&mut self, path: &Path) {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+        match modified {
+            Ok(modified) => self.modified.insert(path.to_owned(), modified),
+            Err(_) => self.modified.remove(path),
+        };
+    }
+
+    fn send(&mut self, job: Job) {
+        let jobs = self.jobs.as_ref().expect("region store shut down");
+        jobs.send(job).expect("region store is gone");
+        self.pending += 1;
+    }
+}
+
+impl Drop for RegionStore {
+    // Anything queued is still saved, as losing it would lose edits
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn store(queue: &Receiver<Job>, done: &Sender<Result<Stored, RegionError>>) {
+    for job in queue {
+        let result = match job {
+            Job::Save(path, chunks) => save(&path, chunks).map(|()| Stored::Saved(path)),
+            Job::Load(path) => read_region(&path).map(Stored::Loaded),
+        };
+
+        // Nobody is left to tell, but jobs go on to be done anyway
+        let _ = done.send(result);
+    }
+}
+
+// Write `chunks` into their region, keeping the rest of the chunks it held
+fn save(path: &Path, chunks: Vec<(ChunkPos, Box<Chunk>)>) -> Result<(), RegionError> {
+    let previous = match read_region(path) {
+        Ok(previous) => previous,
+        Err(RegionError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err),
+    };
+
+    let merged: BTreeMap<_, _> = previous.into_iter().chain(chunks).collect();
+    let merged: Vec<_> = merged
+        .iter()
+        .map(|(&pos, blocks)| (pos, &**blocks))
+        .collect();
+    write_region(path, &merged)?;
+    Ok(())
+}
+
+// Little endian values off the front of a byte slice
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, what: &'static str) -> Result<&'a [u8], RegionError> {
+        if self.0.len() < len {
+            return Err(RegionError::Corrupt(what));
+        }
+
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self, what: &'static str) -> Result<u8, RegionError> {
+        Ok(self.take(1, what)?[0])
+    }
+
+    fn u16(&mut self, what: &'static str) -> Result<u16, RegionError> {
+        let bytes = self.take(2, what)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self, what: &'static str) -> Result<u32, RegionError> {
+        let bytes = self.take(4, what)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{AIR, WATER};
+
+    // Stone below, water in between and air above, with a few odd blocks
+    fn blocks() -> Box<Chunk> {
+        let mut blocks = Box::new([[[AIR; 32]; 32]; 32]);
+        for (z, plane) in blocks.iter_mut().enumerate() {
+            for (y, row) in plane.iter_mut().enumerate() {
+                for (x, block) in row.iter_mut().enumerate() {
+                    *block = match y {
+                        0..=9 => 1,
+                        10..=12 => WATER,
+                        _ if (x + z) % 13 == 0 => (x + y) as BlockId,
+                        _ => AIR,
+                    };
+                }
+            }
+        }
+
+        blocks
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let blocks = blocks();
+        let encoded = encode(&blocks);
+
+        // Three bytes a run of the same block, and nothing more past the palette
+        let flat: Vec<_> = blocks.iter().flatten().flatten().collect();
+        let runs = 1 + flat.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        let mut palette = flat.clone();
+        palette.sort_unstable();
+        palette.dedup();
+        assert_eq!(encoded.len(), 2 + palette.len() * 2 + 4 + runs * 3);
+        assert_eq!(decode(&encoded).unwrap(), blocks);
+
+        // Past 256 distinct blocks, indices take two bytes
+        let mut many = Box::new([[[AIR; 32]; 32]; 32]);
+        for (index, block) in many.iter_mut().flatten().flatten().enumerate() {
+            *block = (index % 1000) as BlockId;
+        }
+
+        assert_eq!(decode(&encode(&many)).unwrap(), many);
+    }
+
+    #[test]
+    fn regions_round_trip_and_merge() {
+        let dir = std::env::temp_dir().join(format!("axial-regions-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = region_path(&dir, region_of([3, 0, -1]));
+        assert_eq!(region_of([3, 0, -1]), [0, -1]);
+
+        let (stone, air) = (blocks(), Box::new([[[AIR; 32]; 32]; 32]));
+        save(&path, vec![([3, 0, -1], stone.clone())]).unwrap();
+        save(&path, vec![([3, 1, -1], air.clone())]).unwrap();
+
+        let chunks = read_region(&path).unwrap();
+        assert_eq!(chunks, vec![([3, 0, -1], stone), ([3, 1, -1], air)]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn later_versions_are_refused() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((VERSION + 1).to_le_bytes());
+        bytes.extend(0u32.to_le_bytes());
+
+        let dir = std::env::temp_dir().join(format!("axial-version-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = region_path(&dir, [0, 0]);
+        fs::write(&path, bytes).unwrap();
+
+        let result = read_region(&path);
+        assert!(matches!(result, Err(RegionError::Version(version)) if version == VERSION + 1));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn regions_changed_elsewhere_are_loaded_again() {
+        let dir = std::env::temp_dir().join(format!("axial-changed-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = region_path(&dir, [0, 0]);
+        write_region(&path, &[([1, 0, 1], &*blocks())]).unwrap();
+
+        let mut store = RegionStore::new(&dir);
+        assert_eq!(store.load_all().unwrap(), 1);
+        store.wait();
+        assert_eq!(store.load_changed().unwrap(), 0);
+
+        // Saved here, so not changed
+        let mut map = ChunkMap::new();
+        map.insert([2, 0, 2], blocks());
+        store.save(&map);
+        store.wait();
+        assert_eq!(store.load_changed().unwrap(), 0);
+
+        // Written by something else a while later, as far as its time tells
+        let air = Box::new([[[AIR; 32]; 32]; 32]);
+        write_region(&path, &[([1, 0, 1], &*air)]).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(10);
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(later).unwrap();
+
+        assert_eq!(store.load_changed().unwrap(), 1);
+        let loaded = store.wait();
+        let [Ok(Stored::Loaded(chunks))] = &loaded[..] else {
+            panic!("{loaded:?}");
+        };
+
+        assert_eq!(*chunks, vec![([1, 0, 1], air)]);
+
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
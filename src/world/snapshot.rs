@@ -0,0 +1,352 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{Display, Write as _},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+use super::{free_layers, free_reserved, Biome, ChunkMap, ChunkPos, Placement};
+use crate::{
+    buddy::Buddy,
+    mesh::{lod::LODS, Chunk, QuadRef},
+};
+
+// Whatever a frame came out of, for it to be gone back to later on: where the camera
+// was, the time of day, every chunk loaded and where its quads lay in the quad buddy.
+// Restoring it loads the same chunks and meshes them into the same blocks, so culling
+// and fragmentation come out as they were.
+//
+// Written out as text, a line at a time, as in
+//
+//     seed 42
+//     eye 64 40 16
+//     facing 3.1415927 -0.5
+//     sun 1 0 0.3
+//     time 12.5
+//     buddy 1048576 4
+//     chunk 0 0 0 0+4096 - 8192+256 4096+1024 -
+//
+// where the buddy is told by its capacity and smallest order, and chunks by
+// where they are and the block of every layer, as offset and length in quads,
+// or - if it has none
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub seed: u64,
+
+    pub eye: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+
+    // Direction towards the sun, and seconds since starting, as the renderer had them
+    pub sun: [f32; 3],
+    pub time: f32,
+
+    // Of the quad buddy, which must be the same for its blocks to be taken again
+    pub capacity: usize,
+    pub min_order: u8,
+
+    pub chunks: Vec<(ChunkPos, Placement)>,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let text = fs::read_to_string(path).map_err(SnapshotError::Io)?;
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SnapshotError> {
+        let mut header = Header::default();
+        let mut chunks = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let line = line.split('#').next().unwrap_or_default();
+            let words: Vec<_> = line.split_whitespace().collect();
+            let syntax = SnapshotError::Syntax(number);
+
+            match words[..] {
+                [] => {}
+                ["chunk", ..] if words.len() > 4 => {
+                    let pos = numbers(&words[1..4]);
+                    let blocks = words[4..].iter().map(|word| block(word));
+                    let blocks: Option<Vec<_>> = blocks.collect();
+                    let placement = blocks.and_then(|blocks| blocks.try_into().ok());
+                    chunks.push(pos.zip(placement).ok_or(syntax)?);
+                }
+                [key, ..] => header.set(key, &words[1..]).ok_or(syntax)?,
+            }
+        }
+
+        let Header {
+            seed: Some(seed),
+            eye: Some(eye),
+            facing: Some([yaw, pitch]),
+            sun: Some(sun),
+            time: Some(time),
+            buddy: Some((capacity, min_order)),
+        } = header
+        else {
+            return Err(SnapshotError::Header);
+        };
+
+        Ok(Self {
+            seed,
+            eye,
+            yaw,
+            pitch,
+            sun,
+            time,
+            capacity,
+            min_order,
+            chunks,
+        })
+    }
+
+    // Whether it can be restored into a world out of `seed` with its quads in `quads`
+    pub fn check(&self, seed: u64, quads: &Buddy<QuadRef>) -> Result<(), SnapshotError> {
+        if seed != self.seed {
+            return Err(SnapshotError::Seed(self.seed));
+        }
+
+        if (quads.capacity(), quads.min_order()) != (self.capacity, self.min_order) {
+            return Err(SnapshotError::Buddy(self.capacity, self.min_order));
+        }
+
+        Ok(())
+    }
+}
+
+// Floats are written out in full, so they read back to the same bits
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y, z] = self.eye;
+        let [sx, sy, sz] = self.sun;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "eye {x} {y} {z}")?;
+        writeln!(f, "facing {} {}", self.yaw, self.pitch)?;
+        writeln!(f, "sun {sx} {sy} {sz}")?;
+        writeln!(f, "time {}", self.time)?;
+        writeln!(f, "buddy {} {}", self.capacity, self.min_order)?;
+
+        let mut line = String::new();
+        for ([x, y, z], placement) in &self.chunks {
+            line.clear();
+            for block in placement {
+                match block {
+                    Some((offset, len)) => write!(line, " {offset}+{len}")?,
+                    None => line.push_str(" -"),
+                }
+            }
+
+            writeln!(f, "chunk {x} {y} {z}{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ChunkMap {
+    // Every chunk loaded along with where its layers lie in the quad buddy, in order
+    pub fn placements(&self, quads: &Buddy<QuadRef>) -> Vec<(ChunkPos, Placement)> {
+        let mut placements = Vec::with_capacity(self.chunks.len());
+        for (&pos, entry) in &self.chunks {
+            let mut placement = [None; 3 + LODS];
+            let layers = entry.layers.iter().chain(&entry.lods);
+            for (block, layer) in placement.iter_mut().zip(layers) {
+                let handle = layer.handle.as_ref();
+                *block = handle.map(|handle| (quads.offset(handle), quads.len(handle)));
+            }
+
+            placements.push((pos, placement));
+        }
+
+        placements.sort_unstable_by_key(|&(pos, _)| pos);
+        placements
+    }
+
+    // Load the chunks of a snapshot in place of every chunk loaded, dirty, keeping
+    // the blocks of those loaded already and making the rest out of `generate`.
+    // The blocks their layers were in are set aside for them, to be taken once meshed
+    // as long as their quads fit, so the quad buddy had better hold nothing else
+    // for them all to be there. Returns how many of them were not
+    pub fn restore(
+        &mut self,
+        quads: &mut Buddy<QuadRef>,
+        snapshot: &Snapshot,
+        mut generate: impl FnMut(ChunkPos) -> (Box<Chunk>, Biome),
+    ) -> usize {
+        let mut kept = HashMap::new();
+        for (pos, mut entry) in self.chunks.drain() {
+            free_layers(quads, &mut entry.layers);
+            free_layers(quads, &mut entry.lods);
+            free_reserved(quads, &mut entry.layers);
+            free_reserved(quads, &mut entry.lods);
+            let blocks = entry.blocks.into_chunk();
+            kept.insert(pos, (blocks, entry.biome, entry.unsaved));
+        }
+
+        for &(pos, _) in &snapshot.chunks {
+            let (blocks, biome, unsaved) = kept.remove(&pos).unwrap_or_else(|| {
+                let (blocks, biome) = generate(pos);
+                (blocks, biome, false)
+            });
+
+            self.insert(pos, blocks);
+            self.set_biome(pos, biome);
+            if let Some(entry) = self.chunks.get_mut(&pos) {
+                entry.unsaved = unsaved;
+            }
+        }
+
+        let mut missed = 0;
+        for (pos, placement) in &snapshot.chunks {
+            let Some(entry) = self.chunks.get_mut(pos) else {
+                continue;
+            };
+
+            let layers = entry.layers.iter_mut().chain(&mut entry.lods);
+            for (layer, &block) in layers.zip(placement) {
+                let Some((offset, len)) = block else {
+                    continue;
+                };
+
+                layer.reserved = quads.alloc_at(offset, len);
+                missed += layer.reserved.is_none() as usize;
+            }
+        }
+
+        missed
+    }
+}
+
+#[derive(Default)]
+struct Header {
+    seed: Option<u64>,
+    eye: Option<[f32; 3]>,
+    facing: Option<[f32; 2]>,
+    sun: Option<[f32; 3]>,
+    time: Option<f32>,
+    buddy: Option<(usize, u8)>,
+}
+
+impl Header {
+    fn set(&mut self, key: &str, words: &[&str]) -> Option<()> {
+        match (key, words) {
+            ("seed", [seed]) => self.seed = Some(seed.parse().ok()?),
+            ("time", [time]) => self.time = Some(time.parse().ok()?),
+            ("buddy", [capacity, order]) => {
+                self.buddy = Some((capacity.parse().ok()?, order.parse().ok()?));
+            }
+            ("eye", _) => self.eye = Some(numbers(words)?),
+            ("facing", _) => self.facing = Some(numbers(words)?),
+            ("sun", _) => self.sun = Some(numbers(words)?),
+            _ => return None,
+        }
+
+        Some(())
+    }
+}
+
+// Offset and length of a block, or - for none
+fn block(word: &str) -> Option<Option<(usize, usize)>> {
+    if word == "-" {
+        return Some(None);
+    }
+
+    let (offset, len) = word.split_once('+')?;
+    Some(Some((offset.parse().ok()?, len.parse().ok()?)))
+}
+
+// Exactly `N` numbers, or none
+fn numbers<T: FromStr + Copy + Default, const N: usize>(words: &[&str]) -> Option<[T; N]> {
+    if words.len() != N {
+        return None;
+    }
+
+    let mut numbers = [T::default(); N];
+    for (number, word) in numbers.iter_mut().zip(words) {
+        *number = word.parse().ok()?;
+    }
+
+    Some(numbers)
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+
+    // Line number of a line that is neither part of the header nor a chunk
+    Syntax(usize),
+
+    // Seed, eye, facing, sun, time or buddy missing
+    Header,
+
+    // Seed of the world it was taken of, some other than the one loaded
+    Seed(u64),
+
+    // Capacity and smallest order of the quad buddy it was taken with
+    Buddy(usize, u8),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "cannot read snapshot: {err}"),
+            Self::Syntax(line) => write!(f, "snapshot line {line}: expected a setting or a chunk"),
+            Self::Header => write!(f, "snapshot is missing part of its header"),
+            Self::Seed(seed) => write!(f, "snapshot was taken of seed {seed}"),
+            Self::Buddy(capacity, order) => {
+                let buddy = format!("{capacity} quads in blocks of order {order} up");
+                write!(f, "snapshot was taken with a quad buddy of {buddy}")
+            }
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshots_read_back_as_written() {
+        let mut placement = [None; 3 + LODS];
+        placement[0] = Some((0, 4096));
+        placement[2] = Some((8192, 256));
+
+        let snapshot = Snapshot {
+            seed: 42,
+            eye: [64.0, 40.5, -16.0],
+            yaw: 3.0,
+            pitch: -0.1,
+            sun: [1.0 / 3.0, 0.5, 0.3],
+            time: 12.25,
+            capacity: 1 << 20,
+            min_order: 4,
+            chunks: vec![([0, 0, 0], placement), ([-1, 0, 2], [None; 3 + LODS])],
+        };
+
+        let text = snapshot.to_string();
+        assert!(text.contains("\nchunk 0 0 0 0+4096 - 8192+256 -"));
+        assert_eq!(Snapshot::parse(&text).unwrap(), snapshot);
+
+        let short = Snapshot::parse(&text.replace(" 8192+256", ""));
+        assert!(matches!(short, Err(SnapshotError::Syntax(7))));
+        let headless = Snapshot::parse("seed 1\nchunk 0 0 0 - - - - -\n");
+        assert!(matches!(headless, Err(SnapshotError::Header)));
+    }
+}
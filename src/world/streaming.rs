@@ -0,0 +1,333 @@
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::PI,
+    ops::RangeInclusive,
+};
+
+use super::{split, ChunkMap, ChunkPos, MeshState, Workers};
+use crate::{buddy::Buddy, camera::Camera, mesh::QuadRef};
+
+// Half the diagonal of a chunk, in blocks
+const CHUNK_RADIUS: f32 = 16.0 * 1.733;
+
+// Chunks past the radius by more than this are unloaded whatever the pressure,
+// for those right at its edge not to come and go as the camera moves back and forth
+pub const HYSTERESIS: i32 = 2;
+
+// Render distances the radius may be set to, in chunks
+pub const RADII: RangeInclusive<i32> = 1..=32;
+
+// Chunks within `radius` of the camera are generated through the workers, nearest
+// and most straight ahead first, while those seen least recently are unloaded
+// once the quad buddy fills up past `pressure`, freeing their blocks in it.
+// Those well past the radius are unloaded right away, so that shrinking it
+// frees their quads. Edits to chunks unloaded are lost, as there is nowhere
+// to keep them yet
+#[derive(Clone, Debug)]
+pub struct Streaming {
+    // In chunks, around the chunk the camera is in
+    pub radius: i32,
+
+    // Fraction of the quad buddy in use past which chunks get unloaded,
+    // and nothing new is asked for
+    pub pressure: f32,
+
+    // Chunks asked for and not loaded yet, at most
+    pub in_flight: usize,
+
+    // Times `update` got called, and the last of them every chunk was in view
+    frame: u64,
+    seen: HashMap<ChunkPos, u64>,
+
+    requested: HashSet<ChunkPos>,
+}
+
+impl Streaming {
+    pub fn new(radius: i32) -> Self {
+        Self {
+            radius,
+            pressure: 0.9,
+            in_flight: 16,
+            frame: 0,
+            seen: HashMap::new(),
+            requested: HashSet::new(),
+        }
+    }
+
+    // Change the render distance, clamped to `RADII`, returning the one set.
+    // Chunks loaded past it by more than `HYSTERESIS` go on the next `update`,
+    // along with those already asked for once they come in
+    pub fn set_radius(&mut self, radius: i32) -> i32 {
+        self.radius = radius.clamp(*RADII.start(), *RADII.end());
+        self.radius
+    }
+
+    // Note which chunks are in view, unload those well past the radius and those
+    // seen least recently while under pressure, and ask the workers for what
+    // is missing otherwise.
+    // Returns how many chunks got unloaded
+    pub fn update(
+        &mut self,
+        map: &mut ChunkMap,
+        quads: &mut Buddy<QuadRef>,
+        workers: &mut Workers,
+        camera: &Camera,
+    ) -> usize {
+        self.update_with(map, quads, camera, |pos| workers.generate(pos))
+    }
+
+    // Same as `update`, handing what is missing to `request` in the order wanted,
+    // say to load it right away where chunks must come in the same order every time
+    pub fn update_with(
+        &mut self,
+        map: &mut ChunkMap,
+        quads: &mut Buddy<QuadRef>,
+        camera: &Camera,
+        mut request: impl FnMut(ChunkPos),
+    ) -> usize {
+        self.frame += 1;
+        self.requested.retain(|&pos| !map.contains(pos));
+
+        // Chunks just loaded count as seen, not to be unloaded right away
+        for pos in map.positions() {
+            let seen = self.seen.entry(pos).or_insert(self.frame);
+            if in_view(camera, pos) {
+                *seen = self.frame;
+            }
+        }
+
+        let under_pressure = |quads: &Buddy<QuadRef>| {
+            quads.metrics().used as f32 > quads.capacity() as f32 * self.pressure
+        };
+
+        let mut unloaded = 0;
+        for pos in self.beyond(map, camera) {
+            map.remove(quads, pos);
+            self.seen.remove(&pos);
+            unloaded += 1;
+        }
+
+        while under_pressure(quads) {
+            let Some(pos) = self.victim(map, camera) else {
+                break;
+            };
+
+            map.remove(quads, pos);
+            self.seen.remove(&pos);
+            unloaded += 1;
+        }
+
+        if !under_pressure(quads) {
+            let room = self.in_flight.saturating_sub(self.requested.len());
+            for pos in self.wanted(map, camera).into_iter().take(room) {
+                request(pos);
+                self.requested.insert(pos);
+            }
+        }
+
+        unloaded
+    }
+
+    // Whether every chunk asked for got loaded, as when nothing was missing
+    // or there is no room for more as of the last `update`
+    pub fn is_settled(&self) -> bool {
+        self.requested.is_empty()
+    }
+
+    // Ask again for chunks asked for and never loaded, as after whatever
+    // they were asked of went away
+    pub fn forget_requests(&mut self) {
+        self.requested.clear();
+    }
+
+    // Chunks within the radius neither loaded nor asked for, most wanted first.
+    // Those behind the camera count as twice as far as those ahead
+    pub fn wanted(&self, map: &ChunkMap, camera: &Camera) -> Vec<ChunkPos> {
+        let center = center(camera);
+        let forward = camera.forward();
+        let range = -self.radius..=self.radius;
+        let mut wanted = Vec::new();
+
+        for dz in range.clone() {
+            for dy in range.clone() {
+                for dx in range.clone() {
+                    let pos = [center[0] + dx, center[1] + dy, center[2] + dz];
+                    let loaded = map.contains(pos) || self.requested.contains(&pos);
+
+                    if !loaded && within(center, pos, self.radius) {
+                        let (distance, ahead) = towards(camera.eye, forward, pos);
+                        wanted.push((distance * (1.5 - ahead * 0.5), pos));
+                    }
+                }
+            }
+        }
+
+        wanted.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        wanted.into_iter().map(|(_, pos)| pos).collect()
+    }
+
+    // Chunks within the radius loaded and meshed, out of how many there are
+    pub fn progress(&self, map: &ChunkMap, camera: &Camera) -> (usize, usize) {
+        let center = center(camera);
+        let range = -self.radius..=self.radius;
+        let (mut ready, mut total) = (0, 0);
+
+        for dz in range.clone() {
+            for dy in range.clone() {
+                for dx in range.clone() {
+                    let pos = [center[0] + dx, center[1] + dy, center[2] + dz];
+                    if within(center, pos, self.radius) {
+                        ready += (map.state(pos) == Some(MeshState::Ready)) as usize;
+                        total += 1;
+                    }
+                }
+            }
+        }
+
+        (ready, total)
+    }
+
+    // Chunks loaded past the radius by more than `HYSTERESIS`, to be unloaded
+    pub fn beyond(&self, map: &ChunkMap, camera: &Camera) -> Vec<ChunkPos> {
+        let center = center(camera);
+        let reach = self.radius + HYSTERESIS;
+        let loaded = map.positions();
+        loaded.filter(|&pos| !within(center, pos, reach)).collect()
+    }
+
+    // Chunk to unload next, out of those out of view, seen least recently
+    // and farthest away. Those beyond the radius go before any other
+    pub fn victim(&self, map: &ChunkMap, camera: &Camera) -> Option<ChunkPos> {
+        let center = center(camera);
+        let outside = |pos| !within(center, pos, self.radius);
+
+        let forward = camera.forward();
+        let candidates = map.positions();
+        let candidates = candidates.filter(|&pos| self.seen.get(&pos) != Some(&self.frame));
+
+        candidates.max_by(|&a, &b| {
+            let seen = |pos: ChunkPos| self.seen.get(&pos).copied().unwrap_or_default();
+            let distance = |pos| towards(camera.eye, forward, pos).0;
+
+            outside(a)
+                .cmp(&outside(b))
+                .then(seen(b).cmp(&seen(a)))
+                .then(distance(a).total_cmp(&distance(b)))
+        })
+    }
+}
+
+// Chunk the camera is in
+fn center(camera: &Camera) -> ChunkPos {
+    split(camera.eye.map(|c| c.floor() as i32)).0
+}
+
+// Whether a chunk is no farther than `radius` chunks from `center`
+fn within(center: ChunkPos, pos: ChunkPos, radius: i32) -> bool {
+    let offset = [0, 1, 2].map(|axis| pos[axis] - center[axis]);
+    offset.map(|c| c * c).iter().sum::<i32>() <= radius * radius
+}
+
+// Whether any of a chunk may be in view, going by a cone wider than the view
+// so that it holds for any aspect ratio up to about 2:1
+fn in_view(camera: &Camera, pos: ChunkPos) -> bool {
+    let (distance, ahead) = towards(camera.eye, camera.forward(), pos);
+    if distance <= CHUNK_RADIUS {
+        return true;
+    }
+
+    let cone = camera.fov_y + (CHUNK_RADIUS / distance).asin();
+    ahead >= cone.min(PI).cos()
+}
+
+// Distance from `eye` to the center of a chunk, in blocks,
+// and the cosine of the angle between `forward` and the way to it
+fn towards(eye: [f32; 3], forward: [f32; 3], pos: ChunkPos) -> (f32, f32) {
+    let way: [f32; 3] = std::array::from_fn(|axis| pos[axis] as f32 * 32.0 + 16.0 - eye[axis]);
+    let distance = way.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+    let dot: f32 = way.iter().zip(forward).map(|(a, b)| a * b).sum();
+    let ahead = if distance > 0.0 { dot / distance } else { 1.0 };
+    (distance, ahead)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::{Chunk, AIR};
+
+    // Looking down +x from the middle of chunk [0, 0, 0]
+    fn camera() -> Camera {
+        Camera::new([16.0; 3])
+    }
+
+    fn empty() -> Box<Chunk> {
+        Box::new([[[AIR; 32]; 32]; 32])
+    }
+
+    #[test]
+    fn nearest_and_ahead_come_first() {
+        let mut map = ChunkMap::new();
+        map.insert([0, 0, 0], empty());
+
+        let streaming = Streaming::new(2);
+        let wanted = streaming.wanted(&map, &camera());
+
+        assert!(!wanted.contains(&[0, 0, 0]));
+        assert!(!wanted.contains(&[2, 1, 0]), "beyond the radius");
+        assert_eq!(wanted[0], [1, 0, 0]);
+
+        let index = |pos| wanted.iter().position(|&wanted| wanted == pos).unwrap();
+        assert!(index([1, 0, 0]) < index([0, 1, 0]));
+        assert!(index([0, 1, 0]) < index([-1, 0, 0]));
+    }
+
+    #[test]
+    fn least_recently_seen_go_first() {
+        let mut map = ChunkMap::new();
+        for pos in [[0, 0, 0], [1, 0, 0], [-1, 0, 0], [-2, 0, 0], [5, 0, 0]] {
+            map.insert(pos, empty());
+        }
+
+        let mut streaming = Streaming::new(2);
+        streaming.frame = 3;
+        streaming.seen = HashMap::from([
+            ([0, 0, 0], 3),
+            ([1, 0, 0], 3),
+            ([-1, 0, 0], 2),
+            ([-2, 0, 0], 1),
+            ([5, 0, 0], 2),
+        ]);
+
+        // Chunks beyond the radius first, whenever they were seen
+        let camera = camera();
+        assert_eq!(streaming.victim(&map, &camera), Some([5, 0, 0]));
+        map.chunks.remove(&[5, 0, 0]);
+
+        // Never those seen this very frame
+        assert_eq!(streaming.victim(&map, &camera), Some([-2, 0, 0]));
+        map.chunks.remove(&[-2, 0, 0]);
+        assert_eq!(streaming.victim(&map, &camera), Some([-1, 0, 0]));
+        map.chunks.remove(&[-1, 0, 0]);
+        assert_eq!(streaming.victim(&map, &camera), None);
+    }
+
+    #[test]
+    fn shrinking_unloads_past_the_hysteresis() {
+        let mut map = ChunkMap::new();
+        for pos in [[0, 0, 0], [3, 0, 0], [4, 0, 0], [0, -5, 0]] {
+            map.insert(pos, empty());
+        }
+
+        // Right past the edge is kept around, in case the camera comes back
+        let mut streaming = Streaming::new(2);
+        let camera = camera();
+        assert_eq!(streaming.beyond(&map, &camera), [[0, -5, 0]]);
+
+        assert_eq!(streaming.set_radius(0), 1);
+        let mut beyond = streaming.beyond(&map, &camera);
+        beyond.sort_unstable();
+        assert_eq!(beyond, [[0, -5, 0], [4, 0, 0]]);
+        assert_eq!(streaming.set_radius(100), *RADII.end());
+    }
+}
@@ -0,0 +1,299 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{biome::smooth, split, Biome, ChunkPos};
+use crate::mesh::{BlockId, Chunk, AIR, LEAVES, LOG};
+
+// Blocks between points of the cave noise
+const CAVE_SCALE: i32 = 12;
+
+// How close to halfway both cave noises have to be for a block to be carved out.
+// Tunnels wind along where their halfway surfaces cross
+const CAVE_WIDTH: f32 = 0.06;
+
+// Blocks of ground always left over caves, for them to stay out of sight
+const CAVE_ROOF: usize = 4;
+
+// Chunks structures reach out of the chunk column they are rooted in, along x and z
+const REACH: i32 = 1;
+
+// Hills at ground level out of `seed`, covered as their biome has them, tunneled
+// through by caves and grown over by structures, and nothing else around them.
+//
+// Generated in two phases, for every chunk to be generated on its own in any order
+// and still come out the same. Every chunk column roots its structures at random
+// out of the seed and where it is alone, then every chunk stamps in whatever
+// falls inside it of the structures rooted within `REACH`, so that structures
+// spanning chunk borders come out whole on both sides
+pub fn terrain(seed: u64, pos: ChunkPos) -> (Box<Chunk>, Biome) {
+    let biome = Biome::at(seed, pos);
+    let mut blocks = match pos[1] {
+        0 => {
+            let hill = Hill::at(seed, pos);
+            let mut blocks = hill.blocks(biome);
+            carve(seed, pos, &hill, &mut blocks);
+            blocks
+        }
+        _ => Box::new([[[AIR; 32]; 32]; 32]),
+    };
+
+    let [x, _, z] = pos;
+    for dz in -REACH..=REACH {
+        for dx in -REACH..=REACH {
+            for structure in structures(seed, [x + dx, 0, z + dz]) {
+                structure.stamp(pos, &mut blocks);
+            }
+        }
+    }
+
+    (blocks, biome)
+}
+
+// A round hill, its peak moved around and raised or lowered
+// at random, the same for every chunk and seed
+struct Hill {
+    peak_x: i32,
+    peak_z: i32,
+    peak: i32,
+}
+
+impl Hill {
+    fn at(seed: u64, [x, _, z]: ChunkPos) -> Self {
+        let mut rng = column_rng(seed, x, z);
+        Self {
+            peak_x: rng.gen_range(8..24),
+            peak_z: rng.gen_range(8..24),
+            peak: rng.gen_range(8..28),
+        }
+    }
+
+    // Where the ground ends, in chunk coordinates
+    fn height(&self, x: usize, z: usize) -> usize {
+        let (dx, dz) = (x as i32 - self.peak_x, z as i32 - self.peak_z);
+        (self.peak - (dx * dx + dz * dz) / 24).clamp(1, 31) as usize
+    }
+
+    fn blocks(&self, biome: Biome) -> Box<Chunk> {
+        let (surface, filler) = biome.blocks();
+
+        let mut chunk = Box::new([[[AIR; 32]; 32]; 32]);
+        for z in 0..32 {
+            for x in 0..32 {
+                let height = self.height(x, z);
+
+                // Stone under a few blocks of filler
+                for y in 0..height {
+                    chunk[z][y][x] = if y + 3 < height { 1 } else { filler };
+                }
+
+                chunk[z][height][x] = surface;
+            }
+        }
+
+        chunk
+    }
+}
+
+// Tunnels through the ground of `hill`, going on from chunk to chunk,
+// with the bottom of the world and some ground over them left alone
+fn carve(seed: u64, pos: ChunkPos, hill: &Hill, blocks: &mut Chunk) {
+    let [origin_x, origin_y, origin_z] = pos.map(|c| c * 32);
+    let near_halfway = |seed, location| (noise(seed, location) - 0.5).abs() < CAVE_WIDTH;
+
+    for (z, slice) in blocks.iter_mut().enumerate() {
+        for (y, row) in slice.iter_mut().enumerate() {
+            for (x, block) in row.iter_mut().enumerate() {
+                if y == 0 || y + CAVE_ROOF >= hill.height(x, z) {
+                    continue;
+                }
+
+                let [x, y, z] = [x, y, z].map(|c| c as i32);
+                let location = [origin_x + x, origin_y + y, origin_z + z];
+                if near_halfway(seed, location) && near_halfway(!seed, location) {
+                    *block = AIR;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    // Trunk of logs `height` blocks tall, under a ball of leaves
+    Tree { height: i32 },
+
+    // Ball of stone, half sunk into the ground
+    Boulder { radius: i32 },
+}
+
+// Something standing on the ground, rooted at `base`, in world coordinates,
+// right above it
+#[derive(Clone, Copy, Debug)]
+struct Structure {
+    kind: Kind,
+    base: [i32; 3],
+}
+
+impl Structure {
+    // Blocks making it up, in world coordinates, those first standing over the rest
+    fn blocks(self) -> Vec<([i32; 3], BlockId)> {
+        let [x, y, z] = self.base;
+        let offset = |[dx, dy, dz]: [i32; 3]| [x + dx, y + dy, z + dz];
+
+        match self.kind {
+            Kind::Tree { height } => {
+                let trunk = (0..height).map(|dy| (offset([0, dy, 0]), LOG));
+                let top = |[dx, dy, dz]: [i32; 3]| offset([dx, height - 1 + dy, dz]);
+                let crown = ball(2).map(|d| (top(d), LEAVES));
+                trunk.chain(crown).collect()
+            }
+            Kind::Boulder { radius } => ball(radius).map(|d| (offset(d), 1)).collect(),
+        }
+    }
+
+    // Set whatever of it falls inside chunk `pos`, wherever there is nothing yet
+    fn stamp(self, pos: ChunkPos, blocks: &mut Chunk) {
+        for (location, block) in self.blocks() {
+            let (at, [x, y, z]) = split(location);
+            if at == pos && blocks[z][y][x] == AIR {
+                blocks[z][y][x] = block;
+            }
+        }
+    }
+}
+
+// Structures rooted in the chunk column at `pos`, as many as its biome grows,
+// out of nothing but the seed and where the column is
+fn structures(seed: u64, pos: ChunkPos) -> Vec<Structure> {
+    let [x, _, z] = pos;
+    let hill = Hill::at(seed, pos);
+    let mut rng = column_rng(!seed, x, z);
+
+    let (trees, boulders) = match Biome::at(seed, pos) {
+        Biome::Forest => (rng.gen_range(3..7), 0),
+        Biome::Swamp => (rng.gen_range(1..4), 0),
+        Biome::Plains | Biome::Tundra => (rng.gen_range(0..2), rng.gen_range(0..2)),
+        Biome::Desert => (0, rng.gen_range(0..3)),
+    };
+
+    let mut structures = Vec::new();
+    for i in 0..trees + boulders {
+        let kind = match i < trees {
+            true => Kind::Tree {
+                height: rng.gen_range(4..7),
+            },
+            false => Kind::Boulder {
+                radius: rng.gen_range(1..3),
+            },
+        };
+
+        let (local_x, local_z) = (rng.gen_range(0..32), rng.gen_range(0..32));
+        let ground = hill.height(local_x, local_z) as i32;
+        let base = [x * 32 + local_x as i32, ground + 1, z * 32 + local_z as i32];
+        structures.push(Structure { kind, base });
+    }
+
+    structures
+}
+
+// Offsets from a block to those around it as far as `radius`, rounded off
+fn ball(radius: i32) -> impl Iterator<Item = [i32; 3]> {
+    let range = move || -radius..=radius;
+    let row = move |dy, dz| range().map(move |dx| [dx, dy, dz]);
+    let offsets = range().flat_map(move |dz| range().flat_map(move |dy| row(dy, dz)));
+
+    offsets.filter(move |[dx, dy, dz]| dx * dx + dy * dy + dz * dz <= radius * radius + 1)
+}
+
+// The same for every chunk column and seed
+fn column_rng(seed: u64, x: i32, z: i32) -> StdRng {
+    let x = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let z = (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    StdRng::seed_from_u64(seed ^ x ^ z)
+}
+
+// Value noise from 0 to 1, trilinearly blended between points `CAVE_SCALE`
+// blocks apart, each at random
+fn noise(seed: u64, location: [i32; 3]) -> f32 {
+    let cell = location.map(|c| c.div_euclid(CAVE_SCALE));
+    let scale = CAVE_SCALE as f32;
+    let [u, v, w] = location.map(|c| smooth(c.rem_euclid(CAVE_SCALE) as f32 / scale));
+
+    let corner = |dx, dy, dz| point(seed, [cell[0] + dx, cell[1] + dy, cell[2] + dz]);
+    let layer = |dz| {
+        let near = corner(0, 0, dz) + (corner(1, 0, dz) - corner(0, 0, dz)) * u;
+        let far = corner(0, 1, dz) + (corner(1, 1, dz) - corner(0, 1, dz)) * u;
+        near + (far - near) * v
+    };
+
+    layer(0) + (layer(1) - layer(0)) * w
+}
+
+// From 0 to 1, the same for every seed and point
+fn point(seed: u64, [x, y, z]: [i32; 3]) -> f32 {
+    let x = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    let y = (y as u64).wrapping_mul(0xD6E8_FEB8_6659_FD93);
+    let z = (z as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    let mut hash = seed ^ x ^ y.rotate_left(21) ^ z.rotate_left(42);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    hash ^= hash >> 33;
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const SEED: u64 = 11;
+
+    #[test]
+    fn structures_come_out_whole_across_chunks() {
+        let mut chunks = HashMap::new();
+        let mut spanning = 0;
+
+        for x in -3..3 {
+            for z in -3..3 {
+                for structure in structures(SEED, [x, 0, z]) {
+                    let blocks = structure.blocks();
+                    let root = split(structure.base).0;
+                    spanning += blocks.iter().any(|&(at, _)| split(at).0 != root) as usize;
+
+                    // Everywhere it goes, generated on its own or along with neighbors
+                    for (location, _) in blocks {
+                        let (pos, [x, y, z]) = split(location);
+                        let chunk = chunks.entry(pos).or_insert_with(|| terrain(SEED, pos).0);
+                        let kind = structure.kind;
+                        assert_ne!(chunk[z][y][x], AIR, "{kind:?} left out at {location:?}");
+                    }
+                }
+            }
+        }
+
+        assert!(spanning > 0, "no structure reached out of its chunk");
+    }
+
+    #[test]
+    fn caves_stay_under_ground() {
+        let mut carved = 0;
+        for x in 0..4 {
+            let pos = [x, 0, 0];
+            let hill = Hill::at(SEED, pos);
+            let (blocks, _) = terrain(SEED, pos);
+
+            for z in 0..32 {
+                for x in 0..32 {
+                    let height = hill.height(x, z);
+                    assert_ne!(blocks[z][0][x], AIR);
+                    assert_ne!(blocks[z][height][x], AIR);
+
+                    let below = (1..height).filter(|&y| blocks[z][y][x] == AIR);
+                    carved += below.count();
+                }
+            }
+        }
+
+        assert!(carved > 0, "no caves carved");
+    }
+}
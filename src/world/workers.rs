@@ -0,0 +1,210 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use super::{Biome, ChunkMap, ChunkPos};
+use crate::{
+    buddy::Buddy,
+    gfx::Gfx,
+    mesh::{self, lod::LODS, Borders, Chunk, LightGrid, Mesh, QuadRef},
+};
+
+// Blocks of a chunk and its biome out of nothing but where it is, say terrain out of noise
+pub type Generator = dyn Fn(ChunkPos) -> (Box<Chunk>, Biome) + Send + Sync;
+
+enum Job {
+    Generate(ChunkPos),
+    Mesh(ChunkPos, Box<Chunk>, LightGrid, Box<Borders>),
+}
+
+struct Done {
+    pos: ChunkPos,
+
+    // Only for chunks generated along the way
+    blocks: Option<(Box<Chunk>, Biome)>,
+    mesh: Mesh,
+    lods: [Mesh; LODS],
+}
+
+// Threads generating and meshing chunks away from the render thread, which
+// only writes the quads they send back into the buddy, as that needs the device.
+// Jobs queue up in the order they are given and come back as they finish
+#[derive(Debug)]
+pub struct Workers {
+    jobs: Option<Sender<Job>>,
+    queue: Arc<Mutex<Receiver<Job>>>,
+    done: Receiver<Done>,
+    threads: Vec<JoinHandle<()>>,
+
+    // Jobs given out and not back yet
+    pending: usize,
+}
+
+impl Workers {
+    // As many threads as asked for, each meshing with a mesher by one
+    // of the `mesh::MESHERS` names. None if there is no such mesher
+    pub fn new(count: usize, mesher: &str, generator: Arc<Generator>) -> Option<Self> {
+        mesh::mesher(mesher)?;
+
+        let (jobs, queue) = mpsc::channel();
+        let (sender, done) = mpsc::channel();
+        let queue = Arc::new(Mutex::new(queue));
+
+        let threads = (0..count.max(1))
+            .map(|index| {
+                let (queue, sender) = (queue.clone(), sender.clone());
+                let (mesher, generator) = (mesher.to_owned(), generator.clone());
+
+                thread::Builder::new()
+                    .name(format!("chunk worker {index}"))
+                    .spawn(move || work(&queue, &sender, &mesher, &generator))
+                    .expect("cannot spawn chunk worker")
+            })
+            .collect();
+
+        let workers = Self {
+            jobs: Some(jobs),
+            queue,
+            done,
+            threads,
+            pending: 0,
+        };
+
+        Some(workers)
+    }
+
+    pub const fn pending(&self) -> usize {
+        self.pending
+    }
+
+    // Generate and mesh a chunk, to be added to the map once finished
+    pub fn generate(&mut self, pos: ChunkPos) {
+        self.send(Job::Generate(pos));
+    }
+
+    // Hand dirty chunks of `map` over to be meshed, until `limit` jobs are pending.
+    // Returns how many were handed over
+    pub fn mesh_dirty(&mut self, map: &mut ChunkMap, limit: usize) -> usize {
+        let mut sent = 0;
+
+        while self.pending < limit {
+            let Some((pos, blocks)) = map.next_dirty() else {
+                break;
+            };
+
+            let blocks = Box::new(*blocks);
+            let borders = Box::new(map.borders(pos));
+            self.send(Job::Mesh(pos, blocks, map.light_grid(pos), borders));
+            sent += 1;
+        }
+
+        sent
+    }
+
+    // Write whatever finished since last time into `map`, without waiting on the rest.
+    // Chunks generated only fill in those not loaded in the meantime, say out of a save.
+    // Returns how many chunks got their quads written
+    pub fn finish(&mut self, gfx: &Gfx, quads: &mut Buddy<QuadRef>, map: &mut ChunkMap) -> usize {
+        let _span = tracing::debug_span!("finish").entered();
+        let mut written = 0;
+
+        while let Ok(finished) = self.done.try_recv() {
+            let Done {
+                pos,
+                blocks,
+                mesh,
+                lods,
+            } = finished;
+
+            self.pending -= 1;
+
+            let done = match blocks {
+                Some(_) if map.contains(pos) => false,
+                Some((blocks, biome)) => {
+                    let done = map.insert_meshed(gfx, quads, pos, blocks, mesh);
+                    map.set_biome(pos, biome);
+                    done
+                }
+                None => map.finish_meshing(gfx, quads, pos, mesh),
+            };
+
+            if done {
+                map.finish_lods(gfx, quads, pos, lods);
+            }
+
+            written += done as usize;
+        }
+
+        written
+    }
+
+    fn send(&mut self, job: Job) {
+        let jobs = self.jobs.as_ref().expect("workers shut down");
+        jobs.send(job).expect("every chunk worker is gone");
+        self.pending += 1;
+    }
+}
+
+impl Drop for Workers {
+    // Workers finish the job at hand, skip the rest and exit
+    fn drop(&mut self) {
+        drop(self.jobs.take());
+
+        if let Ok(queue) = self.queue.lock() {
+            while queue.try_recv().is_ok() {}
+        }
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn work(queue: &Mutex<Receiver<Job>>, done: &Sender<Done>, mesher: &str, generator: &Generator) {
+    let mesher = mesh::mesher(mesher).expect("mesher checked on creation");
+
+    loop {
+        // The lock is held for as long as it takes to get a job, not to run it
+        let Ok(job) = queue.lock().unwrap().recv() else {
+            break;
+        };
+
+        // Chunks generated are lit and get their neighbors once loaded,
+        // so they are meshed dark and alone
+        let (pos, blocks, lit, biome) = match job {
+            Job::Generate(pos) => {
+                let _span = tracing::debug_span!("generate", ?pos).entered();
+                let (blocks, biome) = generator(pos);
+                (pos, blocks, None, Some(biome))
+            }
+            Job::Mesh(pos, blocks, light, borders) => (pos, blocks, Some((light, borders)), None),
+        };
+
+        // Coarser levels are meshed from the same blocks, unlit as they are seen from afar
+        let mesh = || {
+            let mesh = match &lit {
+                Some((light, borders)) => mesher.mesh_lit_with_borders(&blocks, borders, light),
+                None => mesher.mesh(&blocks),
+            };
+
+            (mesh, mesh::lod::mesh_lods(mesher.as_ref(), &blocks))
+        };
+
+        let (mesh, lods) = tracing::debug_span!("mesh", ?pos).in_scope(mesh);
+        let blocks = biome.map(|biome| (blocks, biome));
+        let finished = Done {
+            pos,
+            blocks,
+            mesh,
+            lods,
+        };
+
+        if done.send(finished).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,11 @@
+
+// Appended to quad.wgsl, writing out every corner `expand` places instead of drawing it,
+// world position first and palette entry last. A workgroup per quad, an invocation per corner
+
+@group(2) @binding(0) var<storage, read_write> corners: array<vec4<f32>>;
+
+@compute @workgroup_size(4)
+fn cs_expand(@builtin(workgroup_id) quad: vec3<u32>, @builtin(local_invocation_index) corner: u32) {
+    let out = expand(quad.x, corner, draw.origin.xyz, draw.axes, u32(draw.origin.w), 1.0);
+    corners[quad.x * 4u + corner] = vec4(out.world, f32(out.tint));
+}
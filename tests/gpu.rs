@@ -0,0 +1,210 @@
+// Buffers and shaders checked on a device with no window, on whatever adapter
+// there is, down to a software one. Without any adapter at all, every test passes
+// having checked nothing, so CI machines without one still run the rest
+
+use bytemuck::{Pod, Zeroable};
+use rust_playground::{
+    buddy::Buddy,
+    gfx::{Bindings, Gfx, GfxError, PushConstants},
+    mesh::{geometry::Geometry, greedy::Greedy, Chunk, Facing, Mesher, QuadRef, AIR},
+    renderer::facing_axes,
+};
+use wgpu::{CommandEncoderDescriptor, ComputePassDescriptor, ShaderStages, TextureFormat};
+
+// Matches `Draw` in quad.wgsl
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+struct Draw {
+    view_proj: [[f32; 4]; 4],
+    origin: [f32; 4],
+    axes: [u32; 4],
+}
+
+fn gfx() -> Option<Gfx<'static>> {
+    match pollster::block_on(Gfx::headless(64, 64, TextureFormat::Rgba8Unorm)) {
+        Ok(gfx) => Some(gfx),
+        Err(GfxError::NoAdapter) => {
+            eprintln!("no adapter, skipping");
+            None
+        }
+        Err(err) => panic!("{err}"),
+    }
+}
+
+#[test]
+fn buddy_blocks_read_back_as_written() {
+    let Some(gfx) = gfx() else {
+        return;
+    };
+
+    let mut buddy = Buddy::<u32>::new(&gfx, 1 << 12, 4);
+    let (first, _) = buddy.load(&gfx, &[1, 2, 3, 4, 5]).unwrap();
+    let (second, _) = buddy.load(&gfx, &[0xDEAD_BEEF; 40]).unwrap();
+    buddy.write_at(&gfx, &second, 38, &[7, 8]);
+
+    // Blocks are rounded up, the rest of them left zeroed
+    let mut expected = vec![1, 2, 3, 4, 5];
+    expected.resize(buddy.len(&first), 0);
+    assert_eq!(buddy.read(&gfx, &first).unwrap(), expected);
+
+    let read = buddy.read(&gfx, &second).unwrap();
+    assert_eq!(read[..38], [0xDEAD_BEEF; 38]);
+    assert_eq!(read[38..40], [7, 8]);
+
+    // Moved blocks keep what they hold
+    let descriptor = CommandEncoderDescriptor::default();
+    let mut encoder = gfx.device.create_command_encoder(&descriptor);
+    let moved = buddy.move_block(&gfx, &mut encoder, first).unwrap();
+    gfx.queue.submit([encoder.finish()]);
+    assert_eq!(buddy.read(&gfx, &moved).unwrap(), expected);
+
+    // Oldest first, moved blocks keeping their age
+    let allocations: Vec<_> = buddy.iter_allocations().collect();
+    let offsets = allocations.iter().map(|allocation| allocation.offset);
+    let serials = allocations.iter().map(|allocation| allocation.serial);
+    assert!(offsets.eq([buddy.offset(&moved), buddy.offset(&second)]));
+    assert!(serials.eq([0, 1]));
+
+    buddy.free(moved);
+    buddy.free(second);
+}
+
+#[test]
+fn staged_writes_spill_over_the_budget() {
+    let Some(gfx) = gfx() else {
+        return;
+    };
+
+    let mut buddy = Buddy::<u32>::new(&gfx, 1 << 12, 4);
+    let [first, second, third] = [(); 3].map(|_| buddy.alloc(16).unwrap());
+    buddy.stage_at(&first, 0, &[1; 16]);
+    buddy.stage_at(&second, 0, &[2; 16]);
+    buddy.stage_at(&third, 0, &[3; 16]);
+    buddy.stage_at(&first, 4, &[9; 2]);
+    assert_eq!(buddy.staged_bytes(), 3 * 64 + 8);
+
+    // Over the budget, but the oldest always gets through
+    assert_eq!(buddy.flush_staged(&gfx, 32), 64);
+    assert!(buddy.is_staged(&first) && buddy.is_staged(&second));
+
+    // Freed blocks forget what they had waiting
+    buddy.free(second);
+    assert_eq!(buddy.flush_staged(&gfx, 128), 64 + 8);
+    assert!(!buddy.is_staged(&first) && !buddy.is_staged(&third));
+
+    let mut expected = [1; 16];
+    expected[4..6].copy_from_slice(&[9; 2]);
+    assert_eq!(buddy.read(&gfx, &first).unwrap(), expected);
+    assert_eq!(buddy.read(&gfx, &third).unwrap(), [3; 16]);
+
+    buddy.free(first);
+    buddy.free(third);
+}
+
+#[test]
+fn quads_expand_as_on_the_cpu() {
+    let Some(gfx) = gfx() else {
+        return;
+    };
+
+    // Corners are bound in group 2, so constants falling back to a uniform go after them
+    let Ok(push) = PushConstants::<Draw>::new(&gfx, ShaderStages::COMPUTE, 3) else {
+        eprintln!("no room for push constants, skipping");
+        return;
+    };
+
+    // A slab with a pillar on it, for quads of every size and facing
+    let mut chunk: Box<Chunk> = Box::new([[[AIR; 32]; 32]; 32]);
+    for (z, plane) in chunk.iter_mut().enumerate() {
+        for (y, row) in plane.iter_mut().enumerate() {
+            for (x, block) in row.iter_mut().enumerate() {
+                let slab = (2..9).contains(&x) && y < 2 && (3..7).contains(&z);
+                let pillar = (x, z) == (4, 4) && y < 6;
+                *block = if slab || pillar { 1 } else { AIR };
+            }
+        }
+    }
+
+    let mesh = Greedy::default().mesh(&chunk);
+    let geometry = Geometry::new(&mesh);
+
+    let quad_source = include_str!("../src/quad.wgsl");
+    let source = [quad_source, include_str!("expand.wgsl")].concat();
+    let module = push.module(&gfx, "expand", &source);
+
+    let quad_bindings = Bindings::new(ShaderStages::COMPUTE).storage(true);
+    let corner_bindings = Bindings::new(ShaderStages::COMPUTE).storage(false);
+    let (quad_layout, corner_layout) = (quad_bindings.layout(&gfx), corner_bindings.layout(&gfx));
+    let unused = Bindings::new(ShaderStages::COMPUTE).layout(&gfx);
+
+    let layouts = [&*quad_layout, &*unused, &*corner_layout];
+    let layout = push.pipeline_layout(&gfx, &layouts);
+    let pipeline = gfx.compute_pipeline(&module, Some(&layout), "cs_expand");
+
+    // Vertices of `geometry` go facing by facing, four per quad
+    let mut vertices = geometry.vertices.chunks_exact(4);
+
+    // A facing at a time, its quads at the start of a buffer of their own
+    // as workgroups count them from there
+    for facing in Facing::ALL {
+        let quads = &mesh[facing as usize];
+        if quads.is_empty() {
+            continue;
+        }
+
+        let mut quad_buddy = Buddy::<QuadRef>::new(&gfx, 1 << 10, 4);
+        let (quad_handle, _) = quad_buddy.load(&gfx, quads).unwrap();
+        assert_eq!(quad_buddy.offset(&quad_handle), 0);
+
+        let mut corner_buddy = Buddy::<[f32; 4]>::new(&gfx, 4 << 10, 4);
+        let corners = corner_buddy.alloc(4 * quads.len()).unwrap();
+
+        let quad_group = gfx.bind_group("quads", &quad_layout, [quad_buddy.as_binding()]);
+        let corner_binding = corner_buddy.as_binding();
+        let corner_group = gfx.bind_group("corners", &corner_layout, [corner_binding]);
+
+        let draw = Draw {
+            view_proj: [[0.0; 4]; 4],
+            origin: [0.0; 4],
+            axes: facing_axes(facing),
+        };
+
+        let draw = push.write(&gfx, draw);
+
+        let descriptor = CommandEncoderDescriptor::default();
+        let mut encoder = gfx.device.create_command_encoder(&descriptor);
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &quad_group, &[]);
+            pass.set_bind_group(2, &corner_group, &[]);
+            push.set_compute(&mut pass, &draw);
+            pass.dispatch_workgroups(quads.len() as u32, 1, 1);
+        }
+
+        gfx.queue.submit([encoder.finish()]);
+        let mut expanded = corner_buddy.read(&gfx, &corners).unwrap();
+        expanded.truncate(4 * quads.len());
+
+        // Corners of every quad, in no particular order, to the last bit
+        for (quad, cpu) in expanded.chunks_exact(4).zip(vertices.by_ref()) {
+            let gpu = quad.iter().map(|&[x, y, z, _]| [x, y, z]);
+            let cpu_corners = cpu.iter().map(|vertex| vertex.position);
+            assert_eq!(sorted_bits(gpu), sorted_bits(cpu_corners), "{facing:?}");
+
+            // Untinted row, material straight out of the quad
+            assert!(quad.iter().all(|c| c[3] == cpu[0].material as f32));
+        }
+
+        quad_buddy.free(quad_handle);
+        corner_buddy.free(corners);
+    }
+
+    assert!(vertices.next().is_none());
+}
+
+fn sorted_bits(corners: impl Iterator<Item = [f32; 3]>) -> Vec<[u32; 3]> {
+    let mut bits: Vec<_> = corners.map(|corner| corner.map(f32::to_bits)).collect();
+    bits.sort_unstable();
+    bits
+}
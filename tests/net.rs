@@ -0,0 +1,55 @@
+// A server and two clients talking over loopback, as `axial serve` and the game do
+
+use std::{sync::Arc, thread, time::Duration};
+
+use rust_playground::{
+    net::{
+        protocol::{ToClient, ToServer},
+        Client, Server,
+    },
+    world::{terrain, Generator},
+};
+
+const SEED: u64 = 7;
+
+// Long enough for the slowest of machines to generate a chunk
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn next(client: &Client) -> ToClient {
+    client.wait(TIMEOUT).expect("nothing received").unwrap()
+}
+
+#[test]
+fn chunks_and_edits_reach_every_client() {
+    let generator: Arc<Generator> = Arc::new(|pos| terrain(SEED, pos));
+    let server = Server::bind("127.0.0.1:0", SEED, generator).unwrap();
+    let addr = server.local_addr().unwrap();
+    thread::spawn(move || server.run());
+
+    let a = Client::connect(addr).unwrap();
+    let b = Client::connect(addr).unwrap();
+    assert_eq!(a.seed(), SEED);
+
+    a.send(&ToServer::Request([0, -1, 0]));
+    let ToClient::Chunk(pos, blocks) = next(&a) else {
+        panic!("expected a chunk");
+    };
+
+    assert_eq!(pos, [0, -1, 0]);
+    assert!(blocks == terrain(SEED, pos).0);
+
+    // Going to every client, the one making it included
+    b.send(&ToServer::Edit([1, -30, 3], 9));
+    for client in [&a, &b] {
+        assert_eq!(next(client), ToClient::Edit([1, -30, 3], 9));
+    }
+
+    // Chunks sent from then on have the edit in them
+    b.send(&ToServer::Request([0, -1, 0]));
+    let ToClient::Chunk(_, blocks) = next(&b) else {
+        panic!("expected a chunk");
+    };
+
+    assert_eq!(blocks[3][2][1], 9);
+    assert!(a.poll().next().is_none());
+}